@@ -9,6 +9,12 @@ fn main() {
         linux: { all(unix, not(apple), not(android)) },
     }
 
+    // Target triple of the binary, to pick the binary of updates for this platform
+    println!(
+        "cargo:rustc-env=TARGET={}",
+        std::env::var("TARGET").unwrap()
+    );
+
     #[cfg(all(feature = "packager", target_os = "macos"))]
     println!("cargo:rustc-link-arg=-Wl,-rpath,@executable_path/../Resources/lib");
 }
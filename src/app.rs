@@ -0,0 +1,127 @@
+use ipc_channel::ipc::IpcSender;
+use versoview_messages::ToControllerMessage;
+use winit::{
+    application::ApplicationHandler,
+    event::{StartCause, WindowEvent},
+    event_loop::{ActiveEventLoop, EventLoopProxy},
+    window::WindowId,
+};
+
+use crate::{
+    Verso,
+    config::Config,
+    verso::{EventLoopProxyMessage, try_connect_ipc_and_get_config},
+};
+
+/// Runs Verso on the winit event loop: creates it once the event loop resumes, and hands it the
+/// events of the loop. The binary and the test harness both run Verso with it.
+pub struct App {
+    verso: Option<Verso>,
+    proxy: EventLoopProxy<EventLoopProxyMessage>,
+    /// Config and controller sender to create Verso with, read from the command line arguments
+    /// and the controller when the event loop resumes if `None`
+    config: Option<(Config, Option<IpcSender<ToControllerMessage>>)>,
+    /// Called once Verso is created
+    on_created: Option<Box<dyn FnOnce()>>,
+}
+
+impl App {
+    /// Run Verso with the command line arguments, and the controller connected with
+    /// `--ipc-channel` if any.
+    pub fn new(proxy: EventLoopProxy<EventLoopProxyMessage>) -> Self {
+        Self {
+            verso: None,
+            proxy,
+            config: None,
+            on_created: None,
+        }
+    }
+
+    /// Run Verso with the config instead of the command line arguments, reporting to the
+    /// controller through `to_controller_sender`, see [`Verso::new_with_config`].
+    pub fn with_config(
+        proxy: EventLoopProxy<EventLoopProxyMessage>,
+        config: Config,
+        to_controller_sender: Option<IpcSender<ToControllerMessage>>,
+    ) -> Self {
+        Self {
+            config: Some((config, to_controller_sender)),
+            ..Self::new(proxy)
+        }
+    }
+
+    /// Call `callback` once Verso is created, messages sent to the event loop before are dropped.
+    pub fn on_created(mut self, callback: impl FnOnce() + 'static) -> Self {
+        self.on_created = Some(Box::new(callback));
+        self
+    }
+}
+
+impl ApplicationHandler<EventLoopProxyMessage> for App {
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        if self.verso.is_some() {
+            return;
+        }
+        let (config, to_controller_sender) = self
+            .config
+            .take()
+            .unwrap_or_else(|| try_connect_ipc_and_get_config(&self.proxy));
+        let verso = self.verso.insert(Verso::new_with_config(
+            event_loop,
+            self.proxy.clone(),
+            config,
+            to_controller_sender,
+        ));
+        verso.init();
+        if let Some(on_created) = self.on_created.take() {
+            on_created();
+        }
+    }
+
+    fn exiting(&mut self, _event_loop: &ActiveEventLoop) {
+        if let Some(v) = self.verso.as_mut() {
+            v.before_shutdown();
+        }
+    }
+
+    fn new_events(&mut self, event_loop: &ActiveEventLoop, cause: StartCause) {
+        if let StartCause::ResumeTimeReached { .. } = cause {
+            if let Some(v) = self.verso.as_mut() {
+                v.handle_resume_time_reached(event_loop);
+            }
+        }
+    }
+
+    fn window_event(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        window_id: WindowId,
+        event: WindowEvent,
+    ) {
+        if let Some(v) = self.verso.as_mut() {
+            v.handle_window_event(event_loop, window_id, event);
+        }
+    }
+
+    fn user_event(&mut self, event_loop: &ActiveEventLoop, event: EventLoopProxyMessage) {
+        if let Some(v) = self.verso.as_mut() {
+            match event {
+                EventLoopProxyMessage::Wake => {
+                    v.request_redraw(event_loop);
+                }
+                EventLoopProxyMessage::IpcMessage(message) => {
+                    v.handle_incoming_webview_message(*message, event_loop);
+                }
+                EventLoopProxyMessage::VersoInternalMessage(message) => {
+                    v.handle_verso_internal_message(message);
+                }
+                EventLoopProxyMessage::ControlRequest(request, client) => {
+                    v.handle_control_request(request, client, event_loop);
+                }
+                EventLoopProxyMessage::ControlClientClosed(client) => {
+                    v.handle_control_client_closed(client);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,157 @@
+use std::collections::HashMap;
+
+use base::id::WebViewId;
+use constellation_traits::EmbedderToConstellationMessage;
+use crossbeam_channel::Sender;
+use embedder_traits::{EmbedderMsg, WebResourceResponseMsg};
+use ipc_channel::ipc::IpcSender;
+use versoview_messages::{AuthCallback, ToControllerMessage};
+use winit::window::WindowId;
+
+use crate::verso::send_to_constellation;
+
+/// An OAuth flow started by the controller, running in its own popup window.
+pub struct AuthFlow {
+    /// The ID of the request from the controller.
+    pub id: uuid::Uuid,
+    /// The redirect URL registered by the embedder, the flow is done once the popup navigates to it.
+    pub callback_url: url::Url,
+    /// The webview showing the authorization page.
+    pub webview_id: WebViewId,
+}
+
+impl AuthFlow {
+    /// Whether the URL is the callback URL, its query and fragment are ignored.
+    pub fn is_callback(&self, url: &url::Url) -> bool {
+        url.scheme() == self.callback_url.scheme()
+            && url.host_str() == self.callback_url.host_str()
+            && url.port_or_known_default() == self.callback_url.port_or_known_default()
+            && url.path() == self.callback_url.path()
+    }
+}
+
+/// Collect the parameters of a redirect to the callback URL. Both the query and the fragment
+/// are read since the implicit flow returns its tokens in the fragment.
+pub fn auth_callback(url: url::Url) -> AuthCallback {
+    let mut params: Vec<(String, String)> = url.query_pairs().into_owned().collect();
+    if let Some(fragment) = url.fragment() {
+        params.extend(url::form_urlencoded::parse(fragment.as_bytes()).into_owned());
+    }
+    AuthCallback { url, params }
+}
+
+/// The OAuth flows in progress, by their popup window.
+#[derive(Default)]
+pub struct AuthFlows {
+    flows: HashMap<WindowId, AuthFlow>,
+}
+
+impl AuthFlows {
+    /// Create a new `AuthFlows`.
+    pub fn new() -> Self {
+        Self {
+            flows: HashMap::new(),
+        }
+    }
+
+    /// Track a flow running in the window.
+    pub fn insert(&mut self, window_id: WindowId, flow: AuthFlow) {
+        self.flows.insert(window_id, flow);
+    }
+
+    /// Whether the window is the popup of a flow.
+    pub fn contains(&self, window_id: WindowId) -> bool {
+        self.flows.contains_key(&window_id)
+    }
+
+    /// Cancel the flow of the window, e.g. when the user closes the popup, and close its webview.
+    pub(crate) fn cancel(
+        &mut self,
+        window_id: WindowId,
+        constellation_sender: &Sender<EmbedderToConstellationMessage>,
+        to_controller_sender: &Option<IpcSender<ToControllerMessage>>,
+    ) {
+        if let Some(flow) = self.flows.remove(&window_id) {
+            log::debug!("Verso cancelled auth flow {}", flow.id);
+            send_to_constellation(
+                constellation_sender,
+                EmbedderToConstellationMessage::CloseWebView(flow.webview_id),
+            );
+            send_response(to_controller_sender, flow.id, None);
+        }
+    }
+
+    /// Finish a flow when its popup is redirected to the callback URL. The request is cancelled
+    /// and `None` is returned, other messages are given back.
+    pub(crate) fn handle_callback(
+        &mut self,
+        msg: EmbedderMsg,
+        constellation_sender: &Sender<EmbedderToConstellationMessage>,
+        to_controller_sender: &Option<IpcSender<ToControllerMessage>>,
+    ) -> Option<EmbedderMsg> {
+        let (webview_id, url) = match msg {
+            EmbedderMsg::WebResourceRequested(Some(webview_id), ref request, ref sender)
+                if request.is_for_main_frame
+                    && self
+                        .flow_of(webview_id)
+                        .is_some_and(|(_, flow)| flow.is_callback(&request.url)) =>
+            {
+                let _ = sender.send(WebResourceResponseMsg::CancelLoad);
+                (webview_id, request.url.clone())
+            }
+            EmbedderMsg::AllowNavigationRequest(webview_id, pipeline_id, ref url)
+                if self
+                    .flow_of(webview_id)
+                    .is_some_and(|(_, flow)| flow.is_callback(url.as_url())) =>
+            {
+                send_to_constellation(
+                    constellation_sender,
+                    EmbedderToConstellationMessage::AllowNavigationResponse(pipeline_id, false),
+                );
+                (webview_id, url.as_url().clone())
+            }
+            EmbedderMsg::WebViewClosed(webview_id) => {
+                // The page closed itself before the redirect
+                if let Some((window_id, _)) = self.flow_of(webview_id) {
+                    if let Some(flow) = self.flows.remove(&window_id) {
+                        send_response(to_controller_sender, flow.id, None);
+                    }
+                }
+                return Some(msg);
+            }
+            _ => return Some(msg),
+        };
+
+        let (window_id, _) = self.flow_of(webview_id)?;
+        let flow = self.flows.remove(&window_id)?;
+        log::debug!("Verso finished auth flow {} with {url}", flow.id);
+        send_to_constellation(
+            constellation_sender,
+            EmbedderToConstellationMessage::CloseWebView(flow.webview_id),
+        );
+        send_response(to_controller_sender, flow.id, Some(auth_callback(url)));
+        None
+    }
+
+    fn flow_of(&self, webview_id: WebViewId) -> Option<(WindowId, &AuthFlow)> {
+        self.flows
+            .iter()
+            .find(|(_, flow)| flow.webview_id == webview_id)
+            .map(|(window_id, flow)| (*window_id, flow))
+    }
+}
+
+fn send_response(
+    to_controller_sender: &Option<IpcSender<ToControllerMessage>>,
+    id: uuid::Uuid,
+    callback: Option<AuthCallback>,
+) {
+    let Some(to_controller_sender) = to_controller_sender else {
+        return;
+    };
+    if let Err(error) =
+        to_controller_sender.send(ToControllerMessage::AuthFlowResponse(id, callback))
+    {
+        log::error!("Verso failed to send AuthFlowResponse to controller: {error}");
+    }
+}
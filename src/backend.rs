@@ -0,0 +1,82 @@
+use dpi::PhysicalSize;
+use glutin::{
+    config::{Config, ConfigTemplateBuilder},
+    display::{Display, DisplayApiPreference},
+    prelude::GlDisplay,
+    surface::{Surface, WindowSurface},
+};
+use raw_window_handle::{HasDisplayHandle, HasWindowHandle};
+use versoview_messages::Vsync;
+use winit::window::Window as WinitWindow;
+
+use crate::rendering::{self, RenderingContext, gl_config_picker};
+
+/// A native surface hosting Verso's rendering context. Verso's own windows are winit windows, other
+/// windowing layers implement this to host the rendering context without winit, e.g. a Wayland
+/// surface created with smithay-client-toolkit and driven by a calloop event loop, or a DRM/KMS
+/// output of a device without a compositor.
+pub trait WindowBackend: HasWindowHandle + HasDisplayHandle {
+    /// Size of the drawable area, in physical pixels.
+    fn surface_size(&self) -> PhysicalSize<u32>;
+    /// Ratio of physical pixels to logical pixels of the surface.
+    fn scale_factor(&self) -> f64;
+    /// Ask the event loop of the backend for a new frame, e.g. on the next frame callback of the
+    /// compositor or the next page flip of the output.
+    fn request_redraw(&self);
+}
+
+impl WindowBackend for WinitWindow {
+    fn surface_size(&self) -> PhysicalSize<u32> {
+        self.inner_size()
+    }
+
+    fn scale_factor(&self) -> f64 {
+        WinitWindow::scale_factor(self)
+    }
+
+    fn request_redraw(&self) {
+        WinitWindow::request_redraw(self)
+    }
+}
+
+/// Create a GL display on the display connection of the backend and pick a config rendering to
+/// its surface.
+///
+/// Winit windows pick their config while the window is created instead, since X11 and WGL need it
+/// to create the window.
+pub fn create_gl_config(
+    backend: &(impl WindowBackend + ?Sized),
+    software_rendering: bool,
+) -> Result<Config, Box<dyn std::error::Error>> {
+    let raw_display_handle = backend.display_handle()?.as_raw();
+    let raw_window_handle = backend.window_handle()?.as_raw();
+    #[cfg(windows)]
+    let preference = DisplayApiPreference::WglThenEgl(Some(raw_window_handle));
+    #[cfg(apple)]
+    let preference = DisplayApiPreference::Cgl;
+    // EGL is available on X11, Wayland and GBM devices of DRM/KMS alike
+    #[cfg(not(any(windows, apple)))]
+    let preference = DisplayApiPreference::Egl;
+    let display = unsafe { Display::new(raw_display_handle, preference)? };
+
+    let template = ConfigTemplateBuilder::new()
+        .with_alpha_size(8)
+        .compatible_with_native_window(raw_window_handle);
+    let template = rendering::software_config_template(template, software_rendering);
+    let configs = unsafe { display.find_configs(template.build())? };
+    let mut configs = configs.peekable();
+    if configs.peek().is_none() {
+        return Err("No GL config renders to the surface of the backend".into());
+    }
+    Ok(gl_config_picker(Box::new(configs)))
+}
+
+/// Create the rendering context of a surface of another windowing layer than winit.
+pub fn create_rendering_context(
+    backend: &(impl WindowBackend + ?Sized),
+    software_rendering: bool,
+    vsync: Vsync,
+) -> Result<(RenderingContext, Surface<WindowSurface>), Box<dyn std::error::Error>> {
+    let gl_config = create_gl_config(backend, software_rendering)?;
+    RenderingContext::create(backend, &gl_config, backend.surface_size(), vsync)
+}
@@ -0,0 +1,214 @@
+use winit::window::Window;
+
+/// Script which implements `navigator.setAppBadge` and `navigator.clearAppBadge` with the
+/// `APP_BADGE` request of the Verso bridge.
+pub(crate) const BADGE_SCRIPT: &str = r#"
+(() => {
+  if (location.protocol === 'verso:' || !window.isSecureContext) {
+    return;
+  }
+
+  Navigator.prototype.setAppBadge = async function (contents) {
+    if (contents === undefined) {
+      prompt('VERSO::APP_BADGE::flag');
+      return;
+    }
+    const count = Number(contents);
+    if (!Number.isFinite(count) || count < 0) {
+      throw new TypeError('The badge must be a non-negative number');
+    }
+    prompt(`VERSO::APP_BADGE::${Math.floor(count)}`);
+  };
+  Navigator.prototype.clearAppBadge = async function () {
+    prompt('VERSO::APP_BADGE::0');
+  };
+})();
+"#;
+
+/// Badge of the app, see the [Badging API](https://w3c.github.io/badging/).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Badge {
+    /// No badge
+    None,
+    /// A badge without a number, e.g. for unread content
+    Flag,
+    /// A badge with a count
+    Count(u64),
+}
+
+impl Badge {
+    /// Parse the badge of an `APP_BADGE` request, a count of 0 clears the badge.
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "flag" => Some(Badge::Flag),
+            value => match value.parse::<u64>().ok()? {
+                0 => Some(Badge::None),
+                count => Some(Badge::Count(count)),
+            },
+        }
+    }
+
+    /// The text shown in the badge, `None` if there's no badge.
+    pub fn label(&self) -> Option<String> {
+        match self {
+            Badge::None => None,
+            Badge::Flag => Some("•".to_string()),
+            Badge::Count(count) if *count > 99 => Some("99+".to_string()),
+            Badge::Count(count) => Some(count.to_string()),
+        }
+    }
+}
+
+/// Show the badge on the dock icon on macOS, the taskbar button of the window on Windows, or the
+/// launcher entry of desktops following the Unity launcher API on Linux.
+pub(crate) fn set_badge(window: &Window, badge: Badge) {
+    if let Err(e) = platform::set_badge(window, badge) {
+        log::error!("Verso failed to set the app badge: {e}");
+    }
+}
+
+#[cfg(macos)]
+mod platform {
+    use objc2_app_kit::NSApplication;
+    use objc2_foundation::{MainThreadMarker, NSString};
+    use winit::window::Window;
+
+    use super::Badge;
+
+    pub fn set_badge(_window: &Window, badge: Badge) -> Result<(), String> {
+        let mtm = MainThreadMarker::new().ok_or("Badges must be set on the main thread")?;
+        let label = badge.label().map(|label| NSString::from_str(&label));
+        unsafe {
+            NSApplication::sharedApplication(mtm)
+                .dockTile()
+                .setBadgeLabel(label.as_deref());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use raw_window_handle::{HasWindowHandle, RawWindowHandle};
+    use windows::{
+        Win32::{
+            Foundation::HWND,
+            System::Com::{CLSCTX_INPROC_SERVER, CoCreateInstance},
+            UI::{
+                Shell::{ITaskbarList3, TaskbarList},
+                WindowsAndMessaging::{CreateIcon, DestroyIcon, HICON},
+            },
+        },
+        core::HSTRING,
+    };
+    use winit::window::Window;
+
+    use super::Badge;
+
+    /// Size of overlay icons.
+    const ICON_SIZE: i32 = 16;
+
+    pub fn set_badge(window: &Window, badge: Badge) -> Result<(), String> {
+        let handle = window.window_handle().map_err(|e| e.to_string())?;
+        let RawWindowHandle::Win32(handle) = handle.as_raw() else {
+            return Ok(());
+        };
+        let hwnd = HWND(handle.hwnd.get() as _);
+
+        let set_overlay = || -> windows::core::Result<()> {
+            let taskbar: ITaskbarList3 =
+                unsafe { CoCreateInstance(&TaskbarList, None, CLSCTX_INPROC_SERVER)? };
+            unsafe { taskbar.HrInit()? };
+            let description = HSTRING::from(badge.label().unwrap_or_default());
+            if badge == Badge::None {
+                return unsafe { taskbar.SetOverlayIcon(hwnd, HICON::default(), &description) };
+            }
+            // Overlay icons are too small for text, so counts are shown as a dot too
+            let icon = dot_icon()?;
+            let result = unsafe { taskbar.SetOverlayIcon(hwnd, icon, &description) };
+            let _ = unsafe { DestroyIcon(icon) };
+            result
+        };
+        set_overlay().map_err(|e| e.to_string())
+    }
+
+    /// A red dot on a transparent background.
+    fn dot_icon() -> windows::core::Result<HICON> {
+        let center = (ICON_SIZE as f32 - 1.0) / 2.0;
+        let radius = ICON_SIZE as f32 / 2.0;
+        let mut color = Vec::with_capacity((ICON_SIZE * ICON_SIZE * 4) as usize);
+        for y in 0..ICON_SIZE {
+            for x in 0..ICON_SIZE {
+                let inside = (x as f32 - center).hypot(y as f32 - center) <= radius;
+                // BGRA
+                color.extend_from_slice(if inside {
+                    &[0x30, 0x3b, 0xe5, 0xff]
+                } else {
+                    &[0, 0, 0, 0]
+                });
+            }
+        }
+        // The alpha channel of the color bitmap is used, so the mask is empty
+        let mask = vec![0u8; (ICON_SIZE * ICON_SIZE / 8) as usize];
+        unsafe {
+            CreateIcon(
+                None,
+                ICON_SIZE,
+                ICON_SIZE,
+                1,
+                32,
+                mask.as_ptr(),
+                color.as_ptr(),
+            )
+        }
+    }
+}
+
+#[cfg(linux)]
+mod platform {
+    use std::collections::HashMap;
+
+    use winit::window::Window;
+    use zbus::{blocking::Connection, zvariant::Value};
+
+    use super::Badge;
+
+    /// Desktop entry of Verso, which the launcher shows the badge on.
+    const APP_URI: &str = "application://verso.desktop";
+
+    pub fn set_badge(_window: &Window, badge: Badge) -> Result<(), String> {
+        let connection = Connection::session().map_err(|e| e.to_string())?;
+        let count = match badge {
+            Badge::Count(count) => i64::try_from(count).unwrap_or(i64::MAX),
+            _ => 0,
+        };
+        let properties: HashMap<&str, Value> = HashMap::from([
+            ("count", Value::from(count)),
+            (
+                "count-visible",
+                Value::from(matches!(badge, Badge::Count(_))),
+            ),
+            ("urgent", Value::from(badge == Badge::Flag)),
+        ]);
+        connection
+            .emit_signal(
+                None::<&str>,
+                "/com/canonical/unity/launcherentry/verso",
+                "com.canonical.Unity.LauncherEntry",
+                "Update",
+                &(APP_URI, properties),
+            )
+            .map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(not(any(linux, macos, windows)))]
+mod platform {
+    use winit::window::Window;
+
+    use super::Badge;
+
+    pub fn set_badge(_window: &Window, _badge: Badge) -> Result<(), String> {
+        Ok(())
+    }
+}
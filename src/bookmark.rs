@@ -1,6 +1,7 @@
 use std::{fs::File, path::PathBuf, str::FromStr};
 
 use serde::{Deserialize, Serialize};
+use versoview_messages::BookmarkInfo;
 
 /// A struct representing a bookmark with a name and URL.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -11,18 +12,43 @@ pub struct Bookmark {
     pub name: String,
     /// The URL of the bookmark.
     pub url: String,
+    /// Path of the folder of the bookmark from the top level, empty at the top level.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub folder: Vec<String>,
 }
 
 impl Bookmark {
     /// Creates a new bookmark with the given name and URL.
     pub fn new(name: String, url: String) -> Self {
+        Self::in_folder(name, url, Vec::new())
+    }
+
+    /// Creates a new bookmark in the folder at the path.
+    pub fn in_folder(name: String, url: String, folder: Vec<String>) -> Self {
         Self {
             id: BookmarkId(url.clone()),
             name,
             url,
+            folder,
+        }
+    }
+
+    /// The bookmark as reported to the controller.
+    pub fn to_info(&self) -> BookmarkInfo {
+        BookmarkInfo {
+            name: self.name.clone(),
+            url: self.url.clone(),
+            folder: self.folder.clone(),
         }
     }
 }
+
+impl From<BookmarkInfo> for Bookmark {
+    fn from(info: BookmarkInfo) -> Self {
+        Self::in_folder(info.name, info.url, info.folder)
+    }
+}
+
 impl std::fmt::Display for Bookmark {
     /// Formats the bookmark as a string.
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -31,7 +57,6 @@ impl std::fmt::Display for Bookmark {
 }
 
 /// A struct representing a collection of bookmarks.
-/// TODO: Implement a way to save and load bookmarks from a file.
 pub struct BookmarkManager {
     /// A vector of bookmarks.
     bookmarks: Vec<Bookmark>,
@@ -62,6 +87,63 @@ impl BookmarkManager {
         self.bookmarks.push(bookmark);
     }
 
+    /// Adds a bookmark, or replaces the bookmark of the same page in place.
+    pub fn add_bookmark(&mut self, bookmark: Bookmark) {
+        if let Some(existing) = self.bookmarks.iter_mut().find(|b| b.id == bookmark.id) {
+            *existing = bookmark;
+        } else {
+            self.bookmarks.push(bookmark);
+        }
+    }
+
+    /// Adds the bookmarks of pages which aren't bookmarked yet, returns how many were added.
+    pub fn merge_bookmarks(&mut self, bookmarks: Vec<Bookmark>) -> usize {
+        let mut count = 0;
+        for bookmark in bookmarks {
+            if !self.bookmarks.iter().any(|b| b.id == bookmark.id) {
+                self.bookmarks.push(bookmark);
+                count += 1;
+            }
+        }
+        count
+    }
+
+    /// Bookmarks the page, or removes its bookmark if it's already bookmarked. Returns whether
+    /// the page is bookmarked now.
+    pub fn toggle_bookmark(&mut self, name: String, url: String) -> bool {
+        if let Some(pos) = self.bookmarks.iter().position(|b| b.url == url) {
+            self.bookmarks.remove(pos);
+            false
+        } else {
+            self.append_bookmark(name, url);
+            true
+        }
+    }
+
+    /// Moves a bookmark to the folder at the path, the top level if empty.
+    pub fn move_bookmark(&mut self, id: BookmarkId, folder: Vec<String>) -> Result<(), String> {
+        if let Some(bookmark) = self.bookmarks.iter_mut().find(|bookmark| bookmark.id == id) {
+            bookmark.folder = folder;
+            Ok(())
+        } else {
+            Err(format!("Bookmark with ID {} not found", id.0))
+        }
+    }
+
+    /// Gets the paths of the folders holding bookmarks, in the order they first appear.
+    pub fn folders(&self) -> Vec<&[String]> {
+        let mut folders: Vec<&[String]> = Vec::new();
+        for bookmark in &self.bookmarks {
+            for depth in 1..=bookmark.folder.len() {
+                let path = &bookmark.folder[..depth];
+                if !folders.contains(&path) {
+                    folders.push(path);
+                }
+            }
+        }
+        folders
+    }
+
     /// Removes a bookmark from the manager by its index.
     pub fn remove_bookmark(&mut self, id: BookmarkId) -> Result<(), String> {
         if let Some(pos) = self.bookmarks.iter().position(|bookmark| bookmark.id == id) {
@@ -85,6 +167,154 @@ impl BookmarkManager {
     pub fn bookmarks(&self) -> &Vec<Bookmark> {
         &self.bookmarks
     }
+
+    /// Writes the bookmarks in the Netscape bookmarks HTML format, which other browsers import.
+    pub fn to_netscape_html(&self) -> String {
+        let mut html = String::from(
+            "<!DOCTYPE NETSCAPE-Bookmark-file-1>\n\
+             <!-- This is an automatically generated file.\n     \
+             It will be read and overwritten.\n     \
+             DO NOT EDIT! -->\n\
+             <META HTTP-EQUIV=\"Content-Type\" CONTENT=\"text/html; charset=UTF-8\">\n\
+             <TITLE>Bookmarks</TITLE>\n\
+             <H1>Bookmarks</H1>\n",
+        );
+        let bookmarks: Vec<&Bookmark> = self.bookmarks.iter().collect();
+        write_netscape_folder(&mut html, &bookmarks, 0);
+        html
+    }
+}
+
+/// Writes the list of the bookmarks in a folder `depth` levels deep, each sub folder where its
+/// first bookmark is.
+fn write_netscape_folder(html: &mut String, bookmarks: &[&Bookmark], depth: usize) {
+    let indent = "    ".repeat(depth);
+    html.push_str(&format!("{indent}<DL><p>\n"));
+    let mut written_folders: Vec<&str> = Vec::new();
+    for bookmark in bookmarks {
+        match bookmark.folder.get(depth) {
+            None => html.push_str(&format!(
+                "{indent}    <DT><A HREF=\"{}\">{}</A>\n",
+                escape_html(&bookmark.url),
+                escape_html(&bookmark.name)
+            )),
+            Some(folder) if !written_folders.contains(&folder.as_str()) => {
+                written_folders.push(folder);
+                let sub_folder: Vec<&Bookmark> = bookmarks
+                    .iter()
+                    .filter(|b| b.folder.get(depth) == Some(folder))
+                    .copied()
+                    .collect();
+                html.push_str(&format!(
+                    "{indent}    <DT><H3>{}</H3>\n",
+                    escape_html(folder)
+                ));
+                write_netscape_folder(html, &sub_folder, depth + 1);
+            }
+            Some(_) => {}
+        }
+    }
+    html.push_str(&format!("{indent}</DL><p>\n"));
+}
+
+/// Reads the bookmarks of a file in the Netscape bookmarks HTML format, which other browsers
+/// export. Folders are kept, separators and Firefox's `place:` queries are skipped.
+pub fn parse_netscape_html(html: &str) -> Vec<Bookmark> {
+    let mut bookmarks = Vec::new();
+    // Folder opened by each `<DL>` which isn't closed yet, `None` for the top level list
+    let mut folders: Vec<Option<String>> = Vec::new();
+    let mut folder_name = None;
+    let mut rest = html;
+    while let Some(start) = rest.find('<') {
+        let Some(end) = rest[start..].find('>') else {
+            break;
+        };
+        let tag = &rest[start + 1..start + end];
+        rest = &rest[start + end + 1..];
+        // The text of an element goes until the next tag, since `<` is escaped in the text
+        let text = &rest[..rest.find('<').unwrap_or(rest.len())];
+        let tag_name = tag
+            .split(|c: char| c.is_ascii_whitespace())
+            .next()
+            .unwrap_or_default();
+        if tag_name.eq_ignore_ascii_case("H3") {
+            folder_name = Some(unescape_html(text.trim()));
+        } else if tag_name.eq_ignore_ascii_case("DL") {
+            folders.push(folder_name.take());
+        } else if tag_name.eq_ignore_ascii_case("/DL") {
+            folders.pop();
+        } else if tag_name.eq_ignore_ascii_case("A") {
+            let Some(url) = attribute(tag, "HREF").map(|url| unescape_html(&url)) else {
+                continue;
+            };
+            if url.starts_with("place:") || url.starts_with("javascript:") {
+                continue;
+            }
+            let name = unescape_html(text.trim());
+            let name = if name.is_empty() { url.clone() } else { name };
+            let folder = folders.iter().flatten().cloned().collect();
+            bookmarks.push(Bookmark::in_folder(name, url, folder));
+        }
+    }
+    bookmarks
+}
+
+/// Value of the quoted attribute of a tag, its name in any case.
+fn attribute(tag: &str, name: &str) -> Option<String> {
+    let upper = tag.to_ascii_uppercase();
+    let start = upper.find(&format!(" {name}=\""))? + name.len() + 3;
+    let end = tag[start..].find('"')? + start;
+    Some(tag[start..end].to_string())
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn unescape_html(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        unescaped.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let entity = rest.find(';').map(|end| (&rest[1..end], end));
+        let decoded = entity.and_then(|(entity, end)| {
+            let c = match entity {
+                "amp" => '&',
+                "lt" => '<',
+                "gt" => '>',
+                "quot" => '"',
+                "apos" => '\'',
+                "nbsp" => '\u{a0}',
+                _ => {
+                    let code = match entity
+                        .strip_prefix("#x")
+                        .or_else(|| entity.strip_prefix("#X"))
+                    {
+                        Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                        None => entity.strip_prefix('#')?.parse().ok()?,
+                    };
+                    char::from_u32(code)?
+                }
+            };
+            Some((c, end))
+        });
+        match decoded {
+            Some((c, end)) => {
+                unescaped.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                unescaped.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    unescaped.push_str(rest);
+    unescaped
 }
 
 /// BookmarkId is a unique identifier for a bookmark.
@@ -140,3 +370,83 @@ impl BookmarkStorage {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn folders_and_names(bookmarks: &[Bookmark]) -> Vec<(Vec<String>, &str, &str)> {
+        bookmarks
+            .iter()
+            .map(|b| (b.folder.clone(), b.name.as_str(), b.url.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn test_netscape_html_round_trip() {
+        let mut manager = BookmarkManager::new();
+        manager.set_bookmarks(vec![
+            Bookmark::new(
+                "Verso & \"Servo\"".to_string(),
+                "https://example.com/?a=1&b=2".to_string(),
+            ),
+            Bookmark::in_folder(
+                "Docs".to_string(),
+                "https://docs.example.com/".to_string(),
+                vec!["Work".to_string(), "<Reference>".to_string()],
+            ),
+            Bookmark::in_folder(
+                "Tracker".to_string(),
+                "https://tracker.example.com/".to_string(),
+                vec!["Work".to_string()],
+            ),
+        ]);
+        let bookmarks = parse_netscape_html(&manager.to_netscape_html());
+        assert_eq!(
+            folders_and_names(&bookmarks),
+            folders_and_names(manager.bookmarks())
+        );
+    }
+
+    #[test]
+    fn test_parse_netscape_html() {
+        let html = r#"<!DOCTYPE NETSCAPE-Bookmark-file-1>
+<TITLE>Bookmarks</TITLE>
+<H1>Bookmarks Menu</H1>
+<DL><p>
+    <DT><H3 ADD_DATE="1700000000">Bookmarks Toolbar</H3>
+    <DL><p>
+        <dt><a href="https://example.com/" add_date="1700000000">It&#39;s &lt;here&gt;</a>
+        <DT><A HREF="place:sort=8&amp;maxResults=10">Recent Tags</A>
+        <HR>
+        <DT><A HREF="https://untitled.example.com/"></A>
+    </DL><p>
+    <DT><A HREF="javascript:alert(1)">Bookmarklet</A>
+    <DT><A HREF="https://top.example.com/?a=1&amp;b=2">Top</A>
+</DL><p>
+"#;
+        let bookmarks = parse_netscape_html(html);
+        let toolbar = vec!["Bookmarks Toolbar".to_string()];
+        assert_eq!(
+            folders_and_names(&bookmarks),
+            [
+                (toolbar.clone(), "It's <here>", "https://example.com/"),
+                (
+                    toolbar,
+                    "https://untitled.example.com/",
+                    "https://untitled.example.com/"
+                ),
+                (Vec::new(), "Top", "https://top.example.com/?a=1&b=2"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unescape_html() {
+        assert_eq!(
+            unescape_html("a &amp; b &#x41;&#66; &unknown; & &#xZZ;"),
+            "a & b AB &unknown; & &#xZZ;"
+        );
+        assert_eq!(unescape_html(&escape_html("<\"&\">")), "<\"&\">");
+    }
+}
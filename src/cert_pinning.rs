@@ -0,0 +1,305 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs::File,
+    io::BufReader,
+    net::TcpStream,
+    path::Path,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use base64::{Engine, engine::general_purpose::STANDARD};
+use embedder_traits::{EmbedderMsg, EmbedderProxy, WebResourceResponseMsg};
+use rustls::{
+    ClientConfig, ClientConnection, DigitallySignedStruct, RootCertStore, SignatureScheme,
+    client::{
+        WebPkiServerVerifier,
+        danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier},
+    },
+    pki_types::{CertificateDer, ServerName, UnixTime},
+};
+use sha2::{Digest, Sha256};
+use versoview_messages::CertificatePin;
+
+/// SHA-256 hash of a SubjectPublicKeyInfo.
+type Pin = [u8; 32];
+
+/// Host and port of a pinned origin.
+type PinnedOrigin = (String, u16);
+
+/// Checks the certificate pins of origins before their requests are sent.
+///
+/// Servo's network stack doesn't expose the certificates of its connections, so the pins are
+/// checked with a separate TLS handshake the first time a pinned origin is requested in the
+/// session. Requests are held until the check is done and cancelled if it fails.
+///
+/// This isn't pinning of the connections Servo sends the requests on: an attacker who only
+/// intercepts Servo's connections, or starts intercepting after the check, isn't detected. It
+/// catches interception of the network Verso runs on, e.g. by a proxy with its own root.
+pub struct CertificatePins {
+    pins: HashMap<PinnedOrigin, Vec<Pin>>,
+    roots: Arc<RootCertStore>,
+    /// Whether invalid certificates are accepted, the same as Servo does.
+    ignore_certificate_errors: bool,
+    /// Origins which passed the check in this session.
+    verified: Arc<Mutex<HashSet<PinnedOrigin>>>,
+    /// Requests held for the origins being checked, so each origin is checked once at a time.
+    pending: Arc<Mutex<HashMap<PinnedOrigin, Vec<EmbedderMsg>>>>,
+    /// To hand the held requests back to Verso once they are checked.
+    embedder_proxy: EmbedderProxy,
+}
+
+impl CertificatePins {
+    /// Create certificate pins from the config. Certificates are verified against the roots of
+    /// `certificate_bundle` if set, the same as Servo does.
+    pub fn new(
+        certificate_pins: &[CertificatePin],
+        certificate_bundle: Option<&Path>,
+        ignore_certificate_errors: bool,
+        embedder_proxy: EmbedderProxy,
+    ) -> Self {
+        let mut pins: HashMap<PinnedOrigin, Vec<Pin>> = HashMap::new();
+        for certificate_pin in certificate_pins {
+            let origin = &certificate_pin.origin;
+            let (Some(host), Some(port)) = (origin.host_str(), origin.port_or_known_default())
+            else {
+                log::error!("Invalid origin of certificate pin: {origin}");
+                continue;
+            };
+            let decoded = certificate_pin.sha256_pins.iter().filter_map(|pin| {
+                let decoded = STANDARD
+                    .decode(pin)
+                    .ok()
+                    .and_then(|pin| Pin::try_from(pin).ok());
+                if decoded.is_none() {
+                    log::error!("Invalid certificate pin of {origin}: {pin}");
+                }
+                decoded
+            });
+            pins.entry((host.to_string(), port))
+                .or_default()
+                .extend(decoded);
+        }
+
+        Self {
+            pins,
+            roots: Arc::new(load_roots(certificate_bundle)),
+            ignore_certificate_errors,
+            verified: Arc::new(Mutex::new(HashSet::new())),
+            pending: Arc::new(Mutex::new(HashMap::new())),
+            embedder_proxy,
+        }
+    }
+
+    /// TLS config of the requests Verso sends itself instead of Servo. It verifies certificates
+    /// against the same roots as Servo, unless certificate errors are ignored, and requires the
+    /// certificates of pinned hosts to match their pins on every connection.
+    pub(crate) fn client_config(&self) -> Result<ClientConfig, Box<dyn std::error::Error>> {
+        let mut pins: HashMap<String, Vec<Pin>> = HashMap::new();
+        for ((host, _), origin_pins) in &self.pins {
+            pins.entry(host.clone())
+                .or_default()
+                .extend(origin_pins.iter().copied());
+        }
+        let verifier = PinVerifier {
+            inner: WebPkiServerVerifier::builder(self.roots.clone()).build()?,
+            pins,
+            ignore_certificate_errors: self.ignore_certificate_errors,
+        };
+        Ok(ClientConfig::builder()
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(verifier))
+            .with_no_client_auth())
+    }
+
+    /// Hold requests to pinned origins which aren't checked yet and return `None`, other messages
+    /// are given back. Held requests are sent to Verso again once their origin passes the check.
+    pub(crate) fn handle_request(&self, msg: EmbedderMsg) -> Option<EmbedderMsg> {
+        let EmbedderMsg::WebResourceRequested(_, ref request, _) = msg else {
+            return Some(msg);
+        };
+        let url = request.url.clone();
+        if url.scheme() != "https" {
+            return Some(msg);
+        }
+        let (Some(host), Some(port)) = (url.host_str(), url.port_or_known_default()) else {
+            return Some(msg);
+        };
+        let origin = (host.to_string(), port);
+        let Some(pins) = self.pins.get(&origin).cloned() else {
+            return Some(msg);
+        };
+        if self.verified.lock().unwrap().contains(&origin) {
+            return Some(msg);
+        }
+        {
+            let mut pending = self.pending.lock().unwrap();
+            if let Some(held) = pending.get_mut(&origin) {
+                held.push(msg);
+                return None;
+            }
+            pending.insert(origin.clone(), vec![msg]);
+        }
+
+        let roots = self.roots.clone();
+        let verified = self.verified.clone();
+        let pending = self.pending.clone();
+        let embedder_proxy = self.embedder_proxy.clone();
+        std::thread::spawn(move || {
+            let result = check_pins(&origin, pins, roots);
+            // The origin is verified before the held requests are taken, so requests arriving
+            // meanwhile are either held or sent right away
+            if result.is_ok() {
+                verified.lock().unwrap().insert(origin.clone());
+            }
+            let held = pending.lock().unwrap().remove(&origin).unwrap_or_default();
+            match result {
+                Ok(()) => {
+                    for msg in held {
+                        embedder_proxy.send(msg);
+                    }
+                }
+                Err(error) => {
+                    log::error!(
+                        "Verso blocked {} requests to {url}, certificate pinning failed: {error}",
+                        held.len()
+                    );
+                    for msg in held {
+                        if let EmbedderMsg::WebResourceRequested(_, _, sender) = msg {
+                            let _ = sender.send(WebResourceResponseMsg::CancelLoad);
+                        }
+                    }
+                }
+            }
+        });
+        None
+    }
+}
+
+/// Load the root certificates of the bundle, or the default ones if it's not set.
+fn load_roots(certificate_bundle: Option<&Path>) -> RootCertStore {
+    let mut roots = RootCertStore::empty();
+    let Some(path) = certificate_bundle else {
+        roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+        return roots;
+    };
+    match File::open(path) {
+        Ok(file) => {
+            let certificates = rustls_pemfile::certs(&mut BufReader::new(file)).flatten();
+            let (_, ignored) = roots.add_parsable_certificates(certificates);
+            if ignored > 0 {
+                log::warn!(
+                    "Verso ignored {ignored} invalid certificates of {}",
+                    path.display()
+                );
+            }
+        }
+        Err(error) => log::error!(
+            "Verso failed to read certificate bundle {}: {error}",
+            path.display()
+        ),
+    }
+    roots
+}
+
+/// Connect to the origin and check if its certificate chain has one of the pins.
+fn check_pins(
+    (host, port): &PinnedOrigin,
+    pins: Vec<Pin>,
+    roots: Arc<RootCertStore>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let verifier = PinVerifier {
+        inner: WebPkiServerVerifier::builder(roots).build()?,
+        pins: HashMap::from([(host.clone(), pins)]),
+        ignore_certificate_errors: false,
+    };
+    let config = ClientConfig::builder()
+        .dangerous()
+        .with_custom_certificate_verifier(Arc::new(verifier))
+        .with_no_client_auth();
+    let mut connection =
+        ClientConnection::new(Arc::new(config), ServerName::try_from(host.clone())?)?;
+    let mut stream = TcpStream::connect((host.as_str(), *port))?;
+    stream.set_read_timeout(Some(Duration::from_secs(10)))?;
+    stream.set_write_timeout(Some(Duration::from_secs(10)))?;
+    while connection.is_handshaking() {
+        connection.complete_io(&mut stream)?;
+    }
+    Ok(())
+}
+
+/// Hash the SubjectPublicKeyInfo of a certificate.
+fn spki_pin(certificate: &CertificateDer<'_>) -> Option<Pin> {
+    let (_, certificate) = x509_parser::parse_x509_certificate(certificate.as_ref()).ok()?;
+    Some(Sha256::digest(certificate.tbs_certificate.subject_pki.raw).into())
+}
+
+/// Verifies certificates as usual, then requires one of them to match the pins of the host if
+/// it has some.
+#[derive(Debug)]
+struct PinVerifier {
+    inner: Arc<WebPkiServerVerifier>,
+    /// Pins by host
+    pins: HashMap<String, Vec<Pin>>,
+    /// Whether certificates are accepted when they fail the usual verification, pins are still
+    /// required.
+    ignore_certificate_errors: bool,
+}
+
+impl ServerCertVerifier for PinVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        server_name: &ServerName<'_>,
+        ocsp_response: &[u8],
+        now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        let verified = self.inner.verify_server_cert(
+            end_entity,
+            intermediates,
+            server_name,
+            ocsp_response,
+            now,
+        );
+        if !self.ignore_certificate_errors {
+            verified?;
+        }
+        let Some(pins) = self.pins.get(server_name.to_str().as_ref()) else {
+            return Ok(ServerCertVerified::assertion());
+        };
+        let pinned = std::iter::once(end_entity)
+            .chain(intermediates)
+            .filter_map(spki_pin)
+            .any(|pin| pins.contains(&pin));
+        if pinned {
+            Ok(ServerCertVerified::assertion())
+        } else {
+            Err(rustls::Error::General(
+                "no certificate matches the pinned public keys".to_string(),
+            ))
+        }
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.inner.verify_tls12_signature(message, cert, dss)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.inner.verify_tls13_signature(message, cert, dss)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.inner.supported_verify_schemes()
+    }
+}
@@ -0,0 +1,390 @@
+use std::{borrow::Cow, io::Cursor, thread, time::Duration};
+
+use arboard::{Clipboard, ImageData};
+use base::id::WebViewId;
+use base64::{Engine, engine::general_purpose::STANDARD};
+use constellation_traits::EmbedderToConstellationMessage;
+use crossbeam_channel::Sender;
+use embedder_traits::WebDriverJSValue;
+use ipc_channel::ipc::IpcSender;
+use serde::{Deserialize, Serialize};
+
+use crate::{frames, verso::VersoInternalMsg};
+
+/// How long the page may take to copy or paste.
+const CLIPBOARD_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Script which copies, cuts and pastes in the focused element of the document when the user
+/// presses the shortcuts, since Servo only does it in text fields. The `copy`, `cut` and `paste`
+/// events are fired first, so pages can change the data or handle it themselves.
+pub(crate) const CLIPBOARD_SCRIPT: &str = r#"
+(() => {
+  if (window.versoClipboard) {
+    return;
+  }
+
+  // The focused element, inside shadow roots and frames of the same origin
+  const focused = () => {
+    let doc = document;
+    let element = doc.activeElement;
+    for (;;) {
+      if (element?.shadowRoot?.activeElement) {
+        element = element.shadowRoot.activeElement;
+        continue;
+      }
+      let inner = null;
+      try {
+        inner = element?.contentDocument;
+      } catch {
+        // Frames of other origins can't be reached
+      }
+      if (!inner?.activeElement) {
+        return { doc, element };
+      }
+      doc = inner;
+      element = inner.activeElement;
+    }
+  };
+  const isTextField = (element) => {
+    if (!['input', 'textarea'].includes(element?.localName)) {
+      return false;
+    }
+    try {
+      return typeof element.selectionStart === 'number';
+    } catch {
+      return false;
+    }
+  };
+  const canEdit = (element) => !element.readOnly && !element.disabled;
+
+  // Fire the clipboard event, the page handled it if it's cancelled
+  const dispatch = (target, type, data) => {
+    let clipboardData = null;
+    try {
+      clipboardData = new DataTransfer();
+      for (const [format, value] of Object.entries(data)) {
+        if (value !== null) {
+          clipboardData.setData(format, value);
+        }
+      }
+    } catch {
+      clipboardData = null;
+    }
+    const init = { clipboardData, bubbles: true, cancelable: true, composed: true };
+    let event;
+    try {
+      event = new ClipboardEvent(type, init);
+    } catch {
+      event = new Event(type, init);
+    }
+    return { handled: !target.dispatchEvent(event), clipboardData };
+  };
+
+  const copy = (cut) => {
+    const { doc, element } = focused();
+    const { handled, clipboardData } = dispatch(element ?? doc.body, cut ? 'cut' : 'copy', {});
+    if (handled) {
+      if (!clipboardData) {
+        return null;
+      }
+      const html = clipboardData.getData('text/html');
+      return JSON.stringify({ text: clipboardData.getData('text/plain'), html: html || null });
+    }
+    if (isTextField(element)) {
+      if (element.type === 'password') {
+        return null;
+      }
+      const start = element.selectionStart;
+      const end = element.selectionEnd;
+      const text = element.value.slice(start, end);
+      if (!text) {
+        return null;
+      }
+      if (cut && canEdit(element)) {
+        element.setRangeText('', start, end, 'end');
+        element.dispatchEvent(new InputEvent('input', { bubbles: true, inputType: 'deleteByCut' }));
+      }
+      return JSON.stringify({ text });
+    }
+    const selection = doc.getSelection();
+    if (!selection || selection.isCollapsed || !selection.rangeCount) {
+      return null;
+    }
+    const range = selection.getRangeAt(0);
+    const fragment = range.cloneContents();
+    // A selection of a single image copies the image
+    const image = fragment.children.length === 1 && !fragment.textContent.trim()
+      && fragment.firstElementChild.localName === 'img' ? fragment.firstElementChild.src : null;
+    const container = doc.createElement('div');
+    container.append(fragment);
+    const text = selection.toString();
+    if (cut && element?.isContentEditable) {
+      range.deleteContents();
+      element.dispatchEvent(new InputEvent('input', { bubbles: true, inputType: 'deleteByCut' }));
+    }
+    return JSON.stringify({ text, html: container.innerHTML, image });
+  };
+
+  // Elements and attributes which could run scripts are dropped from pasted HTML
+  const sanitize = (doc, html) => {
+    const parsed = new DOMParser().parseFromString(html, 'text/html');
+    parsed.querySelectorAll('script, style, link, meta, base, iframe, frame, object, embed, form')
+      .forEach((node) => node.remove());
+    for (const node of parsed.body.querySelectorAll('*')) {
+      for (const attribute of [...node.attributes]) {
+        if (attribute.name.startsWith('on') || /^\s*javascript:/i.test(attribute.value)) {
+          node.removeAttribute(attribute.name);
+        }
+      }
+    }
+    const template = doc.createElement('template');
+    template.innerHTML = parsed.body.innerHTML;
+    return template.content;
+  };
+
+  const paste = (text, html) => {
+    const { doc, element } = focused();
+    if (!element) {
+      return false;
+    }
+    if (dispatch(element, 'paste', { 'text/plain': text, 'text/html': html }).handled) {
+      return true;
+    }
+    const input = (data) => element.dispatchEvent(
+      new InputEvent('input', { bubbles: true, inputType: 'insertFromPaste', data }));
+    if (isTextField(element)) {
+      if (!canEdit(element)) {
+        return false;
+      }
+      // Single line fields drop the line breaks
+      const value = element.localName === 'input' ? text.replace(/\r?\n/g, ' ') : text;
+      element.setRangeText(value, element.selectionStart, element.selectionEnd, 'end');
+      input(value);
+      return true;
+    }
+    const selection = doc.getSelection();
+    if (!element.isContentEditable || !selection?.rangeCount) {
+      return false;
+    }
+    const range = selection.getRangeAt(0);
+    range.deleteContents();
+    const content = html === null ? doc.createTextNode(text) : sanitize(doc, html);
+    const last = content.nodeType === Node.DOCUMENT_FRAGMENT_NODE ? content.lastChild : content;
+    range.insertNode(content);
+    if (last) {
+      range.setStartAfter(last);
+    }
+    range.collapse(true);
+    selection.removeAllRanges();
+    selection.addRange(range);
+    input(text);
+    return true;
+  };
+
+  Object.defineProperty(window, 'versoClipboard', { value: Object.freeze({ copy, paste }) });
+})();
+"#;
+
+/// What is copied to the clipboard.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ClipboardContent {
+    /// Plain text, the fallback of the HTML
+    pub text: String,
+    /// HTML of the copied part of the document
+    pub html: Option<String>,
+    /// Copied image, it replaces the text since the clipboard only keeps one of them
+    pub image: Option<ClipboardImage>,
+}
+
+impl ClipboardContent {
+    /// Plain text content.
+    pub fn text(text: String) -> Self {
+        Self {
+            text,
+            ..Self::default()
+        }
+    }
+}
+
+/// An image in RGBA, 8 bits per channel.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClipboardImage {
+    /// Width in pixels
+    pub width: usize,
+    /// Height in pixels
+    pub height: usize,
+    /// Pixels row by row
+    pub rgba: Vec<u8>,
+}
+
+/// The selection the script copied, with the URL of its image.
+#[derive(Debug, Deserialize)]
+struct CopiedSelection {
+    text: String,
+    #[serde(default)]
+    html: Option<String>,
+    #[serde(default)]
+    image: Option<String>,
+}
+
+/// Copy the selection of the focused element of the webview on another thread, or cut it, and
+/// send [`VersoInternalMsg::SetClipboard`] with what the page copied.
+pub(crate) fn copy(
+    constellation_sender: Sender<EmbedderToConstellationMessage>,
+    webview_id: WebViewId,
+    cut: bool,
+    verso_internal_sender: IpcSender<VersoInternalMsg>,
+) {
+    spawn("ClipboardCopy", move || {
+        let script = format!("window.versoClipboard?.copy({cut}) ?? null");
+        let json = match frames::execute(
+            &constellation_sender,
+            webview_id.0,
+            script,
+            CLIPBOARD_TIMEOUT,
+        ) {
+            Some(Ok(WebDriverJSValue::String(json))) => json,
+            // Nothing is selected
+            Some(Ok(WebDriverJSValue::Null)) => return,
+            Some(Ok(value)) => {
+                log::warn!("Verso got an unexpected copied selection: {value:?}");
+                return;
+            }
+            Some(Err(error)) => {
+                log::warn!("Verso failed to copy the selection: {error:?}");
+                return;
+            }
+            None => return,
+        };
+        let selection = match serde_json::from_str::<CopiedSelection>(&json) {
+            Ok(selection) => selection,
+            Err(e) => {
+                log::warn!("Verso got an invalid copied selection: {e}");
+                return;
+            }
+        };
+        let content = ClipboardContent {
+            text: selection.text,
+            html: selection.html,
+            image: selection.image.as_deref().and_then(fetch_image),
+        };
+        let _ = verso_internal_sender.send(VersoInternalMsg::SetClipboard(content));
+    });
+}
+
+/// Fetch and decode the image of the URL on another thread, and send
+/// [`VersoInternalMsg::SetClipboard`] with it.
+pub(crate) fn copy_image(url: String, verso_internal_sender: IpcSender<VersoInternalMsg>) {
+    spawn("ClipboardImage", move || {
+        if let Some(image) = fetch_image(&url) {
+            let content = ClipboardContent {
+                image: Some(image),
+                ..ClipboardContent::default()
+            };
+            let _ = verso_internal_sender.send(VersoInternalMsg::SetClipboard(content));
+        }
+    });
+}
+
+/// Paste the text in the focused element of the webview on another thread, or the HTML in its
+/// editable content if it's `Some`.
+pub(crate) fn paste(
+    constellation_sender: Sender<EmbedderToConstellationMessage>,
+    webview_id: WebViewId,
+    text: String,
+    html: Option<String>,
+) {
+    let script = format!(
+        "window.versoClipboard?.paste({}, {}) ?? false",
+        serde_json::to_string(&text).unwrap(),
+        serde_json::to_string(&html).unwrap()
+    );
+    spawn("ClipboardPaste", move || {
+        match frames::execute(
+            &constellation_sender,
+            webview_id.0,
+            script,
+            CLIPBOARD_TIMEOUT,
+        ) {
+            Some(Ok(WebDriverJSValue::Boolean(true))) => {}
+            Some(Err(error)) => log::warn!("Verso failed to paste: {error:?}"),
+            _ => log::trace!("Verso found no editable element to paste in"),
+        }
+    });
+}
+
+/// Write the content to the clipboard.
+pub(crate) fn write(clipboard: &mut Clipboard, content: ClipboardContent) {
+    let result = match (content.image, content.html) {
+        (Some(image), _) => clipboard.set_image(ImageData {
+            width: image.width,
+            height: image.height,
+            bytes: Cow::Owned(image.rgba),
+        }),
+        (None, Some(html)) => clipboard.set_html(html, Some(content.text)),
+        (None, None) => clipboard.set_text(content.text),
+    };
+    if let Err(e) = result {
+        log::warn!("Verso failed to write to the clipboard: {e}");
+    }
+}
+
+/// Read the text to paste from the clipboard, with its HTML unless it's pasted as plain text. An
+/// image is pasted as HTML.
+pub(crate) fn read(clipboard: &mut Clipboard, plain_text: bool) -> (String, Option<String>) {
+    let text = clipboard.get_text().unwrap_or_default();
+    if plain_text {
+        return (text, None);
+    }
+    let html = clipboard.get().html().ok().or_else(|| {
+        if !text.is_empty() {
+            return None;
+        }
+        let image = clipboard.get_image().ok()?;
+        let image = image::RgbaImage::from_raw(
+            image.width as u32,
+            image.height as u32,
+            image.bytes.into_owned(),
+        )?;
+        let mut png = Vec::new();
+        image
+            .write_to(&mut Cursor::new(&mut png), image::ImageFormat::Png)
+            .ok()?;
+        Some(format!(
+            r#"<img src="data:image/png;base64,{}">"#,
+            STANDARD.encode(png)
+        ))
+    });
+    (text, html)
+}
+
+/// Fetch and decode an image to copy, `None` if it can't be loaded.
+fn fetch_image(url: &str) -> Option<ClipboardImage> {
+    let bytes = match reqwest::blocking::get(url).and_then(|response| response.bytes()) {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            log::warn!("Verso failed to fetch the image {url} to copy: {e}");
+            return None;
+        }
+    };
+    match image::load_from_memory(&bytes) {
+        Ok(image) => {
+            let image = image.to_rgba8();
+            Some(ClipboardImage {
+                width: image.width() as usize,
+                height: image.height() as usize,
+                rgba: image.into_raw(),
+            })
+        }
+        Err(e) => {
+            log::warn!("Verso failed to decode the image {url} to copy: {e}");
+            None
+        }
+    }
+}
+
+fn spawn(name: &str, f: impl FnOnce() + Send + 'static) {
+    if let Err(e) = thread::Builder::new().name(name.to_owned()).spawn(f) {
+        log::error!("Failed to spawn {name} thread: {e}");
+    }
+}
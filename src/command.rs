@@ -0,0 +1,680 @@
+use constellation_traits::{EmbedderToConstellationMessage, TraversalDirection};
+use ipc_channel::ipc::IpcSender;
+use keyboard_types::{Code, Modifiers};
+use serde::{Serialize, Serializer};
+use servo_url::ServoUrl;
+use versoview_messages::{ClipboardAction, ToControllerMessage};
+
+use crate::{
+    compositor::{IOCompositor, WebRenderDebugOption},
+    highlight,
+    keyboard::CMD_OR_CONTROL,
+    verso::{VersoInternalMsg, send_to_constellation},
+    window::Window,
+};
+
+/// Magnification applied by each zoom in or zoom out command
+const ZOOM_STEP: f32 = 1.1;
+
+/// Where a command is registered from.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub enum CommandSource {
+    /// Built-in Verso command
+    Verso,
+    /// Command registered by the webview controller
+    Embedder,
+}
+
+/// Keyboard shortcut of a command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Keybinding {
+    /// Modifiers to hold
+    pub modifiers: Modifiers,
+    /// Key to press
+    pub code: Code,
+}
+
+impl Keybinding {
+    /// Create a new keybinding.
+    pub fn new(modifiers: Modifiers, code: Code) -> Self {
+        Self { modifiers, code }
+    }
+
+    /// Human readable label of the keybinding, e.g. `Ctrl+Shift+P`.
+    pub fn label(&self) -> String {
+        let mut label = String::new();
+        if self.modifiers.contains(Modifiers::CONTROL) {
+            label.push_str("Ctrl+");
+        }
+        if self.modifiers.contains(Modifiers::META) {
+            label.push_str("Cmd+");
+        }
+        if self.modifiers.contains(Modifiers::ALT) {
+            label.push_str("Alt+");
+        }
+        if self.modifiers.contains(Modifiers::SHIFT) {
+            label.push_str("Shift+");
+        }
+        let code = self.code.to_string();
+        label.push_str(code.strip_prefix("Key").unwrap_or(&code));
+        label
+    }
+}
+
+impl Serialize for Keybinding {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.label())
+    }
+}
+
+/// A named user-facing action, which can be searched and executed from the command palette.
+#[derive(Debug, Clone, Serialize)]
+pub struct Command {
+    /// Unique id of the command, e.g. `tab.new`
+    pub id: String,
+    /// Human readable title of the command
+    pub title: String,
+    /// Category used to group commands, e.g. `Tab`
+    pub category: String,
+    /// Keyboard shortcut of the command
+    pub keybinding: Option<Keybinding>,
+    /// Where the command is registered from
+    pub source: CommandSource,
+}
+
+impl Command {
+    /// Create a built-in command.
+    pub fn new(id: &str, category: &str, title: &str, keybinding: Option<Keybinding>) -> Self {
+        Self {
+            id: id.to_string(),
+            title: title.to_string(),
+            category: category.to_string(),
+            keybinding,
+            source: CommandSource::Verso,
+        }
+    }
+
+    /// Create a command registered by the webview controller.
+    pub fn new_embedder(id: String, title: String) -> Self {
+        Self {
+            id,
+            title,
+            category: "Embedder".to_string(),
+            keybinding: None,
+            source: CommandSource::Embedder,
+        }
+    }
+
+    /// The text matched against the search query.
+    fn label(&self) -> String {
+        format!("{}: {}", self.category, self.title)
+    }
+}
+
+/// Registry of every command known to a window.
+pub struct CommandRegistry {
+    /// Registered commands, in registration order.
+    commands: Vec<Command>,
+}
+
+impl Default for CommandRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CommandRegistry {
+    /// Create a new `CommandRegistry` with the built-in commands.
+    pub fn new() -> Self {
+        let shortcut = |modifiers, code| Some(Keybinding::new(modifiers, code));
+        let commands = vec![
+            Command::new(
+                "tab.new",
+                "Tab",
+                "New Tab",
+                shortcut(CMD_OR_CONTROL, Code::KeyT),
+            ),
+            Command::new(
+                "tab.close",
+                "Tab",
+                "Close Tab",
+                shortcut(CMD_OR_CONTROL, Code::KeyW),
+            ),
+            Command::new(
+                "tab.next",
+                "Tab",
+                "Next Tab",
+                shortcut(Modifiers::CONTROL, Code::Tab),
+            ),
+            Command::new(
+                "tab.previous",
+                "Tab",
+                "Previous Tab",
+                shortcut(Modifiers::CONTROL | Modifiers::SHIFT, Code::Tab),
+            ),
+            Command::new(
+                "tab.move_left",
+                "Tab",
+                "Move Tab Left",
+                shortcut(CMD_OR_CONTROL | Modifiers::SHIFT, Code::PageUp),
+            ),
+            Command::new(
+                "tab.move_right",
+                "Tab",
+                "Move Tab Right",
+                shortcut(CMD_OR_CONTROL | Modifiers::SHIFT, Code::PageDown),
+            ),
+            Command::new(
+                "edit.cut",
+                "Edit",
+                "Cut",
+                shortcut(CMD_OR_CONTROL, Code::KeyX),
+            ),
+            Command::new(
+                "edit.copy",
+                "Edit",
+                "Copy",
+                shortcut(CMD_OR_CONTROL, Code::KeyC),
+            ),
+            Command::new(
+                "edit.paste",
+                "Edit",
+                "Paste",
+                shortcut(CMD_OR_CONTROL, Code::KeyV),
+            ),
+            Command::new(
+                "edit.paste_plain",
+                "Edit",
+                "Paste as Plain Text",
+                shortcut(CMD_OR_CONTROL | Modifiers::SHIFT, Code::KeyV),
+            ),
+            Command::new("navigation.back", "Navigation", "Go Back", None),
+            Command::new("navigation.forward", "Navigation", "Go Forward", None),
+            Command::new("navigation.reload", "Navigation", "Reload", None),
+            Command::new(
+                "navigation.focus_url",
+                "Navigation",
+                "Focus Address Bar",
+                shortcut(CMD_OR_CONTROL, Code::KeyL),
+            ),
+            Command::new(
+                "command_palette.open",
+                "View",
+                "Show All Commands",
+                shortcut(CMD_OR_CONTROL | Modifiers::SHIFT, Code::KeyP),
+            ),
+            Command::new(
+                "find.open",
+                "Find",
+                "Find in Page",
+                shortcut(CMD_OR_CONTROL, Code::KeyF),
+            ),
+            Command::new(
+                "find.next",
+                "Find",
+                "Find Next",
+                shortcut(CMD_OR_CONTROL, Code::KeyG),
+            ),
+            Command::new(
+                "find.previous",
+                "Find",
+                "Find Previous",
+                shortcut(CMD_OR_CONTROL | Modifiers::SHIFT, Code::KeyG),
+            ),
+            Command::new(
+                "zoom.in",
+                "Zoom",
+                "Zoom In",
+                shortcut(CMD_OR_CONTROL, Code::Equal),
+            ),
+            Command::new(
+                "zoom.out",
+                "Zoom",
+                "Zoom Out",
+                shortcut(CMD_OR_CONTROL, Code::Minus),
+            ),
+            Command::new(
+                "zoom.reset",
+                "Zoom",
+                "Reset Zoom",
+                shortcut(CMD_OR_CONTROL, Code::Digit0),
+            ),
+            Command::new(
+                "devtools.webrender_profiler",
+                "Developer",
+                "Toggle WebRender Profiler",
+                None,
+            ),
+            Command::new("window.new", "Window", "New Window", None),
+            Command::new(
+                "window.private",
+                "Window",
+                "New Private Window",
+                shortcut(CMD_OR_CONTROL | Modifiers::SHIFT, Code::KeyN),
+            ),
+            Command::new("window.minimize", "Window", "Minimize", None),
+            Command::new("window.maximize", "Window", "Toggle Maximize", None),
+            Command::new(
+                "bookmark.manager",
+                "Bookmark",
+                "Open Bookmark Manager",
+                None,
+            ),
+            Command::new(
+                "bookmark.toggle",
+                "Bookmark",
+                "Bookmark This Page",
+                shortcut(CMD_OR_CONTROL, Code::KeyD),
+            ),
+            Command::new(
+                "bookmark.import",
+                "Bookmark",
+                "Import Bookmarks from HTML",
+                None,
+            ),
+            Command::new(
+                "bookmark.export",
+                "Bookmark",
+                "Export Bookmarks as HTML",
+                None,
+            ),
+            Command::new("download.manager", "Download", "Open Downloads", None),
+            Command::new("history.manager", "History", "Open History", None),
+            Command::new(
+                "history.clear",
+                "History",
+                "Clear History",
+                shortcut(CMD_OR_CONTROL | Modifiers::SHIFT, Code::Delete),
+            ),
+            Command::new("crash.reports", "Crash", "Open Crash Reports", None),
+            Command::new("site.settings", "Site", "Open Site Settings", None),
+            Command::new(
+                "highlight.toggle",
+                "Highlight",
+                "Highlight Selection",
+                shortcut(CMD_OR_CONTROL | Modifiers::SHIFT, Code::KeyH),
+            ),
+            Command::new(
+                "highlight.export",
+                "Highlight",
+                "Export Highlights as Markdown",
+                None,
+            ),
+            Command::new(
+                "metrics.export",
+                "Metrics",
+                "Export Performance Metrics",
+                None,
+            ),
+        ];
+        Self { commands }
+    }
+
+    /// Register a command, replacing the command with the same id.
+    /// Returns `false` without registering it if a built-in command has the id.
+    pub fn register(&mut self, command: Command) -> bool {
+        match self.commands.iter_mut().find(|c| c.id == command.id) {
+            Some(existing) if existing.source == CommandSource::Verso => return false,
+            Some(existing) => *existing = command,
+            None => self.commands.push(command),
+        }
+        true
+    }
+
+    /// Unregister a command by its id, built-in commands can't be unregistered.
+    pub fn unregister(&mut self, id: &str) -> Option<Command> {
+        let pos = self
+            .commands
+            .iter()
+            .position(|command| command.id == id && command.source != CommandSource::Verso)?;
+        Some(self.commands.remove(pos))
+    }
+
+    /// Get a command by its id.
+    pub fn command(&self, id: &str) -> Option<&Command> {
+        self.commands.iter().find(|command| command.id == id)
+    }
+
+    /// Get the command bound to a keyboard shortcut.
+    pub fn command_for_keybinding(&self, keybinding: Keybinding) -> Option<&Command> {
+        self.commands
+            .iter()
+            .find(|command| command.keybinding == Some(keybinding))
+    }
+
+    /// Get all registered commands.
+    pub fn commands(&self) -> &Vec<Command> {
+        &self.commands
+    }
+
+    /// Fuzzy search commands, best matches first.
+    /// An empty query returns every command in registration order.
+    pub fn search(&self, query: &str) -> Vec<&Command> {
+        let mut matches: Vec<(usize, &Command)> = self
+            .commands
+            .iter()
+            .filter_map(|command| fuzzy_score(query, &command.label()).map(|s| (s, command)))
+            .collect();
+        // `sort_by` is stable, so commands with the same score keep their order
+        matches.sort_by(|a, b| b.0.cmp(&a.0));
+        matches.into_iter().map(|(_, command)| command).collect()
+    }
+}
+
+/// What the caller of [`Window::execute_command`] has left to do, since a window can't do it itself.
+#[must_use]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum CommandAction {
+    /// Nothing, the command is done
+    None,
+    /// Open a new window
+    NewWindow,
+}
+
+impl Window {
+    /// Execute a command by its id.
+    pub(crate) fn execute_command(
+        &mut self,
+        id: &str,
+        compositor: &mut IOCompositor,
+        to_controller_sender: &Option<IpcSender<ToControllerMessage>>,
+    ) -> CommandAction {
+        let Some(command) = self.command_registry.command(id) else {
+            log::warn!("Verso failed to find command {id}");
+            return CommandAction::None;
+        };
+
+        if command.source == CommandSource::Embedder {
+            if let Some(to_controller_sender) = to_controller_sender {
+                if let Err(error) = to_controller_sender
+                    .send(ToControllerMessage::OnCommandExecuted(id.to_string()))
+                {
+                    log::error!("Verso failed to send OnCommandExecuted to controller: {error}")
+                }
+            }
+            return CommandAction::None;
+        }
+
+        let sender = compositor.constellation_chan.clone();
+        let current_tab_id = self.tab_manager.current_tab_id();
+        match (id, current_tab_id) {
+            ("tab.new", _) => {
+                self.create_tab(&sender, ServoUrl::parse("https://example.com").unwrap());
+            }
+            ("tab.close", Some(tab_id)) => {
+                self.close_tab(compositor, tab_id);
+            }
+            ("tab.next", _) => {
+                self.activate_adjacent_tab(compositor, true);
+            }
+            ("tab.previous", _) => {
+                self.activate_adjacent_tab(compositor, false);
+            }
+            ("tab.move_left", _) => {
+                self.move_current_tab(&sender, -1);
+            }
+            ("tab.move_right", _) => {
+                self.move_current_tab(&sender, 1);
+            }
+            ("edit.cut" | "edit.copy" | "edit.paste" | "edit.paste_plain", _) => {
+                let action = match id {
+                    "edit.cut" => ClipboardAction::Cut,
+                    "edit.copy" => ClipboardAction::Copy,
+                    "edit.paste" => ClipboardAction::Paste,
+                    _ => ClipboardAction::PasteAsPlainText,
+                };
+                // The focused webview may be the panel or a menu, e.g. to paste in the URL bar
+                if let Some(webview_id) = self.focused_webview_id.or(current_tab_id) {
+                    let _ = self
+                        .verso_internal_sender
+                        .send(VersoInternalMsg::Clipboard(webview_id, action));
+                }
+            }
+            ("navigation.back", Some(tab_id)) => {
+                send_to_constellation(
+                    &sender,
+                    EmbedderToConstellationMessage::TraverseHistory(
+                        tab_id,
+                        TraversalDirection::Back(1),
+                    ),
+                );
+            }
+            ("navigation.forward", Some(tab_id)) => {
+                send_to_constellation(
+                    &sender,
+                    EmbedderToConstellationMessage::TraverseHistory(
+                        tab_id,
+                        TraversalDirection::Forward(1),
+                    ),
+                );
+            }
+            ("navigation.reload", Some(tab_id)) => {
+                send_to_constellation(&sender, EmbedderToConstellationMessage::Reload(tab_id));
+            }
+            ("navigation.focus_url", _) => {
+                self.focus_url_input(&sender);
+            }
+            ("command_palette.open", _) => {
+                self.show_command_palette(&sender);
+            }
+            ("find.open", Some(_)) => {
+                self.show_find_bar(&sender);
+            }
+            ("find.next", Some(_)) => {
+                self.find_next(&sender, false);
+            }
+            ("find.previous", Some(_)) => {
+                self.find_next(&sender, true);
+            }
+            ("zoom.in", Some(tab_id)) => {
+                let page_zoom = compositor.page_zoom(tab_id) * ZOOM_STEP;
+                self.zoom_tab(compositor, tab_id, Some(page_zoom));
+            }
+            ("zoom.out", Some(tab_id)) => {
+                let page_zoom = compositor.page_zoom(tab_id) / ZOOM_STEP;
+                self.zoom_tab(compositor, tab_id, Some(page_zoom));
+            }
+            ("zoom.reset", Some(tab_id)) => {
+                self.zoom_tab(compositor, tab_id, None);
+            }
+            ("devtools.webrender_profiler", _) => {
+                compositor.toggle_webrender_debug(WebRenderDebugOption::Profiler);
+            }
+            ("window.new", _) => return CommandAction::NewWindow,
+            // Private windows share the process of the other private windows
+            ("window.private", _) if self.private => return CommandAction::NewWindow,
+            ("window.private", _) => {
+                let _ = self
+                    .verso_internal_sender
+                    .send(VersoInternalMsg::OpenPrivateWindow);
+            }
+            ("window.minimize", _) => {
+                self.window.set_minimized(true);
+            }
+            ("window.maximize", _) => {
+                let is_maximized = self.window.is_maximized();
+                self.window.set_maximized(!is_maximized);
+            }
+            ("bookmark.manager", _) => {
+                self.create_tab(
+                    &sender,
+                    ServoUrl::parse("verso://resources/components/bookmark.html").unwrap(),
+                );
+            }
+            ("bookmark.toggle", Some(tab_id)) => {
+                let Some(tab) = self.tab_manager.tab(tab_id) else {
+                    return CommandAction::None;
+                };
+                // Verso's own pages can't be bookmarked
+                if let Some(url) = tab.current_url().filter(|url| url.scheme() != "verso") {
+                    let _ = self
+                        .verso_internal_sender
+                        .send(VersoInternalMsg::ToggleBookmark(
+                            tab.title(),
+                            url.to_string(),
+                        ));
+                }
+            }
+            ("bookmark.import", _) => {
+                let _ = self
+                    .verso_internal_sender
+                    .send(VersoInternalMsg::ImportBookmarks);
+            }
+            ("bookmark.export", _) => {
+                let _ = self
+                    .verso_internal_sender
+                    .send(VersoInternalMsg::ExportBookmarks);
+            }
+            ("history.manager", _) => {
+                self.create_tab(
+                    &sender,
+                    ServoUrl::parse("verso://resources/components/history.html").unwrap(),
+                );
+            }
+            ("history.clear", _) => {
+                let verso_internal_sender = self.verso_internal_sender.clone();
+                tokio::spawn(async move {
+                    if rfd::MessageDialogResult::Yes
+                        == rfd::AsyncMessageDialog::new()
+                            .set_buttons(rfd::MessageButtons::YesNo)
+                            .set_description("Forget every visited page?")
+                            .set_title("Clear History")
+                            .show()
+                            .await
+                    {
+                        let _ = verso_internal_sender.send(VersoInternalMsg::ClearHistory);
+                    }
+                });
+            }
+            ("crash.reports", _) => {
+                self.create_tab(&sender, ServoUrl::parse("verso://crashes").unwrap());
+            }
+            ("site.settings", _) => {
+                self.create_tab(&sender, ServoUrl::parse("verso://site-settings").unwrap());
+            }
+            ("highlight.toggle", Some(tab_id)) => {
+                highlight::toggle_highlight(&sender, tab_id);
+            }
+            ("highlight.export", _) => {
+                let _ = self
+                    .verso_internal_sender
+                    .send(VersoInternalMsg::ExportHighlights);
+            }
+            ("metrics.export", _) => {
+                let _ = self
+                    .verso_internal_sender
+                    .send(VersoInternalMsg::ExportMetrics);
+            }
+            ("download.manager", _) => {
+                self.create_tab(
+                    &sender,
+                    ServoUrl::parse("verso://resources/components/downloads.html").unwrap(),
+                );
+            }
+            (id, _) => log::trace!("Verso can't execute command {id} right now"),
+        }
+        CommandAction::None
+    }
+}
+
+/// Score how well `query` matches `text`, `None` if not every query character
+/// appears in `text` in order. Consecutive matches and matches at the start of a word score higher.
+pub fn fuzzy_score(query: &str, text: &str) -> Option<usize> {
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut text_idx = 0;
+    let mut previous_match: Option<usize> = None;
+
+    for query_char in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let found = text[text_idx..].iter().position(|c| *c == query_char)? + text_idx;
+
+        score += 1;
+        if previous_match.is_some_and(|previous| previous + 1 == found) {
+            score += 5;
+        }
+        if found == 0 || !text[found - 1].is_alphanumeric() {
+            score += 3;
+        }
+
+        previous_match = Some(found);
+        text_idx = found + 1;
+    }
+
+    Some(score)
+}
+
+/// Map a panel prompt message to the command it triggers.
+pub(crate) fn panel_command_id(message: &str) -> Option<&'static str> {
+    match message {
+        "PREV" => Some("navigation.back"),
+        "FORWARD" => Some("navigation.forward"),
+        "REFRESH" => Some("navigation.reload"),
+        "NEW_WINDOW" => Some("window.new"),
+        "MINIMIZE" => Some("window.minimize"),
+        "MAXIMIZE" | "DBCLICK_PANEL" => Some("window.maximize"),
+        "DOWNLOAD" => Some("download.manager"),
+        "OPEN_BOOKMARK_MANAGER" => Some("bookmark.manager"),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fuzzy_score_requires_ordered_characters() {
+        assert!(fuzzy_score("ntb", "New Tab").is_some());
+        assert!(fuzzy_score("btn", "New Tab").is_none());
+        assert_eq!(fuzzy_score("", "New Tab"), Some(0));
+    }
+
+    #[test]
+    fn test_fuzzy_score_prefers_consecutive_and_word_start_matches() {
+        let consecutive = fuzzy_score("tab", "Tab: New Tab").unwrap();
+        let scattered = fuzzy_score("tab", "Toggle WebRender Profiler").unwrap_or(0);
+        assert!(consecutive > scattered);
+    }
+
+    #[test]
+    fn test_search_ranks_best_match_first() {
+        let registry = CommandRegistry::new();
+        let results = registry.search("zoom in");
+        assert_eq!(results.first().map(|c| c.id.as_str()), Some("zoom.in"));
+    }
+
+    #[test]
+    fn test_command_for_keybinding_finds_builtin_command() {
+        let registry = CommandRegistry::new();
+        let keybinding = Keybinding::new(CMD_OR_CONTROL | Modifiers::SHIFT, Code::KeyP);
+        let command = registry.command_for_keybinding(keybinding).unwrap();
+        assert_eq!(command.id, "command_palette.open");
+        assert!(keybinding.label().ends_with("+Shift+P"));
+    }
+
+    #[test]
+    fn test_register_replaces_command_with_same_id() {
+        let mut registry = CommandRegistry::new();
+        let count = registry.commands().len();
+        registry.register(Command::new_embedder("x".into(), "First".into()));
+        registry.register(Command::new_embedder("x".into(), "Second".into()));
+        assert_eq!(registry.commands().len(), count + 1);
+        assert_eq!(registry.command("x").unwrap().title, "Second");
+        assert!(registry.unregister("x").is_some());
+        assert!(registry.command("x").is_none());
+    }
+
+    #[test]
+    fn test_register_rejects_builtin_command_id() {
+        let mut registry = CommandRegistry::new();
+        let count = registry.commands().len();
+        assert!(!registry.register(Command::new_embedder("window.new".into(), "New".into())));
+        assert_eq!(registry.commands().len(), count);
+        assert_eq!(
+            registry.command("window.new").unwrap().source,
+            CommandSource::Verso
+        );
+        assert!(registry.unregister("window.new").is_none());
+        assert!(registry.command("window.new").is_some());
+    }
+}
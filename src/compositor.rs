@@ -31,25 +31,43 @@ use profile_traits::time::{self as profile_time, ProfilerCategory};
 use profile_traits::{mem, path, time, time_profile};
 use servo_geometry::{DeviceIndependentIntSize, DeviceIndependentPixel};
 use style_traits::CSSPixel;
+use versoview_messages::{AnimationPolicy, FontAntialiasing, FontRendering, Rotation};
 use webrender::{RenderApi, Transaction};
 use webrender_api::units::{
     DeviceIntPoint, DeviceIntRect, DevicePixel, DevicePoint, DeviceRect, DeviceSize, LayoutPoint,
-    LayoutRect, LayoutSize, LayoutVector2D, WorldPoint,
+    LayoutRect, LayoutSize, LayoutVector2D, WorldPixel,
 };
 use webrender_api::{
     BorderRadius, BoxShadowClipMode, BuiltDisplayList, ClipMode, ColorF, CommonItemProperties,
     ComplexClipRegion, DirtyRect, DisplayListPayload, DocumentId, Epoch as WebRenderEpoch,
-    ExternalScrollId, FontInstanceFlags, FontInstanceKey, FontInstanceOptions, FontKey,
-    HitTestFlags, PipelineId as WebRenderPipelineId, PropertyBinding, ReferenceFrameKind,
-    RenderReasons, SampledScrollOffset, ScrollLocation, SpaceAndClipInfo, SpatialId,
-    SpatialTreeItemKey, TransformStyle,
+    ExternalScrollId, FontInstanceFlags, FontInstanceKey, FontInstanceOptions,
+    FontInstancePlatformOptions, FontKey, FontRenderMode, HitTestFlags,
+    PipelineId as WebRenderPipelineId, PropertyBinding, ReferenceFrameKind, RenderReasons,
+    SampledScrollOffset, ScrollLocation, SpaceAndClipInfo, SpatialId, SpatialTreeItemKey,
+    TransformStyle,
 };
 use winit::window::WindowId;
 
+use crate::metrics::FrameTimes;
 use crate::rendering::RenderingContext;
+use crate::rotation;
 use crate::touch::{TouchAction, TouchHandler};
+use crate::web_app::parse_color;
+use crate::webdriver;
+use crate::webview::find_bar::FindBar;
 use crate::window::Window;
 
+/// Minimum page zoom factor
+const MIN_ZOOM: f32 = 0.1;
+/// Maximum page zoom factor
+const MAX_ZOOM: f32 = 8.0;
+/// How long pages animate with [`AnimationPolicy::Once`]
+const ANIMATE_ONCE_DURATION: Duration = Duration::from_secs(5);
+/// Height of the load progress bar in page pixels
+const LOAD_PROGRESS_BAR_HEIGHT: f32 = 3.0;
+/// Color of the load progress bar if the page has no theme color
+const LOAD_PROGRESS_BAR_COLOR: [u8; 3] = [0x3b, 0x82, 0xf6];
+
 /// Data used to construct a compositor.
 pub struct InitialCompositorState {
     /// A channel to the compositor.
@@ -107,9 +125,20 @@ pub struct IOCompositor {
     /// Size of current viewport that Compositor is handling.
     viewport: DeviceSize,
 
+    /// Clockwise rotation of the output of the current window, the content is laid out for the
+    /// rotated size of the viewport.
+    rotation: Rotation,
+
     /// The pixel density of the display.
     scale_factor: Scale<f32, DeviceIndependentPixel, DevicePixel>,
 
+    /// "Desktop-style" zoom of each webview that resizes its viewport to fit the window, webviews
+    /// with the default zoom aren't listed.
+    page_zooms: HashMap<WebViewId, Scale<f32, CSSPixel, DeviceIndependentPixel>>,
+
+    /// Page zoom of the webviews which weren't zoomed, see `--zoom`.
+    default_page_zoom: Scale<f32, CSSPixel, DeviceIndependentPixel>,
+
     /// The active webrender document.
     webrender_document: DocumentId,
 
@@ -128,6 +157,9 @@ pub struct IOCompositor {
     /// check if the surface is ready to present.
     pub ready_to_present: bool,
 
+    /// Time Webrender took to render frames, for the metrics.
+    pub(crate) frame_times: FrameTimes,
+
     /// Tracks whether we are in the process of shutting down, or have shut down and should close
     /// the compositor.
     pub shutdown_state: ShutdownState,
@@ -182,6 +214,23 @@ pub struct IOCompositor {
     /// ScriptThread with a deluge of animation ticks.
     last_animation_tick: Instant,
 
+    /// The minimum interval between animation ticks, set to save power. If `None`, animations
+    /// are ticked after every composite.
+    animation_tick_limit: Option<Duration>,
+
+    /// When CSS animations, transitions and animated images are ticked.
+    animation_policy: AnimationPolicy,
+
+    /// Webviews of the windows which aren't focused, their animations are paused with
+    /// [`AnimationPolicy::PauseInBackground`].
+    background_webviews: HashSet<WebViewId>,
+
+    /// Draw a progress bar at the top of the current tab while it loads.
+    load_progress_bar: bool,
+
+    /// Antialiasing, hinting and gamma of the font instances added to WebRender.
+    font_rendering: FontRendering,
+
     /// Whether the application is currently animating.
     /// Typically, when animations are active, the window
     /// will want to avoid blocking on UI events, and just
@@ -277,6 +326,10 @@ struct PipelineDetails {
     /// Whether to use less resources by stopping animations.
     throttled: bool,
 
+    /// When the running animations started, they're paused a while after with
+    /// [`AnimationPolicy::Once`].
+    animations_started: Option<Instant>,
+
     /// Hit test items for this pipeline. This is used to map WebRender hit test
     /// information to the full information necessary for Servo.
     hit_test_items: Vec<HitTestInfo>,
@@ -301,6 +354,7 @@ impl PipelineDetails {
             animations_running: false,
             animation_callbacks_running: false,
             throttled: false,
+            animations_started: None,
             hit_test_items: Vec::new(),
             scroll_tree: ScrollTree::default(),
             first_paint_metric: PaintMetricState::Waiting,
@@ -345,10 +399,13 @@ impl IOCompositor {
         let compositor = IOCompositor {
             current_window,
             viewport,
+            rotation: Rotation::default(),
             compositor_receiver: state.receiver,
             webviews: HashMap::new(),
             pipeline_details: HashMap::new(),
             scale_factor,
+            page_zooms: HashMap::new(),
+            default_page_zoom: Scale::new(1.0),
             composition_request: CompositionRequest::NoCompositingNecessary,
             touch_handler: TouchHandler::new(),
             pending_scroll_zoom_events: Vec::new(),
@@ -368,8 +425,14 @@ impl IOCompositor {
             convert_mouse_to_touch,
             pending_frames: 0,
             last_animation_tick: Instant::now(),
+            animation_tick_limit: None,
+            animation_policy: AnimationPolicy::default(),
+            background_webviews: HashSet::new(),
+            load_progress_bar: false,
+            font_rendering: FontRendering::default(),
             is_animating: false,
             ready_to_present: false,
+            frame_times: FrameTimes::default(),
         };
 
         // Make sure the GL state is OK
@@ -515,11 +578,45 @@ impl IOCompositor {
                 self.touch_handler.on_event_processed(result);
             }
 
-            CompositorMsg::CreatePng(_webview_id, _page_rect, reply) => {
-                // TODO create image
-                if let Err(e) = reply.send(None) {
-                    warn!("Sending reply to create png failed ({:?}).", e);
-                }
+            CompositorMsg::CreatePng(webview_id, page_rect, reply) => {
+                let Some((window, _)) = windows
+                    .values()
+                    .find(|(window, _)| window.tab_manager.tab(webview_id).is_some())
+                else {
+                    if let Err(e) = reply.send(None) {
+                        warn!("Sending reply to create png failed ({:?}).", e);
+                    }
+                    return true;
+                };
+                let webview_rect = window.tab_manager.tab(webview_id).unwrap().webview().rect;
+                let rect = webdriver::screenshot_rect(
+                    webview_rect,
+                    page_rect,
+                    self.device_pixels_per_page_pixel(webview_id),
+                );
+                // The frame is read back once the pending changes of the webview are composited
+                self.rendering_context.capture_frame(Box::new(move |image| {
+                    let image = image.and_then(|image| {
+                        let rect = rect.intersection(&DeviceIntRect::from_size(
+                            Size2D::new(image.width(), image.height()).to_i32(),
+                        ))?;
+                        Some(webdriver::screenshot_image(
+                            image::imageops::crop_imm(
+                                &image,
+                                rect.min.x as u32,
+                                rect.min.y as u32,
+                                rect.width() as u32,
+                                rect.height() as u32,
+                            )
+                            .to_image(),
+                        ))
+                    });
+                    if let Err(e) = reply.send(image) {
+                        warn!("Sending reply to create png failed ({:?}).", e);
+                    }
+                }));
+                self.composite(window);
+                window.window.request_redraw();
             }
 
             CompositorMsg::IsReadyToSaveImageReply(is_ready) => {
@@ -568,7 +665,7 @@ impl IOCompositor {
             }
 
             CompositorMsg::WebDriverMouseButtonEvent(webview_id, action, button, x, y) => {
-                let dppx = self.device_pixels_per_page_pixel();
+                let dppx = self.device_pixels_per_page_pixel(webview_id);
                 let point = dppx.transform_point(Point2D::new(x, y));
                 self.dispatch_input_event(
                     webview_id,
@@ -581,7 +678,7 @@ impl IOCompositor {
             }
 
             CompositorMsg::WebDriverMouseMoveEvent(webview_id, x, y) => {
-                let dppx = self.device_pixels_per_page_pixel();
+                let dppx = self.device_pixels_per_page_pixel(webview_id);
                 let point = dppx.transform_point(Point2D::new(x, y));
                 self.dispatch_input_event(
                     webview_id,
@@ -904,8 +1001,12 @@ impl IOCompositor {
     ) {
         match animation_state {
             AnimationState::AnimationsPresent => {
-                let throttled = self.pipeline_details(pipeline_id).throttled;
-                self.pipeline_details(pipeline_id).animations_running = true;
+                let pipeline_details = self.pipeline_details(pipeline_id);
+                let throttled = pipeline_details.throttled;
+                pipeline_details.animations_running = true;
+                pipeline_details
+                    .animations_started
+                    .get_or_insert_with(Instant::now);
                 if !throttled {
                     self.composite_if_necessary(CompositingReason::Animation);
                 }
@@ -919,7 +1020,9 @@ impl IOCompositor {
                 }
             }
             AnimationState::NoAnimationsPresent => {
-                self.pipeline_details(pipeline_id).animations_running = false;
+                let pipeline_details = self.pipeline_details(pipeline_id);
+                pipeline_details.animations_running = false;
+                pipeline_details.animations_started = None;
             }
             AnimationState::NoAnimationCallbacksPresent => {
                 self.pipeline_details(pipeline_id)
@@ -939,7 +1042,7 @@ impl IOCompositor {
 
     /// Set the root pipeline for our WebRender scene to a display list that consists of an iframe
     /// for each visible top-level browsing context, applying a transformation on the root for
-    /// pinch zoom, page zoom, and HiDPI scaling.
+    /// HiDPI scaling, and on each iframe for the page zoom of its webview.
     pub fn send_root_pipeline_display_list(&mut self, window: &Window) {
         let mut transaction = Transaction::new();
         self.send_root_pipeline_display_list_in_transaction(&mut transaction, window);
@@ -950,7 +1053,7 @@ impl IOCompositor {
 
     /// Set the root pipeline for our WebRender scene to a display list that consists of an iframe
     /// for each visible top-level browsing context, applying a transformation on the root for
-    /// pinch zoom, page zoom, and HiDPI scaling.
+    /// HiDPI scaling, and on each iframe for the page zoom of its webview.
     fn send_root_pipeline_display_list_in_transaction(
         &self,
         transaction: &mut Transaction,
@@ -965,34 +1068,69 @@ impl IOCompositor {
         let mut builder = webrender::api::DisplayListBuilder::new(root_pipeline);
         builder.begin();
 
-        let zoom_factor = self.device_pixels_per_page_pixel().0;
-        let zoom_reference_frame = builder.push_reference_frame(
+        let viewport_size = rotation::rotate_size(
+            self.rotation,
+            self.rendering_context.size2d().to_f32().to_untyped(),
+        );
+        let viewport_rect = LayoutRect::from_origin_and_size(
+            LayoutPoint::zero(),
+            LayoutSize::from_untyped(viewport_size),
+        );
+
+        // Everything is drawn turned by the rotation of the window onto the output
+        let rotation_reference_frame = builder.push_reference_frame(
             LayoutPoint::zero(),
             SpatialId::root_reference_frame(root_pipeline),
             TransformStyle::Flat,
-            PropertyBinding::Value(Transform3D::scale(zoom_factor, zoom_factor, 1.)),
+            PropertyBinding::Value(
+                rotation::content_to_output(self.rotation, viewport_rect.size()).to_3d(),
+            ),
             ReferenceFrameKind::Transform {
-                is_2d_scale_translation: true,
+                is_2d_scale_translation: self.rotation == Rotation::Normal,
                 should_snap: true,
                 paired_with_perspective: false,
             },
-            SpatialTreeItemKey::new(0, 0),
+            SpatialTreeItemKey::new(2, 0),
         );
 
-        let viewport_size = self.rendering_context.size2d().to_f32().to_untyped();
-        let viewport_rect = LayoutRect::from_origin_and_size(
-            LayoutPoint::zero(),
-            LayoutSize::from_untyped(viewport_size),
-        );
+        let zoom_factor = self.scale_factor.get();
+        let scale_reference_frame = |builder: &mut webrender::api::DisplayListBuilder,
+                                     factor: f32,
+                                     key: SpatialTreeItemKey| {
+            builder.push_reference_frame(
+                LayoutPoint::zero(),
+                rotation_reference_frame,
+                TransformStyle::Flat,
+                PropertyBinding::Value(Transform3D::scale(factor, factor, 1.)),
+                ReferenceFrameKind::Transform {
+                    is_2d_scale_translation: true,
+                    should_snap: true,
+                    paired_with_perspective: false,
+                },
+                key,
+            )
+        };
+        let zoom_reference_frame =
+            scale_reference_frame(&mut builder, zoom_factor, SpatialTreeItemKey::new(0, 0));
 
         let root_clip_id = builder.define_clip_rect(zoom_reference_frame, viewport_rect);
         let root_clip_chain_id = builder.define_clip_chain(None, [root_clip_id]);
         // Only decorate the webviews if we're in the browser mode
         let should_decorate = window.panel.is_some();
-        for webview in window.painting_order() {
+        for (index, webview) in window.painting_order().into_iter().enumerate() {
             if let Some(pipeline_id) = self.webviews.get(&webview.webview_id) {
                 let scaled_webview_rect =
                     LayoutRect::from_untyped(&(webview.rect.to_f32() / zoom_factor).to_untyped());
+                // The page of the webview is scaled by its page zoom as well
+                let page_zoom_factor = self.device_pixels_per_page_pixel(webview.webview_id).0;
+                let page_reference_frame = scale_reference_frame(
+                    &mut builder,
+                    page_zoom_factor,
+                    SpatialTreeItemKey::new(1, index as u64),
+                );
+                let page_rect = LayoutRect::from_untyped(
+                    &(webview.rect.to_f32() / page_zoom_factor).to_untyped(),
+                );
                 let root_space_and_clip = if should_decorate {
                     let complex = ComplexClipRegion::new(
                         scaled_webview_rect,
@@ -1003,19 +1141,19 @@ impl IOCompositor {
                     let clip_chain_id =
                         builder.define_clip_chain(Some(root_clip_chain_id), [clip_id]);
                     SpaceAndClipInfo {
-                        spatial_id: zoom_reference_frame,
+                        spatial_id: page_reference_frame,
                         clip_chain_id,
                     }
                 } else {
                     SpaceAndClipInfo {
-                        spatial_id: zoom_reference_frame,
+                        spatial_id: page_reference_frame,
                         clip_chain_id: root_clip_chain_id,
                     }
                 };
 
                 builder.push_iframe(
-                    scaled_webview_rect,
-                    scaled_webview_rect,
+                    page_rect,
+                    page_rect,
                     &root_space_and_clip,
                     pipeline_id.into(),
                     true,
@@ -1046,6 +1184,39 @@ impl IOCompositor {
             }
         }
 
+        // The load progress bar is drawn over every webview
+        if let Some((tab, progress)) = window
+            .tab_manager
+            .current_tab()
+            .filter(|_| self.load_progress_bar)
+            .and_then(|tab| Some((tab, tab.load_progress()?)))
+        {
+            let webview_rect =
+                LayoutRect::from_untyped(&(tab.webview().rect.to_f32() / zoom_factor).to_untyped());
+            let bar_rect = LayoutRect::from_origin_and_size(
+                webview_rect.min,
+                LayoutSize::new(webview_rect.width() * progress, LOAD_PROGRESS_BAR_HEIGHT),
+            );
+            let [r, g, b] = tab
+                .theme_color()
+                .and_then(parse_color)
+                .unwrap_or(LOAD_PROGRESS_BAR_COLOR);
+            let root_space = SpaceAndClipInfo {
+                spatial_id: zoom_reference_frame,
+                clip_chain_id: root_clip_chain_id,
+            };
+            builder.push_rect(
+                &CommonItemProperties::new(bar_rect, root_space),
+                bar_rect,
+                ColorF::new(
+                    f32::from(r) / 255.0,
+                    f32::from(g) / 255.0,
+                    f32::from(b) / 255.0,
+                    1.0,
+                ),
+            );
+        }
+
         let built_display_list = builder.end();
 
         // NB: We are always passing 0 as the epoch here, but this doesn't seem to
@@ -1119,6 +1290,8 @@ impl IOCompositor {
                 if let Some(pipeline_id) = self.webviews.remove(&webview.webview_id) {
                     self.remove_pipeline_details_recursively(pipeline_id);
                 }
+                self.page_zooms.remove(&webview.webview_id);
+                self.background_webviews.remove(&webview.webview_id);
 
                 if close_window {
                     window_id = Some(window.id());
@@ -1138,6 +1311,22 @@ impl IOCompositor {
         }
     }
 
+    /// Scroll position of the root scroll frame of the webview's page, `None` if it has no
+    /// display list yet.
+    pub fn webview_scroll_position(&self, webview_id: WebViewId) -> Option<LayoutVector2D> {
+        let pipeline_id = self.webviews.get(&webview_id)?;
+        let root_scroll_id = ExternalScrollId(0, pipeline_id.into());
+        self.pipeline_details
+            .get(pipeline_id)?
+            .scroll_tree
+            .nodes
+            .iter()
+            .find(|node| node.external_id() == Some(root_scroll_id))
+            .and_then(|node| node.offset())
+            // Scroll offsets move the content, so they are the opposite of the scroll position
+            .map(|offset| -offset)
+    }
+
     /// Notify compositor the provided webview is resized. The compositor will tell constellation and update the display list.
     pub fn on_resize_webview_event(&mut self, webview_id: WebViewId, rect: DeviceRect) {
         self.send_window_size_message_for_top_level_browser_context(rect, webview_id);
@@ -1150,7 +1339,7 @@ impl IOCompositor {
     ) {
         // The device pixel ratio used by the style system should include the scale from page pixels
         // to device pixels, but not including any pinch zoom.
-        let hidpi_scale_factor = self.device_pixels_per_page_pixel_not_including_page_zoom();
+        let hidpi_scale_factor = self.device_pixels_per_page_pixel(webview_id);
         let size = rect.size().to_f32() / hidpi_scale_factor;
         let msg = EmbedderToConstellationMessage::ChangeViewportDetails(
             webview_id,
@@ -1265,6 +1454,11 @@ impl IOCompositor {
                 self.on_resize_webview_event(prompt_id, content_size);
             }
         }
+        if let Some(find_bar) = &mut window.find_bar {
+            let rect = FindBar::rect(content_size, window.window.scale_factor());
+            find_bar.webview.set_size(rect);
+            self.on_resize_webview_event(find_bar.webview.webview_id, rect);
+        }
         #[cfg(linux)]
         if let Some(webview_menu) = &mut window.webview_menu {
             let rect = DeviceRect::from_size(size);
@@ -1281,6 +1475,9 @@ impl IOCompositor {
             return;
         }
 
+        // The viewport is laid out rotated, the surface keeps the size of the output
+        self.rotation = window.rotation;
+        let new_viewport = rotation::rotate_size(self.rotation, new_viewport);
         self.rendering_context.resize(
             &window.surface,
             PhysicalSize {
@@ -1401,8 +1598,11 @@ impl IOCompositor {
         flags: HitTestFlags,
         pipeline_id: Option<WebRenderPipelineId>,
     ) -> Vec<CompositorHitTestResult> {
-        // DevicePoint and WorldPoint are the same for us.
-        let world_point = WorldPoint::from_untyped(point.to_untyped());
+        // Points are in the rotated content, the world is the output
+        let content_size = rotation::rotate_size(self.rotation, self.viewport);
+        let world_point =
+            rotation::content_to_output::<DevicePixel, WorldPixel>(self.rotation, content_size)
+                .transform_point(point);
         let results =
             self.webrender_api
                 .hit_test(self.webrender_document, pipeline_id, world_point, flags);
@@ -1456,18 +1656,20 @@ impl IOCompositor {
         }
     }
 
-    /// Handle touch event.
-    pub fn on_touch_event(&mut self, webview_id: WebViewId, event: TouchEvent) {
+    /// Handle touch event, and return the magnification of the page if the touch points pinch
+    /// it. Pages are zoomed by the window, which remembers the zoom of their site.
+    pub fn on_touch_event(&mut self, webview_id: WebViewId, event: TouchEvent) -> Option<f32> {
         if self.shutdown_state != ShutdownState::NotShuttingDown {
-            return;
+            return None;
         }
 
         match event.event_type {
             TouchEventType::Down => self.on_touch_down(webview_id, event),
-            TouchEventType::Move => self.on_touch_move(webview_id, event),
+            TouchEventType::Move => return self.on_touch_move(webview_id, event),
             TouchEventType::Up => self.on_touch_up(webview_id, event),
             TouchEventType::Cancel => self.on_touch_cancel(webview_id, event),
         }
+        None
     }
 
     fn on_touch_down(&mut self, webview_id: WebViewId, event: TouchEvent) {
@@ -1475,32 +1677,25 @@ impl IOCompositor {
         self.send_touch_event(webview_id, event);
     }
 
-    fn on_touch_move(&mut self, webview_id: WebViewId, event: TouchEvent) {
+    fn on_touch_move(&mut self, webview_id: WebViewId, event: TouchEvent) -> Option<f32> {
         match self.touch_handler.on_touch_move(event.id, event.point) {
             TouchAction::Scroll(delta) => self.on_scroll_window_event(
                 ScrollLocation::Delta(LayoutVector2D::from_untyped(delta.to_untyped())),
                 event.point.cast(),
             ),
             TouchAction::Zoom(magnification, scroll_delta) => {
-                let cursor = Point2D::new(-1, -1); // Make sure this hits the base layer.
-
-                // The order of these events doesn't matter, because zoom is handled by
-                // a root display list and the scroll event here is handled by the scroll
-                // applied to the content display list.
-                self.pending_scroll_zoom_events
-                    .push(ScrollZoomEvent::PinchZoom(magnification));
-                self.pending_scroll_zoom_events
-                    .push(ScrollZoomEvent::Scroll(ScrollEvent {
-                        scroll_location: ScrollLocation::Delta(LayoutVector2D::from_untyped(
-                            scroll_delta.to_untyped(),
-                        )),
-                        cursor,
-                        event_count: 1,
-                    }));
+                // The page is scrolled here and zoomed by the caller, the zoom is applied to the
+                // reference frame of the webview and doesn't move its scroll position.
+                self.on_scroll_window_event(
+                    ScrollLocation::Delta(LayoutVector2D::from_untyped(scroll_delta.to_untyped())),
+                    event.point.cast(),
+                );
+                return Some(magnification);
             }
             TouchAction::DispatchEvent => self.send_touch_event(webview_id, event),
             _ => {}
         }
+        None
     }
 
     fn on_touch_up(&mut self, webview_id: WebViewId, event: TouchEvent) {
@@ -1662,9 +1857,17 @@ impl IOCompositor {
         cursor: DevicePoint,
         scroll_location: ScrollLocation,
     ) -> Option<(PipelineId, ExternalScrollId, LayoutVector2D)> {
+        let hit_test_results =
+            self.hit_test_at_point_with_flags_and_pipeline(cursor, HitTestFlags::FIND_ALL, None);
+        let webview_id = hit_test_results
+            .first()
+            .and_then(|result| self.pipeline_details.get(&result.pipeline_id))
+            .and_then(|details| details.pipeline.as_ref())
+            .map(|pipeline| pipeline.webview_id)?;
+
         let scroll_location = match scroll_location {
             ScrollLocation::Delta(delta) => {
-                let device_pixels_per_page = self.device_pixels_per_page_pixel();
+                let device_pixels_per_page = self.device_pixels_per_page_pixel(webview_id);
                 let scaled_delta = (Vector2D::from_untyped(delta.to_untyped())
                     / device_pixels_per_page)
                     .to_untyped();
@@ -1675,9 +1878,6 @@ impl IOCompositor {
             ScrollLocation::Start | ScrollLocation::End => scroll_location,
         };
 
-        let hit_test_results =
-            self.hit_test_at_point_with_flags_and_pipeline(cursor, HitTestFlags::FIND_ALL, None);
-
         // Iterate through all hit test results, processing only the first node of each pipeline.
         // This is needed to propagate the scroll events from a pipeline representing an iframe to
         // its ancestor pipelines.
@@ -1710,14 +1910,21 @@ impl IOCompositor {
         // TODO: This should be based on the refresh rate of the screen and also apply to all
         // animation ticks, not just ones sent while waiting to dump screenshots. This requires
         // something like a refresh driver concept though.
-        if !force && (Instant::now() - self.last_animation_tick) < Duration::from_millis(16) {
+        let interval = self
+            .animation_tick_limit
+            .unwrap_or(Duration::from_millis(16));
+        if (!force || self.animation_tick_limit.is_some())
+            && (Instant::now() - self.last_animation_tick) < interval
+        {
             return;
         }
         self.last_animation_tick = Instant::now();
 
         let mut pipeline_ids = vec![];
         for (pipeline_id, pipeline_details) in &self.pipeline_details {
-            if (pipeline_details.animations_running || pipeline_details.animation_callbacks_running)
+            let animations_running =
+                pipeline_details.animations_running && !self.animations_paused(pipeline_details);
+            if (animations_running || pipeline_details.animation_callbacks_running)
                 && !pipeline_details.throttled
             {
                 pipeline_ids.push(*pipeline_id);
@@ -1729,11 +1936,83 @@ impl IOCompositor {
         }
     }
 
+    /// Draw a progress bar at the top of the current tab while it loads, for windows without
+    /// control panel to show it.
+    pub fn set_load_progress_bar(&mut self, enabled: bool) {
+        self.load_progress_bar = enabled;
+    }
+
+    /// Rasterize the text of the fonts added afterwards with these options.
+    pub fn set_font_rendering(&mut self, font_rendering: FontRendering) {
+        self.font_rendering = font_rendering;
+    }
+
+    /// Redraw the load progress bar of the window after the load progress of its tab changed.
+    pub fn update_load_progress(&mut self, window: &Window) {
+        if self.load_progress_bar && window.id() == self.current_window {
+            self.send_root_pipeline_display_list(window);
+        }
+    }
+
+    /// Limit the animation frame rate, or remove the limit if `None`.
+    pub fn set_animation_tick_limit(&mut self, limit: Option<Duration>) {
+        self.animation_tick_limit = limit;
+    }
+
+    /// When animations should be ticked next if their frame rate is limited.
+    pub fn next_animation_tick(&self) -> Option<Instant> {
+        self.animation_tick_limit
+            .filter(|_| self.is_animating)
+            .map(|limit| self.last_animation_tick + limit)
+    }
+
+    /// Tick animations that were held back by the frame rate limit.
+    pub fn tick_limited_animations(&mut self) {
+        self.process_animations(false);
+    }
+
+    /// Change when CSS animations, transitions and animated images are ticked, the paused ones
+    /// resume if the policy allows them.
+    pub fn set_animation_policy(&mut self, policy: AnimationPolicy) {
+        self.animation_policy = policy;
+        self.process_animations(true);
+    }
+
+    /// Pause the animations of the tabs of the window while it isn't focused, if the animation
+    /// policy says so.
+    pub fn set_window_in_background(&mut self, window: &Window, background: bool) {
+        for tab_id in window.tab_manager.tab_ids() {
+            if background {
+                self.background_webviews.insert(tab_id);
+            } else {
+                self.background_webviews.remove(&tab_id);
+            }
+        }
+        self.process_animations(true);
+    }
+
+    /// Whether the CSS animations, transitions and animated images of the pipeline are paused by
+    /// the animation policy. Its animation frame callbacks still run.
+    fn animations_paused(&self, pipeline_details: &PipelineDetails) -> bool {
+        match self.animation_policy {
+            AnimationPolicy::Normal => false,
+            AnimationPolicy::PauseInBackground => pipeline_details
+                .pipeline
+                .as_ref()
+                .is_some_and(|pipeline| self.background_webviews.contains(&pipeline.webview_id)),
+            AnimationPolicy::Once => pipeline_details
+                .animations_started
+                .is_some_and(|started| started.elapsed() >= ANIMATE_ONCE_DURATION),
+        }
+    }
+
     fn tick_animations_for_pipeline(&mut self, pipeline_id: PipelineId) {
-        let animation_callbacks_running = self
-            .pipeline_details(pipeline_id)
-            .animation_callbacks_running;
-        let animations_running = self.pipeline_details(pipeline_id).animations_running;
+        let Some(pipeline_details) = self.pipeline_details.get(&pipeline_id) else {
+            return;
+        };
+        let animation_callbacks_running = pipeline_details.animation_callbacks_running;
+        let animations_running =
+            pipeline_details.animations_running && !self.animations_paused(pipeline_details);
         if !animation_callbacks_running && !animations_running {
             return;
         }
@@ -1752,36 +2031,53 @@ impl IOCompositor {
         }
     }
 
-    fn device_pixels_per_page_pixel(&self) -> Scale<f32, CSSPixel, DevicePixel> {
-        self.device_pixels_per_page_pixel_not_including_page_zoom()
-    }
-
-    fn device_pixels_per_page_pixel_not_including_page_zoom(
+    /// Device pixels of a CSS pixel of the page of the webview, with its page zoom.
+    pub(crate) fn device_pixels_per_page_pixel(
         &self,
+        webview_id: WebViewId,
     ) -> Scale<f32, CSSPixel, DevicePixel> {
-        Scale::new(self.scale_factor.get())
+        Scale::new(self.page_zoom(webview_id)) * self.scale_factor
     }
 
     fn device_independent_int_size_viewport(&self) -> DeviceIndependentIntSize {
-        (self.viewport.to_f32() / self.scale_factor).to_i32()
+        (rotation::rotate_size(self.rotation, self.viewport) / self.scale_factor).to_i32()
     }
 
-    /// Handle zoom reset event
-    pub fn on_zoom_reset_window_event(&mut self, window: &Window) {
-        if self.shutdown_state != ShutdownState::NotShuttingDown {
-            return;
-        }
+    /// Page zoom of the webview, the default page zoom if it isn't zoomed.
+    pub fn page_zoom(&self, webview_id: WebViewId) -> f32 {
+        self.page_zooms
+            .get(&webview_id)
+            .unwrap_or(&self.default_page_zoom)
+            .get()
+    }
+
+    /// Page zoom of the webviews which weren't zoomed.
+    pub fn default_page_zoom(&self) -> f32 {
+        self.default_page_zoom.get()
+    }
 
+    /// Set the page zoom of the webviews which weren't zoomed, clamped to the supported zoom
+    /// factors.
+    pub fn set_default_page_zoom(&mut self, page_zoom: f32, window: &Window) {
+        self.default_page_zoom = Scale::new(page_zoom.clamp(MIN_ZOOM, MAX_ZOOM));
         self.update_after_zoom_or_hidpi_change(window);
     }
 
-    /// Handle zoom event in the window
-    pub fn on_zoom_window_event(&mut self, _magnification: f32, window: &Window) {
+    /// Zoom the page of the webview in the window, clamped to the supported zoom factors, and
+    /// return the page zoom it's set to.
+    pub fn set_page_zoom(&mut self, webview_id: WebViewId, page_zoom: f32, window: &Window) -> f32 {
         if self.shutdown_state != ShutdownState::NotShuttingDown {
-            return;
+            return self.page_zoom(webview_id);
         }
 
+        let page_zoom = page_zoom.clamp(MIN_ZOOM, MAX_ZOOM);
+        if page_zoom == self.default_page_zoom.get() {
+            self.page_zooms.remove(&webview_id);
+        } else {
+            self.page_zooms.insert(webview_id, Scale::new(page_zoom));
+        }
         self.update_after_zoom_or_hidpi_change(window);
+        page_zoom
     }
 
     fn update_after_zoom_or_hidpi_change(&mut self, window: &Window) {
@@ -1957,9 +2253,11 @@ impl IOCompositor {
                 // Paint the scene.
                 // TODO(gw): Take notice of any errors the renderer returns!
                 if let Some(webrender) = self.webrender.as_mut() {
+                    let start = Instant::now();
                     webrender
                         .render(self.viewport.to_i32(), 0 /* buffer_age */)
                         .ok();
+                    self.frame_times.record(start.elapsed());
                 }
             },
         );
@@ -2087,6 +2385,11 @@ impl IOCompositor {
         let mut transaction = Transaction::new();
         let font_instance_options = FontInstanceOptions {
             flags,
+            render_mode: match self.font_rendering.antialiasing {
+                FontAntialiasing::None => FontRenderMode::Mono,
+                FontAntialiasing::Grayscale => FontRenderMode::Alpha,
+                FontAntialiasing::Subpixel => FontRenderMode::Subpixel,
+            },
             ..Default::default()
         };
         transaction.add_font_instance(
@@ -2094,7 +2397,7 @@ impl IOCompositor {
             font_key,
             size,
             Some(font_instance_options),
-            None,
+            font_platform_options(&self.font_rendering),
             Vec::new(),
         );
         self.webrender_api
@@ -2198,3 +2501,37 @@ impl FrameTreeId {
         self.0 += 1;
     }
 }
+
+/// FreeType hinting of the font instances.
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn font_platform_options(font_rendering: &FontRendering) -> Option<FontInstancePlatformOptions> {
+    use versoview_messages::FontHinting;
+
+    let hinting = match font_rendering.hinting {
+        FontHinting::None => webrender_api::FontHinting::None,
+        FontHinting::Slight => webrender_api::FontHinting::Light,
+        FontHinting::Full if font_rendering.antialiasing == FontAntialiasing::None => {
+            webrender_api::FontHinting::Mono
+        }
+        FontHinting::Full => webrender_api::FontHinting::LCD,
+    };
+    Some(FontInstancePlatformOptions {
+        hinting,
+        ..Default::default()
+    })
+}
+
+/// Gamma of the font instances, in percent for DirectWrite.
+#[cfg(target_os = "windows")]
+fn font_platform_options(font_rendering: &FontRendering) -> Option<FontInstancePlatformOptions> {
+    Some(FontInstancePlatformOptions {
+        gamma: (font_rendering.gamma * 100.0).round() as u16,
+        ..Default::default()
+    })
+}
+
+/// Core Text has no options for hinting or gamma.
+#[cfg(target_os = "macos")]
+fn font_platform_options(_font_rendering: &FontRendering) -> Option<FontInstancePlatformOptions> {
+    None
+}
@@ -1,6 +1,11 @@
 use std::{
     fs,
+    net::SocketAddr,
     path::{Path, PathBuf},
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
 };
 
 use dpi::{LogicalSize, PhysicalPosition, PhysicalSize};
@@ -17,9 +22,39 @@ use servo_config::{
     opts::{Opts, OutputOptions, set_options},
     prefs::Preferences,
 };
-use versoview_messages::{ConfigFromController, UserScript};
+use versoview_messages::{
+    AnimationPolicy, BrowserImport, CertificateErrorPolicy, CertificatePin, ConfigFromController,
+    CookiePolicy, CrossOriginFramePolicy, FileAccessPolicy, FontAntialiasing, FontHinting,
+    FontRendering, ImageLoading, ImagePolicy, JavaScriptSettings, MetricsConfig, PowerSaveConfig,
+    PrivacySettings, ReferrerPolicy, ResourceOverride, ResourceOverrideSource, Rotation,
+    UpdateChannel, UpdateConfig, UserScript, VirtualKeyboard, Vsync,
+};
 use winit::window::{Fullscreen, WindowAttributes};
 
+use crate::{
+    badge::BADGE_SCRIPT,
+    clipboard::CLIPBOARD_SCRIPT,
+    csp::CSP_VIOLATION_SCRIPT,
+    drag_drop::DRAG_DROP_SCRIPT,
+    embedded_resources,
+    forms::FORM_SUBMISSION_SCRIPT,
+    frames::frame_isolation_script,
+    geolocation::GEOLOCATION_SCRIPT,
+    highlight::HIGHLIGHT_SCRIPT,
+    images::IMAGE_POLICY_SCRIPT,
+    integrity::INTEGRITY_SCRIPT,
+    javascript::JAVASCRIPT_POLICY_SCRIPT,
+    power::{BATTERY_SCRIPT, WAKE_LOCK_SCRIPT},
+    privacy::PRIVACY_SCRIPT,
+    secrets,
+    sensors::SENSORS_SCRIPT,
+    share::SHARE_SCRIPT,
+    storage::{default_profile_directory, named_profile_directory, web_app_profile_directory},
+    virtual_keyboard::virtual_keyboard_script,
+    web_app::{THEME_COLOR_SCRIPT, WEB_APP_MANIFEST_SCRIPT},
+    webview::context_menu::CONTEXT_MENU_SCRIPT,
+};
+
 /// Servo time profile settings
 #[derive(Clone, Debug)]
 pub struct ProfilerSettings {
@@ -39,6 +74,10 @@ pub struct CliArgs {
     pub ipc_channel: Option<String>,
     /// Should launch without control panel
     pub no_panel: bool,
+    /// Site to run as a standalone app
+    pub app: Option<url::Url>,
+    /// Named profile to run in
+    pub profile: Option<String>,
     /// Window size for the initial window
     pub inner_size: Option<PhysicalSize<u32>>,
     /// Window position for the initial window
@@ -47,6 +86,12 @@ pub struct CliArgs {
     pub no_maximized: bool,
     /// Port number to start a server to listen to remote Firefox devtools connections. 0 for random port.
     pub devtools_port: Option<u16>,
+    /// Port number to start a WebDriver server on
+    pub webdriver_port: Option<u16>,
+    /// Port number to serve the Chrome DevTools Protocol on
+    pub cdp_port: Option<u16>,
+    /// Origins of web pages allowed to connect to the Chrome DevTools Protocol server
+    pub cdp_allowed_origins: Vec<url::Url>,
     /// Servo time profile settings
     pub profiler_settings: Option<versoview_messages::ProfilerSettings>,
     /// Path to resource directory. If None, Verso will try to get default directory. And if that
@@ -60,6 +105,118 @@ pub struct CliArgs {
     pub userscripts_directory: Option<String>,
     /// Initial window's zoom level
     pub zoom_level: Option<f32>,
+    /// Path to a JSON config file, command line arguments take precedence over it
+    pub config_file: Option<PathBuf>,
+    /// Reload webviews showing `file://` content from this directory when files in it change
+    pub watch: Option<PathBuf>,
+    /// Show hidden files in `file://` directory listings
+    pub show_hidden_files: bool,
+    /// Deny loading `file://` URLs
+    pub no_file_access: bool,
+    /// Only allow loading `file://` URLs under these directories
+    pub file_access_roots: Vec<PathBuf>,
+    /// Block requests from `file://` documents to remote origins
+    pub file_sandbox: bool,
+    /// URLs to serve from local files instead of the network
+    pub resource_overrides: Vec<ResourceOverride>,
+    /// Import cookies, bookmarks and history from another browser on startup
+    pub import: Option<BrowserImport>,
+    /// Origins allowed to store secrets in the OS keychain
+    pub secret_bridge_origins: Vec<url::Url>,
+    /// PEM file of root CA certificates to trust instead of the default ones
+    pub certificate_bundle: Option<PathBuf>,
+    /// Public keys pinned for origins
+    pub certificate_pins: Vec<CertificatePin>,
+    /// Don't let users proceed to sites with an invalid certificate
+    pub block_certificate_errors: bool,
+    /// Referrer policy of every document
+    pub referrer_policy: Option<ReferrerPolicy>,
+    /// Tell pages the user doesn't want to be tracked
+    pub do_not_track: bool,
+    /// Tell pages the user doesn't want their data to be sold or shared
+    pub global_privacy_control: bool,
+    /// Normalize values used to fingerprint users
+    pub resist_fingerprinting: bool,
+    /// Never throttle animations to save power
+    pub no_power_save: bool,
+    /// Throttle animations while on battery
+    pub power_save_on_battery: bool,
+    /// When pages run their animations
+    pub animations: Option<AnimationPolicy>,
+    /// Expose the battery to pages
+    pub battery_status_api: bool,
+    /// Expose the motion and light sensors to pages
+    pub sensors: bool,
+    /// Tint the titlebar with the theme color of pages
+    pub theme_color_titlebar: bool,
+    /// Draw a progress bar while pages load
+    pub load_progress_bar: bool,
+    /// Seconds before asking to stop unresponsive pages
+    pub slow_script_timeout: Option<u64>,
+    /// How frames from other origins are treated
+    pub cross_origin_frames: Option<CrossOriginFramePolicy>,
+    /// Don't index the text of visited pages
+    pub no_history_text_index: bool,
+    /// Days visited pages are kept in the history
+    pub history_retention_days: Option<u32>,
+    /// Blank webviews kept ready for new tabs in every window
+    pub warm_pool_size: Option<usize>,
+    /// Don't cache the compiled shader programs on disk
+    pub no_shader_cache: bool,
+    /// URL of the manifest of signed releases to update from
+    pub update_url: Option<url::Url>,
+    /// Release channel to update from
+    pub update_channel: Option<UpdateChannel>,
+    /// URL crash reports are submitted to
+    pub crash_report_url: Option<url::Url>,
+    /// Ignore the config file, user scripts and optional GPU features
+    pub safe_mode: bool,
+    /// Reopen the windows and tabs of the last session
+    pub restore_session: bool,
+    /// Open private windows
+    pub private: bool,
+    /// Render with the software rasterizer of the GL driver
+    pub software_rendering: bool,
+    /// How windows wait for the vertical blank
+    pub vsync: Option<Vsync>,
+    /// Clockwise rotation of the output of the windows
+    pub rotation: Option<Rotation>,
+    /// How the edges of glyphs are smoothed
+    pub font_antialiasing: Option<FontAntialiasing>,
+    /// How much glyphs are fitted to the pixel grid
+    pub font_hinting: Option<FontHinting>,
+    /// Gamma of glyphs
+    pub font_gamma: Option<f32>,
+    /// Forget the cookies when Verso quits
+    pub session_cookies: bool,
+    /// Block the cookies of third-party sites
+    pub block_third_party_cookies: bool,
+    /// Don't run JavaScript on pages
+    pub disable_javascript: bool,
+    /// Which images pages load
+    pub images: Option<ImageLoading>,
+    /// Load the smallest candidate of responsive images
+    pub data_saver: bool,
+    /// Most megabytes of responses kept in the HTTP cache
+    pub http_cache_size: Option<u64>,
+    /// Key the HTTP cache and third-party cookies by the site of the tab
+    pub partition_network: bool,
+    /// On-screen keyboard of fields focused by touch
+    pub virtual_keyboard: Option<VirtualKeyboard>,
+    /// Unix socket, named pipe or TCP address to serve the control protocol on
+    pub ipc_socket: Option<PathBuf>,
+    /// Token clients of the control protocol authenticate with
+    pub ipc_token: Option<String>,
+    /// Requests per second each client of the control protocol may send
+    pub ipc_rate_limit: Option<u32>,
+    /// Serve the control protocol on TCP addresses other than loopback
+    pub ipc_allow_remote: bool,
+    /// Address to also serve the control protocol on over WebSocket
+    pub ipc_websocket: Option<SocketAddr>,
+    /// Record performance metrics in the profile
+    pub metrics: bool,
+    /// Endpoint to upload the metrics to
+    pub metrics_upload_url: Option<url::Url>,
 }
 
 /// Parse CLI arguments to a [`CliArgs`]
@@ -76,12 +233,42 @@ pub fn parse_cli_args() -> Result<CliArgs, getopts::Fail> {
         "",
     );
     opts.optflag("", "no-panel", "Launch Verso without control panel");
+    opts.optopt(
+        "",
+        "app",
+        "Run the site as a standalone app, without control panel and with its own profile",
+        "https://example.com",
+    );
+    opts.optopt(
+        "",
+        "profile",
+        "Run in the named profile, with its own cookies, cache, history and settings",
+        "NAME",
+    );
     opts.optopt(
         "",
         "devtools-port",
         "Launch Verso with devtools server enabled and listen to port",
         "1234",
     );
+    opts.optopt(
+        "",
+        "webdriver-port",
+        "Launch Verso with a WebDriver server listening to port",
+        "4444",
+    );
+    opts.optopt(
+        "",
+        "cdp-port",
+        "Serve the Chrome DevTools Protocol on the loopback interface on port",
+        "9222",
+    );
+    opts.optmulti(
+        "",
+        "cdp-allow-origin",
+        "Allow web pages of the origin to connect to the Chrome DevTools Protocol server, can be set multiple times",
+        "http://localhost:8080",
+    );
     opts.optopt(
         "p",
         "profiler",
@@ -152,21 +339,335 @@ pub fn parse_cli_args() -> Result<CliArgs, getopts::Fail> {
 
     opts.optopt("", "zoom", "Initial window's zoom level", "1.5");
 
+    opts.optopt(
+        "",
+        "config",
+        "Path to a JSON config file, e.g. to declare the windows to open on startup",
+        "verso.json",
+    );
+    opts.optopt(
+        "",
+        "watch",
+        "Reload webviews showing file:// content from this directory when files in it change",
+        "PATH",
+    );
+    opts.optflag(
+        "",
+        "show-hidden-files",
+        "Show hidden files in file:// directory listings",
+    );
+    opts.optflag("", "no-file-access", "Deny loading file:// URLs");
+    opts.optmulti(
+        "",
+        "file-access-root",
+        "Only allow loading file:// URLs under this directory, can be set multiple times",
+        "PATH",
+    );
+    opts.optflag(
+        "",
+        "file-sandbox",
+        "Block requests from file:// documents to remote origins",
+    );
+    opts.optmulti(
+        "",
+        "override",
+        "Serve the URL from a local file instead of the network, can be set multiple times",
+        "https://example.com/app.js=patched.js",
+    );
+    opts.optopt(
+        "",
+        "import",
+        "Import cookies, bookmarks and history from another browser on startup",
+        "chrome|firefox",
+    );
+    opts.optopt(
+        "",
+        "import-profile",
+        "Profile directory to import from, the default profile of the browser is used if not set",
+        "PATH",
+    );
+    opts.optmulti(
+        "",
+        "secret-bridge-origin",
+        "Allow the origin to store secrets in the OS keychain through window.verso.secrets, can be set multiple times",
+        "https://example.com",
+    );
+    opts.optopt(
+        "",
+        "certificate-bundle",
+        "PEM file of root CA certificates to trust instead of the default ones",
+        "ca.pem",
+    );
+    opts.optopt(
+        "",
+        "referrer-policy",
+        "Referrer policy of every document",
+        "no-referrer|same-origin|strict-origin|strict-origin-when-cross-origin",
+    );
+    opts.optflag(
+        "",
+        "do-not-track",
+        "Tell pages the user doesn't want to be tracked",
+    );
+    opts.optflag(
+        "",
+        "global-privacy-control",
+        "Tell pages the user doesn't want their data to be sold or shared",
+    );
+    opts.optflag(
+        "",
+        "no-power-save",
+        "Never throttle animations when the system is idle or in power saver mode",
+    );
+    opts.optflag(
+        "",
+        "power-save-on-battery",
+        "Also throttle animations while the system runs on its battery",
+    );
+    opts.optopt(
+        "",
+        "animations",
+        "Pause the animations of windows which aren't focused, or of pages after they ran once",
+        "normal|pause-in-background|once",
+    );
+    opts.optflag(
+        "",
+        "battery-status-api",
+        "Expose the battery of the system to pages with navigator.getBattery()",
+    );
+    opts.optflag(
+        "",
+        "sensors",
+        "Expose the accelerometer, gyroscope and ambient light sensor of the device to pages",
+    );
+    opts.optflag(
+        "",
+        "theme-color-titlebar",
+        "Tint the titlebar with the theme-color of the current page",
+    );
+    opts.optflag(
+        "",
+        "load-progress-bar",
+        "Draw a progress bar at the top of the page while it loads",
+    );
+    opts.optopt(
+        "",
+        "slow-script-timeout",
+        "Seconds a page can be unresponsive before asking whether to stop it, 0 to never ask",
+        "10",
+    );
+    opts.optopt(
+        "",
+        "cross-origin-frames",
+        "Load frames from other origins, only sandboxed ones, or none",
+        "allow|sandbox|block",
+    );
+    opts.optflag(
+        "",
+        "no-history-text-index",
+        "Don't index the text of visited pages to search the history by content",
+    );
+    opts.optopt(
+        "",
+        "history-retention-days",
+        "Days visited pages are kept in the history, 0 to keep them forever",
+        "90",
+    );
+    opts.optopt(
+        "",
+        "warm-pool-size",
+        "Blank webviews kept ready for new tabs in every window",
+        "2",
+    );
+    opts.optflag(
+        "",
+        "no-shader-cache",
+        "Don't cache the compiled shader programs on disk",
+    );
+    opts.optopt(
+        "",
+        "update-url",
+        "Check the manifest of signed releases at the URL on startup and update on restart",
+        "https://example.com/verso/releases.json",
+    );
+    opts.optopt(
+        "",
+        "update-channel",
+        "Release channel to update from",
+        "stable|nightly",
+    );
+    opts.optflag(
+        "",
+        "restore-session",
+        "Reopen the windows and tabs of the last session",
+    );
+    opts.optflag(
+        "",
+        "private",
+        "Open private windows, which keep no cookies, cache, storage or history after they close",
+    );
+    opts.optflag(
+        "",
+        "safe-mode",
+        "Start without the config file, user scripts, resource overrides, the shader cache and optional GPU features",
+    );
+    opts.optflag(
+        "",
+        "software-rendering",
+        "Render with the software rasterizer of the GL driver instead of the GPU",
+    );
+    opts.optopt(
+        "",
+        "vsync",
+        "Wait for every vertical blank, never, unless the last frame missed it, or every N blanks",
+        "enabled|immediate|adaptive|N",
+    );
+    opts.optopt(
+        "",
+        "rotation",
+        "Rotate the output of the windows clockwise, for screens mounted in another orientation",
+        "0|90|180|270",
+    );
+    opts.optopt(
+        "",
+        "font-antialiasing",
+        "How the edges of glyphs are smoothed",
+        "none|grayscale|subpixel",
+    );
+    opts.optopt(
+        "",
+        "font-hinting",
+        "How much glyphs are fitted to the pixel grid, with FreeType",
+        "none|slight|full",
+    );
+    opts.optopt(
+        "",
+        "font-gamma",
+        "Gamma of glyphs, higher makes text look bolder, on Windows",
+        "1.8",
+    );
+    opts.optflag(
+        "",
+        "session-cookies",
+        "Forget the cookies when Verso quits instead of saving them in the profile",
+    );
+    opts.optflag(
+        "",
+        "block-third-party-cookies",
+        "Send the requests of pages to other sites without cookies, and ignore the cookies they set",
+    );
+    opts.optflag(
+        "",
+        "disable-javascript",
+        "Don't run JavaScript on pages, except on the origins of verso://site-settings allowing it",
+    );
+    opts.optopt(
+        "",
+        "images",
+        "Which images pages load, or show a placeholder for each until it's clicked",
+        "all|block|click-to-load",
+    );
+    opts.optflag(
+        "",
+        "data-saver",
+        "Load the smallest candidate of responsive images",
+    );
+    opts.optopt(
+        "",
+        "http-cache-size",
+        "Most megabytes of responses kept in the HTTP cache of the profile, 0 to disable it",
+        "MB",
+    );
+    opts.optflag(
+        "",
+        "partition-network",
+        "Key the HTTP cache and the cookies of requests to other sites by the site of the tab",
+    );
+    opts.optopt(
+        "",
+        "virtual-keyboard",
+        "On-screen keyboard of fields focused by touch, Verso's own one for DRM/KMS outputs",
+        "off|platform|built-in",
+    );
+    opts.optopt(
+        "",
+        "ipc-socket",
+        "Serve the JSON control protocol on this unix socket, named pipe on Windows, or TCP address",
+        "PATH",
+    );
+    opts.optopt(
+        "",
+        "ipc-token",
+        "Token clients of the control protocol authenticate with, defaults to $VERSO_IPC_TOKEN",
+        "TOKEN",
+    );
+    opts.optopt(
+        "",
+        "ipc-rate-limit",
+        "Requests per second each client of the control protocol may send, 0 for no limit",
+        "100",
+    );
+    opts.optflag(
+        "",
+        "ipc-allow-remote",
+        "Serve the control protocol on TCP addresses other than loopback, needs --ipc-token",
+    );
+    opts.optopt(
+        "",
+        "ipc-websocket",
+        "Also serve the JSON control protocol over WebSocket on this address",
+        "127.0.0.1:9230",
+    );
+    opts.optopt(
+        "",
+        "crash-report-url",
+        "Let crash reports be submitted to the URL from verso://crashes",
+        "https://example.com/crashes",
+    );
+    opts.optflag(
+        "",
+        "metrics",
+        "Record frame times, page load times and crashes in the profile",
+    );
+    opts.optopt(
+        "",
+        "metrics-upload-url",
+        "Upload the recorded metrics to the endpoint on startup",
+        "https://example.com/metrics",
+    );
+    opts.optflag(
+        "",
+        "resist-fingerprinting",
+        "Normalize the screen size, timezone, hardware concurrency and canvas readbacks reported to pages",
+    );
+    opts.optmulti(
+        "",
+        "certificate-pin",
+        "Pin a base64 SHA-256 hash of a public key for the origin, can be set multiple times",
+        "https://example.com=BASE64",
+    );
+    opts.optflag(
+        "",
+        "block-certificate-errors",
+        "Don't let users proceed to sites with an invalid certificate",
+    );
+
     let matches: getopts::Matches = opts.parse(&args[1..])?;
-    let url = matches
-        .opt_str("url")
-        .and_then(|url| match url::Url::parse(&url) {
-            Ok(url_parsed) => Some(url_parsed),
-            Err(e) => {
-                if e == url::ParseError::RelativeUrlWithoutBase {
-                    if let Ok(url_parsed) = url::Url::parse(&format!("https://{url}")) {
-                        return Some(url_parsed);
-                    }
+    let parse_url = |url: String| match url::Url::parse(&url) {
+        Ok(url_parsed) => Some(url_parsed),
+        Err(e) => {
+            if e == url::ParseError::RelativeUrlWithoutBase {
+                if let Ok(url_parsed) = url::Url::parse(&format!("https://{url}")) {
+                    return Some(url_parsed);
                 }
-                log::error!("Invalid initial url: {url}");
-                None
             }
-        });
+            log::error!("Invalid initial url: {url}");
+            None
+        }
+    };
+    let url = matches.opt_str("url").and_then(parse_url);
+    let app = matches.opt_str("app").and_then(parse_url);
+    let profile = matches.opt_str("profile");
     let resource_dir = matches.opt_str("resources").map(PathBuf::from);
     let ipc_channel = matches.opt_str("ipc-channel");
     let no_panel = matches.opt_present("no-panel");
@@ -174,6 +675,27 @@ pub fn parse_cli_args() -> Result<CliArgs, getopts::Fail> {
         log::error!("Failed to parse devtools-port command line argument: {e}");
         None
     });
+    let webdriver_port = matches
+        .opt_get::<u16>("webdriver-port")
+        .unwrap_or_else(|e| {
+            log::error!("Failed to parse webdriver-port command line argument: {e}");
+            None
+        });
+    let cdp_port = matches.opt_get::<u16>("cdp-port").unwrap_or_else(|e| {
+        log::error!("Failed to parse cdp-port command line argument: {e}");
+        None
+    });
+    let cdp_allowed_origins = matches
+        .opt_strs("cdp-allow-origin")
+        .into_iter()
+        .filter_map(|origin| match url::Url::parse(&origin) {
+            Ok(origin) => Some(origin),
+            Err(e) => {
+                log::error!("Failed to parse cdp-allow-origin command line argument: {e}");
+                None
+            }
+        })
+        .collect();
 
     let profiler_settings = if let Ok(Some(profiler_interval)) = matches.opt_get("profiler") {
         let profile_output = matches.opt_str("profiler-output-file");
@@ -243,12 +765,231 @@ pub fn parse_cli_args() -> Result<CliArgs, getopts::Fail> {
         None
     });
 
+    let config_file = matches.opt_str("config").map(PathBuf::from);
+    let watch = matches.opt_str("watch").map(PathBuf::from);
+    let show_hidden_files = matches.opt_present("show-hidden-files");
+    let no_file_access = matches.opt_present("no-file-access");
+    let file_access_roots = matches
+        .opt_strs("file-access-root")
+        .into_iter()
+        .map(PathBuf::from)
+        .collect();
+    let file_sandbox = matches.opt_present("file-sandbox");
+    let import = matches
+        .opt_get::<versoview_messages::Browser>("import")
+        .unwrap_or_else(|e| {
+            log::error!("Failed to parse import command line argument: {e}");
+            None
+        })
+        .map(|browser| BrowserImport {
+            browser,
+            profile_directory: matches.opt_str("import-profile").map(PathBuf::from),
+        });
+    let secret_bridge_origins = matches
+        .opt_strs("secret-bridge-origin")
+        .into_iter()
+        .filter_map(|origin| match url::Url::parse(&origin) {
+            Ok(origin) => Some(origin),
+            Err(e) => {
+                log::error!("Failed to parse secret-bridge-origin command line argument: {e}");
+                None
+            }
+        })
+        .collect();
+    let referrer_policy = matches
+        .opt_get::<ReferrerPolicy>("referrer-policy")
+        .unwrap_or_else(|e| {
+            log::error!("Failed to parse referrer-policy command line argument: {e}");
+            None
+        });
+    let do_not_track = matches.opt_present("do-not-track");
+    let global_privacy_control = matches.opt_present("global-privacy-control");
+    let resist_fingerprinting = matches.opt_present("resist-fingerprinting");
+    let no_power_save = matches.opt_present("no-power-save");
+    let power_save_on_battery = matches.opt_present("power-save-on-battery");
+    let animations = matches
+        .opt_get::<AnimationPolicy>("animations")
+        .unwrap_or_else(|e| {
+            log::error!("Failed to parse animations command line argument: {e}");
+            None
+        });
+    let battery_status_api = matches.opt_present("battery-status-api");
+    let sensors = matches.opt_present("sensors");
+    let theme_color_titlebar = matches.opt_present("theme-color-titlebar");
+    let load_progress_bar = matches.opt_present("load-progress-bar");
+    let slow_script_timeout = matches
+        .opt_get::<u64>("slow-script-timeout")
+        .unwrap_or_else(|e| {
+            log::error!("Failed to parse slow-script-timeout command line argument: {e}");
+            None
+        });
+    let cross_origin_frames = matches
+        .opt_get::<CrossOriginFramePolicy>("cross-origin-frames")
+        .unwrap_or_else(|e| {
+            log::error!("Failed to parse cross-origin-frames command line argument: {e}");
+            None
+        });
+    let no_history_text_index = matches.opt_present("no-history-text-index");
+    let history_retention_days = matches
+        .opt_get::<u32>("history-retention-days")
+        .unwrap_or_else(|e| {
+            log::error!("Failed to parse history-retention-days command line argument: {e}");
+            None
+        });
+    let no_shader_cache = matches.opt_present("no-shader-cache");
+    let update_url = matches
+        .opt_get::<url::Url>("update-url")
+        .unwrap_or_else(|e| {
+            log::error!("Failed to parse update-url command line argument: {e}");
+            None
+        });
+    let crash_report_url = matches
+        .opt_get::<url::Url>("crash-report-url")
+        .unwrap_or_else(|e| {
+            log::error!("Failed to parse crash-report-url command line argument: {e}");
+            None
+        });
+    let safe_mode = matches.opt_present("safe-mode");
+    let restore_session = matches.opt_present("restore-session");
+    let private = matches.opt_present("private");
+    let software_rendering = matches.opt_present("software-rendering");
+    let vsync = matches.opt_get::<Vsync>("vsync").unwrap_or_else(|e| {
+        log::error!("Failed to parse vsync command line argument: {e}");
+        None
+    });
+    let rotation = matches.opt_get::<Rotation>("rotation").unwrap_or_else(|e| {
+        log::error!("Failed to parse rotation command line argument: {e}");
+        None
+    });
+    let font_antialiasing = matches
+        .opt_get::<FontAntialiasing>("font-antialiasing")
+        .unwrap_or_else(|e| {
+            log::error!("Failed to parse font-antialiasing command line argument: {e}");
+            None
+        });
+    let font_hinting = matches
+        .opt_get::<FontHinting>("font-hinting")
+        .unwrap_or_else(|e| {
+            log::error!("Failed to parse font-hinting command line argument: {e}");
+            None
+        });
+    let font_gamma = matches.opt_get::<f32>("font-gamma").unwrap_or_else(|e| {
+        log::error!("Failed to parse font-gamma command line argument: {e}");
+        None
+    });
+    let session_cookies = matches.opt_present("session-cookies");
+    let block_third_party_cookies = matches.opt_present("block-third-party-cookies");
+    let disable_javascript = matches.opt_present("disable-javascript");
+    let images = matches
+        .opt_get::<ImageLoading>("images")
+        .unwrap_or_else(|e| {
+            log::error!("Failed to parse images command line argument: {e}");
+            None
+        });
+    let data_saver = matches.opt_present("data-saver");
+    let http_cache_size = matches
+        .opt_get::<u64>("http-cache-size")
+        .unwrap_or_else(|e| {
+            log::error!("Failed to parse http-cache-size command line argument: {e}");
+            None
+        });
+    let partition_network = matches.opt_present("partition-network");
+    let virtual_keyboard = matches
+        .opt_get::<VirtualKeyboard>("virtual-keyboard")
+        .unwrap_or_else(|e| {
+            log::error!("Failed to parse virtual-keyboard command line argument: {e}");
+            None
+        });
+    let ipc_socket = matches.opt_str("ipc-socket").map(PathBuf::from);
+    let ipc_token = matches
+        .opt_str("ipc-token")
+        .or_else(|| std::env::var("VERSO_IPC_TOKEN").ok())
+        .filter(|token| !token.is_empty());
+    let ipc_rate_limit = matches
+        .opt_get::<u32>("ipc-rate-limit")
+        .unwrap_or_else(|e| {
+            log::error!("Failed to parse ipc-rate-limit command line argument: {e}");
+            None
+        });
+    let ipc_allow_remote = matches.opt_present("ipc-allow-remote");
+    let ipc_websocket = matches
+        .opt_get::<SocketAddr>("ipc-websocket")
+        .unwrap_or_else(|e| {
+            log::error!("Failed to parse ipc-websocket command line argument: {e}");
+            None
+        });
+    let metrics = matches.opt_present("metrics");
+    let metrics_upload_url = matches
+        .opt_get::<url::Url>("metrics-upload-url")
+        .unwrap_or_else(|e| {
+            log::error!("Failed to parse metrics-upload-url command line argument: {e}");
+            None
+        });
+    let update_channel = matches
+        .opt_get::<UpdateChannel>("update-channel")
+        .unwrap_or_else(|e| {
+            log::error!("Failed to parse update-channel command line argument: {e}");
+            None
+        });
+    let warm_pool_size = matches
+        .opt_get::<usize>("warm-pool-size")
+        .unwrap_or_else(|e| {
+            log::error!("Failed to parse warm-pool-size command line argument: {e}");
+            None
+        });
+    let certificate_bundle = matches.opt_str("certificate-bundle").map(PathBuf::from);
+    let block_certificate_errors = matches.opt_present("block-certificate-errors");
+    let mut certificate_pins: Vec<CertificatePin> = Vec::new();
+    for arg in matches.opt_strs("certificate-pin") {
+        let Some((origin, pin)) = arg
+            .split_once('=')
+            .and_then(|(origin, pin)| Some((url::Url::parse(origin).ok()?, pin)))
+        else {
+            log::error!(
+                "Invalid certificate-pin command line argument, expected ORIGIN=BASE64: {arg}"
+            );
+            continue;
+        };
+        match certificate_pins
+            .iter_mut()
+            .find(|certificate_pin| certificate_pin.origin.origin() == origin.origin())
+        {
+            Some(certificate_pin) => certificate_pin.sha256_pins.push(pin.to_string()),
+            None => certificate_pins.push(CertificatePin {
+                origin,
+                sha256_pins: vec![pin.to_string()],
+            }),
+        }
+    }
+    let resource_overrides = matches
+        .opt_strs("override")
+        .into_iter()
+        .filter_map(|arg| {
+            let parsed = arg.split_once('=').and_then(|(url, path)| {
+                Some(ResourceOverride {
+                    url: url::Url::parse(url).ok()?,
+                    source: ResourceOverrideSource::File(PathBuf::from(path)),
+                    content_type: None,
+                })
+            });
+            if parsed.is_none() {
+                log::error!("Invalid override command line argument, expected URL=PATH: {arg}");
+            }
+            parsed
+        })
+        .collect();
+
     Ok(CliArgs {
         url,
         resource_dir,
         ipc_channel,
+        app,
+        profile,
         no_panel,
         devtools_port,
+        webdriver_port,
+        cdp_port,
+        cdp_allowed_origins,
         profiler_settings,
         user_agent,
         init_script,
@@ -257,6 +998,62 @@ pub fn parse_cli_args() -> Result<CliArgs, getopts::Fail> {
         inner_size,
         position,
         no_maximized,
+        config_file,
+        watch,
+        show_hidden_files,
+        no_file_access,
+        file_access_roots,
+        file_sandbox,
+        resource_overrides,
+        import,
+        secret_bridge_origins,
+        certificate_bundle,
+        certificate_pins,
+        block_certificate_errors,
+        referrer_policy,
+        do_not_track,
+        global_privacy_control,
+        resist_fingerprinting,
+        no_power_save,
+        power_save_on_battery,
+        animations,
+        battery_status_api,
+        sensors,
+        theme_color_titlebar,
+        load_progress_bar,
+        slow_script_timeout,
+        cross_origin_frames,
+        no_history_text_index,
+        history_retention_days,
+        warm_pool_size,
+        no_shader_cache,
+        update_url,
+        update_channel,
+        crash_report_url,
+        safe_mode,
+        restore_session,
+        private,
+        software_rendering,
+        vsync,
+        rotation,
+        font_antialiasing,
+        font_hinting,
+        font_gamma,
+        session_cookies,
+        block_third_party_cookies,
+        disable_javascript,
+        images,
+        data_saver,
+        http_cache_size,
+        partition_network,
+        virtual_keyboard,
+        ipc_socket,
+        ipc_token,
+        ipc_rate_limit,
+        ipc_allow_remote,
+        ipc_websocket,
+        metrics,
+        metrics_upload_url,
     })
 }
 
@@ -267,10 +1064,18 @@ pub struct Config {
     pub url: url::Url,
     /// Should launch without or without control panel
     pub with_panel: bool,
-    /// Window settings for the initial winit window
+    /// Window settings for the initial winit window, also used for new windows
     pub window_attributes: WindowAttributes,
+    /// Windows to open on startup, the first one is the initial window
+    pub initial_windows: Vec<InitialWindow>,
     /// Port number to start a server to listen to remote Firefox devtools connections. 0 for random port.
     pub devtools_port: Option<u16>,
+    /// Port number to start a WebDriver server on.
+    pub webdriver_port: Option<u16>,
+    /// Port number to serve the Chrome DevTools Protocol on.
+    pub cdp_port: Option<u16>,
+    /// Origins of web pages allowed to connect to the Chrome DevTools Protocol server.
+    pub cdp_allowed_origins: Vec<url::Origin>,
     /// Servo time profile settings
     pub profiler_settings: Option<ProfilerSettings>,
     /// Override the user agent
@@ -282,48 +1087,482 @@ pub struct Config {
     /// Path to resource directory. If None, Verso will try to get default directory. And if that
     /// still doesn't exist, all resource configuration will set to default values.
     pub resource_dir: PathBuf,
+    /// Reload webviews showing `file://` content from this directory when files in it change.
+    pub watch_directory: Option<PathBuf>,
+    /// Show hidden files in `file://` directory listings.
+    pub show_hidden_files: bool,
+    /// Access policy of `file://` URLs.
+    pub file_access: FileAccessPolicy,
+    /// URLs to serve from local files or in-memory bytes instead of the network.
+    pub resource_overrides: Vec<ResourceOverride>,
+    /// Network requests are only answered by the controller and never reach the network.
+    pub mock_network: bool,
+    /// Import cookies, bookmarks and history from another browser on startup.
+    pub import: Option<BrowserImport>,
+    /// Origins allowed to store secrets in the OS keychain through `window.verso.secrets`.
+    pub secret_bridge_origins: Vec<url::Origin>,
+    /// Token of the session the secrets bridge sends its requests with.
+    pub secret_bridge_token: String,
+    /// PEM file of root CA certificates to trust instead of the default ones.
+    pub certificate_bundle: Option<PathBuf>,
+    /// Public keys pinned for origins.
+    pub certificate_pins: Vec<CertificatePin>,
+    /// Whether users can proceed to sites with an invalid certificate.
+    pub certificate_errors: CertificateErrorPolicy,
+    /// Whether [`Self::certificate_errors`] blocks them, shared with the resource reader which
+    /// leaves the secret out of the page shown for them so it can't allow them.
+    pub certificate_errors_blocked: Arc<AtomicBool>,
+    /// Referrer policy and Do-Not-Track settings of webviews without their own settings.
+    pub privacy: PrivacySettings,
+    /// When and how much animations are throttled to save power.
+    pub power_save: PowerSaveConfig,
+    /// When pages run their animations.
+    pub animations: AnimationPolicy,
+    /// Expose the battery of the system to pages.
+    pub battery_status_api: bool,
+    /// Expose the motion and light sensors of the device to pages.
+    pub sensors: bool,
+    /// Tint the titlebar of windows with the `theme-color` of their current tab.
+    pub theme_color_titlebar: bool,
+    /// Draw a progress bar at the top of the current tab while it loads.
+    pub load_progress_bar: bool,
+    /// Seconds the script of a page can be unresponsive before the user is asked whether to stop
+    /// it, 0 to never ask.
+    pub slow_script_timeout: u64,
+    /// Index the text of visited pages to search the history by content.
+    pub index_history_text: bool,
+    /// Days visited pages are kept in the history, 0 to keep them forever.
+    pub history_retention_days: u32,
+    /// Blank webviews kept ready for new tabs in every window.
+    pub warm_pool_size: usize,
+    /// Cache the shader programs compiled by the GL driver on disk.
+    pub shader_cache: bool,
+    /// Where to check for updates, disabled if `None`.
+    pub update: Option<UpdateConfig>,
+    /// Whether performance metrics are recorded and where they are uploaded.
+    pub metrics: MetricsConfig,
+    /// Where crash reports are submitted, they can't be submitted if `None`.
+    pub crash_report_url: Option<url::Url>,
+    /// Started in safe mode, without user scripts, resource overrides, the shader cache and
+    /// optional GPU features.
+    pub safe_mode: bool,
+    /// Reopen the windows and tabs of the last session instead of the initial windows.
+    pub restore_session: bool,
+    /// The windows are private, nothing is loaded from or saved to the profile.
+    pub private: bool,
+    /// Render with the software rasterizer of the GL driver instead of the GPU.
+    pub software_rendering: bool,
+    /// How windows wait for the vertical blank before presenting a frame.
+    pub vsync: Vsync,
+    /// Clockwise rotation of the output of the windows.
+    pub rotation: Rotation,
+    /// Antialiasing, hinting and gamma of the text of pages.
+    pub font_rendering: FontRendering,
+    /// What is kept of the cookies pages set.
+    pub cookies: CookiePolicy,
+    /// Whether pages run JavaScript, for every origin and for some of them.
+    pub javascript: JavaScriptSettings,
+    /// Which images pages load and whether to save data on them, the one saved in the profile is
+    /// used if `None`.
+    pub images: Option<ImagePolicy>,
+    /// Most megabytes of responses kept in the HTTP cache of the profile, 0 if it's disabled.
+    pub http_cache_size: u64,
+    /// Whether the HTTP cache and third-party cookies are keyed by the site of the tab.
+    pub partition_network: bool,
+    /// On-screen keyboard shown when an editable field is focused by touch.
+    pub virtual_keyboard: VirtualKeyboard,
+    /// Unix socket, named pipe or TCP address the control protocol is served on.
+    pub ipc_socket: Option<PathBuf>,
+    /// Token clients of the control protocol authenticate with.
+    pub ipc_token: Option<String>,
+    /// Requests per second each client of the control protocol may send, 0 for no limit.
+    pub ipc_rate_limit: u32,
+    /// Whether the control protocol may be served on TCP addresses other than loopback.
+    pub ipc_allow_remote: bool,
+    /// Address the control protocol is also served on over WebSocket.
+    pub ipc_websocket: Option<SocketAddr>,
+    /// Site running as a standalone app.
+    pub app: Option<url::Url>,
+    /// The settings this config is created from, read and replaced by the controller at runtime.
+    pub settings: ConfigFromController,
+    /// Directory of the bookmarks, history, session and Servo's storage, the config directory of
+    /// Verso if `None`.
+    pub profile_directory: Option<PathBuf>,
+}
+
+/// A window to open on startup.
+#[derive(Clone, Debug)]
+pub struct InitialWindow {
+    /// URL to load in the window
+    pub url: url::Url,
+    /// Window settings of the winit window
+    pub window_attributes: WindowAttributes,
+    /// Name to address the window with in the control protocol
+    pub name: Option<String>,
 }
 
 impl Config {
     /// Create a new configuration for creating Verso instance from the CLI arguments.
     pub fn from_cli_args(cli_args: CliArgs) -> Self {
-        let mut user_scripts = Vec::new();
-        if let Some(init_script) = cli_args.init_script {
-            user_scripts.push(init_script.into());
-        }
-        user_scripts.extend(
-            load_userscripts(cli_args.userscripts_directory).expect("Failed to load userscript"),
-        );
-        Self::from_controller_config(ConfigFromController {
-            url: cli_args.url,
-            with_panel: !cli_args.no_panel,
-            devtools_port: cli_args.devtools_port,
-            profiler_settings: cli_args.profiler_settings,
-            user_agent: cli_args.user_agent,
-            user_scripts,
-            zoom_level: cli_args.zoom_level,
-            resources_directory: cli_args.resource_dir,
-            maximized: !cli_args.no_maximized,
-            position: cli_args.position.map(Into::into),
-            inner_size: cli_args.inner_size.map(Into::into),
-            ..Default::default()
-        })
+        let mut config = match &cli_args.config_file {
+            Some(path) if !cli_args.safe_mode => load_config_file(path).unwrap_or_else(|e| {
+                log::error!("Failed to load config file {}: {e}", path.display());
+                ConfigFromController::default()
+            }),
+            _ => ConfigFromController {
+                with_panel: true,
+                maximized: true,
+                ..Default::default()
+            },
+        };
+
+        if cli_args.safe_mode {
+            config.safe_mode = true;
+        } else {
+            if let Some(init_script) = cli_args.init_script {
+                config.user_scripts.push(init_script.into());
+            }
+            config.user_scripts.extend(
+                load_userscripts(cli_args.userscripts_directory)
+                    .expect("Failed to load userscript"),
+            );
+        }
+        if cli_args.no_panel {
+            config.with_panel = false;
+        }
+        if cli_args.no_maximized {
+            config.maximized = false;
+        }
+        if cli_args.show_hidden_files {
+            config.show_hidden_files = true;
+        }
+        if cli_args.no_file_access {
+            config.file_access.enabled = false;
+        }
+        if cli_args.file_sandbox {
+            config.file_access.block_remote_requests = true;
+        }
+        config
+            .file_access
+            .allowed_roots
+            .extend(cli_args.file_access_roots);
+        config
+            .resource_overrides
+            .extend(cli_args.resource_overrides);
+        config.import = cli_args.import.or(config.import);
+        config
+            .secret_bridge_origins
+            .extend(cli_args.secret_bridge_origins);
+        config.certificate_bundle = cli_args.certificate_bundle.or(config.certificate_bundle);
+        config.certificate_pins.extend(cli_args.certificate_pins);
+        if cli_args.block_certificate_errors {
+            config.certificate_errors = CertificateErrorPolicy::Block;
+        }
+        config.privacy.referrer_policy =
+            cli_args.referrer_policy.or(config.privacy.referrer_policy);
+        if cli_args.do_not_track {
+            config.privacy.do_not_track = true;
+        }
+        if cli_args.global_privacy_control {
+            config.privacy.global_privacy_control = true;
+        }
+        if cli_args.resist_fingerprinting {
+            config.privacy.resist_fingerprinting = true;
+        }
+        if cli_args.no_power_save {
+            config.power_save.enabled = false;
+        }
+        if cli_args.power_save_on_battery {
+            config.power_save.on_battery = true;
+        }
+        if let Some(animations) = cli_args.animations {
+            config.animations = animations;
+        }
+        if cli_args.battery_status_api {
+            config.battery_status_api = true;
+        }
+        if cli_args.sensors {
+            config.sensors = true;
+        }
+        if cli_args.theme_color_titlebar {
+            config.theme_color_titlebar = true;
+        }
+        if cli_args.load_progress_bar {
+            config.load_progress_bar = true;
+        }
+        if let Some(slow_script_timeout) = cli_args.slow_script_timeout {
+            config.slow_script_timeout = slow_script_timeout;
+        }
+        if let Some(cross_origin_frames) = cli_args.cross_origin_frames {
+            config.cross_origin_frames = cross_origin_frames;
+        }
+        if cli_args.no_history_text_index {
+            config.index_history_text = false;
+        }
+        if let Some(history_retention_days) = cli_args.history_retention_days {
+            config.history_retention_days = history_retention_days;
+        }
+        if let Some(warm_pool_size) = cli_args.warm_pool_size {
+            config.warm_pool_size = warm_pool_size;
+        }
+        if cli_args.no_shader_cache {
+            config.shader_cache = false;
+        }
+        if let Some(url) = cli_args.update_url {
+            match &mut config.update {
+                Some(update) => update.url = url,
+                None => {
+                    config.update = Some(UpdateConfig {
+                        url,
+                        channel: UpdateChannel::default(),
+                        public_key: None,
+                    })
+                }
+            }
+        }
+        if let (Some(update), Some(channel)) = (&mut config.update, cli_args.update_channel) {
+            update.channel = channel;
+        }
+        config.crash_report_url = cli_args.crash_report_url.or(config.crash_report_url);
+        if cli_args.restore_session {
+            config.restore_session = true;
+        }
+        if cli_args.private {
+            config.private = true;
+        }
+        if cli_args.software_rendering {
+            config.software_rendering = true;
+        }
+        if let Some(vsync) = cli_args.vsync {
+            config.vsync = vsync;
+        }
+        if let Some(rotation) = cli_args.rotation {
+            config.rotation = rotation;
+        }
+        if let Some(antialiasing) = cli_args.font_antialiasing {
+            config.font_rendering.antialiasing = antialiasing;
+        }
+        if let Some(hinting) = cli_args.font_hinting {
+            config.font_rendering.hinting = hinting;
+        }
+        if let Some(gamma) = cli_args.font_gamma {
+            config.font_rendering.gamma = gamma;
+        }
+        if cli_args.session_cookies {
+            config.cookies.session_only = true;
+        }
+        if cli_args.block_third_party_cookies {
+            config.cookies.block_third_party = true;
+        }
+        if cli_args.disable_javascript {
+            config.javascript.enabled = false;
+        }
+        if cli_args.images.is_some() || cli_args.data_saver {
+            let images = config.images.get_or_insert_default();
+            if let Some(loading) = cli_args.images {
+                images.loading = loading;
+            }
+            if cli_args.data_saver {
+                images.data_saver = true;
+            }
+        }
+        if let Some(http_cache_size) = cli_args.http_cache_size {
+            config.http_cache_size = http_cache_size;
+        }
+        if cli_args.partition_network {
+            config.partition_network = true;
+        }
+        if let Some(virtual_keyboard) = cli_args.virtual_keyboard {
+            config.virtual_keyboard = virtual_keyboard;
+        }
+        config.ipc_socket = cli_args.ipc_socket.or(config.ipc_socket);
+        config.ipc_token = cli_args.ipc_token.or(config.ipc_token);
+        if let Some(ipc_rate_limit) = cli_args.ipc_rate_limit {
+            config.ipc_rate_limit = ipc_rate_limit;
+        }
+        if cli_args.ipc_allow_remote {
+            config.ipc_allow_remote = true;
+        }
+        config.ipc_websocket = cli_args.ipc_websocket.or(config.ipc_websocket);
+        if cli_args.metrics {
+            config.metrics.enabled = true;
+        }
+        config.metrics.upload_url = cli_args.metrics_upload_url.or(config.metrics.upload_url);
+        config.url = cli_args.url.or(config.url);
+        config.app = cli_args.app.or(config.app);
+        config.profile = cli_args.profile.or(config.profile);
+        config.devtools_port = cli_args.devtools_port.or(config.devtools_port);
+        config.webdriver_port = cli_args.webdriver_port.or(config.webdriver_port);
+        config.cdp_port = cli_args.cdp_port.or(config.cdp_port);
+        config
+            .cdp_allowed_origins
+            .extend(cli_args.cdp_allowed_origins);
+        config.profiler_settings = cli_args.profiler_settings.or(config.profiler_settings);
+        config.user_agent = cli_args.user_agent.or(config.user_agent);
+        config.zoom_level = cli_args.zoom_level.or(config.zoom_level);
+        config.resources_directory = cli_args.resource_dir.or(config.resources_directory);
+        config.position = cli_args.position.map(Into::into).or(config.position);
+        config.inner_size = cli_args.inner_size.map(Into::into).or(config.inner_size);
+        config.watch_directory = cli_args.watch.or(config.watch_directory);
+
+        Self::from_controller_config(config)
     }
 
     /// Create a new configuration for creating Verso instance from the controller config.
-    pub fn from_controller_config(config: ConfigFromController) -> Self {
+    pub fn from_controller_config(mut config: ConfigFromController) -> Self {
+        if config.safe_mode {
+            log::info!(
+                "Verso is starting in safe mode, user scripts, resource overrides, the shader cache and optional GPU features are disabled"
+            );
+            config.user_scripts.clear();
+            config.resource_overrides.clear();
+            config.shader_cache = false;
+        }
+        if config.private {
+            // Private windows start from nothing of the profile
+            config.restore_session = false;
+            config.import = None;
+        }
+        let settings = config.clone();
         let resource_dir = config
             .resources_directory
             .unwrap_or_else(resources_dir_path);
-        let with_panel = config.with_panel;
+        // Standalone apps have no control panel and their own profile
+        let with_panel = config.with_panel && config.app.is_none();
+        let profile_directory = match (&config.app, &config.profile) {
+            _ if config.private => None,
+            (Some(app), _) => web_app_profile_directory(app),
+            (None, Some(name)) => named_profile_directory(name)
+                .inspect_err(|error| log::error!("{error}, using the default profile"))
+                .ok(),
+            (None, None) => None,
+        };
         let user_agent = config
             .user_agent
             .unwrap_or_else(|| default_user_agent_string().to_string());
+        let secret_bridge_origins: Vec<_> = config
+            .secret_bridge_origins
+            .iter()
+            .map(|origin| origin.origin())
+            .collect();
+        let mut user_scripts: Vec<_> = config
+            .user_scripts
+            .into_iter()
+            .map(|userscript| ServoUserScript {
+                script: userscript.script,
+                source_file: userscript.source_file,
+            })
+            .collect();
+        // The image policy can change at runtime, so the script is always added
+        user_scripts.push(ServoUserScript {
+            script: IMAGE_POLICY_SCRIPT.to_string(),
+            source_file: None,
+        });
+        // JavaScript can be disabled for origins at runtime, so the script is always added
+        user_scripts.push(ServoUserScript {
+            script: JAVASCRIPT_POLICY_SCRIPT.to_string(),
+            source_file: None,
+        });
+        // Webviews can have their own privacy settings, so the script is always added
+        user_scripts.push(ServoUserScript {
+            script: PRIVACY_SCRIPT.to_string(),
+            source_file: None,
+        });
+        user_scripts.push(ServoUserScript {
+            script: WAKE_LOCK_SCRIPT.to_string(),
+            source_file: None,
+        });
+        user_scripts.push(ServoUserScript {
+            script: GEOLOCATION_SCRIPT.to_string(),
+            source_file: None,
+        });
+        user_scripts.push(ServoUserScript {
+            script: SHARE_SCRIPT.to_string(),
+            source_file: None,
+        });
+        user_scripts.push(ServoUserScript {
+            script: BADGE_SCRIPT.to_string(),
+            source_file: None,
+        });
+        if config.battery_status_api {
+            user_scripts.push(ServoUserScript {
+                script: BATTERY_SCRIPT.to_string(),
+                source_file: None,
+            });
+        }
+        if let Some(script) = frame_isolation_script(config.cross_origin_frames) {
+            user_scripts.push(ServoUserScript {
+                script,
+                source_file: None,
+            });
+        }
+        if let Some(script) = virtual_keyboard_script(config.virtual_keyboard) {
+            user_scripts.push(ServoUserScript {
+                script,
+                source_file: None,
+            });
+        }
+        user_scripts.push(ServoUserScript {
+            script: CLIPBOARD_SCRIPT.to_string(),
+            source_file: None,
+        });
+        user_scripts.push(ServoUserScript {
+            script: CONTEXT_MENU_SCRIPT.to_string(),
+            source_file: None,
+        });
+        user_scripts.push(ServoUserScript {
+            script: CSP_VIOLATION_SCRIPT.to_string(),
+            source_file: None,
+        });
+        user_scripts.push(ServoUserScript {
+            script: DRAG_DROP_SCRIPT.to_string(),
+            source_file: None,
+        });
+        user_scripts.push(ServoUserScript {
+            script: FORM_SUBMISSION_SCRIPT.to_string(),
+            source_file: None,
+        });
+        user_scripts.push(ServoUserScript {
+            script: HIGHLIGHT_SCRIPT.to_string(),
+            source_file: None,
+        });
+        user_scripts.push(ServoUserScript {
+            script: INTEGRITY_SCRIPT.to_string(),
+            source_file: None,
+        });
+        user_scripts.push(ServoUserScript {
+            script: THEME_COLOR_SCRIPT.to_string(),
+            source_file: None,
+        });
+        if config.app.is_some() {
+            user_scripts.push(ServoUserScript {
+                script: WEB_APP_MANIFEST_SCRIPT.to_string(),
+                source_file: None,
+            });
+        }
+        if config.sensors {
+            user_scripts.push(ServoUserScript {
+                script: SENSORS_SCRIPT.to_string(),
+                source_file: None,
+            });
+        }
+        let secret_bridge_token = uuid::Uuid::new_v4().to_string();
+        if !secret_bridge_origins.is_empty() {
+            user_scripts.push(ServoUserScript {
+                script: secrets::bridge_script(&secret_bridge_token, &secret_bridge_origins),
+                source_file: None,
+            });
+        }
 
         let mut window_attributes = winit::window::Window::default_attributes()
             .with_transparent(config.transparent)
             .with_decorations(config.decorated)
-            .with_title(config.title.unwrap_or("Verso".to_owned()))
+            .with_title(match config.title {
+                Some(title) if config.private => format!("{title} (Private)"),
+                Some(title) => title,
+                None if config.private => "Verso (Private)".to_owned(),
+                None => "Verso".to_owned(),
+            })
             .with_window_icon(config.icon.and_then(|icon| {
                 winit::window::Icon::from_rgba(icon.rgba, icon.width, icon.height).ok()
             }));
@@ -348,6 +1587,41 @@ impl Config {
         window_attributes = window_attributes.with_visible(config.visible);
         window_attributes = window_attributes.with_active(config.focused);
 
+        let url = config
+            .app
+            .clone()
+            .or(config.url)
+            .unwrap_or_else(|| url::Url::parse("https://example.com").unwrap());
+        let initial_windows = if config.windows.is_empty() {
+            vec![InitialWindow {
+                url: url.clone(),
+                window_attributes: window_attributes.clone(),
+                name: None,
+            }]
+        } else {
+            config
+                .windows
+                .into_iter()
+                .map(|window| {
+                    let mut attributes = window_attributes
+                        .clone()
+                        .with_maximized(window.maximized)
+                        .with_fullscreen(if window.fullscreen {
+                            Some(Fullscreen::Borderless(None))
+                        } else {
+                            None
+                        });
+                    attributes.position = window.position;
+                    attributes.inner_size = window.inner_size;
+                    InitialWindow {
+                        url: window.url.unwrap_or_else(|| url.clone()),
+                        window_attributes: attributes,
+                        name: window.name,
+                    }
+                })
+                .collect()
+        };
+
         let profiler_settings =
             config
                 .profiler_settings
@@ -364,30 +1638,97 @@ impl Config {
                 });
 
         Self {
-            url: config
-                .url
-                .unwrap_or_else(|| url::Url::parse("https://example.com").unwrap()),
+            url,
             with_panel,
             window_attributes,
+            initial_windows,
             devtools_port: config.devtools_port,
+            webdriver_port: config.webdriver_port,
+            cdp_port: config.cdp_port,
+            cdp_allowed_origins: config
+                .cdp_allowed_origins
+                .iter()
+                .map(|origin| origin.origin())
+                .collect(),
             profiler_settings,
             user_agent,
-            user_scripts: config
-                .user_scripts
-                .into_iter()
-                .map(|userscript| ServoUserScript {
-                    script: userscript.script,
-                    source_file: userscript.source_file,
-                })
-                .collect(),
+            user_scripts,
             zoom_level: config.zoom_level,
             resource_dir,
+            watch_directory: config
+                .watch_directory
+                .map(|dir| dir.canonicalize().unwrap_or(dir)),
+            show_hidden_files: config.show_hidden_files,
+            file_access: FileAccessPolicy {
+                allowed_roots: config
+                    .file_access
+                    .allowed_roots
+                    .into_iter()
+                    .map(|root| root.canonicalize().unwrap_or(root))
+                    .collect(),
+                ..config.file_access
+            },
+            resource_overrides: config.resource_overrides,
+            mock_network: config.mock_network,
+            import: config.import,
+            secret_bridge_origins,
+            secret_bridge_token,
+            certificate_bundle: config.certificate_bundle,
+            certificate_pins: config.certificate_pins,
+            certificate_errors: config.certificate_errors,
+            certificate_errors_blocked: Arc::new(AtomicBool::new(
+                config.certificate_errors == CertificateErrorPolicy::Block,
+            )),
+            privacy: config.privacy,
+            power_save: config.power_save,
+            animations: config.animations,
+            battery_status_api: config.battery_status_api,
+            sensors: config.sensors,
+            theme_color_titlebar: config.theme_color_titlebar,
+            load_progress_bar: config.load_progress_bar,
+            slow_script_timeout: config.slow_script_timeout,
+            index_history_text: config.index_history_text,
+            history_retention_days: config.history_retention_days,
+            warm_pool_size: config.warm_pool_size,
+            shader_cache: config.shader_cache,
+            update: config.update,
+            metrics: config.metrics,
+            crash_report_url: config.crash_report_url,
+            safe_mode: config.safe_mode,
+            restore_session: config.restore_session,
+            private: config.private,
+            software_rendering: config.software_rendering,
+            vsync: config.vsync,
+            rotation: config.rotation,
+            font_rendering: config.font_rendering,
+            cookies: config.cookies,
+            javascript: config.javascript,
+            images: config.images,
+            http_cache_size: config.http_cache_size,
+            partition_network: config.partition_network,
+            virtual_keyboard: config.virtual_keyboard,
+            ipc_socket: config.ipc_socket,
+            ipc_token: config.ipc_token,
+            ipc_rate_limit: config.ipc_rate_limit,
+            ipc_allow_remote: config.ipc_allow_remote,
+            ipc_websocket: config.ipc_websocket,
+            app: config.app,
+            settings,
+            profile_directory,
+        }
+    }
+
+    fn resource_reader(&self) -> ResourceReader {
+        ResourceReader {
+            resource_dir: self.resource_dir.clone(),
+            show_hidden_files: self.show_hidden_files,
+            certificate_errors_blocked: self.certificate_errors_blocked.clone(),
         }
     }
 
     /// Register URL scheme protocols
     pub fn create_protocols(&self) -> ProtocolRegistry {
-        let handler = ResourceReader(self.resource_dir.clone());
+        let handler = self.resource_reader();
         let mut protocols = ProtocolRegistry::with_internal_protocols();
         protocols.register("verso", handler);
         protocols
@@ -396,7 +1737,7 @@ impl Config {
     /// Init options and preferences.
     pub fn init(&self) {
         // Set the resource files of Servo.
-        resources::set(Box::new(ResourceReader(self.resource_dir.clone())));
+        resources::set(Box::new(self.resource_reader()));
 
         let mut opts = Opts::default();
 
@@ -404,6 +1745,20 @@ impl Config {
             opts.time_profiling = Some(profiler_settings.output_options.clone());
             opts.time_profiler_trace_path = profiler_settings.trace_path.clone();
         }
+        // Servo saves its cookie jar, HSTS list and HTTP auth cache in the profile when it shuts
+        // down, private windows keep them in memory
+        opts.config_dir = if self.private {
+            None
+        } else {
+            self.profile_directory
+                .clone()
+                .or_else(default_profile_directory)
+        };
+        opts.webdriver_port = self.webdriver_port;
+        opts.certificate_path = self
+            .certificate_bundle
+            .as_ref()
+            .map(|path| path.to_string_lossy().into_owned());
 
         // Set the global options of Servo.
         set_options(opts);
@@ -416,17 +1771,31 @@ impl Config {
             };
 
         // Set the preferences of Servo.
-        servo_config::prefs::set(Preferences {
+        let mut prefs = Preferences {
             dom_svg_enabled: true, // Some pages fail to render if this is disabled
             devtools_server_enabled,
             devtools_server_port: devtools_port as i64,
             dom_notification_enabled: true, // experimental feature
+            network_local_directory_listing_enabled: true,
             user_agent: self.user_agent.clone(),
             ..Default::default()
-        });
+        };
+        if self.safe_mode {
+            // Avoid the GPU features drivers are most likely to break on
+            prefs.gfx_precache_shaders = false;
+            prefs.gfx_texture_swizzling_enabled = false;
+            prefs.gfx_subpixel_text_antialiasing_enabled = false;
+        }
+        servo_config::prefs::set(prefs);
     }
 }
 
+fn load_config_file(path: &Path) -> std::io::Result<ConfigFromController> {
+    let file = fs::File::open(path)?;
+    let config = serde_json::from_reader(file)?;
+    Ok(config)
+}
+
 fn load_userscripts(
     userscripts_directory: Option<impl AsRef<Path>>,
 ) -> std::io::Result<Vec<UserScript>> {
@@ -446,35 +1815,67 @@ fn load_userscripts(
     Ok(userscripts)
 }
 
-struct ResourceReader(PathBuf);
+struct ResourceReader {
+    /// Path to resource directory
+    resource_dir: PathBuf,
+    /// Show hidden files in `file://` directory listings
+    show_hidden_files: bool,
+    /// Whether certificate errors are blocked, see [`Config::certificate_errors_blocked`]
+    certificate_errors_blocked: Arc<AtomicBool>,
+}
 
 impl ResourceReaderMethods for ResourceReader {
     fn read(&self, resource: Resource) -> Vec<u8> {
-        let path = self.0.join(resource.filename());
-        fs::read(&path).unwrap_or_else(|_| {
-            match resource {
-                // Rigppy image is the only one needs to be valid bytes.
-                // Others can be empty and Servo will set to default.
-                Resource::RippyPNG => &include_bytes!("../resources/rippy.png")[..],
-                #[cfg(feature = "embed-useragent-stylesheets")]
-                Resource::UserAgentCSS => &include_bytes!("../resources/user-agent.css")[..],
-                #[cfg(feature = "embed-useragent-stylesheets")]
-                Resource::ServoCSS => &include_bytes!("../resources/servo.css")[..],
-                #[cfg(feature = "embed-useragent-stylesheets")]
-                Resource::PresentationalHintsCSS => {
-                    &include_bytes!("../resources/presentational-hints.css")[..]
-                }
-                Resource::HstsPreloadList => {
-                    log::warn!(
-                        "HSTS preload list not found, falling back to an empty list, to set this, put the list at '{}'",
-                        path.display()
-                    );
-                    r###"{ "entries": [] }"###.as_bytes()
+        let path = self.resource_dir.join(resource.filename());
+        let is_directory_listing = matches!(resource, Resource::DirectoryListingHTML);
+        let bytes = fs::read(&path)
+            .ok()
+            .or_else(|| embedded_resources::get(resource.filename()).map(<[u8]>::to_vec))
+            .unwrap_or_else(|| {
+                match resource {
+                    // Rigppy image is the only one needs to be valid bytes.
+                    // Others can be empty and Servo will set to default.
+                    Resource::RippyPNG => &include_bytes!("../resources/rippy.png")[..],
+                    #[cfg(feature = "embed-useragent-stylesheets")]
+                    Resource::UserAgentCSS => &include_bytes!("../resources/user-agent.css")[..],
+                    #[cfg(feature = "embed-useragent-stylesheets")]
+                    Resource::ServoCSS => &include_bytes!("../resources/servo.css")[..],
+                    #[cfg(feature = "embed-useragent-stylesheets")]
+                    Resource::PresentationalHintsCSS => {
+                        &include_bytes!("../resources/presentational-hints.css")[..]
+                    }
+                    Resource::HstsPreloadList => {
+                        log::warn!(
+                            "HSTS preload list not found, falling back to an empty list, to set this, put the list at '{}'",
+                            path.display()
+                        );
+                        r###"{ "entries": [] }"###.as_bytes()
+                    }
+                    _ => &[],
                 }
-                _ => &[],
-            }
-            .to_vec()
-        })
+                .to_vec()
+            });
+
+        if matches!(resource, Resource::BadCertHTML)
+            && self.certificate_errors_blocked.load(Ordering::Relaxed)
+        {
+            // Servo only allows the certificate if the page sends back the secret it fills in,
+            // Verso hands it to the page itself for the hosts the controller allowed
+            String::from_utf8_lossy(&bytes)
+                .replace("${secret}", "")
+                .into_bytes()
+        } else if is_directory_listing && self.show_hidden_files {
+            // The listing page reads this attribute to decide whether to filter dotfiles
+            String::from_utf8_lossy(&bytes)
+                .replacen(
+                    r#"data-show-hidden-files="false""#,
+                    r#"data-show-hidden-files="true""#,
+                    1,
+                )
+                .into_bytes()
+        } else {
+            bytes
+        }
     }
 
     fn sandbox_access_files(&self) -> Vec<PathBuf> {
@@ -495,9 +1896,18 @@ impl ProtocolHandler for ResourceReader {
     ) -> std::pin::Pin<Box<dyn std::future::Future<Output = Response> + Send>> {
         let current_url = request.current_url();
         let path = current_url.path();
-        let path = self.0.join(path.strip_prefix('/').unwrap_or(path));
+        // Internal pages with their own host, e.g. `verso://crashes`
+        let relative_path = match current_url.host_str() {
+            Some("crashes") => "components/crashes.html",
+            Some("site-settings") => "components/site_settings.html",
+            _ => path.strip_prefix('/').unwrap_or(path),
+        };
+        let path = self.resource_dir.join(relative_path);
 
-        let response = if let Ok(file) = fs::read(path.clone()) {
+        let file = fs::read(&path)
+            .ok()
+            .or_else(|| embedded_resources::get(relative_path).map(<[u8]>::to_vec));
+        let response = if let Some(file) = file {
             let mut response = Response::new(
                 request.current_url(),
                 ResourceFetchTiming::new(request.timing_type()),
@@ -0,0 +1,1054 @@
+use std::{
+    collections::HashMap,
+    io,
+    net::{SocketAddr, TcpStream},
+    path::{Path, PathBuf},
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use base::id::WebViewId;
+use embedder_traits::{EmbedderMsg, LoadStatus};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader},
+    sync::mpsc::{UnboundedSender, unbounded_channel},
+};
+use tungstenite::{
+    Message,
+    handshake::server::{ErrorResponse, Request, Response},
+    http::StatusCode,
+};
+use versoview_messages::{
+    CONTROL_CAPABILITIES, CONTROL_PROTOCOL_VERSION, ControlCommand, ControlErrorCode, ControlEvent,
+    ControlHello, ControlMessage, ControlOutcome, ControlRequest, ControlResponse,
+    MAX_CONTROL_REQUEST_LENGTH,
+};
+use winit::event_loop::EventLoopProxy;
+
+use crate::{devtools_bridge::target_id, verso::EventLoopProxyMessage};
+
+/// How long a WebSocket client may take to send its handshake.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+/// How often a WebSocket connection stops waiting for requests to send the responses.
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Who may use the control server, and how much.
+#[derive(Debug, Clone)]
+pub(crate) struct ControlAccess {
+    /// Token clients send with [`ControlCommand::Hello`] before any other request, anyone able to
+    /// connect may send requests if `None`
+    pub token: Option<String>,
+    /// Requests per second each client may send, 0 for no limit
+    pub rate_limit: u32,
+    /// Whether TCP addresses other than loopback may be listened on
+    pub allow_remote: bool,
+}
+
+/// ID of the next client to connect, over any transport.
+static NEXT_CLIENT_ID: AtomicU64 = AtomicU64::new(1);
+
+/// A connected client of the control server, and the sender of its connection to answer it.
+#[derive(Debug, Clone)]
+pub struct ControlClient {
+    /// ID of the client, unique for the whole run
+    pub id: u64,
+    /// Sender of the responses and events to write to the connection
+    pub sender: UnboundedSender<ControlMessage>,
+}
+
+impl ControlClient {
+    fn new(sender: UnboundedSender<ControlMessage>) -> Self {
+        Self {
+            id: NEXT_CLIENT_ID.fetch_add(1, Ordering::Relaxed),
+            sender,
+        }
+    }
+
+    /// Answer a request of the client.
+    pub fn respond(&self, id: u64, outcome: ControlOutcome) {
+        let _ = self.sender.send(ControlResponse { id, outcome }.into());
+    }
+}
+
+/// Serve the control protocol on the unix socket, named pipe or TCP address. Every request is
+/// handled by the event loop, sent to it with [`EventLoopProxyMessage::ControlRequest`].
+pub(crate) fn listen(
+    path: PathBuf,
+    access: ControlAccess,
+    proxy: EventLoopProxy<EventLoopProxyMessage>,
+) {
+    let access = Arc::new(access);
+    tokio::spawn(async move {
+        let result = match path
+            .to_str()
+            .and_then(|path| path.parse::<SocketAddr>().ok())
+        {
+            Some(address) => serve_tcp(address, access, proxy).await,
+            None => serve(&path, access, proxy).await,
+        };
+        if let Err(e) = result {
+            log::error!(
+                "Failed to serve the control protocol on {}: {e}",
+                path.display()
+            );
+        }
+    });
+}
+
+/// Serve the control protocol over WebSocket on the TCP address, a request or response per
+/// message, for clients which can't open a unix socket, e.g. dashboards running in a browser and
+/// Node tooling. Clients connecting from a web page need the token, since any page could connect
+/// to a loopback port.
+pub(crate) fn listen_websocket(
+    address: SocketAddr,
+    access: ControlAccess,
+    proxy: EventLoopProxy<EventLoopProxyMessage>,
+) {
+    let listener =
+        check_address(address, &access).and_then(|_| std::net::TcpListener::bind(address));
+    let listener = match listener {
+        Ok(listener) => listener,
+        Err(e) => {
+            log::error!("Failed to serve the control protocol over WebSocket on {address}: {e}");
+            return;
+        }
+    };
+    let access = Arc::new(access);
+    let result = thread::Builder::new()
+        .name("ControlWebSocket".to_owned())
+        .spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let access = access.clone();
+                let proxy = proxy.clone();
+                let result = thread::Builder::new()
+                    .name("ControlWebSocketConnection".to_owned())
+                    .spawn(move || {
+                        if let Err(e) = handle_websocket(stream, access, proxy) {
+                            log::debug!("Control WebSocket connection failed: {e}");
+                        }
+                    });
+                if let Err(e) = result {
+                    log::error!("Failed to spawn control WebSocket connection thread: {e}");
+                }
+            }
+        });
+    if let Err(e) = result {
+        log::error!("Failed to spawn control WebSocket server thread: {e}");
+        return;
+    }
+    log::info!("Control protocol served over WebSocket on {address}");
+}
+
+/// Refuse to listen on addresses other than loopback unless remote clients are allowed, anyone
+/// on the network could drive the browser otherwise.
+fn check_address(address: SocketAddr, access: &ControlAccess) -> io::Result<()> {
+    if address.ip().is_loopback() {
+        return Ok(());
+    }
+    if !access.allow_remote {
+        return Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            "only loopback addresses are listened on without --ipc-allow-remote",
+        ));
+    }
+    if access.token.is_none() {
+        return Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            "remote clients need --ipc-token",
+        ));
+    }
+    Ok(())
+}
+
+async fn serve_tcp(
+    address: SocketAddr,
+    access: Arc<ControlAccess>,
+    proxy: EventLoopProxy<EventLoopProxyMessage>,
+) -> std::io::Result<()> {
+    check_address(address, &access)?;
+    let listener = tokio::net::TcpListener::bind(address).await?;
+    loop {
+        let (stream, peer) = listener.accept().await?;
+        log::debug!("Control client connected from {peer}");
+        tokio::spawn(handle_connection(stream, access.clone(), proxy.clone()));
+    }
+}
+
+#[cfg(unix)]
+async fn serve(
+    path: &Path,
+    access: Arc<ControlAccess>,
+    proxy: EventLoopProxy<EventLoopProxyMessage>,
+) -> std::io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    // The socket of a previous instance which didn't shut down cleanly makes binding fail
+    let _ = std::fs::remove_file(path);
+    let listener = tokio::net::UnixListener::bind(path)?;
+    // Only the user running Verso may connect
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+    loop {
+        let (stream, _) = listener.accept().await?;
+        tokio::spawn(handle_connection(stream, access.clone(), proxy.clone()));
+    }
+}
+
+#[cfg(windows)]
+async fn serve(
+    path: &Path,
+    access: Arc<ControlAccess>,
+    proxy: EventLoopProxy<EventLoopProxyMessage>,
+) -> std::io::Result<()> {
+    use tokio::net::windows::named_pipe::ServerOptions;
+
+    let mut server = ServerOptions::new()
+        .first_pipe_instance(true)
+        .reject_remote_clients(true)
+        .create(path)?;
+    loop {
+        server.connect().await?;
+        // Create the next instance before handing this one to its client, so there is always an
+        // instance to connect to
+        let connected = std::mem::replace(
+            &mut server,
+            ServerOptions::new()
+                .reject_remote_clients(true)
+                .create(path)?,
+        );
+        tokio::spawn(handle_connection(connected, access.clone(), proxy.clone()));
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+async fn serve(
+    _path: &Path,
+    _access: Arc<ControlAccess>,
+    _proxy: EventLoopProxy<EventLoopProxyMessage>,
+) -> std::io::Result<()> {
+    Err(std::io::ErrorKind::Unsupported.into())
+}
+
+/// Parse a line of the control protocol. Any input is answered, with the error to send back if it
+/// isn't a valid request, so it's safe to feed untrusted bytes, e.g. from a fuzzer.
+pub fn parse_request(line: &[u8]) -> Result<ControlRequest, ControlResponse> {
+    let error = |id, code, message: String| ControlResponse {
+        id,
+        outcome: ControlOutcome::error(code, message),
+    };
+    if line.len() > MAX_CONTROL_REQUEST_LENGTH {
+        return Err(error(
+            0,
+            ControlErrorCode::TooLong,
+            format!("Requests are limited to {MAX_CONTROL_REQUEST_LENGTH} bytes"),
+        ));
+    }
+    let value = match serde_json::from_slice::<serde_json::Value>(line) {
+        Ok(serde_json::Value::Object(value)) => value,
+        Ok(_) => {
+            return Err(error(
+                0,
+                ControlErrorCode::InvalidJson,
+                "The request isn't a JSON object".to_string(),
+            ));
+        }
+        Err(e) => return Err(error(0, ControlErrorCode::InvalidJson, e.to_string())),
+    };
+    // Errors are answered with the ID of the request as soon as it can be read
+    let id = value
+        .get("id")
+        .and_then(serde_json::Value::as_u64)
+        .unwrap_or_default();
+    match value.get("version") {
+        None | Some(serde_json::Value::Null) => {}
+        Some(version) => match version.as_u64() {
+            Some(version) if (1..=CONTROL_PROTOCOL_VERSION as u64).contains(&version) => {}
+            _ => {
+                return Err(error(
+                    id,
+                    ControlErrorCode::UnsupportedVersion,
+                    format!(
+                        "Version {version} isn't supported, the latest version is {CONTROL_PROTOCOL_VERSION}"
+                    ),
+                ));
+            }
+        },
+    }
+    match value.get("type").and_then(serde_json::Value::as_str) {
+        Some(command) if CONTROL_CAPABILITIES.contains(&command) => {}
+        Some(command) => {
+            return Err(error(
+                id,
+                ControlErrorCode::UnknownCommand,
+                format!("Unknown command {command}"),
+            ));
+        }
+        None => {
+            return Err(error(
+                id,
+                ControlErrorCode::UnknownCommand,
+                "The request has no type".to_string(),
+            ));
+        }
+    }
+    serde_json::from_value(serde_json::Value::Object(value))
+        .map_err(|e| error(id, ControlErrorCode::InvalidRequest, e.to_string()))
+}
+
+/// The protocol agreed on with the client supporting versions up to `client_version`.
+pub(crate) fn hello(client_version: u32, client: u64) -> ControlOutcome {
+    if client_version == 0 {
+        return ControlOutcome::error(ControlErrorCode::UnsupportedVersion, "Versions start at 1");
+    }
+    ControlOutcome::Hello(ControlHello {
+        version: client_version.min(CONTROL_PROTOCOL_VERSION),
+        capabilities: CONTROL_CAPABILITIES
+            .iter()
+            .map(|command| command.to_string())
+            .collect(),
+        client,
+    })
+}
+
+/// A client known to the event loop.
+struct ClientState {
+    sender: UnboundedSender<ControlMessage>,
+    subscribed: bool,
+}
+
+/// A lease of a client on a tab.
+struct Lease {
+    owner: u64,
+    expires: Instant,
+}
+
+/// The clients of the control server seen by the event loop, the leases they hold on tabs, and
+/// the events they subscribed to. Expired leases are ignored rather than removed, and nobody is
+/// told when they expire.
+#[derive(Default)]
+pub(crate) struct ControlClients {
+    clients: HashMap<u64, ClientState>,
+    leases: HashMap<WebViewId, Lease>,
+}
+
+impl ControlClients {
+    /// Remember the client, the first time one of its requests is handled.
+    pub fn register(&mut self, client: &ControlClient) {
+        self.clients
+            .entry(client.id)
+            .or_insert_with(|| ClientState {
+                sender: client.sender.clone(),
+                subscribed: false,
+            });
+    }
+
+    /// Forget the disconnected client and release its leases.
+    pub fn remove(&mut self, client: u64) {
+        self.clients.remove(&client);
+        let released: Vec<WebViewId> = self
+            .leases
+            .iter()
+            .filter(|(_, lease)| lease.owner == client)
+            .map(|(webview_id, _)| *webview_id)
+            .collect();
+        for webview_id in released {
+            self.leases.remove(&webview_id);
+            self.broadcast(ControlEvent::LeaseChanged {
+                webview: target_id(webview_id),
+                owner: None,
+            });
+        }
+    }
+
+    /// Start or stop sending the events to the client.
+    pub fn set_subscribed(&mut self, client: u64, subscribed: bool) {
+        if let Some(state) = self.clients.get_mut(&client) {
+            state.subscribed = subscribed;
+        }
+    }
+
+    /// Client holding an unexpired lease on the tab at `now`.
+    pub fn owner(&self, webview_id: WebViewId, now: Instant) -> Option<u64> {
+        self.leases
+            .get(&webview_id)
+            .filter(|lease| lease.expires > now)
+            .map(|lease| lease.owner)
+    }
+
+    /// Lease the tab to the client for `ttl`, or renew its lease.
+    pub fn acquire(
+        &mut self,
+        client: u64,
+        webview_id: WebViewId,
+        ttl: Duration,
+        now: Instant,
+    ) -> Result<(), ControlOutcome> {
+        let owner = self.owner(webview_id, now);
+        if owner.is_some_and(|owner| owner != client) {
+            return Err(lease_held());
+        }
+        self.leases.insert(
+            webview_id,
+            Lease {
+                owner: client,
+                expires: now + ttl,
+            },
+        );
+        if owner.is_none() {
+            self.broadcast(ControlEvent::LeaseChanged {
+                webview: target_id(webview_id),
+                owner: Some(client),
+            });
+        }
+        Ok(())
+    }
+
+    /// Release the lease of the client on the tab, if it holds one.
+    pub fn release(
+        &mut self,
+        client: u64,
+        webview_id: WebViewId,
+        now: Instant,
+    ) -> Result<(), ControlOutcome> {
+        match self.owner(webview_id, now) {
+            Some(owner) if owner != client => Err(lease_held()),
+            owner => {
+                self.leases.remove(&webview_id);
+                if owner.is_some() {
+                    self.broadcast(ControlEvent::LeaseChanged {
+                        webview: target_id(webview_id),
+                        owner: None,
+                    });
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Whether the client may change the tab, or the whole browser if `None`, which no other
+    /// client may hold a lease in.
+    pub fn check(
+        &self,
+        client: u64,
+        webview_id: Option<WebViewId>,
+        now: Instant,
+    ) -> Result<(), ControlOutcome> {
+        let held = match webview_id {
+            Some(webview_id) => self
+                .owner(webview_id, now)
+                .is_some_and(|owner| owner != client),
+            None => self
+                .leases
+                .values()
+                .any(|lease| lease.owner != client && lease.expires > now),
+        };
+        if held {
+            return Err(lease_held());
+        }
+        Ok(())
+    }
+
+    /// Send the event to the subscribed clients.
+    pub fn broadcast(&self, event: ControlEvent) {
+        for state in self.clients.values().filter(|state| state.subscribed) {
+            let _ = state.sender.send(event.clone().into());
+        }
+    }
+
+    /// Send the events of the embedder message of a tab to the subscribed clients.
+    pub fn observe(&mut self, message: &EmbedderMsg) {
+        let event = match message {
+            EmbedderMsg::HistoryChanged(webview_id, list, index) => {
+                let Some(url) = list.get(*index) else {
+                    return;
+                };
+                ControlEvent::UrlChanged {
+                    webview: target_id(*webview_id),
+                    url: url.to_string(),
+                }
+            }
+            EmbedderMsg::ChangePageTitle(webview_id, title) => ControlEvent::TitleChanged {
+                webview: target_id(*webview_id),
+                title: title.clone(),
+            },
+            EmbedderMsg::NotifyLoadStatusChanged(webview_id, LoadStatus::Complete) => {
+                ControlEvent::LoadComplete {
+                    webview: target_id(*webview_id),
+                }
+            }
+            EmbedderMsg::WebViewClosed(webview_id) => {
+                self.leases.remove(webview_id);
+                ControlEvent::WebViewClosed {
+                    webview: target_id(*webview_id),
+                }
+            }
+            _ => return,
+        };
+        self.broadcast(event);
+    }
+}
+
+fn lease_held() -> ControlOutcome {
+    ControlOutcome::error(
+        ControlErrorCode::LeaseHeld,
+        "Another client holds a lease on the tab",
+    )
+}
+
+/// Whether the token sent by a client is the one of the control server. It takes as long
+/// wherever the tokens differ, so the token can't be guessed byte by byte from the timing.
+fn token_matches(expected: &str, sent: Option<&str>) -> bool {
+    let Some(sent) = sent else {
+        return false;
+    };
+    let (expected, sent) = (expected.as_bytes(), sent.as_bytes());
+    let difference = expected
+        .iter()
+        .zip(sent)
+        .fold(0, |difference, (a, b)| difference | (a ^ b));
+    difference == 0 && expected.len() == sent.len()
+}
+
+/// Token bucket limiting the requests of a client, allowing bursts of a second's worth.
+struct RateLimiter {
+    requests_per_second: f64,
+    available: f64,
+    last_refill: Instant,
+}
+
+impl RateLimiter {
+    /// Create a limiter of the requests per second, `None` if they aren't limited.
+    fn new(requests_per_second: u32) -> Option<Self> {
+        (requests_per_second > 0).then(|| Self {
+            requests_per_second: requests_per_second as f64,
+            available: requests_per_second as f64,
+            last_refill: Instant::now(),
+        })
+    }
+
+    /// Whether a request arriving at `now` is allowed, using up its share of the limit.
+    fn allow(&mut self, now: Instant) -> bool {
+        let elapsed = now
+            .saturating_duration_since(self.last_refill)
+            .as_secs_f64();
+        self.available =
+            (self.available + elapsed * self.requests_per_second).min(self.requests_per_second);
+        self.last_refill = now;
+        if self.available < 1.0 {
+            return false;
+        }
+        self.available -= 1.0;
+        true
+    }
+}
+
+/// What to do with a request received from a client.
+enum Received {
+    /// Nothing, the request is blank
+    Nothing,
+    /// Send the request to the event loop
+    Request(ControlRequest),
+    /// Answer the client right away
+    Answer(ControlResponse),
+    /// Answer the client and close the connection
+    Close(ControlResponse),
+}
+
+/// A client of the control server, whichever transport it's connected with. Requests are parsed,
+/// rate limited and refused until the client sends the token here, before the event loop sees
+/// them.
+struct ControlSession {
+    client: ControlClient,
+    access: Arc<ControlAccess>,
+    authenticated: bool,
+    rate_limiter: Option<RateLimiter>,
+    /// Whether the connection is closed on the first request which doesn't parse, see
+    /// [`handle_connection`].
+    close_on_invalid: bool,
+}
+
+impl ControlSession {
+    fn new(
+        access: Arc<ControlAccess>,
+        sender: UnboundedSender<ControlMessage>,
+        close_on_invalid: bool,
+    ) -> Self {
+        Self {
+            client: ControlClient::new(sender),
+            authenticated: access.token.is_none(),
+            rate_limiter: RateLimiter::new(access.rate_limit),
+            access,
+            close_on_invalid,
+        }
+    }
+
+    /// Send the request to the event loop, false if it has exited.
+    fn forward(
+        &self,
+        request: ControlRequest,
+        proxy: &EventLoopProxy<EventLoopProxyMessage>,
+    ) -> bool {
+        proxy
+            .send_event(EventLoopProxyMessage::ControlRequest(
+                request,
+                self.client.clone(),
+            ))
+            .is_ok()
+    }
+
+    /// Tell the event loop the client disconnected, so its leases are released.
+    fn close(self, proxy: &EventLoopProxy<EventLoopProxyMessage>) {
+        let _ = proxy.send_event(EventLoopProxyMessage::ControlClientClosed(self.client.id));
+    }
+
+    /// Check a request of the client, a line of the socket or a WebSocket message.
+    fn receive(&mut self, line: &[u8]) -> Received {
+        if line.iter().all(u8::is_ascii_whitespace) {
+            return Received::Nothing;
+        }
+        let request = parse_request(line);
+        if self
+            .rate_limiter
+            .as_mut()
+            .is_some_and(|rate_limiter| !rate_limiter.allow(Instant::now()))
+        {
+            let id = match &request {
+                Ok(request) => request.id,
+                Err(response) => response.id,
+            };
+            return Received::Answer(ControlResponse {
+                id,
+                outcome: ControlOutcome::error(
+                    ControlErrorCode::RateLimited,
+                    format!(
+                        "Clients are limited to {} requests per second",
+                        self.access.rate_limit
+                    ),
+                ),
+            });
+        }
+        let request = match request {
+            Ok(request) => request,
+            Err(response) if self.close_on_invalid => return Received::Close(response),
+            Err(response) => return Received::Answer(response),
+        };
+        if !self.authenticated {
+            let unauthorized = |message: &str| ControlResponse {
+                id: request.id,
+                outcome: ControlOutcome::error(ControlErrorCode::Unauthorized, message),
+            };
+            match &request.command {
+                ControlCommand::Hello { token, .. }
+                    if self
+                        .access
+                        .token
+                        .as_deref()
+                        .is_some_and(|expected| token_matches(expected, token.as_deref())) =>
+                {
+                    self.authenticated = true;
+                }
+                // Clients guessing the token have to reconnect for every guess
+                ControlCommand::Hello { .. } => {
+                    return Received::Close(unauthorized("Wrong token"));
+                }
+                _ => return Received::Answer(unauthorized("Send hello with the token first")),
+            }
+        }
+        Received::Request(request)
+    }
+}
+
+/// Whether the line looks like the request line or a header of an HTTP request, which web pages
+/// can send to loopback addresses.
+fn looks_like_http(line: &[u8]) -> bool {
+    const METHODS: [&[u8]; 9] = [
+        b"GET ",
+        b"POST ",
+        b"PUT ",
+        b"HEAD ",
+        b"DELETE ",
+        b"OPTIONS ",
+        b"PATCH ",
+        b"CONNECT ",
+        b"TRACE ",
+    ];
+    if METHODS.iter().any(|method| line.starts_with(method)) {
+        return true;
+    }
+    // Requests are JSON objects, header names are tokens followed by a colon
+    let name_end = line.iter().position(|byte| *byte == b':');
+    name_end.is_some_and(|end| {
+        end > 0
+            && line[..end]
+                .iter()
+                .all(|byte| byte.is_ascii_alphanumeric() || *byte == b'-')
+    })
+}
+
+/// Read the requests of a client line by line, and write the responses of the event loop back
+/// as they come.
+///
+/// Web pages can send HTTP requests to TCP addresses, whose body could hold a request of the
+/// control protocol, so the connection is closed on the first line which isn't a request. HTTP
+/// request lines and headers aren't answered.
+async fn handle_connection(
+    stream: impl AsyncRead + AsyncWrite + Send + 'static,
+    access: Arc<ControlAccess>,
+    proxy: EventLoopProxy<EventLoopProxyMessage>,
+) {
+    let (reader, mut writer) = tokio::io::split(stream);
+    let (response_sender, mut response_receiver) = unbounded_channel::<ControlMessage>();
+    let writer_task = tokio::spawn(async move {
+        while let Some(message) = response_receiver.recv().await {
+            let mut line = serde_json::to_string(&message).unwrap();
+            line.push('\n');
+            if writer.write_all(line.as_bytes()).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    let mut session = ControlSession::new(access, response_sender.clone(), true);
+    let mut reader = BufReader::new(reader);
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        // A line is read up to one byte past the limit, to tell a request of the maximum length
+        // from a longer one without buffering it
+        match (&mut reader)
+            .take(MAX_CONTROL_REQUEST_LENGTH as u64 + 1)
+            .read_until(b'\n', &mut line)
+            .await
+        {
+            Ok(0) | Err(_) => break,
+            Ok(_) => {}
+        }
+        if line.last() == Some(&b'\n') {
+            line.pop();
+        } else if line.len() > MAX_CONTROL_REQUEST_LENGTH {
+            // The rest of the line can't be told apart from the next request
+            if let Err(response) = parse_request(&line) {
+                let _ = response_sender.send(response.into());
+            }
+            break;
+        }
+        if looks_like_http(&line) {
+            log::warn!("Control client closed for sending an HTTP request");
+            break;
+        }
+        match session.receive(&line) {
+            Received::Nothing => {}
+            Received::Answer(response) => {
+                let _ = response_sender.send(response.into());
+            }
+            Received::Close(response) => {
+                let _ = response_sender.send(response.into());
+                break;
+            }
+            Received::Request(request) => {
+                if !session.forward(request, &proxy) {
+                    break;
+                }
+            }
+        }
+    }
+
+    // The writer finishes once the event loop has answered the pending requests and forgotten
+    // the client
+    session.close(&proxy);
+    drop(response_sender);
+    let _ = writer_task.await;
+}
+
+/// Read the requests of a WebSocket client, a request per text or binary message, and send the
+/// responses of the event loop back in between.
+fn handle_websocket(
+    stream: TcpStream,
+    access: Arc<ControlAccess>,
+    proxy: EventLoopProxy<EventLoopProxyMessage>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    stream.set_read_timeout(Some(HANDSHAKE_TIMEOUT))?;
+    let has_token = access.token.is_some();
+    let mut websocket = tungstenite::accept_hdr(
+        stream,
+        |request: &Request, response: Response| -> Result<Response, ErrorResponse> {
+            // Browsers send the origin of the page opening the WebSocket
+            if !has_token && request.headers().contains_key("origin") {
+                let mut error = ErrorResponse::new(Some(
+                    "Web pages need --ipc-token to use the control protocol".to_string(),
+                ));
+                *error.status_mut() = StatusCode::FORBIDDEN;
+                return Err(error);
+            }
+            Ok(response)
+        },
+    )?;
+    websocket.get_ref().set_read_timeout(Some(POLL_INTERVAL))?;
+
+    let (response_sender, mut response_receiver) = unbounded_channel::<ControlMessage>();
+    let send = |websocket: &mut tungstenite::WebSocket<TcpStream>, message: &ControlMessage| {
+        websocket.send(Message::Text(serde_json::to_string(message).unwrap()))
+    };
+    // Web pages need the token or are refused in the handshake, invalid messages can't be sent
+    // by other protocols
+    let mut session = ControlSession::new(access, response_sender.clone(), false);
+    'connection: loop {
+        match websocket.read() {
+            Ok(message @ (Message::Text(_) | Message::Binary(_))) => {
+                match session.receive(&message.into_data()) {
+                    Received::Nothing => {}
+                    Received::Answer(response) => {
+                        let _ = response_sender.send(response.into());
+                    }
+                    Received::Close(response) => {
+                        let _ = send(&mut websocket, &response.into());
+                        let _ = websocket.close(None);
+                        break;
+                    }
+                    Received::Request(request) => {
+                        if !session.forward(request, &proxy) {
+                            break;
+                        }
+                    }
+                }
+            }
+            Ok(Message::Close(_)) => break,
+            Ok(_) => {}
+            Err(tungstenite::Error::Io(e))
+                if matches!(
+                    e.kind(),
+                    io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                ) => {}
+            Err(_) => break,
+        }
+        while let Ok(message) = response_receiver.try_recv() {
+            if send(&mut websocket, &message).is_err() {
+                break 'connection;
+            }
+        }
+    }
+    session.close(&proxy);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use versoview_messages::{BlockedReason, BlockedSubresource, CspViolation};
+
+    fn error_code(line: &[u8]) -> Option<ControlErrorCode> {
+        match parse_request(line) {
+            Err(ControlResponse {
+                outcome: ControlOutcome::Error(error),
+                ..
+            }) => Some(error.code),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_parse_request() {
+        let request =
+            parse_request(br#"{"id": 7, "type": "navigate", "url": "https://example.com"}"#)
+                .unwrap();
+        assert_eq!(request.id, 7);
+        assert!(matches!(
+            request.command,
+            ControlCommand::Navigate { url } if url.as_str() == "https://example.com/"
+        ));
+        let request = parse_request(br#"{"type": "getTitle", "version": 1}"#).unwrap();
+        assert_eq!(request.version, Some(1));
+        assert!(matches!(request.command, ControlCommand::GetTitle));
+        let request =
+            parse_request(br##"{"type": "setValue", "selector": "#name", "value": "Verso"}"##)
+                .unwrap();
+        assert!(matches!(
+            request.command,
+            ControlCommand::SetValue { selector, value } if selector == "#name" && value == "Verso"
+        ));
+    }
+
+    #[test]
+    fn test_parse_request_errors() {
+        let too_long = vec![b' '; MAX_CONTROL_REQUEST_LENGTH + 1];
+        assert_eq!(error_code(&too_long), Some(ControlErrorCode::TooLong));
+        assert_eq!(error_code(b"{"), Some(ControlErrorCode::InvalidJson));
+        assert_eq!(error_code(b"[1, 2]"), Some(ControlErrorCode::InvalidJson));
+        assert_eq!(error_code(b"\xff\xfe"), Some(ControlErrorCode::InvalidJson));
+        assert_eq!(
+            error_code(br#"{"id": 1, "type": "launchMissiles"}"#),
+            Some(ControlErrorCode::UnknownCommand)
+        );
+        assert_eq!(
+            error_code(br#"{"id": 1, "type": "reload", "version": 99}"#),
+            Some(ControlErrorCode::UnsupportedVersion)
+        );
+        assert_eq!(
+            error_code(br#"{"id": 1, "type": "navigate", "url": "not a url"}"#),
+            Some(ControlErrorCode::InvalidRequest)
+        );
+        assert_eq!(
+            error_code(br#"{"id": 1, "type": "resize", "width": -1, "height": 1}"#),
+            Some(ControlErrorCode::InvalidRequest)
+        );
+        assert_eq!(
+            error_code(br#"{"id": 1, "type": "setValue", "selector": "input"}"#),
+            Some(ControlErrorCode::InvalidRequest)
+        );
+        // The ID of the request is kept in the error
+        let Err(response) = parse_request(br#"{"id": 5, "type": "click"}"#) else {
+            panic!("A click without a position shouldn't parse");
+        };
+        assert_eq!(response.id, 5);
+    }
+
+    #[test]
+    fn test_parse_truncated_requests() {
+        let line = br#"{"id": 1, "version": 1, "type": "text", "text": "\u00e9\ud83d\ude00"}"#;
+        for end in 0..line.len() {
+            assert!(parse_request(&line[..end]).is_err());
+        }
+        assert!(parse_request(line).is_ok());
+    }
+
+    #[test]
+    fn test_looks_like_http() {
+        assert!(looks_like_http(b"POST / HTTP/1.1\r"));
+        assert!(looks_like_http(b"GET /json HTTP/1.1\r"));
+        assert!(looks_like_http(b"Host: 127.0.0.1:4000\r"));
+        assert!(looks_like_http(b"content-type: text/plain"));
+        assert!(!looks_like_http(
+            br#"{"id": 1, "type": "hello", "version": 1}"#
+        ));
+        assert!(!looks_like_http(b""));
+        assert!(!looks_like_http(b":"));
+    }
+
+    #[test]
+    fn test_hello() {
+        assert!(matches!(
+            hello(CONTROL_PROTOCOL_VERSION + 1, 3),
+            ControlOutcome::Hello(ControlHello { version, client: 3, .. }) if version == CONTROL_PROTOCOL_VERSION
+        ));
+        assert!(matches!(hello(0, 3), ControlOutcome::Error(_)));
+    }
+
+    #[test]
+    fn test_token_matches() {
+        assert!(token_matches("secret", Some("secret")));
+        assert!(!token_matches("secret", Some("secreT")));
+        assert!(!token_matches("secret", Some("secret2")));
+        assert!(!token_matches("secret", Some("")));
+        assert!(!token_matches("secret", None));
+        assert!(matches!(
+            parse_request(br#"{"type": "hello", "version": 1, "token": "secret"}"#)
+                .unwrap()
+                .command,
+            ControlCommand::Hello { token: Some(token), .. } if token == "secret"
+        ));
+    }
+
+    #[test]
+    fn test_rate_limiter() {
+        assert!(RateLimiter::new(0).is_none());
+        let mut rate_limiter = RateLimiter::new(2).unwrap();
+        let start = rate_limiter.last_refill;
+        assert!(rate_limiter.allow(start));
+        assert!(rate_limiter.allow(start));
+        assert!(!rate_limiter.allow(start));
+        assert!(rate_limiter.allow(start + Duration::from_millis(500)));
+        assert!(!rate_limiter.allow(start + Duration::from_millis(500)));
+        // Idle time doesn't add up to more than a second's worth of requests
+        let later = start + Duration::from_secs(60);
+        assert!(rate_limiter.allow(later));
+        assert!(rate_limiter.allow(later));
+        assert!(!rate_limiter.allow(later));
+    }
+
+    #[test]
+    fn test_leases() {
+        base::id::PipelineNamespace::install(base::id::PipelineNamespaceId(1));
+        let (tab, other_tab) = (WebViewId::new(), WebViewId::new());
+        let (sender, mut receiver) = unbounded_channel();
+        let dashboard = ControlClient::new(sender);
+        let (sender, _receiver) = unbounded_channel();
+        let script = ControlClient::new(sender);
+        let mut clients = ControlClients::default();
+        clients.register(&dashboard);
+        clients.register(&script);
+        clients.set_subscribed(dashboard.id, true);
+
+        let now = Instant::now();
+        let ttl = Duration::from_secs(30);
+        assert!(clients.acquire(script.id, tab, ttl, now).is_ok());
+        assert!(matches!(
+            receiver.try_recv(),
+            Ok(ControlMessage::Event(ControlEvent::LeaseChanged { owner: Some(owner), .. }))
+                if owner == script.id
+        ));
+        assert!(clients.acquire(dashboard.id, tab, ttl, now).is_err());
+        assert!(clients.check(dashboard.id, Some(tab), now).is_err());
+        assert!(clients.check(dashboard.id, Some(other_tab), now).is_ok());
+        assert!(clients.check(dashboard.id, None, now).is_err());
+        assert!(clients.check(script.id, Some(tab), now).is_ok());
+        assert!(clients.release(dashboard.id, tab, now).is_err());
+        // Expired leases may be taken over
+        assert!(clients.check(dashboard.id, Some(tab), now + ttl).is_ok());
+        assert!(clients.acquire(script.id, tab, ttl, now).is_ok());
+
+        // Disconnecting releases the leases of the client
+        clients.remove(script.id);
+        assert_eq!(clients.owner(tab, now), None);
+        assert!(matches!(
+            receiver.try_recv(),
+            Ok(ControlMessage::Event(ControlEvent::LeaseChanged {
+                owner: None,
+                ..
+            }))
+        ));
+        assert!(receiver.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_csp_violation_event() {
+        let event = ControlEvent::CspViolation {
+            webview: "tab".to_string(),
+            violation: CspViolation {
+                document_url: "https://example.com/".to_string(),
+                blocked_url: "inline".to_string(),
+                effective_directive: "script-src-elem".to_string(),
+                original_policy: "script-src 'self'".to_string(),
+                enforced: true,
+                source_file: None,
+                line_number: 3,
+                column_number: 7,
+                sample: String::new(),
+            },
+        };
+        let line = serde_json::to_value(ControlMessage::from(event)).unwrap();
+        assert_eq!(line["event"], "cspViolation");
+        assert_eq!(line["webview"], "tab");
+        assert_eq!(line["effectiveDirective"], "script-src-elem");
+        assert_eq!(line["enforced"], true);
+    }
+
+    #[test]
+    fn test_subresource_blocked_event() {
+        let event = ControlEvent::SubresourceBlocked {
+            webview: "tab".to_string(),
+            subresource: BlockedSubresource {
+                document_url: "https://example.com/".to_string(),
+                url: "https://cdn.example.com/app.js".to_string(),
+                element: "script".to_string(),
+                reason: BlockedReason::Integrity,
+            },
+        };
+        let line = serde_json::to_value(ControlMessage::from(event)).unwrap();
+        assert_eq!(line["event"], "subresourceBlocked");
+        assert_eq!(line["url"], "https://cdn.example.com/app.js");
+        assert_eq!(line["reason"], "integrity");
+    }
+}
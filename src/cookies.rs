@@ -0,0 +1,344 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs::File,
+    path::{Path, PathBuf},
+    sync::Arc,
+    thread,
+};
+
+use crossbeam_channel::Sender;
+use embedder_traits::{
+    EmbedderMsg, WebResourceRequest, WebResourceResponse, WebResourceResponseMsg,
+};
+use http::{
+    HeaderMap, Method,
+    header::{
+        ACCEPT_ENCODING, CONTENT_ENCODING, CONTENT_LENGTH, COOKIE, SET_COOKIE, TRANSFER_ENCODING,
+    },
+};
+use ipc_channel::ipc::IpcSender;
+use net_traits::{CoreResourceMsg, IpcSend, ResourceThreads, pub_domains::reg_suffix};
+use reqwest::cookie::{CookieStore, Jar};
+use rustls::ClientConfig;
+use servo_url::ServoUrl;
+use versoview_messages::CookiePolicy;
+
+/// File of the profile directory Servo's resource thread loads the cookie jar from when it starts,
+/// and saves it to when it exits.
+const COOKIE_JAR_FILE: &str = "cookie_jar.json";
+/// Threads fetching the third-party requests without the cookies of the jar.
+const THIRD_PARTY_FETCH_THREADS: usize = 4;
+
+/// A third-party request fetched by Verso instead of Servo, so it's sent without the cookies of
+/// the jar.
+struct ThirdPartyRequest {
+    url: url::Url,
+    method: Method,
+    headers: HeaderMap,
+    sender: IpcSender<WebResourceResponseMsg>,
+    /// Cookies of the partition of the tab's site, `None` if it's sent without cookies.
+    partition: Option<Arc<Jar>>,
+}
+
+/// Registrable domain of the host, the site its cookies are kept under.
+pub(crate) fn site(host: &str) -> String {
+    reg_suffix(host).to_lowercase()
+}
+
+/// Cookie jar of the profile. Servo keeps the cookies in memory and saves them in the profile
+/// directory when it shuts down, this applies the cookie policy on top of it.
+///
+/// Servo can't list the cookies of the jar or tell which document a request is sent for, so the
+/// sites which may have cookies are tracked from the saved jar and the requests of the session.
+///
+/// Servo adds the cookies of the jar to requests itself, so third-party requests are fetched by
+/// Verso instead when they are blocked: without the `Cookie` header, and their `Set-Cookie`
+/// headers are dropped before the response is handed to Servo. When the network is partitioned
+/// they're fetched the same way, with the cookies of a jar of the tab's site kept in memory
+/// instead. Their certificates are verified against the same roots as Servo's, and the
+/// certificate pins.
+pub(crate) struct CookieJar {
+    /// Directory Servo saves the jar in, `None` if the cookies are never saved, e.g. for private
+    /// windows.
+    directory: Option<PathBuf>,
+    policy: CookiePolicy,
+    /// Sites which may have cookies in the jar.
+    sites: HashSet<String>,
+    /// Whether the cookies of third-party requests are kept apart for each site of the tabs.
+    partitioned: bool,
+    /// Cookies of third-party requests keyed by the site of the tab, for the session.
+    partitions: HashMap<String, Arc<Jar>>,
+    /// To fetch third-party requests without the cookies of the jar.
+    third_party_sender: Sender<ThirdPartyRequest>,
+}
+
+impl CookieJar {
+    /// Create the cookie jar saved in the directory. If the policy only keeps cookies for the
+    /// session, the saved jar is removed before Servo loads it. Third-party requests are fetched
+    /// with the TLS config, see [`crate::cert_pinning::CertificatePins::client_config`], and
+    /// cancelled if there's none.
+    pub fn new(
+        directory: Option<&Path>,
+        policy: CookiePolicy,
+        partitioned: bool,
+        tls_config: Option<ClientConfig>,
+    ) -> Self {
+        let (third_party_sender, third_party_receiver) = crossbeam_channel::unbounded();
+        let client = tls_config.and_then(|tls_config| {
+            reqwest::blocking::Client::builder()
+                // Servo follows the redirects itself, and checks them against its policies
+                .redirect(reqwest::redirect::Policy::none())
+                .use_preconfigured_tls(tls_config)
+                .build()
+                .inspect_err(|e| {
+                    log::error!("Verso failed to build the client of third-party requests: {e}")
+                })
+                .ok()
+        });
+        for _ in 0..THIRD_PARTY_FETCH_THREADS {
+            let Some(client) = client.clone() else {
+                break;
+            };
+            let receiver = third_party_receiver.clone();
+            let result = thread::Builder::new()
+                .name("ThirdPartyFetch".to_owned())
+                .spawn(move || {
+                    for request in receiver {
+                        fetch_third_party(&client, request);
+                    }
+                });
+            if let Err(e) = result {
+                log::error!("Failed to spawn third-party fetch thread: {e}");
+            }
+        }
+
+        let mut jar = Self {
+            directory: directory.map(Path::to_path_buf),
+            policy,
+            sites: HashSet::new(),
+            partitioned,
+            partitions: HashMap::new(),
+            third_party_sender,
+        };
+        if policy.session_only {
+            jar.remove_saved_jar();
+        } else {
+            jar.sites = jar.saved_sites();
+        }
+        jar
+    }
+
+    fn jar_file_path(&self) -> Option<PathBuf> {
+        Some(self.directory.as_ref()?.join(COOKIE_JAR_FILE))
+    }
+
+    /// Sites of the cookies of the saved jar.
+    fn saved_sites(&self) -> HashSet<String> {
+        let Some(file) = self.jar_file_path().and_then(|path| File::open(path).ok()) else {
+            return HashSet::new();
+        };
+        match serde_json::from_reader::<_, serde_json::Value>(file) {
+            Ok(jar) => jar
+                .get("cookies_map")
+                .and_then(serde_json::Value::as_object)
+                .map(|cookies| cookies.keys().cloned().collect())
+                .unwrap_or_default(),
+            Err(e) => {
+                log::warn!("Verso failed to read the sites of the cookie jar: {e}");
+                HashSet::new()
+            }
+        }
+    }
+
+    fn remove_saved_jar(&self) {
+        let Some(path) = self.jar_file_path().filter(|path| path.exists()) else {
+            return;
+        };
+        if let Err(e) = std::fs::remove_file(&path) {
+            log::error!("Failed to remove cookie jar {}: {e}", path.display());
+        }
+    }
+
+    /// Change the policy. It applies to the requests sent from now on, and to the jar saved when
+    /// Verso quits.
+    pub fn set_policy(&mut self, policy: CookiePolicy) {
+        self.policy = policy;
+    }
+
+    /// Change whether the cookies of third-party requests are kept apart for each site of the
+    /// tabs. It applies to the requests sent from now on.
+    pub fn set_partitioned(&mut self, partitioned: bool) {
+        self.partitioned = partitioned;
+    }
+
+    /// Track the site of a request before it's sent, it may set cookies.
+    pub fn observe_request(&mut self, request: &WebResourceRequest) {
+        if let Some(host) = request.url.host_str() {
+            self.sites.insert(site(host));
+        }
+    }
+
+    /// Fetch the request and return `None` if it's sent from a tab of another site, and
+    /// third-party cookies are blocked or partitioned: without cookies, or with the cookies of
+    /// the partition of the tab's site. Other messages are given back. Servo doesn't tell which
+    /// document a request is sent for, so the site of the tab is the one compared. Requests with
+    /// a body can't be fetched by Verso, they are sent by Servo with the cookies of the jar.
+    pub(crate) fn handle_request(
+        &mut self,
+        msg: EmbedderMsg,
+        top_level_url: Option<&ServoUrl>,
+    ) -> Option<EmbedderMsg> {
+        if !self.policy.block_third_party && !self.partitioned {
+            return Some(msg);
+        }
+        let top_level_site = match &msg {
+            EmbedderMsg::WebResourceRequested(_, request, _) => {
+                third_party_top_level_site(request, top_level_url)
+            }
+            _ => None,
+        };
+        match (msg, top_level_site) {
+            (EmbedderMsg::WebResourceRequested(_, request, sender), Some(top_level_site)) => {
+                let partition = if self.policy.block_third_party {
+                    log::debug!(
+                        "Verso is fetching third-party {} without cookies",
+                        request.url
+                    );
+                    None
+                } else {
+                    log::debug!(
+                        "Verso is fetching third-party {} with the cookies of {top_level_site}",
+                        request.url
+                    );
+                    Some(self.partitions.entry(top_level_site).or_default().clone())
+                };
+                let result = self.third_party_sender.send(ThirdPartyRequest {
+                    url: request.url,
+                    method: request.method,
+                    headers: request.headers,
+                    sender,
+                    partition,
+                });
+                // There is no fetch thread, Servo would send the jar's cookies
+                if let Err(error) = result {
+                    log::warn!(
+                        "Verso cancelled third-party {}, it can't fetch it",
+                        error.0.url
+                    );
+                    let _ = error.0.sender.send(WebResourceResponseMsg::CancelLoad);
+                }
+                None
+            }
+            (msg, _) => Some(msg),
+        }
+    }
+
+    /// Delete the cookies of the site of the origin and of its partition, or every cookie if `None`.
+    pub fn clear(&mut self, resource_threads: &ResourceThreads, origin: Option<&url::Url>) {
+        let sites: Vec<String> = match origin {
+            Some(origin) => {
+                let Some(host) = origin.host_str() else {
+                    log::warn!("Verso can't clear the cookies of {origin}, it has no host");
+                    return;
+                };
+                let origin_site = site(host);
+                self.sites.remove(&origin_site);
+                self.partitions.remove(&origin_site);
+                vec![origin_site]
+            }
+            None => {
+                self.partitions.clear();
+                self.sites.drain().collect()
+            }
+        };
+        for site in sites {
+            // The jar deletes the cookies of the site of the URL, whatever its scheme and path
+            if let Ok(url) = ServoUrl::parse(&format!("https://{site}/")) {
+                delete_cookies(resource_threads, url);
+            }
+        }
+    }
+
+    /// Remove the jar Servo saved while shutting down if the cookies are kept for the session
+    /// only. It must be called after Servo is shut down.
+    pub fn after_shutdown(&self) {
+        if self.policy.session_only {
+            self.remove_saved_jar();
+        }
+    }
+}
+
+/// Site of the tab if the request is a subresource of a tab of another site, which Verso can
+/// fetch.
+fn third_party_top_level_site(
+    request: &WebResourceRequest,
+    top_level_url: Option<&ServoUrl>,
+) -> Option<String> {
+    if request.is_for_main_frame
+        || !matches!(request.url.scheme(), "http" | "https")
+        || !matches!(request.method, Method::GET | Method::HEAD)
+    {
+        return None;
+    }
+    let top_level_site = site(top_level_url?.host_str()?);
+    (site(request.url.host_str()?) != top_level_site).then_some(top_level_site)
+}
+
+/// Fetch a third-party request with the cookies of its partition, or without cookies, and hand
+/// the response to Servo without the cookies it sets. Bodies are decoded by the client.
+fn fetch_third_party(client: &reqwest::blocking::Client, request: ThirdPartyRequest) {
+    let ThirdPartyRequest {
+        url,
+        method,
+        mut headers,
+        sender,
+        partition,
+    } = request;
+    headers.remove(COOKIE);
+    headers.remove(ACCEPT_ENCODING);
+    if let Some(cookie) = partition.as_ref().and_then(|jar| jar.cookies(&url)) {
+        headers.insert(COOKIE, cookie);
+    }
+    let result = client
+        .request(method, url.clone())
+        .headers(headers)
+        .send()
+        .and_then(|response| {
+            let status = response.status();
+            let headers = response.headers().clone();
+            response.bytes().map(|body| (status, headers, body))
+        });
+    let (status, mut headers, body) = match result {
+        Ok(response) => response,
+        Err(e) => {
+            log::debug!("Verso failed to fetch third-party {url}: {e}");
+            let _ = sender.send(WebResourceResponseMsg::CancelLoad);
+            return;
+        }
+    };
+    if let Some(jar) = &partition {
+        jar.set_cookies(&mut headers.get_all(SET_COOKIE).iter(), &url);
+    }
+    for name in [
+        SET_COOKIE,
+        CONTENT_ENCODING,
+        CONTENT_LENGTH,
+        TRANSFER_ENCODING,
+    ] {
+        headers.remove(name);
+    }
+    let _ = sender
+        .send(WebResourceResponseMsg::Start(
+            WebResourceResponse::new(url)
+                .headers(headers)
+                .status_code(status),
+        ))
+        .and_then(|_| sender.send(WebResourceResponseMsg::SendBodyData(body.to_vec())))
+        .and_then(|_| sender.send(WebResourceResponseMsg::FinishLoad));
+}
+
+fn delete_cookies(resource_threads: &ResourceThreads, url: ServoUrl) {
+    if let Err(e) = resource_threads.send(CoreResourceMsg::DeleteCookies(url)) {
+        log::error!("Verso failed to send DeleteCookies to resource thread: {e}");
+    }
+}
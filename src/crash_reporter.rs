@@ -0,0 +1,237 @@
+use std::{
+    backtrace::Backtrace,
+    fs,
+    path::{Path, PathBuf},
+    sync::{Mutex, PoisonError, TryLockError},
+    thread,
+};
+
+use serde::{Deserialize, Serialize};
+
+/// What Verso was doing when it crashed, written with the crash reports.
+struct CrashContext {
+    /// Directory crash reports are written to, crashes aren't recorded if `None`
+    directory: Option<PathBuf>,
+    /// Renderer of the GL driver
+    gpu: Option<String>,
+    /// URL of the last page which finished loading
+    url: Option<String>,
+}
+
+/// The panic hook can run on any thread, so the context is shared by all of them.
+static CONTEXT: Mutex<CrashContext> = Mutex::new(CrashContext {
+    directory: None,
+    gpu: None,
+    url: None,
+});
+
+/// Where a crash happened.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CrashKind {
+    /// A thread of Verso panicked
+    Browser,
+    /// The script or layout of a page panicked, Servo replaced it with the crash page
+    Page,
+}
+
+/// A crash report, as shown on `verso://crashes` and submitted to the crash report URL.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CrashReport {
+    /// The ID of the report.
+    pub id: uuid::Uuid,
+    /// Time of the crash, in milliseconds since the Unix epoch.
+    pub time: i64,
+    /// Where the crash happened.
+    pub kind: CrashKind,
+    /// Version of Verso.
+    pub version: String,
+    /// Operating system and architecture.
+    pub os: String,
+    /// Renderer of the GL driver.
+    pub gpu: Option<String>,
+    /// URL of the page which crashed, or of the last page which loaded for browser crashes.
+    pub url: Option<String>,
+    /// Panic message.
+    pub reason: String,
+    /// Backtrace of the panicking thread.
+    pub backtrace: Option<String>,
+    /// Whether the report was submitted.
+    #[serde(default)]
+    pub submitted: bool,
+}
+
+impl CrashReport {
+    fn new(
+        kind: CrashKind,
+        reason: String,
+        backtrace: Option<String>,
+        url: Option<String>,
+    ) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4(),
+            time: chrono::Utc::now().timestamp_millis(),
+            kind,
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            os: format!("{} {}", std::env::consts::OS, std::env::consts::ARCH),
+            gpu: None,
+            url,
+            reason,
+            backtrace,
+            submitted: false,
+        }
+    }
+}
+
+/// Write a report of every panic of Verso to the crash directory of the profile, before the
+/// previous panic hook runs.
+pub(crate) fn install(storage: &CrashStorage) {
+    CONTEXT
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .directory = Some(storage.directory());
+    let previous_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let reason = match info.payload().downcast_ref::<&str>() {
+            Some(reason) => reason.to_string(),
+            None => info
+                .payload()
+                .downcast_ref::<String>()
+                .cloned()
+                .unwrap_or_else(|| "Unknown panic".to_string()),
+        };
+        let reason = match info.location() {
+            Some(location) => format!("{reason} at {location}"),
+            None => reason,
+        };
+        let backtrace = Backtrace::force_capture().to_string();
+        write_report(CrashReport::new(
+            CrashKind::Browser,
+            reason,
+            Some(backtrace),
+            None,
+        ));
+        previous_hook(info);
+    }));
+}
+
+/// Set the renderer of the GL driver written with crash reports.
+pub(crate) fn set_gpu(gpu: String) {
+    CONTEXT.lock().unwrap_or_else(PoisonError::into_inner).gpu = Some(gpu);
+}
+
+/// Set the URL of the last page which finished loading, written with browser crash reports.
+pub(crate) fn set_url(url: String) {
+    CONTEXT.lock().unwrap_or_else(PoisonError::into_inner).url = Some(url);
+}
+
+/// Record the crash of a page reported by Servo.
+pub(crate) fn record_page_crash(reason: String, backtrace: Option<String>, url: Option<String>) {
+    write_report(CrashReport::new(CrashKind::Page, reason, backtrace, url));
+}
+
+fn write_report(mut report: CrashReport) {
+    // The panicking thread may hold the lock itself, waiting for it would deadlock
+    let context = match CONTEXT.try_lock() {
+        Ok(context) => context,
+        Err(TryLockError::Poisoned(error)) => error.into_inner(),
+        Err(TryLockError::WouldBlock) => return,
+    };
+    let Some(directory) = context.directory.clone() else {
+        return;
+    };
+    report.gpu = context.gpu.clone();
+    if report.url.is_none() {
+        report.url = context.url.clone();
+    }
+    drop(context);
+    if let Err(e) = CrashStorage::save(&directory, &report) {
+        log::error!("Failed to write crash report: {e}");
+    }
+}
+
+/// Submit the report to the crash report URL on another thread, and mark it submitted. The
+/// callback is called with whether it was submitted.
+pub(crate) fn submit(
+    storage: &CrashStorage,
+    mut report: CrashReport,
+    url: url::Url,
+    callback: impl FnOnce(bool) + Send + 'static,
+) {
+    let directory = storage.directory();
+    let result = thread::Builder::new()
+        .name("CrashReportSubmit".to_owned())
+        .spawn(move || {
+            let result = reqwest::blocking::Client::new()
+                .post(url)
+                .json(&report)
+                .send()
+                .and_then(|response| response.error_for_status());
+            let submitted = match result {
+                Ok(_) => {
+                    report.submitted = true;
+                    if let Err(e) = CrashStorage::save(&directory, &report) {
+                        log::error!("Failed to write crash report: {e}");
+                    }
+                    true
+                }
+                Err(e) => {
+                    log::warn!("Failed to submit crash report {}: {e}", report.id);
+                    false
+                }
+            };
+            callback(submitted);
+        });
+    if let Err(e) = result {
+        log::error!("Failed to spawn crash report submission thread: {e}");
+    }
+}
+
+pub(crate) struct CrashStorage {
+    config_dir_path: PathBuf,
+}
+
+impl CrashStorage {
+    /// Create a new `CrashStorage`.
+    pub fn new(config_dir_path: PathBuf) -> Self {
+        Self { config_dir_path }
+    }
+
+    fn directory(&self) -> PathBuf {
+        self.config_dir_path.join("crashes")
+    }
+
+    fn save(directory: &Path, report: &CrashReport) -> Result<(), std::io::Error> {
+        fs::create_dir_all(directory)?;
+        let file = fs::File::create(directory.join(format!("{}.json", report.id)))?;
+        serde_json::to_writer(file, report)?;
+        Ok(())
+    }
+
+    /// Load every crash report, the latest first.
+    pub fn load_reports(&self) -> Vec<CrashReport> {
+        let Ok(entries) = fs::read_dir(self.directory()) else {
+            return Vec::new();
+        };
+        let mut reports: Vec<CrashReport> = entries
+            .filter_map(|entry| {
+                let file = fs::File::open(entry.ok()?.path()).ok()?;
+                serde_json::from_reader(file).ok()
+            })
+            .collect();
+        reports.sort_by_key(|report| std::cmp::Reverse(report.time));
+        reports
+    }
+
+    /// Load the crash report with the ID.
+    pub fn load_report(&self, id: uuid::Uuid) -> Option<CrashReport> {
+        let file = fs::File::open(self.directory().join(format!("{id}.json"))).ok()?;
+        serde_json::from_reader(file).ok()
+    }
+
+    /// Delete the crash report with the ID.
+    pub fn delete_report(&self, id: uuid::Uuid) -> Result<(), std::io::Error> {
+        fs::remove_file(self.directory().join(format!("{id}.json")))
+    }
+}
@@ -0,0 +1,44 @@
+use versoview_messages::CspViolation;
+
+/// Script which reports the violations of the Content Security Policy of the page with the
+/// `CSP_VIOLATION` request of the Verso bridge, and logs them to the console of the page.
+pub(crate) const CSP_VIOLATION_SCRIPT: &str = r#"
+(() => {
+  if (window.versoCspViolation) {
+    return;
+  }
+  window.versoCspViolation = true;
+
+  addEventListener('securitypolicyviolation', (event) => {
+    const violation = {
+      documentUrl: event.documentURI,
+      blockedUrl: event.blockedURI,
+      effectiveDirective: event.effectiveDirective || event.violatedDirective,
+      originalPolicy: event.originalPolicy,
+      enforced: event.disposition !== 'report',
+      sourceFile: event.sourceFile || null,
+      lineNumber: event.lineNumber,
+      columnNumber: event.columnNumber,
+      sample: event.sample,
+    };
+    const log = violation.enforced ? console.error : console.warn;
+    log(`${violation.enforced ? 'Blocked' : 'Reported'} ${violation.blockedUrl} for violating ` +
+      `the Content Security Policy directive "${violation.effectiveDirective}"`);
+    prompt(`VERSO::CSP_VIOLATION::${JSON.stringify(violation)}`);
+  }, true);
+})();
+"#;
+
+/// Describe the violation like the console of the page does.
+pub(crate) fn describe(violation: &CspViolation) -> String {
+    format!(
+        "{} {} for violating the Content Security Policy directive \"{}\"",
+        if violation.enforced {
+            "Blocked"
+        } else {
+            "Reported"
+        },
+        violation.blocked_url,
+        violation.effective_directive,
+    )
+}
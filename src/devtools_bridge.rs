@@ -0,0 +1,951 @@
+use std::{
+    collections::HashSet,
+    io::{self, Cursor, Read, Write},
+    net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream},
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+use base::id::WebViewId;
+use base64::{Engine, engine::general_purpose::STANDARD};
+use constellation_traits::EmbedderToConstellationMessage;
+use crossbeam_channel::{Sender, unbounded};
+use embedder_traits::{EmbedderMsg, LoadStatus, WebDriverCommandMsg, WebDriverJSValue};
+use image::{DynamicImage, ImageFormat, RgbaImage};
+use ipc_channel::ipc;
+use serde_json::{Value, json};
+use servo_url::ServoUrl;
+use tungstenite::{Message, WebSocket};
+use versoview_messages::{BlockedSubresource, CspViolation};
+
+use crate::{csp, frames, integrity, verso::send_to_constellation};
+
+/// Version of the Chrome DevTools Protocol the bridge implements a subset of.
+const PROTOCOL_VERSION: &str = "1.3";
+
+/// How long the request head of a connection may take to arrive.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Longest request head of the HTTP endpoints and the WebSocket handshake.
+const MAX_HEAD_LENGTH: usize = 8 * 1024;
+
+/// How often connections check for events to send while waiting for messages.
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// How long `Runtime.evaluate` waits for the script, and `Page.captureScreenshot` for the frame.
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Error codes of the protocol, from JSON-RPC.
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const SERVER_ERROR: i64 = -32000;
+
+/// Evaluate the expression in the page's global scope and describe its result as JSON.
+const EVALUATE_SCRIPT: &str = r#"
+((expression) => {
+  try {
+    const value = (0, eval)(expression);
+    let json;
+    try {
+      json = JSON.stringify(value);
+    } catch (e) {}
+    return JSON.stringify({
+      type: value === null ? 'object' : typeof value,
+      subtype: value === null ? 'null' : Array.isArray(value) ? 'array' : undefined,
+      value: json === undefined ? undefined : JSON.parse(json),
+      description: String(value),
+    });
+  } catch (e) {
+    return JSON.stringify({ exception: String(e) });
+  }
+})
+"#;
+
+/// Bridge of the Chrome DevTools Protocol, so tools like Puppeteer and Playwright can attach to
+/// the tabs of Verso. It serves the `Page`, `Runtime`, `Network` and `Log` domains of tabs, and
+/// the `Browser` and `Target` domains, over WebSockets on the loopback interface.
+///
+/// Web pages can reach the loopback interface too, so requests naming another host are rejected
+/// against DNS rebinding, and requests sent by pages of origins which aren't allowed are
+/// rejected before the handshake.
+///
+/// Commands are translated to constellation messages on the thread of the connection, and
+/// events are reported by Verso with [`DevtoolsBridge::observe`].
+#[derive(Clone)]
+pub(crate) struct DevtoolsBridge {
+    state: Arc<Mutex<BridgeState>>,
+}
+
+struct BridgeState {
+    /// Address the server listens on, for the URLs of the targets.
+    address: SocketAddr,
+    /// Origins of web pages allowed to connect.
+    allowed_origins: Vec<url::Origin>,
+    /// When the bridge started, the origin of the timestamps of events.
+    started: Instant,
+    targets: Vec<Target>,
+    sessions: Vec<Session>,
+    next_connection: u64,
+}
+
+/// A tab clients can attach to.
+struct Target {
+    webview_id: WebViewId,
+    id: String,
+    url: String,
+    title: String,
+}
+
+/// A client attached to a target, either on a connection to the target, or on a session of a
+/// connection to the browser.
+struct Session {
+    connection: u64,
+    id: Option<String>,
+    webview_id: WebViewId,
+    domains: HashSet<Domain>,
+    events: Sender<String>,
+}
+
+/// Domains whose events are sent once enabled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Domain {
+    Page,
+    Runtime,
+    Network,
+    Log,
+}
+
+impl DevtoolsBridge {
+    /// Listen to clients on the loopback interface, 0 picks a random port.
+    pub(crate) fn start(
+        port: u16,
+        allowed_origins: Vec<url::Origin>,
+        constellation_sender: Sender<EmbedderToConstellationMessage>,
+    ) -> Option<Self> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))
+            .and_then(|listener| Ok((listener.local_addr()?, listener)));
+        let (address, listener) = match listener {
+            Ok(listener) => listener,
+            Err(e) => {
+                log::error!("Failed to start the DevTools protocol server on port {port}: {e}");
+                return None;
+            }
+        };
+        let bridge = Self {
+            state: Arc::new(Mutex::new(BridgeState {
+                address,
+                allowed_origins,
+                started: Instant::now(),
+                targets: Vec::new(),
+                sessions: Vec::new(),
+                next_connection: 0,
+            })),
+        };
+        let server = bridge.clone();
+        let result = thread::Builder::new()
+            .name("DevtoolsBridge".to_owned())
+            .spawn(move || {
+                for stream in listener.incoming().flatten() {
+                    let bridge = server.clone();
+                    let constellation_sender = constellation_sender.clone();
+                    let result = thread::Builder::new()
+                        .name("DevtoolsBridgeConnection".to_owned())
+                        .spawn(move || {
+                            if let Err(e) =
+                                handle_connection(stream, &bridge, &constellation_sender)
+                            {
+                                log::debug!("DevTools protocol connection failed: {e}");
+                            }
+                        });
+                    if let Err(e) = result {
+                        log::error!("Failed to spawn DevTools protocol connection thread: {e}");
+                    }
+                }
+            });
+        if let Err(e) = result {
+            log::error!("Failed to spawn DevTools protocol server thread: {e}");
+            return None;
+        }
+        log::info!("DevTools protocol server listening on {address}");
+        Some(bridge)
+    }
+
+    /// Track the tabs and send the events of the embedder message to the sessions which enabled
+    /// their domain. Only messages of tabs should be observed.
+    pub(crate) fn observe(&self, message: &EmbedderMsg) {
+        let mut state = self.state.lock().unwrap();
+        let timestamp = state.started.elapsed().as_secs_f64();
+        match message {
+            EmbedderMsg::HistoryChanged(webview_id, list, index) => {
+                let Some(url) = list.get(*index) else {
+                    return;
+                };
+                let target = state.target_mut(*webview_id);
+                target.url = url.to_string();
+                let frame = frame(target);
+                state.send_event(
+                    *webview_id,
+                    Domain::Page,
+                    "Page.frameNavigated",
+                    json!({ "frame": frame, "type": "Navigation" }),
+                );
+            }
+            EmbedderMsg::ChangePageTitle(webview_id, title) => {
+                state.target_mut(*webview_id).title = title.clone().unwrap_or_default();
+            }
+            EmbedderMsg::NotifyLoadStatusChanged(webview_id, LoadStatus::Complete) => {
+                state.send_event(
+                    *webview_id,
+                    Domain::Page,
+                    "Page.loadEventFired",
+                    json!({ "timestamp": timestamp }),
+                );
+            }
+            EmbedderMsg::WebResourceRequested(Some(webview_id), request, _) => {
+                let headers: serde_json::Map<String, Value> = request
+                    .headers
+                    .iter()
+                    .map(|(name, value)| {
+                        (
+                            name.to_string(),
+                            Value::from(String::from_utf8_lossy(value.as_bytes())),
+                        )
+                    })
+                    .collect();
+                let loader_id = state.target_mut(*webview_id).id.clone();
+                state.send_event(
+                    *webview_id,
+                    Domain::Network,
+                    "Network.requestWillBeSent",
+                    json!({
+                        "requestId": uuid::Uuid::new_v4().to_string(),
+                        "loaderId": loader_id,
+                        "documentURL": request.url.as_str(),
+                        "request": {
+                            "url": request.url.as_str(),
+                            "method": request.method.as_str(),
+                            "headers": headers,
+                        },
+                        "timestamp": timestamp,
+                        "wallTime": chrono::Utc::now().timestamp_millis() as f64 / 1000.0,
+                        "initiator": { "type": "other" },
+                        "type": if request.is_for_main_frame { "Document" } else { "Other" },
+                        "frameId": loader_id,
+                    }),
+                );
+            }
+            EmbedderMsg::WebViewClosed(webview_id) => {
+                let webview_id = *webview_id;
+                state
+                    .targets
+                    .retain(|target| target.webview_id != webview_id);
+                // Dropping the event senders of the sessions closes connections to the target
+                state
+                    .sessions
+                    .retain(|session| session.webview_id != webview_id);
+            }
+            _ => {}
+        }
+    }
+
+    /// Send the violation of the Content Security Policy of the page of the webview to the
+    /// console of the sessions which enabled the `Log` domain.
+    pub(crate) fn report_csp_violation(&self, webview_id: WebViewId, violation: &CspViolation) {
+        let state = self.state.lock().unwrap();
+        state.send_event(
+            webview_id,
+            Domain::Log,
+            "Log.entryAdded",
+            json!({
+                "entry": {
+                    "source": "security",
+                    "level": if violation.enforced { "error" } else { "warning" },
+                    "text": csp::describe(violation),
+                    "timestamp": chrono::Utc::now().timestamp_millis() as f64,
+                    "url": violation.source_file.as_deref().unwrap_or(&violation.document_url),
+                    "lineNumber": violation.line_number.saturating_sub(1),
+                },
+            }),
+        );
+    }
+
+    /// Send the blocked script or stylesheet of the page of the webview to the console of the
+    /// sessions which enabled the `Log` domain.
+    pub(crate) fn report_blocked_subresource(
+        &self,
+        webview_id: WebViewId,
+        subresource: &BlockedSubresource,
+    ) {
+        let state = self.state.lock().unwrap();
+        state.send_event(
+            webview_id,
+            Domain::Log,
+            "Log.entryAdded",
+            json!({
+                "entry": {
+                    "source": "security",
+                    "level": "error",
+                    "text": integrity::describe(subresource),
+                    "timestamp": chrono::Utc::now().timestamp_millis() as f64,
+                    "url": subresource.document_url,
+                },
+            }),
+        );
+    }
+}
+
+impl BridgeState {
+    fn target_mut(&mut self, webview_id: WebViewId) -> &mut Target {
+        let index = match self
+            .targets
+            .iter()
+            .position(|target| target.webview_id == webview_id)
+        {
+            Some(index) => index,
+            None => {
+                self.targets.push(Target {
+                    webview_id,
+                    id: target_id(webview_id),
+                    url: String::new(),
+                    title: String::new(),
+                });
+                self.targets.len() - 1
+            }
+        };
+        &mut self.targets[index]
+    }
+
+    fn target_by_id(&self, id: &str) -> Option<&Target> {
+        self.targets.iter().find(|target| target.id == id)
+    }
+
+    fn send_event(&self, webview_id: WebViewId, domain: Domain, method: &str, params: Value) {
+        for session in &self.sessions {
+            if session.webview_id != webview_id || !session.domains.contains(&domain) {
+                continue;
+            }
+            let mut event = json!({ "method": method, "params": params });
+            if let Some(id) = &session.id {
+                event["sessionId"] = Value::from(id.clone());
+            }
+            let _ = session.events.send(event.to_string());
+        }
+    }
+
+    fn session_mut(&mut self, connection: u64, id: Option<&str>) -> Option<&mut Session> {
+        self.sessions
+            .iter_mut()
+            .find(|session| session.connection == connection && session.id.as_deref() == id)
+    }
+
+    fn target_info(&self, target: &Target) -> Value {
+        json!({
+            "targetId": target.id,
+            "type": "page",
+            "title": target.title,
+            "url": target.url,
+            "attached": self.sessions.iter().any(|session| session.webview_id == target.webview_id),
+        })
+    }
+}
+
+/// ID of the target of a webview, also the ID of its main frame and of the tab in the control
+/// protocol.
+pub(crate) fn target_id(webview_id: WebViewId) -> String {
+    bincode::serialize(&webview_id)
+        .unwrap()
+        .iter()
+        .map(|byte| format!("{byte:02X}"))
+        .collect()
+}
+
+fn frame(target: &Target) -> Value {
+    let security_origin = url::Url::parse(&target.url)
+        .map(|url| url.origin().ascii_serialization())
+        .unwrap_or_default();
+    json!({
+        "id": target.id,
+        "loaderId": target.id,
+        "url": target.url,
+        "securityOrigin": security_origin,
+        "mimeType": "text/html",
+    })
+}
+
+/// A stream replaying the request head read to route the connection, so the WebSocket handshake
+/// can read it again.
+struct ReplayStream {
+    head: Cursor<Vec<u8>>,
+    stream: TcpStream,
+}
+
+impl Read for ReplayStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self.head.read(buf)? {
+            0 => self.stream.read(buf),
+            read => Ok(read),
+        }
+    }
+}
+
+impl Write for ReplayStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.stream.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.stream.flush()
+    }
+}
+
+/// Serve the discovery endpoints over HTTP, or the protocol if the client upgrades to a
+/// WebSocket on `/devtools/browser` or `/devtools/page/<target>`.
+fn handle_connection(
+    mut stream: TcpStream,
+    bridge: &DevtoolsBridge,
+    constellation_sender: &Sender<EmbedderToConstellationMessage>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    stream.set_read_timeout(Some(HANDSHAKE_TIMEOUT))?;
+    let head = read_head(&mut stream)?;
+    let head_text = String::from_utf8_lossy(&head);
+    let path = head_text
+        .lines()
+        .next()
+        .and_then(|line| line.split(' ').nth(1))
+        .unwrap_or("/")
+        .to_string();
+    let upgrade =
+        header(&head_text, "upgrade").is_some_and(|value| value.eq_ignore_ascii_case("websocket"));
+
+    if let Some(reason) = reject_reason(bridge, &head_text) {
+        log::warn!("DevTools protocol connection rejected: {reason}");
+        write!(
+            stream,
+            "HTTP/1.1 403 Forbidden\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+        )?;
+        return Ok(());
+    }
+
+    if !upgrade {
+        let (status, body) = match discovery(bridge, &path) {
+            Some(body) => ("200 OK", body.to_string()),
+            None => ("404 Not Found", String::new()),
+        };
+        write!(
+            stream,
+            "HTTP/1.1 {status}\r\nContent-Type: application/json; charset=UTF-8\r\n\
+             Content-Length: {}\r\nConnection: close\r\n\r\n{body}",
+            body.len()
+        )?;
+        return Ok(());
+    }
+
+    let default_target = if path.starts_with("/devtools/browser") {
+        None
+    } else if let Some(id) = path.strip_prefix("/devtools/page/") {
+        match bridge.state.lock().unwrap().target_by_id(id) {
+            Some(target) => Some(target.webview_id),
+            None => {
+                write!(
+                    stream,
+                    "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n"
+                )?;
+                return Ok(());
+            }
+        }
+    } else {
+        write!(
+            stream,
+            "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n"
+        )?;
+        return Ok(());
+    };
+
+    stream.set_read_timeout(Some(POLL_INTERVAL))?;
+    let websocket = tungstenite::accept(ReplayStream {
+        head: Cursor::new(head),
+        stream,
+    })?;
+    run_connection(websocket, bridge, constellation_sender, default_target);
+    Ok(())
+}
+
+/// Why the request must be rejected, `None` if the host is the loopback interface and the origin
+/// is allowed or not sent.
+fn reject_reason(bridge: &DevtoolsBridge, head: &str) -> Option<String> {
+    let Some(host) = header(head, "host") else {
+        return Some("no Host header".to_owned());
+    };
+    if !is_loopback_host(host) {
+        return Some(format!("Host {host} isn't the loopback interface"));
+    }
+    let origin = header(head, "origin")?;
+    let allowed = url::Url::parse(origin).is_ok_and(|url| {
+        bridge
+            .state
+            .lock()
+            .unwrap()
+            .allowed_origins
+            .contains(&url.origin())
+    });
+    (!allowed).then(|| format!("origin {origin} isn't allowed"))
+}
+
+/// Whether the value of a Host header names the loopback interface, with or without a port.
+fn is_loopback_host(host: &str) -> bool {
+    let name = match host.rsplit_once(':') {
+        Some((name, port)) if !name.ends_with(':') && port.parse::<u16>().is_ok() => name,
+        _ => host,
+    };
+    matches!(
+        name.to_ascii_lowercase().as_str(),
+        "localhost" | "127.0.0.1" | "[::1]"
+    )
+}
+
+/// Value of the first header of the request head with the name.
+fn header<'a>(head: &'a str, name: &str) -> Option<&'a str> {
+    head.lines().skip(1).find_map(|line| {
+        let (header, value) = line.split_once(':')?;
+        header
+            .trim()
+            .eq_ignore_ascii_case(name)
+            .then(|| value.trim())
+    })
+}
+
+/// Read the request head, up to the empty line ending it.
+fn read_head(stream: &mut TcpStream) -> io::Result<Vec<u8>> {
+    let mut head = Vec::new();
+    let mut byte = [0; 1];
+    while !head.ends_with(b"\r\n\r\n") {
+        if head.len() > MAX_HEAD_LENGTH {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "The request head is too long",
+            ));
+        }
+        // Read a byte at a time, so nothing past the head is consumed
+        if stream.read(&mut byte)? == 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        head.push(byte[0]);
+    }
+    Ok(head)
+}
+
+/// Body of the discovery endpoints, `None` if there is none at the path.
+fn discovery(bridge: &DevtoolsBridge, path: &str) -> Option<Value> {
+    let state = bridge.state.lock().unwrap();
+    let address = state.address;
+    match path.trim_end_matches('/') {
+        "/json/version" => Some(json!({
+            "Browser": format!("Verso/{}", env!("CARGO_PKG_VERSION")),
+            "Protocol-Version": PROTOCOL_VERSION,
+            "webSocketDebuggerUrl": format!("ws://{address}/devtools/browser"),
+        })),
+        "/json" | "/json/list" => Some(Value::Array(
+            state
+                .targets
+                .iter()
+                .map(|target| {
+                    json!({
+                        "id": target.id,
+                        "type": "page",
+                        "title": target.title,
+                        "url": target.url,
+                        "webSocketDebuggerUrl":
+                            format!("ws://{address}/devtools/page/{}", target.id),
+                    })
+                })
+                .collect(),
+        )),
+        "/json/protocol" => Some(json!({ "version": { "major": "1", "minor": "3" } })),
+        _ => None,
+    }
+}
+
+/// Answer the commands of a client and send it the events of its sessions until it disconnects.
+fn run_connection(
+    mut websocket: WebSocket<ReplayStream>,
+    bridge: &DevtoolsBridge,
+    constellation_sender: &Sender<EmbedderToConstellationMessage>,
+    default_target: Option<WebViewId>,
+) {
+    let (events, event_receiver) = unbounded();
+    let connection_id = {
+        let mut state = bridge.state.lock().unwrap();
+        let connection_id = state.next_connection;
+        state.next_connection += 1;
+        if let Some(webview_id) = default_target {
+            state.sessions.push(Session {
+                connection: connection_id,
+                id: None,
+                webview_id,
+                domains: HashSet::new(),
+                events: events.clone(),
+            });
+        }
+        connection_id
+    };
+    let mut connection = Connection {
+        id: connection_id,
+        bridge,
+        constellation_sender,
+        events,
+    };
+
+    'connection: loop {
+        match websocket.read() {
+            Ok(Message::Text(message)) => {
+                let response = connection.handle_message(&message);
+                if websocket.send(Message::Text(response.to_string())).is_err() {
+                    break;
+                }
+            }
+            Ok(Message::Close(_)) => break,
+            Ok(_) => {}
+            Err(tungstenite::Error::Io(e))
+                if matches!(
+                    e.kind(),
+                    io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                ) => {}
+            Err(_) => break,
+        }
+        for event in event_receiver.try_iter() {
+            if websocket.send(Message::Text(event)).is_err() {
+                break 'connection;
+            }
+        }
+        // The target of the connection was closed
+        if default_target.is_some()
+            && bridge
+                .state
+                .lock()
+                .unwrap()
+                .session_mut(connection_id, None)
+                .is_none()
+        {
+            let _ = websocket.close(None);
+            break;
+        }
+    }
+
+    bridge
+        .state
+        .lock()
+        .unwrap()
+        .sessions
+        .retain(|session| session.connection != connection_id);
+}
+
+/// A connection of a client, handling its commands.
+struct Connection<'a> {
+    id: u64,
+    bridge: &'a DevtoolsBridge,
+    constellation_sender: &'a Sender<EmbedderToConstellationMessage>,
+    events: Sender<String>,
+}
+
+/// Error answered to a command, with its JSON-RPC code.
+struct CommandError(i64, String);
+
+impl Connection<'_> {
+    fn handle_message(&mut self, message: &str) -> Value {
+        let message = match serde_json::from_str::<Value>(message) {
+            Ok(message) => message,
+            Err(e) => return error_response(Value::Null, None, INVALID_REQUEST, e.to_string()),
+        };
+        let id = message.get("id").cloned().unwrap_or(Value::Null);
+        let session_id = message
+            .get("sessionId")
+            .and_then(Value::as_str)
+            .map(str::to_string);
+        let Some(method) = message.get("method").and_then(Value::as_str) else {
+            return error_response(
+                id,
+                session_id,
+                INVALID_REQUEST,
+                "The message has no method".to_string(),
+            );
+        };
+        let params = message.get("params").cloned().unwrap_or(json!({}));
+        match self.handle_command(method, &params, session_id.as_deref()) {
+            Ok(result) => {
+                let mut response = json!({ "id": id, "result": result });
+                if let Some(session_id) = session_id {
+                    response["sessionId"] = Value::from(session_id);
+                }
+                response
+            }
+            Err(CommandError(code, message)) => error_response(id, session_id, code, message),
+        }
+    }
+
+    fn handle_command(
+        &mut self,
+        method: &str,
+        params: &Value,
+        session_id: Option<&str>,
+    ) -> Result<Value, CommandError> {
+        match method {
+            "Browser.getVersion" => {
+                return Ok(json!({
+                    "protocolVersion": PROTOCOL_VERSION,
+                    "product": format!("Verso/{}", env!("CARGO_PKG_VERSION")),
+                    "revision": "",
+                    "userAgent": "",
+                    "jsVersion": "",
+                }));
+            }
+            "Target.getTargets" => {
+                let state = self.bridge.state.lock().unwrap();
+                let infos: Vec<Value> = state
+                    .targets
+                    .iter()
+                    .map(|target| state.target_info(target))
+                    .collect();
+                return Ok(json!({ "targetInfos": infos }));
+            }
+            "Target.getTargetInfo" => {
+                let state = self.bridge.state.lock().unwrap();
+                let target = match params.get("targetId").and_then(Value::as_str) {
+                    Some(id) => state.target_by_id(id),
+                    None => self.webview_id(session_id).ok().and_then(|webview_id| {
+                        state
+                            .targets
+                            .iter()
+                            .find(|target| target.webview_id == webview_id)
+                    }),
+                };
+                return match target {
+                    Some(target) => Ok(json!({ "targetInfo": state.target_info(target) })),
+                    None => Err(CommandError(
+                        INVALID_PARAMS,
+                        "No target with given id".into(),
+                    )),
+                };
+            }
+            "Target.attachToTarget" => {
+                let mut state = self.bridge.state.lock().unwrap();
+                let Some(webview_id) = params
+                    .get("targetId")
+                    .and_then(Value::as_str)
+                    .and_then(|id| state.target_by_id(id))
+                    .map(|target| target.webview_id)
+                else {
+                    return Err(CommandError(
+                        INVALID_PARAMS,
+                        "No target with given id".into(),
+                    ));
+                };
+                let id = uuid::Uuid::new_v4().simple().to_string().to_uppercase();
+                state.sessions.push(Session {
+                    connection: self.id,
+                    id: Some(id.clone()),
+                    webview_id,
+                    domains: HashSet::new(),
+                    events: self.events.clone(),
+                });
+                return Ok(json!({ "sessionId": id }));
+            }
+            "Target.detachFromTarget" => {
+                let id = params.get("sessionId").and_then(Value::as_str);
+                self.bridge
+                    .state
+                    .lock()
+                    .unwrap()
+                    .sessions
+                    .retain(|session| session.connection != self.id || session.id.as_deref() != id);
+                return Ok(json!({}));
+            }
+            // Targets are only discovered with `Target.getTargets`
+            "Target.setDiscoverTargets" | "Target.setAutoAttach" => return Ok(json!({})),
+            _ => {}
+        }
+
+        let webview_id = self.webview_id(session_id)?;
+        let (domain, command) = method.split_once('.').unwrap_or((method, ""));
+        let domain = match domain {
+            "Page" => Domain::Page,
+            "Runtime" => Domain::Runtime,
+            "Network" => Domain::Network,
+            "Log" => Domain::Log,
+            _ => return Err(method_not_found(method)),
+        };
+        match command {
+            "enable" | "disable" => {
+                let mut state = self.bridge.state.lock().unwrap();
+                if let Some(session) = state.session_mut(self.id, session_id) {
+                    if command == "enable" {
+                        session.domains.insert(domain);
+                    } else {
+                        session.domains.remove(&domain);
+                    }
+                }
+                Ok(json!({}))
+            }
+            _ => match method {
+                "Page.navigate" => self.navigate(webview_id, params),
+                "Page.reload" => {
+                    send_to_constellation(
+                        self.constellation_sender,
+                        EmbedderToConstellationMessage::Reload(webview_id),
+                    );
+                    Ok(json!({}))
+                }
+                "Page.getFrameTree" => {
+                    let mut state = self.bridge.state.lock().unwrap();
+                    let frame = frame(state.target_mut(webview_id));
+                    Ok(json!({ "frameTree": { "frame": frame } }))
+                }
+                "Page.captureScreenshot" => self.capture_screenshot(webview_id, params),
+                "Runtime.evaluate" => self.evaluate(webview_id, params),
+                _ => Err(method_not_found(method)),
+            },
+        }
+    }
+
+    /// The webview the command addresses, the one of the session or of the connection.
+    fn webview_id(&self, session_id: Option<&str>) -> Result<WebViewId, CommandError> {
+        self.bridge
+            .state
+            .lock()
+            .unwrap()
+            .session_mut(self.id, session_id)
+            .map(|session| session.webview_id)
+            .ok_or_else(|| match session_id {
+                Some(session_id) => CommandError(
+                    SERVER_ERROR,
+                    format!("Session with given id not found: {session_id}"),
+                ),
+                None => CommandError(
+                    SERVER_ERROR,
+                    "Attach to a target to send it commands".to_string(),
+                ),
+            })
+    }
+
+    fn navigate(&self, webview_id: WebViewId, params: &Value) -> Result<Value, CommandError> {
+        let url = params
+            .get("url")
+            .and_then(Value::as_str)
+            .and_then(|url| url::Url::parse(url).ok())
+            .ok_or_else(|| CommandError(INVALID_PARAMS, "Cannot navigate to invalid URL".into()))?;
+        send_to_constellation(
+            self.constellation_sender,
+            EmbedderToConstellationMessage::LoadUrl(webview_id, ServoUrl::from_url(url)),
+        );
+        let id = target_id(webview_id);
+        Ok(json!({ "frameId": id, "loaderId": id }))
+    }
+
+    fn capture_screenshot(
+        &self,
+        webview_id: WebViewId,
+        params: &Value,
+    ) -> Result<Value, CommandError> {
+        let format = match params.get("format").and_then(Value::as_str) {
+            None | Some("png") => ImageFormat::Png,
+            Some("jpeg") => ImageFormat::Jpeg,
+            Some(format) => {
+                return Err(CommandError(
+                    INVALID_PARAMS,
+                    format!("Unsupported image format {format}"),
+                ));
+            }
+        };
+        let (sender, receiver) =
+            ipc::channel().map_err(|e| CommandError(SERVER_ERROR, e.to_string()))?;
+        send_to_constellation(
+            self.constellation_sender,
+            EmbedderToConstellationMessage::WebDriverCommand(WebDriverCommandMsg::TakeScreenshot(
+                webview_id, None, sender,
+            )),
+        );
+        let image = receiver
+            .try_recv_timeout(COMMAND_TIMEOUT)
+            .ok()
+            .flatten()
+            .and_then(|image| RgbaImage::from_raw(image.width, image.height, image.bytes.to_vec()))
+            .ok_or_else(|| CommandError(SERVER_ERROR, "Unable to capture screenshot".into()))?;
+        let image = match format {
+            // JPEG has no alpha channel
+            ImageFormat::Jpeg => DynamicImage::ImageRgb8(DynamicImage::ImageRgba8(image).to_rgb8()),
+            _ => DynamicImage::ImageRgba8(image),
+        };
+        let mut data = Vec::new();
+        image
+            .write_to(&mut Cursor::new(&mut data), format)
+            .map_err(|e| CommandError(SERVER_ERROR, e.to_string()))?;
+        Ok(json!({ "data": STANDARD.encode(data) }))
+    }
+
+    fn evaluate(&self, webview_id: WebViewId, params: &Value) -> Result<Value, CommandError> {
+        let Some(expression) = params.get("expression").and_then(Value::as_str) else {
+            return Err(CommandError(
+                INVALID_PARAMS,
+                "expression is required".into(),
+            ));
+        };
+        let script = format!(
+            "{EVALUATE_SCRIPT}({})",
+            serde_json::to_string(expression).unwrap()
+        );
+        let result = match frames::execute(
+            self.constellation_sender,
+            webview_id.0,
+            script,
+            COMMAND_TIMEOUT,
+        ) {
+            Some(Ok(WebDriverJSValue::String(json))) => {
+                serde_json::from_str::<Value>(&json).unwrap_or_default()
+            }
+            Some(Ok(value)) => {
+                return Err(CommandError(
+                    SERVER_ERROR,
+                    format!("Unexpected script result: {value:?}"),
+                ));
+            }
+            Some(Err(error)) => return Err(CommandError(SERVER_ERROR, format!("{error:?}"))),
+            None => {
+                return Err(CommandError(
+                    SERVER_ERROR,
+                    "The page didn't respond".to_string(),
+                ));
+            }
+        };
+        match result.get("exception").and_then(Value::as_str) {
+            Some(exception) => Ok(json!({
+                "result": {
+                    "type": "object",
+                    "subtype": "error",
+                    "className": "Error",
+                    "description": exception,
+                },
+                "exceptionDetails": {
+                    "exceptionId": 1,
+                    "text": "Uncaught",
+                    "lineNumber": 0,
+                    "columnNumber": 0,
+                    "exception": { "type": "object", "subtype": "error", "description": exception },
+                },
+            })),
+            None => Ok(json!({ "result": result })),
+        }
+    }
+}
+
+fn method_not_found(method: &str) -> CommandError {
+    CommandError(METHOD_NOT_FOUND, format!("'{method}' wasn't found"))
+}
+
+fn error_response(id: Value, session_id: Option<String>, code: i64, message: String) -> Value {
+    let mut response = json!({ "id": id, "error": { "code": code, "message": message } });
+    if let Some(session_id) = session_id {
+        response["sessionId"] = Value::from(session_id);
+    }
+    response
+}
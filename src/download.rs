@@ -1,10 +1,12 @@
 use std::{fs::File, io::Write, str::FromStr, time::Duration};
 
+use base::id::WebViewId;
 use ipc_channel::ipc::IpcSender;
 use mime::Mime;
 use reqwest::{Client, Response};
 use serde::{Deserialize, Serialize};
 use url::Url;
+use versoview_messages::{DownloadState, DownloadUpdate};
 
 use crate::{
     utils::content_disposition_parser::{DispositionType, parse_content_disposition},
@@ -36,14 +38,27 @@ impl Default for DownloadId {
     }
 }
 
+/// Control message sent from the downloads page or the controller to a running download
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub enum DownloadControl {
+    /// Stop reading the body until the download is resumed
+    Pause,
+    /// Continue a paused download
+    Resume,
+    /// Stop the download and remove its temporary file
+    Cancel,
+}
+
 /// Download status
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct DownloadItem {
     id: DownloadId,
     filename: String,
     file_size: u64,
-    url: String,
-    /// The status of the download
+    /// The number of bytes written to the file
+    received: u64,
+    url: Url,
+    /// The status of the download, as shown on the downloads page
     pub status: String,
     /// The progress of the download
     pub progress: f64,
@@ -51,22 +66,29 @@ pub struct DownloadItem {
     created_at: i64,
     /// Whether the download is stopped
     pub stopped: bool,
-    abort_sender: Option<IpcSender<bool>>,
+    state: DownloadState,
+    control_sender: Option<IpcSender<DownloadControl>>,
 }
 
 impl DownloadItem {
     /// Create a new download status
-    pub fn new(url: String, file_name: String, abort_sender: Option<IpcSender<bool>>) -> Self {
+    pub fn new(
+        url: Url,
+        file_name: String,
+        control_sender: Option<IpcSender<DownloadControl>>,
+    ) -> Self {
         Self {
             id: DownloadId::new(),
             status: "Waiting".to_string(),
             url,
             filename: file_name,
             file_size: 0,
+            received: 0,
             progress: 0.0,
             created_at: chrono::Local::now().timestamp_millis(),
             stopped: false,
-            abort_sender,
+            state: DownloadState::Waiting,
+            control_sender,
         }
     }
 
@@ -75,10 +97,52 @@ impl DownloadItem {
         &self.id
     }
 
+    /// Pause, resume or cancel the download, if it's still running
+    pub fn control(&mut self, control: DownloadControl) {
+        if self.stopped {
+            return;
+        }
+        if let Some(sender) = &self.control_sender {
+            let _ = sender.send(control);
+        }
+        if control == DownloadControl::Cancel {
+            self.control_sender = None;
+        }
+    }
+
     /// Abort the download
     pub fn abort(&mut self) {
-        if let Some(sender) = self.abort_sender.take() {
-            let _ = sender.send(true);
+        self.control(DownloadControl::Cancel);
+    }
+
+    /// Apply the changes sent by the download task
+    pub fn update(&mut self, update: UpdateDownloadState) {
+        if let Some(state) = update.state {
+            self.status = match &state {
+                DownloadState::Waiting => "Waiting".to_string(),
+                DownloadState::Downloading => "Downloading".to_string(),
+                DownloadState::Paused => "Paused".to_string(),
+                DownloadState::Finished => "Finished".to_string(),
+                DownloadState::Cancelled => "Cancelled".to_string(),
+                DownloadState::Failed(error) => format!("Error: {error}"),
+            };
+            self.stopped = matches!(
+                state,
+                DownloadState::Finished | DownloadState::Cancelled | DownloadState::Failed(_)
+            );
+            if self.stopped {
+                self.control_sender = None;
+            }
+            self.state = state;
+        }
+        if let Some(received) = update.received {
+            self.received = received;
+            if self.file_size > 0 {
+                self.progress = received as f64 / self.file_size as f64 * 100.0;
+            }
+        }
+        if self.state == DownloadState::Finished {
+            self.progress = 100.0;
         }
     }
 
@@ -87,6 +151,18 @@ impl DownloadItem {
         serde_json::to_string(self).unwrap()
     }
 
+    /// The download as reported to the controller
+    pub fn to_update(&self) -> DownloadUpdate {
+        DownloadUpdate {
+            id: self.id.0.clone(),
+            url: self.url.clone(),
+            file_name: self.filename.clone(),
+            received_bytes: self.received,
+            total_bytes: (self.file_size > 0).then_some(self.file_size),
+            state: self.state.clone(),
+        }
+    }
+
     fn set_file_size(&mut self, file_size: u64) {
         self.file_size = file_size;
     }
@@ -95,12 +171,10 @@ impl DownloadItem {
 /// Update download state
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct UpdateDownloadState {
-    /// The status of the download
-    pub status: Option<String>,
-    /// The progress of the download
-    pub progress: Option<f64>,
-    /// Whether the download is stopped
-    pub stopped: Option<bool>,
+    /// The new state of the download
+    pub state: Option<DownloadState>,
+    /// The number of bytes written to the file
+    pub received: Option<u64>,
 }
 
 // TODO: support `multipart/form-data`
@@ -157,8 +231,18 @@ fn should_download_content_type(content_type: Mime) -> bool {
 pub(crate) async fn download_body(
     url: Url,
     mut resp: reqwest::Response,
+    webview_id: WebViewId,
+    max_size: Option<u64>,
     verso_internal_sender: IpcSender<VersoInternalMsg>,
 ) {
+    // Downloads known to exceed the budget of the webview are refused without asking
+    let exceeds_limit = |size: u64| max_size.is_some_and(|max_size| size > max_size);
+    if resp.content_length().is_some_and(exceeds_limit) {
+        log::warn!("[Download] Download of {url} exceeds the size limit of the webview");
+        let _ = verso_internal_sender.send(VersoInternalMsg::DownloadSizeLimitExceeded(webview_id));
+        return;
+    }
+
     let filename = resp
         .headers()
         .get(reqwest::header::CONTENT_DISPOSITION)
@@ -197,11 +281,11 @@ pub(crate) async fn download_body(
         chrono::Utc::now().timestamp()
     ));
 
-    // Create a channel to abort the download. Abort the download if we receive `true` from the channel.
-    let (abort_sender, abort_receiver) = ipc_channel::ipc::channel::<bool>().unwrap();
+    // Create a channel to pause, resume and cancel the download.
+    let (control_sender, control_receiver) = ipc_channel::ipc::channel().unwrap();
 
     /* -- START DOWNLOAD --*/
-    let mut download = DownloadItem::new(url.to_string(), filename, Some(abort_sender));
+    let mut download = DownloadItem::new(url.clone(), filename, Some(control_sender));
 
     // Create a dummy file with a temporary name.
     let mut file = match File::create_new(&temp_file_path) {
@@ -213,9 +297,13 @@ pub(crate) async fn download_body(
                 log::error!("[Download] Failed to create temporary file");
             }
 
-            download.status = "Error: Failed to create temporary file.".to_string();
-            download.stopped = true;
-
+            download.update(UpdateDownloadState {
+                state: Some(DownloadState::Failed(
+                    "Failed to create temporary file.".to_string(),
+                )),
+                received: None,
+            });
+            let _ = verso_internal_sender.send(VersoInternalMsg::CreateDownload(download));
             return;
         }
     };
@@ -231,8 +319,15 @@ pub(crate) async fn download_body(
         download.set_file_size(file_size);
         if file.set_len(file_size).is_err() {
             log::error!("[Download] Failed to allocate space for file");
-            download.status = "Error: No space left on device.".to_string();
-            download.stopped = true;
+            drop(file);
+            let _ = std::fs::remove_file(&temp_file_path);
+            download.update(UpdateDownloadState {
+                state: Some(DownloadState::Failed(
+                    "No space left on device.".to_string(),
+                )),
+                received: None,
+            });
+            let _ = verso_internal_sender.send(VersoInternalMsg::CreateDownload(download));
             return;
         }
     }
@@ -243,57 +338,124 @@ pub(crate) async fn download_body(
     // Send a initial message to the main process to create a download status on the downloads page.
     let _ = verso_internal_sender.send(VersoInternalMsg::CreateDownload(download));
 
-    let mut wrote_bytes: usize = 0;
+    let mut wrote_bytes: u64 = 0;
     let mut last_update = tokio::time::Instant::now();
 
     send_update_to_verso(
         &verso_internal_sender,
         &download_id,
-        Some("Downloading".to_string()),
-        None,
+        Some(DownloadState::Downloading),
         None,
     );
 
     // Read the response body in chunks and write to the file.
-    while let Ok(chunk) = resp.chunk().await {
-        if let Ok(abort) = abort_receiver.try_recv() {
-            if abort {
+    loop {
+        match control_receiver.try_recv() {
+            Ok(DownloadControl::Pause) => {
+                send_update_to_verso(
+                    &verso_internal_sender,
+                    &download_id,
+                    Some(DownloadState::Paused),
+                    Some(wrote_bytes),
+                );
+                // The body isn't read while paused, the server holds the rest of it back
+                let control = loop {
+                    match control_receiver.try_recv() {
+                        Ok(DownloadControl::Pause) => {}
+                        Ok(control) => break control,
+                        Err(ipc_channel::ipc::TryRecvError::Empty) => {
+                            tokio::time::sleep(Duration::from_millis(100)).await;
+                        }
+                        Err(_) => break DownloadControl::Cancel,
+                    }
+                };
+                if control == DownloadControl::Resume {
+                    send_update_to_verso(
+                        &verso_internal_sender,
+                        &download_id,
+                        Some(DownloadState::Downloading),
+                        None,
+                    );
+                    continue;
+                }
+                drop(file);
+                let _ = std::fs::remove_file(&temp_file_path);
                 send_update_to_verso(
                     &verso_internal_sender,
                     &download_id,
-                    Some("Cancelled".to_string()),
+                    Some(DownloadState::Cancelled),
                     None,
-                    Some(true),
                 );
                 return;
             }
+            Ok(DownloadControl::Cancel) => {
+                drop(file);
+                let _ = std::fs::remove_file(&temp_file_path);
+                send_update_to_verso(
+                    &verso_internal_sender,
+                    &download_id,
+                    Some(DownloadState::Cancelled),
+                    None,
+                );
+                return;
+            }
+            Ok(DownloadControl::Resume) | Err(_) => {}
         }
 
+        let chunk = match resp.chunk().await {
+            Ok(chunk) => chunk,
+            Err(error) => {
+                log::error!("[Download] Failed to read the body of {url}: {error}");
+                drop(file);
+                let _ = std::fs::remove_file(&temp_file_path);
+                send_update_to_verso(
+                    &verso_internal_sender,
+                    &download_id,
+                    Some(DownloadState::Failed("Connection lost.".to_string())),
+                    Some(wrote_bytes),
+                );
+                return;
+            }
+        };
+
         if let Some(bytes) = chunk {
             if file.write_all(&bytes).is_err() {
                 send_update_to_verso(
                     &verso_internal_sender,
                     &download_id,
-                    Some("Error: Failed to write to file.".to_string()),
+                    Some(DownloadState::Failed(
+                        "Failed to write to file.".to_string(),
+                    )),
                     None,
-                    Some(true),
                 );
                 return;
             }
 
-            wrote_bytes += bytes.len();
+            wrote_bytes += bytes.len() as u64;
+            if exceeds_limit(wrote_bytes) {
+                drop(file);
+                let _ = std::fs::remove_file(&temp_file_path);
+                send_update_to_verso(
+                    &verso_internal_sender,
+                    &download_id,
+                    Some(DownloadState::Failed(
+                        "Exceeded the size limit.".to_string(),
+                    )),
+                    None,
+                );
+                let _ = verso_internal_sender
+                    .send(VersoInternalMsg::DownloadSizeLimitExceeded(webview_id));
+                return;
+            }
 
             // Update the progress with throttling
             if last_update.elapsed() >= Duration::from_millis(500) {
-                if let Some(file_size) = content_length {
-                    send_update_to_verso(
-                        &verso_internal_sender,
-                        &download_id,
-                        None,
-                        Some(wrote_bytes as f64 / file_size as f64 * 100.0),
-                        None,
-                    );
-                }
+                send_update_to_verso(
+                    &verso_internal_sender,
+                    &download_id,
+                    None,
+                    Some(wrote_bytes),
+                );
                 last_update = tokio::time::Instant::now();
             }
         } else {
@@ -304,18 +466,18 @@ pub(crate) async fn download_body(
                 send_update_to_verso(
                     &verso_internal_sender,
                     &download_id,
-                    Some("Finished".to_string()),
-                    Some(100.0),
-                    Some(true),
+                    Some(DownloadState::Finished),
+                    Some(wrote_bytes),
                 );
             } else {
                 log::error!("[Download] Failed to rename dummy file back to original filename");
                 send_update_to_verso(
                     &verso_internal_sender,
                     &download_id,
-                    Some("Error: Failed to rename temporary file.".to_string()),
+                    Some(DownloadState::Failed(
+                        "Failed to rename temporary file.".to_string(),
+                    )),
                     None,
-                    Some(true),
                 );
             }
 
@@ -327,16 +489,11 @@ pub(crate) async fn download_body(
 fn send_update_to_verso(
     verso_internal_sender: &IpcSender<VersoInternalMsg>,
     download_id: &DownloadId,
-    status: Option<String>,
-    progress: Option<f64>,
-    stopped: Option<bool>,
+    state: Option<DownloadState>,
+    received: Option<u64>,
 ) {
     let _ = verso_internal_sender.send(VersoInternalMsg::UpdateDownload(
         download_id.clone(),
-        UpdateDownloadState {
-            status,
-            progress,
-            stopped,
-        },
+        UpdateDownloadState { state, received },
     ));
 }
@@ -0,0 +1,431 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    thread,
+    time::Duration,
+};
+
+use base::id::WebViewId;
+use base64::{Engine, engine::general_purpose::STANDARD};
+use constellation_traits::EmbedderToConstellationMessage;
+use crossbeam_channel::Sender;
+use embedder_traits::WebDriverJSValue;
+use serde::{Deserialize, Serialize};
+use servo_url::ServoUrl;
+
+use crate::{
+    compositor::IOCompositor, frames, resource_override::guess_content_type,
+    verso::send_to_constellation, window::Window,
+};
+
+/// How long the page may take to handle a drag event.
+const DRAG_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Largest dropped file given to the page, bigger ones are only in the URLs of the drop.
+const MAX_DROPPED_FILE_SIZE: u64 = 64 * 1024 * 1024;
+
+/// Script which fires the drag events of files dragged from other apps at the element under the
+/// pointer, since Servo doesn't fire them, and drags the links dragged out of the page to other
+/// apps with the `DRAG_LINK` request of the Verso bridge.
+pub(crate) const DRAG_DROP_SCRIPT: &str = r#"
+(() => {
+  if (window.versoDragDrop) {
+    return;
+  }
+
+  let target = null;
+  let names = [];
+
+  const transfer = (files) => {
+    const dataTransfer = new DataTransfer();
+    for (const file of files) {
+      dataTransfer.items.add(file);
+    }
+    return dataTransfer;
+  };
+
+  // Files are listed while they're dragged, but only their drop gives their content
+  const listed = () => transfer(names.map((name) => new File([], name)));
+
+  // Fire the drag event at the element, `true` if the page canceled it
+  const fire = (type, element, x, y, dataTransfer) => {
+    const event = new DragEvent(type, {
+      bubbles: true,
+      cancelable: type !== 'dragleave',
+      composed: true,
+      clientX: x,
+      clientY: y,
+      dataTransfer,
+    });
+    return !element.dispatchEvent(event);
+  };
+
+  // Move the drag to the element under the point, `true` if the page accepts a drop there
+  const over = (x, y, dataTransfer) => {
+    const element = document.elementFromPoint(x, y) ?? document.documentElement;
+    if (element !== target) {
+      fire('dragenter', element, x, y, dataTransfer);
+      if (target) {
+        fire('dragleave', target, x, y, dataTransfer);
+      }
+      target = element;
+    }
+    return fire('dragover', element, x, y, dataTransfer);
+  };
+
+  window.versoDragDrop = {
+    over(x, y, fileNames) {
+      names = fileNames;
+      return over(x, y, listed());
+    },
+    leave() {
+      if (target) {
+        fire('dragleave', target, 0, 0, listed());
+      }
+      target = null;
+    },
+    // Drop the files, `true` if the page handled them
+    drop(x, y, files, urls) {
+      const dataTransfer = transfer(files.map(({ name, type, data }) => {
+        const bytes = Uint8Array.from(atob(data), (c) => c.charCodeAt(0));
+        return new File([bytes], name, { type });
+      }));
+      dataTransfer.setData('text/uri-list', urls.join('\r\n'));
+      const handled = over(x, y, dataTransfer) && fire('drop', target, x, y, dataTransfer);
+      target = null;
+      return handled;
+    },
+  };
+
+  // A link pressed and moved is dragged out of the page, unless the page cancels `dragstart`
+  let pressed = null;
+  addEventListener('mousedown', (event) => {
+    const link = event.button === 0 && event.composedPath()
+      .find((element) => element instanceof HTMLAnchorElement && element.href);
+    pressed = link && link.draggable ? { link, x: event.clientX, y: event.clientY } : null;
+  }, true);
+  addEventListener('mouseup', () => {
+    pressed = null;
+  }, true);
+  addEventListener('mousemove', (event) => {
+    if (!pressed || Math.hypot(event.clientX - pressed.x, event.clientY - pressed.y) < 8) {
+      return;
+    }
+    const { link } = pressed;
+    pressed = null;
+    const dataTransfer = new DataTransfer();
+    dataTransfer.setData('text/uri-list', link.href);
+    dataTransfer.setData('text/plain', link.href);
+    if (fire('dragstart', link, event.clientX, event.clientY, dataTransfer)) {
+      return;
+    }
+    const title = link.textContent.trim() || link.title;
+    prompt(`VERSO::DRAG_LINK::${JSON.stringify({ url: link.href, title })}`);
+  }, true);
+})();
+"#;
+
+/// Files dragged over the window from other apps. Winit reports each file of a drag on its own,
+/// without a position, so the drag follows the last position of the pointer in the window.
+#[derive(Debug, Default)]
+pub(crate) struct FileDrag {
+    /// Files of the drag
+    hovered: Vec<PathBuf>,
+    /// Files of the drag dropped so far
+    dropped: Vec<PathBuf>,
+    /// Tab the files are dragged over
+    webview_id: Option<WebViewId>,
+}
+
+impl FileDrag {
+    /// Whether files are dragged over the window.
+    pub(crate) fn is_active(&self) -> bool {
+        !self.hovered.is_empty()
+    }
+}
+
+/// A dropped file given to the page.
+#[derive(Debug, Serialize)]
+struct DroppedFile {
+    name: String,
+    #[serde(rename = "type")]
+    content_type: String,
+    /// Content of the file, in base64
+    data: String,
+}
+
+/// A link the user drags out of the page.
+#[derive(Debug, Deserialize)]
+pub(crate) struct DraggedLink {
+    url: url::Url,
+    #[serde(default)]
+    title: String,
+}
+
+impl Window {
+    /// A file of a drag from another app entered the window.
+    pub(crate) fn file_hovered(
+        &mut self,
+        compositor: &IOCompositor,
+        sender: &Sender<EmbedderToConstellationMessage>,
+        path: &Path,
+    ) {
+        self.file_drag.hovered.push(path.to_path_buf());
+        self.file_drag_moved(compositor, sender);
+    }
+
+    /// Fire the drag events of the dragged files at the tab under the pointer.
+    pub(crate) fn file_drag_moved(
+        &mut self,
+        compositor: &IOCompositor,
+        sender: &Sender<EmbedderToConstellationMessage>,
+    ) {
+        let Some((webview_id, x, y)) = self.drop_target(compositor) else {
+            return;
+        };
+        if let Some(previous) = self.file_drag.webview_id.replace(webview_id) {
+            if previous != webview_id {
+                run_script(
+                    sender,
+                    previous,
+                    "window.versoDragDrop?.leave()".to_string(),
+                );
+            }
+        }
+        let names: Vec<String> = self
+            .file_drag
+            .hovered
+            .iter()
+            .map(|path| file_name(path))
+            .collect();
+        let script = format!(
+            "window.versoDragDrop?.over({x}, {y}, {}) ?? false",
+            serde_json::to_string(&names).unwrap()
+        );
+        run_script(sender, webview_id, script);
+    }
+
+    /// The drag of files left the window, or was cancelled.
+    pub(crate) fn file_drag_cancelled(&mut self, sender: &Sender<EmbedderToConstellationMessage>) {
+        if let Some(webview_id) = std::mem::take(&mut self.file_drag).webview_id {
+            run_script(
+                sender,
+                webview_id,
+                "window.versoDragDrop?.leave()".to_string(),
+            );
+        }
+    }
+
+    /// A file of the drag was dropped on the window. Once every file of the drag is dropped, the
+    /// tab under the pointer gets them, or opens the first one if its page doesn't handle the
+    /// drop.
+    pub(crate) fn file_dropped(
+        &mut self,
+        compositor: &IOCompositor,
+        sender: &Sender<EmbedderToConstellationMessage>,
+        path: &Path,
+    ) {
+        self.file_drag.dropped.push(path.to_path_buf());
+        if self.file_drag.dropped.len() < self.file_drag.hovered.len() {
+            return;
+        }
+        let drag = std::mem::take(&mut self.file_drag);
+        let Some((webview_id, x, y)) = self.drop_target(compositor) else {
+            return;
+        };
+        if let Some(previous) = drag.webview_id.filter(|previous| *previous != webview_id) {
+            run_script(
+                sender,
+                previous,
+                "window.versoDragDrop?.leave()".to_string(),
+            );
+        }
+        drop_files(sender.clone(), webview_id, x, y, drag.dropped);
+    }
+
+    /// The tab under the pointer, or else the current tab, with the position of the pointer in
+    /// the CSS pixels of its page.
+    fn drop_target(&self, compositor: &IOCompositor) -> Option<(WebViewId, f32, f32)> {
+        let point = self
+            .mouse_position
+            .get()
+            .map(|position| self.content_point(position));
+        let webview_id = point
+            .and_then(|point| compositor.webview_id_from_point(point))
+            .filter(|webview_id| self.tab_manager.tab(*webview_id).is_some())
+            .or_else(|| self.tab_manager.current_tab_id())?;
+        let rect = self.tab_manager.tab(webview_id)?.webview().rect;
+        let point = (point.unwrap_or(rect.center()) - rect.min.to_vector())
+            / compositor.device_pixels_per_page_pixel(webview_id);
+        Some((webview_id, point.x, point.y))
+    }
+
+    /// Drag the link out of the page to other apps, as an internet shortcut file.
+    pub(crate) fn drag_link(&self, link: DraggedLink) {
+        #[cfg(any(target_os = "macos", target_os = "windows"))]
+        {
+            let path = match link_file(&link) {
+                Ok(path) => path,
+                Err(e) => {
+                    log::error!("Verso failed to write the shortcut of {}: {e}", link.url);
+                    return;
+                }
+            };
+            if let Err(e) = drag::start_drag(
+                &self.window,
+                drag::DragItem::Files(vec![path]),
+                drag::Image::Raw(drag_image()),
+                |_, _| {},
+                drag::Options::default(),
+            ) {
+                log::warn!("Verso failed to drag {}: {e}", link.url);
+            }
+        }
+        #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+        log::debug!(
+            "Verso can't drag {} ({}) to other apps on this platform",
+            link.url,
+            link.title
+        );
+    }
+}
+
+/// Name of the file for the page.
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+fn run_script(
+    sender: &Sender<EmbedderToConstellationMessage>,
+    webview_id: WebViewId,
+    script: String,
+) {
+    let sender = sender.clone();
+    spawn("DragEvent", move || {
+        if let Some(Err(error)) = frames::execute(&sender, webview_id.0, script, DRAG_TIMEOUT) {
+            log::warn!("Verso failed to fire the drag events: {error:?}");
+        }
+    });
+}
+
+/// Read the dropped files and drop them on the page of the webview on another thread.
+fn drop_files(
+    sender: Sender<EmbedderToConstellationMessage>,
+    webview_id: WebViewId,
+    x: f32,
+    y: f32,
+    paths: Vec<PathBuf>,
+) {
+    spawn("DragDrop", move || {
+        let files: Vec<DroppedFile> = paths.iter().filter_map(|path| read_file(path)).collect();
+        let urls: Vec<String> = paths
+            .iter()
+            .filter_map(|path| url::Url::from_file_path(path).ok())
+            .map(String::from)
+            .collect();
+        let script = format!(
+            "window.versoDragDrop?.drop({x}, {y}, {}, {}) ?? false",
+            serde_json::to_string(&files).unwrap(),
+            serde_json::to_string(&urls).unwrap()
+        );
+        match frames::execute(&sender, webview_id.0, script, DRAG_TIMEOUT) {
+            Some(Ok(WebDriverJSValue::Boolean(true))) => {}
+            // Pages which don't handle the drop navigate to the first file, like other browsers
+            Some(Ok(_)) => {
+                if let Some(url) = paths
+                    .first()
+                    .and_then(|path| url::Url::from_file_path(path).ok())
+                {
+                    send_to_constellation(
+                        &sender,
+                        EmbedderToConstellationMessage::LoadUrl(
+                            webview_id,
+                            ServoUrl::from_url(url),
+                        ),
+                    );
+                }
+            }
+            Some(Err(error)) => log::warn!("Verso failed to drop the files: {error:?}"),
+            None => {}
+        }
+    });
+}
+
+/// Read the dropped file for the page, `None` if it's a directory or too big.
+fn read_file(path: &Path) -> Option<DroppedFile> {
+    let metadata = fs::metadata(path)
+        .ok()
+        .filter(|metadata| metadata.is_file())?;
+    if metadata.len() > MAX_DROPPED_FILE_SIZE {
+        log::warn!(
+            "Verso doesn't give the dropped file {} to the page, it's too big",
+            path.display()
+        );
+        return None;
+    }
+    match fs::read(path) {
+        Ok(data) => Some(DroppedFile {
+            name: file_name(path),
+            content_type: guess_content_type(path).to_string(),
+            data: STANDARD.encode(data),
+        }),
+        Err(e) => {
+            log::warn!(
+                "Verso failed to read the dropped file {}: {e}",
+                path.display()
+            );
+            None
+        }
+    }
+}
+
+/// Write the internet shortcut of the link in the temporary directory, the file other apps get
+/// when it's dropped on them.
+#[cfg(any(target_os = "macos", target_os = "windows"))]
+fn link_file(link: &DraggedLink) -> std::io::Result<PathBuf> {
+    let directory = std::env::temp_dir().join("verso-dragged-links");
+    fs::create_dir_all(&directory)?;
+    let name: String = link
+        .title
+        .chars()
+        .filter(|c| !c.is_control() && !r#"\/:*?"<>|"#.contains(*c))
+        .take(64)
+        .collect();
+    let name = match name.trim() {
+        "" => link.url.host_str().unwrap_or("Link").to_string(),
+        name => name.to_string(),
+    };
+    #[cfg(target_os = "windows")]
+    let (extension, contents) = ("url", format!("[InternetShortcut]\r\nURL={}\r\n", link.url));
+    #[cfg(target_os = "macos")]
+    let (extension, contents) = (
+        "webloc",
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0"><dict><key>URL</key><string>{}</string></dict></plist>
+"#,
+            link.url.as_str().replace('&', "&amp;")
+        ),
+    );
+    let path = directory.join(format!("{name}.{extension}"));
+    fs::write(&path, contents)?;
+    Ok(path)
+}
+
+/// Image shown under the pointer while a link is dragged, the system shows the shortcut file.
+#[cfg(any(target_os = "macos", target_os = "windows"))]
+fn drag_image() -> Vec<u8> {
+    let mut png = Vec::new();
+    let _ = image::RgbaImage::new(1, 1)
+        .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png);
+    png
+}
+
+fn spawn(name: &str, f: impl FnOnce() + Send + 'static) {
+    if let Err(e) = thread::Builder::new().name(name.to_owned()).spawn(f) {
+        log::error!("Failed to spawn {name} thread: {e}");
+    }
+}
@@ -0,0 +1,132 @@
+/// Resources compiled into the binary with the `embed-resources` feature, by their path in the
+/// resources directory. Files in the resources directory override them, so Verso can run from a
+/// single file and still have its internal pages customized.
+#[cfg(feature = "embed-resources")]
+const RESOURCES: &[(&str, &[u8])] = &[
+    (
+        "about-memory.html",
+        include_bytes!("../resources/about-memory.html"),
+    ),
+    ("badcert.html", include_bytes!("../resources/badcert.html")),
+    (
+        "components/bookmark.html",
+        include_bytes!("../resources/components/bookmark.html"),
+    ),
+    (
+        "components/command_palette.html",
+        include_bytes!("../resources/components/command_palette.html"),
+    ),
+    (
+        "components/context_menu.html",
+        include_bytes!("../resources/components/context_menu.html"),
+    ),
+    (
+        "components/crashes.html",
+        include_bytes!("../resources/components/crashes.html"),
+    ),
+    (
+        "components/downloads.html",
+        include_bytes!("../resources/components/downloads.html"),
+    ),
+    (
+        "components/find_bar.html",
+        include_bytes!("../resources/components/find_bar.html"),
+    ),
+    (
+        "components/history.html",
+        include_bytes!("../resources/components/history.html"),
+    ),
+    (
+        "components/history_menu.html",
+        include_bytes!("../resources/components/history_menu.html"),
+    ),
+    (
+        "components/panel.html",
+        include_bytes!("../resources/components/panel.html"),
+    ),
+    (
+        "components/prompt/alert.html",
+        include_bytes!("../resources/components/prompt/alert.html"),
+    ),
+    (
+        "components/prompt/allow_deny.html",
+        include_bytes!("../resources/components/prompt/allow_deny.html"),
+    ),
+    (
+        "components/prompt/http_basic_auth.html",
+        include_bytes!("../resources/components/prompt/http_basic_auth.html"),
+    ),
+    (
+        "components/prompt/leave_stay.html",
+        include_bytes!("../resources/components/prompt/leave_stay.html"),
+    ),
+    (
+        "components/prompt/ok_cancel.html",
+        include_bytes!("../resources/components/prompt/ok_cancel.html"),
+    ),
+    (
+        "components/prompt/prompt.css",
+        include_bytes!("../resources/components/prompt/prompt.css"),
+    ),
+    (
+        "components/prompt/prompt.html",
+        include_bytes!("../resources/components/prompt/prompt.html"),
+    ),
+    (
+        "components/site_settings.html",
+        include_bytes!("../resources/components/site_settings.html"),
+    ),
+    ("crash.html", include_bytes!("../resources/crash.html")),
+    (
+        "directory-listing.html",
+        include_bytes!("../resources/directory-listing.html"),
+    ),
+    (
+        "gatt_blocklist.txt",
+        include_bytes!("../resources/gatt_blocklist.txt"),
+    ),
+    (
+        "hsts_preload.json",
+        include_bytes!("../resources/hsts_preload.json"),
+    ),
+    (
+        "media-controls.css",
+        include_bytes!("../resources/media-controls.css"),
+    ),
+    (
+        "media-controls.js",
+        include_bytes!("../resources/media-controls.js"),
+    ),
+    (
+        "neterror.html",
+        include_bytes!("../resources/neterror.html"),
+    ),
+    (
+        "presentational-hints.css",
+        include_bytes!("../resources/presentational-hints.css"),
+    ),
+    (
+        "public_domains.txt",
+        include_bytes!("../resources/public_domains.txt"),
+    ),
+    (
+        "quirks-mode.css",
+        include_bytes!("../resources/quirks-mode.css"),
+    ),
+    ("rippy.png", include_bytes!("../resources/rippy.png")),
+    ("servo.css", include_bytes!("../resources/servo.css")),
+    (
+        "user-agent.css",
+        include_bytes!("../resources/user-agent.css"),
+    ),
+];
+#[cfg(not(feature = "embed-resources"))]
+const RESOURCES: &[(&str, &[u8])] = &[];
+
+/// Get the embedded resource at the path, relative to the resources directory.
+pub(crate) fn get(path: &str) -> Option<&'static [u8]> {
+    RESOURCES
+        .iter()
+        .find(|(resource_path, _)| *resource_path == path)
+        .map(|(_, bytes)| *bytes)
+}
@@ -17,4 +17,19 @@ pub enum Error {
     /// IPC errors.
     #[error(transparent)]
     IpcError(#[from] ipc_channel::ipc::IpcError),
+    /// IO errors.
+    #[error(transparent)]
+    IoError(#[from] std::io::Error),
+    /// SQLite database errors.
+    #[error(transparent)]
+    SqliteError(#[from] rusqlite::Error),
+    /// JSON errors.
+    #[error(transparent)]
+    JsonError(#[from] serde_json::Error),
+    /// The browser profile to import from isn't found.
+    #[error("browser profile not found")]
+    ProfileNotFound,
+    /// OS keychain errors.
+    #[error(transparent)]
+    SecretError(#[from] keyring::Error),
 }
@@ -0,0 +1,60 @@
+use std::path::{Path, PathBuf};
+
+use embedder_traits::FilterPattern;
+use winit::window::Window as WinitWindow;
+
+/// Show the file dialog of the platform for `<input type="file">` over the window, `None` if the
+/// user cancelled it. The filter patterns are the extensions of the types the input accepts,
+/// Servo resolves the MIME types of its `accept` attribute to them.
+pub(crate) fn pick_files(
+    window: &WinitWindow,
+    filter_patterns: &[FilterPattern],
+    allow_multiple: bool,
+) -> Option<Vec<PathBuf>> {
+    let mut dialog = rfd::FileDialog::new().set_parent(window);
+    if !filter_patterns.is_empty() {
+        let extensions: Vec<&str> = filter_patterns
+            .iter()
+            .map(|pattern| pattern.0.as_str())
+            .collect();
+        // `accept` is a hint, users may still pick other files
+        dialog = dialog
+            .add_filter("Accepted files", &extensions)
+            .add_filter("All files", &["*"]);
+    }
+    if allow_multiple {
+        dialog.pick_files()
+    } else {
+        dialog.pick_file().map(|file| vec![file])
+    }
+}
+
+/// The files of the override which the input accepts, like a dialog showing only them, `None` if
+/// there are none.
+pub(crate) fn pick_override(
+    paths: &[PathBuf],
+    filter_patterns: &[FilterPattern],
+    allow_multiple: bool,
+) -> Option<Vec<PathBuf>> {
+    let mut files: Vec<PathBuf> = paths
+        .iter()
+        .filter(|path| accepts(filter_patterns, path))
+        .cloned()
+        .collect();
+    if !allow_multiple {
+        files.truncate(1);
+    }
+    (!files.is_empty()).then_some(files)
+}
+
+fn accepts(filter_patterns: &[FilterPattern], path: &Path) -> bool {
+    filter_patterns.is_empty()
+        || path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .is_some_and(|extension| {
+                filter_patterns
+                    .iter()
+                    .any(|pattern| pattern.0.eq_ignore_ascii_case(extension))
+            })
+}
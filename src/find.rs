@@ -0,0 +1,179 @@
+use std::{thread, time::Duration};
+
+use base::id::WebViewId;
+use constellation_traits::EmbedderToConstellationMessage;
+use crossbeam_channel::Sender;
+use embedder_traits::WebDriverJSValue;
+use versoview_messages::{FindOptions, FindResult};
+
+use crate::frames;
+
+/// How long the page is given to search its text.
+const FIND_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Script finding the text passed as argument in the document, and wrapping the matches in
+/// marks. Finding the same text again moves to the next match, `null` removes the marks. It
+/// keeps its state on the window, and searches again if the page replaced the marks. Only the
+/// document of the top frame is searched.
+const FIND_SCRIPT: &str = r#"
+((query, caseSensitive, backwards) => {
+  const ATTRIBUTE = 'data-verso-find';
+  const ACTIVE = 'data-verso-find-active';
+  const MAX_MATCHES = 1000;
+
+  const state = (window.__versoFindState ??= { query: null, caseSensitive: false, matches: 0, active: -1 });
+  const marks = () => document.querySelectorAll(`mark[${ATTRIBUTE}]`);
+  const result = () =>
+    JSON.stringify({ matches: state.matches, activeMatch: state.matches > 0 ? state.active : null });
+
+  const clear = () => {
+    for (const mark of marks()) {
+      mark.replaceWith(...mark.childNodes);
+    }
+    document.body?.normalize();
+    state.query = null;
+    state.matches = 0;
+    state.active = -1;
+  };
+
+  // Wrap the part of the text node between the offsets in a mark of the match
+  const wrap = ({ node, start: nodeStart }, start, end, index) => {
+    const nodeEnd = nodeStart + node.data.length;
+    if (nodeEnd <= start || nodeStart >= end || node.data.length === 0) {
+      return;
+    }
+    let target = node;
+    if (start > nodeStart) {
+      target = target.splitText(start - nodeStart);
+    }
+    if (end < nodeEnd) {
+      target.splitText(end - Math.max(start, nodeStart));
+    }
+    const mark = document.createElement('mark');
+    mark.setAttribute(ATTRIBUTE, index);
+    target.replaceWith(mark);
+    mark.appendChild(target);
+  };
+
+  const search = () => {
+    const nodes = [];
+    let text = '';
+    const walker = document.createTreeWalker(document.body, NodeFilter.SHOW_TEXT, {
+      acceptNode: (node) =>
+        node.parentElement?.closest('script, style, noscript, template, textarea')
+          ? NodeFilter.FILTER_REJECT
+          : NodeFilter.FILTER_ACCEPT,
+    });
+    while (walker.nextNode()) {
+      nodes.push({ node: walker.currentNode, start: text.length });
+      text += walker.currentNode.data;
+    }
+    const escaped = query.replace(/[.*+?^${}()|[\]\\]/g, '\\$&');
+    const ranges = [];
+    for (const match of text.matchAll(new RegExp(escaped, caseSensitive ? 'g' : 'gi'))) {
+      ranges.push([match.index, match.index + match[0].length]);
+      if (ranges.length === MAX_MATCHES) {
+        break;
+      }
+    }
+    // Wrapped from the last match, splitting a text node keeps the part before the split in it
+    let last = nodes.length - 1;
+    for (let index = ranges.length - 1; index >= 0; index--) {
+      const [start, end] = ranges[index];
+      while (last > 0 && nodes[last].start >= end) {
+        last--;
+      }
+      for (let i = last; i >= 0 && nodes[i].start + nodes[i].node.data.length >= start; i--) {
+        wrap(nodes[i], start, end, index);
+      }
+    }
+    return ranges.length;
+  };
+
+  const select = (index) => {
+    state.active = index;
+    let first = null;
+    for (const mark of marks()) {
+      const active = Number(mark.getAttribute(ATTRIBUTE)) === index;
+      mark.toggleAttribute(ACTIVE, active);
+      if (active && !first) {
+        first = mark;
+      }
+    }
+    first?.scrollIntoView({ block: 'center', inline: 'nearest' });
+  };
+
+  if (!document.body) {
+    return result();
+  }
+  if (!query) {
+    clear();
+    return result();
+  }
+  if (!document.getElementById('verso-find-style')) {
+    const style = document.createElement('style');
+    style.id = 'verso-find-style';
+    style.textContent = `mark[${ATTRIBUTE}] { background: #fff176; color: inherit; }
+      mark[${ACTIVE}] { background: #ff9632; }`;
+    (document.head ?? document.body).appendChild(style);
+  }
+  const sameSearch = state.query === query && state.caseSensitive === caseSensitive;
+  if (sameSearch && state.matches > 0 && marks().length > 0) {
+    select((state.active + (backwards ? -1 : 1) + state.matches) % state.matches);
+    return result();
+  }
+  clear();
+  state.matches = search();
+  state.query = query;
+  state.caseSensitive = caseSensitive;
+  if (state.matches > 0) {
+    select(backwards ? state.matches - 1 : 0);
+  }
+  return result();
+})
+"#;
+
+/// Script finding the text in the document, or removing the marks of the matches if `None`.
+pub(crate) fn find_script(text: Option<&str>, options: FindOptions) -> String {
+    format!(
+        "{FIND_SCRIPT}({}, {}, {})",
+        serde_json::to_string(&text).unwrap(),
+        options.case_sensitive,
+        options.backwards
+    )
+}
+
+/// Find the text in the webview on another thread, so a busy page doesn't block Verso, and call
+/// back with the matches. Pages which don't answer in time have no match.
+pub(crate) fn find_in_page(
+    constellation_sender: Sender<EmbedderToConstellationMessage>,
+    webview_id: WebViewId,
+    text: Option<String>,
+    options: FindOptions,
+    callback: impl FnOnce(FindResult) + Send + 'static,
+) {
+    let result = thread::Builder::new()
+        .name("FindInPage".to_owned())
+        .spawn(move || {
+            let script = find_script(text.as_deref(), options);
+            let result =
+                match frames::execute(&constellation_sender, webview_id.0, script, FIND_TIMEOUT) {
+                    Some(Ok(WebDriverJSValue::String(json))) => {
+                        serde_json::from_str(&json).unwrap_or_default()
+                    }
+                    Some(Ok(_)) => FindResult::default(),
+                    Some(Err(error)) => {
+                        log::warn!("Verso failed to find the text in the page: {error:?}");
+                        FindResult::default()
+                    }
+                    None => {
+                        log::warn!("The page didn't answer the search in time");
+                        FindResult::default()
+                    }
+                };
+            callback(result);
+        });
+    if let Err(e) = result {
+        log::error!("Failed to spawn find in page thread: {e}");
+    }
+}
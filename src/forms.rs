@@ -0,0 +1,177 @@
+use base::id::WebViewId;
+use embedder_traits::PromptResponse;
+use ipc_channel::ipc::IpcSender;
+use serde::Deserialize;
+use servo_url::ServoUrl;
+use versoview_messages::{FormField, FormSubmission, FormSubmissionResponse, ToControllerMessage};
+
+use crate::window::Window;
+
+/// Script which asks Verso what to do with every form the page submits with the
+/// `FORM_SUBMISSION` request of the Verso bridge, both with the `submit` event and with
+/// `form.submit()`. The prompt is answered with nothing to submit the form as it is, `block`, or
+/// the fields to submit instead.
+pub(crate) const FORM_SUBMISSION_SCRIPT: &str = r#"
+(() => {
+  if (window.versoFormSubmission) {
+    return;
+  }
+  window.versoFormSubmission = true;
+
+  const nativeSubmit = HTMLFormElement.prototype.submit;
+
+  const fields = (form, submitter) => {
+    let data;
+    try {
+      data = new FormData(form, submitter ?? undefined);
+    } catch {
+      data = new FormData(form);
+    }
+    return [...data].map(([name, value]) => ({
+      name,
+      value: typeof value === 'string' ? value : value.name,
+    }));
+  };
+
+  const check = (form, submitter) => {
+    const action = submitter?.hasAttribute('formaction') ? submitter.formAction : form.action;
+    const method = (submitter?.hasAttribute('formmethod') ? submitter.formMethod : form.method)
+      || 'get';
+    if (method === 'dialog') {
+      return null;
+    }
+    const submission = { action, method, fields: fields(form, submitter) };
+    const response = prompt(`VERSO::FORM_SUBMISSION::${JSON.stringify(submission)}`);
+    if (response === null || response === 'block') {
+      return response;
+    }
+    try {
+      return JSON.parse(response);
+    } catch {
+      return null;
+    }
+  };
+
+  // Submit the fields in a form of their own, sent like the form of the page
+  const submitInstead = (form, submitter, fields) => {
+    const replacement = document.createElement('form');
+    for (const attribute of ['action', 'method', 'enctype', 'target', 'accept-charset']) {
+      const value = form.getAttribute(attribute);
+      if (value !== null) {
+        replacement.setAttribute(attribute, value);
+      }
+    }
+    for (const attribute of ['action', 'method', 'enctype', 'target']) {
+      if (submitter?.hasAttribute(`form${attribute}`)) {
+        replacement.setAttribute(attribute, submitter.getAttribute(`form${attribute}`));
+      }
+    }
+    for (const { name, value } of fields) {
+      const input = document.createElement('input');
+      input.type = 'hidden';
+      input.name = name;
+      input.value = value;
+      replacement.append(input);
+    }
+    replacement.hidden = true;
+    document.documentElement.append(replacement);
+    nativeSubmit.call(replacement);
+    replacement.remove();
+  };
+
+  addEventListener('submit', (event) => {
+    const form = event.target;
+    if (!(form instanceof HTMLFormElement)) {
+      return;
+    }
+    const decision = check(form, event.submitter);
+    if (decision === null) {
+      return;
+    }
+    event.preventDefault();
+    event.stopImmediatePropagation();
+    if (Array.isArray(decision)) {
+      submitInstead(form, event.submitter, decision);
+    }
+  }, true);
+
+  HTMLFormElement.prototype.submit = function () {
+    const decision = check(this, null);
+    if (decision === null) {
+      nativeSubmit.call(this);
+    } else if (Array.isArray(decision)) {
+      submitInstead(this, null, decision);
+    }
+  };
+})();
+"#;
+
+/// A form as the page reports it.
+#[derive(Debug, Deserialize)]
+struct SubmittedForm {
+    action: url::Url,
+    method: String,
+    fields: Vec<FormField>,
+}
+
+impl Window {
+    /// Send a form the page of the tab submits to the controller, the page waits until it's
+    /// answered with a [`versoview_messages::ToVersoMessage::FormSubmissionResponse`]. Forms are
+    /// submitted as they are if the controller doesn't listen to them.
+    pub(crate) fn delegate_form_submission(
+        &mut self,
+        webview_id: WebViewId,
+        page_url: Option<ServoUrl>,
+        form: &str,
+        response_sender: IpcSender<PromptResponse>,
+        to_controller_sender: &Option<IpcSender<ToControllerMessage>>,
+    ) {
+        let (Some(to_controller_sender), Some(submissions)) = (
+            to_controller_sender,
+            &mut self.event_listeners.on_form_submission,
+        ) else {
+            let _ = response_sender.send(PromptResponse::Cancel);
+            return;
+        };
+        let form = match serde_json::from_str::<SubmittedForm>(form) {
+            Ok(form) => form,
+            Err(e) => {
+                log::error!("Invalid form submission: {e}");
+                let _ = response_sender.send(PromptResponse::Cancel);
+                return;
+            }
+        };
+        let id = uuid::Uuid::new_v4();
+        let submission = FormSubmission {
+            webview_id: bincode::serialize(&webview_id).unwrap(),
+            page_url: page_url.map(ServoUrl::into_url),
+            action: form.action,
+            method: form.method,
+            fields: form.fields,
+        };
+        match to_controller_sender.send(ToControllerMessage::OnFormSubmission(id, submission)) {
+            Ok(_) => {
+                submissions.insert(id, response_sender);
+            }
+            Err(error) => {
+                log::error!("Verso failed to send OnFormSubmission to controller: {error}");
+                let _ = response_sender.send(PromptResponse::Cancel);
+            }
+        }
+    }
+}
+
+/// Answer the page waiting for its form submission with what the controller chose.
+pub(crate) fn respond(
+    response_sender: IpcSender<PromptResponse>,
+    response: FormSubmissionResponse,
+) {
+    let response = match response {
+        FormSubmissionResponse::Allow => PromptResponse::Cancel,
+        FormSubmissionResponse::Block => PromptResponse::Ok("block".to_string()),
+        FormSubmissionResponse::Modify(fields) => {
+            PromptResponse::Ok(serde_json::to_string(&fields).unwrap())
+        }
+    };
+    let _ = response_sender.send(response);
+}
@@ -0,0 +1,373 @@
+use std::{thread, time::Duration};
+
+use base::id::{BrowsingContextId, WebViewId};
+use constellation_traits::EmbedderToConstellationMessage;
+use crossbeam_channel::Sender;
+use embedder_traits::{
+    WebDriverCommandMsg, WebDriverFrameId, WebDriverJSResult, WebDriverJSValue,
+    WebDriverScriptCommand,
+};
+use ipc_channel::ipc::{self, IpcSender};
+use serde::{Serialize, de::DeserializeOwned};
+use versoview_messages::{CrossOriginFramePolicy, FrameInfo, FrameInput, ToControllerMessage};
+
+use crate::verso::send_to_constellation;
+
+/// How long a frame may take to answer while walking the tree, frames whose script is hung are
+/// left out.
+const FRAME_TIMEOUT: Duration = Duration::from_secs(1);
+/// How long a script evaluated for the controller may run.
+const SCRIPT_TIMEOUT: Duration = Duration::from_secs(30);
+/// Deepest frames listed, so pages nesting frames endlessly can't stall the walk.
+const MAX_DEPTH: usize = 16;
+/// Most child frames listed per document.
+const MAX_CHILDREN: u16 = 256;
+
+/// Evaluate the script in the frame's global scope and return its result as JSON.
+const EVALUATE_SCRIPT: &str = r#"
+((script) => {
+  const result = (0, eval)(script);
+  return result === undefined ? 'null' : JSON.stringify(result) ?? 'null';
+})
+"#;
+
+/// Click the element at the point, with the mouse events before the click.
+const CLICK_SCRIPT: &str = r#"
+((x, y) => {
+  const target = document.elementFromPoint(x, y);
+  if (!target) {
+    return false;
+  }
+  const init = { bubbles: true, cancelable: true, composed: true, clientX: x, clientY: y, view: window };
+  target.dispatchEvent(new MouseEvent('mousedown', init));
+  target.focus?.();
+  target.dispatchEvent(new MouseEvent('mouseup', init));
+  target.click();
+  return true;
+})
+"#;
+
+/// Insert the text at the caret of the focused text field or editable element.
+const TEXT_SCRIPT: &str = r#"
+((text) => {
+  const target = document.activeElement;
+  if (target instanceof HTMLInputElement || target instanceof HTMLTextAreaElement) {
+    const start = target.selectionStart ?? target.value.length;
+    target.setRangeText(text, start, target.selectionEnd ?? start, 'end');
+  } else if (target?.isContentEditable && getSelection().rangeCount > 0) {
+    const range = getSelection().getRangeAt(0);
+    range.deleteContents();
+    range.insertNode(document.createTextNode(text));
+    range.collapse(false);
+  } else {
+    return false;
+  }
+  target.dispatchEvent(new InputEvent('input', { bubbles: true, inputType: 'insertText', data: text }));
+  return true;
+})
+"#;
+
+/// Press and release the key on the focused element, `Enter` submits forms.
+const KEY_SCRIPT: &str = r#"
+((key) => {
+  const target = document.activeElement ?? document.body;
+  const init = { key, bubbles: true, cancelable: true, composed: true };
+  if (target.dispatchEvent(new KeyboardEvent('keydown', init)) && key === 'Enter') {
+    target.form?.requestSubmit();
+  }
+  target.dispatchEvent(new KeyboardEvent('keyup', init));
+  return true;
+})
+"#;
+
+/// Get the value of the first form field matching the selector, or set it if `value` isn't
+/// `null` with the events of a user edit. The setter of the prototype is used, so pages which
+/// override the `value` of their fields still see the change. `null` if no field matches.
+const FIELD_VALUE_SCRIPT: &str = r#"
+((selector, value) => {
+  const field = document.querySelector(selector);
+  const checkable = field instanceof HTMLInputElement && ['checkbox', 'radio'].includes(field.type);
+  if (field instanceof HTMLInputElement || field instanceof HTMLTextAreaElement
+    || field instanceof HTMLSelectElement) {
+    if (value !== null) {
+      field.focus();
+      if (checkable) {
+        field.checked = value === 'true';
+      } else {
+        Object.getOwnPropertyDescriptor(Object.getPrototypeOf(field), 'value').set.call(field, value);
+      }
+      field.dispatchEvent(new InputEvent('input', {
+        bubbles: true, composed: true, inputType: 'insertReplacementText', data: value,
+      }));
+      field.dispatchEvent(new Event('change', { bubbles: true }));
+    }
+    return checkable ? String(field.checked) : field.value;
+  }
+  if (field?.isContentEditable) {
+    if (value !== null) {
+      field.focus();
+      field.textContent = value;
+      field.dispatchEvent(new InputEvent('input', {
+        bubbles: true, composed: true, inputType: 'insertReplacementText', data: value,
+      }));
+    }
+    return field.textContent;
+  }
+  return null;
+})
+"#;
+
+/// Script which sandboxes or removes the frames of the document from other origins, with the
+/// policy passed as argument. An empty `sandbox` attribute only applies from the next navigation
+/// of the frame, so the frame is navigated again.
+const FRAME_ISOLATION_SCRIPT: &str = r#"
+((policy) => {
+  if (location.protocol === 'verso:') {
+    return;
+  }
+
+  const FRAMES = 'iframe, frame';
+  const isCrossOrigin = (frame) => {
+    const src = frame.getAttribute('src');
+    if (frame.hasAttribute('srcdoc') || !src) {
+      return false;
+    }
+    try {
+      const url = new URL(src, document.baseURI);
+      return !['about:', 'javascript:'].includes(url.protocol) && url.origin !== location.origin;
+    } catch {
+      return false;
+    }
+  };
+  const isolate = (frame) => {
+    if (!isCrossOrigin(frame)) {
+      return;
+    }
+    if (policy === 'block' || frame.localName === 'frame') {
+      console.warn(`Verso removed the cross-origin frame ${frame.src}`);
+      frame.remove();
+    } else if (frame.getAttribute('sandbox') !== '') {
+      frame.setAttribute('sandbox', '');
+      frame.setAttribute('src', frame.getAttribute('src'));
+    }
+  };
+  const scan = (node) => {
+    if (node.nodeType === Node.ELEMENT_NODE) {
+      if (node.matches(FRAMES)) {
+        isolate(node);
+      }
+      node.querySelectorAll(FRAMES).forEach(isolate);
+    }
+  };
+
+  new MutationObserver((records) => {
+    for (const record of records) {
+      if (record.type === 'attributes') {
+        if (record.target.matches(FRAMES)) {
+          isolate(record.target);
+        }
+      } else {
+        record.addedNodes.forEach(scan);
+      }
+    }
+  }).observe(document, {
+    childList: true,
+    subtree: true,
+    attributes: true,
+    attributeFilter: ['src', 'srcdoc', 'sandbox'],
+  });
+  scan(document.documentElement);
+})
+"#;
+
+/// User script enforcing the cross-origin frame policy, `None` if cross-origin frames are
+/// allowed.
+pub(crate) fn frame_isolation_script(policy: CrossOriginFramePolicy) -> Option<String> {
+    let policy = match policy {
+        CrossOriginFramePolicy::Allow => return None,
+        CrossOriginFramePolicy::Sandbox => "sandbox",
+        CrossOriginFramePolicy::Block => "block",
+    };
+    Some(format!("{FRAME_ISOLATION_SCRIPT}('{policy}')"))
+}
+
+/// Walk the frames of the webview on another thread, since the script thread of every frame is
+/// asked in turn, and send [`ToControllerMessage::GetFrameTreeResponse`].
+pub(crate) fn send_frame_tree(
+    constellation_sender: Sender<EmbedderToConstellationMessage>,
+    to_controller_sender: IpcSender<ToControllerMessage>,
+    id: uuid::Uuid,
+    webview_id: WebViewId,
+) {
+    spawn("FrameTree", move || {
+        let tree = frame_info(&constellation_sender, webview_id.0, 0);
+        if let Err(error) =
+            to_controller_sender.send(ToControllerMessage::GetFrameTreeResponse(id, tree))
+        {
+            log::error!("Verso failed to send GetFrameTreeResponse to controller: {error}")
+        }
+    });
+}
+
+/// Evaluate the script in the frame on another thread, and send
+/// [`ToControllerMessage::ExecuteScriptInFrameResponse`].
+pub(crate) fn execute_script_in_frame(
+    constellation_sender: Sender<EmbedderToConstellationMessage>,
+    to_controller_sender: IpcSender<ToControllerMessage>,
+    id: uuid::Uuid,
+    frame_id: BrowsingContextId,
+    script: String,
+) {
+    spawn("FrameScript", move || {
+        let script = format!(
+            "{EVALUATE_SCRIPT}({})",
+            serde_json::to_string(&script).unwrap()
+        );
+        let result = match execute(&constellation_sender, frame_id, script, SCRIPT_TIMEOUT) {
+            Some(Ok(WebDriverJSValue::String(json))) => Ok(json),
+            Some(Ok(value)) => Err(format!("Unexpected script result: {value:?}")),
+            Some(Err(error)) => Err(format!("{error:?}")),
+            None => Err("The frame doesn't exist or didn't respond".to_string()),
+        };
+        if let Err(error) = to_controller_sender.send(
+            ToControllerMessage::ExecuteScriptInFrameResponse(id, result),
+        ) {
+            log::error!("Verso failed to send ExecuteScriptInFrameResponse to controller: {error}")
+        }
+    });
+}
+
+/// Dispatch the input events to the document of the frame on another thread.
+pub(crate) fn send_frame_input(
+    constellation_sender: Sender<EmbedderToConstellationMessage>,
+    frame_id: BrowsingContextId,
+    input: FrameInput,
+) {
+    let script = match &input {
+        FrameInput::Click { x, y } => format!("{CLICK_SCRIPT}({x}, {y})"),
+        FrameInput::Text(text) => {
+            format!("{TEXT_SCRIPT}({})", serde_json::to_string(text).unwrap())
+        }
+        FrameInput::Key(key) => format!("{KEY_SCRIPT}({})", serde_json::to_string(key).unwrap()),
+    };
+    spawn("FrameInput", move || {
+        match execute(&constellation_sender, frame_id, script, SCRIPT_TIMEOUT) {
+            Some(Ok(WebDriverJSValue::Boolean(true))) => {}
+            Some(Err(error)) => log::warn!("Verso failed to send {input:?} to a frame: {error:?}"),
+            _ => log::warn!("Verso found no target in the frame for {input:?}"),
+        }
+    });
+}
+
+/// Get the value of the form field matching the selector in the frame on another thread, or set
+/// it if `value` isn't `None`, and pass the value of the field to `respond`.
+pub(crate) fn field_value(
+    constellation_sender: Sender<EmbedderToConstellationMessage>,
+    frame_id: BrowsingContextId,
+    selector: String,
+    value: Option<String>,
+    respond: impl FnOnce(Result<String, String>) + Send + 'static,
+) {
+    let script = format!(
+        "{FIELD_VALUE_SCRIPT}({}, {})",
+        serde_json::to_string(&selector).unwrap(),
+        serde_json::to_string(&value).unwrap()
+    );
+    spawn("FieldValue", move || {
+        let result = match execute(&constellation_sender, frame_id, script, SCRIPT_TIMEOUT) {
+            Some(Ok(WebDriverJSValue::String(value))) => Ok(value),
+            Some(Ok(WebDriverJSValue::Null)) => Err(format!("No form field matches {selector}")),
+            Some(Ok(value)) => Err(format!("Unexpected script result: {value:?}")),
+            Some(Err(error)) => Err(format!("Invalid selector {selector}: {error:?}")),
+            None => Err("The page doesn't exist or didn't respond".to_string()),
+        };
+        respond(result);
+    });
+}
+
+/// Describe the frame and its children, `None` if it doesn't answer.
+fn frame_info(
+    constellation_sender: &Sender<EmbedderToConstellationMessage>,
+    frame_id: BrowsingContextId,
+    depth: usize,
+) -> Option<FrameInfo> {
+    let name = match execute(constellation_sender, frame_id, "window.name", FRAME_TIMEOUT)? {
+        Ok(WebDriverJSValue::String(name)) => name,
+        _ => String::new(),
+    };
+    let url = script_command(
+        constellation_sender,
+        frame_id,
+        WebDriverScriptCommand::GetUrl,
+        FRAME_TIMEOUT,
+    )
+    .map(|url| url.into_url());
+
+    let mut children = Vec::new();
+    if depth < MAX_DEPTH {
+        for index in 0..MAX_CHILDREN {
+            let child = script_command(
+                constellation_sender,
+                frame_id,
+                |sender| {
+                    WebDriverScriptCommand::GetBrowsingContextId(
+                        WebDriverFrameId::Short(index),
+                        sender,
+                    )
+                },
+                FRAME_TIMEOUT,
+            );
+            let Some(Ok(child)) = child else {
+                break;
+            };
+            children.extend(frame_info(constellation_sender, child, depth + 1));
+        }
+    }
+
+    Some(FrameInfo {
+        id: bincode::serialize(&frame_id).unwrap(),
+        url,
+        name,
+        children,
+    })
+}
+
+/// Evaluate the script in the frame, `None` if it doesn't answer in time.
+pub(crate) fn execute(
+    constellation_sender: &Sender<EmbedderToConstellationMessage>,
+    frame_id: BrowsingContextId,
+    script: impl ToString,
+    timeout: Duration,
+) -> Option<WebDriverJSResult> {
+    script_command(
+        constellation_sender,
+        frame_id,
+        |sender| WebDriverScriptCommand::ExecuteScript(script.to_string(), sender),
+        timeout,
+    )
+}
+
+/// Send a WebDriver command to the script thread of the frame, `None` if it doesn't answer in
+/// time.
+fn script_command<T: Serialize + DeserializeOwned>(
+    constellation_sender: &Sender<EmbedderToConstellationMessage>,
+    frame_id: BrowsingContextId,
+    command: impl FnOnce(IpcSender<T>) -> WebDriverScriptCommand,
+    timeout: Duration,
+) -> Option<T> {
+    let (sender, receiver) = ipc::channel::<T>().ok()?;
+    send_to_constellation(
+        constellation_sender,
+        EmbedderToConstellationMessage::WebDriverCommand(WebDriverCommandMsg::ScriptCommand(
+            frame_id,
+            command(sender),
+        )),
+    );
+    receiver.try_recv_timeout(timeout).ok()
+}
+
+fn spawn(name: &str, f: impl FnOnce() + Send + 'static) {
+    if let Err(e) = thread::Builder::new().name(name.to_owned()).spawn(f) {
+        log::error!("Failed to spawn {name} thread: {e}");
+    }
+}
@@ -0,0 +1,379 @@
+use std::{thread, time::Duration};
+
+use base::id::WebViewId;
+use ipc_channel::ipc::IpcSender;
+use versoview_messages::GeolocationPosition;
+
+use crate::verso::VersoInternalMsg;
+
+/// How long the platform provider is given to find the position.
+const POSITION_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Script which implements `navigator.geolocation` with the Verso bridge. The user is asked for
+/// the permission once per document with `GEOLOCATION_PERMISSION`, then positions are requested
+/// with `GEOLOCATION_POSITION` and resolved by Verso with [`geolocation_result_script`].
+pub(crate) const GEOLOCATION_SCRIPT: &str = r#"
+(() => {
+  if (location.protocol === 'verso:' || !window.isSecureContext) {
+    return;
+  }
+
+  const PERMISSION_DENIED = 1;
+  const POSITION_UNAVAILABLE = 2;
+  const TIMEOUT = 3;
+
+  class GeolocationPositionError {
+    constructor(code, message) {
+      this.code = code;
+      this.message = message;
+    }
+  }
+  Object.assign(GeolocationPositionError.prototype, {
+    PERMISSION_DENIED,
+    POSITION_UNAVAILABLE,
+    TIMEOUT,
+  });
+
+  let permission = null;
+  let nextRequestId = 0;
+  const requests = new Map();
+
+  const hasPermission = () => {
+    if (permission === null) {
+      permission = prompt('VERSO::GEOLOCATION_PERMISSION') === 'allow';
+    }
+    return permission;
+  };
+
+  const requestPosition = (options, callback) => {
+    if (!hasPermission()) {
+      callback(null, new GeolocationPositionError(PERMISSION_DENIED, 'User denied Geolocation'));
+      return;
+    }
+    const id = nextRequestId++;
+    const timeout = options?.timeout ?? Infinity;
+    const timer = Number.isFinite(timeout)
+      ? setTimeout(() => {
+          if (requests.delete(id)) {
+            callback(null, new GeolocationPositionError(TIMEOUT, 'Timeout expired'));
+          }
+        }, timeout)
+      : null;
+    requests.set(id, (position, error) => {
+      clearTimeout(timer);
+      callback(position, error);
+    });
+    prompt(`VERSO::GEOLOCATION_POSITION::${options?.enableHighAccuracy ? 'HIGH' : 'LOW'}::${id}`);
+  };
+
+  const toPosition = (coords) => ({
+    coords: {
+      latitude: coords.latitude,
+      longitude: coords.longitude,
+      accuracy: coords.accuracy,
+      altitude: coords.altitude ?? null,
+      altitudeAccuracy: coords.altitudeAccuracy ?? null,
+      heading: coords.heading ?? null,
+      speed: coords.speed ?? null,
+    },
+    timestamp: Date.now(),
+  });
+
+  const report = (success, error) => (position, positionError) => {
+    if (position) {
+      success(position);
+    } else if (typeof error === 'function') {
+      error(positionError);
+    }
+  };
+
+  const watches = new Map();
+  let nextWatchId = 1;
+
+  const geolocation = {
+    getCurrentPosition(success, error, options) {
+      requestPosition(options, report(success, error));
+    },
+    watchPosition(success, error, options) {
+      const id = nextWatchId++;
+      const poll = () =>
+        requestPosition(options, (position, positionError) => {
+          if (!watches.has(id)) {
+            return;
+          }
+          report(success, error)(position, positionError);
+          if (positionError?.code !== PERMISSION_DENIED) {
+            watches.set(id, setTimeout(poll, 5000));
+          }
+        });
+      watches.set(id, null);
+      poll();
+      return id;
+    },
+    clearWatch(id) {
+      clearTimeout(watches.get(id));
+      watches.delete(id);
+    },
+  };
+
+  Object.defineProperty(Navigator.prototype, 'geolocation', {
+    get: () => geolocation,
+    configurable: true,
+  });
+  Object.defineProperty(window, '__versoGeolocation', {
+    value: (id, coords, message) => {
+      const callback = requests.get(id);
+      if (!callback) {
+        return;
+      }
+      requests.delete(id);
+      if (coords) {
+        callback(toPosition(coords), null);
+      } else {
+        callback(null, new GeolocationPositionError(POSITION_UNAVAILABLE, message));
+      }
+    },
+  });
+})();
+"#;
+
+/// Script to resolve a position request of a document.
+pub(crate) fn geolocation_result_script(
+    request_id: u64,
+    result: &Result<GeolocationPosition, String>,
+) -> String {
+    match result {
+        Ok(position) => {
+            let position = serde_json::to_string(position).unwrap_or_else(|_| "null".to_string());
+            format!("window.__versoGeolocation?.({request_id}, {position})")
+        }
+        Err(message) => {
+            let message = serde_json::to_string(message).unwrap_or_else(|_| "''".to_string());
+            format!("window.__versoGeolocation?.({request_id}, null, {message})")
+        }
+    }
+}
+
+/// Ask the platform provider for the position on another thread, the result is sent back with
+/// [`VersoInternalMsg::GeolocationResult`].
+pub(crate) fn request_position(
+    webview_id: WebViewId,
+    request_id: u64,
+    high_accuracy: bool,
+    sender: IpcSender<VersoInternalMsg>,
+) {
+    let result = thread::Builder::new()
+        .name("Geolocation".to_owned())
+        .spawn(move || {
+            let result = platform::position(high_accuracy, POSITION_TIMEOUT);
+            if let Err(e) = &result {
+                log::warn!("Verso failed to get the position: {e}");
+            }
+            let _ = sender.send(VersoInternalMsg::GeolocationResult(
+                webview_id, request_id, result,
+            ));
+        });
+    if let Err(e) = result {
+        log::error!("Failed to spawn geolocation thread: {e}");
+    }
+}
+
+#[cfg(linux)]
+mod platform {
+    use std::{
+        thread,
+        time::{Duration, Instant},
+    };
+
+    use versoview_messages::GeolocationPosition;
+    use zbus::{
+        blocking::{Connection, Proxy},
+        zvariant::OwnedObjectPath,
+    };
+
+    /// Position from GeoClue.
+    pub fn position(high_accuracy: bool, timeout: Duration) -> Result<GeolocationPosition, String> {
+        let connection = Connection::system().map_err(|e| e.to_string())?;
+        let manager = Proxy::new(
+            &connection,
+            "org.freedesktop.GeoClue2",
+            "/org/freedesktop/GeoClue2/Manager",
+            "org.freedesktop.GeoClue2.Manager",
+        )
+        .map_err(|e| e.to_string())?;
+        let client_path: OwnedObjectPath =
+            manager.call("GetClient", &()).map_err(|e| e.to_string())?;
+        let client = Proxy::new(
+            &connection,
+            "org.freedesktop.GeoClue2",
+            client_path,
+            "org.freedesktop.GeoClue2.Client",
+        )
+        .map_err(|e| e.to_string())?;
+        // Accuracy levels are 4 for street and 8 for exact
+        let accuracy_level: u32 = if high_accuracy { 8 } else { 4 };
+        client
+            .set_property("DesktopId", "verso")
+            .and_then(|_| client.set_property("RequestedAccuracyLevel", accuracy_level))
+            .map_err(|e| e.to_string())?;
+        client
+            .call_method("Start", &())
+            .map_err(|e| e.to_string())?;
+
+        let start = Instant::now();
+        let location_path = loop {
+            let path: OwnedObjectPath =
+                client.get_property("Location").map_err(|e| e.to_string())?;
+            if path.as_str() != "/" {
+                break Ok(path);
+            }
+            if start.elapsed() >= timeout {
+                break Err("GeoClue didn't find the position in time".to_string());
+            }
+            thread::sleep(Duration::from_millis(200));
+        };
+        let _ = client.call_method("Stop", &());
+
+        let location = Proxy::new(
+            &connection,
+            "org.freedesktop.GeoClue2",
+            location_path?,
+            "org.freedesktop.GeoClue2.Location",
+        )
+        .map_err(|e| e.to_string())?;
+        let property = |name: &str| location.get_property::<f64>(name).ok();
+        // Unknown altitudes are the lowest double, unknown speeds and headings are negative
+        Ok(GeolocationPosition {
+            latitude: property("Latitude").ok_or("GeoClue has no latitude")?,
+            longitude: property("Longitude").ok_or("GeoClue has no longitude")?,
+            accuracy: property("Accuracy").unwrap_or_default(),
+            altitude: property("Altitude").filter(|altitude| *altitude > f64::MIN),
+            altitude_accuracy: None,
+            heading: property("Heading").filter(|heading| *heading >= 0.0),
+            speed: property("Speed").filter(|speed| *speed >= 0.0),
+        })
+    }
+}
+
+#[cfg(macos)]
+mod platform {
+    use std::time::{Duration, Instant};
+
+    use core_foundation::runloop::{CFRunLoop, kCFRunLoopDefaultMode};
+    use objc2_core_location::CLLocationManager;
+    use versoview_messages::GeolocationPosition;
+
+    /// Position from CoreLocation. The run loop of the thread is run while waiting, so the
+    /// location manager can ask for the authorization and receive updates.
+    #[allow(unused_unsafe)]
+    pub fn position(high_accuracy: bool, timeout: Duration) -> Result<GeolocationPosition, String> {
+        let manager = unsafe { CLLocationManager::new() };
+        unsafe {
+            if high_accuracy {
+                manager.setDesiredAccuracy(objc2_core_location::kCLLocationAccuracyBest);
+            }
+            manager.requestWhenInUseAuthorization();
+            manager.startUpdatingLocation();
+        }
+
+        let start = Instant::now();
+        let location = loop {
+            if let Some(location) = unsafe { manager.location() } {
+                break Ok(location);
+            }
+            if start.elapsed() >= timeout {
+                break Err("CoreLocation didn't find the position in time".to_string());
+            }
+            CFRunLoop::run_in_mode(
+                unsafe { kCFRunLoopDefaultMode },
+                Duration::from_millis(200),
+                true,
+            );
+        };
+        unsafe { manager.stopUpdatingLocation() };
+
+        let location = location?;
+        let (coordinate, accuracy, altitude, altitude_accuracy, heading, speed) = unsafe {
+            (
+                location.coordinate(),
+                location.horizontalAccuracy(),
+                location.altitude(),
+                location.verticalAccuracy(),
+                location.course(),
+                location.speed(),
+            )
+        };
+        // Invalid accuracies, headings and speeds are negative
+        Ok(GeolocationPosition {
+            latitude: coordinate.latitude,
+            longitude: coordinate.longitude,
+            accuracy,
+            altitude: (altitude_accuracy >= 0.0).then_some(altitude),
+            altitude_accuracy: (altitude_accuracy >= 0.0).then_some(altitude_accuracy),
+            heading: (heading >= 0.0).then_some(heading),
+            speed: (speed >= 0.0).then_some(speed),
+        })
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use std::time::Duration;
+
+    use versoview_messages::GeolocationPosition;
+    use windows::Devices::Geolocation::{Geolocator, PositionAccuracy};
+
+    /// Position from Windows.Devices.Geolocation, which asks for the location permission of the
+    /// system itself.
+    pub fn position(
+        high_accuracy: bool,
+        _timeout: Duration,
+    ) -> Result<GeolocationPosition, String> {
+        let position = || -> windows::core::Result<GeolocationPosition> {
+            let geolocator = Geolocator::new()?;
+            if high_accuracy {
+                geolocator.SetDesiredAccuracy(PositionAccuracy::High)?;
+            }
+            let coordinate = geolocator.GetGeopositionAsync()?.get()?.Coordinate()?;
+            let point = coordinate.Point()?.Position()?;
+            Ok(GeolocationPosition {
+                latitude: point.Latitude,
+                longitude: point.Longitude,
+                accuracy: coordinate.Accuracy()?,
+                altitude: coordinate
+                    .AltitudeAccuracy()
+                    .is_ok_and(|accuracy| accuracy.Value().is_ok())
+                    .then_some(point.Altitude),
+                altitude_accuracy: coordinate
+                    .AltitudeAccuracy()
+                    .and_then(|accuracy| accuracy.Value())
+                    .ok(),
+                heading: coordinate
+                    .Heading()
+                    .and_then(|heading| heading.Value())
+                    .ok()
+                    .filter(|heading| !heading.is_nan()),
+                speed: coordinate
+                    .Speed()
+                    .and_then(|speed| speed.Value())
+                    .ok()
+                    .filter(|speed| !speed.is_nan()),
+            })
+        };
+        position().map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(not(any(linux, macos, windows)))]
+mod platform {
+    use std::time::Duration;
+
+    use versoview_messages::GeolocationPosition;
+
+    pub fn position(
+        _high_accuracy: bool,
+        _timeout: Duration,
+    ) -> Result<GeolocationPosition, String> {
+        Err("Geolocation isn't supported on this platform".to_string())
+    }
+}
@@ -0,0 +1,322 @@
+use std::{collections::BTreeMap, fs::File, path::PathBuf};
+
+use base::id::WebViewId;
+use constellation_traits::EmbedderToConstellationMessage;
+use crossbeam_channel::Sender;
+use embedder_traits::{WebDriverCommandMsg, WebDriverScriptCommand};
+use serde::{Deserialize, Serialize};
+
+use crate::verso::send_to_constellation;
+
+/// User script re-applying the highlights of the page once it's parsed, and defining
+/// `__versoToggleHighlight` which highlights the selection, or removes the highlight the
+/// selection is in. Highlights are anchored by their text and the text around it, so they
+/// survive small changes of the page.
+pub(crate) const HIGHLIGHT_SCRIPT: &str = r#"
+(() => {
+  if (location.protocol === 'verso:' || window.top !== window) {
+    return;
+  }
+
+  const CONTEXT_LENGTH = 32;
+  const ATTRIBUTE = 'data-verso-highlight';
+
+  // Text nodes of the body with their offset in its whole text
+  const textNodes = () => {
+    const nodes = [];
+    let text = '';
+    const walker = document.createTreeWalker(document.body, NodeFilter.SHOW_TEXT);
+    while (walker.nextNode()) {
+      nodes.push({ node: walker.currentNode, start: text.length });
+      text += walker.currentNode.data;
+    }
+    return { nodes, text };
+  };
+
+  // Wrap every text node between the offsets in a mark
+  const wrap = (nodes, start, end, id) => {
+    for (const { node, start: nodeStart } of nodes) {
+      const nodeEnd = nodeStart + node.data.length;
+      if (nodeEnd <= start || nodeStart >= end || !node.data.trim()) {
+        continue;
+      }
+      let target = node;
+      if (start > nodeStart) {
+        target = target.splitText(start - nodeStart);
+      }
+      if (end < nodeEnd) {
+        target.splitText(end - Math.max(start, nodeStart));
+      }
+      const mark = document.createElement('mark');
+      mark.setAttribute(ATTRIBUTE, id);
+      target.replaceWith(mark);
+      mark.appendChild(target);
+    }
+  };
+
+  const apply = (highlight) => {
+    if (document.querySelector(`[${ATTRIBUTE}="${highlight.id}"]`)) {
+      return;
+    }
+    const { nodes, text } = textNodes();
+    const anchored = text.indexOf(highlight.prefix + highlight.text + highlight.suffix);
+    const start = anchored === -1 ? text.indexOf(highlight.text) : anchored + highlight.prefix.length;
+    if (start !== -1) {
+      wrap(nodes, start, start + highlight.text.length, highlight.id);
+    }
+  };
+
+  const remove = (id) => {
+    for (const mark of document.querySelectorAll(`[${ATTRIBUTE}="${id}"]`)) {
+      mark.replaceWith(...mark.childNodes);
+    }
+    document.body.normalize();
+  };
+
+  window.__versoToggleHighlight = () => {
+    const selection = getSelection();
+    if (selection.rangeCount === 0) {
+      return false;
+    }
+    const container = selection.getRangeAt(0).commonAncestorContainer;
+    const element = container.nodeType === Node.ELEMENT_NODE ? container : container.parentElement;
+    const mark = element?.closest(`[${ATTRIBUTE}]`);
+    if (mark) {
+      const id = mark.getAttribute(ATTRIBUTE);
+      remove(id);
+      prompt(`VERSO::HIGHLIGHT_REMOVE::${id}`);
+      return true;
+    }
+    if (selection.isCollapsed) {
+      return false;
+    }
+
+    // Offsets of the selection in the whole text of the body
+    const range = selection.getRangeAt(0);
+    const { nodes, text } = textNodes();
+    const offset = (container, offset) => {
+      if (container.nodeType === Node.TEXT_NODE) {
+        return nodes.find(({ node }) => node === container).start + offset;
+      }
+      const before = document.createRange();
+      before.selectNodeContents(document.body);
+      before.setEnd(container, offset);
+      return before.toString().length;
+    };
+    const start = offset(range.startContainer, range.startOffset);
+    const end = offset(range.endContainer, range.endOffset);
+    const highlight = {
+      text: text.slice(start, end),
+      prefix: text.slice(Math.max(0, start - CONTEXT_LENGTH), start),
+      suffix: text.slice(end, end + CONTEXT_LENGTH),
+      title: document.title || null,
+    };
+    const id = prompt(`VERSO::HIGHLIGHT_ADD::${JSON.stringify(highlight)}`);
+    if (id) {
+      wrap(nodes, start, end, id);
+      selection.removeAllRanges();
+    }
+    return true;
+  };
+
+  const applyAll = () => {
+    const highlights = JSON.parse(prompt('VERSO::HIGHLIGHT_LIST') ?? '[]') ?? [];
+    highlights.forEach(apply);
+    // Pages often render their content after they are parsed, try again once they're loaded
+    if (highlights.length > 0 && document.readyState !== 'complete') {
+      window.addEventListener('load', () => highlights.forEach(apply), { once: true });
+    }
+  };
+
+  const style = document.createElement('style');
+  style.textContent = `mark[${ATTRIBUTE}] { background: #fff176; color: inherit; }`;
+  if (document.readyState === 'loading') {
+    document.addEventListener('DOMContentLoaded', () => {
+      document.head.appendChild(style);
+      applyAll();
+    });
+  } else {
+    document.head.appendChild(style);
+    applyAll();
+  }
+})();
+"#;
+
+/// A passage of a page highlighted by the user.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Highlight {
+    /// The ID of the highlight.
+    pub id: uuid::Uuid,
+    /// The highlighted text.
+    pub text: String,
+    /// The text right before the highlight, to find it again in the page.
+    pub prefix: String,
+    /// The text right after the highlight, to find it again in the page.
+    pub suffix: String,
+    /// Time of the highlight, in milliseconds since the Unix epoch.
+    pub created: i64,
+}
+
+/// Highlights of a page.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PageHighlights {
+    /// The title of the page when it was last highlighted.
+    pub title: Option<String>,
+    /// The highlights of the page, in the order they were made.
+    pub highlights: Vec<Highlight>,
+}
+
+/// A highlight sent by the page, before it's given an ID.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NewHighlight {
+    /// The highlighted text.
+    pub text: String,
+    /// The text right before the highlight.
+    pub prefix: String,
+    /// The text right after the highlight.
+    pub suffix: String,
+    /// The title of the page.
+    pub title: Option<String>,
+}
+
+/// A struct managing the highlights of every page, by URL without fragment.
+#[derive(Default)]
+pub struct HighlightManager {
+    pages: BTreeMap<String, PageHighlights>,
+}
+
+impl HighlightManager {
+    /// Creates a new `HighlightManager`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the highlights of every page, e.g. loaded from disk.
+    pub fn set_pages(&mut self, pages: BTreeMap<String, PageHighlights>) {
+        self.pages = pages;
+    }
+
+    /// Get the highlights of every page.
+    pub fn pages(&self) -> &BTreeMap<String, PageHighlights> {
+        &self.pages
+    }
+
+    /// Get the highlights of the page.
+    pub fn highlights(&self, url: &url::Url) -> &[Highlight] {
+        self.pages
+            .get(&page_key(url))
+            .map_or(&[], |page| page.highlights.as_slice())
+    }
+
+    /// Highlight a passage of the page, returns the ID of the new highlight.
+    pub fn add_highlight(&mut self, url: &url::Url, highlight: NewHighlight) -> uuid::Uuid {
+        let id = uuid::Uuid::new_v4();
+        let page = self.pages.entry(page_key(url)).or_default();
+        page.title = highlight.title.or(page.title.take());
+        page.highlights.push(Highlight {
+            id,
+            text: highlight.text,
+            prefix: highlight.prefix,
+            suffix: highlight.suffix,
+            created: chrono::Utc::now().timestamp_millis(),
+        });
+        id
+    }
+
+    /// Removes a highlight of the page, pages without highlights are forgotten.
+    pub fn remove_highlight(&mut self, url: &url::Url, id: uuid::Uuid) -> Result<(), String> {
+        let key = page_key(url);
+        let Some(page) = self.pages.get_mut(&key) else {
+            return Err(format!("Highlight with ID {id} not found"));
+        };
+        let Some(position) = page.highlights.iter().position(|h| h.id == id) else {
+            return Err(format!("Highlight with ID {id} not found"));
+        };
+        page.highlights.remove(position);
+        if page.highlights.is_empty() {
+            self.pages.remove(&key);
+        }
+        Ok(())
+    }
+
+    /// Export every highlight as Markdown, a section per page with its highlights quoted.
+    pub fn to_markdown(&self) -> String {
+        let mut markdown = String::from("# Highlights\n");
+        for (url, page) in &self.pages {
+            let title = page.title.as_deref().unwrap_or(url);
+            markdown.push_str(&format!(
+                "\n## [{}](<{url}>)\n",
+                title.replace('[', "\\[").replace(']', "\\]")
+            ));
+            for highlight in &page.highlights {
+                markdown.push('\n');
+                for line in highlight.text.trim().lines() {
+                    markdown.push_str(&format!("> {}\n", line.trim_end()));
+                }
+            }
+        }
+        markdown
+    }
+}
+
+/// Highlights don't depend on the fragment of the URL, so they're shown for every anchor of
+/// the page.
+fn page_key(url: &url::Url) -> String {
+    let mut url = url.clone();
+    url.set_fragment(None);
+    url.to_string()
+}
+
+/// Highlight the selection of the webview, or remove the highlight it's in, without waiting for
+/// the page to answer.
+pub(crate) fn toggle_highlight(
+    constellation_sender: &Sender<EmbedderToConstellationMessage>,
+    webview_id: WebViewId,
+) {
+    let Ok((result_sender, _)) = ipc_channel::ipc::channel() else {
+        return;
+    };
+    send_to_constellation(
+        constellation_sender,
+        EmbedderToConstellationMessage::WebDriverCommand(WebDriverCommandMsg::ScriptCommand(
+            webview_id.0,
+            WebDriverScriptCommand::ExecuteScript(
+                "window.__versoToggleHighlight?.() ?? false".to_string(),
+                result_sender,
+            ),
+        )),
+    );
+}
+
+pub(crate) struct HighlightStorage {
+    config_dir_path: PathBuf,
+}
+
+impl HighlightStorage {
+    /// Create a new `HighlightStorage`.
+    pub fn new(config_dir_path: PathBuf) -> Self {
+        Self { config_dir_path }
+    }
+
+    fn highlight_file_path(&self) -> PathBuf {
+        self.config_dir_path.join("highlights.json")
+    }
+
+    /// Load highlights from disk.
+    pub fn load_from_file(&self) -> Result<BTreeMap<String, PageHighlights>, std::io::Error> {
+        let file = File::open(self.highlight_file_path())?;
+        let pages = serde_json::from_reader(file)?;
+        Ok(pages)
+    }
+
+    /// Save highlights to disk.
+    pub fn save_to_file(
+        &self,
+        pages: &BTreeMap<String, PageHighlights>,
+    ) -> Result<(), std::io::Error> {
+        let file = File::create(self.highlight_file_path())?;
+        serde_json::to_writer(file, pages)?;
+        Ok(())
+    }
+}
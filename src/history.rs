@@ -0,0 +1,437 @@
+use std::path::{Path, PathBuf};
+
+use rusqlite::{Connection, params};
+use serde::{Deserialize, Serialize};
+use versoview_messages::HistoryMatch;
+
+/// Most characters of the text of a page kept in the index.
+const MAX_INDEXED_TEXT: usize = 100_000;
+/// Most pages kept in the history, the least recently visited ones are pruned beyond it.
+const MAX_ENTRIES: usize = 100_000;
+/// Milliseconds in a day.
+const DAY: i64 = 24 * 60 * 60 * 1000;
+
+/// Script of the panel, completing the URL typed in the URL bar inline with the best suggestion
+/// of the history. The completed part is selected, so typing on replaces it.
+pub(crate) const URL_BAR_AUTOFILL_SCRIPT: &str = r#"
+(() => {
+  if (window.versoUrlBarAutofill) {
+    return;
+  }
+  window.versoUrlBarAutofill = true;
+  let completing = false;
+  document.addEventListener('input', (event) => {
+    const input = event.target;
+    if (completing || !(input instanceof HTMLInputElement) || input.type !== 'text') {
+      return;
+    }
+    // Deleting the completion shouldn't bring it back
+    if (event.inputType && !event.inputType.startsWith('insert')) {
+      return;
+    }
+    const typed = input.value;
+    if (!typed.trim() || input.selectionEnd !== typed.length) {
+      return;
+    }
+    let suggestions;
+    try {
+      suggestions = JSON.parse(window.prompt(`HISTORY_SUGGEST::${typed}`) || '[]');
+    } catch {
+      return;
+    }
+    const lower = typed.toLowerCase();
+    for (const { url } of suggestions) {
+      const completion = [url, url.replace(/^[a-z]+:\/\//, ''), url.replace(/^[a-z]+:\/\/www\./, '')]
+        .find((candidate) => candidate.toLowerCase().startsWith(lower));
+      if (completion && completion.length > typed.length) {
+        completing = true;
+        input.value = typed + completion.slice(typed.length);
+        // Let the panel know about the completed value
+        input.dispatchEvent(new Event('input', { bubbles: true }));
+        completing = false;
+        input.setSelectionRange(typed.length, input.value.length);
+        return;
+      }
+    }
+  }, true);
+})()
+"#;
+
+/// A visited page.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    /// The URL of the page.
+    pub url: String,
+    /// The title of the page.
+    pub title: Option<String>,
+    /// How many times the page is visited.
+    pub visit_count: u32,
+    /// Time of the last visit, in milliseconds since the Unix epoch.
+    pub last_visit: i64,
+}
+
+/// History of visited pages persisted in a SQLite database.
+pub struct HistoryStore {
+    connection: Connection,
+}
+
+impl HistoryStore {
+    /// Open the history database at this path, it's created if it doesn't exist.
+    pub fn open(path: &Path) -> rusqlite::Result<Self> {
+        Self::with_connection(Connection::open(path)?)
+    }
+
+    /// Open a history database which is not persisted.
+    pub fn open_in_memory() -> rusqlite::Result<Self> {
+        Self::with_connection(Connection::open_in_memory()?)
+    }
+
+    fn with_connection(connection: Connection) -> rusqlite::Result<Self> {
+        connection.execute_batch(
+            "CREATE TABLE IF NOT EXISTS history (
+                url TEXT PRIMARY KEY,
+                title TEXT,
+                visit_count INTEGER NOT NULL DEFAULT 0,
+                last_visit INTEGER NOT NULL DEFAULT 0
+            );
+            CREATE VIRTUAL TABLE IF NOT EXISTS history_text USING fts5(
+                url UNINDEXED,
+                title,
+                content
+            );",
+        )?;
+        Ok(Self { connection })
+    }
+
+    /// Merge entries into the history, e.g. when importing from another browser. For a URL which
+    /// already exists, the larger visit count and the title of the latest visit are kept, so
+    /// importing the same entries twice doesn't change anything.
+    pub fn add_entries(&mut self, entries: &[HistoryEntry]) -> rusqlite::Result<()> {
+        let transaction = self.connection.transaction()?;
+        {
+            let mut statement = transaction.prepare(
+                "INSERT INTO history (url, title, visit_count, last_visit) VALUES (?1, ?2, ?3, ?4)
+                ON CONFLICT(url) DO UPDATE SET
+                    title = CASE WHEN excluded.last_visit >= last_visit
+                        THEN COALESCE(excluded.title, title) ELSE title END,
+                    visit_count = MAX(visit_count, excluded.visit_count),
+                    last_visit = MAX(last_visit, excluded.last_visit)",
+            )?;
+            for entry in entries {
+                statement.execute(params![
+                    entry.url,
+                    entry.title,
+                    entry.visit_count,
+                    entry.last_visit
+                ])?;
+            }
+        }
+        transaction.commit()
+    }
+
+    /// Record a visit of the page now, and replace its indexed text with this one. Pass an empty
+    /// text to only record the visit.
+    pub fn add_visit(
+        &mut self,
+        url: &str,
+        title: Option<&str>,
+        text: &str,
+    ) -> rusqlite::Result<()> {
+        let now = chrono::Utc::now().timestamp_millis();
+        let transaction = self.connection.transaction()?;
+        transaction.execute(
+            "INSERT INTO history (url, title, visit_count, last_visit) VALUES (?1, ?2, 1, ?3)
+            ON CONFLICT(url) DO UPDATE SET
+                title = COALESCE(excluded.title, title),
+                visit_count = visit_count + 1,
+                last_visit = excluded.last_visit",
+            params![url, title, now],
+        )?;
+        if !text.is_empty() {
+            let text: String = text.chars().take(MAX_INDEXED_TEXT).collect();
+            transaction.execute("DELETE FROM history_text WHERE url = ?1", params![url])?;
+            transaction.execute(
+                "INSERT INTO history_text (url, title, content) VALUES (?1, ?2, ?3)",
+                params![url, title, text],
+            )?;
+        }
+        transaction.commit()
+    }
+
+    /// Search the indexed text and titles of visited pages, the best matches first. Every word of
+    /// the query must appear in the page, the last one may only be the start of a word. An empty
+    /// query returns the most recently visited pages.
+    pub fn search(&self, query: &str, limit: usize) -> rusqlite::Result<Vec<HistoryMatch>> {
+        let Some(query) = full_text_query(query) else {
+            let mut statement = self.connection.prepare(
+                "SELECT url, title, '', last_visit FROM history ORDER BY last_visit DESC LIMIT ?1",
+            )?;
+            return collect_matches(statement.query(params![limit as i64])?);
+        };
+        let mut statement = self.connection.prepare(
+            "SELECT history.url, history.title, snippet(history_text, 2, '\u{E000}', '\u{E001}', '…', 24),
+                history.last_visit
+            FROM history_text JOIN history ON history.url = history_text.url
+            WHERE history_text MATCH ?1 ORDER BY rank LIMIT ?2",
+        )?;
+        collect_matches(statement.query(params![query, limit as i64])?)
+    }
+
+    /// Suggest visited pages for the text typed in the URL bar, the pages visited most often and
+    /// most recently first, like the frecency of Firefox. The text is matched against the URLs
+    /// and titles. Pages whose URL starts with the text, without the scheme and `www.`, come first
+    /// so the URL bar can complete them inline.
+    pub fn suggest(&self, input: &str, limit: usize) -> rusqlite::Result<Vec<HistoryMatch>> {
+        let input = input.trim();
+        if input.is_empty() {
+            return Ok(Vec::new());
+        }
+        let escaped = input
+            .replace('\\', "\\\\")
+            .replace('%', "\\%")
+            .replace('_', "\\_");
+        let now = chrono::Utc::now().timestamp_millis();
+        let mut statement = self.connection.prepare(
+            "SELECT url, title, '', last_visit FROM history
+            WHERE url LIKE '%' || ?1 || '%' ESCAPE '\\' OR title LIKE '%' || ?1 || '%' ESCAPE '\\'
+            ORDER BY
+                (url LIKE ?1 || '%' ESCAPE '\\'
+                    OR url LIKE 'http://' || ?1 || '%' ESCAPE '\\'
+                    OR url LIKE 'https://' || ?1 || '%' ESCAPE '\\'
+                    OR url LIKE 'http://www.' || ?1 || '%' ESCAPE '\\'
+                    OR url LIKE 'https://www.' || ?1 || '%' ESCAPE '\\') DESC,
+                visit_count * CASE
+                    WHEN ?2 - last_visit < 4 * ?3 THEN 100
+                    WHEN ?2 - last_visit < 14 * ?3 THEN 70
+                    WHEN ?2 - last_visit < 31 * ?3 THEN 50
+                    WHEN ?2 - last_visit < 90 * ?3 THEN 30
+                    ELSE 10
+                END DESC,
+                last_visit DESC
+            LIMIT ?4",
+        )?;
+        collect_matches(statement.query(params![escaped, now, DAY, limit as i64])?)
+    }
+
+    /// Forget the pages not visited for more than this many days, 0 to keep them, and the least
+    /// recently visited pages beyond the most kept. Returns how many pages are forgotten.
+    pub fn prune(&mut self, retention_days: u32) -> rusqlite::Result<usize> {
+        let transaction = self.connection.transaction()?;
+        let mut pruned = 0;
+        if retention_days > 0 {
+            let cutoff = chrono::Utc::now().timestamp_millis() - i64::from(retention_days) * DAY;
+            pruned += transaction.execute("DELETE FROM history WHERE last_visit < ?1", [cutoff])?;
+        }
+        pruned += transaction.execute(
+            "DELETE FROM history WHERE url NOT IN
+                (SELECT url FROM history ORDER BY last_visit DESC LIMIT ?1)",
+            [MAX_ENTRIES as i64],
+        )?;
+        if pruned > 0 {
+            remove_orphaned_text(&transaction)?;
+        }
+        transaction.commit()?;
+        Ok(pruned)
+    }
+
+    /// Forget the pages last visited since this time, in milliseconds since the Unix epoch, or
+    /// every page if `None`.
+    pub fn clear(&mut self, since: Option<i64>) -> rusqlite::Result<()> {
+        let transaction = self.connection.transaction()?;
+        match since {
+            Some(since) => {
+                transaction.execute("DELETE FROM history WHERE last_visit >= ?1", [since])?;
+                remove_orphaned_text(&transaction)?;
+            }
+            None => {
+                transaction.execute_batch("DELETE FROM history; DELETE FROM history_text;")?;
+            }
+        }
+        transaction.commit()
+    }
+
+    /// Get all entries, the most recently visited first.
+    pub fn entries(&self) -> rusqlite::Result<Vec<HistoryEntry>> {
+        let mut statement = self.connection.prepare(
+            "SELECT url, title, visit_count, last_visit FROM history ORDER BY last_visit DESC",
+        )?;
+        statement
+            .query_map([], |row| {
+                Ok(HistoryEntry {
+                    url: row.get(0)?,
+                    title: row.get(1)?,
+                    visit_count: row.get(2)?,
+                    last_visit: row.get(3)?,
+                })
+            })?
+            .collect()
+    }
+}
+
+/// Quote every word of the query, so the FTS5 syntax isn't interpreted, and match the last one as
+/// a prefix for search-as-you-type. `None` if the query has no words.
+fn full_text_query(query: &str) -> Option<String> {
+    let words: Vec<String> = query
+        .split_whitespace()
+        .map(|word| format!("\"{}\"", word.replace('"', "\"\"")))
+        .collect();
+    (!words.is_empty()).then(|| format!("{}*", words.join(" ")))
+}
+
+/// Remove the indexed text of the pages which aren't in the history anymore.
+fn remove_orphaned_text(connection: &Connection) -> rusqlite::Result<()> {
+    connection.execute(
+        "DELETE FROM history_text WHERE url NOT IN (SELECT url FROM history)",
+        [],
+    )?;
+    Ok(())
+}
+
+/// Read the URL, title, snippet and last visit of the rows, rows with an invalid URL are skipped.
+fn collect_matches(mut rows: rusqlite::Rows) -> rusqlite::Result<Vec<HistoryMatch>> {
+    let mut matches = Vec::new();
+    while let Some(row) = rows.next()? {
+        let Ok(url) = url::Url::parse(&row.get::<_, String>(0)?) else {
+            continue;
+        };
+        matches.push(HistoryMatch {
+            url,
+            title: row.get(1)?,
+            snippet: row.get(2)?,
+            last_visit: row.get(3)?,
+        });
+    }
+    Ok(matches)
+}
+
+pub(crate) struct HistoryStorage {
+    config_dir_path: PathBuf,
+}
+
+impl HistoryStorage {
+    /// Create a new `HistoryStorage`.
+    pub fn new(config_dir_path: PathBuf) -> Self {
+        Self { config_dir_path }
+    }
+
+    fn history_file_path(&self) -> PathBuf {
+        self.config_dir_path.join("history.sqlite")
+    }
+
+    /// Open the history database on disk.
+    pub fn open(&self) -> rusqlite::Result<HistoryStore> {
+        HistoryStore::open(&self.history_file_path())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(url: &str, visit_count: u32, days_ago: i64) -> HistoryEntry {
+        HistoryEntry {
+            url: url.to_string(),
+            title: None,
+            visit_count,
+            last_visit: chrono::Utc::now().timestamp_millis() - days_ago * DAY,
+        }
+    }
+
+    fn urls(matches: Vec<HistoryMatch>) -> Vec<String> {
+        matches.into_iter().map(|m| m.url.to_string()).collect()
+    }
+
+    #[test]
+    fn test_suggest_frecency() {
+        let mut history = HistoryStore::open_in_memory().unwrap();
+        history
+            .add_entries(&[
+                entry("https://blog.example.org/?q=example", 50, 1),
+                entry("https://www.example.net/", 3, 100),
+                entry("https://example.com/", 1, 1),
+                entry("https://other.org/", 10, 1),
+            ])
+            .unwrap();
+        // Pages starting with the input first, then the most visited and recent ones
+        assert_eq!(
+            urls(history.suggest("example", 10).unwrap()),
+            [
+                "https://example.com/",
+                "https://www.example.net/",
+                "https://blog.example.org/?q=example",
+            ]
+        );
+        assert_eq!(history.suggest("example", 1).unwrap().len(), 1);
+        assert!(history.suggest("  ", 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_suggest_escapes_wildcards() {
+        let mut history = HistoryStore::open_in_memory().unwrap();
+        history
+            .add_entries(&[
+                entry("https://a.com/a_b", 1, 1),
+                entry("https://b.com/axb", 1, 1),
+            ])
+            .unwrap();
+        assert_eq!(
+            urls(history.suggest("a_b", 10).unwrap()),
+            ["https://a.com/a_b"]
+        );
+    }
+
+    #[test]
+    fn test_add_entries_keeps_larger_visit_count() {
+        let mut history = HistoryStore::open_in_memory().unwrap();
+        history
+            .add_entries(&[entry("https://example.com/", 5, 10)])
+            .unwrap();
+        history
+            .add_entries(&[entry("https://example.com/", 2, 1)])
+            .unwrap();
+        let entries = history.entries().unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].visit_count, 5);
+    }
+
+    #[test]
+    fn test_prune() {
+        let mut history = HistoryStore::open_in_memory().unwrap();
+        history
+            .add_entries(&[
+                entry("https://old.com/", 1, 30),
+                entry("https://recent.com/", 1, 1),
+            ])
+            .unwrap();
+        assert_eq!(history.prune(0).unwrap(), 0);
+        assert_eq!(history.prune(7).unwrap(), 1);
+        let entries = history.entries().unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].url, "https://recent.com/");
+    }
+
+    #[test]
+    fn test_clear() {
+        let mut history = HistoryStore::open_in_memory().unwrap();
+        history
+            .add_entries(&[
+                entry("https://old.com/", 1, 30),
+                entry("https://recent.com/", 1, 1),
+            ])
+            .unwrap();
+        let since = chrono::Utc::now().timestamp_millis() - 7 * DAY;
+        history.clear(Some(since)).unwrap();
+        assert_eq!(history.entries().unwrap()[0].url, "https://old.com/");
+        history.clear(None).unwrap();
+        assert!(history.entries().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_full_text_query() {
+        assert_eq!(full_text_query("  "), None);
+        assert_eq!(
+            full_text_query(r#"say "hi" now"#).unwrap(),
+            r#""say" """hi""" "now"*"#
+        );
+    }
+}
@@ -0,0 +1,152 @@
+use std::{
+    collections::HashMap,
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use base::id::WebViewId;
+use embedder_traits::AuthenticationResponse;
+use ipc_channel::ipc::IpcSender;
+use versoview_messages::{AuthenticationRequest, HttpCredentials, ToControllerMessage};
+
+use crate::{secrets::SecretStore, window::Window};
+
+/// How long after answering with remembered credentials another request for the same origin
+/// means they were rejected, so the user is asked instead.
+const REJECTED_WITHIN: Duration = Duration::from_secs(10);
+
+/// Credentials of HTTP authentication the user chose to remember, saved in the OS keychain for
+/// the profile and answered without asking again.
+pub(crate) struct RememberedCredentials {
+    /// Namespace of the profile in the secret store, nothing is remembered in private windows.
+    namespace: Option<String>,
+    /// When credentials were last answered without asking, by origin.
+    answered: HashMap<String, Instant>,
+}
+
+impl RememberedCredentials {
+    /// Create the remembered credentials of the profile, the default one if `None`.
+    pub fn new(profile_directory: Option<&Path>, private: bool) -> Self {
+        let namespace = (!private).then(|| match profile_directory {
+            Some(profile_directory) => format!("http-auth:{}", profile_directory.display()),
+            None => "http-auth".to_string(),
+        });
+        Self {
+            namespace,
+            answered: HashMap::new(),
+        }
+    }
+
+    /// Credentials remembered for the origin of the URL, `None` if there are none or the server
+    /// just rejected them.
+    pub fn get(
+        &mut self,
+        secret_store: &SecretStore,
+        url: &url::Url,
+        for_proxy: bool,
+    ) -> Option<AuthenticationResponse> {
+        let namespace = self.namespace.as_ref()?;
+        let key = credentials_key(url, for_proxy)?;
+        if self
+            .answered
+            .remove(&key)
+            .is_some_and(|answered| answered.elapsed() < REJECTED_WITHIN)
+        {
+            return None;
+        }
+        let secret = secret_store.get(namespace, &key).unwrap_or_else(|e| {
+            log::error!("Verso failed to get the credentials of {key}: {e}");
+            None
+        })?;
+        let credentials = serde_json::from_str::<HttpCredentials>(&secret)
+            .inspect_err(|e| log::error!("Invalid credentials of {key}: {e}"))
+            .ok()?;
+        self.answered.insert(key, Instant::now());
+        Some(AuthenticationResponse {
+            username: credentials.username,
+            password: credentials.password,
+        })
+    }
+
+    /// Remember the credentials the user signs in to the origin of the URL with. They are only
+    /// answered again once the server accepts them.
+    pub fn remember(
+        &mut self,
+        secret_store: &SecretStore,
+        url: &url::Url,
+        for_proxy: bool,
+        credentials: HttpCredentials,
+    ) {
+        let (Some(namespace), Some(key)) = (&self.namespace, credentials_key(url, for_proxy))
+        else {
+            return;
+        };
+        let secret = serde_json::to_string(&credentials).unwrap();
+        if let Err(e) = secret_store.set(namespace, &key, &secret) {
+            log::error!("Verso failed to remember the credentials of {key}: {e}");
+            return;
+        }
+        self.answered.insert(key, Instant::now());
+    }
+}
+
+fn credentials_key(url: &url::Url, for_proxy: bool) -> Option<String> {
+    let origin = url.origin();
+    origin.is_tuple().then(|| {
+        let origin = origin.ascii_serialization();
+        if for_proxy {
+            format!("proxy:{origin}")
+        } else {
+            origin
+        }
+    })
+}
+
+impl Window {
+    /// Send the HTTP authentication asked for the webview to the controller if it listens to
+    /// them, the request waits until it's answered with a
+    /// [`versoview_messages::ToVersoMessage::AuthenticationResponse`]. The response sender is
+    /// given back if the user has to be asked instead.
+    pub(crate) fn delegate_authentication(
+        &mut self,
+        webview_id: WebViewId,
+        url: url::Url,
+        for_proxy: bool,
+        response_sender: IpcSender<Option<AuthenticationResponse>>,
+        to_controller_sender: &Option<IpcSender<ToControllerMessage>>,
+    ) -> Option<IpcSender<Option<AuthenticationResponse>>> {
+        let (Some(to_controller_sender), Some(requests)) = (
+            to_controller_sender,
+            &mut self.event_listeners.on_authentication,
+        ) else {
+            return Some(response_sender);
+        };
+        let id = uuid::Uuid::new_v4();
+        let request = AuthenticationRequest {
+            webview_id: bincode::serialize(&webview_id).unwrap(),
+            url,
+            for_proxy,
+        };
+        match to_controller_sender.send(ToControllerMessage::OnAuthentication(id, request)) {
+            Ok(_) => {
+                requests.insert(id, response_sender);
+                None
+            }
+            Err(error) => {
+                log::error!("Verso failed to send OnAuthentication to controller: {error}");
+                Some(response_sender)
+            }
+        }
+    }
+}
+
+/// Answer the request waiting for its credentials with the ones of the controller.
+pub(crate) fn respond(
+    response_sender: IpcSender<Option<AuthenticationResponse>>,
+    credentials: Option<HttpCredentials>,
+) {
+    let _ = response_sender.send(credentials.map(|credentials| AuthenticationResponse {
+        username: credentials.username,
+        password: credentials.password,
+    }));
+}
@@ -0,0 +1,619 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs::{self, File},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crossbeam_channel::Sender;
+use embedder_traits::{EmbedderMsg, WebResourceResponse, WebResourceResponseMsg};
+use headers::{CacheControl, Date, Expires, HeaderMapExt, LastModified};
+use http::{
+    HeaderMap, HeaderName, HeaderValue, Method, StatusCode,
+    header::{
+        ACCEPT_ENCODING, AUTHORIZATION, CONTENT_ENCODING, CONTENT_LENGTH, COOKIE, ETAG,
+        IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED, RANGE, SET_COOKIE, TRANSFER_ENCODING,
+        VARY,
+    },
+};
+use ipc_channel::ipc;
+use net_traits::{CookieSource, CoreResourceMsg, IpcSend, ResourceThreads};
+use rustls::ClientConfig;
+use serde::{Deserialize, Serialize};
+use servo_url::ServoUrl;
+use sha2::{Digest, Sha256};
+
+use crate::cookies::site;
+
+/// Name of the cache directory in the profile directory.
+const CACHE_DIRECTORY: &str = "http_cache";
+/// File of the cache directory listing the cached responses.
+const INDEX_FILE: &str = "index.json";
+/// Longest heuristic freshness of responses without an expiry, see
+/// [RFC 9111](https://httpwg.org/specs/rfc9111.html#heuristic.freshness).
+const MAX_HEURISTIC_FRESHNESS: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Seconds since the Unix epoch.
+fn unix_time(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+/// A response saved in the cache, its body is in a file named after its key.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CacheEntry {
+    url: String,
+    status: u16,
+    headers: Vec<(String, String)>,
+    /// Size of the body, in bytes.
+    size: u64,
+    /// Time the response is fresh until, in seconds since the Unix epoch.
+    fresh_until: u64,
+    /// Time the response was last served or stored, the least recently used ones are evicted
+    /// first.
+    last_used: u64,
+}
+
+impl CacheEntry {
+    fn header(&self, name: &HeaderName) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name.as_str()))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CacheIndex {
+    entries: HashMap<String, CacheEntry>,
+    /// Total size of the bodies, in bytes.
+    size: u64,
+}
+
+struct Cache {
+    directory: PathBuf,
+    /// Most bytes of bodies kept in the cache.
+    max_size: u64,
+    /// Whether responses are keyed by the site of the tab too.
+    partitioned: bool,
+    index: CacheIndex,
+    /// Keys of the responses being fetched to be stored.
+    pending: HashSet<String>,
+}
+
+impl Cache {
+    fn body_path(&self, key: &str) -> PathBuf {
+        self.directory.join(key)
+    }
+
+    fn remove(&mut self, key: &str) {
+        if let Some(entry) = self.index.entries.remove(key) {
+            self.index.size = self.index.size.saturating_sub(entry.size);
+            let _ = fs::remove_file(self.body_path(key));
+        }
+    }
+
+    fn insert(&mut self, key: String, entry: CacheEntry, body: &[u8]) {
+        self.remove(&key);
+        if entry.size > self.max_size {
+            return;
+        }
+        if let Err(e) = fs::write(self.body_path(&key), body) {
+            log::error!("Failed to write HTTP cache entry of {}: {e}", entry.url);
+            return;
+        }
+        self.index.size += entry.size;
+        self.index.entries.insert(key, entry);
+        self.evict();
+    }
+
+    /// Remove the least recently used responses until the cache fits its size limit.
+    fn evict(&mut self) {
+        while self.index.size > self.max_size {
+            let Some(key) = self
+                .index
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| key.clone())
+            else {
+                break;
+            };
+            self.remove(&key);
+        }
+    }
+
+    fn save_index(&self) -> Result<(), std::io::Error> {
+        let file = File::create(self.directory.join(INDEX_FILE))?;
+        serde_json::to_writer(file, &self.index)?;
+        Ok(())
+    }
+}
+
+/// A response fetched to be stored, and the entry it revalidates if any.
+struct StoreRequest {
+    key: String,
+    url: url::Url,
+    headers: HeaderMap,
+    stale: Option<CacheEntry>,
+}
+
+/// HTTP cache of the profile on disk, so resources aren't fetched again on every launch.
+///
+/// Servo's own HTTP cache is in memory and doesn't hand the responses it gets to the embedder,
+/// so responses are fetched once more in the background to be stored, without cookies and
+/// without following redirects. Responses which are private, vary on more than their encoding or
+/// set cookies aren't stored. Fresh responses are served from disk, stale ones are loaded by
+/// Servo and stored again. Responses of URLs the jar has cookies for are neither stored nor
+/// served, they may be personal.
+///
+/// When it's partitioned, responses are keyed by the site of the tab they're requested for as
+/// well as their URL, so a site can't tell whether a resource was loaded by another one.
+pub(crate) struct DiskCache {
+    cache: Arc<Mutex<Cache>>,
+    store_sender: Sender<StoreRequest>,
+    /// To check the cookies of a URL before serving it.
+    resource_threads: ResourceThreads,
+}
+
+impl DiskCache {
+    /// Open the cache in the profile directory, with a size limit in bytes. The cache is disabled
+    /// if it's 0. Responses are fetched with the TLS config, see
+    /// [`crate::cert_pinning::CertificatePins::client_config`], and never stored if there's none.
+    pub fn new(
+        profile_directory: &Path,
+        max_size: u64,
+        partitioned: bool,
+        tls_config: Option<ClientConfig>,
+        resource_threads: ResourceThreads,
+    ) -> Self {
+        let directory = profile_directory.join(CACHE_DIRECTORY);
+        if let Err(e) = fs::create_dir_all(&directory) {
+            log::error!(
+                "Failed to create HTTP cache directory {}: {e}",
+                directory.display()
+            );
+        }
+        let index = File::open(directory.join(INDEX_FILE))
+            .ok()
+            .and_then(|file| serde_json::from_reader::<_, CacheIndex>(file).ok())
+            .unwrap_or_default();
+        let mut cache = Cache {
+            directory,
+            max_size,
+            partitioned,
+            index,
+            pending: HashSet::new(),
+        };
+        // The size limit may be smaller than last time
+        cache.evict();
+
+        let cache = Arc::new(Mutex::new(cache));
+        let (store_sender, store_receiver) = crossbeam_channel::unbounded::<StoreRequest>();
+        let store_cache = cache.clone();
+        let store_resource_threads = resource_threads.clone();
+        let client = tls_config.and_then(|tls_config| {
+            reqwest::blocking::Client::builder()
+                // A redirected response belongs to the URL it's redirected to, Servo follows it
+                .redirect(reqwest::redirect::Policy::none())
+                .use_preconfigured_tls(tls_config)
+                .build()
+                .inspect_err(|e| log::error!("Verso failed to build the HTTP cache client: {e}"))
+                .ok()
+        });
+        if let Some(client) = client {
+            let result = thread::Builder::new()
+                .name("HttpCacheStore".to_owned())
+                .spawn(move || {
+                    for request in store_receiver {
+                        store(&client, &store_cache, &store_resource_threads, request);
+                    }
+                });
+            if let Err(e) = result {
+                log::error!("Failed to spawn HTTP cache thread: {e}");
+            }
+        }
+
+        Self {
+            cache,
+            store_sender,
+            resource_threads,
+        }
+    }
+
+    /// Change the size limit of the cache, in bytes.
+    pub fn set_max_size(&self, max_size: u64) {
+        let mut cache = self.cache.lock().unwrap();
+        cache.max_size = max_size;
+        cache.evict();
+    }
+
+    /// Change whether responses are keyed by the site of the tab. Responses stored in the other
+    /// mode are evicted as the cache fills up.
+    pub fn set_partitioned(&self, partitioned: bool) {
+        self.cache.lock().unwrap().partitioned = partitioned;
+    }
+
+    /// Remove every response of the cache.
+    pub fn clear(&self) {
+        let mut cache = self.cache.lock().unwrap();
+        let keys: Vec<_> = cache.index.entries.keys().cloned().collect();
+        for key in keys {
+            cache.remove(&key);
+        }
+        if let Err(e) = cache.save_index() {
+            log::error!("Failed to save HTTP cache index: {e}");
+        }
+    }
+
+    /// Save the list of cached responses, so they are found on the next launch.
+    pub fn save_index(&self) {
+        if let Err(e) = self.cache.lock().unwrap().save_index() {
+            log::error!("Failed to save HTTP cache index: {e}");
+        }
+    }
+
+    /// Answer a request with its cached response if it's fresh and return `None`, other
+    /// messages are given back. Cacheable requests which aren't answered are stored in the
+    /// background. When `reload` is true the cached response is never served, and it's fetched
+    /// again unconditionally to replace it. `top_level_url` is the URL of the tab the request is
+    /// sent for, its site is part of the key if the cache is partitioned.
+    pub(crate) fn handle_request(
+        &self,
+        msg: EmbedderMsg,
+        top_level_url: Option<&ServoUrl>,
+        reload: bool,
+    ) -> Option<EmbedderMsg> {
+        let EmbedderMsg::WebResourceRequested(_, ref request, ref sender) = msg else {
+            return Some(msg);
+        };
+        if request.method != Method::GET
+            || !matches!(request.url.scheme(), "http" | "https")
+            || request.headers.contains_key(AUTHORIZATION)
+            || request.headers.contains_key(RANGE)
+        {
+            return Some(msg);
+        }
+        let mut url = request.url.clone();
+        url.set_fragment(None);
+        let now = unix_time(SystemTime::now());
+
+        let mut cache = self.cache.lock().unwrap();
+        if cache.max_size == 0 {
+            return Some(msg);
+        }
+        let partition = top_level_url
+            .and_then(|url| url.host_str())
+            .filter(|_| cache.partitioned);
+        let key = match partition {
+            Some(host) => format!("{:x}", Sha256::digest(format!("{} {url}", site(host)))),
+            None => format!("{:x}", Sha256::digest(url.as_str())),
+        };
+        let entry = cache.index.entries.get(&key).filter(|_| !reload).cloned();
+        let Some(entry) = entry.filter(|entry| entry.fresh_until > now) else {
+            if cache.pending.insert(key.clone()) {
+                let result = self.store_sender.send(StoreRequest {
+                    key: key.clone(),
+                    url,
+                    headers: request.headers.clone(),
+                    stale: entry,
+                });
+                if result.is_err() {
+                    cache.pending.remove(&key);
+                }
+            }
+            return Some(msg);
+        };
+        let body = match fs::read(cache.body_path(&key)) {
+            Ok(body) => body,
+            Err(_) => {
+                cache.remove(&key);
+                return Some(msg);
+            }
+        };
+        if let Some(entry) = cache.index.entries.get_mut(&key) {
+            entry.last_used = now;
+        }
+        drop(cache);
+        if has_cookies(&self.resource_threads, &url) {
+            return Some(msg);
+        }
+
+        let mut headers = HeaderMap::new();
+        for (name, value) in &entry.headers {
+            if let (Ok(name), Ok(value)) = (
+                HeaderName::from_bytes(name.as_bytes()),
+                HeaderValue::from_str(value),
+            ) {
+                headers.append(name, value);
+            }
+        }
+        log::debug!("Verso is serving {} from the HTTP cache", request.url);
+        let _ = sender
+            .send(WebResourceResponseMsg::Start(
+                WebResourceResponse::new(request.url.clone())
+                    .headers(headers)
+                    .status_code(StatusCode::from_u16(entry.status).unwrap_or(StatusCode::OK)),
+            ))
+            .and_then(|_| sender.send(WebResourceResponseMsg::SendBodyData(body)))
+            .and_then(|_| sender.send(WebResourceResponseMsg::FinishLoad));
+        None
+    }
+}
+
+/// How long a response may be served from the cache, or `None` if it must not be stored.
+fn freshness(headers: &HeaderMap) -> Option<Duration> {
+    if headers.contains_key(SET_COOKIE)
+        || headers
+            .get_all(VARY)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .any(|field| !field.trim().eq_ignore_ascii_case("accept-encoding"))
+    {
+        return None;
+    }
+    let cache_control = headers.typed_get::<CacheControl>();
+    if let Some(cache_control) = &cache_control {
+        if cache_control.no_store() || cache_control.private() {
+            return None;
+        }
+        if cache_control.no_cache() {
+            return Some(Duration::ZERO);
+        }
+        if let Some(max_age) = cache_control.max_age() {
+            return Some(max_age);
+        }
+    }
+    let date = headers
+        .typed_get::<Date>()
+        .map(SystemTime::from)
+        .unwrap_or_else(SystemTime::now);
+    if let Some(expires) = headers.typed_get::<Expires>() {
+        return Some(
+            SystemTime::from(expires)
+                .duration_since(date)
+                .unwrap_or_default(),
+        );
+    }
+    // Responses without an expiry are fresh for a tenth of the time since they were modified
+    let last_modified = headers.typed_get::<LastModified>()?;
+    let age = date
+        .duration_since(SystemTime::from(last_modified))
+        .unwrap_or_default();
+    Some((age / 10).min(MAX_HEURISTIC_FRESHNESS))
+}
+
+/// Whether the jar has cookies Servo would send with a request of the URL, or they can't be read.
+fn has_cookies(resource_threads: &ResourceThreads, url: &url::Url) -> bool {
+    let Ok((sender, receiver)) = ipc::channel() else {
+        return true;
+    };
+    let url = ServoUrl::from_url(url.clone());
+    if let Err(e) = resource_threads.send(CoreResourceMsg::GetCookiesForUrl(
+        url,
+        sender,
+        CookieSource::HTTP,
+    )) {
+        log::error!("Verso failed to send GetCookiesForUrl to resource thread: {e}");
+        return true;
+    }
+    !matches!(receiver.recv(), Ok(None))
+}
+
+/// Fetch a response and store it in the cache if it's cacheable. A stale entry is revalidated
+/// with its validators, and kept if it's not modified.
+fn store(
+    client: &reqwest::blocking::Client,
+    cache: &Mutex<Cache>,
+    resource_threads: &ResourceThreads,
+    request: StoreRequest,
+) {
+    let StoreRequest {
+        key,
+        url,
+        mut headers,
+        stale,
+    } = request;
+    if has_cookies(resource_threads, &url) {
+        let mut cache = cache.lock().unwrap();
+        cache.pending.remove(&key);
+        cache.remove(&key);
+        return;
+    }
+    // Only responses every user gets are stored, and bodies are stored decoded
+    headers.remove(COOKIE);
+    headers.remove(ACCEPT_ENCODING);
+    if let Some(stale) = &stale {
+        if let Some(etag) = stale
+            .header(&ETAG)
+            .and_then(|v| HeaderValue::from_str(v).ok())
+        {
+            headers.insert(IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = stale
+            .header(&LAST_MODIFIED)
+            .and_then(|v| HeaderValue::from_str(v).ok())
+        {
+            headers.insert(IF_MODIFIED_SINCE, last_modified);
+        }
+    }
+
+    let result = client
+        .get(url.clone())
+        .headers(headers)
+        .send()
+        .and_then(|response| {
+            let status = response.status();
+            let headers = response.headers().clone();
+            let redirected = response.url() != &url;
+            response
+                .bytes()
+                .map(|body| (status, headers, body, redirected))
+        });
+    let mut cache = cache.lock().unwrap();
+    cache.pending.remove(&key);
+    let (status, headers, body, redirected) = match result {
+        Ok(response) => response,
+        Err(e) => {
+            log::debug!("Verso failed to fetch {url} for the HTTP cache: {e}");
+            return;
+        }
+    };
+    let now = SystemTime::now();
+
+    if status == StatusCode::NOT_MODIFIED {
+        let Some(mut entry) = stale else {
+            return;
+        };
+        match freshness(&headers) {
+            Some(freshness) => {
+                entry.fresh_until = unix_time(now + freshness);
+                entry.last_used = unix_time(now);
+                if let Some(cached) = cache.index.entries.get_mut(&key) {
+                    *cached = entry;
+                }
+            }
+            None => cache.remove(&key),
+        }
+        return;
+    }
+
+    let Some(freshness) = freshness(&headers).filter(|_| status == StatusCode::OK && !redirected)
+    else {
+        cache.remove(&key);
+        return;
+    };
+    let headers = headers
+        .iter()
+        .filter(|(name, _)| {
+            ![
+                SET_COOKIE,
+                CONTENT_ENCODING,
+                CONTENT_LENGTH,
+                TRANSFER_ENCODING,
+            ]
+            .contains(name)
+        })
+        .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+        .collect();
+    let entry = CacheEntry {
+        url: url.to_string(),
+        status: status.as_u16(),
+        headers,
+        size: body.len() as u64,
+        fresh_until: unix_time(now + freshness),
+        last_used: unix_time(now),
+    };
+    cache.insert(key, entry, &body);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(pairs: &[(&str, &str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.append(
+                HeaderName::from_bytes(name.as_bytes()).unwrap(),
+                HeaderValue::from_str(value).unwrap(),
+            );
+        }
+        headers
+    }
+
+    const DATE: &str = "Wed, 21 Oct 2015 07:28:00 GMT";
+
+    #[test]
+    fn test_freshness_cache_control() {
+        assert_eq!(
+            freshness(&headers(&[("cache-control", "public, max-age=60")])),
+            Some(Duration::from_secs(60))
+        );
+        assert_eq!(
+            freshness(&headers(&[("cache-control", "no-cache")])),
+            Some(Duration::ZERO)
+        );
+        assert_eq!(freshness(&headers(&[("cache-control", "no-store")])), None);
+        assert_eq!(
+            freshness(&headers(&[("cache-control", "private, max-age=60")])),
+            None
+        );
+        // Cache-Control takes precedence over Expires
+        assert_eq!(
+            freshness(&headers(&[
+                ("cache-control", "max-age=5"),
+                ("date", DATE),
+                ("expires", "Wed, 21 Oct 2015 08:28:00 GMT"),
+            ])),
+            Some(Duration::from_secs(5))
+        );
+    }
+
+    #[test]
+    fn test_freshness_not_stored() {
+        assert_eq!(
+            freshness(&headers(&[
+                ("cache-control", "max-age=60"),
+                ("set-cookie", "id=1"),
+            ])),
+            None
+        );
+        assert_eq!(
+            freshness(&headers(&[
+                ("cache-control", "max-age=60"),
+                ("vary", "Accept-Encoding, Cookie"),
+            ])),
+            None
+        );
+        assert_eq!(
+            freshness(&headers(&[
+                ("cache-control", "max-age=60"),
+                ("vary", "accept-encoding"),
+            ])),
+            Some(Duration::from_secs(60))
+        );
+        // Nothing tells how long it's fresh
+        assert_eq!(freshness(&headers(&[("date", DATE)])), None);
+    }
+
+    #[test]
+    fn test_freshness_expires() {
+        assert_eq!(
+            freshness(&headers(&[
+                ("date", DATE),
+                ("expires", "Wed, 21 Oct 2015 08:28:00 GMT"),
+            ])),
+            Some(Duration::from_secs(60 * 60))
+        );
+        assert_eq!(
+            freshness(&headers(&[
+                ("date", DATE),
+                ("expires", "Wed, 21 Oct 2015 06:28:00 GMT"),
+            ])),
+            Some(Duration::ZERO)
+        );
+    }
+
+    #[test]
+    fn test_freshness_heuristic() {
+        assert_eq!(
+            freshness(&headers(&[
+                ("date", DATE),
+                ("last-modified", "Fri, 16 Oct 2015 07:28:00 GMT"),
+            ])),
+            Some(Duration::from_secs(12 * 60 * 60))
+        );
+        assert_eq!(
+            freshness(&headers(&[
+                ("date", DATE),
+                ("last-modified", "Mon, 21 Oct 2013 07:28:00 GMT"),
+            ])),
+            Some(MAX_HEURISTIC_FRESHNESS)
+        );
+    }
+}
@@ -0,0 +1,163 @@
+use std::{collections::HashMap, fs::File, path::PathBuf};
+
+use embedder_traits::{EmbedderMsg, WebResourceResponseMsg};
+use http::{HeaderMap, header::ACCEPT};
+use versoview_messages::{ImageLoading, ImagePolicy};
+use webrender_api::DocumentId;
+use winit::window::WindowId;
+
+use crate::window::Window;
+
+/// Script which applies the image policy to every document, asking Verso for it with the
+/// `IMAGE_POLICY` request of the Verso bridge. If images load on click, the blocked ones are
+/// outlined and load with the `LOAD_IMAGE` request when clicked. In data saver mode, responsive
+/// images are narrowed to the smallest candidate of their `srcset` as soon as they're inserted.
+pub(crate) const IMAGE_POLICY_SCRIPT: &str = r#"
+(() => {
+  if (location.protocol === 'verso:') {
+    return;
+  }
+  const response = window.prompt('VERSO::IMAGE_POLICY');
+  if (!response) {
+    return;
+  }
+  const policy = JSON.parse(response);
+
+  if (policy.data_saver) {
+    const smallest = (srcset) => {
+      let best = null;
+      for (const candidate of srcset.split(/,\s+/)) {
+        const [url, descriptor = '1x'] = candidate.trim().split(/\s+/);
+        const value = parseFloat(descriptor);
+        if (url && !Number.isNaN(value) && (best === null || value < best.value)) {
+          best = { url, value };
+        }
+      }
+      return best?.url;
+    };
+    const narrow = (element) => {
+      const srcset = element.getAttribute('srcset');
+      const url = srcset && smallest(srcset);
+      if (url) {
+        element.setAttribute('srcset', url);
+      }
+    };
+    new MutationObserver((records) => {
+      for (const record of records) {
+        for (const node of record.addedNodes) {
+          if (node instanceof HTMLImageElement || node instanceof HTMLSourceElement) {
+            narrow(node);
+          } else if (node instanceof Element) {
+            node.querySelectorAll('img[srcset], source[srcset]').forEach(narrow);
+          }
+        }
+      }
+    }).observe(document, { childList: true, subtree: true });
+  }
+
+  if (policy.loading === 'click-to-load') {
+    addEventListener('error', (event) => {
+      const image = event.target;
+      if (!(image instanceof HTMLImageElement) || 'versoBlocked' in image.dataset) {
+        return;
+      }
+      image.dataset.versoBlocked = '';
+      image.style.outline = '1px dashed #808080';
+      image.style.cursor = 'pointer';
+      image.style.minWidth ||= '24px';
+      image.style.minHeight ||= '24px';
+      image.title ||= 'Click to load the image';
+    }, true);
+
+    addEventListener('click', (event) => {
+      const image = event.target;
+      if (!(image instanceof HTMLImageElement) || !('versoBlocked' in image.dataset)) {
+        return;
+      }
+      event.preventDefault();
+      event.stopImmediatePropagation();
+      delete image.dataset.versoBlocked;
+      image.style.outline = '';
+      image.style.cursor = '';
+      const url = new URL(image.currentSrc || image.src);
+      window.prompt(`VERSO::LOAD_IMAGE::${url.href}`);
+      // The failed load is cached by URL, so the image is loaded again with a fragment
+      url.hash ||= 'verso-load-image';
+      image.removeAttribute('srcset');
+      image.src = url.href;
+    }, true);
+  }
+})();
+"#;
+
+/// Cancel the image requests the image policy blocks and return `None`, other messages are given
+/// back. Images are told apart by the `Accept` header Servo sends for them. Images opened as the
+/// page of a tab, and the ones clicked to load in the current document, are always loaded.
+pub(crate) fn apply_image_policy(
+    msg: EmbedderMsg,
+    windows: &mut HashMap<WindowId, (Window, DocumentId)>,
+    policy: &ImagePolicy,
+) -> Option<EmbedderMsg> {
+    let EmbedderMsg::WebResourceRequested(webview_id, ref request, ref sender) = msg else {
+        return Some(msg);
+    };
+    let tab = webview_id.and_then(|webview_id| {
+        windows
+            .values_mut()
+            .find_map(|(window, _)| window.tab_manager.tab_mut(webview_id))
+    });
+    if request.is_for_main_frame {
+        if let Some(tab) = tab.filter(|_| !request.is_redirect) {
+            tab.clear_loaded_images();
+        }
+        return Some(msg);
+    }
+    if policy.loading == ImageLoading::All || !is_image_request(&request.headers) {
+        return Some(msg);
+    }
+    let mut url = request.url.clone();
+    url.set_fragment(None);
+    if policy.loading == ImageLoading::ClickToLoad && tab.is_some_and(|tab| tab.image_loaded(&url))
+    {
+        return Some(msg);
+    }
+    log::debug!("Verso blocked image {}", request.url);
+    let _ = sender.send(WebResourceResponseMsg::CancelLoad);
+    None
+}
+
+fn is_image_request(headers: &HeaderMap) -> bool {
+    headers
+        .get(ACCEPT)
+        .and_then(|accept| accept.to_str().ok())
+        .is_some_and(|accept| accept.trim_start().starts_with("image/"))
+}
+
+pub(crate) struct ImagePolicyStorage {
+    config_dir_path: PathBuf,
+}
+
+impl ImagePolicyStorage {
+    /// Create a new `ImagePolicyStorage`.
+    pub fn new(config_dir_path: PathBuf) -> Self {
+        Self { config_dir_path }
+    }
+
+    fn image_policy_file_path(&self) -> PathBuf {
+        self.config_dir_path.join("images.json")
+    }
+
+    /// Load the image policy from disk.
+    pub fn load_from_file(&self) -> Result<ImagePolicy, std::io::Error> {
+        let file = File::open(self.image_policy_file_path())?;
+        let policy = serde_json::from_reader(file)?;
+        Ok(policy)
+    }
+
+    /// Save the image policy to disk.
+    pub fn save_to_file(&self, policy: &ImagePolicy) -> Result<(), std::io::Error> {
+        let file = File::create(self.image_policy_file_path())?;
+        serde_json::to_writer(file, policy)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,602 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use cookie::{Cookie, time::OffsetDateTime};
+use directories::BaseDirs;
+use hyper_serde::Serde;
+use net_traits::{CookieSource, CoreResourceMsg, IpcSend, ResourceThreads};
+use rusqlite::{Connection, OpenFlags};
+use serde::Deserialize;
+use servo_url::ServoUrl;
+use versoview_messages::Browser;
+
+use crate::{
+    bookmark::Bookmark,
+    errors::{Error, Result},
+    history::HistoryEntry,
+};
+
+/// Milliseconds between 1601-01-01, the epoch of Chrome timestamps, and the Unix epoch.
+const CHROME_EPOCH_OFFSET_MS: i64 = 11_644_473_600_000;
+
+/// A cookie read from another browser.
+#[derive(Debug, Clone)]
+pub struct ImportedCookie {
+    /// Domain of the cookie, with a leading `.` if it applies to subdomains.
+    pub domain: String,
+    /// Path of the cookie.
+    pub path: String,
+    /// Name of the cookie.
+    pub name: String,
+    /// Value of the cookie.
+    pub value: String,
+    /// Only send the cookie over HTTPS.
+    pub secure: bool,
+    /// Hide the cookie from scripts.
+    pub http_only: bool,
+    /// Expiry time in seconds since the Unix epoch, `None` for session cookies.
+    pub expires: Option<i64>,
+}
+
+/// Everything read from the profile of another browser.
+#[derive(Debug, Default)]
+pub struct ImportedData {
+    /// Cookies of the profile.
+    pub cookies: Vec<ImportedCookie>,
+    /// Bookmarks of the profile, folders are flattened.
+    pub bookmarks: Vec<Bookmark>,
+    /// History of the profile.
+    pub history: Vec<HistoryEntry>,
+    /// Number of cookies which can't be imported because they are encrypted.
+    pub skipped_cookies: usize,
+}
+
+/// Get the default profile directory of a browser on this platform.
+pub fn default_profile_directory(browser: Browser) -> Option<PathBuf> {
+    let base_dirs = BaseDirs::new()?;
+    match browser {
+        Browser::Chrome => {
+            #[cfg(linux)]
+            let dir = base_dirs.config_dir().join("google-chrome");
+            #[cfg(macos)]
+            let dir = base_dirs.data_dir().join("Google/Chrome");
+            #[cfg(windows)]
+            let dir = base_dirs.data_local_dir().join("Google/Chrome/User Data");
+            #[cfg(not(any(linux, macos, windows)))]
+            let dir = base_dirs.home_dir().join(".config/google-chrome");
+            Some(dir.join("Default")).filter(|dir| dir.is_dir())
+        }
+        Browser::Firefox => {
+            #[cfg(linux)]
+            let dir = base_dirs.home_dir().join(".mozilla/firefox");
+            #[cfg(macos)]
+            let dir = base_dirs.data_dir().join("Firefox/Profiles");
+            #[cfg(windows)]
+            let dir = base_dirs.data_dir().join("Mozilla/Firefox/Profiles");
+            #[cfg(not(any(linux, macos, windows)))]
+            let dir = base_dirs.home_dir().join(".mozilla/firefox");
+            find_firefox_profile(&dir)
+        }
+    }
+}
+
+/// Find the profile in use, prefer the one Firefox creates by default, otherwise the most
+/// recently used one.
+fn find_firefox_profile(profiles_dir: &Path) -> Option<PathBuf> {
+    let mut profiles: Vec<PathBuf> = fs::read_dir(profiles_dir)
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.join("places.sqlite").is_file())
+        .collect();
+    if let Some(pos) = profiles.iter().position(|path| {
+        path.extension()
+            .is_some_and(|extension| extension == "default-release")
+    }) {
+        return Some(profiles.swap_remove(pos));
+    }
+    profiles.sort_by_key(|path| {
+        fs::metadata(path.join("places.sqlite"))
+            .and_then(|metadata| metadata.modified())
+            .ok()
+    });
+    profiles.pop()
+}
+
+/// Read cookies, bookmarks and history from a browser profile. If `profile_directory` is
+/// `None`, the default profile of the browser is used. Parts which fail to be read are
+/// logged and skipped.
+pub fn import(browser: Browser, profile_directory: Option<PathBuf>) -> Result<ImportedData> {
+    let profile_directory = profile_directory
+        .or_else(|| default_profile_directory(browser))
+        .ok_or(Error::ProfileNotFound)?;
+    log::info!(
+        "Verso is importing {browser:?} profile {}",
+        profile_directory.display()
+    );
+
+    let mut data = ImportedData::default();
+    let (cookies, bookmarks, history) = match browser {
+        Browser::Chrome => (
+            read_chrome_cookies(&profile_directory),
+            read_chrome_bookmarks(&profile_directory),
+            read_chrome_history(&profile_directory),
+        ),
+        Browser::Firefox => (
+            read_firefox_cookies(&profile_directory),
+            read_firefox_bookmarks(&profile_directory),
+            read_firefox_history(&profile_directory),
+        ),
+    };
+    match cookies {
+        Ok((cookies, skipped_cookies)) => {
+            data.cookies = cookies;
+            data.skipped_cookies = skipped_cookies;
+        }
+        Err(e) => log::error!("Failed to import {browser:?} cookies: {e}"),
+    }
+    match bookmarks {
+        Ok(bookmarks) => data.bookmarks = bookmarks,
+        Err(e) => log::error!("Failed to import {browser:?} bookmarks: {e}"),
+    }
+    match history {
+        Ok(history) => data.history = history,
+        Err(e) => log::error!("Failed to import {browser:?} history: {e}"),
+    }
+    Ok(data)
+}
+
+/// Add an imported cookie to the cookie jar of Servo.
+pub(crate) fn set_cookie(resource_threads: &ResourceThreads, cookie: ImportedCookie) {
+    let expires = match cookie.expires {
+        Some(expires) => match OffsetDateTime::from_unix_timestamp(expires) {
+            Ok(expires) if expires > OffsetDateTime::now_utc() => Some(expires),
+            // Skip expired cookies
+            _ => return,
+        },
+        None => None,
+    };
+    let scheme = if cookie.secure { "https" } else { "http" };
+    let host = cookie.domain.trim_start_matches('.');
+    let Ok(url) = ServoUrl::parse(&format!("{scheme}://{host}{}", cookie.path)) else {
+        log::warn!(
+            "Verso can't import cookie of invalid domain {}",
+            cookie.domain
+        );
+        return;
+    };
+
+    let mut builder = Cookie::build((cookie.name, cookie.value))
+        .path(cookie.path)
+        .secure(cookie.secure)
+        .http_only(cookie.http_only);
+    // Cookies without a leading `.` only apply to the exact host
+    if cookie.domain.starts_with('.') {
+        builder = builder.domain(cookie.domain);
+    }
+    if let Some(expires) = expires {
+        builder = builder.expires(expires);
+    }
+
+    if let Err(e) = resource_threads.send(CoreResourceMsg::SetCookieForUrl(
+        url,
+        Serde(builder.build()),
+        CookieSource::HTTP,
+    )) {
+        log::error!("Verso failed to send imported cookie to resource thread: {e}");
+    }
+}
+
+/// Browsers lock their databases while running, so read from a copy of it.
+struct DatabaseCopy {
+    connection: Option<Connection>,
+    paths: Vec<PathBuf>,
+}
+
+impl DatabaseCopy {
+    fn open(path: &Path) -> Result<Self> {
+        let copy_path = std::env::temp_dir().join(format!(
+            "verso-import-{}.sqlite",
+            uuid::Uuid::new_v4().simple()
+        ));
+        fs::copy(path, &copy_path)?;
+        let mut paths = vec![copy_path.clone()];
+        // Recent changes may still be in the write-ahead log
+        let mut wal_path = path.as_os_str().to_owned();
+        wal_path.push("-wal");
+        let mut wal_copy_path = copy_path.as_os_str().to_owned();
+        wal_copy_path.push("-wal");
+        if fs::copy(&wal_path, &wal_copy_path).is_ok() {
+            paths.push(wal_copy_path.into());
+        }
+
+        let connection = Connection::open_with_flags(&copy_path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+        Ok(Self {
+            connection: Some(connection),
+            paths,
+        })
+    }
+
+    fn connection(&self) -> &Connection {
+        self.connection.as_ref().unwrap()
+    }
+}
+
+impl Drop for DatabaseCopy {
+    fn drop(&mut self) {
+        // Close the connection before removing its files
+        self.connection.take();
+        for path in &self.paths {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+fn read_firefox_cookies(profile: &Path) -> Result<(Vec<ImportedCookie>, usize)> {
+    let database = DatabaseCopy::open(&profile.join("cookies.sqlite"))?;
+    let mut statement = database
+        .connection()
+        .prepare("SELECT host, path, name, value, isSecure, isHttpOnly, expiry FROM moz_cookies")?;
+    let cookies = statement
+        .query_map([], |row| {
+            let expiry: i64 = row.get(6)?;
+            Ok(ImportedCookie {
+                domain: row.get(0)?,
+                path: row.get(1)?,
+                name: row.get(2)?,
+                value: row.get(3)?,
+                secure: row.get(4)?,
+                http_only: row.get(5)?,
+                // Newer versions of Firefox store milliseconds instead of seconds
+                expires: Some(if expiry > 100_000_000_000 {
+                    expiry / 1000
+                } else {
+                    expiry
+                }),
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok((cookies, 0))
+}
+
+fn read_firefox_bookmarks(profile: &Path) -> Result<Vec<Bookmark>> {
+    let database = DatabaseCopy::open(&profile.join("places.sqlite"))?;
+    let mut statement = database.connection().prepare(
+        "SELECT b.title, p.url FROM moz_bookmarks b JOIN moz_places p ON b.fk = p.id
+        WHERE b.type = 1 AND p.url NOT LIKE 'place:%' ORDER BY b.position",
+    )?;
+    let bookmarks = statement
+        .query_map([], |row| {
+            let title: Option<String> = row.get(0)?;
+            let url: String = row.get(1)?;
+            Ok(Bookmark::new(title.unwrap_or_else(|| url.clone()), url))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(bookmarks)
+}
+
+fn read_firefox_history(profile: &Path) -> Result<Vec<HistoryEntry>> {
+    let database = DatabaseCopy::open(&profile.join("places.sqlite"))?;
+    let mut statement = database.connection().prepare(
+        "SELECT url, title, visit_count, last_visit_date FROM moz_places
+        WHERE visit_count > 0 AND last_visit_date IS NOT NULL",
+    )?;
+    let history = statement
+        .query_map([], |row| {
+            let last_visit: i64 = row.get(3)?;
+            Ok(HistoryEntry {
+                url: row.get(0)?,
+                title: row.get(1)?,
+                visit_count: row.get(2)?,
+                // Firefox stores microseconds
+                last_visit: last_visit / 1000,
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(history)
+}
+
+fn read_chrome_cookies(profile: &Path) -> Result<(Vec<ImportedCookie>, usize)> {
+    // Newer versions of Chrome moved cookies into the Network directory
+    let path = [profile.join("Network/Cookies"), profile.join("Cookies")]
+        .into_iter()
+        .find(|path| path.is_file())
+        .ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                "Chrome cookies database not found",
+            )
+        })?;
+    let database = DatabaseCopy::open(&path)?;
+    let mut statement = database.connection().prepare(
+        "SELECT host_key, path, name, value, is_secure, is_httponly, expires_utc,
+        length(encrypted_value) FROM cookies",
+    )?;
+    let mut cookies = Vec::new();
+    let mut skipped_cookies = 0;
+    let rows = statement.query_map([], |row| {
+        let expires: i64 = row.get(6)?;
+        let encrypted_length: i64 = row.get(7)?;
+        let cookie = ImportedCookie {
+            domain: row.get(0)?,
+            path: row.get(1)?,
+            name: row.get(2)?,
+            value: row.get(3)?,
+            secure: row.get(4)?,
+            http_only: row.get(5)?,
+            expires: (expires != 0).then(|| (expires / 1000 - CHROME_EPOCH_OFFSET_MS) / 1000),
+        };
+        Ok((cookie, encrypted_length))
+    })?;
+    for row in rows {
+        let (cookie, encrypted_length) = row?;
+        // Chrome encrypts cookie values with a key from the OS keychain, which we can't read
+        if cookie.value.is_empty() && encrypted_length > 0 {
+            skipped_cookies += 1;
+        } else {
+            cookies.push(cookie);
+        }
+    }
+    Ok((cookies, skipped_cookies))
+}
+
+#[derive(Deserialize)]
+struct ChromeBookmarks {
+    roots: HashMap<String, serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+struct ChromeBookmarkNode {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    name: String,
+    url: Option<String>,
+    #[serde(default)]
+    children: Vec<ChromeBookmarkNode>,
+}
+
+fn read_chrome_bookmarks(profile: &Path) -> Result<Vec<Bookmark>> {
+    let file = fs::File::open(profile.join("Bookmarks"))?;
+    let bookmarks: ChromeBookmarks = serde_json::from_reader(file)?;
+
+    fn collect(node: ChromeBookmarkNode, bookmarks: &mut Vec<Bookmark>) {
+        match (node.kind.as_str(), node.url) {
+            ("url", Some(url)) => bookmarks.push(Bookmark::new(node.name, url)),
+            _ => {
+                for child in node.children {
+                    collect(child, bookmarks);
+                }
+            }
+        }
+    }
+
+    let mut result = Vec::new();
+    // Roots also contain non-folder entries such as sync metadata
+    for root in bookmarks.roots.into_values() {
+        if let Ok(node) = serde_json::from_value::<ChromeBookmarkNode>(root) {
+            collect(node, &mut result);
+        }
+    }
+    Ok(result)
+}
+
+fn read_chrome_history(profile: &Path) -> Result<Vec<HistoryEntry>> {
+    let database = DatabaseCopy::open(&profile.join("History"))?;
+    let mut statement = database.connection().prepare(
+        "SELECT url, title, visit_count, last_visit_time FROM urls WHERE visit_count > 0",
+    )?;
+    let history = statement
+        .query_map([], |row| {
+            let title: String = row.get(1)?;
+            let last_visit: i64 = row.get(3)?;
+            Ok(HistoryEntry {
+                url: row.get(0)?,
+                title: Some(title).filter(|title| !title.is_empty()),
+                visit_count: row.get(2)?,
+                // Chrome stores microseconds since 1601-01-01
+                last_visit: last_visit / 1000 - CHROME_EPOCH_OFFSET_MS,
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(history)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Profile directory removed when the test ends.
+    struct TestProfile(PathBuf);
+
+    impl TestProfile {
+        fn new() -> Self {
+            let path = std::env::temp_dir().join(format!(
+                "verso-import-test-{}",
+                uuid::Uuid::new_v4().simple()
+            ));
+            fs::create_dir_all(&path).unwrap();
+            Self(path)
+        }
+
+        fn database(&self, name: &str, sql: &str) {
+            let path = self.0.join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            Connection::open(path).unwrap().execute_batch(sql).unwrap();
+        }
+    }
+
+    impl Drop for TestProfile {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    /// 2023-11-14T22:13:20Z, in milliseconds since the Unix epoch.
+    const VISIT_MS: i64 = 1_700_000_000_000;
+
+    #[test]
+    fn test_import_chrome() {
+        let profile = TestProfile::new();
+        fs::write(
+            profile.0.join("Bookmarks"),
+            r#"{
+                "roots": {
+                    "bookmark_bar": {"type": "folder", "name": "Bar", "children": [
+                        {"type": "url", "name": "Example", "url": "https://example.com/"},
+                        {"type": "folder", "name": "Docs", "children": [
+                            {"type": "url", "name": "Servo", "url": "https://servo.org/"}
+                        ]}
+                    ]},
+                    "sync_transaction_version": "1"
+                },
+                "version": 1
+            }"#,
+        )
+        .unwrap();
+        let chrome_time = (VISIT_MS + CHROME_EPOCH_OFFSET_MS) * 1000;
+        profile.database(
+            "History",
+            &format!(
+                "CREATE TABLE urls (url TEXT, title TEXT, visit_count INTEGER, last_visit_time INTEGER);
+                INSERT INTO urls VALUES ('https://example.com/', 'Example', 3, {chrome_time});
+                INSERT INTO urls VALUES ('https://untitled.com/', '', 1, {chrome_time});
+                INSERT INTO urls VALUES ('https://typed.com/', 'Typed', 0, {chrome_time});"
+            ),
+        );
+        profile.database(
+            "Network/Cookies",
+            &format!(
+                "CREATE TABLE cookies (host_key TEXT, path TEXT, name TEXT, value TEXT,
+                    is_secure INTEGER, is_httponly INTEGER, expires_utc INTEGER,
+                    encrypted_value BLOB);
+                INSERT INTO cookies VALUES ('.example.com', '/', 'id', '1', 1, 1, {chrome_time}, x'');
+                INSERT INTO cookies VALUES ('example.com', '/', 'session', 'a', 0, 0, 0, x'');
+                INSERT INTO cookies VALUES ('example.com', '/', 'secret', '', 1, 1, 0, x'0102');"
+            ),
+        );
+
+        let data = import(Browser::Chrome, Some(profile.0.clone())).unwrap();
+
+        let mut bookmarks: Vec<_> = data
+            .bookmarks
+            .iter()
+            .map(|b| (b.name.as_str(), b.url.as_str()))
+            .collect();
+        bookmarks.sort();
+        assert_eq!(
+            bookmarks,
+            [
+                ("Example", "https://example.com/"),
+                ("Servo", "https://servo.org/")
+            ]
+        );
+
+        assert_eq!(data.history.len(), 2);
+        let example = data
+            .history
+            .iter()
+            .find(|entry| entry.url == "https://example.com/")
+            .unwrap();
+        assert_eq!(example.title.as_deref(), Some("Example"));
+        assert_eq!(example.visit_count, 3);
+        assert_eq!(example.last_visit, VISIT_MS);
+        let untitled = data
+            .history
+            .iter()
+            .find(|entry| entry.url == "https://untitled.com/")
+            .unwrap();
+        assert_eq!(untitled.title, None);
+
+        assert_eq!(data.skipped_cookies, 1);
+        assert_eq!(data.cookies.len(), 2);
+        let id = data.cookies.iter().find(|c| c.name == "id").unwrap();
+        assert_eq!(id.domain, ".example.com");
+        assert!(id.secure && id.http_only);
+        assert_eq!(id.expires, Some(VISIT_MS / 1000));
+        let session = data.cookies.iter().find(|c| c.name == "session").unwrap();
+        assert_eq!(session.expires, None);
+    }
+
+    #[test]
+    fn test_import_firefox() {
+        let profile = TestProfile::new();
+        let firefox_time = VISIT_MS * 1000;
+        profile.database(
+            "places.sqlite",
+            &format!(
+                "CREATE TABLE moz_places (id INTEGER PRIMARY KEY, url TEXT, title TEXT,
+                    visit_count INTEGER, last_visit_date INTEGER);
+                CREATE TABLE moz_bookmarks (id INTEGER PRIMARY KEY, type INTEGER, fk INTEGER,
+                    title TEXT, position INTEGER);
+                INSERT INTO moz_places VALUES (1, 'https://example.com/', 'Example', 2, {firefox_time});
+                INSERT INTO moz_places VALUES (2, 'https://servo.org/', NULL, 0, NULL);
+                INSERT INTO moz_places VALUES (3, 'place:sort=8', NULL, 0, NULL);
+                INSERT INTO moz_bookmarks VALUES (1, 1, 2, NULL, 0);
+                INSERT INTO moz_bookmarks VALUES (2, 1, 1, 'Example site', 1);
+                INSERT INTO moz_bookmarks VALUES (3, 1, 3, 'Recent Tags', 2);
+                INSERT INTO moz_bookmarks VALUES (4, 2, NULL, 'Toolbar', 3);"
+            ),
+        );
+        profile.database(
+            "cookies.sqlite",
+            &format!(
+                "CREATE TABLE moz_cookies (host TEXT, path TEXT, name TEXT, value TEXT,
+                    isSecure INTEGER, isHttpOnly INTEGER, expiry INTEGER);
+                INSERT INTO moz_cookies VALUES ('.example.com', '/', 'seconds', '1', 0, 0, {});
+                INSERT INTO moz_cookies VALUES ('example.com', '/', 'milliseconds', '2', 1, 0, {VISIT_MS});",
+                VISIT_MS / 1000
+            ),
+        );
+
+        let data = import(Browser::Firefox, Some(profile.0.clone())).unwrap();
+
+        let bookmarks: Vec<_> = data
+            .bookmarks
+            .iter()
+            .map(|b| (b.name.as_str(), b.url.as_str()))
+            .collect();
+        assert_eq!(
+            bookmarks,
+            [
+                ("https://servo.org/", "https://servo.org/"),
+                ("Example site", "https://example.com/"),
+            ]
+        );
+
+        assert_eq!(data.history.len(), 1);
+        assert_eq!(data.history[0].url, "https://example.com/");
+        assert_eq!(data.history[0].visit_count, 2);
+        assert_eq!(data.history[0].last_visit, VISIT_MS);
+
+        assert_eq!(data.skipped_cookies, 0);
+        assert!(
+            data.cookies
+                .iter()
+                .all(|cookie| cookie.expires == Some(VISIT_MS / 1000))
+        );
+    }
+
+    #[test]
+    fn test_find_firefox_profile() {
+        let profiles = TestProfile::new();
+        for name in ["abc.default", "xyz.default-release", "empty.default"] {
+            fs::create_dir_all(profiles.0.join(name)).unwrap();
+        }
+        for name in ["abc.default", "xyz.default-release"] {
+            fs::write(profiles.0.join(name).join("places.sqlite"), b"").unwrap();
+        }
+        assert_eq!(
+            find_firefox_profile(&profiles.0),
+            Some(profiles.0.join("xyz.default-release"))
+        );
+        fs::remove_dir_all(profiles.0.join("xyz.default-release")).unwrap();
+        assert_eq!(
+            find_firefox_profile(&profiles.0),
+            Some(profiles.0.join("abc.default"))
+        );
+    }
+}
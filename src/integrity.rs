@@ -0,0 +1,76 @@
+use versoview_messages::{BlockedReason, BlockedSubresource, CspViolation};
+
+/// Script which reports the scripts and stylesheets of the page failing their Subresource
+/// Integrity checks with the `SUBRESOURCE_BLOCKED` request of the Verso bridge. Failing the check
+/// fires the same `error` event as failing to load, so the resource is fetched again without its
+/// `integrity` attribute to tell them apart, mostly from the cache.
+pub(crate) const INTEGRITY_SCRIPT: &str = r#"
+(() => {
+  if (window.versoIntegrity) {
+    return;
+  }
+  window.versoIntegrity = true;
+
+  addEventListener('error', (event) => {
+    const element = event.target;
+    const url = element instanceof HTMLScriptElement ? element.src
+      : element instanceof HTMLLinkElement ? element.href : null;
+    if (!url || !element.integrity) {
+      return;
+    }
+    const init = {
+      mode: element.crossOrigin === null ? 'no-cors' : 'cors',
+      credentials: element.crossOrigin === 'use-credentials' ? 'include' : 'same-origin',
+    };
+    fetch(url, init).then(() => {
+      const subresource = {
+        documentUrl: document.URL,
+        url,
+        element: element.localName,
+        reason: 'integrity',
+      };
+      console.error(`Blocked ${url}, it doesn't match its integrity attribute`);
+      prompt(`VERSO::SUBRESOURCE_BLOCKED::${JSON.stringify(subresource)}`);
+    }, () => {});
+  }, true);
+})();
+"#;
+
+/// The script or stylesheet the Content Security Policy blocked in the violation, `None` if it
+/// blocked something else or was only reported.
+pub(crate) fn blocked_by_csp(violation: &CspViolation) -> Option<BlockedSubresource> {
+    if !violation.enforced {
+        return None;
+    }
+    let element = if violation.effective_directive.starts_with("script-src") {
+        "script"
+    } else if violation.effective_directive.starts_with("style-src") {
+        if violation.blocked_url == "inline" {
+            "style"
+        } else {
+            "link"
+        }
+    } else {
+        return None;
+    };
+    Some(BlockedSubresource {
+        document_url: violation.document_url.clone(),
+        url: violation.blocked_url.clone(),
+        element: element.to_string(),
+        reason: BlockedReason::ContentSecurityPolicy,
+    })
+}
+
+/// Describe the blocked resource like the console of the page does.
+pub(crate) fn describe(subresource: &BlockedSubresource) -> String {
+    match subresource.reason {
+        BlockedReason::Integrity => format!(
+            "Blocked {}, it doesn't match its integrity attribute",
+            subresource.url
+        ),
+        BlockedReason::ContentSecurityPolicy => format!(
+            "Blocked {}, the Content Security Policy doesn't allow it",
+            subresource.url
+        ),
+    }
+}
@@ -0,0 +1,69 @@
+use serde::Deserialize;
+use versoview_messages::JavaScriptSettings;
+
+/// Script which disables JavaScript on the pages of origins it's disabled for, asking Verso with
+/// the `JAVASCRIPT_ENABLED` request of the Verso bridge. Verso only tells the setting of the tab's
+/// origin, so pages can't read the settings of other sites, and frames of other origins get the
+/// setting of every origin. User scripts run once the head of the document is inserted, before
+/// the parser reaches the content of the page, so the `script-src 'none'` policy put in the head
+/// applies to every script of the document. The parser still treats scripting as enabled, so
+/// `<noscript>` elements are shown once the document is parsed.
+pub(crate) const JAVASCRIPT_POLICY_SCRIPT: &str = r#"
+(() => {
+  if (location.protocol === 'verso:'
+    || window.prompt(`VERSO::JAVASCRIPT_ENABLED::${location.origin}`) !== 'false') {
+    return;
+  }
+  const policy = document.createElement('meta');
+  policy.httpEquiv = 'Content-Security-Policy';
+  policy.content = "script-src 'none'";
+  (document.head ?? document.documentElement)?.prepend(policy);
+
+  document.addEventListener('DOMContentLoaded', () => {
+    for (const noscript of document.querySelectorAll('noscript')) {
+      const range = document.createRange();
+      range.selectNode(noscript);
+      noscript.replaceWith(range.createContextualFragment(noscript.textContent));
+    }
+  });
+})();
+"#;
+
+/// A setting changed on `verso://site-settings`, of every origin if `origin` is `None`.
+#[derive(Debug, Deserialize)]
+pub(crate) struct JavaScriptSetting {
+    pub origin: Option<url::Url>,
+    pub enabled: Option<bool>,
+}
+
+/// Whether the pages of the origin run JavaScript. Origins which can't be parsed, such as the
+/// opaque origins of sandboxed documents, use the setting of every origin.
+pub(crate) fn javascript_enabled(settings: &JavaScriptSettings, origin: &str) -> bool {
+    url::Url::parse(origin)
+        .ok()
+        .and_then(|url| origin_key(&url))
+        .and_then(|key| settings.origins.get(&key).copied())
+        .unwrap_or(settings.enabled)
+}
+
+/// Set whether the pages of the origin of the URL run JavaScript, or remove its setting if
+/// `None`. URLs without an origin of their own, such as data URLs, are ignored.
+pub(crate) fn set_origin_javascript_enabled(
+    settings: &mut JavaScriptSettings,
+    url: &url::Url,
+    enabled: Option<bool>,
+) {
+    let Some(key) = origin_key(url) else {
+        log::error!("Verso can't set whether {url} runs JavaScript, it has no origin");
+        return;
+    };
+    match enabled {
+        Some(enabled) => settings.origins.insert(key, enabled),
+        None => settings.origins.remove(&key),
+    };
+}
+
+fn origin_key(url: &url::Url) -> Option<String> {
+    let origin = url.origin();
+    origin.is_tuple().then(|| origin.ascii_serialization())
+}
@@ -0,0 +1,604 @@
+use std::{
+    cell::Cell,
+    ffi::c_void,
+    fs::{File, OpenOptions},
+    os::{
+        fd::{AsFd, BorrowedFd, OwnedFd},
+        unix::fs::OpenOptionsExt,
+    },
+    path::Path,
+    ptr::NonNull,
+};
+
+use base::id::WebViewId;
+use constellation_traits::EmbedderToConstellationMessage;
+use crossbeam_channel::Sender;
+use dpi::PhysicalSize;
+use drm::control::{
+    Device as ControlDevice, Event as DrmEvent, Mode, ModeTypeFlags, PageFlipFlags, connector,
+    crtc, framebuffer,
+};
+use embedder_traits::{
+    InputEvent, MouseButton, MouseButtonAction, MouseButtonEvent, MouseMoveEvent, TouchEvent,
+    TouchEventType, TouchId,
+};
+use gbm::{AsRaw, BufferObject, BufferObjectFlags, Format};
+use input::{
+    Libinput, LibinputInterface,
+    event::{
+        Event, EventTrait,
+        keyboard::{
+            KeyState as LibinputKeyState, KeyboardEvent as LibinputKeyboardEvent,
+            KeyboardEventTrait,
+        },
+        pointer::{Axis, ButtonState, PointerEvent, PointerScrollEvent},
+        touch::{TouchEvent as LibinputTouchEvent, TouchEventPosition, TouchEventSlot},
+    },
+};
+use keyboard_types::{Code, Key, KeyState, KeyboardEvent, Location, Modifiers};
+use raw_window_handle::{
+    DisplayHandle, GbmDisplayHandle, GbmWindowHandle, HandleError, HasDisplayHandle,
+    HasWindowHandle, RawDisplayHandle, RawWindowHandle, WindowHandle,
+};
+use versoview_messages::Rotation;
+use webrender_api::{
+    ScrollLocation,
+    units::{DeviceIntPoint, DevicePoint, DeviceSize, LayoutVector2D},
+};
+use xkbcommon::xkb;
+
+use crate::{backend::WindowBackend, compositor::IOCompositor, rotation};
+
+/// Card opened when no other is given.
+const DEFAULT_CARD: &str = "/dev/dri/card0";
+/// Pixels scrolled per notch of a mouse wheel, like a line of the winit windows.
+const LINE_HEIGHT: f32 = 38.0;
+/// Linux input event codes of the mouse buttons.
+const BTN_LEFT: u32 = 0x110;
+const BTN_RIGHT: u32 = 0x111;
+const BTN_MIDDLE: u32 = 0x112;
+/// Offset of XKB keycodes from Linux input event codes.
+const XKB_KEYCODE_OFFSET: u32 = 8;
+
+/// A DRM card, opened read and write.
+struct Card(File);
+
+impl AsFd for Card {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.0.as_fd()
+    }
+}
+
+impl drm::Device for Card {}
+impl ControlDevice for Card {}
+
+/// A display connected to the card, rendered to fullscreen without X11 or Wayland.
+///
+/// The output is a [`WindowBackend`] on a GBM surface, create its rendering context with
+/// [`crate::backend::create_rendering_context`] and call [`DrmOutput::present`] after every
+/// [`crate::rendering::RenderingContext::present`] to scan the frame out. Verso must be the DRM
+/// master of the card, i.e. the only process drawing to it.
+pub struct DrmOutput {
+    gbm: gbm::Device<Card>,
+    surface: gbm::Surface<()>,
+    connector: connector::Handle,
+    crtc: crtc::Handle,
+    mode: Mode,
+    /// The buffer being scanned out with its framebuffer, released once the next one replaces it
+    front_buffer: Option<(BufferObject<()>, framebuffer::Handle)>,
+    redraw_requested: Cell<bool>,
+}
+
+impl DrmOutput {
+    /// Open the first connected display of the card, `/dev/dri/card0` if `None`, in its preferred
+    /// mode.
+    pub fn open(card: Option<&Path>) -> Result<Self, String> {
+        let path = card.unwrap_or(Path::new(DEFAULT_CARD));
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(path)
+            .map_err(|error| format!("Failed to open {}: {error}", path.display()))?;
+        let card = Card(file);
+
+        let resources = card
+            .resource_handles()
+            .map_err(|error| format!("Failed to get the resources of the card: {error}"))?;
+        let connector = resources
+            .connectors()
+            .iter()
+            .filter_map(|handle| card.get_connector(*handle, false).ok())
+            .find(|connector| connector.state() == connector::State::Connected)
+            .ok_or("No display is connected to the card")?;
+        let mode = connector
+            .modes()
+            .iter()
+            .find(|mode| mode.mode_type().contains(ModeTypeFlags::PREFERRED))
+            .or_else(|| connector.modes().first())
+            .copied()
+            .ok_or("The display has no mode")?;
+        // Keep the CRTC the display is driven by, or take the first one its encoders can use
+        let crtc = connector
+            .current_encoder()
+            .and_then(|encoder| card.get_encoder(encoder).ok())
+            .and_then(|encoder| encoder.crtc())
+            .or_else(|| {
+                connector
+                    .encoders()
+                    .iter()
+                    .filter_map(|encoder| card.get_encoder(*encoder).ok())
+                    .flat_map(|encoder| resources.filter_crtcs(encoder.possible_crtcs()))
+                    .next()
+            })
+            .ok_or("No CRTC can drive the display")?;
+
+        let gbm = gbm::Device::new(card)
+            .map_err(|error| format!("Failed to create the GBM device: {error}"))?;
+        let (width, height) = mode.size();
+        let surface = gbm
+            .create_surface::<()>(
+                width.into(),
+                height.into(),
+                Format::Argb8888,
+                BufferObjectFlags::SCANOUT | BufferObjectFlags::RENDERING,
+            )
+            .map_err(|error| format!("Failed to create the GBM surface: {error}"))?;
+        log::info!(
+            "Rendering to {width}x{height} on {:?} of {}",
+            connector.interface(),
+            path.display()
+        );
+
+        Ok(Self {
+            gbm,
+            surface,
+            connector: connector.handle(),
+            crtc,
+            mode,
+            front_buffer: None,
+            redraw_requested: Cell::new(true),
+        })
+    }
+
+    /// Scan out the frame last presented to the GBM surface, waiting for the page flip so the
+    /// buffer of the previous frame can be reused.
+    pub fn present(&mut self) -> Result<(), String> {
+        let buffer = unsafe { self.surface.lock_front_buffer() }
+            .map_err(|error| format!("Failed to lock the front buffer: {error}"))?;
+        let framebuffer = self
+            .gbm
+            .add_framebuffer(&buffer, 24, 32)
+            .map_err(|error| format!("Failed to add the framebuffer: {error}"))?;
+
+        if self.front_buffer.is_none() {
+            // The first frame sets the mode of the display
+            self.gbm
+                .set_crtc(
+                    self.crtc,
+                    Some(framebuffer),
+                    (0, 0),
+                    &[self.connector],
+                    Some(self.mode),
+                )
+                .map_err(|error| format!("Failed to set the mode of the display: {error}"))?;
+        } else {
+            self.gbm
+                .page_flip(self.crtc, framebuffer, PageFlipFlags::EVENT, None)
+                .map_err(|error| format!("Failed to flip the page: {error}"))?;
+            self.wait_for_page_flip()?;
+        }
+
+        if let Some((_buffer, framebuffer)) = self.front_buffer.replace((buffer, framebuffer)) {
+            let _ = self.gbm.destroy_framebuffer(framebuffer);
+        }
+        self.redraw_requested.set(false);
+        Ok(())
+    }
+
+    fn wait_for_page_flip(&self) -> Result<(), String> {
+        loop {
+            let events = self
+                .gbm
+                .receive_events()
+                .map_err(|error| format!("Failed to receive DRM events: {error}"))?;
+            if events
+                .into_iter()
+                .any(|event| matches!(event, DrmEvent::PageFlip(_)))
+            {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Whether a redraw was requested since the last frame was presented.
+    pub fn redraw_requested(&self) -> bool {
+        self.redraw_requested.get()
+    }
+}
+
+impl Drop for DrmOutput {
+    fn drop(&mut self) {
+        if let Some((_buffer, framebuffer)) = self.front_buffer.take() {
+            let _ = self.gbm.destroy_framebuffer(framebuffer);
+        }
+    }
+}
+
+impl HasWindowHandle for DrmOutput {
+    fn window_handle(&self) -> Result<WindowHandle<'_>, HandleError> {
+        let surface =
+            NonNull::new(self.surface.as_raw() as *mut c_void).ok_or(HandleError::Unavailable)?;
+        let raw = RawWindowHandle::Gbm(GbmWindowHandle::new(surface));
+        Ok(unsafe { WindowHandle::borrow_raw(raw) })
+    }
+}
+
+impl HasDisplayHandle for DrmOutput {
+    fn display_handle(&self) -> Result<DisplayHandle<'_>, HandleError> {
+        let device =
+            NonNull::new(self.gbm.as_raw() as *mut c_void).ok_or(HandleError::Unavailable)?;
+        let raw = RawDisplayHandle::Gbm(GbmDisplayHandle::new(device));
+        Ok(unsafe { DisplayHandle::borrow_raw(raw) })
+    }
+}
+
+impl WindowBackend for DrmOutput {
+    fn surface_size(&self) -> PhysicalSize<u32> {
+        let (width, height) = self.mode.size();
+        PhysicalSize::new(width.into(), height.into())
+    }
+
+    fn scale_factor(&self) -> f64 {
+        1.0
+    }
+
+    fn request_redraw(&self) {
+        self.redraw_requested.set(true);
+    }
+}
+
+/// Opens the input devices of the seat for libinput, with the permissions of the process.
+struct Interface;
+
+impl LibinputInterface for Interface {
+    fn open_restricted(&mut self, path: &Path, flags: i32) -> Result<OwnedFd, i32> {
+        let access_mode = flags & libc::O_ACCMODE;
+        OpenOptions::new()
+            .custom_flags(flags)
+            .read(access_mode == libc::O_RDONLY || access_mode == libc::O_RDWR)
+            .write(access_mode == libc::O_WRONLY || access_mode == libc::O_RDWR)
+            .open(path)
+            .map(Into::into)
+            .map_err(|error| error.raw_os_error().unwrap_or(libc::EIO))
+    }
+
+    fn close_restricted(&mut self, fd: OwnedFd) {
+        drop(File::from(fd));
+    }
+}
+
+/// Input of the output, read from the keyboards, mice, touchpads and touchscreens of the seat.
+pub enum DrmInput {
+    /// An input event, at the pointer or touch point if it has a point
+    Event(InputEvent),
+    /// Scroll the page under the point by the delta
+    Scroll(DeviceIntPoint, LayoutVector2D),
+}
+
+/// Reads the input devices of a seat with libinput, in place of the window events of winit. The
+/// pointer is drawn by the pages, there is no cursor plane.
+pub struct DrmInputReader {
+    libinput: Libinput,
+    keyboard: xkb::State,
+    pointer: DevicePoint,
+    size: PhysicalSize<u32>,
+    rotation: Rotation,
+}
+
+impl DrmInputReader {
+    /// Read the devices of the seat, `seat0` if `None`, for an output of this size.
+    pub fn new(seat: Option<&str>, size: PhysicalSize<u32>) -> Result<Self, String> {
+        let mut libinput = Libinput::new_with_udev(Interface);
+        libinput
+            .udev_assign_seat(seat.unwrap_or("seat0"))
+            .map_err(|_| "Failed to assign the seat to libinput")?;
+        // The keymap of the system, configured with the XKB_DEFAULT_* environment variables
+        let context = xkb::Context::new(xkb::CONTEXT_NO_FLAGS);
+        let keymap = xkb::Keymap::new_from_names(
+            &context,
+            "",
+            "",
+            "",
+            "",
+            None,
+            xkb::KEYMAP_COMPILE_NO_FLAGS,
+        )
+        .ok_or("Failed to compile the keymap")?;
+        Ok(Self {
+            libinput,
+            keyboard: xkb::State::new(&keymap),
+            pointer: DevicePoint::new(size.width as f32 / 2.0, size.height as f32 / 2.0),
+            size,
+            rotation: Rotation::default(),
+        })
+    }
+
+    /// Rotate the input with the output, so points are in the rotated content. The pointer moves
+    /// back to the center.
+    pub fn set_rotation(&mut self, rotation: Rotation) {
+        self.rotation = rotation;
+        let size = self.content_size();
+        self.pointer = DevicePoint::new(size.width / 2.0, size.height / 2.0);
+    }
+
+    /// File descriptor to poll in the event loop, readable when input is pending.
+    pub fn as_fd(&self) -> BorrowedFd<'_> {
+        self.libinput.as_fd()
+    }
+
+    /// Read the pending input.
+    pub fn dispatch(&mut self) -> Vec<DrmInput> {
+        if let Err(error) = self.libinput.dispatch() {
+            log::error!("Failed to read input: {error}");
+            return Vec::new();
+        }
+        let events: Vec<Event> = self.libinput.by_ref().collect();
+        events
+            .into_iter()
+            .flat_map(|event| self.translate(event))
+            .collect()
+    }
+
+    fn translate(&mut self, event: Event) -> Vec<DrmInput> {
+        let (width, height) = (self.size.width, self.size.height);
+        match event {
+            Event::Pointer(PointerEvent::Motion(motion)) => {
+                self.move_pointer(
+                    self.pointer.x + motion.dx() as f32,
+                    self.pointer.y + motion.dy() as f32,
+                );
+                vec![self.pointer_moved()]
+            }
+            Event::Pointer(PointerEvent::MotionAbsolute(motion)) => {
+                let point = self.content_point(
+                    motion.absolute_x_transformed(width),
+                    motion.absolute_y_transformed(height),
+                );
+                self.move_pointer(point.x, point.y);
+                vec![self.pointer_moved()]
+            }
+            Event::Pointer(PointerEvent::Button(button)) => {
+                let button_type = match button.button() {
+                    BTN_LEFT => MouseButton::Left,
+                    BTN_RIGHT => MouseButton::Right,
+                    BTN_MIDDLE => MouseButton::Middle,
+                    code => {
+                        log::trace!("Verso isn't supporting this mouse button yet: {code}");
+                        return Vec::new();
+                    }
+                };
+                let button_event = |action| {
+                    DrmInput::Event(InputEvent::MouseButton(MouseButtonEvent {
+                        point: self.pointer,
+                        action,
+                        button: button_type,
+                    }))
+                };
+                match button.button_state() {
+                    ButtonState::Pressed => vec![button_event(MouseButtonAction::Down)],
+                    // Like winit, libinput has no click event, so it's sent after mouse up
+                    ButtonState::Released => vec![
+                        button_event(MouseButtonAction::Up),
+                        button_event(MouseButtonAction::Click),
+                    ],
+                }
+            }
+            Event::Pointer(PointerEvent::ScrollWheel(scroll)) => {
+                // A notch of the wheel is 120
+                let value = |axis| {
+                    if scroll.has_axis(axis) {
+                        scroll.scroll_value_v120(axis) as f32 / 120.0 * LINE_HEIGHT
+                    } else {
+                        0.0
+                    }
+                };
+                self.scroll(value(Axis::Horizontal), value(Axis::Vertical))
+            }
+            Event::Pointer(PointerEvent::ScrollFinger(scroll)) => {
+                let value = |axis| {
+                    if scroll.has_axis(axis) {
+                        scroll.scroll_value(axis) as f32
+                    } else {
+                        0.0
+                    }
+                };
+                self.scroll(value(Axis::Horizontal), value(Axis::Vertical))
+            }
+            Event::Keyboard(LibinputKeyboardEvent::Key(key)) => {
+                let keycode = xkb::Keycode::new(key.key() + XKB_KEYCODE_OFFSET);
+                let (state, direction) = match key.key_state() {
+                    LibinputKeyState::Pressed => (KeyState::Down, xkb::KeyDirection::Down),
+                    LibinputKeyState::Released => (KeyState::Up, xkb::KeyDirection::Up),
+                };
+                // The key and modifiers of the event are the ones before the key is applied
+                let event = KeyboardEvent {
+                    state,
+                    key: self.key(keycode),
+                    code: Code::Unidentified,
+                    location: Location::Standard,
+                    modifiers: self.modifiers(),
+                    repeat: false,
+                    is_composing: false,
+                };
+                self.keyboard.update_key(keycode, direction);
+                vec![DrmInput::Event(InputEvent::Keyboard(event))]
+            }
+            Event::Touch(touch) => {
+                let (event_type, slot, point) = match &touch {
+                    LibinputTouchEvent::Down(down) => (
+                        TouchEventType::Down,
+                        down.slot(),
+                        Some((down.x_transformed(width), down.y_transformed(height))),
+                    ),
+                    LibinputTouchEvent::Motion(motion) => (
+                        TouchEventType::Move,
+                        motion.slot(),
+                        Some((motion.x_transformed(width), motion.y_transformed(height))),
+                    ),
+                    LibinputTouchEvent::Up(up) => (TouchEventType::Up, up.slot(), None),
+                    LibinputTouchEvent::Cancel(cancel) => {
+                        (TouchEventType::Cancel, cancel.slot(), None)
+                    }
+                    _ => return Vec::new(),
+                };
+                // Touches lifted or cancelled end where they last moved
+                let point = point
+                    .map(|(x, y)| self.content_point(x, y))
+                    .unwrap_or(self.pointer);
+                if event_type != TouchEventType::Cancel {
+                    self.pointer = point;
+                }
+                let id = TouchId(slot.unwrap_or_default() as i32);
+                vec![DrmInput::Event(InputEvent::Touch(TouchEvent::new(
+                    event_type, id, point,
+                )))]
+            }
+            event => {
+                log::trace!(
+                    "Verso isn't supporting this input event yet: {:?}",
+                    event.device().name()
+                );
+                Vec::new()
+            }
+        }
+    }
+
+    fn move_pointer(&mut self, x: f32, y: f32) {
+        let size = self.content_size();
+        self.pointer = DevicePoint::new(
+            x.clamp(0.0, (size.width - 1.0).max(0.0)),
+            y.clamp(0.0, (size.height - 1.0).max(0.0)),
+        );
+    }
+
+    fn content_size(&self) -> DeviceSize {
+        rotation::rotate_size(
+            self.rotation,
+            DeviceSize::new(self.size.width as f32, self.size.height as f32),
+        )
+    }
+
+    /// Point of the rotated content at a position of the output.
+    fn content_point(&self, x: f64, y: f64) -> DevicePoint {
+        rotation::output_to_content(
+            self.rotation,
+            DevicePoint::new(x as f32, y as f32),
+            DeviceSize::new(self.size.width as f32, self.size.height as f32),
+        )
+    }
+
+    fn pointer_moved(&self) -> DrmInput {
+        DrmInput::Event(InputEvent::MouseMove(MouseMoveEvent {
+            point: self.pointer,
+        }))
+    }
+
+    fn scroll(&self, x: f32, y: f32) -> Vec<DrmInput> {
+        // Do one axis at a time, like the winit windows
+        let delta = if y.abs() >= x.abs() {
+            LayoutVector2D::new(0.0, -y)
+        } else {
+            LayoutVector2D::new(-x, 0.0)
+        };
+        let point = DeviceIntPoint::new(self.pointer.x as i32, self.pointer.y as i32);
+        vec![DrmInput::Scroll(point, delta)]
+    }
+
+    fn key(&self, keycode: xkb::Keycode) -> Key {
+        match self.keyboard.key_get_one_sym(keycode) {
+            xkb::Keysym::Return | xkb::Keysym::KP_Enter => Key::Enter,
+            xkb::Keysym::BackSpace => Key::Backspace,
+            xkb::Keysym::Tab | xkb::Keysym::ISO_Left_Tab => Key::Tab,
+            xkb::Keysym::Escape => Key::Escape,
+            xkb::Keysym::Delete => Key::Delete,
+            xkb::Keysym::Insert => Key::Insert,
+            xkb::Keysym::Home => Key::Home,
+            xkb::Keysym::End => Key::End,
+            xkb::Keysym::Page_Up => Key::PageUp,
+            xkb::Keysym::Page_Down => Key::PageDown,
+            xkb::Keysym::Left => Key::ArrowLeft,
+            xkb::Keysym::Right => Key::ArrowRight,
+            xkb::Keysym::Up => Key::ArrowUp,
+            xkb::Keysym::Down => Key::ArrowDown,
+            xkb::Keysym::Shift_L | xkb::Keysym::Shift_R => Key::Shift,
+            xkb::Keysym::Control_L | xkb::Keysym::Control_R => Key::Control,
+            xkb::Keysym::Alt_L | xkb::Keysym::Alt_R => Key::Alt,
+            xkb::Keysym::Super_L | xkb::Keysym::Super_R => Key::Meta,
+            _ => {
+                let text = self.keyboard.key_get_utf8(keycode);
+                if text.is_empty() || text.chars().any(char::is_control) {
+                    Key::Unidentified
+                } else {
+                    Key::Character(text)
+                }
+            }
+        }
+    }
+
+    fn modifiers(&self) -> Modifiers {
+        let active = |name| {
+            self.keyboard
+                .mod_name_is_active(name, xkb::STATE_MODS_EFFECTIVE)
+        };
+        let mut modifiers = Modifiers::empty();
+        modifiers.set(Modifiers::SHIFT, active(xkb::MOD_NAME_SHIFT));
+        modifiers.set(Modifiers::CONTROL, active(xkb::MOD_NAME_CTRL));
+        modifiers.set(Modifiers::ALT, active(xkb::MOD_NAME_ALT));
+        modifiers.set(Modifiers::META, active(xkb::MOD_NAME_LOGO));
+        modifiers
+    }
+}
+
+impl DrmInput {
+    /// Send the input to the webview under its point, or to the focused webview if it has no
+    /// point, like the winit windows do with their window events.
+    pub fn forward(
+        self,
+        compositor: &mut IOCompositor,
+        focused_webview_id: Option<WebViewId>,
+        sender: &Sender<EmbedderToConstellationMessage>,
+    ) {
+        match self {
+            DrmInput::Event(event) => match event.point() {
+                Some(point) => {
+                    let Some(webview_id) = compositor.webview_id_from_point(point) else {
+                        log::trace!("No webview at point, skipping input event.");
+                        return;
+                    };
+                    match event {
+                        InputEvent::Touch(touch) => {
+                            compositor.on_touch_event(webview_id, touch);
+                        }
+                        event => compositor.on_input_event(webview_id, event),
+                    }
+                }
+                None => {
+                    let Some(webview_id) = focused_webview_id else {
+                        log::trace!("No focused webview, skipping input event.");
+                        return;
+                    };
+                    let _ = sender.send(EmbedderToConstellationMessage::ForwardInputEvent(
+                        webview_id, event, None, /* hit_test */
+                    ));
+                }
+            },
+            DrmInput::Scroll(point, delta) => {
+                compositor.on_scroll_event(
+                    ScrollLocation::Delta(delta),
+                    point,
+                    TouchEventType::Move,
+                );
+            }
+        }
+    }
+}
@@ -5,6 +5,10 @@
 
 #![deny(missing_docs)]
 
+/// Winit application running Verso on the event loop.
+pub mod app;
+/// Command registry of user-facing actions.
+pub mod command;
 /// Verso's compositor component to handle webrender.
 pub mod compositor;
 /// Utilities to read options and preferences.
@@ -15,6 +19,8 @@ pub mod errors;
 pub mod keyboard;
 /// Verso's rendering context.
 pub mod rendering;
+/// Serve requests from local files, in-memory bytes or the controller instead of the network.
+pub mod resource_override;
 /// Utilities to handle touch inputs and states.
 pub mod touch;
 /// Main entry types and functions.
@@ -23,20 +29,121 @@ pub mod verso;
 pub mod webview;
 /// Verso's window types to handle Winit's window.
 pub mod window;
+pub use app::App;
 pub use errors::{Error, Result};
 /// Utilities to write tests.
-// pub mod test;
+pub mod test;
 pub use verso::Verso;
 /// Re-exporting Winit for the sake of convenience.
 pub use winit;
+/// OAuth flows running in popup windows
+pub mod auth_flow;
+/// Windowing backends hosting Verso's rendering context
+pub mod backend;
+/// Badging API backed by dock and taskbar badges
+pub mod badge;
 /// Bookmark manager
 pub mod bookmark;
+/// Certificate pinning of origins
+pub mod cert_pinning;
+/// Copy and paste between the clipboard and the focused element of webviews
+pub(crate) mod clipboard;
+/// Control protocol served on a unix socket or named pipe with `--ipc-socket`, or over WebSocket
+/// with `--ipc-websocket`
+pub mod control;
+/// Persistent cookie jar of the profile and its policy
+pub(crate) mod cookies;
+/// Crash reports of panics of Verso and of pages
+pub mod crash_reporter;
+/// Reports of the violations of the Content Security Policy of pages
+pub(crate) mod csp;
+/// Chrome DevTools Protocol bridge for automation tools
+pub(crate) mod devtools_bridge;
 /// Download manager
 pub mod download;
+/// Drag-and-drop of files into webviews, and of links out of them
+pub(crate) mod drag_drop;
+/// Resources compiled into the binary
+pub(crate) mod embedded_resources;
+/// Native file dialogs of `<input type="file">`, and the files picked instead of them
+pub(crate) mod file_picker;
+/// Find-in-page of the text of webviews
+pub(crate) mod find;
+/// Form submissions of pages observed by the controller
+pub(crate) mod forms;
+/// Frame trees of webviews, scripts and input targeted at their frames, and isolation of
+/// cross-origin frames
+pub(crate) mod frames;
+/// Geolocation API backed by the location services of the system
+pub mod geolocation;
+/// Highlights of pages made by the user
+pub mod highlight;
+/// History of visited pages
+pub mod history;
+/// HTTP authentication answered by the controller or with the credentials remembered for sites
+pub(crate) mod http_auth;
+/// HTTP cache of the profile on disk
+pub(crate) mod http_cache;
+/// Image loading policy of the profile, blocking images or loading them on click
+pub(crate) mod images;
+/// Import cookies, bookmarks and history from other browsers
+pub mod import;
+/// Reports of the scripts and stylesheets of pages failing their integrity checks or blocked
+pub(crate) mod integrity;
+/// Per-origin settings of whether pages run JavaScript
+pub(crate) mod javascript;
+/// Fullscreen rendering to a DRM/KMS output with GBM and EGL, and libinput input, for embedded
+/// Linux without X11 or Wayland
+#[cfg(all(linux, feature = "drm-kms"))]
+pub mod kms;
+/// Opt-in performance metrics recorded in the profile
+pub mod metrics;
+/// Size, position and monitor of the windows, restored on startup
+pub(crate) mod placement;
+/// Pointers and touch points of the windows, each with a position and hover state of its own
+pub(crate) mod pointer;
+/// Idle detection, power saving and sleep inhibitors
+pub mod power;
+/// Referrer policy, Do-Not-Track and fingerprinting resistance settings of webviews
+pub(crate) mod privacy;
+/// Private windows, running in a process of their own
+pub(crate) mod private;
+/// Script time, request and download budgets of webviews
+pub(crate) mod resource_limits;
+/// Rotated output of windows, for screens mounted in another orientation
+pub(crate) mod rotation;
+/// Secrets stored in the OS keychain
+pub mod secrets;
+/// Motion and light sensors of the device
+pub mod sensors;
+/// Session manager, handles tab groups
+pub mod session;
+/// Disk cache of the shader programs of Webrender
+pub(crate) mod shader_cache;
+/// Web Share API backed by the share sheet of the system
+pub mod share;
+/// Snapping windows to areas of the monitors
+pub(crate) mod snap;
 /// Storage manager, handles all the storage operations,
 /// such as reading and writing bookmarks, preferences, etc.
 pub(crate) mod storage;
 /// Window tabs manager
 pub mod tab;
+/// Text extraction of pages
+pub mod text;
+/// Self-update from signed releases
+pub(crate) mod updater;
 /// Utilities
 pub(crate) mod utils;
+/// On-screen keyboard of fields focused by touch
+pub(crate) mod virtual_keyboard;
+/// Waiting for conditions in webviews for the controller
+pub(crate) mod wait;
+/// Watchdog detecting pages whose script is hung
+pub(crate) mod watchdog;
+/// Standalone web apps, their manifests and the theme colors of pages
+pub mod web_app;
+/// WebDriver server to drive Verso with automation tools
+pub(crate) mod webdriver;
+/// Page zoom remembered for each site
+pub(crate) mod zoom;
@@ -0,0 +1,148 @@
+use std::{fs::File, path::PathBuf, thread, time::Duration};
+
+use serde::{Deserialize, Serialize};
+
+/// Upper bounds in milliseconds of the buckets of frame times, the last bucket has no bound.
+const FRAME_TIME_BUCKETS: [f64; 5] = [8.0, 16.7, 33.3, 50.0, 100.0];
+
+/// Upper bounds in milliseconds of the buckets of page load times, the last bucket has no bound.
+const LOAD_TIME_BUCKETS: [f64; 5] = [500.0, 1000.0, 2500.0, 5000.0, 10000.0];
+
+/// Aggregate of durations, individual durations aren't kept so the metrics say nothing about the
+/// pages which were visited.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Timings {
+    /// Number of durations recorded
+    pub count: u64,
+    /// Sum of the durations, in milliseconds
+    pub total_ms: f64,
+    /// Longest duration, in milliseconds
+    pub max_ms: f64,
+    /// Number of durations in every bucket, the bounds of the buckets depend on what is measured
+    pub buckets: Vec<u64>,
+}
+
+impl Timings {
+    fn record(&mut self, duration: Duration, bounds: &[f64]) {
+        let ms = duration.as_secs_f64() * 1000.0;
+        self.count += 1;
+        self.total_ms += ms;
+        self.max_ms = self.max_ms.max(ms);
+        self.buckets.resize(bounds.len() + 1, 0);
+        let bucket = bounds
+            .iter()
+            .position(|bound| ms < *bound)
+            .unwrap_or(bounds.len());
+        self.buckets[bucket] += 1;
+    }
+
+    fn merge(&mut self, other: Timings) {
+        self.count += other.count;
+        self.total_ms += other.total_ms;
+        self.max_ms = self.max_ms.max(other.max_ms);
+        self.buckets
+            .resize(self.buckets.len().max(other.buckets.len()), 0);
+        for (bucket, count) in self.buckets.iter_mut().zip(other.buckets) {
+            *bucket += count;
+        }
+    }
+}
+
+/// Frame times measured by the compositor, merged in [`Metrics`] when they are saved.
+#[derive(Debug, Default)]
+pub(crate) struct FrameTimes(Timings);
+
+impl FrameTimes {
+    /// Record the time Webrender took to render a frame.
+    pub fn record(&mut self, duration: Duration) {
+        self.0.record(duration, &FRAME_TIME_BUCKETS);
+    }
+}
+
+/// Performance counters of every session of the profile, only recorded when metrics are enabled.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Metrics {
+    /// Version of Verso which last recorded the metrics
+    pub version: String,
+    /// Number of times Verso was started
+    pub sessions: u64,
+    /// Time Webrender took to render frames
+    pub frame_times: Timings,
+    /// Time from the start of a navigation until its document is loaded
+    pub load_times: Timings,
+    /// Number of times a page crashed
+    pub crashes: u64,
+}
+
+impl Metrics {
+    /// Record the start of a session.
+    pub fn record_session(&mut self) {
+        self.version = env!("CARGO_PKG_VERSION").to_string();
+        self.sessions += 1;
+    }
+
+    /// Record the time a document took to load.
+    pub fn record_load_time(&mut self, duration: Duration) {
+        self.load_times.record(duration, &LOAD_TIME_BUCKETS);
+    }
+
+    /// Record a crash of a page.
+    pub fn record_crash(&mut self) {
+        self.crashes += 1;
+    }
+
+    /// Merge the frame times measured by the compositor since they were last merged.
+    pub(crate) fn merge_frame_times(&mut self, frame_times: FrameTimes) {
+        self.frame_times.merge(frame_times.0);
+    }
+}
+
+/// Send the metrics to the upload endpoint on another thread.
+pub(crate) fn upload(metrics: Metrics, url: url::Url) {
+    let result = thread::Builder::new()
+        .name("MetricsUpload".to_owned())
+        .spawn(move || {
+            let result = reqwest::blocking::Client::new()
+                .post(url)
+                .json(&metrics)
+                .send()
+                .and_then(|response| response.error_for_status());
+            if let Err(e) = result {
+                log::warn!("Failed to upload metrics: {e}");
+            }
+        });
+    if let Err(e) = result {
+        log::error!("Failed to spawn metrics upload thread: {e}");
+    }
+}
+
+pub(crate) struct MetricsStorage {
+    config_dir_path: PathBuf,
+}
+
+impl MetricsStorage {
+    /// Create a new `MetricsStorage`.
+    pub fn new(config_dir_path: PathBuf) -> Self {
+        Self { config_dir_path }
+    }
+
+    fn metrics_file_path(&self) -> PathBuf {
+        self.config_dir_path.join("metrics.json")
+    }
+
+    /// Load metrics from disk.
+    pub fn load_from_file(&self) -> Result<Metrics, std::io::Error> {
+        let file = File::open(self.metrics_file_path())?;
+        let metrics = serde_json::from_reader(file)?;
+        Ok(metrics)
+    }
+
+    /// Save metrics to disk.
+    pub fn save_to_file(&self, metrics: &Metrics) -> Result<(), std::io::Error> {
+        let file = File::create(self.metrics_file_path())?;
+        serde_json::to_writer(file, metrics)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,157 @@
+use std::{fs::File, path::PathBuf};
+
+use dpi::{PhysicalPosition, PhysicalSize};
+use serde::{Deserialize, Serialize};
+use winit::{
+    event_loop::ActiveEventLoop,
+    monitor::MonitorHandle,
+    window::{Window as WinitWindow, WindowAttributes},
+};
+
+/// Size, position and monitor of a window when it was last closed, in physical pixels.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct WindowPlacement {
+    /// Name of the window, if it was named.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Horizontal position of the outer window on the desktop, `None` if the platform doesn't
+    /// tell windows where they are.
+    #[serde(default)]
+    pub x: Option<i32>,
+    /// Vertical position of the outer window on the desktop.
+    #[serde(default)]
+    pub y: Option<i32>,
+    /// Width of the inner window.
+    pub width: u32,
+    /// Height of the inner window.
+    pub height: u32,
+    /// Whether the window was maximized.
+    #[serde(default)]
+    pub maximized: bool,
+    /// Name of the monitor the window was on.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub monitor: Option<String>,
+}
+
+impl WindowPlacement {
+    /// Placement of the winit window.
+    pub fn of_window(window: &WinitWindow, name: Option<String>) -> Self {
+        let position = window.outer_position().ok();
+        let size = window.inner_size();
+        Self {
+            name,
+            x: position.map(|position| position.x),
+            y: position.map(|position| position.y),
+            width: size.width,
+            height: size.height,
+            maximized: window.is_maximized(),
+            monitor: window.current_monitor().and_then(|monitor| monitor.name()),
+        }
+    }
+
+    /// Place a new window like the saved one. If its monitor is gone, the system places the
+    /// window and it's shrunk to fit the primary monitor. Windows given a size or position by the
+    /// config or the command line keep them.
+    pub fn apply(&self, evl: &ActiveEventLoop, attributes: WindowAttributes) -> WindowAttributes {
+        if attributes.position.is_some() || attributes.inner_size.is_some() {
+            return attributes;
+        }
+        let monitor = self.monitor.as_ref().and_then(|name| {
+            evl.available_monitors()
+                .find(|monitor| monitor.name().as_ref() == Some(name))
+        });
+        let bounds = monitor
+            .clone()
+            .or_else(|| evl.primary_monitor())
+            .or_else(|| evl.available_monitors().next())
+            .map(|monitor| monitor.size());
+        let size = match bounds {
+            Some(bounds) => PhysicalSize::new(
+                self.width.min(bounds.width).max(1),
+                self.height.min(bounds.height).max(1),
+            ),
+            None => PhysicalSize::new(self.width.max(1), self.height.max(1)),
+        };
+
+        let mut attributes = attributes
+            .with_inner_size(size)
+            .with_maximized(self.maximized);
+        if let (Some(monitor), Some(position)) = (monitor, self.position()) {
+            attributes = attributes.with_position(clamp(&monitor, position, size));
+        }
+        attributes
+    }
+
+    fn position(&self) -> Option<PhysicalPosition<i32>> {
+        Some(PhysicalPosition::new(self.x?, self.y?))
+    }
+}
+
+/// Move the position so a window of this size is on the monitor, in case the monitors were
+/// rearranged.
+fn clamp(
+    monitor: &MonitorHandle,
+    position: PhysicalPosition<i32>,
+    size: PhysicalSize<u32>,
+) -> PhysicalPosition<i32> {
+    let origin = monitor.position();
+    let bounds = monitor.size();
+    let max_x = origin.x + bounds.width.saturating_sub(size.width) as i32;
+    let max_y = origin.y + bounds.height.saturating_sub(size.height) as i32;
+    PhysicalPosition::new(
+        position.x.clamp(origin.x, max_x),
+        position.y.clamp(origin.y, max_y),
+    )
+}
+
+/// Placements of the windows of the profile, given back to the windows opened on startup.
+#[derive(Debug, Default)]
+pub(crate) struct WindowPlacements {
+    placements: Vec<WindowPlacement>,
+}
+
+impl WindowPlacements {
+    /// Create the placements loaded from disk.
+    pub fn new(placements: Vec<WindowPlacement>) -> Self {
+        Self { placements }
+    }
+
+    /// Take the placement of the window with this name, or of the next unnamed window if `None`.
+    pub fn take(&mut self, name: Option<&str>) -> Option<WindowPlacement> {
+        let index = self
+            .placements
+            .iter()
+            .position(|placement| placement.name.as_deref() == name)?;
+        Some(self.placements.remove(index))
+    }
+}
+
+pub(crate) struct PlacementStorage {
+    config_dir_path: PathBuf,
+}
+
+impl PlacementStorage {
+    /// Create a new `PlacementStorage`.
+    pub fn new(config_dir_path: PathBuf) -> Self {
+        Self { config_dir_path }
+    }
+
+    fn placement_file_path(&self) -> PathBuf {
+        self.config_dir_path.join("window_placement.json")
+    }
+
+    /// Load window placements from disk.
+    pub fn load_from_file(&self) -> Result<Vec<WindowPlacement>, std::io::Error> {
+        let file = File::open(self.placement_file_path())?;
+        let placements = serde_json::from_reader(file)?;
+        Ok(placements)
+    }
+
+    /// Save window placements to disk.
+    pub fn save_to_file(&self, placements: &[WindowPlacement]) -> Result<(), std::io::Error> {
+        let file = File::create(self.placement_file_path())?;
+        serde_json::to_writer(file, placements)?;
+        Ok(())
+    }
+}
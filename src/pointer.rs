@@ -0,0 +1,87 @@
+use std::collections::HashMap;
+
+use base::id::WebViewId;
+use embedder_traits::TouchId;
+use winit::{dpi::PhysicalPosition, event::DeviceId};
+
+/// A mouse, touchpad or pen of a window, moved independently of the others, e.g. one for each
+/// user of a kiosk table, or for each seat of the Wayland compositor.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Pointer {
+    /// Position of the pointer in the window
+    pub position: PhysicalPosition<f64>,
+    /// Webview under the pointer, which keeps its hover state while other pointers move elsewhere
+    pub hovered: Option<WebViewId>,
+}
+
+/// Pointers and touch points of a window. Every pointer has a position and hover state of its
+/// own, and touch points of every touch screen get ids which don't collide, so a touch and a
+/// mouse, or two touch screens, can be used at the same time.
+///
+/// Winit doesn't tell which seat a keyboard belongs to, keys go to the focused webview of the
+/// window whichever keyboard they're typed on.
+#[derive(Debug, Default)]
+pub(crate) struct Pointers {
+    pointers: HashMap<DeviceId, Pointer>,
+    /// Pointer moved last, the one the cursor and context menus follow
+    last_moved: Option<DeviceId>,
+    /// Touch points being touched, by device and id of the touch screen
+    touches: HashMap<(DeviceId, u64), TouchId>,
+    next_touch_id: i32,
+}
+
+impl Pointers {
+    /// Move the pointer of the device over the webview.
+    pub fn moved(
+        &mut self,
+        device_id: DeviceId,
+        position: PhysicalPosition<f64>,
+        hovered: Option<WebViewId>,
+    ) {
+        self.last_moved = Some(device_id);
+        self.pointers
+            .insert(device_id, Pointer { position, hovered });
+    }
+
+    /// Forget the pointer of the device when it leaves the window, returns another pointer still
+    /// in the window, if any.
+    pub fn left(&mut self, device_id: DeviceId) -> Option<&Pointer> {
+        self.pointers.remove(&device_id);
+        if self.last_moved == Some(device_id) {
+            self.last_moved = self.pointers.keys().next().copied();
+        }
+        self.last_moved.and_then(|id| self.pointers.get(&id))
+    }
+
+    /// The pointer of the device, if it's in the window.
+    pub fn get(&self, device_id: DeviceId) -> Option<&Pointer> {
+        self.pointers.get(&device_id)
+    }
+
+    /// Id of the touch point of the touch screen, unique among every touch screen of the window.
+    /// The id is released when the touch ends.
+    pub fn touch_id(&mut self, device_id: DeviceId, id: u64, ended: bool) -> TouchId {
+        let touch_id = match self.touches.get(&(device_id, id)) {
+            Some(touch_id) => *touch_id,
+            None => {
+                let touch_id = TouchId(self.next_touch_id);
+                self.next_touch_id = self.next_touch_id.wrapping_add(1);
+                self.touches.insert((device_id, id), touch_id);
+                touch_id
+            }
+        };
+        if ended {
+            self.touches.remove(&(device_id, id));
+        }
+        touch_id
+    }
+
+    /// Forget the webview, e.g. when it's closed.
+    pub fn remove_webview(&mut self, webview_id: WebViewId) {
+        for pointer in self.pointers.values_mut() {
+            if pointer.hovered == Some(webview_id) {
+                pointer.hovered = None;
+            }
+        }
+    }
+}
@@ -0,0 +1,681 @@
+use std::{collections::HashSet, thread, time::Duration};
+
+use base::id::WebViewId;
+use ipc_channel::ipc::IpcSender;
+use serde::{Deserialize, Serialize};
+
+use crate::verso::VersoInternalMsg;
+
+/// How often the power state of the system is checked.
+const POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Power state of the system.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct PowerState {
+    /// Nothing has been typed or clicked for the idle timeout.
+    pub idle: bool,
+    /// The power saver mode of the system is on.
+    pub power_saver: bool,
+    /// The battery of the system, `None` if it has no battery.
+    pub battery: Option<BatteryStatus>,
+}
+
+impl PowerState {
+    /// Whether the system runs on its battery.
+    pub fn on_battery(&self) -> bool {
+        self.battery.is_some_and(|battery| !battery.charging)
+    }
+}
+
+/// Status of the battery, reported to pages by the Battery Status API.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatteryStatus {
+    /// The system is plugged in.
+    pub charging: bool,
+    /// Charge level, from 0 to 1.
+    pub level: f64,
+    /// Seconds until the battery is full, `None` if unknown or discharging.
+    pub charging_time: Option<u64>,
+    /// Seconds until the battery is empty, `None` if unknown or charging.
+    pub discharging_time: Option<u64>,
+}
+
+/// Spawn a thread checking the power state of the system, and send
+/// [`VersoInternalMsg::PowerStateChanged`] whenever it changes.
+pub(crate) fn watch_power_state(idle_timeout: Duration, sender: IpcSender<VersoInternalMsg>) {
+    let result = thread::Builder::new()
+        .name("PowerWatcher".to_owned())
+        .spawn(move || {
+            let mut state = PowerState::default();
+            loop {
+                let new_state = PowerState {
+                    idle: platform::idle_time().is_some_and(|idle_time| idle_time >= idle_timeout),
+                    power_saver: platform::is_power_saver_enabled(),
+                    battery: platform::battery_status(),
+                };
+                if new_state != state {
+                    state = new_state;
+                    if let Err(e) = sender.send(VersoInternalMsg::PowerStateChanged(state)) {
+                        log::error!("Power watcher failed to notify Verso, stop watching: {e}");
+                        break;
+                    }
+                }
+                thread::sleep(POLL_INTERVAL);
+            }
+        });
+    if let Err(e) = result {
+        log::error!("Failed to spawn power watcher thread: {e}");
+    }
+}
+
+/// Keeps the display and the system awake while any webview asks for it.
+#[derive(Default)]
+pub struct SleepInhibitors {
+    /// Webviews the controller keeps awake.
+    webview_ids: HashSet<WebViewId>,
+    /// Webviews holding a screen wake lock.
+    wake_locks: HashSet<WebViewId>,
+    inhibitor: Option<platform::Inhibitor>,
+}
+
+impl SleepInhibitors {
+    /// Create a new `SleepInhibitors`.
+    pub fn new() -> Self {
+        Self {
+            webview_ids: HashSet::new(),
+            wake_locks: HashSet::new(),
+            inhibitor: None,
+        }
+    }
+
+    /// Set whether the webview keeps the display awake, e.g. while playing a video.
+    pub fn set(&mut self, webview_id: WebViewId, inhibit: bool) {
+        if inhibit {
+            self.webview_ids.insert(webview_id);
+        } else {
+            self.webview_ids.remove(&webview_id);
+        }
+        self.update();
+    }
+
+    /// Set whether the document of the webview holds a screen wake lock.
+    pub fn set_wake_lock(&mut self, webview_id: WebViewId, held: bool) {
+        if held {
+            self.wake_locks.insert(webview_id);
+        } else {
+            self.wake_locks.remove(&webview_id);
+        }
+        self.update();
+    }
+
+    /// Release everything the webview holds, e.g. when it's closed.
+    pub fn release(&mut self, webview_id: WebViewId) {
+        self.webview_ids.remove(&webview_id);
+        self.wake_locks.remove(&webview_id);
+        self.update();
+    }
+
+    /// Whether any webview keeps the display awake.
+    pub fn is_inhibited(&self) -> bool {
+        !self.webview_ids.is_empty() || !self.wake_locks.is_empty()
+    }
+
+    fn update(&mut self) {
+        if !self.is_inhibited() {
+            // The inhibitor is released when dropped
+            self.inhibitor = None;
+        } else if self.inhibitor.is_none() {
+            self.inhibitor = platform::Inhibitor::acquire("Keeping the page awake");
+        }
+    }
+}
+
+/// Script which implements `navigator.wakeLock` with the `WAKE_LOCK` requests of the Verso bridge.
+/// Locks are released when the document is hidden or unloaded, as the Screen Wake Lock API
+/// requires, and by Verso when the tab is deactivated.
+pub(crate) const WAKE_LOCK_SCRIPT: &str = r#"
+(() => {
+  if (location.protocol === 'verso:' || !window.isSecureContext) {
+    return;
+  }
+
+  const sentinels = new Set();
+  const releaseAll = () => {
+    for (const sentinel of [...sentinels]) {
+      sentinel.release();
+    }
+  };
+
+  class WakeLockSentinel extends EventTarget {
+    #released = false;
+    onrelease = null;
+
+    get released() {
+      return this.#released;
+    }
+
+    get type() {
+      return 'screen';
+    }
+
+    async release() {
+      if (this.#released) {
+        return;
+      }
+      this.#released = true;
+      sentinels.delete(this);
+      if (sentinels.size === 0) {
+        prompt('VERSO::WAKE_LOCK::RELEASE');
+      }
+      const event = new Event('release');
+      this.onrelease?.(event);
+      this.dispatchEvent(event);
+    }
+  }
+
+  class WakeLock {
+    async request(type = 'screen') {
+      if (type !== 'screen') {
+        throw new TypeError(`Unsupported wake lock type: ${type}`);
+      }
+      if (document.visibilityState !== 'visible') {
+        throw new DOMException('The document is hidden', 'NotAllowedError');
+      }
+      if (sentinels.size === 0 && prompt('VERSO::WAKE_LOCK::ACQUIRE') === null) {
+        throw new DOMException('The wake lock request was denied', 'NotAllowedError');
+      }
+      const sentinel = new WakeLockSentinel();
+      sentinels.add(sentinel);
+      return sentinel;
+    }
+  }
+
+  const wakeLock = new WakeLock();
+  Object.defineProperty(Navigator.prototype, 'wakeLock', {
+    get: () => wakeLock,
+    configurable: true,
+  });
+  document.addEventListener('visibilitychange', () => {
+    if (document.visibilityState !== 'visible') {
+      releaseAll();
+    }
+  });
+  window.addEventListener('pagehide', releaseAll);
+  Object.defineProperty(window, '__versoReleaseWakeLocks', { value: releaseAll });
+})();
+"#;
+
+/// Script which implements `navigator.getBattery()` with the `BATTERY_STATUS` request of the Verso
+/// bridge. Verso pushes the changes of the status with [`battery_status_script`].
+pub(crate) const BATTERY_SCRIPT: &str = r#"
+(() => {
+  if (location.protocol === 'verso:' || !window.isSecureContext) {
+    return;
+  }
+
+  // A system without battery is reported as a full battery which is charging
+  const toValues = (status) => ({
+    charging: status ? status.charging : true,
+    level: status ? status.level : 1,
+    chargingTime: status ? (status.chargingTime ?? Infinity) : 0,
+    dischargingTime: status ? (status.dischargingTime ?? Infinity) : Infinity,
+  });
+
+  class BatteryManager extends EventTarget {
+    #values;
+    onchargingchange = null;
+    onlevelchange = null;
+    onchargingtimechange = null;
+    ondischargingtimechange = null;
+
+    constructor(values) {
+      super();
+      this.#values = values;
+    }
+
+    get charging() {
+      return this.#values.charging;
+    }
+    get level() {
+      return this.#values.level;
+    }
+    get chargingTime() {
+      return this.#values.chargingTime;
+    }
+    get dischargingTime() {
+      return this.#values.dischargingTime;
+    }
+
+    _update(values) {
+      const previous = this.#values;
+      this.#values = values;
+      for (const name of ['charging', 'level', 'chargingTime', 'dischargingTime']) {
+        if (previous[name] !== values[name]) {
+          const event = new Event(`${name.toLowerCase()}change`);
+          this[`on${event.type}`]?.(event);
+          this.dispatchEvent(event);
+        }
+      }
+    }
+  }
+
+  let manager = null;
+  Navigator.prototype.getBattery = function () {
+    if (!manager) {
+      const response = prompt('VERSO::BATTERY_STATUS');
+      manager = new BatteryManager(toValues(response ? JSON.parse(response) : null));
+    }
+    return Promise.resolve(manager);
+  };
+  Object.defineProperty(window, '__versoUpdateBattery', {
+    value: (status) => manager?._update(toValues(status)),
+  });
+})();
+"#;
+
+/// Script to update the battery status of a document.
+pub(crate) fn battery_status_script(battery: Option<BatteryStatus>) -> String {
+    let battery = serde_json::to_string(&battery).unwrap_or_else(|_| "null".to_string());
+    format!("window.__versoUpdateBattery?.({battery})")
+}
+
+/// Script to release the wake locks of a document which is no longer visible.
+pub(crate) const RELEASE_WAKE_LOCKS_SCRIPT: &str = "window.__versoReleaseWakeLocks?.()";
+
+#[cfg(linux)]
+mod platform {
+    use std::time::Duration;
+
+    use zbus::blocking::{Connection, Proxy};
+
+    use super::BatteryStatus;
+
+    /// Time since the last user input, from the idle monitor of Mutter or the screen saver
+    /// service of other desktops.
+    pub fn idle_time() -> Option<Duration> {
+        let connection = Connection::session().ok()?;
+        let mutter = connection
+            .call_method(
+                Some("org.gnome.Mutter.IdleMonitor"),
+                "/org/gnome/Mutter/IdleMonitor/Core",
+                Some("org.gnome.Mutter.IdleMonitor"),
+                "GetIdletime",
+                &(),
+            )
+            .ok()
+            .and_then(|reply| reply.body().deserialize::<u64>().ok())
+            .map(Duration::from_millis);
+        mutter.or_else(|| {
+            connection
+                .call_method(
+                    Some("org.freedesktop.ScreenSaver"),
+                    "/org/freedesktop/ScreenSaver",
+                    Some("org.freedesktop.ScreenSaver"),
+                    "GetSessionIdleTime",
+                    &(),
+                )
+                .ok()
+                .and_then(|reply| reply.body().deserialize::<u32>().ok())
+                .map(|seconds| Duration::from_secs(seconds.into()))
+        })
+    }
+
+    /// Whether power-profiles-daemon is in the power saver profile.
+    pub fn is_power_saver_enabled() -> bool {
+        let Ok(connection) = Connection::system() else {
+            return false;
+        };
+        Proxy::new(
+            &connection,
+            "net.hadess.PowerProfiles",
+            "/net/hadess/PowerProfiles",
+            "net.hadess.PowerProfiles",
+        )
+        .and_then(|proxy| proxy.get_property::<String>("ActiveProfile"))
+        .is_ok_and(|profile| profile == "power-saver")
+    }
+
+    /// The display device of UPower, which combines all the batteries of the system.
+    pub fn battery_status() -> Option<BatteryStatus> {
+        let connection = Connection::system().ok()?;
+        let proxy = Proxy::new(
+            &connection,
+            "org.freedesktop.UPower",
+            "/org/freedesktop/UPower/devices/DisplayDevice",
+            "org.freedesktop.UPower.Device",
+        )
+        .ok()?;
+        if !proxy.get_property::<bool>("IsPresent").ok()? {
+            return None;
+        }
+        let percentage: f64 = proxy.get_property("Percentage").ok()?;
+        // 2 is discharging, 4 is fully charged and 6 is pending discharge
+        let state: u32 = proxy.get_property("State").ok()?;
+        let seconds = |name: &str| {
+            proxy
+                .get_property::<i64>(name)
+                .ok()
+                .and_then(|seconds| u64::try_from(seconds).ok())
+                .filter(|seconds| *seconds > 0)
+        };
+        let charging = !matches!(state, 2 | 6);
+        Some(BatteryStatus {
+            charging,
+            level: percentage / 100.0,
+            charging_time: match state {
+                4 => Some(0),
+                _ if charging => seconds("TimeToFull"),
+                _ => None,
+            },
+            discharging_time: if charging {
+                None
+            } else {
+                seconds("TimeToEmpty")
+            },
+        })
+    }
+
+    /// Screen saver inhibition, kept while the D-Bus connection is open.
+    pub struct Inhibitor {
+        connection: Connection,
+        cookie: u32,
+    }
+
+    impl Inhibitor {
+        pub fn acquire(reason: &str) -> Option<Self> {
+            let connection = Connection::session().ok()?;
+            let reply = connection
+                .call_method(
+                    Some("org.freedesktop.ScreenSaver"),
+                    "/org/freedesktop/ScreenSaver",
+                    Some("org.freedesktop.ScreenSaver"),
+                    "Inhibit",
+                    &("Verso", reason),
+                )
+                .inspect_err(|e| log::error!("Failed to inhibit the screen saver: {e}"))
+                .ok()?;
+            let cookie = reply.body().deserialize::<u32>().ok()?;
+            Some(Self { connection, cookie })
+        }
+    }
+
+    impl Drop for Inhibitor {
+        fn drop(&mut self) {
+            let _ = self.connection.call_method(
+                Some("org.freedesktop.ScreenSaver"),
+                "/org/freedesktop/ScreenSaver",
+                Some("org.freedesktop.ScreenSaver"),
+                "UnInhibit",
+                &(self.cookie,),
+            );
+        }
+    }
+}
+
+#[cfg(macos)]
+mod platform {
+    use std::time::Duration;
+
+    use core_foundation::{
+        array::{CFArray, CFArrayRef},
+        base::{CFType, CFTypeRef, TCFType},
+        boolean::CFBoolean,
+        dictionary::{CFDictionary, CFDictionaryRef},
+        number::CFNumber,
+        string::{CFString, CFStringRef},
+    };
+    use objc2_foundation::NSProcessInfo;
+
+    use super::BatteryStatus;
+
+    const K_CG_EVENT_SOURCE_STATE_COMBINED_SESSION_STATE: i32 = 0;
+    const K_CG_ANY_INPUT_EVENT_TYPE: u32 = !0;
+    const K_IOPM_ASSERTION_LEVEL_ON: u32 = 255;
+
+    #[link(name = "CoreGraphics", kind = "framework")]
+    unsafe extern "C" {
+        fn CGEventSourceSecondsSinceLastEventType(state: i32, event_type: u32) -> f64;
+    }
+
+    #[link(name = "IOKit", kind = "framework")]
+    unsafe extern "C" {
+        fn IOPMAssertionCreateWithName(
+            assertion_type: CFStringRef,
+            level: u32,
+            name: CFStringRef,
+            assertion_id: *mut u32,
+        ) -> i32;
+        fn IOPMAssertionRelease(assertion_id: u32) -> i32;
+        fn IOPSCopyPowerSourcesInfo() -> CFTypeRef;
+        fn IOPSCopyPowerSourcesList(blob: CFTypeRef) -> CFArrayRef;
+        fn IOPSGetPowerSourceDescription(blob: CFTypeRef, source: CFTypeRef) -> CFDictionaryRef;
+    }
+
+    pub fn idle_time() -> Option<Duration> {
+        let seconds = unsafe {
+            CGEventSourceSecondsSinceLastEventType(
+                K_CG_EVENT_SOURCE_STATE_COMBINED_SESSION_STATE,
+                K_CG_ANY_INPUT_EVENT_TYPE,
+            )
+        };
+        Duration::try_from_secs_f64(seconds).ok()
+    }
+
+    #[allow(unused_unsafe)]
+    pub fn is_power_saver_enabled() -> bool {
+        unsafe { NSProcessInfo::processInfo().isLowPowerModeEnabled() }
+    }
+
+    /// The internal battery from the power sources of IOKit.
+    pub fn battery_status() -> Option<BatteryStatus> {
+        let blob = unsafe { IOPSCopyPowerSourcesInfo() };
+        if blob.is_null() {
+            return None;
+        }
+        let blob = unsafe { CFType::wrap_under_create_rule(blob) };
+        let sources = unsafe { IOPSCopyPowerSourcesList(blob.as_CFTypeRef()) };
+        if sources.is_null() {
+            return None;
+        }
+        let sources: CFArray<CFType> = unsafe { CFArray::wrap_under_create_rule(sources) };
+        sources.iter().find_map(|source| {
+            let description = unsafe {
+                IOPSGetPowerSourceDescription(blob.as_CFTypeRef(), source.as_CFTypeRef())
+            };
+            if description.is_null() {
+                return None;
+            }
+            let description: CFDictionary<CFString, CFType> =
+                unsafe { CFDictionary::wrap_under_get_rule(description) };
+            let value = |key: &'static str| {
+                description
+                    .find(CFString::from_static_string(key))
+                    .map(|value| (*value).clone())
+            };
+            let string = |key| value(key).and_then(|value| value.downcast::<CFString>());
+            let number = |key| {
+                value(key)
+                    .and_then(|value| value.downcast::<CFNumber>())
+                    .and_then(|number| number.to_i64())
+            };
+            // Times are in minutes, -1 while they are estimated
+            let seconds = |key| {
+                number(key)
+                    .and_then(|minutes| u64::try_from(minutes).ok())
+                    .map(|minutes| minutes * 60)
+            };
+
+            if string("Type")?.to_string() != "InternalBattery" {
+                return None;
+            }
+            let charging = string("Power Source State")?.to_string() == "AC Power";
+            let is_charging = value("Is Charging")
+                .and_then(|value| value.downcast::<CFBoolean>())
+                .is_some_and(bool::from);
+            let level = number("Current Capacity")? as f64 / number("Max Capacity")?.max(1) as f64;
+            Some(BatteryStatus {
+                charging,
+                level: level.min(1.0),
+                charging_time: if !charging {
+                    None
+                } else if is_charging {
+                    seconds("Time to Full Charge")
+                } else {
+                    Some(0)
+                },
+                discharging_time: if charging {
+                    None
+                } else {
+                    seconds("Time to Empty")
+                },
+            })
+        })
+    }
+
+    /// Power assertion preventing the display from sleeping.
+    pub struct Inhibitor {
+        assertion_id: u32,
+    }
+
+    impl Inhibitor {
+        pub fn acquire(reason: &str) -> Option<Self> {
+            let assertion_type = CFString::new("PreventUserIdleDisplaySleep");
+            let name = CFString::new(reason);
+            let mut assertion_id = 0;
+            let result = unsafe {
+                IOPMAssertionCreateWithName(
+                    assertion_type.as_concrete_TypeRef(),
+                    K_IOPM_ASSERTION_LEVEL_ON,
+                    name.as_concrete_TypeRef(),
+                    &mut assertion_id,
+                )
+            };
+            if result != 0 {
+                log::error!("Failed to create power assertion: {result}");
+                return None;
+            }
+            Some(Self { assertion_id })
+        }
+    }
+
+    impl Drop for Inhibitor {
+        fn drop(&mut self) {
+            unsafe {
+                IOPMAssertionRelease(self.assertion_id);
+            }
+        }
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use std::time::Duration;
+
+    use windows_sys::Win32::{
+        System::{
+            Power::{
+                ES_CONTINUOUS, ES_DISPLAY_REQUIRED, ES_SYSTEM_REQUIRED, GetSystemPowerStatus,
+                SYSTEM_POWER_STATUS, SetThreadExecutionState,
+            },
+            SystemInformation::GetTickCount,
+        },
+        UI::Input::KeyboardAndMouse::{GetLastInputInfo, LASTINPUTINFO},
+    };
+
+    use super::BatteryStatus;
+
+    pub fn idle_time() -> Option<Duration> {
+        let mut info = LASTINPUTINFO {
+            cbSize: size_of::<LASTINPUTINFO>() as u32,
+            dwTime: 0,
+        };
+        if unsafe { GetLastInputInfo(&mut info) } == 0 {
+            return None;
+        }
+        let now = unsafe { GetTickCount() };
+        Some(Duration::from_millis(now.wrapping_sub(info.dwTime).into()))
+    }
+
+    fn power_status() -> Option<SYSTEM_POWER_STATUS> {
+        let mut status: SYSTEM_POWER_STATUS = unsafe { std::mem::zeroed() };
+        if unsafe { GetSystemPowerStatus(&mut status) } == 0 {
+            return None;
+        }
+        Some(status)
+    }
+
+    pub fn is_power_saver_enabled() -> bool {
+        // Battery saver is on
+        power_status().is_some_and(|status| status.SystemStatusFlag == 1)
+    }
+
+    pub fn battery_status() -> Option<BatteryStatus> {
+        let status = power_status()?;
+        // 128 is no system battery, 255 is unknown
+        if status.BatteryFlag & 128 != 0 || status.BatteryFlag == 255 {
+            return None;
+        }
+        let charging = status.ACLineStatus == 1;
+        let level = match status.BatteryLifePercent {
+            percent @ 0..=100 => f64::from(percent) / 100.0,
+            _ => 1.0,
+        };
+        Some(BatteryStatus {
+            charging,
+            level,
+            charging_time: (charging && level >= 1.0).then_some(0),
+            discharging_time: (!charging && status.BatteryLifeTime != u32::MAX)
+                .then(|| status.BatteryLifeTime.into()),
+        })
+    }
+
+    /// Execution state of the thread, so it must be dropped on the thread which acquired it.
+    pub struct Inhibitor;
+
+    impl Inhibitor {
+        pub fn acquire(_reason: &str) -> Option<Self> {
+            let result = unsafe {
+                SetThreadExecutionState(ES_CONTINUOUS | ES_DISPLAY_REQUIRED | ES_SYSTEM_REQUIRED)
+            };
+            if result == 0 {
+                log::error!("Failed to set thread execution state");
+                return None;
+            }
+            Some(Self)
+        }
+    }
+
+    impl Drop for Inhibitor {
+        fn drop(&mut self) {
+            unsafe {
+                SetThreadExecutionState(ES_CONTINUOUS);
+            }
+        }
+    }
+}
+
+#[cfg(not(any(linux, macos, windows)))]
+mod platform {
+    use std::time::Duration;
+
+    pub fn idle_time() -> Option<Duration> {
+        None
+    }
+
+    pub fn is_power_saver_enabled() -> bool {
+        false
+    }
+
+    pub fn battery_status() -> Option<super::BatteryStatus> {
+        None
+    }
+
+    pub struct Inhibitor;
+
+    impl Inhibitor {
+        pub fn acquire(_reason: &str) -> Option<Self> {
+            None
+        }
+    }
+}
@@ -0,0 +1,89 @@
+/// Script which applies the privacy settings of the webview to every document. Verso answers the
+/// `PRIVACY_SETTINGS` request with the settings of the tab, or the ones of the config.
+pub(crate) const PRIVACY_SCRIPT: &str = r#"
+(() => {
+  const define = (target, name, value) =>
+    Object.defineProperty(target, name, { get: () => value, configurable: true });
+
+  // Report the values of a common desktop, and make canvas readbacks differ between documents
+  function resistFingerprinting() {
+    for (const [name, value] of Object.entries({
+      width: 1920,
+      height: 1080,
+      availWidth: 1920,
+      availHeight: 1080,
+      colorDepth: 24,
+      pixelDepth: 24,
+    })) {
+      define(Screen.prototype, name, value);
+    }
+    define(window, 'devicePixelRatio', 1);
+    Object.defineProperty(window, 'outerWidth', { get: () => window.innerWidth });
+    Object.defineProperty(window, 'outerHeight', { get: () => window.innerHeight });
+    define(Navigator.prototype, 'hardwareConcurrency', 4);
+
+    Date.prototype.getTimezoneOffset = () => 0;
+    const resolvedOptions = Intl.DateTimeFormat.prototype.resolvedOptions;
+    Intl.DateTimeFormat.prototype.resolvedOptions = function () {
+      return { ...resolvedOptions.call(this), timeZone: 'UTC' };
+    };
+
+    const addNoise = (imageData) => {
+      for (let i = 0; i < imageData.data.length; i += 4) {
+        if (Math.random() < 0.1) {
+          imageData.data[i] ^= 1;
+        }
+      }
+      return imageData;
+    };
+    const getImageData = CanvasRenderingContext2D.prototype.getImageData;
+    CanvasRenderingContext2D.prototype.getImageData = function (...args) {
+      return addNoise(getImageData.apply(this, args));
+    };
+    const noisyCopy = (canvas) => {
+      const context = canvas.getContext('2d');
+      if (!context || canvas.width === 0 || canvas.height === 0) {
+        return canvas;
+      }
+      const copy = document.createElement('canvas');
+      copy.width = canvas.width;
+      copy.height = canvas.height;
+      copy.getContext('2d').putImageData(context.getImageData(0, 0, canvas.width, canvas.height), 0, 0);
+      return copy;
+    };
+    const toDataURL = HTMLCanvasElement.prototype.toDataURL;
+    HTMLCanvasElement.prototype.toDataURL = function (...args) {
+      return toDataURL.apply(noisyCopy(this), args);
+    };
+    const toBlob = HTMLCanvasElement.prototype.toBlob;
+    HTMLCanvasElement.prototype.toBlob = function (...args) {
+      return toBlob.apply(noisyCopy(this), args);
+    };
+  }
+
+  if (location.protocol === 'verso:') {
+    return;
+  }
+  const response = window.prompt('VERSO::PRIVACY_SETTINGS');
+  if (!response) {
+    return;
+  }
+  const settings = JSON.parse(response);
+  if (settings.do_not_track) {
+    define(Navigator.prototype, 'doNotTrack', '1');
+  }
+  if (settings.global_privacy_control) {
+    define(Navigator.prototype, 'globalPrivacyControl', true);
+  }
+  if (settings.resist_fingerprinting) {
+    resistFingerprinting();
+  }
+  if (settings.referrer_policy) {
+    // A referrer meta element applies to the whole document once it's inserted
+    const meta = document.createElement('meta');
+    meta.name = 'referrer';
+    meta.content = settings.referrer_policy;
+    (document.head || document.documentElement).prepend(meta);
+  }
+})();
+"#;
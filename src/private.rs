@@ -0,0 +1,59 @@
+use std::{process::Command, thread};
+
+use crate::config::Config;
+
+/// Script of the panel of private windows, drawing a strip over the top of the panel so they
+/// can be told apart from the normal windows.
+pub(crate) const PRIVATE_PANEL_SCRIPT: &str = r#"
+(() => {
+  if (document.getElementById('verso-private-style')) {
+    return;
+  }
+  const style = document.createElement('style');
+  style.id = 'verso-private-style';
+  style.textContent = `body::before {
+    content: '';
+    position: fixed;
+    top: 0;
+    left: 0;
+    right: 0;
+    height: 3px;
+    background: #8000d7;
+    pointer-events: none;
+    z-index: 2147483647;
+  }`;
+  document.head.appendChild(style);
+})()
+"#;
+
+/// Open a private window in a new Verso process. Servo has one cookie jar, cache and storage per
+/// process, so private windows can't share the process of the normal ones. The new process keeps
+/// them in memory, and they're dropped when its last window closes.
+pub(crate) fn open_private_window(config: &Config) {
+    let exe = match std::env::current_exe() {
+        Ok(exe) => exe,
+        Err(e) => {
+            log::error!("Failed to find the executable to open a private window: {e}");
+            return;
+        }
+    };
+    let mut command = Command::new(exe);
+    command
+        .arg("--private")
+        .arg("--resources")
+        .arg(&config.resource_dir)
+        .arg("--url")
+        .arg(config.url.as_str());
+    match command.spawn() {
+        Ok(mut child) => {
+            // Wait for the process so it doesn't linger once it exits
+            let result = thread::Builder::new()
+                .name("PrivateWindow".to_owned())
+                .spawn(move || child.wait());
+            if let Err(e) = result {
+                log::error!("Failed to spawn private window thread: {e}");
+            }
+        }
+        Err(e) => log::error!("Failed to open a private window: {e}"),
+    }
+}
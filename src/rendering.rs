@@ -1,23 +1,46 @@
 use std::num::NonZeroU32;
 use std::rc::Rc;
-use std::{cell::Cell, ffi::CString};
+use std::{
+    cell::{Cell, RefCell},
+    ffi::CString,
+    time::{Duration, Instant},
+};
 
 use dpi::PhysicalSize;
 use euclid::Size2D;
 use gleam::gl;
+#[cfg(not(apple))]
+use glutin::{
+    config::ConfigSurfaceTypes,
+    display::Display,
+    surface::{PbufferSurface, SurfaceAttributesBuilder},
+};
 use glutin::{
-    config::{Config, GetGlConfig, GlConfig},
-    context::{ContextApi, ContextAttributesBuilder, PossiblyCurrentContext, Version},
+    config::{Config, ConfigTemplateBuilder, GetGlConfig, GlConfig},
+    context::{
+        ContextApi, ContextAttributesBuilder, NotCurrentContext, PossiblyCurrentContext, Version,
+    },
     display::GetGlDisplay,
     prelude::{GlContext, GlDisplay, NotCurrentGlContext, PossiblyCurrentGlContext},
     surface::{
-        GlSurface, ResizeableSurface, Surface, SurfaceTypeTrait, SwapInterval, WindowSurface,
+        GlSurface, ResizeableSurface, Surface, SurfaceAttributesBuilder, SurfaceTypeTrait,
+        SwapInterval, WindowSurface,
     },
 };
-use glutin_winit::GlWindow;
-use raw_window_handle::HasWindowHandle;
-use webrender_api::units::DevicePixel;
-use winit::window::Window;
+use image::RgbaImage;
+use raw_window_handle::RawWindowHandle;
+use versoview_messages::Vsync;
+use webrender_api::units::{DeviceIntRect, DevicePixel};
+
+use crate::backend::WindowBackend;
+
+/// Callback of [`RenderingContext::capture_frame`], called with the presented frame, or `None` if
+/// it couldn't be read.
+pub type FrameCaptureCallback = Box<dyn FnOnce(Option<RgbaImage>)>;
+
+/// Longest time between two frames which still makes the vertical blank with adaptive vsync, a
+/// bit more than a refresh at 60 Hz.
+const ADAPTIVE_VSYNC_FRAME_BUDGET: Duration = Duration::from_millis(20);
 
 /// A Verso rendering context, which holds all of the information needed
 /// to render Servo's layout, and bridges WebRender and glutin.
@@ -25,94 +48,110 @@ pub struct RenderingContext {
     context: PossiblyCurrentContext,
     size: Cell<PhysicalSize<u32>>,
     pub(crate) gl: Rc<dyn gl::Gl>,
+    /// Callbacks waiting for the next presented frame
+    frame_captures: RefCell<Vec<FrameCaptureCallback>>,
+    /// How surfaces wait for the vertical blank
+    vsync: Cell<Vsync>,
+    /// When the last frame was presented, to tell whether it missed the vertical blank
+    last_present: Cell<Option<Instant>>,
 }
 
 impl RenderingContext {
-    /// Create a rendering context instance.
+    /// Create a rendering context instance on the surface of the window backend, see
+    /// [`WindowBackend`].
     pub fn create(
-        window: &Window,
+        window: &(impl WindowBackend + ?Sized),
         gl_config: &Config,
         size: PhysicalSize<u32>,
+        vsync: Vsync,
     ) -> Result<(Self, Surface<WindowSurface>), Box<dyn std::error::Error>> {
         // XXX This will panic on Android, but we care about Desktop for now.
-        let raw_window_handle = window.window_handle().ok().map(|handle| handle.as_raw());
-        // XXX The display could be obtained from any object created by it, so we can
-        // query it from the config.
-        let gl_display = gl_config.display();
-        // The context creation part.
-        let context_attributes = ContextAttributesBuilder::new().build(raw_window_handle);
-        // Since glutin by default tries to create OpenGL core context, which may not be
-        // present we should try GLES.
-        let fallback_context_attributes = ContextAttributesBuilder::new()
-            .with_context_api(ContextApi::Gles(None))
-            .build(raw_window_handle);
-        // There are also some old devices that support neither modern OpenGL nor GLES.
-        // To support these we can try and create a 2.1 context.
-        let legacy_context_attributes = ContextAttributesBuilder::new()
-            .with_context_api(ContextApi::OpenGl(Some(Version::new(2, 1))))
-            .build(raw_window_handle);
-        let not_current_gl_context = unsafe {
-            gl_display
-                .create_context(gl_config, &context_attributes)
-                .unwrap_or_else(|_| {
-                    gl_display
-                        .create_context(gl_config, &fallback_context_attributes)
-                        .unwrap_or_else(|_| {
-                            gl_display
-                                .create_context(gl_config, &legacy_context_attributes)
-                                .expect("failed to create context")
-                        })
-                })
-        };
+        let raw_window_handle = window.window_handle()?.as_raw();
+        let not_current_gl_context = create_context(gl_config, Some(raw_window_handle))?;
 
         // Create surface
-        let attrs = window
-            .build_surface_attributes(Default::default())
-            .expect("Failed to build surface attributes");
+        let surface_size = window.surface_size();
+        let (Some(width), Some(height)) = (
+            NonZeroU32::new(surface_size.width),
+            NonZeroU32::new(surface_size.height),
+        ) else {
+            return Err("Window surface size must not be zero".into());
+        };
+        let attrs = SurfaceAttributesBuilder::<WindowSurface>::new().build(
+            raw_window_handle,
+            width,
+            height,
+        );
         let surface = unsafe {
             gl_config
                 .display()
-                .create_window_surface(gl_config, &attrs)
-                .unwrap()
+                .create_window_surface(gl_config, &attrs)?
         };
 
         // Make it current.
-        let context = not_current_gl_context.make_current(&surface).unwrap();
+        let context = not_current_gl_context.make_current(&surface)?;
 
         // Try setting vsync.
-        if let Err(res) =
-            surface.set_swap_interval(&context, SwapInterval::Wait(NonZeroU32::new(1).unwrap()))
-        {
+        if let Err(res) = surface.set_swap_interval(&context, swap_interval(vsync)) {
             log::error!("Error setting vsync: {res:?}");
         }
 
-        let gl = match context.context_api() {
-            ContextApi::OpenGl(_) => unsafe {
-                gleam::gl::GlFns::load_with(|symbol| {
-                    let symbol = CString::new(symbol).unwrap();
-                    gl_display.get_proc_address(symbol.as_c_str()) as *const _
-                })
-            },
-            ContextApi::Gles(_) => unsafe {
-                gleam::gl::GlesFns::load_with(|symbol| {
-                    let symbol = CString::new(symbol).unwrap();
-                    gl_display.get_proc_address(symbol.as_c_str()) as *const _
-                })
+        let gl = load_gl(&context);
+
+        Ok((
+            Self {
+                size: Cell::new(size),
+                context,
+                gl,
+                frame_captures: RefCell::default(),
+                vsync: Cell::new(vsync),
+                last_present: Cell::default(),
             },
+            surface,
+        ))
+    }
+
+    /// Create a rendering context instance without any window, rendering to a pbuffer surface
+    /// of the given size, e.g. to render pages on a server and read them back with
+    /// [`RenderingContext::read_to_image`]. The display is the first GPU found by EGL, so this
+    /// works without any display server.
+    #[cfg(not(apple))]
+    pub fn create_offscreen(
+        size: PhysicalSize<u32>,
+    ) -> Result<(Self, Surface<PbufferSurface>), Box<dyn std::error::Error>> {
+        use glutin::api::egl;
+
+        let (Some(width), Some(height)) =
+            (NonZeroU32::new(size.width), NonZeroU32::new(size.height))
+        else {
+            return Err("Offscreen rendering context size must not be zero".into());
         };
+        let device = egl::device::Device::query_devices()?
+            .next()
+            .ok_or("No EGL device found for offscreen rendering")?;
+        let gl_display =
+            Display::Egl(unsafe { egl::display::Display::with_device(&device, None)? });
 
-        println!("Running on {}", gl.get_string(gl::RENDERER));
-        println!("OpenGL Version {}", gl.get_string(gl::VERSION));
-        println!(
-            "Shaders version on {}",
-            gl.get_string(gl::SHADING_LANGUAGE_VERSION)
-        );
+        let template = ConfigTemplateBuilder::new()
+            .with_surface_type(ConfigSurfaceTypes::PBUFFER)
+            .build();
+        let gl_config = gl_config_picker(unsafe { gl_display.find_configs(template)? });
+        let not_current_gl_context = create_context(&gl_config, None)?;
+
+        let attrs = SurfaceAttributesBuilder::<PbufferSurface>::new().build(width, height);
+        let surface = unsafe { gl_display.create_pbuffer_surface(&gl_config, &attrs)? };
+        let context = not_current_gl_context.make_current(&surface)?;
+        let gl = load_gl(&context);
+        gl.viewport(0, 0, size.width as i32, size.height as i32);
 
         Ok((
             Self {
                 size: Cell::new(size),
                 context,
                 gl,
+                frame_captures: RefCell::default(),
+                vsync: Cell::default(),
+                last_present: Cell::default(),
             },
             surface,
         ))
@@ -127,7 +166,27 @@ impl RenderingContext {
             .build_surface_attributes(Default::default())
             .expect("Failed to build surface attributes");
         let config = self.context.config();
-        unsafe { Ok(config.display().create_window_surface(&config, &attrs)?) }
+        let surface = unsafe { config.display().create_window_surface(&config, &attrs)? };
+        if let Err(e) = self.apply_vsync(&surface) {
+            log::error!("Error setting vsync: {e:?}");
+        }
+        Ok(surface)
+    }
+
+    /// Set how surfaces wait for the vertical blank, the surfaces created already are updated
+    /// with [`RenderingContext::apply_vsync`].
+    pub fn set_vsync(&self, vsync: Vsync) {
+        self.vsync.set(vsync);
+    }
+
+    /// Apply the vsync mode of the rendering context to the surface.
+    pub fn apply_vsync(
+        &self,
+        surface: &Surface<impl SurfaceTypeTrait>,
+    ) -> Result<(), crate::errors::Error> {
+        self.context.make_current(surface)?;
+        surface.set_swap_interval(&self.context, swap_interval(self.vsync.get()))?;
+        Ok(())
     }
 
     /// Make GL context current.
@@ -154,12 +213,37 @@ impl RenderingContext {
             .viewport(0, 0, size.width as i32, size.height as i32);
     }
 
-    /// Present the surface of the rendering context.
+    /// Present the surface of the rendering context, after calling the callbacks of
+    /// [`RenderingContext::capture_frame`] with it.
     pub fn present(
         &self,
         surface: &Surface<impl SurfaceTypeTrait>,
     ) -> Result<(), crate::errors::Error> {
         self.context.make_current(surface)?;
+        let frame_captures = self.frame_captures.take();
+        if !frame_captures.is_empty() {
+            let image = self.read_to_image();
+            for callback in frame_captures {
+                callback(image.clone());
+            }
+        }
+        let now = Instant::now();
+        if self.vsync.get() == Vsync::Adaptive {
+            // Present late frames right away instead of waiting for the next vertical blank
+            let missed_blank = self
+                .last_present
+                .get()
+                .is_some_and(|last_present| now - last_present > ADAPTIVE_VSYNC_FRAME_BUDGET);
+            let interval = if missed_blank {
+                SwapInterval::DontWait
+            } else {
+                SwapInterval::Wait(NonZeroU32::MIN)
+            };
+            if let Err(e) = surface.set_swap_interval(&self.context, interval) {
+                log::warn!("Error setting adaptive vsync: {e:?}");
+            }
+        }
+        self.last_present.set(Some(now));
         surface.swap_buffers(&self.context)?;
         Ok(())
     }
@@ -174,6 +258,138 @@ impl RenderingContext {
         let size = self.size();
         Size2D::new(size.width, size.height)
     }
+
+    /// Read back the rectangle of what was rendered to the current surface, before it's presented.
+    /// The rectangle is in device pixels from the top left corner, and is clipped to the surface.
+    pub fn read_pixels(&self, rect: DeviceIntRect) -> Option<RgbaImage> {
+        let size = self.size2d().to_i32();
+        let rect = rect.intersection(&DeviceIntRect::from_size(size))?;
+        let (width, height) = (rect.width() as usize, rect.height() as usize);
+        self.gl.bind_framebuffer(gl::FRAMEBUFFER, 0);
+        self.gl.pixel_store_i(gl::PACK_ALIGNMENT, 1);
+        // GL rows start at the bottom of the surface
+        let pixels = self.gl.read_pixels(
+            rect.min.x,
+            size.height - rect.max.y,
+            rect.width(),
+            rect.height(),
+            gl::RGBA,
+            gl::UNSIGNED_BYTE,
+        );
+        if pixels.len() != width * height * 4 {
+            return None;
+        }
+        let flipped = pixels
+            .chunks_exact(width * 4)
+            .rev()
+            .flatten()
+            .copied()
+            .collect();
+        RgbaImage::from_raw(width as u32, height as u32, flipped)
+    }
+
+    /// Read back the whole surface, which must be current, before it's presented.
+    pub fn read_to_image(&self) -> Option<RgbaImage> {
+        self.read_pixels(DeviceIntRect::from_size(self.size2d().to_i32()))
+    }
+
+    /// Call the callback with the next frame presented with [`RenderingContext::present`], e.g.
+    /// to take a screenshot once the pending changes of webviews are composited.
+    pub fn capture_frame(&self, callback: FrameCaptureCallback) {
+        self.frame_captures.borrow_mut().push(callback);
+    }
+}
+
+/// Make the GL displays created from now on use the llvmpipe software rasterizer of Mesa. On
+/// other platforms, [`software_config_template`] picks a software config instead.
+pub(crate) fn use_software_rasterizer() {
+    // SAFETY: This is called on the main thread before Servo starts its threads, nothing else
+    // reads or writes the environment concurrently.
+    unsafe {
+        std::env::set_var("LIBGL_ALWAYS_SOFTWARE", "1");
+        std::env::set_var("GALLIUM_DRIVER", "llvmpipe");
+    }
+}
+
+/// Prefer configs which aren't hardware accelerated, so WGL and CGL pick their software
+/// renderers when software rendering is requested.
+pub(crate) fn software_config_template(
+    template: ConfigTemplateBuilder,
+    software_rendering: bool,
+) -> ConfigTemplateBuilder {
+    if software_rendering && !cfg!(linux) {
+        template.prefer_hardware_accelerated(Some(false))
+    } else {
+        template
+    }
+}
+
+/// Create a GL context for the config, falling back to GLES and then to OpenGL 2.1 if the driver
+/// doesn't support the default OpenGL core context.
+fn create_context(
+    gl_config: &Config,
+    raw_window_handle: Option<RawWindowHandle>,
+) -> Result<NotCurrentContext, glutin::error::Error> {
+    // XXX The display could be obtained from any object created by it, so we can
+    // query it from the config.
+    let gl_display = gl_config.display();
+    // The context creation part.
+    let context_attributes = ContextAttributesBuilder::new().build(raw_window_handle);
+    // Since glutin by default tries to create OpenGL core context, which may not be
+    // present we should try GLES.
+    let fallback_context_attributes = ContextAttributesBuilder::new()
+        .with_context_api(ContextApi::Gles(None))
+        .build(raw_window_handle);
+    // There are also some old devices that support neither modern OpenGL nor GLES.
+    // To support these we can try and create a 2.1 context.
+    let legacy_context_attributes = ContextAttributesBuilder::new()
+        .with_context_api(ContextApi::OpenGl(Some(Version::new(2, 1))))
+        .build(raw_window_handle);
+    unsafe {
+        gl_display
+            .create_context(gl_config, &context_attributes)
+            .or_else(|_| gl_display.create_context(gl_config, &fallback_context_attributes))
+            .or_else(|_| gl_display.create_context(gl_config, &legacy_context_attributes))
+    }
+}
+
+/// Swap interval of the vsync mode, adaptive vsync waits for the vertical blank until a frame
+/// misses it.
+fn swap_interval(vsync: Vsync) -> SwapInterval {
+    match vsync {
+        Vsync::Enabled | Vsync::Adaptive => SwapInterval::Wait(NonZeroU32::MIN),
+        Vsync::Immediate => SwapInterval::DontWait,
+        Vsync::Interval(interval) => {
+            NonZeroU32::new(interval).map_or(SwapInterval::DontWait, SwapInterval::Wait)
+        }
+    }
+}
+
+/// Load the GL functions of the current context.
+fn load_gl(context: &PossiblyCurrentContext) -> Rc<dyn gl::Gl> {
+    let gl_display = context.display();
+    let gl = match context.context_api() {
+        ContextApi::OpenGl(_) => unsafe {
+            gleam::gl::GlFns::load_with(|symbol| {
+                let symbol = CString::new(symbol).unwrap();
+                gl_display.get_proc_address(symbol.as_c_str()) as *const _
+            })
+        },
+        ContextApi::Gles(_) => unsafe {
+            gleam::gl::GlesFns::load_with(|symbol| {
+                let symbol = CString::new(symbol).unwrap();
+                gl_display.get_proc_address(symbol.as_c_str()) as *const _
+            })
+        },
+    };
+
+    println!("Running on {}", gl.get_string(gl::RENDERER));
+    println!("OpenGL Version {}", gl.get_string(gl::VERSION));
+    println!(
+        "Shaders version on {}",
+        gl.get_string(gl::SHADING_LANGUAGE_VERSION)
+    );
+    gl
 }
 
 /// Find the config with the maximum number of samples, so our triangle will be
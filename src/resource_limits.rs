@@ -0,0 +1,82 @@
+use std::collections::HashMap;
+
+use base::id::WebViewId;
+use embedder_traits::{EmbedderMsg, WebResourceResponseMsg};
+use ipc_channel::ipc::IpcSender;
+use versoview_messages::{ResourceLimit, ToControllerMessage};
+use webrender_api::DocumentId;
+use winit::window::WindowId;
+
+use crate::window::Window;
+
+/// Cancel the requests of webviews which sent more than their budget since their document
+/// started loading and return `None`, other messages are given back. Navigations aren't counted,
+/// so a webview over its budget can still leave the page.
+pub(crate) fn apply_request_limit(
+    msg: EmbedderMsg,
+    windows: &mut HashMap<WindowId, (Window, DocumentId)>,
+    to_controller_sender: Option<&IpcSender<ToControllerMessage>>,
+) -> Option<EmbedderMsg> {
+    let EmbedderMsg::WebResourceRequested(Some(webview_id), ref request, ref sender) = msg else {
+        return Some(msg);
+    };
+    let Some((window, _)) = windows
+        .values_mut()
+        .find(|(window, _)| window.tab_manager.tab(webview_id).is_some())
+    else {
+        return Some(msg);
+    };
+    let Some(tab) = window.tab_manager.tab_mut(webview_id) else {
+        return Some(msg);
+    };
+    if request.is_for_main_frame {
+        if !request.is_redirect {
+            tab.reset_request_count();
+        }
+        return Some(msg);
+    }
+    let Some(max_requests) = tab.resource_limits().max_requests else {
+        return Some(msg);
+    };
+
+    let count = tab.count_request();
+    if count <= max_requests {
+        return Some(msg);
+    }
+    log::warn!(
+        "Verso cancelled request to {}, webview {webview_id:?} exceeded its request limit",
+        request.url
+    );
+    let _ = sender.send(WebResourceResponseMsg::CancelLoad);
+    // Only the first cancelled request of the document is reported
+    if count == max_requests + 1 {
+        notify_limit_exceeded(
+            window,
+            to_controller_sender,
+            webview_id,
+            ResourceLimit::Requests,
+        );
+    }
+    None
+}
+
+/// Tell the controller that the webview exceeded one of its budgets.
+pub(crate) fn notify_limit_exceeded(
+    window: &Window,
+    to_controller_sender: Option<&IpcSender<ToControllerMessage>>,
+    webview_id: WebViewId,
+    limit: ResourceLimit,
+) {
+    let Some(to_controller_sender) = to_controller_sender else {
+        return;
+    };
+    if !window.event_listeners.on_resource_limit_exceeded {
+        return;
+    }
+    if let Err(error) = to_controller_sender.send(ToControllerMessage::OnResourceLimitExceeded(
+        bincode::serialize(&webview_id).unwrap(),
+        limit,
+    )) {
+        log::error!("Verso failed to send OnResourceLimitExceeded to controller: {error}")
+    }
+}
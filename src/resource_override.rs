@@ -0,0 +1,150 @@
+use std::{collections::HashMap, fs, path::Path};
+
+use embedder_traits::{EmbedderMsg, WebResourceResponse, WebResourceResponseMsg};
+use http::{HeaderMap, HeaderValue, StatusCode, header::CONTENT_TYPE};
+use versoview_messages::{ResourceOverride, ResourceOverrideSource};
+use webrender_api::DocumentId;
+use winit::window::WindowId;
+
+use crate::window::Window;
+
+/// Serves requests of specific URLs from local files or in-memory bytes instead of the network,
+/// like the local overrides of browser devtools.
+#[derive(Default)]
+pub struct ResourceOverrides {
+    /// Overrides by URL, the fragment is always stripped.
+    overrides: HashMap<url::Url, ResourceOverride>,
+}
+
+impl ResourceOverrides {
+    /// Create a new `ResourceOverrides`.
+    pub fn new() -> Self {
+        Self {
+            overrides: HashMap::new(),
+        }
+    }
+
+    /// Add an override, replacing the existing one of the same URL.
+    pub fn set(&mut self, resource_override: ResourceOverride) {
+        self.overrides
+            .insert(strip_fragment(&resource_override.url), resource_override);
+    }
+
+    /// Remove the override of this URL, returns `false` if there isn't one.
+    pub fn remove(&mut self, url: &url::Url) -> bool {
+        self.overrides.remove(&strip_fragment(url)).is_some()
+    }
+
+    /// Get the override of this URL.
+    pub fn get(&self, url: &url::Url) -> Option<&ResourceOverride> {
+        self.overrides.get(&strip_fragment(url))
+    }
+
+    /// Answer a resource request if its URL is overridden and return `None`, other messages are
+    /// given back.
+    pub(crate) fn handle_request(&self, msg: EmbedderMsg) -> Option<EmbedderMsg> {
+        let EmbedderMsg::WebResourceRequested(_, ref request, ref sender) = msg else {
+            return Some(msg);
+        };
+        let Some(resource_override) = self.get(&request.url) else {
+            return Some(msg);
+        };
+
+        let body = match &resource_override.source {
+            ResourceOverrideSource::File(path) => match fs::read(path) {
+                Ok(body) => body,
+                Err(error) => {
+                    log::error!(
+                        "Verso failed to read override file {} of {}: {error}",
+                        path.display(),
+                        request.url
+                    );
+                    let _ = sender.send(WebResourceResponseMsg::DoNotIntercept);
+                    return None;
+                }
+            },
+            ResourceOverrideSource::Bytes(bytes) => bytes.clone(),
+        };
+
+        let content_type =
+            resource_override
+                .content_type
+                .clone()
+                .or_else(|| match &resource_override.source {
+                    ResourceOverrideSource::File(path) => Some(guess_content_type(path).to_owned()),
+                    ResourceOverrideSource::Bytes(_) => None,
+                });
+        let mut headers = HeaderMap::new();
+        if let Some(content_type) = content_type.and_then(|c| HeaderValue::from_str(&c).ok()) {
+            headers.insert(CONTENT_TYPE, content_type);
+        }
+
+        log::debug!("Verso is serving {} from override", request.url);
+        let _ = sender
+            .send(WebResourceResponseMsg::Start(
+                WebResourceResponse::new(request.url.clone())
+                    .headers(headers)
+                    .status_code(StatusCode::OK),
+            ))
+            .and_then(|_| sender.send(WebResourceResponseMsg::SendBodyData(body)))
+            .and_then(|_| sender.send(WebResourceResponseMsg::FinishLoad));
+        None
+    }
+}
+
+/// In mock network mode, network requests must be answered by the controller. Requests which
+/// can't be sent to the controller are cancelled here and `None` is returned, other messages are
+/// given back.
+pub(crate) fn apply_mock_network(
+    msg: EmbedderMsg,
+    windows: &HashMap<WindowId, (Window, DocumentId)>,
+) -> Option<EmbedderMsg> {
+    let EmbedderMsg::WebResourceRequested(webview_id, ref request, ref sender) = msg else {
+        return Some(msg);
+    };
+    if !matches!(request.url.scheme(), "http" | "https" | "ws" | "wss") {
+        return Some(msg);
+    }
+
+    let listened = webview_id
+        .and_then(|webview_id| {
+            windows
+                .values()
+                .find(|(window, _)| window.has_webview(webview_id))
+        })
+        .is_some_and(|(window, _)| window.event_listeners.on_web_resource_requested.is_some());
+    if listened {
+        return Some(msg);
+    }
+
+    log::warn!(
+        "Verso cancelled request to {} in mock network mode, nothing is listening to it",
+        request.url
+    );
+    let _ = sender.send(WebResourceResponseMsg::CancelLoad);
+    None
+}
+
+fn strip_fragment(url: &url::Url) -> url::Url {
+    let mut url = url.clone();
+    url.set_fragment(None);
+    url
+}
+
+/// Guess the content type of a file from its extension, for overrides and dropped files.
+pub(crate) fn guess_content_type(path: &Path) -> &'static str {
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("html" | "htm") => "text/html",
+        Some("css") => "text/css",
+        Some("js" | "mjs") => "text/javascript",
+        Some("json") => "application/json",
+        Some("svg") => "image/svg+xml",
+        Some("png") => "image/png",
+        Some("jpg" | "jpeg") => "image/jpeg",
+        Some("gif") => "image/gif",
+        Some("webp") => "image/webp",
+        Some("wasm") => "application/wasm",
+        Some("txt") => "text/plain",
+        _ => "application/octet-stream",
+    }
+}
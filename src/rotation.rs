@@ -0,0 +1,113 @@
+use euclid::{Point2D, Size2D, Transform2D};
+use versoview_messages::Rotation;
+
+/// Size of the content shown on an output of this size, or of the output showing content of this
+/// size, since quarter turns swap the width and the height either way.
+pub(crate) fn rotate_size<T, U>(rotation: Rotation, size: Size2D<T, U>) -> Size2D<T, U> {
+    match rotation {
+        Rotation::Rotate90 | Rotation::Rotate270 => Size2D::new(size.height, size.width),
+        Rotation::Normal | Rotation::Rotate180 => size,
+    }
+}
+
+/// Transform drawing content of this size onto the output, turned clockwise by the rotation.
+pub(crate) fn content_to_output<Src, Dst>(
+    rotation: Rotation,
+    content_size: Size2D<f32, Src>,
+) -> Transform2D<f32, Src, Dst> {
+    let (width, height) = (content_size.width, content_size.height);
+    match rotation {
+        Rotation::Normal => Transform2D::identity(),
+        Rotation::Rotate90 => Transform2D::new(0., 1., -1., 0., height, 0.),
+        Rotation::Rotate180 => Transform2D::new(-1., 0., 0., -1., width, height),
+        Rotation::Rotate270 => Transform2D::new(0., -1., 1., 0., 0., width),
+    }
+}
+
+/// Point of the content under a point of the output of this size, e.g. under the cursor or a
+/// touch.
+pub(crate) fn output_to_content<U>(
+    rotation: Rotation,
+    point: Point2D<f32, U>,
+    output_size: Size2D<f32, U>,
+) -> Point2D<f32, U> {
+    let content_size = rotate_size(rotation, output_size);
+    content_to_output::<U, U>(rotation, content_size)
+        .inverse()
+        .map_or(point, |transform| transform.transform_point(point))
+}
+
+#[cfg(test)]
+mod tests {
+    use euclid::UnknownUnit;
+
+    use super::*;
+
+    const ROTATIONS: [Rotation; 4] = [
+        Rotation::Normal,
+        Rotation::Rotate90,
+        Rotation::Rotate180,
+        Rotation::Rotate270,
+    ];
+
+    fn output_size() -> Size2D<f32, UnknownUnit> {
+        Size2D::new(200., 100.)
+    }
+
+    #[test]
+    fn test_rotate_size() {
+        assert_eq!(
+            rotate_size(Rotation::Rotate90, output_size()),
+            Size2D::new(100., 200.)
+        );
+        assert_eq!(
+            rotate_size(Rotation::Rotate270, output_size()),
+            Size2D::new(100., 200.)
+        );
+        assert_eq!(
+            rotate_size(Rotation::Rotate180, output_size()),
+            output_size()
+        );
+    }
+
+    #[test]
+    fn test_output_to_content() {
+        let point = |x, y| Point2D::<f32, UnknownUnit>::new(x, y);
+        // The top left corner of the content is at the top right of the output turned clockwise
+        let cases = [
+            (Rotation::Normal, point(10., 20.), point(10., 20.)),
+            (Rotation::Rotate90, point(190., 10.), point(10., 10.)),
+            (Rotation::Rotate180, point(10., 20.), point(190., 80.)),
+            (Rotation::Rotate270, point(10., 90.), point(10., 10.)),
+        ];
+        for (rotation, output_point, content_point) in cases {
+            assert_eq!(
+                output_to_content(rotation, output_point, output_size()),
+                content_point,
+                "{rotation:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_content_to_output_round_trip() {
+        for rotation in ROTATIONS {
+            let content_size = rotate_size(rotation, output_size());
+            let transform = content_to_output::<UnknownUnit, UnknownUnit>(rotation, content_size);
+            for content_point in [
+                Point2D::new(0., 0.),
+                Point2D::new(content_size.width, content_size.height),
+                Point2D::new(30., 40.),
+            ] {
+                let output_point = transform.transform_point(content_point);
+                assert!(output_point.x >= 0. && output_point.x <= output_size().width);
+                assert!(output_point.y >= 0. && output_point.y <= output_size().height);
+                assert_eq!(
+                    output_to_content(rotation, output_point, output_size()),
+                    content_point,
+                    "{rotation:?}"
+                );
+            }
+        }
+    }
+}
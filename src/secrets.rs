@@ -0,0 +1,115 @@
+use keyring::Entry;
+
+use crate::errors::Result;
+
+/// Service name of the secrets saved by Verso in the OS keychain.
+const SERVICE_NAME: &str = "org.versotile.verso";
+
+/// Script which exposes the secret store to documents of the origins allowed by
+/// [`crate::config::Config::secret_bridge_origins`] as `window.verso.secrets`, including frames.
+///
+/// User scripts run in every frame, and Verso can't tell which frame a prompt comes from, so
+/// requests carry a token of the session and the origin of their document. The token is only
+/// reachable from the closure of the script, which isn't installed in other origins, and
+/// `prompt` and `JSON.stringify` are captured before the scripts of the document run.
+const SECRETS_BRIDGE_SCRIPT: &str = r#"
+(() => {
+  const token = SECRETS_BRIDGE_TOKEN;
+  const origins = SECRETS_BRIDGE_ORIGINS;
+  const origin = location.origin;
+  if (!origins.includes(origin)) {
+    return;
+  }
+  const prompt = window.prompt.bind(window);
+  const stringify = JSON.stringify;
+  const request = (operation, key, value) =>
+    prompt(`VERSO::SECRET_${operation}::${stringify({ token, origin, key, value })}`);
+  window.verso = window.verso || {};
+  window.verso.secrets = Object.freeze({
+    get: (key) => request('GET', key),
+    set: (key, value) => request('SET', key, value) !== null,
+    delete: (key) => request('DELETE', key) !== null,
+  });
+})();
+"#;
+
+/// The secrets bridge script for the token of the session and the allowed origins.
+pub(crate) fn bridge_script(token: &str, origins: &[url::Origin]) -> String {
+    let origins: Vec<_> = origins
+        .iter()
+        .map(|origin| origin.ascii_serialization())
+        .collect();
+    SECRETS_BRIDGE_SCRIPT
+        .replace(
+            "SECRETS_BRIDGE_TOKEN",
+            &serde_json::Value::from(token).to_string(),
+        )
+        .replace(
+            "SECRETS_BRIDGE_ORIGINS",
+            &serde_json::Value::from(origins).to_string(),
+        )
+}
+
+/// Stores secrets such as tokens in the OS keychain: the Secret Service (libsecret) on Linux,
+/// the Keychain on macOS and the Credential Manager (DPAPI) on Windows.
+///
+/// Secrets are grouped by a namespace, e.g. the origin of the page which saves them, so a
+/// namespace can't read the secrets of others.
+pub struct SecretStore {
+    service: String,
+}
+
+impl Default for SecretStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SecretStore {
+    /// Create a secret store under Verso's service name.
+    pub fn new() -> Self {
+        Self {
+            service: SERVICE_NAME.to_string(),
+        }
+    }
+
+    fn entry(&self, namespace: &str, key: &str) -> Result<Entry> {
+        Ok(Entry::new(&self.service, &format!("{namespace}|{key}"))?)
+    }
+
+    /// Save a secret, replacing the existing one of the same key.
+    pub fn set(&self, namespace: &str, key: &str, secret: &str) -> Result<()> {
+        Ok(self.entry(namespace, key)?.set_password(secret)?)
+    }
+
+    /// Get a secret, returns `None` if it doesn't exist.
+    pub fn get(&self, namespace: &str, key: &str) -> Result<Option<String>> {
+        match self.entry(namespace, key)?.get_password() {
+            Ok(secret) => Ok(Some(secret)),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Delete a secret, returns `false` if it doesn't exist.
+    pub fn delete(&self, namespace: &str, key: &str) -> Result<bool> {
+        match self.entry(namespace, key)?.delete_credential() {
+            Ok(()) => Ok(true),
+            Err(keyring::Error::NoEntry) => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+/// Request of the secrets bridge, see [`SECRETS_BRIDGE_SCRIPT`].
+#[derive(Debug, serde::Deserialize)]
+pub(crate) struct SecretRequest {
+    /// Token of the session, only known by the bridge script.
+    pub token: String,
+    /// Origin of the document which sent the request.
+    pub origin: String,
+    pub key: String,
+    /// Secret to save, for requests to save one.
+    #[serde(default)]
+    pub value: Option<String>,
+}
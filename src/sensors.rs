@@ -0,0 +1,354 @@
+use std::{thread, time::Duration};
+
+use ipc_channel::ipc::IpcSender;
+use serde::{Deserialize, Serialize};
+
+use crate::verso::VersoInternalMsg;
+
+/// How often the sensors are read.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Standard gravity in m/s².
+#[cfg(any(linux, windows))]
+const GRAVITY: f64 = 9.80665;
+
+/// Readings of the motion and light sensors of the device, in the units of the
+/// [Generic Sensor API](https://www.w3.org/TR/generic-sensor/). `None` if the device doesn't have
+/// the sensor.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SensorReading {
+    /// Acceleration including gravity in m/s² along the x, y and z axes of the device.
+    pub acceleration: Option<[f64; 3]>,
+    /// Angular velocity in rad/s around the x, y and z axes of the device.
+    pub angular_velocity: Option<[f64; 3]>,
+    /// Ambient light level in lux.
+    pub illuminance: Option<f64>,
+}
+
+/// Spawn a thread reading the sensors, and send [`VersoInternalMsg::SensorReadingChanged`]
+/// whenever the readings change.
+pub(crate) fn watch_sensors(sender: IpcSender<VersoInternalMsg>) {
+    let result = thread::Builder::new()
+        .name("SensorWatcher".to_owned())
+        .spawn(move || {
+            let Some(sensors) = platform::Sensors::open() else {
+                log::warn!("Verso found no sensors on this device");
+                return;
+            };
+            let mut reading = SensorReading::default();
+            loop {
+                let new_reading = sensors.read();
+                if new_reading != reading {
+                    reading = new_reading;
+                    if let Err(e) = sender.send(VersoInternalMsg::SensorReadingChanged(reading)) {
+                        log::error!("Sensor watcher failed to notify Verso, stop watching: {e}");
+                        break;
+                    }
+                }
+                thread::sleep(POLL_INTERVAL);
+            }
+        });
+    if let Err(e) = result {
+        log::error!("Failed to spawn sensor watcher thread: {e}");
+    }
+}
+
+/// Script which implements `Accelerometer`, `Gyroscope`, `AmbientLightSensor` and the
+/// `devicemotion` and `deviceorientation` events. The first reading comes from the
+/// `SENSOR_READING` request of the Verso bridge, then Verso pushes the changes to the visible tabs
+/// with [`sensor_reading_script`].
+pub(crate) const SENSORS_SCRIPT: &str = r#"
+(() => {
+  if (location.protocol === 'verso:' || !window.isSecureContext) {
+    return;
+  }
+
+  let reading = null;
+  const sensors = new Set();
+  const currentReading = () => {
+    if (reading === null) {
+      const response = prompt('VERSO::SENSOR_READING');
+      reading = response ? JSON.parse(response) : {};
+    }
+    return reading;
+  };
+
+  class Sensor extends EventTarget {
+    #activated = false;
+    #values = null;
+    #timestamp = null;
+    onreading = null;
+    onactivate = null;
+    onerror = null;
+
+    get activated() {
+      return this.#activated;
+    }
+    get hasReading() {
+      return this.#values !== null;
+    }
+    get timestamp() {
+      return this.#timestamp;
+    }
+    _value(index) {
+      return this.#values ? this.#values[index] : null;
+    }
+
+    #dispatch(type, event = new Event(type)) {
+      this[`on${type}`]?.(event);
+      this.dispatchEvent(event);
+    }
+
+    start() {
+      if (this.#activated) {
+        return;
+      }
+      if (this.constructor._read(currentReading()) == null) {
+        const error = new Event('error');
+        error.error = new DOMException('The sensor is not available', 'NotReadableError');
+        this.#dispatch('error', error);
+        return;
+      }
+      this.#activated = true;
+      sensors.add(this);
+      this.#dispatch('activate');
+      this._update(currentReading());
+    }
+
+    stop() {
+      this.#activated = false;
+      this.#values = null;
+      this.#timestamp = null;
+      sensors.delete(this);
+    }
+
+    _update(reading) {
+      const values = this.constructor._read(reading);
+      if (values == null) {
+        return;
+      }
+      this.#values = Array.isArray(values) ? values : [values];
+      this.#timestamp = performance.now();
+      this.#dispatch('reading');
+    }
+  }
+
+  class Accelerometer extends Sensor {
+    static _read(reading) {
+      return reading.acceleration;
+    }
+    get x() {
+      return this._value(0);
+    }
+    get y() {
+      return this._value(1);
+    }
+    get z() {
+      return this._value(2);
+    }
+  }
+
+  class Gyroscope extends Accelerometer {
+    static _read(reading) {
+      return reading.angularVelocity;
+    }
+  }
+
+  class AmbientLightSensor extends Sensor {
+    static _read(reading) {
+      return reading.illuminance;
+    }
+    get illuminance() {
+      return this._value(0);
+    }
+  }
+
+  const degrees = (radians) => (radians * 180) / Math.PI;
+  const dispatchDeviceEvents = (reading) => {
+    if (reading.acceleration) {
+      const [x, y, z] = reading.acceleration;
+      const motion = new Event('devicemotion');
+      const rotation = reading.angularVelocity?.map(degrees);
+      Object.assign(motion, {
+        acceleration: null,
+        accelerationIncludingGravity: { x, y, z },
+        rotationRate: rotation ? { alpha: rotation[2], beta: rotation[0], gamma: rotation[1] } : null,
+        interval: 100,
+      });
+      window.ondevicemotion?.(motion);
+      window.dispatchEvent(motion);
+
+      // Without a compass, only the tilt of the device is known
+      const orientation = new Event('deviceorientation');
+      Object.assign(orientation, {
+        absolute: false,
+        alpha: null,
+        beta: degrees(Math.atan2(y, z)),
+        gamma: degrees(Math.atan2(-x, Math.hypot(y, z))),
+      });
+      window.ondeviceorientation?.(orientation);
+      window.dispatchEvent(orientation);
+    }
+  };
+
+  Object.assign(window, { Accelerometer, Gyroscope, AmbientLightSensor });
+  Object.defineProperty(window, '__versoSensors', {
+    value: (newReading) => {
+      reading = newReading;
+      for (const sensor of sensors) {
+        sensor._update(reading);
+      }
+      dispatchDeviceEvents(reading);
+    },
+  });
+})();
+"#;
+
+/// Script to push new sensor readings to a document.
+pub(crate) fn sensor_reading_script(reading: &SensorReading) -> String {
+    let reading = serde_json::to_string(reading).unwrap_or_else(|_| "{}".to_string());
+    format!("window.__versoSensors?.({reading})")
+}
+
+#[cfg(linux)]
+mod platform {
+    use zbus::blocking::{Connection, Proxy};
+
+    use super::{GRAVITY, SensorReading};
+
+    /// Sensors of iio-sensor-proxy. It only reports the orientation of the device, which is turned
+    /// into the gravity read by an accelerometer at rest.
+    pub struct Sensors {
+        proxy: Proxy<'static>,
+        has_accelerometer: bool,
+        has_ambient_light: bool,
+    }
+
+    impl Sensors {
+        pub fn open() -> Option<Self> {
+            let connection = Connection::system().ok()?;
+            let proxy = Proxy::new_owned(
+                connection,
+                "net.hadess.SensorProxy",
+                "/net/hadess/SensorProxy",
+                "net.hadess.SensorProxy",
+            )
+            .ok()?;
+            // Readings are only updated while they are claimed by a connection
+            let has_accelerometer = proxy.get_property::<bool>("HasAccelerometer").ok()?
+                && proxy.call_method("ClaimAccelerometer", &()).is_ok();
+            let has_ambient_light = proxy.get_property::<bool>("HasAmbientLight").ok()?
+                && proxy.call_method("ClaimLight", &()).is_ok();
+            (has_accelerometer || has_ambient_light).then_some(Self {
+                proxy,
+                has_accelerometer,
+                has_ambient_light,
+            })
+        }
+
+        pub fn read(&self) -> SensorReading {
+            let acceleration = self
+                .has_accelerometer
+                .then(|| {
+                    self.proxy
+                        .get_property::<String>("AccelerometerOrientation")
+                })
+                .and_then(Result::ok)
+                .and_then(|orientation| match orientation.as_str() {
+                    "normal" => Some([0.0, GRAVITY, 0.0]),
+                    "bottom-up" => Some([0.0, -GRAVITY, 0.0]),
+                    "left-up" => Some([-GRAVITY, 0.0, 0.0]),
+                    "right-up" => Some([GRAVITY, 0.0, 0.0]),
+                    _ => None,
+                });
+            // Some devices only report a vendor-specific level which isn't in lux
+            let illuminance = self
+                .has_ambient_light
+                .then(|| self.proxy.get_property::<String>("LightLevelUnit"))
+                .and_then(Result::ok)
+                .filter(|unit| unit == "lux")
+                .and_then(|_| self.proxy.get_property::<f64>("LightLevel").ok());
+            SensorReading {
+                acceleration,
+                angular_velocity: None,
+                illuminance,
+            }
+        }
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use windows::Devices::Sensors::{Accelerometer, Gyrometer, LightSensor};
+
+    use super::{GRAVITY, SensorReading};
+
+    /// Sensors of Windows.Devices.Sensors.
+    pub struct Sensors {
+        accelerometer: Option<Accelerometer>,
+        gyrometer: Option<Gyrometer>,
+        light_sensor: Option<LightSensor>,
+    }
+
+    impl Sensors {
+        pub fn open() -> Option<Self> {
+            let sensors = Self {
+                accelerometer: Accelerometer::GetDefault().ok(),
+                gyrometer: Gyrometer::GetDefault().ok(),
+                light_sensor: LightSensor::GetDefault().ok(),
+            };
+            (sensors.accelerometer.is_some()
+                || sensors.gyrometer.is_some()
+                || sensors.light_sensor.is_some())
+            .then_some(sensors)
+        }
+
+        pub fn read(&self) -> SensorReading {
+            // Windows reports the acceleration in g towards the ground, the opposite of the
+            // Generic Sensor API
+            let acceleration = self.accelerometer.as_ref().and_then(|accelerometer| {
+                let reading = accelerometer.GetCurrentReading().ok()?;
+                Some([
+                    -reading.AccelerationX().ok()? * GRAVITY,
+                    -reading.AccelerationY().ok()? * GRAVITY,
+                    -reading.AccelerationZ().ok()? * GRAVITY,
+                ])
+            });
+            let angular_velocity = self.gyrometer.as_ref().and_then(|gyrometer| {
+                let reading = gyrometer.GetCurrentReading().ok()?;
+                Some([
+                    reading.AngularVelocityX().ok()?.to_radians(),
+                    reading.AngularVelocityY().ok()?.to_radians(),
+                    reading.AngularVelocityZ().ok()?.to_radians(),
+                ])
+            });
+            let illuminance = self.light_sensor.as_ref().and_then(|light_sensor| {
+                let reading = light_sensor.GetCurrentReading().ok()?;
+                Some(reading.IlluminanceInLux().ok()?.into())
+            });
+            SensorReading {
+                acceleration,
+                angular_velocity,
+                illuminance,
+            }
+        }
+    }
+}
+
+#[cfg(not(any(linux, windows)))]
+mod platform {
+    use super::SensorReading;
+
+    pub struct Sensors;
+
+    impl Sensors {
+        pub fn open() -> Option<Self> {
+            None
+        }
+
+        pub fn read(&self) -> SensorReading {
+            SensorReading::default()
+        }
+    }
+}
@@ -0,0 +1,201 @@
+use std::{fs::File, path::PathBuf, thread, time::Duration};
+
+use base::id::WebViewId;
+use ipc_channel::ipc::IpcSender;
+use serde::{Deserialize, Serialize};
+
+use crate::verso::VersoInternalMsg;
+
+/// A named collection of webviews which can be collapsed and restored together.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TabGroup {
+    /// The ID of the tab group.
+    pub id: uuid::Uuid,
+    /// The name of the tab group.
+    pub name: String,
+    /// Whether the tabs of this group are closed and only kept as URLs.
+    pub collapsed: bool,
+    /// The URLs of the tabs in this group, updated when the group is collapsed or saved.
+    pub urls: Vec<String>,
+    /// The webviews currently opened for this group.
+    #[serde(skip)]
+    pub webview_ids: Vec<WebViewId>,
+}
+
+/// A tab of a window saved in the session.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionTab {
+    /// The URL loaded in the tab.
+    pub url: String,
+    /// Horizontal scroll position of the page, in CSS pixels.
+    #[serde(default)]
+    pub scroll_x: f32,
+    /// Vertical scroll position of the page, in CSS pixels.
+    #[serde(default)]
+    pub scroll_y: f32,
+    /// Name given to the tab.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
+
+/// A window open when the session was saved.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionWindow {
+    /// The tabs of the window, in the order of the tab strip.
+    pub tabs: Vec<SessionTab>,
+    /// Index of the active tab in `tabs`.
+    #[serde(default)]
+    pub active_tab: usize,
+    /// Name given to the window.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
+
+/// Everything persisted in the session store.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Session {
+    /// Tab groups of the session.
+    #[serde(default)]
+    pub tab_groups: Vec<TabGroup>,
+    /// Windows open when the session was saved.
+    #[serde(default)]
+    pub windows: Vec<SessionWindow>,
+}
+
+/// A struct managing the tab groups of this Verso instance.
+#[derive(Default)]
+pub struct SessionManager {
+    /// A vector of tab groups.
+    tab_groups: Vec<TabGroup>,
+    /// Windows open when the session was last saved.
+    windows: Vec<SessionWindow>,
+}
+
+impl SessionManager {
+    /// Creates a new `SessionManager`.
+    pub fn new() -> Self {
+        Self {
+            tab_groups: Vec::new(),
+            windows: Vec::new(),
+        }
+    }
+
+    /// Restore the state from a saved session.
+    /// Webviews don't survive a restart, so every tab group starts collapsed.
+    pub fn set_session(&mut self, session: Session) {
+        self.tab_groups = session.tab_groups;
+        self.windows = session.windows;
+        for group in &mut self.tab_groups {
+            group.collapsed = true;
+            group.webview_ids.clear();
+        }
+    }
+
+    /// Get the current state to be saved.
+    pub fn session(&self) -> Session {
+        Session {
+            tab_groups: self.tab_groups.clone(),
+            windows: self.windows.clone(),
+        }
+    }
+
+    /// Set the windows which are open, to be saved.
+    pub fn set_windows(&mut self, windows: Vec<SessionWindow>) {
+        self.windows = windows;
+    }
+
+    /// Creates a tab group from already opened webviews.
+    pub fn create_tab_group(&mut self, id: uuid::Uuid, name: String, webview_ids: Vec<WebViewId>) {
+        // A webview only belongs to one group at a time
+        for group in &mut self.tab_groups {
+            group
+                .webview_ids
+                .retain(|webview_id| !webview_ids.contains(webview_id));
+        }
+        self.tab_groups.push(TabGroup {
+            id,
+            name,
+            collapsed: false,
+            urls: Vec::new(),
+            webview_ids,
+        });
+    }
+
+    /// Removes a tab group, its webviews are kept opened.
+    pub fn remove_tab_group(&mut self, id: uuid::Uuid) -> Result<TabGroup, String> {
+        if let Some(pos) = self.tab_groups.iter().position(|group| group.id == id) {
+            Ok(self.tab_groups.remove(pos))
+        } else {
+            Err(format!("Tab group with ID {id} not found"))
+        }
+    }
+
+    /// Gets a tab group by its ID.
+    pub fn tab_group(&self, id: uuid::Uuid) -> Option<&TabGroup> {
+        self.tab_groups.iter().find(|group| group.id == id)
+    }
+
+    /// Gets a tab group by its ID as mutable.
+    pub fn tab_group_mut(&mut self, id: uuid::Uuid) -> Option<&mut TabGroup> {
+        self.tab_groups.iter_mut().find(|group| group.id == id)
+    }
+
+    /// Gets all tab groups.
+    pub fn tab_groups(&self) -> &Vec<TabGroup> {
+        &self.tab_groups
+    }
+
+    /// Gets all tab groups as mutable.
+    pub fn tab_groups_mut(&mut self) -> &mut Vec<TabGroup> {
+        &mut self.tab_groups
+    }
+}
+
+/// Spawn a thread sending [`VersoInternalMsg::SaveSession`] every `interval`, so open windows
+/// and tabs are recovered even if Verso doesn't shut down cleanly.
+pub(crate) fn autosave(interval: Duration, sender: IpcSender<VersoInternalMsg>) {
+    let result = thread::Builder::new()
+        .name("SessionAutosave".to_owned())
+        .spawn(move || {
+            loop {
+                thread::sleep(interval);
+                if sender.send(VersoInternalMsg::SaveSession).is_err() {
+                    break;
+                }
+            }
+        });
+    if let Err(e) = result {
+        log::error!("Failed to spawn session autosave thread: {e}");
+    }
+}
+
+pub(crate) struct SessionStorage {
+    config_dir_path: PathBuf,
+}
+
+impl SessionStorage {
+    /// Create a new `SessionStorage`.
+    pub fn new(config_dir_path: PathBuf) -> Self {
+        Self { config_dir_path }
+    }
+
+    fn session_file_path(&self) -> PathBuf {
+        self.config_dir_path.join("session.json")
+    }
+
+    /// Load session from disk.
+    pub fn load_from_file(&self) -> Result<Session, std::io::Error> {
+        let file = File::open(self.session_file_path())?;
+        let session: Session = serde_json::from_reader(file)?;
+        Ok(session)
+    }
+
+    /// Save session to disk.
+    pub fn save_to_file(&self, session: &Session) -> Result<(), std::io::Error> {
+        let file = File::create(self.session_file_path())?;
+        serde_json::to_writer(file, session)?;
+        Ok(())
+    }
+}
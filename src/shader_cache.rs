@@ -0,0 +1,90 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    rc::Rc,
+    sync::Arc,
+};
+
+use webrender::{ProgramBinary, ProgramCache, ProgramCacheObserver, ProgramSourceDigest};
+
+/// Disk cache of the shader programs compiled by the GL driver for Webrender, so they aren't
+/// compiled again on the next startup. Each program is a file named after the digest of its
+/// source, programs which the driver rejects, e.g. after it's updated, are removed.
+pub(crate) struct ShaderCache {
+    directory: PathBuf,
+}
+
+impl ShaderCache {
+    /// Create the program cache of Webrender backed by this directory, `None` if the directory
+    /// can't be created.
+    pub fn create(directory: PathBuf) -> Option<Rc<ProgramCache>> {
+        if let Err(e) = fs::create_dir_all(&directory) {
+            log::error!(
+                "Failed to create shader cache directory {}: {e}",
+                directory.display()
+            );
+            return None;
+        }
+        Some(ProgramCache::new(Some(Box::new(Self { directory }))))
+    }
+
+    fn program_path(&self, digest: &ProgramSourceDigest) -> PathBuf {
+        self.directory.join(format!("{digest}.bin"))
+    }
+}
+
+impl ProgramCacheObserver for ShaderCache {
+    fn save_shaders_to_disk(&self, entries: Vec<Arc<ProgramBinary>>) {
+        for entry in entries {
+            let path = self.program_path(entry.source_digest());
+            if path.exists() {
+                continue;
+            }
+            let result = bincode::serialize(&*entry)
+                .map_err(|e| e.to_string())
+                .and_then(|bytes| write_atomically(&path, &bytes).map_err(|e| e.to_string()));
+            if let Err(e) = result {
+                log::warn!("Failed to save shader program {}: {e}", path.display());
+            }
+        }
+    }
+
+    fn set_startup_shaders(&self, _entries: Vec<Arc<ProgramBinary>>) {
+        // Programs are loaded from the disk when Webrender asks for them, so there is no list
+        // of programs to load on startup
+    }
+
+    fn try_load_shader_from_disk(
+        &self,
+        digest: &ProgramSourceDigest,
+        program_cache: &Rc<ProgramCache>,
+    ) {
+        let path = self.program_path(digest);
+        let Ok(bytes) = fs::read(&path) else {
+            return;
+        };
+        match bincode::deserialize::<ProgramBinary>(&bytes) {
+            Ok(binary) => program_cache.load_program_binary(Arc::new(binary)),
+            Err(e) => {
+                log::warn!("Removing invalid shader program {}: {e}", path.display());
+                let _ = fs::remove_file(&path);
+            }
+        }
+    }
+
+    fn notify_program_binary_failed(&self, program_binary: &Arc<ProgramBinary>) {
+        let path = self.program_path(program_binary.source_digest());
+        log::warn!(
+            "The GL driver rejected shader program {}, removing it",
+            path.display()
+        );
+        let _ = fs::remove_file(&path);
+    }
+}
+
+/// Write the file through a temporary one, so a crash can't leave a truncated program behind.
+fn write_atomically(path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    let temporary_path = path.with_extension("tmp");
+    fs::write(&temporary_path, bytes)?;
+    fs::rename(&temporary_path, path)
+}
@@ -0,0 +1,290 @@
+use std::path::PathBuf;
+
+use arboard::Clipboard;
+use base64::{Engine, engine::general_purpose::STANDARD};
+use serde::{Deserialize, Serialize};
+use winit::window::Window;
+
+/// Script which implements `navigator.share` and `navigator.canShare` with the `SHARE` request of
+/// the Verso bridge. Files are read by the page and sent with the request in base64.
+pub(crate) const SHARE_SCRIPT: &str = r#"
+(() => {
+  if (location.protocol === 'verso:' || !window.isSecureContext) {
+    return;
+  }
+
+  const canShare = (data) =>
+    !!data && !!(data.title || data.text || data.url || (data.files && data.files.length));
+
+  const readFile = (file) =>
+    new Promise((resolve, reject) => {
+      const reader = new FileReader();
+      reader.onload = () =>
+        resolve({
+          name: file.name,
+          data: reader.result.slice(reader.result.indexOf(',') + 1),
+        });
+      reader.onerror = () => reject(reader.error);
+      reader.readAsDataURL(file);
+    });
+
+  let sharing = false;
+  Navigator.prototype.canShare = function (data) {
+    return canShare(data);
+  };
+  Navigator.prototype.share = async function (data) {
+    if (!canShare(data)) {
+      throw new TypeError('Nothing to share');
+    }
+    if (sharing) {
+      throw new DOMException('A share is already in progress', 'InvalidStateError');
+    }
+    sharing = true;
+    try {
+      const request = {
+        title: data.title ?? null,
+        text: data.text ?? null,
+        url: data.url ? new URL(data.url, location.href).href : null,
+        files: await Promise.all(Array.from(data.files ?? [], readFile)),
+      };
+      const response = prompt(`VERSO::SHARE::${JSON.stringify(request)}`);
+      if (response === null) {
+        throw new DOMException('Share canceled', 'AbortError');
+      }
+      if (response === 'copied') {
+        alert('Copied to the clipboard');
+      }
+    } finally {
+      sharing = false;
+    }
+  };
+})();
+"#;
+
+/// Data to share from `navigator.share`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShareData {
+    /// Title of the shared content
+    pub title: Option<String>,
+    /// Text to share
+    pub text: Option<String>,
+    /// URL to share
+    pub url: Option<String>,
+    /// Files to share
+    pub files: Vec<SharedFile>,
+}
+
+/// A file to share.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SharedFile {
+    /// Name of the file
+    pub name: String,
+    /// Content of the file in base64
+    pub data: String,
+}
+
+/// How the data was shared.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShareOutcome {
+    /// The share sheet of the system was shown.
+    Shown,
+    /// There's no share sheet, so the text and URL were copied to the clipboard.
+    Copied,
+}
+
+/// Show the share sheet of the system for the window, or copy the data to the clipboard if the
+/// platform doesn't have one.
+pub(crate) fn share(
+    window: &Window,
+    data: &ShareData,
+    clipboard: Option<&mut Clipboard>,
+) -> Result<ShareOutcome, String> {
+    let files = write_files(&data.files)?;
+    if platform::share(window, data, &files)? {
+        return Ok(ShareOutcome::Shown);
+    }
+    if !files.is_empty() {
+        return Err("Sharing files isn't supported on this platform".to_string());
+    }
+
+    let text = [&data.title, &data.text, &data.url]
+        .into_iter()
+        .flatten()
+        .map(String::as_str)
+        .collect::<Vec<_>>()
+        .join("\n");
+    clipboard
+        .ok_or("No clipboard to copy the shared data to")?
+        .set_text(text)
+        .map_err(|e| e.to_string())?;
+    Ok(ShareOutcome::Copied)
+}
+
+/// Write the shared files to the temporary directory, share sheets need them on the disk.
+fn write_files(files: &[SharedFile]) -> Result<Vec<PathBuf>, String> {
+    if files.is_empty() {
+        return Ok(Vec::new());
+    }
+    let directory = std::env::temp_dir()
+        .join("verso-share")
+        .join(uuid::Uuid::new_v4().to_string());
+    std::fs::create_dir_all(&directory).map_err(|e| e.to_string())?;
+    files
+        .iter()
+        .map(|file| {
+            // Pages choose the names, so only their last component is kept
+            let name = std::path::Path::new(&file.name)
+                .file_name()
+                .ok_or_else(|| format!("Invalid file name: {}", file.name))?;
+            let path = directory.join(name);
+            let bytes = STANDARD.decode(&file.data).map_err(|e| e.to_string())?;
+            std::fs::write(&path, bytes).map_err(|e| e.to_string())?;
+            Ok(path)
+        })
+        .collect()
+}
+
+#[cfg(macos)]
+mod platform {
+    use std::path::PathBuf;
+
+    use objc2::{rc::Id, runtime::AnyObject};
+    use objc2_app_kit::{NSSharingServicePicker, NSView};
+    use objc2_foundation::{MainThreadMarker, NSArray, NSRectEdge, NSString, NSURL};
+    use raw_window_handle::{HasWindowHandle, RawWindowHandle};
+    use winit::window::Window;
+
+    use super::ShareData;
+
+    /// Show `NSSharingServicePicker` below the window's view.
+    pub fn share(window: &Window, data: &ShareData, files: &[PathBuf]) -> Result<bool, String> {
+        let mtm = MainThreadMarker::new().ok_or("Sharing must be done on the main thread")?;
+        let handle = window.window_handle().map_err(|e| e.to_string())?;
+        let RawWindowHandle::AppKit(handle) = handle.as_raw() else {
+            return Ok(false);
+        };
+
+        let mut items: Vec<Id<AnyObject>> = Vec::new();
+        unsafe {
+            for text in [&data.title, &data.text].into_iter().flatten() {
+                items.push(Id::cast(NSString::from_str(text)));
+            }
+            if let Some(url) = data
+                .url
+                .as_ref()
+                .and_then(|url| NSURL::URLWithString(&NSString::from_str(url)))
+            {
+                items.push(Id::cast(url));
+            }
+            for file in files {
+                let path = NSString::from_str(&file.to_string_lossy());
+                items.push(Id::cast(NSURL::fileURLWithPath(&path)));
+            }
+
+            let view: Id<NSView> =
+                Id::retain(handle.ns_view.as_ptr().cast()).ok_or("The window has no view")?;
+            let picker =
+                NSSharingServicePicker::initWithItems(mtm.alloc(), &NSArray::from_vec(items));
+            picker.showRelativeToRect_ofView_preferredEdge(
+                view.bounds(),
+                &view,
+                NSRectEdge::NSRectEdgeMinY,
+            );
+        }
+        Ok(true)
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use std::{cell::RefCell, path::PathBuf};
+
+    use raw_window_handle::{HasWindowHandle, RawWindowHandle};
+    use windows::{
+        ApplicationModel::DataTransfer::{DataRequestedEventArgs, DataTransferManager},
+        Foundation::{Collections::IIterable, TypedEventHandler, Uri},
+        Storage::{IStorageItem, StorageFile},
+        Win32::{Foundation::HWND, UI::Shell::IDataTransferManagerInterop},
+        core::{HSTRING, Interface, factory},
+    };
+    use winit::window::Window;
+
+    use super::ShareData;
+
+    thread_local! {
+        /// Removes the data handler of the previous share, so only the latest data is sent.
+        static REMOVE_HANDLER: RefCell<Option<Box<dyn FnOnce()>>> = RefCell::new(None);
+    }
+
+    /// Show the share UI of Windows for the window.
+    pub fn share(window: &Window, data: &ShareData, files: &[PathBuf]) -> Result<bool, String> {
+        let handle = window.window_handle().map_err(|e| e.to_string())?;
+        let RawWindowHandle::Win32(handle) = handle.as_raw() else {
+            return Ok(false);
+        };
+        let hwnd = HWND(handle.hwnd.get() as _);
+
+        let show = || -> windows::core::Result<()> {
+            let interop = factory::<DataTransferManager, IDataTransferManagerInterop>()?;
+            let manager: DataTransferManager = unsafe { interop.GetForWindow(hwnd)? };
+
+            let data = data.clone();
+            let files = files.to_vec();
+            let handler =
+                TypedEventHandler::new(move |_, args: &Option<DataRequestedEventArgs>| {
+                    let Some(args) = args else {
+                        return Ok(());
+                    };
+                    let package = args.Request()?.Data()?;
+                    let title = data.title.as_deref().or(data.url.as_deref()).unwrap_or("");
+                    package.Properties()?.SetTitle(&HSTRING::from(title))?;
+                    if let Some(text) = &data.text {
+                        package.SetText(&HSTRING::from(text))?;
+                    }
+                    if let Some(url) = &data.url {
+                        package.SetWebLink(&Uri::CreateUri(&HSTRING::from(url))?)?;
+                    }
+                    if !files.is_empty() {
+                        let items = files
+                            .iter()
+                            .map(|file| {
+                                StorageFile::GetFileFromPathAsync(&HSTRING::from(file.as_path()))?
+                                    .get()?
+                                    .cast::<IStorageItem>()
+                                    .map(Some)
+                            })
+                            .collect::<windows::core::Result<Vec<_>>>()?;
+                        package.SetStorageItemsReadOnly(&IIterable::try_from(items)?)?;
+                    }
+                    Ok(())
+                });
+            let token = manager.DataRequested(&handler)?;
+            let previous = REMOVE_HANDLER.take();
+            if let Some(remove) = previous {
+                remove();
+            }
+            let manager_clone = manager.clone();
+            REMOVE_HANDLER.set(Some(Box::new(move || {
+                let _ = manager_clone.RemoveDataRequested(token);
+            })));
+
+            unsafe { interop.ShowShareUIForWindow(hwnd) }
+        };
+        show().map_err(|e| e.to_string())?;
+        Ok(true)
+    }
+}
+
+#[cfg(not(any(macos, windows)))]
+mod platform {
+    use std::path::PathBuf;
+
+    use winit::window::Window;
+
+    use super::ShareData;
+
+    /// There's no common share sheet on other platforms.
+    pub fn share(_window: &Window, _data: &ShareData, _files: &[PathBuf]) -> Result<bool, String> {
+        Ok(false)
+    }
+}
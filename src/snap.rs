@@ -0,0 +1,119 @@
+use dpi::{PhysicalPosition, PhysicalSize};
+use versoview_messages::{ControlMonitor, SnapArea};
+
+use crate::window::Window;
+
+/// Grid cells an area covers: columns, rows, column, row, column span and row span.
+fn grid(area: SnapArea) -> (u32, u32, u32, u32, u32, u32) {
+    match area {
+        SnapArea::Full => (1, 1, 0, 0, 1, 1),
+        SnapArea::Left => (2, 1, 0, 0, 1, 1),
+        SnapArea::Right => (2, 1, 1, 0, 1, 1),
+        SnapArea::Top => (1, 2, 0, 0, 1, 1),
+        SnapArea::Bottom => (1, 2, 0, 1, 1, 1),
+        SnapArea::TopLeft => (2, 2, 0, 0, 1, 1),
+        SnapArea::TopRight => (2, 2, 1, 0, 1, 1),
+        SnapArea::BottomLeft => (2, 2, 0, 1, 1, 1),
+        SnapArea::BottomRight => (2, 2, 1, 1, 1, 1),
+        SnapArea::Cell {
+            columns,
+            rows,
+            column,
+            row,
+            column_span,
+            row_span,
+        } => (columns, rows, column, row, column_span, row_span),
+    }
+}
+
+/// Outer position and size of the area of the monitor. Neighbouring cells share their edges, so
+/// windows tile the monitor without gaps.
+fn area_rect(
+    area: SnapArea,
+    origin: PhysicalPosition<i32>,
+    size: PhysicalSize<u32>,
+) -> Result<(PhysicalPosition<i32>, PhysicalSize<u32>), String> {
+    let (columns, rows, column, row, column_span, row_span) = grid(area);
+    if columns == 0 || rows == 0 || column_span == 0 || row_span == 0 {
+        return Err("The grid and the spans need at least one cell".to_string());
+    }
+    if column_span > columns
+        || column > columns - column_span
+        || row_span > rows
+        || row > rows - row_span
+    {
+        return Err(format!("The cell is outside of the {columns}x{rows} grid"));
+    }
+    let edge = |length: u32, cells: u32, index: u32| {
+        (u64::from(length) * u64::from(index) / u64::from(cells)) as i32
+    };
+    let left = edge(size.width, columns, column);
+    let right = edge(size.width, columns, column + column_span);
+    let top = edge(size.height, rows, row);
+    let bottom = edge(size.height, rows, row + row_span);
+    Ok((
+        PhysicalPosition::new(origin.x + left, origin.y + top),
+        PhysicalSize::new((right - left) as u32, (bottom - top) as u32),
+    ))
+}
+
+/// Monitors of the system as listed by the control protocol.
+pub(crate) fn list_monitors(window: &Window) -> Vec<ControlMonitor> {
+    let primary = window.window.primary_monitor();
+    window
+        .window
+        .available_monitors()
+        .enumerate()
+        .map(|(index, monitor)| {
+            let position = monitor.position();
+            let size = monitor.size();
+            ControlMonitor {
+                index,
+                primary: primary.as_ref() == Some(&monitor),
+                name: monitor.name(),
+                x: position.x,
+                y: position.y,
+                width: size.width,
+                height: size.height,
+                scale_factor: monitor.scale_factor(),
+            }
+        })
+        .collect()
+}
+
+impl Window {
+    /// Move and resize the window to fill the area of the monitor at the index of
+    /// [`list_monitors`], or of its current monitor if `None`.
+    pub(crate) fn snap(&self, area: SnapArea, monitor: Option<usize>) -> Result<(), String> {
+        let monitor = match monitor {
+            Some(index) => self
+                .window
+                .available_monitors()
+                .nth(index)
+                .ok_or_else(|| format!("No monitor {index}"))?,
+            None => self
+                .window
+                .current_monitor()
+                .or_else(|| self.window.primary_monitor())
+                .ok_or_else(|| "The window isn't on a monitor".to_string())?,
+        };
+        let (position, size) = area_rect(area, monitor.position(), monitor.size())?;
+
+        self.window.set_fullscreen(None);
+        self.window.set_maximized(false);
+        // The area includes the decorations of the window, its inner size excludes them
+        let outer_size = self.window.outer_size();
+        let inner_size = self.window.inner_size();
+        let inner_size = PhysicalSize::new(
+            size.width
+                .saturating_sub(outer_size.width.saturating_sub(inner_size.width))
+                .max(1),
+            size.height
+                .saturating_sub(outer_size.height.saturating_sub(inner_size.height))
+                .max(1),
+        );
+        self.window.set_outer_position(position);
+        let _ = self.window.request_inner_size(inner_size);
+        Ok(())
+    }
+}
@@ -1,26 +1,160 @@
 use directories::ProjectDirs;
-use std::{fs::create_dir_all, path::PathBuf};
+use std::{
+    fs::{create_dir, create_dir_all, read_dir, remove_dir_all},
+    path::{Path, PathBuf},
+};
 
-use crate::bookmark::BookmarkStorage;
+use crate::{
+    bookmark::BookmarkStorage, crash_reporter::CrashStorage, highlight::HighlightStorage,
+    history::HistoryStorage, images::ImagePolicyStorage, metrics::MetricsStorage,
+    placement::PlacementStorage, session::SessionStorage, zoom::ZoomStorage,
+};
+
+/// Profile directory of a standalone web app, a subdirectory of the config directory of Verso
+/// named after the host of the app.
+pub(crate) fn web_app_profile_directory(app_url: &url::Url) -> Option<PathBuf> {
+    let project_dir = ProjectDirs::from("org", "versotile", "verso")?;
+    let host = app_url.host_str().unwrap_or("local");
+    let name = match app_url.port() {
+        Some(port) => format!("{host}_{port}"),
+        None => host.to_string(),
+    };
+    Some(project_dir.config_dir().join("apps").join(name))
+}
+
+/// Profile directory used when Verso isn't given one, the config directory of Verso.
+pub(crate) fn default_profile_directory() -> Option<PathBuf> {
+    let project_dir = ProjectDirs::from("org", "versotile", "verso")?;
+    Some(project_dir.config_dir().to_path_buf())
+}
+
+/// Directory of the named profiles, a subdirectory of the config directory of Verso.
+fn profiles_directory() -> Option<PathBuf> {
+    let project_dir = ProjectDirs::from("org", "versotile", "verso")?;
+    Some(project_dir.config_dir().join("profiles"))
+}
+
+/// Check the name can be used as the directory of a profile.
+fn validate_profile_name(name: &str) -> Result<(), String> {
+    if name.is_empty()
+        || name.starts_with('.')
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+    {
+        return Err(format!(
+            "Invalid profile name \"{name}\", use letters, digits, '-', '_' and '.'"
+        ));
+    }
+    Ok(())
+}
+
+/// Profile directory of a named profile, created the first time the profile is used.
+pub(crate) fn named_profile_directory(name: &str) -> Result<PathBuf, String> {
+    validate_profile_name(name)?;
+    let directory = profiles_directory().ok_or("Project directory not found")?;
+    Ok(directory.join(name))
+}
+
+/// Names of the named profiles, in alphabetical order.
+pub(crate) fn list_profiles() -> Vec<String> {
+    let Some(entries) = profiles_directory().and_then(|directory| read_dir(directory).ok()) else {
+        return Vec::new();
+    };
+    let mut names: Vec<_> = entries
+        .flatten()
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .filter(|name| validate_profile_name(name).is_ok())
+        .collect();
+    names.sort();
+    names
+}
+
+/// Create an empty named profile.
+pub(crate) fn create_profile(name: &str) -> Result<(), String> {
+    let directory = named_profile_directory(name)?;
+    if directory.exists() {
+        return Err(format!("Profile \"{name}\" already exists"));
+    }
+    if let Some(parent) = directory.parent() {
+        create_dir_all(parent).map_err(|error| error.to_string())?;
+    }
+    create_dir(&directory).map_err(|error| error.to_string())
+}
+
+/// Delete a named profile and everything in it, unless it's the profile in use.
+pub(crate) fn delete_profile(name: &str, profile_in_use: Option<&Path>) -> Result<(), String> {
+    let directory = named_profile_directory(name)?;
+    if !directory.is_dir() {
+        return Err(format!("Profile \"{name}\" doesn't exist"));
+    }
+    if profile_in_use == Some(directory.as_path()) {
+        return Err(format!("Profile \"{name}\" is in use"));
+    }
+    remove_dir_all(&directory).map_err(|error| error.to_string())
+}
+
+/// Directory of the shader cache, in the profile directory if there is one, otherwise in the cache
+/// directory of Verso.
+pub(crate) fn shader_cache_directory(profile_directory: Option<&Path>) -> Option<PathBuf> {
+    match profile_directory {
+        Some(path) => Some(path.join("shader_cache")),
+        None => {
+            let project_dir = ProjectDirs::from("org", "versotile", "verso")?;
+            Some(project_dir.cache_dir().join("shaders"))
+        }
+    }
+}
 
 #[derive(Default)]
 pub(crate) struct Storage {
     bookmark_storage: Option<BookmarkStorage>,
+    session_storage: Option<SessionStorage>,
+    history_storage: Option<HistoryStorage>,
+    highlight_storage: Option<HighlightStorage>,
+    metrics_storage: Option<MetricsStorage>,
+    crash_storage: Option<CrashStorage>,
+    zoom_storage: Option<ZoomStorage>,
+    image_policy_storage: Option<ImagePolicyStorage>,
+    placement_storage: Option<PlacementStorage>,
 }
 
 impl Storage {
-    pub fn new() -> Self {
-        let project_dir = ProjectDirs::from("org", "versotile", "verso");
-
-        let config_dir_path = Self::get_and_create_config_dir_path(project_dir);
+    /// Create the storage in the profile directory, the config directory of Verso by default.
+    pub fn new(profile_directory: Option<&Path>) -> Self {
+        let config_dir_path = match profile_directory {
+            Some(path) => Self::create_dir(path.to_path_buf()),
+            None => {
+                let project_dir = ProjectDirs::from("org", "versotile", "verso");
+                Self::get_and_create_config_dir_path(project_dir)
+            }
+        };
         if config_dir_path.is_none() {
             return Self::default();
         }
 
-        let bookmark_storage = BookmarkStorage::new(config_dir_path.unwrap());
+        let config_dir_path = config_dir_path.unwrap();
+        let bookmark_storage = BookmarkStorage::new(config_dir_path.clone());
+        let session_storage = SessionStorage::new(config_dir_path.clone());
+        let history_storage = HistoryStorage::new(config_dir_path.clone());
+        let highlight_storage = HighlightStorage::new(config_dir_path.clone());
+        let metrics_storage = MetricsStorage::new(config_dir_path.clone());
+        let crash_storage = CrashStorage::new(config_dir_path.clone());
+        let zoom_storage = ZoomStorage::new(config_dir_path.clone());
+        let image_policy_storage = ImagePolicyStorage::new(config_dir_path.clone());
+        let placement_storage = PlacementStorage::new(config_dir_path);
 
         Self {
             bookmark_storage: Some(bookmark_storage),
+            session_storage: Some(session_storage),
+            history_storage: Some(history_storage),
+            highlight_storage: Some(highlight_storage),
+            metrics_storage: Some(metrics_storage),
+            crash_storage: Some(crash_storage),
+            zoom_storage: Some(zoom_storage),
+            image_policy_storage: Some(image_policy_storage),
+            placement_storage: Some(placement_storage),
         }
     }
 
@@ -31,7 +165,10 @@ impl Storage {
         }
 
         let config_path = project_dir.unwrap().config_dir().to_path_buf();
+        Self::create_dir(config_path)
+    }
 
+    fn create_dir(config_path: PathBuf) -> Option<PathBuf> {
         if create_dir_all(&config_path).is_err() {
             log::error!(
                 "Failed to create config directory: {}",
@@ -46,4 +183,36 @@ impl Storage {
     pub(crate) fn bookmark_storage(&self) -> Option<&BookmarkStorage> {
         self.bookmark_storage.as_ref()
     }
+
+    pub(crate) fn session_storage(&self) -> Option<&SessionStorage> {
+        self.session_storage.as_ref()
+    }
+
+    pub(crate) fn history_storage(&self) -> Option<&HistoryStorage> {
+        self.history_storage.as_ref()
+    }
+
+    pub(crate) fn highlight_storage(&self) -> Option<&HighlightStorage> {
+        self.highlight_storage.as_ref()
+    }
+
+    pub(crate) fn metrics_storage(&self) -> Option<&MetricsStorage> {
+        self.metrics_storage.as_ref()
+    }
+
+    pub(crate) fn crash_storage(&self) -> Option<&CrashStorage> {
+        self.crash_storage.as_ref()
+    }
+
+    pub(crate) fn zoom_storage(&self) -> Option<&ZoomStorage> {
+        self.zoom_storage.as_ref()
+    }
+
+    pub(crate) fn image_policy_storage(&self) -> Option<&ImagePolicyStorage> {
+        self.image_policy_storage.as_ref()
+    }
+
+    pub(crate) fn placement_storage(&self) -> Option<&PlacementStorage> {
+        self.placement_storage.as_ref()
+    }
 }
@@ -1,10 +1,11 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use crate::webview::{WebView, prompt::PromptDialog};
 use base::id::WebViewId;
 use serde::{Deserialize, Serialize};
 use servo_url::ServoUrl;
-use webrender_api::units::DeviceRect;
+use versoview_messages::{LifecycleState, PrivacySettings, ResourceLimits};
+use webrender_api::units::{DeviceRect, LayoutVector2D};
 
 /// Tab state
 pub struct Tab {
@@ -18,6 +19,30 @@ pub struct Tab {
     prompt: Option<PromptDialog>,
     /// Title
     title: String,
+    /// Favicon URL
+    favicon: Option<ServoUrl>,
+    /// `theme-color` of the page, as `#rrggbb`
+    theme_color: Option<String>,
+    /// Load progress of the page between 0 and 1, `None` if it's loaded
+    load_progress: Option<f32>,
+    /// Whether the tab is playing media
+    audible: bool,
+    /// Whether the tab's media elements are muted
+    muted: bool,
+    /// Privacy settings set by the controller, the ones of the config are used if `None`
+    privacy: Option<PrivacySettings>,
+    /// Lifecycle state set by the controller, Verso manages it if `None`
+    lifecycle_state: Option<LifecycleState>,
+    /// Resource budgets set by the controller
+    resource_limits: ResourceLimits,
+    /// Requests sent since the current document started loading
+    request_count: u32,
+    /// Blocked images the user clicked to load in the current document, without fragment
+    loaded_images: HashSet<url::Url>,
+    /// Scroll position of the restored session, applied once the page loads
+    restore_scroll: Option<LayoutVector2D>,
+    /// Name to address the tab with in the control protocol, kept in the session
+    name: Option<String>,
 }
 
 impl Tab {
@@ -32,6 +57,18 @@ impl Tab {
             },
             prompt: None,
             title: "null".to_string(),
+            favicon: None,
+            theme_color: None,
+            load_progress: None,
+            audible: false,
+            muted: false,
+            privacy: None,
+            lifecycle_state: None,
+            resource_limits: ResourceLimits::default(),
+            request_count: 0,
+            loaded_images: HashSet::new(),
+            restore_scroll: None,
+            name: None,
         }
     }
 
@@ -60,6 +97,11 @@ impl Tab {
         self.history = TabHistory { list, current_idx };
     }
 
+    /// Get the URL currently loaded in the tab.
+    pub fn current_url(&self) -> Option<&ServoUrl> {
+        self.history.list.get(self.history.current_idx)
+    }
+
     /// Get tab prompt dialog.
     pub fn prompt(&self) -> Option<&PromptDialog> {
         self.prompt.as_ref()
@@ -101,6 +143,134 @@ impl Tab {
     pub fn title(&self) -> String {
         self.title.clone()
     }
+
+    /// Set tab favicon URL.
+    pub fn set_favicon(&mut self, favicon: ServoUrl) {
+        self.favicon = Some(favicon);
+    }
+
+    /// Get tab favicon URL.
+    pub fn favicon(&self) -> Option<&ServoUrl> {
+        self.favicon.as_ref()
+    }
+
+    /// Set tab `theme-color`.
+    pub fn set_theme_color(&mut self, theme_color: Option<String>) {
+        self.theme_color = theme_color;
+    }
+
+    /// Get tab `theme-color`.
+    pub fn theme_color(&self) -> Option<&str> {
+        self.theme_color.as_deref()
+    }
+
+    /// Set tab load progress, `None` once loaded.
+    pub fn set_load_progress(&mut self, load_progress: Option<f32>) {
+        self.load_progress = load_progress;
+    }
+
+    /// Get tab load progress between 0 and 1, `None` if it's loaded.
+    pub fn load_progress(&self) -> Option<f32> {
+        self.load_progress
+    }
+
+    /// Set whether the tab is playing media.
+    pub fn set_audible(&mut self, audible: bool) {
+        self.audible = audible;
+    }
+
+    /// Check if the tab is playing media.
+    pub fn is_audible(&self) -> bool {
+        self.audible
+    }
+
+    /// Set whether the tab is muted.
+    pub fn set_muted(&mut self, muted: bool) {
+        self.muted = muted;
+    }
+
+    /// Check if the tab is muted.
+    pub fn is_muted(&self) -> bool {
+        self.muted
+    }
+
+    /// Set the privacy settings of the tab, `None` to use the ones of the config.
+    pub fn set_privacy(&mut self, privacy: Option<PrivacySettings>) {
+        self.privacy = privacy;
+    }
+
+    /// Set the lifecycle state of the tab.
+    pub fn set_lifecycle_state(&mut self, state: LifecycleState) {
+        self.lifecycle_state = Some(state);
+    }
+
+    /// Get the lifecycle state of the tab if it's set by the controller.
+    pub fn lifecycle_state(&self) -> Option<LifecycleState> {
+        self.lifecycle_state
+    }
+
+    /// Get the privacy settings of the tab if they are set.
+    pub fn privacy(&self) -> Option<&PrivacySettings> {
+        self.privacy.as_ref()
+    }
+
+    /// Set the resource budgets of the tab.
+    pub fn set_resource_limits(&mut self, limits: ResourceLimits) {
+        self.resource_limits = limits;
+    }
+
+    /// Get the resource budgets of the tab.
+    pub fn resource_limits(&self) -> &ResourceLimits {
+        &self.resource_limits
+    }
+
+    /// Count a request of the current document, and return how many were sent.
+    pub fn count_request(&mut self) -> u32 {
+        self.request_count = self.request_count.saturating_add(1);
+        self.request_count
+    }
+
+    /// Set the scroll position to restore once the page loads.
+    pub fn set_restore_scroll(&mut self, scroll: LayoutVector2D) {
+        self.restore_scroll = Some(scroll);
+    }
+
+    /// Take the scroll position of the restored session, if it isn't applied yet.
+    pub fn take_restore_scroll(&mut self) -> Option<LayoutVector2D> {
+        self.restore_scroll.take()
+    }
+
+    /// Get the name of the tab.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Set the name of the tab, or remove it if `None`.
+    pub fn set_name(&mut self, name: Option<String>) {
+        self.name = name;
+    }
+
+    /// Reset the request count when a new document starts loading.
+    pub fn reset_request_count(&mut self) {
+        self.request_count = 0;
+    }
+
+    /// Load the image of this URL even if the image policy blocks it, until another document
+    /// starts loading.
+    pub fn load_image(&mut self, mut url: url::Url) {
+        url.set_fragment(None);
+        self.loaded_images.insert(url);
+    }
+
+    /// Whether the user clicked to load the image of this URL, without fragment.
+    pub fn image_loaded(&self, url: &url::Url) -> bool {
+        self.loaded_images.contains(url)
+    }
+
+    /// Forget the images loaded on click when a new document starts loading.
+    pub fn clear_loaded_images(&mut self) {
+        self.loaded_images.clear();
+    }
 }
 
 /// Tab manager to handle multiple tab in a window.
@@ -109,6 +279,8 @@ pub struct TabManager {
     active_tab_id: Option<WebViewId>,
     /// Tab webview id -> Tab webview
     tab_map: HashMap<WebViewId, Tab>,
+    /// Tab webview ids in the order of the tab strip
+    tab_order: Vec<WebViewId>,
     /// Prompt webview id -> Parent tab webview id
     prompt_tab_map: HashMap<WebViewId, WebViewId>,
 }
@@ -119,6 +291,7 @@ impl TabManager {
         Self {
             active_tab_id: None,
             tab_map: HashMap::new(),
+            tab_order: Vec::new(),
             prompt_tab_map: HashMap::new(),
         }
     }
@@ -146,9 +319,33 @@ impl TabManager {
             None
         }
     }
-    /// Get all tab id.
+    /// Get all tab id, in the order of the tab strip.
     pub fn tab_ids(&self) -> Vec<WebViewId> {
-        self.tab_map.keys().cloned().collect()
+        self.tab_order.clone()
+    }
+    /// Get the position of the tab in the tab strip.
+    pub fn tab_index(&self, id: WebViewId) -> Option<usize> {
+        self.tab_order.iter().position(|tab_id| *tab_id == id)
+    }
+    /// Get the tab after the current one in the tab strip, or before it if `forward` is false.
+    /// Wraps around at both ends.
+    pub fn adjacent_tab_id(&self, forward: bool) -> Option<WebViewId> {
+        let index = self.tab_index(self.active_tab_id?)?;
+        let count = self.tab_order.len();
+        let index = if forward {
+            (index + 1) % count
+        } else {
+            (index + count - 1) % count
+        };
+        self.tab_order.get(index).copied()
+    }
+    /// Move the tab to the position in the tab strip, clamped to the last position.
+    pub fn move_tab(&mut self, id: WebViewId, index: usize) -> Result<usize, TabManagerErr> {
+        let old_index = self.tab_index(id).ok_or(TabManagerErr::WebViewIdNotFound)?;
+        self.tab_order.remove(old_index);
+        let index = index.min(self.tab_order.len());
+        self.tab_order.insert(index, id);
+        Ok(index)
     }
     /// Activate the tab by tab id.
     pub fn activate_tab(&mut self, tab_id: WebViewId) -> Option<&Tab> {
@@ -164,11 +361,16 @@ impl TabManager {
     pub fn tab(&self, id: WebViewId) -> Option<&Tab> {
         self.tab_map.get(&id)
     }
+    /// Get tab by tab id as mutable.
+    pub fn tab_mut(&mut self, id: WebViewId) -> Option<&mut Tab> {
+        self.tab_map.get_mut(&id)
+    }
     /// Append a tab.
     pub fn append_tab(&mut self, webview: WebView, active: bool) {
         let id = webview.webview_id;
         let tab = Tab::new(webview);
         self.tab_map.insert(id, tab);
+        self.tab_order.push(id);
         if active {
             self.active_tab_id = Some(id);
         }
@@ -176,7 +378,10 @@ impl TabManager {
     /// Close a tab.
     pub fn close_tab(&mut self, id: WebViewId) -> Result<Tab, TabManagerErr> {
         match self.tab_map.remove(&id) {
-            Some(tab) => Ok(tab),
+            Some(tab) => {
+                self.tab_order.retain(|tab_id| *tab_id != id);
+                Ok(tab)
+            }
             None => Err(TabManagerErr::WebViewIdNotFound),
         }
     }
@@ -320,3 +525,12 @@ pub struct TabCloseRequest {
     /// Tab WebView id
     pub id: WebViewId,
 }
+
+/// Mute or unmute the tab request from UI.
+#[derive(Debug, Clone, Deserialize)]
+pub struct TabMuteRequest {
+    /// Tab WebView id
+    pub id: WebViewId,
+    /// Mute or unmute
+    pub muted: bool,
+}
@@ -8,32 +8,39 @@
 /// harness = false
 /// ```
 ///
-/// Then, in your test, use the `verso_test!` macro to run your tests. The tests must be functions that take an `EventLoopWindowTarget`.
+/// Then, in your test, use the `verso_test!` macro to run your tests. The tests must be functions
+/// that take a [`TestContext`], which drives a Verso window like a controller would. They run one
+/// after another in the same window and on their own thread, while the event loop runs on the main
+/// thread.
 ///
-/// ```rust
-/// use verso::verso_test;
-/// use verso::winit::event_loop::EventLoopWindowTarget;
+/// ```rust,no_run
+/// use versoview::test::TestContext;
+/// use versoview::verso_test;
 ///
-/// fn my_test(elwt: &EventLoopWindowTarget<()>) {
-///     // ...
+/// fn my_test(ctx: &TestContext) {
+///     assert!(ctx.navigate("data:text/html,<title>Test</title>".parse().unwrap()));
 /// }
 ///
-/// fn other_test(elwt: &EventLoopWindowTarget<()>) {
+/// fn other_test(ctx: &TestContext) {
 ///     // ...
 /// }
 ///
 /// verso_test!(my_test, other_test);
 /// ```
+///
+/// The window renders with the software rasterizer and without vsync, so frames are the same on
+/// every machine, and it's hidden on Linux. The event loop still needs a display connection, on
+/// CI machines without a display run the tests in a virtual display server, e.g. with
+/// `xvfb-run cargo test`.
 #[macro_export]
 macro_rules! verso_test {
     ($($test:expr),*) => {
-        fn main() -> Result<(), Box<dyn std::error::Error>> {
+        fn main() {
             const TESTS: &[$crate::test::__private::VersoBasedTest] = &[$(
                 $crate::__verso_test_internal_collect_test!($test)
             ),*];
 
-            $crate::test::__private::run(TESTS, ());
-            Ok(())
+            std::process::exit($crate::test::__private::run(TESTS));
         }
     };
 }
@@ -49,88 +56,286 @@ macro_rules! __verso_test_internal_collect_test {
     };
 }
 
+use std::{path::PathBuf, time::Duration};
+
+use image::RgbaImage;
+use ipc_channel::ipc::IpcReceiver;
+use versoview_messages::{
+    FrameInfo, FrameInput, SerializedWebViewId, ToControllerMessage, ToVersoMessage, WaitCondition,
+};
+use winit::event_loop::EventLoopProxy;
+
+use crate::verso::EventLoopProxyMessage;
+
+/// How long to wait for Verso to answer a request before failing the test.
+const RESPONSE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How long [`TestContext::navigate`] and [`TestContext::wait_for`] wait for their condition.
+const WAIT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Drives the Verso window of the tests, with the messages a controller would send.
+pub struct TestContext {
+    proxy: EventLoopProxy<EventLoopProxyMessage>,
+    receiver: IpcReceiver<ToControllerMessage>,
+}
+
+impl TestContext {
+    /// Send a message to Verso, like a controller would.
+    pub fn send(&self, message: ToVersoMessage) {
+        self.proxy
+            .send_event(EventLoopProxyMessage::IpcMessage(Box::new(message)))
+            .expect("The event loop of Verso is closed");
+    }
+
+    /// Send the request made with a new id, and wait for its response. Other messages of Verso
+    /// are dropped.
+    fn request<T>(
+        &self,
+        message: impl FnOnce(uuid::Uuid) -> ToVersoMessage,
+        response: impl Fn(uuid::Uuid, ToControllerMessage) -> Option<T>,
+    ) -> T {
+        let id = uuid::Uuid::new_v4();
+        self.send(message(id));
+        loop {
+            let message = self
+                .receiver
+                .try_recv_timeout(RESPONSE_TIMEOUT)
+                .expect("Verso didn't respond in time");
+            if let Some(value) = response(id, message) {
+                return value;
+            }
+        }
+    }
+
+    /// Navigate the active tab to the URL, returns whether it loaded in time.
+    pub fn navigate(&self, url: url::Url) -> bool {
+        self.send(ToVersoMessage::NavigateTo(url.clone()));
+        self.wait_for(WaitCondition::Url(url.to_string()))
+            && self.wait_for(WaitCondition::LoadComplete)
+    }
+
+    /// Wait for the condition in the active tab, returns whether it was met in time.
+    pub fn wait_for(&self, condition: WaitCondition) -> bool {
+        self.request(
+            |id| ToVersoMessage::WaitFor(id, condition, WAIT_TIMEOUT.as_millis() as u64),
+            |id, message| match message {
+                ToControllerMessage::WaitForResponse(response_id, met) if response_id == id => {
+                    Some(met)
+                }
+                _ => None,
+            },
+        )
+    }
+
+    /// Get the URL of the active tab.
+    pub fn current_url(&self) -> url::Url {
+        self.request(ToVersoMessage::GetCurrentUrl, |id, message| match message {
+            ToControllerMessage::GetCurrentUrlResponse(response_id, url) if response_id == id => {
+                Some(url)
+            }
+            _ => None,
+        })
+    }
+
+    /// Get the webviews of every window, the tabs of a window in tab strip order.
+    pub fn webviews(&self) -> Vec<SerializedWebViewId> {
+        self.request(ToVersoMessage::GetWebViews, |id, message| match message {
+            ToControllerMessage::GetWebViewsResponse(response_id, webview_ids)
+                if response_id == id =>
+            {
+                Some(webview_ids)
+            }
+            _ => None,
+        })
+    }
+
+    /// Get the frame tree of the webview, `None` if it doesn't exist.
+    pub fn frame_tree(&self, webview_id: SerializedWebViewId) -> Option<FrameInfo> {
+        self.request(
+            |id| ToVersoMessage::GetFrameTree(id, webview_id),
+            |id, message| match message {
+                ToControllerMessage::GetFrameTreeResponse(response_id, tree)
+                    if response_id == id =>
+                {
+                    Some(tree)
+                }
+                _ => None,
+            },
+        )
+    }
+
+    /// Get the top-level frame of the first webview.
+    fn top_frame(&self) -> FrameInfo {
+        let webview_id = self
+            .webviews()
+            .into_iter()
+            .next()
+            .expect("Verso has no webview");
+        self.frame_tree(webview_id)
+            .expect("The webview of the test is closed")
+    }
+
+    /// Evaluate the script in the document of the first webview, returns its result as JSON.
+    pub fn evaluate(&self, script: impl ToString) -> Result<String, String> {
+        let frame_id = self.top_frame().id;
+        self.request(
+            |id| ToVersoMessage::ExecuteScriptInFrame(id, frame_id, script.to_string()),
+            |id, message| match message {
+                ToControllerMessage::ExecuteScriptInFrameResponse(response_id, result)
+                    if response_id == id =>
+                {
+                    Some(result)
+                }
+                _ => None,
+            },
+        )
+    }
+
+    /// Dispatch the input event to the document of the first webview.
+    pub fn send_input(&self, input: FrameInput) {
+        let frame_id = self.top_frame().id;
+        self.send(ToVersoMessage::SendFrameInput(frame_id, input));
+    }
+
+    /// Answer the file pickers of `<input type="file">` with the files they accept among these,
+    /// since tests can't use the file dialog.
+    pub fn set_picked_files(&self, paths: Vec<PathBuf>) {
+        self.send(ToVersoMessage::SetFilePickerOverride(Some(paths)));
+    }
+
+    /// Capture the next frame composited in the window, `None` if it couldn't be read.
+    pub fn capture_frame(&self) -> Option<RgbaImage> {
+        let png = self.request(ToVersoMessage::CaptureFrame, |id, message| match message {
+            ToControllerMessage::CaptureFrameResponse(response_id, png) if response_id == id => {
+                Some(png)
+            }
+            _ => None,
+        })?;
+        image::load_from_memory(&png)
+            .ok()
+            .map(|image| image.to_rgba8())
+    }
+}
+
 #[doc(hidden)]
 // This module is forked from winit-test.
 pub mod __private {
-    use winit::event_loop::EventLoop;
-    pub use winit::event_loop::EventLoopWindowTarget;
-
-    use owo_colors::OwoColorize;
     use std::any::Any;
-    use std::panic::{catch_unwind, AssertUnwindSafe};
+    use std::panic::{AssertUnwindSafe, catch_unwind};
+    use std::sync::mpsc;
+    use std::thread;
     use std::time::Instant;
-    use winit::window::WindowBuilder;
 
-    use crate::{Status, Verso};
+    use dpi::{PhysicalSize, Size};
+    use ipc_channel::ipc;
+    use versoview_messages::{
+        ConfigFromController, ToControllerMessage, ToVersoMessage, Vsync, WaitCondition,
+    };
+    use winit::event_loop::EventLoop;
 
-    pub type Context = ();
+    use super::TestContext;
+    use crate::App;
+    use crate::config::Config;
+    use crate::verso::EventLoopProxyMessage;
 
     struct State {
         passed: i32,
         panics: Vec<(&'static str, Box<dyn Any + Send>)>,
         start: Instant,
-        run: bool,
-        code: i32,
     }
 
-    /// Run a set of tests using a Winit context.
-    pub fn run(tests: &'static [VersoBasedTest], _ctx: Context) {
-        // Create a new event loop and obtain a window target.
-        let event_loop = EventLoop::new().expect("Failed to build event loop");
-        let window = WindowBuilder::new()
-            .with_visible(false)
-            .build(&event_loop)
-            .expect("Failed to create Winit window");
-        let mut verso = Verso::new(window, event_loop.create_proxy());
+    /// A blank window of a fixed size, without control panel and rendered in software, so the
+    /// results don't depend on the machine. On Linux the window is hidden, frames of hidden X11
+    /// and Wayland windows are still composited, other platforms only paint visible windows.
+    fn test_settings() -> ConfigFromController {
+        ConfigFromController {
+            url: Some(url::Url::parse("about:blank").unwrap()),
+            with_panel: false,
+            inner_size: Some(Size::Physical(PhysicalSize::new(800, 600))),
+            visible: !cfg!(linux),
+            focused: false,
+            decorated: false,
+            shader_cache: false,
+            software_rendering: true,
+            vsync: Vsync::Immediate,
+            ..Default::default()
+        }
+    }
 
-        println!("\nRunning {} tests...", tests.len());
-        let mut state = State {
-            passed: 0,
-            panics: vec![],
-            start: Instant::now(),
-            run: false,
-            code: 0,
+    /// Run a set of tests in a Verso window, returns the exit code of the tests.
+    pub fn run(tests: &'static [VersoBasedTest]) -> i32 {
+        let _ = rustls::crypto::aws_lc_rs::default_provider().install_default();
+        let runtime = tokio::runtime::Runtime::new().expect("Failed to create tokio runtime");
+        let _guard = runtime.enter();
+
+        let event_loop = EventLoop::<EventLoopProxyMessage>::with_user_event()
+            .build()
+            .expect("Failed to build event loop");
+        let proxy = event_loop.create_proxy();
+        let (to_controller_sender, receiver) = ipc::channel::<ToControllerMessage>().unwrap();
+        let (ready_sender, ready_receiver) = mpsc::channel();
+        let context = TestContext {
+            proxy: proxy.clone(),
+            receiver,
         };
 
-        // Run the tests.
-        event_loop
-            .run(move |event, elwt| match verso.run(event, elwt) {
-                Status::LoadComplete => {
-                    run_internal(tests, &mut state, elwt);
-                    verso.shutdown();
+        let tests_thread = thread::Builder::new()
+            .name("VersoTests".to_owned())
+            .spawn(move || {
+                if ready_receiver.recv().is_err() {
+                    return 1;
                 }
-                Status::Shutdown => elwt.exit(),
-                _ => (),
+                let code = if context.wait_for(WaitCondition::LoadComplete) {
+                    run_internal(tests, &context)
+                } else {
+                    println!("The initial page of Verso didn't load");
+                    1
+                };
+                context.send(ToVersoMessage::Exit);
+                code
             })
+            .expect("Failed to spawn tests thread");
+
+        let mut app = App::with_config(
+            proxy,
+            Config::from_controller_config(test_settings()),
+            Some(to_controller_sender),
+        )
+        .on_created(move || {
+            let _ = ready_sender.send(());
+        });
+        event_loop
+            .run_app(&mut app)
             .expect("Event loop failed to run");
+        tests_thread.join().unwrap_or(1)
     }
 
-    /// Run a set of tests using a Winit context.
-    fn run_internal(
-        tests: &'static [VersoBasedTest],
-        state: &mut State,
-        elwt: &EventLoopWindowTarget<()>,
-    ) {
-        if state.run {
-            return;
-        }
-        state.run = true;
+    /// Run a set of tests with the context, returns the exit code of the tests.
+    fn run_internal(tests: &'static [VersoBasedTest], context: &TestContext) -> i32 {
+        println!("\nRunning {} tests...", tests.len());
+        let mut state = State {
+            passed: 0,
+            panics: vec![],
+            start: Instant::now(),
+        };
 
         for test in tests {
             print!("test {} ... ", test.name);
 
             match test.function {
-                TestFunction::OneOff(f) => match catch_unwind(AssertUnwindSafe(move || f(elwt))) {
-                    Ok(()) => {
-                        println!("{}", "ok".green());
-                        state.passed += 1;
-                    }
+                TestFunction::OneOff(f) => {
+                    match catch_unwind(AssertUnwindSafe(move || f(context))) {
+                        Ok(()) => {
+                            println!("ok");
+                            state.passed += 1;
+                        }
 
-                    Err(e) => {
-                        println!("{}", "FAILED".red());
-                        state.panics.push((test.name, e));
+                        Err(e) => {
+                            println!("FAILED");
+                            state.panics.push((test.name, e));
+                        }
                     }
-                },
+                }
             }
         }
 
@@ -142,19 +347,19 @@ pub mod __private {
                 println!("---- {} panic ----", name);
 
                 if let Some(s) = e.downcast_ref::<&'static str>() {
-                    println!("{}", s.red());
+                    println!("{}", s);
                 } else if let Some(s) = e.downcast_ref::<String>() {
-                    println!("{}", s.red());
+                    println!("{}", s);
                 } else {
-                    println!("{}", "unknown panic type".red());
+                    println!("unknown panic type");
                 }
 
                 println!();
             }
 
-            print!("test result: {}", "FAILED".red());
+            print!("test result: FAILED");
         } else {
-            print!("test result: {}", "ok".green());
+            print!("test result: ok");
         }
 
         let elapsed = state.start.elapsed();
@@ -163,7 +368,7 @@ pub mod __private {
             state.passed, failures, elapsed
         );
 
-        state.code = if failures == 0 { 0 } else { 1 };
+        if failures == 0 { 0 } else { 1 }
     }
 
     pub struct VersoBasedTest {
@@ -172,6 +377,6 @@ pub mod __private {
     }
 
     pub enum TestFunction {
-        OneOff(fn(&EventLoopWindowTarget<()>)),
+        OneOff(fn(&TestContext)),
     }
 }
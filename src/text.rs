@@ -0,0 +1,577 @@
+use std::{path::PathBuf, thread};
+
+use base::id::WebViewId;
+use constellation_traits::EmbedderToConstellationMessage;
+use crossbeam_channel::Sender;
+use embedder_traits::WebDriverJSValue;
+use ipc_channel::ipc::IpcSender;
+use serde::{Deserialize, Serialize};
+use versoview_messages::ToControllerMessage;
+
+use crate::{verso::VersoInternalMsg, webview::execute_script};
+
+/// Script returning the rendered text of the element matching the selector passed as argument,
+/// or of the body if it's `null`. `innerText` leaves out hidden elements, scripts and styles.
+const EXTRACT_TEXT_SCRIPT: &str = r#"
+((selector) => {
+  const element = selector === null ? document.body : document.querySelector(selector);
+  return element ? element.innerText ?? element.textContent : null;
+})
+"#;
+
+/// Script returning the URL, title and, if the argument is `true`, the visible text of the
+/// document as a [`PageText`] in JSON.
+const PAGE_TEXT_SCRIPT: &str = r#"
+((withText) => JSON.stringify({
+  url: location.href,
+  title: document.title || null,
+  text: withText ? document.body?.innerText ?? '' : '',
+}))
+"#;
+
+/// Script returning the readable content of the document as a [`DocumentContent`] in JSON: its
+/// main element or article if it has one, without navigation, forms and hidden elements. The
+/// nodes are sent in document order rather than nested, so deep documents don't hit the
+/// recursion limit of the JSON parser.
+const DOCUMENT_CONTENT_SCRIPT: &str = r#"
+(() => {
+  const SKIPPED = new Set([
+    'script', 'style', 'noscript', 'template', 'nav', 'aside', 'footer', 'header', 'form',
+    'button', 'iframe', 'svg', 'canvas', 'select', 'input', 'textarea', 'dialog',
+  ]);
+
+  const articles = document.querySelectorAll('article');
+  const root = document.querySelector('main, [role="main"]')
+    ?? (articles.length === 1 ? articles[0] : document.body);
+
+  const hidden = (element) =>
+    element.hidden || element.getAttribute('aria-hidden') === 'true'
+      || getComputedStyle(element).display === 'none';
+
+  const nodes = [];
+  const visit = (node, parent) => {
+    if (node.nodeType === Node.TEXT_NODE) {
+      nodes.push({ parent, text: node.data });
+      return;
+    }
+    if (node.nodeType !== Node.ELEMENT_NODE || SKIPPED.has(node.localName) || hidden(node)) {
+      return;
+    }
+    const tag = node.localName;
+    const index = nodes.length;
+    if (tag === 'code' || tag === 'pre') {
+      nodes.push({ parent, tag, text: node.textContent });
+      return;
+    }
+    nodes.push({
+      parent,
+      tag,
+      href: tag === 'a' ? node.href : '',
+      src: tag === 'img' ? node.src : '',
+      alt: tag === 'img' ? node.alt ?? '' : '',
+    });
+    for (const child of node.childNodes) {
+      visit(child, index);
+    }
+  };
+
+  if (root) {
+    visit(root, null);
+  }
+  return JSON.stringify({ title: document.title, nodes });
+})()
+"#;
+
+/// Elements converted to a block of their content.
+const BLOCKS: &[&str] = &[
+    "address",
+    "article",
+    "dd",
+    "details",
+    "div",
+    "dl",
+    "dt",
+    "figcaption",
+    "figure",
+    "main",
+    "section",
+    "summary",
+];
+
+/// A loaded document, to record in the history.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PageText {
+    /// URL of the document
+    pub url: String,
+    /// Title of the document
+    pub title: Option<String>,
+    /// Visible text of the document, empty if it isn't indexed
+    pub text: String,
+}
+
+/// Readable content of a document, see [`DOCUMENT_CONTENT_SCRIPT`].
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub(crate) struct DocumentContent {
+    /// Title of the document
+    pub title: String,
+    /// Nodes of the content in document order, the first one is the root
+    pub nodes: Vec<ContentNode>,
+}
+
+/// Text node or element of a [`DocumentContent`].
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub(crate) struct ContentNode {
+    /// Index of the parent node, `None` for the root
+    pub parent: Option<usize>,
+    /// Local name of the element, empty for a text node
+    pub tag: String,
+    /// Data of a text node, or text content of a `code` or `pre` element
+    pub text: String,
+    /// Absolute URL of a link
+    pub href: String,
+    /// Absolute URL of an image
+    pub src: String,
+    /// Alternative text of an image
+    pub alt: String,
+}
+
+impl DocumentContent {
+    /// Convert the content to Markdown, headed by the title of the document if it has no `h1`.
+    pub(crate) fn to_markdown(&self) -> String {
+        let mut children = vec![Vec::new(); self.nodes.len()];
+        for (index, node) in self.nodes.iter().enumerate() {
+            // Parents come before their children in document order
+            if let Some(parent) = node.parent.filter(|&parent| parent < index) {
+                children[parent].push(index);
+            }
+        }
+        let converter = MarkdownConverter {
+            nodes: &self.nodes,
+            children,
+        };
+
+        let mut markdown = if self.nodes.is_empty() {
+            String::new()
+        } else {
+            converter.convert(0)
+        };
+        if !self.title.is_empty() && !self.nodes.iter().any(|node| node.tag == "h1") {
+            markdown = format!("# {}\n\n{markdown}", escape(&self.title));
+        }
+        format!("{}\n", tidy(&markdown))
+    }
+}
+
+/// Converts the nodes of a [`DocumentContent`] to Markdown.
+struct MarkdownConverter<'a> {
+    nodes: &'a [ContentNode],
+    /// Indices of the children of each node
+    children: Vec<Vec<usize>>,
+}
+
+impl MarkdownConverter<'_> {
+    fn convert(&self, index: usize) -> String {
+        let node = &self.nodes[index];
+        let tag = node.tag.as_str();
+        match tag {
+            "" => escape(&collapse_whitespace(&node.text)),
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                let level = usize::from(tag.as_bytes()[1] - b'0');
+                let content = collapse_whitespace(&self.children(index));
+                block(&format!("{} {}", "#".repeat(level), content.trim()))
+            }
+            "p" => block(&self.children(index)),
+            "br" => "  \n".to_string(),
+            "hr" => block("---"),
+            "strong" | "b" => self.wrapped(index, "**"),
+            "em" | "i" => self.wrapped(index, "_"),
+            "code" if node.text.is_empty() => String::new(),
+            "code" => format!("`{}`", node.text),
+            "pre" => {
+                let text = node.text.strip_suffix('\n').unwrap_or(&node.text);
+                block(&format!("```\n{text}\n```"))
+            }
+            "a" => {
+                let content = self.children(index).trim().to_string();
+                if content.is_empty()
+                    || node.href.is_empty()
+                    || node.href.starts_with("javascript:")
+                {
+                    content
+                } else {
+                    format!("[{content}](<{}>)", node.href)
+                }
+            }
+            "img" if node.src.is_empty() => String::new(),
+            "img" => format!("![{}](<{}>)", escape(&node.alt), node.src),
+            "blockquote" => {
+                let content = tidy(&self.children(index));
+                let lines: Vec<_> = content
+                    .split('\n')
+                    .map(|line| format!("> {line}"))
+                    .collect();
+                block(&lines.join("\n"))
+            }
+            "ul" | "ol" => block(&self.list(index)),
+            "table" => block(&self.table(index)),
+            _ if BLOCKS.contains(&tag) => block(&self.children(index)),
+            _ => self.children(index),
+        }
+    }
+
+    fn children(&self, index: usize) -> String {
+        self.children[index]
+            .iter()
+            .map(|&child| self.convert(child))
+            .collect()
+    }
+
+    fn wrapped(&self, index: usize, marker: &str) -> String {
+        let content = self.children(index);
+        let content = content.trim();
+        if content.is_empty() {
+            String::new()
+        } else {
+            format!("{marker}{content}{marker}")
+        }
+    }
+
+    fn list(&self, index: usize) -> String {
+        let ordered = self.nodes[index].tag == "ol";
+        let items: Vec<_> = self.children[index]
+            .iter()
+            .filter(|&&child| self.nodes[child].tag == "li")
+            .enumerate()
+            .map(|(position, &item)| {
+                let marker = if ordered {
+                    format!("{}. ", position + 1)
+                } else {
+                    "- ".to_string()
+                };
+                let indent = " ".repeat(marker.len());
+                let content = tidy(&self.children(item));
+                let lines: Vec<_> = content
+                    .split('\n')
+                    .enumerate()
+                    .map(|(i, line)| {
+                        if i == 0 || line.is_empty() {
+                            line.to_string()
+                        } else {
+                            format!("{indent}{line}")
+                        }
+                    })
+                    .collect();
+                format!("{marker}{}", lines.join("\n"))
+            })
+            .collect();
+        items.join("\n")
+    }
+
+    /// Collect the rows of a table, directly in it or in its sections.
+    fn rows(&self, index: usize, rows: &mut Vec<usize>) {
+        for &child in &self.children[index] {
+            match self.nodes[child].tag.as_str() {
+                "tr" => rows.push(child),
+                "thead" | "tbody" | "tfoot" => self.rows(child, rows),
+                _ => {}
+            }
+        }
+    }
+
+    fn table(&self, index: usize) -> String {
+        let mut row_indices = Vec::new();
+        self.rows(index, &mut row_indices);
+        let rows: Vec<Vec<String>> = row_indices
+            .iter()
+            .map(|&row| {
+                self.children[row]
+                    .iter()
+                    .filter(|&&cell| matches!(self.nodes[cell].tag.as_str(), "td" | "th"))
+                    .map(|&cell| {
+                        let content = tidy(&self.children(cell));
+                        let lines: Vec<_> = content.split('\n').filter(|l| !l.is_empty()).collect();
+                        lines.join(" ").replace('|', "\\|")
+                    })
+                    .collect()
+            })
+            .collect();
+        let Some(width) = rows.iter().map(Vec::len).max() else {
+            return String::new();
+        };
+
+        let line = |cells: &[String]| {
+            let cells: Vec<_> = (0..width)
+                .map(|i| cells.get(i).map_or("", String::as_str))
+                .collect();
+            format!("| {} |", cells.join(" | "))
+        };
+        let separator = vec!["---".to_string(); width];
+        let mut lines = vec![line(&rows[0]), line(&separator)];
+        lines.extend(rows[1..].iter().map(|row| line(row)));
+        lines.join("\n")
+    }
+}
+
+/// Escape the characters of the text Markdown would read as formatting.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '`' | '*' | '_' | '[' | ']' | '<') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Replace each run of whitespace of the text with a space.
+fn collapse_whitespace(text: &str) -> String {
+    let mut collapsed = String::with_capacity(text.len());
+    let mut in_whitespace = false;
+    for c in text.chars() {
+        if c.is_whitespace() {
+            if !in_whitespace {
+                collapsed.push(' ');
+            }
+            in_whitespace = true;
+        } else {
+            collapsed.push(c);
+            in_whitespace = false;
+        }
+    }
+    collapsed
+}
+
+/// Trim the Markdown, and keep at most one blank line between blocks.
+fn tidy(markdown: &str) -> String {
+    let mut tidied = String::with_capacity(markdown.len());
+    let mut newlines = 0;
+    for c in markdown.trim().chars() {
+        if c == '\n' {
+            newlines += 1;
+            if newlines > 2 {
+                continue;
+            }
+        } else {
+            newlines = 0;
+        }
+        tidied.push(c);
+    }
+    tidied
+}
+
+fn block(markdown: &str) -> String {
+    format!("\n\n{}\n\n", tidy(markdown))
+}
+
+/// Script extracting the visible text of the document, or of the element matching the selector.
+pub(crate) fn extract_text_script(selector: Option<&str>) -> String {
+    format!(
+        "{EXTRACT_TEXT_SCRIPT}({})",
+        serde_json::to_string(&selector).unwrap()
+    )
+}
+
+/// Extract the text of the webview on another thread, so a busy page doesn't block Verso, and
+/// send [`ToControllerMessage::ExtractTextResponse`].
+pub(crate) fn send_extracted_text(
+    constellation_sender: Sender<EmbedderToConstellationMessage>,
+    to_controller_sender: IpcSender<ToControllerMessage>,
+    id: uuid::Uuid,
+    webview_id: WebViewId,
+    selector: Option<String>,
+) {
+    let result = thread::Builder::new()
+        .name("TextExtraction".to_owned())
+        .spawn(move || {
+            let script = extract_text_script(selector.as_deref());
+            let text = match execute_script(&constellation_sender, &webview_id, script) {
+                Ok(WebDriverJSValue::String(text)) => Some(text),
+                Ok(_) => None,
+                Err(error) => {
+                    log::warn!("Verso failed to extract the text of the page: {error:?}");
+                    None
+                }
+            };
+            if let Err(error) =
+                to_controller_sender.send(ToControllerMessage::ExtractTextResponse(id, text))
+            {
+                log::error!("Verso failed to send ExtractTextResponse to controller: {error}")
+            }
+        });
+    if let Err(e) = result {
+        log::error!("Failed to spawn text extraction thread: {e}");
+    }
+}
+
+/// Read the content of the webview, convert it to Markdown and write it to the file on another thread,
+/// and send [`ToControllerMessage::SaveAsMarkdownResponse`].
+pub(crate) fn save_as_markdown(
+    constellation_sender: Sender<EmbedderToConstellationMessage>,
+    to_controller_sender: IpcSender<ToControllerMessage>,
+    id: uuid::Uuid,
+    webview_id: WebViewId,
+    path: PathBuf,
+) {
+    let result = thread::Builder::new()
+        .name("MarkdownExport".to_owned())
+        .spawn(move || {
+            let result =
+                match execute_script(&constellation_sender, &webview_id, DOCUMENT_CONTENT_SCRIPT) {
+                    Ok(WebDriverJSValue::String(content)) => {
+                        serde_json::from_str::<DocumentContent>(&content)
+                            .map_err(|e| format!("Failed to read the page: {e}"))
+                            .and_then(|content| {
+                                std::fs::write(&path, content.to_markdown())
+                                    .map_err(|e| format!("Failed to write {}: {e}", path.display()))
+                            })
+                    }
+                    Ok(value) => Err(format!("Unexpected script result: {value:?}")),
+                    Err(error) => Err(format!("Failed to convert the page: {error:?}")),
+                };
+            if let Err(error) =
+                to_controller_sender.send(ToControllerMessage::SaveAsMarkdownResponse(id, result))
+            {
+                log::error!("Verso failed to send SaveAsMarkdownResponse to controller: {error}")
+            }
+        });
+    if let Err(e) = result {
+        log::error!("Failed to spawn markdown export thread: {e}");
+    }
+}
+
+/// Read the loaded document of the webview on another thread, with its text if `with_text` is
+/// `true`, and send [`VersoInternalMsg::PageLoaded`].
+pub(crate) fn send_page_text(
+    constellation_sender: Sender<EmbedderToConstellationMessage>,
+    verso_internal_sender: IpcSender<VersoInternalMsg>,
+    webview_id: WebViewId,
+    with_text: bool,
+) {
+    let result = thread::Builder::new()
+        .name("PageText".to_owned())
+        .spawn(move || {
+            let script = format!("{PAGE_TEXT_SCRIPT}({with_text})");
+            let Ok(WebDriverJSValue::String(page)) =
+                execute_script(&constellation_sender, &webview_id, script)
+            else {
+                return;
+            };
+            match serde_json::from_str::<PageText>(&page) {
+                Ok(page) => {
+                    let _ = verso_internal_sender.send(VersoInternalMsg::PageLoaded(page));
+                }
+                Err(e) => log::error!("Verso failed to read the loaded page: {e}"),
+            }
+        });
+    if let Err(e) = result {
+        log::error!("Failed to spawn page text thread: {e}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn content(value: serde_json::Value) -> DocumentContent {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_to_markdown_inline() {
+        let content = content(serde_json::json!({
+            "title": "Ignored",
+            "nodes": [
+                { "tag": "main" },
+                { "parent": 0, "tag": "h1" },
+                { "parent": 1, "text": "Verso \n  docs" },
+                { "parent": 0, "tag": "p" },
+                { "parent": 3, "text": "Use " },
+                { "parent": 3, "tag": "strong" },
+                { "parent": 5, "text": "*fast*" },
+                { "parent": 3, "text": " and " },
+                { "parent": 3, "tag": "a", "href": "https://servo.org/" },
+                { "parent": 8, "text": "Servo" },
+                { "parent": 3, "tag": "a", "href": "javascript:void(0)" },
+                { "parent": 10, "text": " here" },
+                { "parent": 3, "tag": "em" },
+                { "parent": 12, "text": " " },
+            ],
+        }));
+        assert_eq!(
+            content.to_markdown(),
+            "# Verso docs\n\nUse **\\*fast\\*** and [Servo](<https://servo.org/>)here\n"
+        );
+    }
+
+    #[test]
+    fn test_to_markdown_lists() {
+        let content = content(serde_json::json!({
+            "title": "Page",
+            "nodes": [
+                { "tag": "div" },
+                { "parent": 0, "tag": "ol" },
+                { "parent": 1, "tag": "li" },
+                { "parent": 2, "text": "First" },
+                { "parent": 2, "tag": "ul" },
+                { "parent": 4, "tag": "li" },
+                { "parent": 5, "text": "Nested" },
+                { "parent": 1, "text": "\n" },
+                { "parent": 1, "tag": "li" },
+                { "parent": 8, "tag": "code", "text": "a_b" },
+                { "parent": 0, "tag": "pre", "text": "fn main() {}\n" },
+            ],
+        }));
+        assert_eq!(
+            content.to_markdown(),
+            "# Page\n\n1. First\n\n   - Nested\n2. `a_b`\n\n```\nfn main() {}\n```\n"
+        );
+    }
+
+    #[test]
+    fn test_to_markdown_blocks() {
+        let content = content(serde_json::json!({
+            "title": "",
+            "nodes": [
+                { "tag": "article" },
+                { "parent": 0, "tag": "table" },
+                { "parent": 1, "tag": "thead" },
+                { "parent": 2, "tag": "tr" },
+                { "parent": 3, "tag": "th" },
+                { "parent": 4, "text": "Name" },
+                { "parent": 3, "tag": "th" },
+                { "parent": 6, "text": "Value" },
+                { "parent": 1, "tag": "tbody" },
+                { "parent": 8, "tag": "tr" },
+                { "parent": 9, "tag": "td" },
+                { "parent": 10, "text": "a|b" },
+                { "parent": 0, "tag": "blockquote" },
+                { "parent": 12, "tag": "p" },
+                { "parent": 13, "text": "Quoted" },
+                { "parent": 12, "tag": "p" },
+                { "parent": 15, "text": "Twice" },
+                { "parent": 0, "tag": "img", "src": "https://example.com/logo.png", "alt": "Logo [1]" },
+            ],
+        }));
+        assert_eq!(
+            content.to_markdown(),
+            "| Name | Value |\n| --- | --- |\n| a\\|b |  |\n\n> Quoted\n> \n> Twice\n\n![Logo \\[1\\]](<https://example.com/logo.png>)\n"
+        );
+    }
+
+    #[test]
+    fn test_to_markdown_empty() {
+        assert_eq!(DocumentContent::default().to_markdown(), "\n");
+        let content = content(serde_json::json!({ "title": "A_title", "nodes": [] }));
+        assert_eq!(content.to_markdown(), "# A\\_title\n");
+    }
+
+    #[test]
+    fn test_tidy() {
+        assert_eq!(tidy("\n\n a\n\n\n\nb \n"), "a\n\nb");
+        assert_eq!(collapse_whitespace("a \t\n b"), "a b");
+        assert_eq!(escape("<a> [b](c) `d`"), "\\<a> \\[b\\](c) \\`d\\`");
+    }
+}
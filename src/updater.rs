@@ -0,0 +1,321 @@
+use std::{
+    cmp::Ordering,
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    thread,
+    time::Duration,
+};
+
+use base64::Engine;
+use rustls::SignatureScheme;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use versoview_messages::{UpdateChannel, UpdateConfig};
+
+/// Public key the releases are signed with if the config doesn't set one, base64 encoded.
+const BUILD_PUBLIC_KEY: Option<&str> = option_env!("VERSO_UPDATE_PUBLIC_KEY");
+
+/// Target triple Verso was built for, to pick the binary of this platform.
+const TARGET: &str = env!("TARGET");
+
+/// Timeout of the requests to the update server.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Releases of every channel, as served at the update URL.
+///
+/// ```json
+/// {
+///   "stable": {
+///     "version": "0.2.0",
+///     "platforms": {
+///       "x86_64-unknown-linux-gnu": { "url": "https://...", "signature": "BASE64" }
+///     }
+///   },
+///   "nightly": { ... }
+/// }
+/// ```
+#[derive(Debug, Deserialize)]
+struct UpdateManifest {
+    stable: Option<Release>,
+    nightly: Option<Release>,
+}
+
+/// The latest release of a channel.
+#[derive(Debug, Deserialize)]
+struct Release {
+    version: String,
+    platforms: HashMap<String, ReleaseBinary>,
+}
+
+/// The binary of a release for a platform.
+#[derive(Debug, Deserialize)]
+struct ReleaseBinary {
+    url: url::Url,
+    /// Base64 Ed25519 signature of the release, see [`signed_payload`]
+    signature: String,
+}
+
+/// Check for a newer release of the channel on another thread, download it and verify its
+/// signature. The new binary is staged next to the current one, and replaces it with
+/// [`apply_staged_update`] when Verso shuts down.
+pub(crate) fn check_for_update(config: UpdateConfig) {
+    let Some(public_key) = config
+        .public_key
+        .as_deref()
+        .or(BUILD_PUBLIC_KEY)
+        .and_then(|key| base64::engine::general_purpose::STANDARD.decode(key).ok())
+    else {
+        log::warn!("Updates are disabled, no valid public key to verify releases with");
+        return;
+    };
+    let result =
+        thread::Builder::new()
+            .name("Updater".to_owned())
+            .spawn(move || match stage_update(&config, &public_key) {
+                Ok(Some(version)) => log::info!("Verso {version} will be installed on restart"),
+                Ok(None) => log::debug!("Verso is up to date"),
+                Err(e) => log::error!("Failed to update Verso: {e}"),
+            });
+    if let Err(e) = result {
+        log::error!("Failed to spawn updater thread: {e}");
+    }
+}
+
+/// Download and stage the latest release if it's newer, returns its version.
+fn stage_update(
+    config: &UpdateConfig,
+    public_key: &[u8],
+) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    let client = reqwest::blocking::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()?;
+    let manifest: UpdateManifest = client
+        .get(config.url.clone())
+        .send()?
+        .error_for_status()?
+        .json()?;
+    let release = match config.channel {
+        UpdateChannel::Stable => manifest.stable,
+        UpdateChannel::Nightly => manifest.nightly,
+    };
+    let Some(release) = release else {
+        return Ok(None);
+    };
+    if compare_versions(&release.version, env!("CARGO_PKG_VERSION")) != Ordering::Greater {
+        return Ok(None);
+    }
+    let Some(binary) = release.platforms.get(TARGET) else {
+        return Err(format!("Release {} has no binary for {TARGET}", release.version).into());
+    };
+
+    let bytes = client
+        .get(binary.url.clone())
+        .send()?
+        .error_for_status()?
+        .bytes()?;
+    let signature = base64::engine::general_purpose::STANDARD.decode(&binary.signature)?;
+    verify_signature(
+        public_key,
+        &signed_payload(&release.version, TARGET, &bytes),
+        &signature,
+    )?;
+
+    let staged_path = staged_binary_path()?;
+    fs::write(&staged_path, &bytes)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&staged_path, fs::Permissions::from_mode(0o755))?;
+    }
+    Ok(Some(release.version))
+}
+
+/// What the signature of a release signs: its version and target with the hash of the binary, so
+/// an older signed binary, or the binary of another platform, can't be served as the latest
+/// release.
+///
+/// ```text
+/// verso-update
+/// version=0.2.0
+/// target=x86_64-unknown-linux-gnu
+/// sha256=<lowercase hex SHA-256 of the binary>
+/// ```
+fn signed_payload(version: &str, target: &str, binary: &[u8]) -> Vec<u8> {
+    let hash: String = Sha256::digest(binary)
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect();
+    format!("verso-update\nversion={version}\ntarget={target}\nsha256={hash}\n").into_bytes()
+}
+
+/// Verify the Ed25519 signature of the message, with the algorithms of the crypto provider rustls
+/// uses.
+fn verify_signature(
+    public_key: &[u8],
+    message: &[u8],
+    signature: &[u8],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let algorithms =
+        rustls::crypto::aws_lc_rs::default_provider().signature_verification_algorithms;
+    let algorithm = algorithms
+        .mapping
+        .iter()
+        .find(|(scheme, _)| *scheme == SignatureScheme::ED25519)
+        .and_then(|(_, algorithms)| algorithms.first())
+        .ok_or("Ed25519 isn't supported by the crypto provider")?;
+    algorithm
+        .verify_signature(public_key, message, signature)
+        .map_err(|_| "The signature of the release is invalid".into())
+}
+
+/// Replace the current binary with the staged update, if there is one, so the next launch runs
+/// the new release. The running binary is moved aside first, which also works on Windows.
+pub(crate) fn apply_staged_update() {
+    let (Ok(current_path), Ok(staged_path)) = (std::env::current_exe(), staged_binary_path())
+    else {
+        return;
+    };
+    if !staged_path.exists() {
+        return;
+    }
+    let previous_path = previous_binary_path(&current_path);
+    let result = fs::rename(&current_path, &previous_path).and_then(|_| {
+        fs::rename(&staged_path, &current_path).inspect_err(|_| {
+            // Put the current binary back, so Verso can still be launched
+            let _ = fs::rename(&previous_path, &current_path);
+        })
+    });
+    if let Err(e) = result {
+        log::error!("Failed to install the update of Verso: {e}");
+    }
+}
+
+/// Remove the binary replaced by the last update.
+pub(crate) fn remove_previous_binary() {
+    if let Ok(current_path) = std::env::current_exe() {
+        let _ = fs::remove_file(previous_binary_path(&current_path));
+    }
+}
+
+fn staged_binary_path() -> std::io::Result<PathBuf> {
+    let current_path = std::env::current_exe()?;
+    Ok(current_path.with_file_name(format!(
+        "{}.update",
+        current_path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+    )))
+}
+
+fn previous_binary_path(current_path: &Path) -> PathBuf {
+    current_path.with_file_name(format!(
+        "{}.old",
+        current_path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+    ))
+}
+
+/// Compare `MAJOR.MINOR.PATCH[-PRERELEASE]` versions. A release is newer than its prereleases,
+/// and prereleases are compared as text, so nightlies should be tagged by date, e.g.
+/// `0.2.0-nightly.20250101`.
+fn compare_versions(a: &str, b: &str) -> Ordering {
+    let parse = |version: &str| {
+        let (release, prerelease) = match version.split_once('-') {
+            Some((release, prerelease)) => (release.to_string(), Some(prerelease.to_string())),
+            None => (version.to_string(), None),
+        };
+        let numbers: Vec<u64> = release
+            .split('.')
+            .map(|number| number.parse().unwrap_or_default())
+            .collect();
+        (numbers, prerelease)
+    };
+    let (a_numbers, a_prerelease) = parse(a);
+    let (b_numbers, b_prerelease) = parse(b);
+    a_numbers
+        .cmp(&b_numbers)
+        .then_with(|| match (a_prerelease, b_prerelease) {
+            (None, None) => Ordering::Equal,
+            (None, Some(_)) => Ordering::Greater,
+            (Some(_), None) => Ordering::Less,
+            (Some(a), Some(b)) => a.cmp(&b),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Public key of the first test vector of RFC 8032, which signed [`SIGNATURE`].
+    const PUBLIC_KEY: &str = "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a";
+    /// Signature of the payload of release 0.2.0 of `x86_64-unknown-linux-gnu`, with the binary
+    /// `verso binary`.
+    const SIGNATURE: &str =
+        "qy1+GY3D/3qXt8oC5UyoGNzvoOTKXZkbjfgbBuC/KdlWpDP1dFOZpYJWSIJPb83sD23RZ/vUa86YzKCJVqAIAQ==";
+
+    fn verify(version: &str, target: &str, binary: &[u8]) -> bool {
+        let public_key: Vec<u8> = (0..PUBLIC_KEY.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&PUBLIC_KEY[i..i + 2], 16).unwrap())
+            .collect();
+        let signature = base64::engine::general_purpose::STANDARD
+            .decode(SIGNATURE)
+            .unwrap();
+        verify_signature(
+            &public_key,
+            &signed_payload(version, target, binary),
+            &signature,
+        )
+        .is_ok()
+    }
+
+    #[test]
+    fn test_verify_signature() {
+        assert!(verify("0.2.0", "x86_64-unknown-linux-gnu", b"verso binary"));
+        assert!(!verify(
+            "0.2.0",
+            "x86_64-unknown-linux-gnu",
+            b"other binary"
+        ));
+        assert!(!verify(
+            "0.3.0",
+            "x86_64-unknown-linux-gnu",
+            b"verso binary"
+        ));
+        assert!(!verify("0.2.0", "x86_64-pc-windows-msvc", b"verso binary"));
+    }
+
+    #[test]
+    fn test_signed_payload() {
+        assert_eq!(
+            signed_payload("0.2.0", "x86_64-unknown-linux-gnu", b"verso binary"),
+            b"verso-update\nversion=0.2.0\ntarget=x86_64-unknown-linux-gnu\n\
+              sha256=ae84522af16e83896123ecdb06c011d6addb684f8ea4d4df7780f068905af963\n"
+        );
+    }
+
+    #[test]
+    fn test_compare_versions() {
+        assert_eq!(compare_versions("0.2.0", "0.1.9"), Ordering::Greater);
+        assert_eq!(compare_versions("0.10.0", "0.9.0"), Ordering::Greater);
+        assert_eq!(compare_versions("1.0.0", "1.0.0"), Ordering::Equal);
+        assert_eq!(compare_versions("0.1.0", "0.2.0"), Ordering::Less);
+        // Releases are newer than their prereleases
+        assert_eq!(
+            compare_versions("0.2.0", "0.2.0-nightly.20250101"),
+            Ordering::Greater
+        );
+        assert_eq!(
+            compare_versions("0.2.0-nightly.20250101", "0.2.0"),
+            Ordering::Less
+        );
+        assert_eq!(
+            compare_versions("0.2.0-nightly.20250102", "0.2.0-nightly.20250101"),
+            Ordering::Greater
+        );
+    }
+}
@@ -0,0 +1,86 @@
+use std::collections::HashMap;
+
+use constellation_traits::EmbedderToConstellationMessage;
+use crossbeam_channel::Sender;
+use embedder_traits::{EmbedderMsg, WebResourceResponseMsg};
+use servo_url::ServoUrl;
+use versoview_messages::FileAccessPolicy;
+use webrender_api::DocumentId;
+use winit::window::WindowId;
+
+use crate::{verso::send_to_constellation, window::Window};
+
+/// Check if the policy allows loading this URL, URLs other than `file://` are always allowed.
+pub(crate) fn is_url_allowed(policy: &FileAccessPolicy, url: &ServoUrl) -> bool {
+    if url.scheme() != "file" {
+        return true;
+    }
+    if !policy.enabled {
+        return false;
+    }
+    if policy.allowed_roots.is_empty() {
+        return true;
+    }
+    let Ok(path) = url.as_url().to_file_path() else {
+        return false;
+    };
+    // Resolve symlinks so they can't point outside of the allowed roots
+    let path = path.canonicalize().unwrap_or(path);
+    policy
+        .allowed_roots
+        .iter()
+        .any(|root| path.starts_with(root))
+}
+
+/// Apply the `file://` access policy to an embedder message. Navigation and resource requests
+/// denied by the policy are answered here and `None` is returned, other messages are given back.
+pub(crate) fn apply_file_access_policy(
+    policy: &FileAccessPolicy,
+    msg: EmbedderMsg,
+    windows: &HashMap<WindowId, (Window, DocumentId)>,
+    constellation_sender: &Sender<EmbedderToConstellationMessage>,
+) -> Option<EmbedderMsg> {
+    match msg {
+        EmbedderMsg::AllowNavigationRequest(_, pipeline_id, ref url)
+            if !is_url_allowed(policy, url) =>
+        {
+            log::warn!("Verso blocked navigation to {url} by file access policy");
+            send_to_constellation(
+                constellation_sender,
+                EmbedderToConstellationMessage::AllowNavigationResponse(pipeline_id, false),
+            );
+            None
+        }
+        EmbedderMsg::WebResourceRequested(webview_id, ref request, ref sender) => {
+            let url = ServoUrl::from_url(request.url.clone());
+            let blocked = if !is_url_allowed(policy, &url) {
+                true
+            } else if policy.block_remote_requests && !request.is_for_main_frame {
+                // We can't tell if cookies will be attached to the request at this point,
+                // so every remote subresource request of a file document is blocked.
+                let from_file_document = webview_id
+                    .and_then(|webview_id| {
+                        windows
+                            .values()
+                            .find_map(|(window, _)| window.tab_manager.tab(webview_id))
+                    })
+                    .and_then(|tab| tab.current_url())
+                    .is_some_and(|document_url| document_url.scheme() == "file");
+                from_file_document && matches!(url.scheme(), "http" | "https" | "ws" | "wss")
+            } else {
+                false
+            };
+
+            if blocked {
+                log::warn!("Verso blocked request to {url} by file access policy");
+                if let Err(error) = sender.send(WebResourceResponseMsg::CancelLoad) {
+                    log::error!("Verso failed to cancel blocked request: {error}");
+                }
+                None
+            } else {
+                Some(msg)
+            }
+        }
+        msg => Some(msg),
+    }
+}
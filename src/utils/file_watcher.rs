@@ -0,0 +1,64 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    thread,
+    time::{Duration, SystemTime},
+};
+
+use ipc_channel::ipc::IpcSender;
+
+use crate::verso::VersoInternalMsg;
+
+/// How often the watched directory is scanned for changes.
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Spawn a thread polling the modification time of every file under `directory`, and send
+/// [`VersoInternalMsg::WatchedFilesChanged`] whenever a file is created, modified or removed.
+pub(crate) fn watch_directory(directory: PathBuf, sender: IpcSender<VersoInternalMsg>) {
+    let result = thread::Builder::new()
+        .name("FileWatcher".to_owned())
+        .spawn(move || {
+            let mut files = scan_directory(&directory);
+            loop {
+                thread::sleep(POLL_INTERVAL);
+                let new_files = scan_directory(&directory);
+                if new_files != files {
+                    files = new_files;
+                    if let Err(e) = sender.send(VersoInternalMsg::WatchedFilesChanged) {
+                        log::error!("File watcher failed to notify Verso, stop watching: {e}");
+                        break;
+                    }
+                }
+            }
+        });
+    if let Err(e) = result {
+        log::error!("Failed to spawn file watcher thread: {e}");
+    }
+}
+
+/// Collect the modification time of every file under `directory`, hidden entries like `.git`
+/// are skipped.
+fn scan_directory(directory: &Path) -> HashMap<PathBuf, SystemTime> {
+    let mut files = HashMap::new();
+    let mut directories = vec![directory.to_path_buf()];
+    while let Some(directory) = directories.pop() {
+        let Ok(entries) = fs::read_dir(&directory) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            if entry.file_name().to_string_lossy().starts_with('.') {
+                continue;
+            }
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            if metadata.is_dir() {
+                directories.push(entry.path());
+            } else if let Ok(modified) = metadata.modified() {
+                files.insert(entry.path(), modified);
+            }
+        }
+    }
+    files
+}
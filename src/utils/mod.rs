@@ -1 +1,3 @@
 pub(crate) mod content_disposition_parser;
+pub(crate) mod file_access;
+pub(crate) mod file_watcher;
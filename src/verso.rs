@@ -1,12 +1,18 @@
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     fmt::Debug,
+    io::Cursor,
+    path::{Path, PathBuf},
+    str::FromStr,
     sync::{Arc, atomic::Ordering},
+    time::{Duration, Instant},
 };
 
 use arboard::Clipboard;
 use base::id::{PipelineNamespace, PipelineNamespaceId, WebViewId};
+#[cfg(feature = "bluetooth")]
 use bluetooth::BluetoothThreadFactory;
+#[cfg(feature = "bluetooth")]
 use bluetooth_traits::BluetoothRequest;
 use canvas::canvas_paint_thread::CanvasPaintThread;
 use compositing_traits::{
@@ -14,46 +20,107 @@ use compositing_traits::{
     WebrenderImageHandlerType,
 };
 use constellation::{Constellation, FromEmbedderLogger, InitialConstellationState};
-use constellation_traits::EmbedderToConstellationMessage;
+use constellation_traits::{EmbedderToConstellationMessage, TraversalDirection};
 use crossbeam_channel::{Receiver, Sender, unbounded};
+#[cfg(feature = "devtools")]
 use devtools;
 use embedder_traits::{
-    AllowOrDeny, EmbedderMsg, EmbedderProxy, EventLoopWaker, PromptResponse, WebResourceResponse,
-    WebResourceResponseMsg, user_content_manager::UserContentManager,
+    AllowOrDeny, EmbedderMsg, EmbedderProxy, EventLoopWaker, LoadStatus, PromptResponse,
+    WebResourceResponse, WebResourceResponseMsg, user_content_manager::UserContentManager,
 };
 use euclid::Scale;
 use fonts::SystemFontService;
+use image::ImageFormat;
 use ipc_channel::ipc::{self, IpcSender};
 use ipc_channel::router::ROUTER;
 use layout_thread_2020;
 use log::{Log, Metadata, Record};
 use net::resource_thread;
+use net_traits::ResourceThreads;
 use profile;
 use script::{self, JSEngineSetup};
 use serde::{Deserialize, Serialize};
 use servo_config::{opts, pref};
 use servo_url::ServoUrl;
 use style;
-use versoview_messages::{PositionType, SizeType, ToControllerMessage, ToVersoMessage};
+use versoview_messages::{
+    BlockedSubresource, BrowserImport, CertificateErrorPolicy, ClipboardAction,
+    ConfigFromController, ControlCommand, ControlErrorCode, ControlEvent, ControlOutcome,
+    ControlRequest, ControlWebView, CspViolation, DEFAULT_CONTROL_LEASE, FindResult,
+    FontAntialiasing, FrameInput, GeolocationPosition, HistoryMatch, HttpCredentials, Icon,
+    ImagePolicy, PositionType, PrivacySettings, ResourceLimit, SizeType, TabGroupInfo,
+    ToControllerMessage, ToVersoMessage, VirtualKeyboard,
+};
+#[cfg(feature = "webgpu")]
 use webgpu;
 use webrender::{ShaderPrecacheFlags, WebRenderOptions, create_webrender_instance};
 use webrender_api::*;
 use winit::{
+    dpi::{LogicalSize, PhysicalSize},
     event::WindowEvent,
     event_loop::{ActiveEventLoop, ControlFlow, EventLoopProxy},
     window::WindowId,
 };
 
 use crate::{
-    bookmark::{BookmarkId, BookmarkManager},
+    auth_flow::{AuthFlow, AuthFlows},
+    bookmark::{self, Bookmark, BookmarkId, BookmarkManager},
+    cert_pinning::CertificatePins,
+    clipboard::{self, ClipboardContent},
+    command::{Command, CommandAction},
     compositor::{IOCompositor, InitialCompositorState, ShutdownState},
     config::{Config, parse_cli_args},
-    download::{DownloadId, DownloadItem, UpdateDownloadState},
-    storage::Storage,
+    control::{self, ControlAccess, ControlClient, ControlClients},
+    cookies::CookieJar,
+    crash_reporter, csp,
+    devtools_bridge::{self, DevtoolsBridge},
+    download::{DownloadControl, DownloadId, DownloadItem, UpdateDownloadState},
+    file_picker, find, forms, frames,
+    geolocation::{self, geolocation_result_script},
+    highlight::{HighlightManager, NewHighlight},
+    history::HistoryStore,
+    http_auth::{self, RememberedCredentials},
+    http_cache::DiskCache,
+    images::apply_image_policy,
+    import, integrity, javascript,
+    metrics::{self, Metrics},
+    placement::{WindowPlacement, WindowPlacements},
+    power,
+    power::{PowerState, SleepInhibitors, battery_status_script},
+    private,
+    resource_limits::{apply_request_limit, notify_limit_exceeded},
+    resource_override::{ResourceOverrides, apply_mock_network},
+    secrets::{SecretRequest, SecretStore},
+    sensors::{self, SensorReading, sensor_reading_script},
+    session::{self, SessionManager, SessionTab, SessionWindow},
+    shader_cache::ShaderCache,
+    share::{self, ShareData, ShareOutcome},
+    snap,
+    storage::{self, Storage, shader_cache_directory},
+    tab::Tab,
+    text::{self, PageText},
+    updater,
+    utils::{file_access, file_watcher},
+    wait,
+    watchdog::ScriptWatchdog,
+    web_app::{WebApp, WebAppManifest, apply_manifest, parse_color, set_titlebar_color},
+    webdriver,
     webview::execute_script,
     window::Window,
 };
 
+/// Namespace of the secrets saved by the controller, page namespaces are origins
+/// so they never collide with it.
+const CONTROLLER_SECRET_NAMESPACE: &str = "controller";
+/// Most matches returned by a history search.
+const HISTORY_SEARCH_LIMIT: usize = 100;
+/// Most suggestions of the history for the URL bar.
+const HISTORY_SUGGEST_LIMIT: usize = 8;
+/// Bytes in a megabyte.
+const MEGABYTE: u64 = 1024 * 1024;
+/// How often the open windows and tabs are saved in the session.
+const SESSION_SAVE_INTERVAL: Duration = Duration::from_secs(30);
+
 /// Main entry point of Verso browser.
 pub struct Verso {
     windows: HashMap<WindowId, (Window, DocumentId)>,
@@ -71,7 +138,56 @@ pub struct Verso {
     config: Config,
     storage: Storage,
     bookmark_manager: BookmarkManager,
+    session_manager: SessionManager,
+    highlight_manager: HighlightManager,
+    /// Page zoom remembered for each site.
+    zoom_levels: ZoomLevels,
+    /// Which images pages load, saved in the profile.
+    image_policy: ImagePolicy,
+    resource_overrides: ResourceOverrides,
+    secret_store: SecretStore,
+    /// HTTP authentication credentials the user chose to remember.
+    remembered_credentials: RememberedCredentials,
+    auth_flows: AuthFlows,
+    certificate_pins: CertificatePins,
+    /// Hosts the controller allowed to load with an invalid certificate in this session,
+    /// lowercase.
+    certificate_overrides: HashSet<String>,
+    history: Option<HistoryStore>,
+    resource_threads: ResourceThreads,
+    cookie_jar: CookieJar,
+    /// HTTP cache of the profile, `None` for private windows. It's skipped in mock network mode.
+    http_cache: Option<DiskCache>,
+    /// Network requests are only answered by the controller in mock network mode.
+    mock_network: bool,
+    /// Files the file pickers of pages get instead of showing a dialog.
+    file_picker_override: Option<Vec<PathBuf>>,
     downloads: HashMap<DownloadId, DownloadItem>,
+    power_state: PowerState,
+    /// Latest readings of the sensors of the device.
+    sensor_reading: SensorReading,
+    /// Position reported to pages instead of the one of the system.
+    mock_geolocation: Option<GeolocationPosition>,
+    sleep_inhibitors: SleepInhibitors,
+    /// The site running as a standalone app, see `--app`.
+    web_app: Option<WebApp>,
+    /// Detects tabs whose script is hung or exceeds its time budget.
+    script_watchdog: ScriptWatchdog,
+    /// Tabs showing the slow script dialog.
+    slow_script_dialogs: HashSet<WebViewId>,
+    /// Tabs reloaded because watched files changed, their requests bypass the HTTP cache until
+    /// they're loaded.
+    watch_reloads: HashSet<WebViewId>,
+    /// Whether the subsystems started after the first page loaded are started.
+    deferred_subsystems_started: bool,
+    /// Performance counters, only recorded if metrics are enabled.
+    metrics: Metrics,
+    /// When the navigation of webviews which are loading started, for the metrics.
+    load_starts: HashMap<WebViewId, Instant>,
+    /// Chrome DevTools Protocol server, see `--cdp-port`.
+    devtools_bridge: Option<DevtoolsBridge>,
+    /// Clients of the control protocol, their leases and subscriptions.
+    control_clients: ControlClients,
 }
 
 /// Message for Verso internal communication
@@ -79,6 +195,8 @@ pub struct Verso {
 pub enum VersoInternalMsg {
     /// Abort a download
     AbortDownload(DownloadId),
+    /// Pause or resume a download.
+    ControlDownload(DownloadId, DownloadControl),
     /// Create a download state in Verso.
     CreateDownload(DownloadItem),
     /// Update a specific download state.
@@ -91,18 +209,177 @@ pub enum VersoInternalMsg {
     BookmarkRemove(BookmarkId),
     /// Rename a bookmark in the bookmark manager.
     BookmarkRename(BookmarkId, String),
+    /// Bookmark the page with its title and URL, or remove its bookmark if it's bookmarked.
+    ToggleBookmark(String, String),
+    /// Import the bookmarks of a Netscape bookmarks HTML file chosen by the user.
+    ImportBookmarks,
+    /// Export the bookmarks to a Netscape bookmarks HTML file chosen by the user.
+    ExportBookmarks,
+    /// Send the highlights of the page to the frontend.
+    HighlightList(ServoUrl, IpcSender<PromptResponse>),
+    /// Highlight a passage of the page, and send the ID of the highlight to the frontend.
+    HighlightAdd(ServoUrl, NewHighlight, IpcSender<PromptResponse>),
+    /// Remove a highlight of the page.
+    HighlightRemove(ServoUrl, uuid::Uuid),
+    /// Save the highlights of every page to a Markdown file chosen by the user.
+    ExportHighlights,
+    /// Export the recorded performance metrics to a file chosen by the user.
+    ExportMetrics,
+    /// Save the open windows and tabs in the session.
+    SaveSession,
+    /// Send the crash reports to the crash reports page.
+    CrashList(IpcSender<PromptResponse>),
+    /// Submit a crash report, and send whether it was submitted to the crash reports page.
+    CrashSubmit(uuid::Uuid, IpcSender<PromptResponse>),
+    /// Delete a crash report.
+    CrashDelete(uuid::Uuid),
+    /// Files in the watched directory have changed.
+    WatchedFilesChanged,
+    /// The power state of the system has changed.
+    PowerStateChanged(PowerState),
+    /// Get the position for a request of the document of the webview.
+    GetGeolocation(WebViewId, u64, bool),
+    /// The platform provider found the position for a request of the document of the webview.
+    GeolocationResult(WebViewId, u64, Result<GeolocationPosition, String>),
+    /// Share data from the document of the webview with the share sheet of the system.
+    Share(WebViewId, ShareData, IpcSender<PromptResponse>),
+    /// The readings of the sensors of the device have changed.
+    SensorReadingChanged(SensorReading),
+    /// Send the latest sensor readings to a document.
+    GetSensorReading(IpcSender<PromptResponse>),
+    /// Send the battery status to a document.
+    GetBatteryStatus(IpcSender<PromptResponse>),
+    /// The document of the webview acquired or released its screen wake locks.
+    SetWakeLock(WebViewId, bool),
+    /// Send the privacy settings of a tab to its document, the config's ones are used if `None`.
+    GetPrivacySettings(Option<PrivacySettings>, IpcSender<PromptResponse>),
+    /// The script of the webview hasn't responded for the slow script timeout.
+    ScriptHung(WebViewId),
+    /// The script of the webview responds again after hanging.
+    ScriptResponsive(WebViewId),
+    /// A tab finished loading its document, record it in the history.
+    PageLoaded(PageText),
+    /// Search the history for the history page.
+    SearchHistory(String, IpcSender<PromptResponse>),
+    /// Suggest pages of the history for the text typed in the URL bar.
+    SuggestHistory(String, IpcSender<PromptResponse>),
+    /// Clear the history.
+    ClearHistory,
+    /// The script of the webview ran for longer than its budget.
+    ScriptTimeLimitExceeded(WebViewId),
+    /// A download of the webview was cancelled for exceeding its budget.
+    DownloadSizeLimitExceeded(WebViewId),
+    /// The user chose to stop the hung tab, or to wait if `false`.
+    SlowScriptDialogResponse(WebViewId, bool),
+    /// The `theme-color` of the document of the webview changed.
+    ThemeColorChanged(WebViewId, Option<String>),
+    /// The webview became the current tab of its window, so the titlebar uses its theme color.
+    UpdateTitlebarColor(WebViewId),
+    /// The manifest of the standalone app was loaded by the document of the webview, with the
+    /// icon for its window.
+    WebAppManifestLoaded(WebViewId, WebAppManifest, Option<Icon>),
+    /// Get a secret saved by the origin of the request through the secrets bridge.
+    SecretGet(SecretRequest, IpcSender<PromptResponse>),
+    /// Save the secret for the origin of the request through the secrets bridge.
+    SecretSet(SecretRequest, String, IpcSender<PromptResponse>),
+    /// Delete a secret saved by the origin of the request through the secrets bridge.
+    SecretDelete(SecretRequest, IpcSender<PromptResponse>),
+    /// The user zoomed the page of the URL, remember the zoom for its origin, or forget it if
+    /// `None`.
+    ZoomChanged(ServoUrl, Option<f32>),
+    /// Open a private window in a process of its own.
+    OpenPrivateWindow,
+    /// Copy, cut or paste in the focused element of the webview.
+    Clipboard(WebViewId, ClipboardAction),
+    /// Write to the clipboard what a page copied, or a link a context menu is opened on.
+    SetClipboard(ClipboardContent),
+    /// The page of the webview violated its Content Security Policy.
+    CspViolation(WebViewId, CspViolation),
+    /// A script or stylesheet of the page of the webview was blocked.
+    SubresourceBlocked(WebViewId, BlockedSubresource),
+    /// Tell a document whether its origin runs JavaScript, or whether every origin does if it's
+    /// `None` because the document isn't of the tab's origin.
+    GetJavaScriptEnabled(Option<String>, IpcSender<PromptResponse>),
+    /// List the settings of the site settings page.
+    SiteSettings(IpcSender<PromptResponse>),
+    /// Set whether the pages of the origin run JavaScript, or of every origin if `None`, from the
+    /// site settings page.
+    SetJavaScriptEnabled(Option<url::Url>, Option<bool>),
+    /// Remember the credentials the user signed in to the origin with, for a proxy if `true`.
+    RememberCredentials(url::Url, bool, HttpCredentials),
+    /// Send the image policy to a document.
+    GetImagePolicy(IpcSender<PromptResponse>),
+    /// Tell the page shown instead of the host with an invalid certificate whether the controller
+    /// allowed it and whether the user can proceed. The host is `None` if it isn't the one of the
+    /// tab.
+    CertificateError(Option<String>, IpcSender<PromptResponse>),
+    /// Load the blocked image of this URL in the tab, the user clicked it.
+    LoadImage(WebViewId, url::Url),
 }
 
 impl Debug for VersoInternalMsg {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             VersoInternalMsg::AbortDownload(_) => write!(f, "AbortDownload"),
+            VersoInternalMsg::ControlDownload(_, _) => write!(f, "ControlDownload"),
             VersoInternalMsg::CreateDownload(_) => write!(f, "CreateDownload"),
             VersoInternalMsg::UpdateDownload(_, _) => write!(f, "UpdateDownload"),
             VersoInternalMsg::UpdateDownloadsPage(_) => write!(f, "UpdateDownloadsPageStatus"),
             VersoInternalMsg::UpdateBookmarkManager(_) => write!(f, "UpdateBookmarkManager"),
             VersoInternalMsg::BookmarkRemove(_) => write!(f, "BookmarkRemove"),
             VersoInternalMsg::BookmarkRename(_, _) => write!(f, "BookmarkRename"),
+            VersoInternalMsg::ToggleBookmark(..) => write!(f, "ToggleBookmark"),
+            VersoInternalMsg::ImportBookmarks => write!(f, "ImportBookmarks"),
+            VersoInternalMsg::ExportBookmarks => write!(f, "ExportBookmarks"),
+            VersoInternalMsg::HighlightList(..) => write!(f, "HighlightList"),
+            VersoInternalMsg::HighlightAdd(..) => write!(f, "HighlightAdd"),
+            VersoInternalMsg::HighlightRemove(..) => write!(f, "HighlightRemove"),
+            VersoInternalMsg::ExportHighlights => write!(f, "ExportHighlights"),
+            VersoInternalMsg::ExportMetrics => write!(f, "ExportMetrics"),
+            VersoInternalMsg::OpenPrivateWindow => write!(f, "OpenPrivateWindow"),
+            VersoInternalMsg::SaveSession => write!(f, "SaveSession"),
+            VersoInternalMsg::CrashList(_) => write!(f, "CrashList"),
+            VersoInternalMsg::CrashSubmit(..) => write!(f, "CrashSubmit"),
+            VersoInternalMsg::CrashDelete(_) => write!(f, "CrashDelete"),
+            VersoInternalMsg::WatchedFilesChanged => write!(f, "WatchedFilesChanged"),
+            VersoInternalMsg::PowerStateChanged(_) => write!(f, "PowerStateChanged"),
+            VersoInternalMsg::GetGeolocation(..) => write!(f, "GetGeolocation"),
+            VersoInternalMsg::GeolocationResult(..) => write!(f, "GeolocationResult"),
+            VersoInternalMsg::Share(..) => write!(f, "Share"),
+            VersoInternalMsg::SensorReadingChanged(_) => write!(f, "SensorReadingChanged"),
+            VersoInternalMsg::GetSensorReading(_) => write!(f, "GetSensorReading"),
+            VersoInternalMsg::GetBatteryStatus(_) => write!(f, "GetBatteryStatus"),
+            VersoInternalMsg::SetWakeLock(..) => write!(f, "SetWakeLock"),
+            VersoInternalMsg::GetPrivacySettings(..) => write!(f, "GetPrivacySettings"),
+            VersoInternalMsg::ScriptHung(_) => write!(f, "ScriptHung"),
+            VersoInternalMsg::ScriptResponsive(_) => write!(f, "ScriptResponsive"),
+            VersoInternalMsg::PageLoaded(_) => write!(f, "PageLoaded"),
+            VersoInternalMsg::SearchHistory(..) => write!(f, "SearchHistory"),
+            VersoInternalMsg::SuggestHistory(..) => write!(f, "SuggestHistory"),
+            VersoInternalMsg::ClearHistory => write!(f, "ClearHistory"),
+            VersoInternalMsg::ScriptTimeLimitExceeded(_) => write!(f, "ScriptTimeLimitExceeded"),
+            VersoInternalMsg::DownloadSizeLimitExceeded(_) => {
+                write!(f, "DownloadSizeLimitExceeded")
+            }
+            VersoInternalMsg::SlowScriptDialogResponse(..) => write!(f, "SlowScriptDialogResponse"),
+            VersoInternalMsg::ThemeColorChanged(..) => write!(f, "ThemeColorChanged"),
+            VersoInternalMsg::UpdateTitlebarColor(_) => write!(f, "UpdateTitlebarColor"),
+            VersoInternalMsg::WebAppManifestLoaded(..) => write!(f, "WebAppManifestLoaded"),
+            VersoInternalMsg::SecretGet(..) => write!(f, "SecretGet"),
+            VersoInternalMsg::SecretSet(..) => write!(f, "SecretSet"),
+            VersoInternalMsg::SecretDelete(..) => write!(f, "SecretDelete"),
+            VersoInternalMsg::ZoomChanged(..) => write!(f, "ZoomChanged"),
+            VersoInternalMsg::Clipboard(..) => write!(f, "Clipboard"),
+            VersoInternalMsg::SetClipboard(_) => write!(f, "SetClipboard"),
+            VersoInternalMsg::CspViolation(..) => write!(f, "CspViolation"),
+            VersoInternalMsg::SubresourceBlocked(..) => write!(f, "SubresourceBlocked"),
+            VersoInternalMsg::GetJavaScriptEnabled(..) => write!(f, "GetJavaScriptEnabled"),
+            VersoInternalMsg::SiteSettings(_) => write!(f, "SiteSettings"),
+            VersoInternalMsg::SetJavaScriptEnabled(..) => write!(f, "SetJavaScriptEnabled"),
+            VersoInternalMsg::RememberCredentials(..) => write!(f, "RememberCredentials"),
+            VersoInternalMsg::GetImagePolicy(_) => write!(f, "GetImagePolicy"),
+            VersoInternalMsg::CertificateError(..) => write!(f, "CertificateError"),
+            VersoInternalMsg::LoadImage(..) => write!(f, "LoadImage"),
         }
     }
 }
@@ -126,24 +403,79 @@ impl Verso {
     /// - Image Cache: Enabled
     pub fn new(evl: &ActiveEventLoop, proxy: EventLoopProxy<EventLoopProxyMessage>) -> Self {
         let (config, to_controller_sender) = try_connect_ipc_and_get_config(&proxy);
+        Self::new_with_config(evl, proxy, config, to_controller_sender)
+    }
+
+    /// Create a Verso instance from the config instead of the command line arguments, reporting
+    /// to the controller through `to_controller_sender`, e.g. to drive it from the same process
+    /// in tests. Messages of the controller are sent with [`EventLoopProxyMessage::IpcMessage`].
+    pub fn new_with_config(
+        evl: &ActiveEventLoop,
+        proxy: EventLoopProxy<EventLoopProxyMessage>,
+        config: Config,
+        to_controller_sender: Option<IpcSender<ToControllerMessage>>,
+    ) -> Self {
         let (verso_internal_sender, verso_internal_receiver) = ipc_channel::ipc::channel().unwrap();
 
         // Initialize configurations and Verso window
         let protocols = config.create_protocols();
-        let initial_url = config.url.clone();
+        let mut initial_windows = config.initial_windows.clone().into_iter();
+        // Private windows don't read or write the profile
+        let storage = if config.private {
+            Storage::default()
+        } else {
+            Storage::new(config.profile_directory.as_deref())
+        };
+        if let Some(crash_storage) = storage.crash_storage() {
+            crash_reporter::install(crash_storage);
+        }
+        // The windows of the last session are opened in place of the initial windows
+        let mut restored_windows = config
+            .restore_session
+            .then(|| storage.session_storage()?.load_from_file().ok())
+            .flatten()
+            .map(|session| session.windows)
+            .unwrap_or_default()
+            .into_iter();
+        // Windows are placed where they were when Verso was last closed
+        let mut placements = WindowPlacements::new(
+            storage
+                .placement_storage()
+                .and_then(|storage| storage.load_from_file().ok())
+                .unwrap_or_default(),
+        );
+        let mut place = |name: Option<&str>, attributes| match placements.take(name) {
+            Some(placement) => placement.apply(evl, attributes),
+            None => attributes,
+        };
+        let initial_window = initial_windows
+            .next()
+            .expect("Verso needs at least one initial window");
         let with_panel = config.with_panel;
-        let window_settings = config.window_attributes.clone();
         let user_scripts = config.user_scripts.clone();
         let zoom_level = config.zoom_level;
 
         config.init();
         // Reserving a namespace to create WebViewId.
         PipelineNamespace::install(PipelineNamespaceId(0));
-        let (mut window, rendering_context) =
-            Window::new(evl, window_settings, verso_internal_sender.clone());
+        let (mut window, rendering_context) = Window::new(
+            evl,
+            place(
+                initial_window.name.as_deref(),
+                initial_window.window_attributes,
+            ),
+            verso_internal_sender.clone(),
+            config.software_rendering,
+            config.vsync,
+        );
+        // Show the window before Webrender compiles its shaders and Servo starts its threads. The
+        // GL context is still created synchronously, and so are Webrender and the constellation
+        // below, only the history and the browser import are deferred, see
+        // `start_deferred_subsystems`.
+        window.paint_placeholder(&rendering_context);
+        crash_reporter::set_gpu(rendering_context.gl.get_string(gleam::gl::RENDERER));
         let event_loop_waker = Box::new(Waker(proxy.clone()));
         let opts = opts::get();
-
         // Set Stylo flags
         style::context::DEFAULT_DISABLE_STYLE_SHARING_CACHE
             .store(opts.debug.disable_share_style_cache, Ordering::Relaxed);
@@ -172,8 +504,28 @@ impl Verso {
         let (compositor_proxy, compositor_receiver) =
             create_compositor_channel(event_loop_waker.clone());
         let (embedder_proxy, embedder_receiver) = create_embedder_channel(event_loop_waker.clone());
+        let certificate_pins = CertificatePins::new(
+            &config.certificate_pins,
+            config.certificate_bundle.as_deref(),
+            opts.ignore_certificate_errors,
+            embedder_proxy.clone(),
+        );
+        let tls_config = certificate_pins
+            .client_config()
+            .inspect_err(|e| {
+                log::error!("Verso failed to create the TLS config of its requests: {e}")
+            })
+            .ok();
+        // Servo loads the saved cookie jar when its resource threads start
+        let cookie_jar = CookieJar::new(
+            opts.config_dir.as_deref(),
+            config.cookies,
+            config.partition_network,
+            tls_config.clone(),
+        );
 
         // Create dev tools thread
+        #[cfg(feature = "devtools")]
         let devtools_sender = if pref!(devtools_server_enabled) {
             Some(devtools::start_server(
                 pref!(devtools_server_port) as u16,
@@ -182,6 +534,15 @@ impl Verso {
         } else {
             None
         };
+        #[cfg(not(feature = "devtools"))]
+        let devtools_sender = {
+            if pref!(devtools_server_enabled) {
+                log::warn!(
+                    "Verso is built without the devtools feature, devtools server is disabled"
+                );
+            }
+            None
+        };
 
         // Create Webrender threads
         let (mut webrender, webrender_api_sender) = {
@@ -206,10 +567,17 @@ impl Verso {
                     } else {
                         ShaderPrecacheFlags::empty()
                     },
-                    enable_aa: pref!(gfx_text_antialiasing_enabled),
-                    enable_subpixel_aa: pref!(gfx_subpixel_text_antialiasing_enabled),
+                    enable_aa: pref!(gfx_text_antialiasing_enabled)
+                        && config.font_rendering.antialiasing != FontAntialiasing::None,
+                    enable_subpixel_aa: pref!(gfx_subpixel_text_antialiasing_enabled)
+                        && config.font_rendering.antialiasing == FontAntialiasing::Subpixel,
                     allow_texture_swizzling: pref!(gfx_texture_swizzling_enabled),
                     clear_color,
+                    cached_programs: config
+                        .shader_cache
+                        .then(|| shader_cache_directory(config.profile_directory.as_deref()))
+                        .flatten()
+                        .and_then(ShaderCache::create),
                     ..Default::default()
                 },
                 None,
@@ -250,7 +618,9 @@ impl Verso {
         // external_image_handlers.set_handler(image_handler, WebrenderImageHandlerType::WebGL);
 
         // Set webrender external image handler for WebGPU textures
+        #[cfg(feature = "webgpu")]
         let wgpu_image_handler = webgpu::WGPUExternalImages::default();
+        #[cfg(feature = "webgpu")]
         external_image_handlers.set_handler(
             Box::new(wgpu_image_handler),
             WebrenderImageHandlerType::WebGPU,
@@ -259,6 +629,7 @@ impl Verso {
         webrender.set_external_image_handler(external_image_handlers);
 
         // Create bluetooth thread
+        #[cfg(feature = "bluetooth")]
         let bluetooth_thread: IpcSender<BluetoothRequest> =
             BluetoothThreadFactory::new(embedder_proxy.clone());
 
@@ -275,6 +646,8 @@ impl Verso {
                 Arc::new(protocols),
             );
 
+        let resource_threads = public_resource_threads.clone();
+
         // Create font cache thread
         let system_font_service = Arc::new(
             SystemFontService::spawn(compositor_proxy.cross_process_compositor_api.clone())
@@ -299,6 +672,7 @@ impl Verso {
             compositor_proxy: compositor_proxy.clone(),
             embedder_proxy,
             devtools_sender,
+            #[cfg(feature = "bluetooth")]
             bluetooth_thread,
             system_font_service,
             public_resource_threads,
@@ -327,7 +701,7 @@ impl Verso {
 
         // Create webdriver thread
         if let Some(port) = opts.webdriver_port {
-            webdriver_server::start_server(port, constellation_sender.clone());
+            webdriver::start_server(port, constellation_sender.clone());
         }
 
         // The compositor coordinates with the client window to create the final
@@ -352,19 +726,75 @@ impl Verso {
             opts.debug.convert_mouse_to_touch,
         );
 
+        compositor.set_load_progress_bar(config.load_progress_bar);
+        compositor.set_animation_policy(config.animations);
+        compositor.set_font_rendering(config.font_rendering);
+
         if let Some(zoom_level) = zoom_level {
-            compositor.on_zoom_window_event(zoom_level, &window);
+            compositor.set_default_page_zoom(zoom_level, &window);
         }
 
-        if with_panel {
-            window.create_panel(&constellation_sender, initial_url);
-        } else {
-            window.create_tab(&constellation_sender, initial_url.into());
-        }
+        window.name = initial_window.name;
+        window.private = config.private;
+        window.set_rotation(config.rotation, &mut compositor);
+        window.open_initial_tabs(
+            &constellation_sender,
+            with_panel,
+            initial_window.url,
+            restored_windows.next(),
+        );
+        window.set_warm_pool_size(&constellation_sender, config.warm_pool_size);
 
         let mut windows = HashMap::new();
         windows.insert(window.id(), (window, webrender_document));
 
+        // Open the rest of the windows declared in the config
+        for initial_window in initial_windows {
+            let mut window = Window::new_with_compositor(
+                evl,
+                place(
+                    initial_window.name.as_deref(),
+                    initial_window.window_attributes,
+                ),
+                &mut compositor,
+                verso_internal_sender.clone(),
+            );
+            window.name = initial_window.name;
+            window.private = config.private;
+            window.set_rotation(config.rotation, &mut compositor);
+            window.open_initial_tabs(
+                &constellation_sender,
+                with_panel,
+                initial_window.url,
+                restored_windows.next(),
+            );
+            window.set_warm_pool_size(&constellation_sender, config.warm_pool_size);
+            windows.insert(window.id(), (window, webrender_document));
+        }
+
+        // and the windows of the last session which are left
+        for restored_window in restored_windows {
+            let mut window = Window::new_with_compositor(
+                evl,
+                place(
+                    restored_window.name.as_deref(),
+                    config.window_attributes.clone(),
+                ),
+                &mut compositor,
+                verso_internal_sender.clone(),
+            );
+            window.private = config.private;
+            window.set_rotation(config.rotation, &mut compositor);
+            window.open_initial_tabs(
+                &constellation_sender,
+                with_panel,
+                config.url.clone(),
+                Some(restored_window),
+            );
+            window.set_warm_pool_size(&constellation_sender, config.warm_pool_size);
+            windows.insert(window.id(), (window, webrender_document));
+        }
+
         let proxy_clone = proxy.clone();
         ROUTER.add_typed_route(
             verso_internal_receiver,
@@ -380,6 +810,64 @@ impl Verso {
             }),
         );
 
+        if let Some(watch_directory) = &config.watch_directory {
+            file_watcher::watch_directory(watch_directory.clone(), verso_internal_sender.clone());
+        }
+        let control_access = ControlAccess {
+            token: config.ipc_token.clone(),
+            rate_limit: config.ipc_rate_limit,
+            allow_remote: config.ipc_allow_remote,
+        };
+        if let Some(ipc_socket) = &config.ipc_socket {
+            control::listen(ipc_socket.clone(), control_access.clone(), proxy.clone());
+        }
+        if let Some(address) = config.ipc_websocket {
+            control::listen_websocket(address, control_access, proxy.clone());
+        }
+        if config.sensors {
+            sensors::watch_sensors(verso_internal_sender.clone());
+        }
+        if storage.session_storage().is_some() {
+            session::autosave(SESSION_SAVE_INTERVAL, verso_internal_sender.clone());
+        }
+        if config.power_save.enabled || config.battery_status_api {
+            power::watch_power_state(
+                Duration::from_secs(config.power_save.idle_timeout),
+                verso_internal_sender.clone(),
+            );
+        }
+
+        let mock_network = config.mock_network;
+        let http_cache = opts.config_dir.as_deref().map(|directory| {
+            DiskCache::new(
+                directory,
+                config.http_cache_size.saturating_mul(MEGABYTE),
+                config.partition_network,
+                tls_config,
+                resource_threads.clone(),
+            )
+        });
+        let web_app = config.app.as_ref().map(WebApp::new);
+        let script_watchdog = ScriptWatchdog::new(
+            (config.slow_script_timeout > 0)
+                .then(|| Duration::from_secs(config.slow_script_timeout)),
+            constellation_sender.clone(),
+            verso_internal_sender.clone(),
+        );
+        let devtools_bridge = config.cdp_port.and_then(|port| {
+            DevtoolsBridge::start(
+                port,
+                config.cdp_allowed_origins.clone(),
+                constellation_sender.clone(),
+            )
+        });
+        let mut resource_overrides = ResourceOverrides::new();
+        for resource_override in config.resource_overrides.clone() {
+            resource_overrides.set(resource_override);
+        }
+        let remembered_credentials =
+            RememberedCredentials::new(config.profile_directory.as_deref(), config.private);
+
         // Create Verso instance
         let verso = Verso {
             windows,
@@ -391,9 +879,38 @@ impl Verso {
             clipboard: Clipboard::new().ok(),
             config,
             bookmark_manager: BookmarkManager::new(),
+            session_manager: SessionManager::new(),
+            highlight_manager: HighlightManager::new(),
+            zoom_levels: ZoomLevels::default(),
+            image_policy: config.images.unwrap_or_default(),
+            resource_overrides,
+            secret_store: SecretStore::new(),
+            remembered_credentials,
+            auth_flows: AuthFlows::new(),
+            certificate_pins,
+            certificate_overrides: HashSet::new(),
+            history: None,
+            resource_threads,
+            cookie_jar,
+            http_cache,
+            mock_network,
+            file_picker_override: None,
             downloads: HashMap::new(),
+            power_state: PowerState::default(),
+            mock_geolocation: None,
+            sensor_reading: SensorReading::default(),
+            sleep_inhibitors: SleepInhibitors::new(),
+            web_app,
+            script_watchdog,
+            slow_script_dialogs: HashSet::new(),
+            watch_reloads: HashSet::new(),
             verso_internal_sender,
-            storage: Storage::new(),
+            storage,
+            deferred_subsystems_started: false,
+            metrics: Metrics::default(),
+            load_starts: HashMap::new(),
+            devtools_bridge,
+            control_clients: ControlClients::default(),
         };
 
         verso.setup_logging();
@@ -411,16 +928,219 @@ impl Verso {
                 self.bookmark_manager.set_bookmarks(bookmarks);
             }
         }
+
+        // Load highlights from disk
+        if let Some(highlight_storage) = self.storage.highlight_storage() {
+            if let Ok(pages) = highlight_storage.load_from_file() {
+                self.highlight_manager.set_pages(pages);
+            }
+        }
+
+        // Load zoom levels from disk
+        if let Some(zoom_storage) = self.storage.zoom_storage() {
+            if let Ok(levels) = zoom_storage.load_from_file() {
+                self.zoom_levels.set_levels(levels);
+            }
+        }
+
+        // Load the image policy from disk, unless the config sets one
+        if self.config.images.is_none() {
+            if let Some(image_policy_storage) = self.storage.image_policy_storage() {
+                if let Ok(policy) = image_policy_storage.load_from_file() {
+                    self.image_policy = policy;
+                }
+            }
+        }
+
+        // Load session from disk
+        if let Some(session_storage) = self.storage.session_storage() {
+            if let Ok(session) = session_storage.load_from_file() {
+                self.session_manager.set_session(session);
+            }
+        }
+
+        // Load metrics from disk
+        if self.config.metrics.enabled {
+            if let Some(metrics_storage) = self.storage.metrics_storage() {
+                if let Ok(metrics) = metrics_storage.load_from_file() {
+                    self.metrics = metrics;
+                }
+            }
+            self.metrics.record_session();
+        }
+    }
+
+    /// Open the history database and import the data of other browsers. They aren't needed to
+    /// show the first page, so they're started once it's loaded instead of delaying it.
+    fn start_deferred_subsystems(&mut self) {
+        if std::mem::replace(&mut self.deferred_subsystems_started, true) {
+            return;
+        }
+
+        // Open history database
+        if let Some(history_storage) = self.storage.history_storage() {
+            match history_storage.open() {
+                Ok(mut history) => {
+                    match history.prune(self.config.history_retention_days) {
+                        Ok(0) => {}
+                        Ok(pruned) => log::info!("Pruned {pruned} pages from the history"),
+                        Err(e) => log::error!("Failed to prune the history: {e}"),
+                    }
+                    self.history = Some(history);
+                }
+                Err(e) => log::error!("Failed to open history database: {e}"),
+            }
+        }
+
+        if let Some(browser_import) = self.config.import.clone() {
+            self.import_browser_data(browser_import);
+        }
+
+        if let Some(update) = self.config.update.clone() {
+            updater::remove_previous_binary();
+            updater::check_for_update(update);
+        }
+
+        if let (true, Some(upload_url)) = (
+            self.config.metrics.enabled,
+            self.config.metrics.upload_url.clone(),
+        ) {
+            metrics::upload(self.metrics.clone(), upload_url);
+        }
+    }
+
+    /// Import cookies, bookmarks and history from another browser.
+    fn import_browser_data(&mut self, browser_import: BrowserImport) {
+        let data = match import::import(browser_import.browser, browser_import.profile_directory) {
+            Ok(data) => data,
+            Err(e) => {
+                log::error!("Failed to import {:?} profile: {e}", browser_import.browser);
+                return;
+            }
+        };
+        let (cookie_count, bookmark_count, history_count) =
+            (data.cookies.len(), data.bookmarks.len(), data.history.len());
+
+        // Skip bookmarks which already exist
+        self.bookmark_manager.merge_bookmarks(data.bookmarks);
+        if let Some(history) = &mut self.history {
+            if let Err(e) = history.add_entries(&data.history) {
+                log::error!("Failed to import history: {e}");
+            }
+        }
+        for cookie in data.cookies {
+            import::set_cookie(&self.resource_threads, cookie);
+        }
+
+        log::info!(
+            "Verso imported {cookie_count} cookies, {bookmark_count} bookmarks and {history_count} history entries from {:?}",
+            browser_import.browser
+        );
+        if data.skipped_cookies > 0 {
+            log::warn!(
+                "Verso skipped {} encrypted cookies which can't be imported",
+                data.skipped_cookies
+            );
+        }
+    }
+
+    /// Import the bookmarks of a Netscape bookmarks HTML file, returns how many were added.
+    fn import_bookmarks(&mut self, path: &Path) -> Result<usize, String> {
+        let html = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+        let bookmarks = bookmark::parse_netscape_html(&html);
+        let count = self.bookmark_manager.merge_bookmarks(bookmarks);
+        log::info!("Verso imported {count} bookmarks from {}", path.display());
+        Ok(count)
+    }
+
+    /// Export the bookmarks to a Netscape bookmarks HTML file.
+    fn export_bookmarks(&self, path: &Path) -> Result<(), String> {
+        std::fs::write(path, self.bookmark_manager.to_netscape_html())
+            .map_err(|e| format!("Failed to export bookmarks to {}: {e}", path.display()))
     }
 
     /// Task to be done before shutting down.
     ///
     /// This function is called when the Verso instance is shutting down.
     pub fn before_shutdown(&mut self) {
+        // Install the update downloaded while running, if any
+        if self.config.update.is_some() {
+            updater::apply_staged_update();
+        }
+
         // Save bookmarks to disk
         if let Some(bookmark_storage) = self.storage.bookmark_storage() {
             let _ = bookmark_storage.save_to_file(self.bookmark_manager.bookmarks());
         }
+
+        // Save highlights to disk
+        if let Some(highlight_storage) = self.storage.highlight_storage() {
+            let _ = highlight_storage.save_to_file(self.highlight_manager.pages());
+        }
+
+        // Save zoom levels to disk
+        if let Some(zoom_storage) = self.storage.zoom_storage() {
+            let _ = zoom_storage.save_to_file(self.zoom_levels.levels());
+        }
+
+        // Save metrics to disk
+        if self.config.metrics.enabled {
+            self.merge_frame_times();
+            if let Some(metrics_storage) = self.storage.metrics_storage() {
+                let _ = metrics_storage.save_to_file(&self.metrics);
+            }
+        }
+
+        // Save session to disk
+        self.save_session();
+
+        if let Some(http_cache) = &self.http_cache {
+            http_cache.save_index();
+        }
+
+        // Servo saved the cookie jar when it shut down
+        self.cookie_jar.after_shutdown();
+    }
+
+    /// Save the tab groups, and the open windows, their tabs and placement unless Verso is already
+    /// shutting down and closing them, in which case they were saved when it started to.
+    fn save_session(&mut self) {
+        self.update_tab_group_urls(None);
+        if let Some(compositor) = self
+            .compositor
+            .as_ref()
+            .filter(|compositor| compositor.shutdown_state == ShutdownState::NotShuttingDown)
+        {
+            let windows: Vec<_> = self
+                .windows
+                .iter()
+                .filter(|(window_id, _)| !self.auth_flows.contains(**window_id))
+                .filter_map(|(_, (window, _))| session_window(window, compositor))
+                .collect();
+            if !windows.is_empty() {
+                self.session_manager.set_windows(windows);
+            }
+
+            let placements: Vec<_> = self
+                .windows
+                .iter()
+                .filter(|(window_id, _)| !self.auth_flows.contains(**window_id))
+                .map(|(_, (window, _))| {
+                    WindowPlacement::of_window(&window.window, window.name.clone())
+                })
+                .collect();
+            if let Some(placement_storage) = self.storage.placement_storage() {
+                if let Err(e) = placement_storage.save_to_file(&placements) {
+                    log::error!("Failed to save window placements: {e}");
+                }
+            }
+        }
+        if let Some(session_storage) = self.storage.session_storage() {
+            if let Err(e) = session_storage.save_to_file(&self.session_manager.session()) {
+                log::error!("Failed to save session: {e}");
+            }
+        }
     }
 
     /// Handle Winit window events. The strategy to handle event are different between platforms
@@ -433,32 +1153,37 @@ impl Verso {
     ) {
         #[cfg(linux)]
         if let WindowEvent::Resized(_) = event {
-            self.handle_winit_window_event(window_id, event);
+            self.handle_winit_window_event(event_loop, window_id, event);
         } else {
-            self.handle_winit_window_event(window_id, event);
+            self.handle_winit_window_event(event_loop, window_id, event);
             self.handle_servo_messages(event_loop);
         }
 
         #[cfg(apple)]
         if let WindowEvent::RedrawRequested = event {
-            let resizing = self.handle_winit_window_event(window_id, event);
+            let resizing = self.handle_winit_window_event(event_loop, window_id, event);
             if !resizing {
                 self.handle_servo_messages(event_loop);
             }
         } else {
-            self.handle_winit_window_event(window_id, event);
+            self.handle_winit_window_event(event_loop, window_id, event);
             self.handle_servo_messages(event_loop);
         }
 
         #[cfg(windows)]
         {
-            self.handle_winit_window_event(window_id, event);
+            self.handle_winit_window_event(event_loop, window_id, event);
             self.handle_servo_messages(event_loop);
         }
     }
 
     /// Handle Winit window events
-    fn handle_winit_window_event(&mut self, window_id: WindowId, event: WindowEvent) -> bool {
+    fn handle_winit_window_event(
+        &mut self,
+        evl: &ActiveEventLoop,
+        window_id: WindowId,
+        event: WindowEvent,
+    ) -> bool {
         log::trace!("Verso is handling Winit event: {event:?}");
 
         let Some(compositor) = &mut self.compositor else {
@@ -469,6 +1194,15 @@ impl Verso {
         };
 
         if let WindowEvent::CloseRequested = event {
+            // Closing an auth popup only cancels its flow
+            if self.auth_flows.contains(window_id) {
+                self.auth_flows.cancel(
+                    window_id,
+                    &self.constellation_sender,
+                    &self.to_controller_sender,
+                );
+                return false;
+            }
             if let Some(to_controller_sender) = &self.to_controller_sender {
                 if window.event_listeners.on_close_requested {
                     if let Err(error) =
@@ -483,10 +1217,27 @@ impl Verso {
                 }
             }
             // self.windows.remove(&window_id);
-            compositor.maybe_start_shutting_down();
+            self.save_session();
+            if let Some(compositor) = &mut self.compositor {
+                compositor.maybe_start_shutting_down();
+            }
         } else {
-            window.handle_winit_window_event(&self.constellation_sender, compositor, &event);
-            return window.resizing;
+            if let WindowEvent::Resized(_) = event {
+                if let Some(orientation) = self.web_app.as_ref().and_then(WebApp::orientation) {
+                    orientation.apply(&window.window, false);
+                }
+            }
+            let action = window.handle_winit_window_event(
+                &self.constellation_sender,
+                &self.to_controller_sender,
+                compositor,
+                &event,
+            );
+            let resizing = window.resizing;
+            if action == CommandAction::NewWindow {
+                self.open_window(evl);
+            }
+            return resizing;
         }
 
         false
@@ -517,37 +1268,211 @@ impl Verso {
             }
         }
 
-        match compositor.shutdown_state {
+        // The compositor is borrowed again where it's needed, so Verso can be borrowed in between
+        let shutdown_state = compositor.shutdown_state;
+        match shutdown_state {
             ShutdownState::NotShuttingDown => {
                 for msg in messages {
+                    let Some(msg) = file_access::apply_file_access_policy(
+                        &self.config.file_access,
+                        msg,
+                        &self.windows,
+                        &self.constellation_sender,
+                    ) else {
+                        continue;
+                    };
+                    // Closed tabs may already be removed from their window
+                    let is_tab = matches!(msg, EmbedderMsg::WebViewClosed(_))
+                        || Self::get_embedder_message_webview_id(&msg).is_some_and(|webview_id| {
+                            self.windows
+                                .values()
+                                .any(|(window, _)| window.tab_manager.tab(*webview_id).is_some())
+                        });
+                    if is_tab {
+                        if let Some(devtools_bridge) = &self.devtools_bridge {
+                            devtools_bridge.observe(&msg);
+                        }
+                        self.control_clients.observe(&msg);
+                    }
+                    // Wake locks are released when the document is unloaded, and tabs are watched
+                    // for hung scripts while they are opened and recorded in the history once loaded
+                    match msg {
+                        // Sites are zoomed as they were the last time
+                        EmbedderMsg::HistoryChanged(webview_id, ref list, index) if is_tab => {
+                            if let Some(url) = list.get(index) {
+                                self.apply_zoom_level(webview_id, url);
+                            }
+                        }
+                        EmbedderMsg::WebViewClosed(webview_id) => {
+                            self.load_starts.remove(&webview_id);
+                            self.sleep_inhibitors.release(webview_id);
+                            self.update_power_save();
+                            self.slow_script_dialogs.remove(&webview_id);
+                            self.watch_reloads.remove(&webview_id);
+                            self.script_watchdog.unwatch(webview_id);
+                        }
+                        EmbedderMsg::NotifyLoadStatusChanged(webview_id, LoadStatus::Started) => {
+                            if self.config.metrics.enabled {
+                                self.load_starts.insert(webview_id, Instant::now());
+                            }
+                            self.sleep_inhibitors.set_wake_lock(webview_id, false);
+                            self.update_power_save();
+                            if self
+                                .windows
+                                .values()
+                                .any(|(window, _)| window.tab_manager.tab(webview_id).is_some())
+                            {
+                                self.script_watchdog.watch(webview_id);
+                            }
+                        }
+                        EmbedderMsg::NotifyLoadStatusChanged(webview_id, LoadStatus::Complete) => {
+                            self.start_deferred_subsystems();
+                            if let Some(url) = self.tab_url(webview_id) {
+                                crash_reporter::set_url(url.to_string());
+                            }
+                            if let Some(scroll) = self
+                                .windows
+                                .values_mut()
+                                .find_map(|(window, _)| window.tab_manager.tab_mut(webview_id))
+                                .and_then(Tab::take_restore_scroll)
+                            {
+                                let _ = execute_script(
+                                    &self.constellation_sender,
+                                    &webview_id,
+                                    format!("window.scrollTo({}, {})", scroll.x, scroll.y),
+                                );
+                            }
+                            if let Some(start) = self.load_starts.remove(&webview_id) {
+                                self.metrics.record_load_time(start.elapsed());
+                            }
+                            self.watch_reloads.remove(&webview_id);
+                            if self.history.is_some()
+                                && self
+                                    .windows
+                                    .values()
+                                    .any(|(window, _)| window.tab_manager.tab(webview_id).is_some())
+                            {
+                                text::send_page_text(
+                                    self.constellation_sender.clone(),
+                                    self.verso_internal_sender.clone(),
+                                    webview_id,
+                                    self.config.index_history_text,
+                                );
+                            }
+                        }
+                        EmbedderMsg::WebResourceRequested(_, ref request, _) => {
+                            self.cookie_jar.observe_request(request);
+                        }
+                        EmbedderMsg::SelectFiles(_, ref filter_patterns, allow_multiple, _)
+                            if self.file_picker_override.is_some() =>
+                        {
+                            let files = file_picker::pick_override(
+                                self.file_picker_override.as_deref().unwrap_or_default(),
+                                filter_patterns,
+                                allow_multiple,
+                            );
+                            if let EmbedderMsg::SelectFiles(.., sender) = msg {
+                                if let Err(e) = sender.send(files) {
+                                    log::warn!("Verso failed to send the picked files: {e}");
+                                }
+                            }
+                            continue;
+                        }
+                        EmbedderMsg::RequestAuthentication(_, ref url, for_proxy, _) => {
+                            if let Some(credentials) = self.remembered_credentials.get(
+                                &self.secret_store,
+                                url.as_url(),
+                                for_proxy,
+                            ) {
+                                if let EmbedderMsg::RequestAuthentication(.., sender) = msg {
+                                    let _ = sender.send(Some(credentials));
+                                }
+                                continue;
+                            }
+                        }
+                        // The built-in keyboard replaces the platform's keyboard and input methods
+                        EmbedderMsg::ShowIME(..)
+                            if self.config.virtual_keyboard == VirtualKeyboard::BuiltIn =>
+                        {
+                            continue;
+                        }
+                        EmbedderMsg::Panic(webview_id, ref reason, ref backtrace) => {
+                            crash_reporter::record_page_crash(
+                                reason.clone(),
+                                backtrace.clone(),
+                                self.tab_url(webview_id).map(|url| url.to_string()),
+                            );
+                            if self.config.metrics.enabled {
+                                self.metrics.record_crash();
+                            }
+                        }
+                        _ => {}
+                    }
+                    let Some(msg) = self.auth_flows.handle_callback(
+                        msg,
+                        &self.constellation_sender,
+                        &self.to_controller_sender,
+                    ) else {
+                        continue;
+                    };
+                    let msg = match &self.web_app {
+                        Some(web_app) => web_app.handle_navigation(msg, &self.constellation_sender),
+                        None => Some(msg),
+                    };
+                    let Some(msg) = msg.and_then(|msg| self.resource_overrides.handle_request(msg))
+                    else {
+                        continue;
+                    };
+                    let Some(msg) = apply_request_limit(
+                        msg,
+                        &mut self.windows,
+                        self.to_controller_sender.as_ref(),
+                    ) else {
+                        continue;
+                    };
+                    let Some(msg) = apply_image_policy(msg, &mut self.windows, &self.image_policy)
+                    else {
+                        continue;
+                    };
+                    let msg = if self.mock_network {
+                        apply_mock_network(msg, &self.windows)
+                    } else {
+                        Some(msg)
+                    };
+                    let Some(msg) = msg.and_then(|msg| self.certificate_pins.handle_request(msg))
+                    else {
+                        continue;
+                    };
+                    // Requests the controller intercepts only reach the cache and the cookie jar
+                    // if it doesn't answer them
+                    let msg = if self.is_intercepted_by_controller(&msg) {
+                        msg
+                    } else {
+                        let Some(msg) = self.handle_network_request(msg) else {
+                            continue;
+                        };
+                        msg
+                    };
                     if let Some(webview_id) = Self::get_embedder_message_webview_id(&msg) {
-                        for (window, document) in self.windows.values_mut() {
-                            if window.has_webview(*webview_id) {
-                                if window.handle_servo_message(
-                                    *webview_id,
+                        let webview_id = *webview_id;
+                        let compositor = self.compositor.as_mut().unwrap();
+                        let action = self
+                            .windows
+                            .values_mut()
+                            .find(|(window, _)| window.has_webview(webview_id))
+                            .map(|(window, _)| {
+                                window.handle_servo_message(
+                                    webview_id,
                                     msg,
                                     self.constellation_sender.clone(),
                                     &self.to_controller_sender,
                                     self.clipboard.as_mut(),
                                     compositor,
                                     &mut self.bookmark_manager,
-                                ) {
-                                    let mut window = Window::new_with_compositor(
-                                        evl,
-                                        self.config.window_attributes.clone(),
-                                        compositor,
-                                        self.verso_internal_sender.clone(),
-                                    );
-                                    window.create_panel(
-                                        &self.constellation_sender,
-                                        self.config.url.clone(),
-                                    );
-                                    let webrender_document = *document;
-                                    self.windows
-                                        .insert(window.id(), (window, webrender_document));
-                                }
-                                break;
-                            }
+                                )
+                            });
+                        if action == Some(CommandAction::NewWindow) {
+                            self.open_window(evl);
                         }
                     } else {
                         // Handle message in Verso Window
@@ -584,6 +1509,7 @@ impl Verso {
             ShutdownState::ShuttingDown => {}
         }
 
+        let compositor = self.compositor.as_mut().unwrap();
         if compositor.shutdown_state != ShutdownState::FinishedShuttingDown {
             // Update compositor
             compositor.perform_updates(&mut self.windows);
@@ -606,12 +1532,134 @@ impl Verso {
             }
             evl.exit();
         } else if self.is_animating() {
-            evl.set_control_flow(ControlFlow::Poll);
+            // Wait for the next tick if the animation frame rate is limited to save power
+            match self
+                .compositor
+                .as_ref()
+                .and_then(IOCompositor::next_animation_tick)
+            {
+                Some(next_tick) => evl.set_control_flow(ControlFlow::WaitUntil(next_tick)),
+                None => evl.set_control_flow(ControlFlow::Poll),
+            }
         } else {
             evl.set_control_flow(ControlFlow::Wait);
         }
     }
 
+    /// Tick animations held back by the animation frame rate limit, called when the time set by
+    /// [`ControlFlow::WaitUntil`] is reached.
+    pub fn handle_resume_time_reached(&mut self, evl: &ActiveEventLoop) {
+        if let Some(compositor) = &mut self.compositor {
+            compositor.tick_limited_animations();
+        }
+        self.handle_servo_messages(evl);
+    }
+
+    /// Throttle animations if the system is idle, in power saver mode or on battery if the config
+    /// says so. Idleness is ignored while a webview keeps the display awake.
+    fn update_power_save(&mut self) {
+        let power_save = &self.config.power_save;
+        let saving = power_save.enabled
+            && (self.power_state.power_saver
+                || (power_save.on_battery && self.power_state.on_battery())
+                || (self.power_state.idle && !self.sleep_inhibitors.is_inhibited()));
+        let limit = saving.then(|| Duration::from_secs(1) / power_save.frame_rate.max(1));
+        if let Some(compositor) = &mut self.compositor {
+            compositor.set_animation_tick_limit(limit);
+        }
+    }
+
+    /// Suggest pages of the history for the text typed in the URL bar.
+    fn suggest_history(&self, input: &str) -> Vec<HistoryMatch> {
+        let Some(history) = &self.history else {
+            return Vec::new();
+        };
+        history
+            .suggest(input, HISTORY_SUGGEST_LIMIT)
+            .unwrap_or_else(|e| {
+                log::error!("Failed to suggest pages of the history: {e}");
+                Vec::new()
+            })
+    }
+
+    /// Forget the pages of the history last visited since the time, or every page if `None`.
+    fn clear_history(&mut self, since: Option<i64>) {
+        let Some(history) = &mut self.history else {
+            return;
+        };
+        if let Err(e) = history.clear(since) {
+            log::error!("Failed to clear the history: {e}");
+        }
+    }
+
+    /// Search the history by the text of its pages, the most recent pages for an empty query.
+    fn search_history(&self, query: &str) -> Vec<HistoryMatch> {
+        let Some(history) = &self.history else {
+            return Vec::new();
+        };
+        history
+            .search(query, HISTORY_SEARCH_LIMIT)
+            .unwrap_or_else(|e| {
+                log::error!("Failed to search the history: {e}");
+                Vec::new()
+            })
+    }
+
+    /// Tell the controller that the script of the webview hangs or responds again.
+    fn notify_script_unresponsive(&self, webview_id: WebViewId, unresponsive: bool) {
+        let Some(to_controller_sender) = &self.to_controller_sender else {
+            return;
+        };
+        if !self.windows.values().any(|(window, _)| {
+            window.event_listeners.on_script_unresponsive
+                && window.tab_manager.tab(webview_id).is_some()
+        }) {
+            return;
+        }
+        if let Err(error) = to_controller_sender.send(ToControllerMessage::OnScriptUnresponsive(
+            bincode::serialize(&webview_id).unwrap(),
+            unresponsive,
+        )) {
+            log::error!("Verso failed to send OnScriptUnresponsive to controller: {error}")
+        }
+    }
+
+    /// Tint the titlebar of the window of the webview with its theme color if it's the current tab,
+    /// or with the theme color of the manifest in standalone app mode.
+    fn update_titlebar_color(&self, webview_id: WebViewId) {
+        if !self.config.theme_color_titlebar && self.web_app.is_none() {
+            return;
+        }
+        let Some((window, _)) = self
+            .windows
+            .values()
+            .find(|(window, _)| window.tab_manager.current_tab_id() == Some(webview_id))
+        else {
+            return;
+        };
+        let color = window
+            .tab_manager
+            .tab(webview_id)
+            .and_then(|tab| tab.theme_color())
+            .and_then(parse_color)
+            .or_else(|| {
+                self.web_app
+                    .as_ref()
+                    .and_then(|web_app| web_app.manifest()?.theme_color)
+            });
+        set_titlebar_color(&window.window, color);
+    }
+
+    /// Send the battery status to the documents of every tab.
+    fn notify_battery_status(&self) {
+        let script = battery_status_script(self.power_state.battery);
+        for (window, _) in self.windows.values() {
+            for tab_id in window.tab_manager.tab_ids() {
+                let _ = execute_script(&self.constellation_sender, &tab_id, &script);
+            }
+        }
+    }
+
     /// Handle message from the Verso internal channel
     pub fn handle_verso_internal_message(&mut self, message: VersoInternalMsg) {
         match message {
@@ -621,7 +1669,13 @@ impl Verso {
                     download.abort();
                 }
             }
+            VersoInternalMsg::ControlDownload(id, control) => {
+                if let Some(download) = self.downloads.get_mut(&id) {
+                    download.control(control);
+                }
+            }
             VersoInternalMsg::CreateDownload(download) => {
+                self.notify_download_updated(&download);
                 let _ = self.downloads.insert(download.id().clone(), download);
 
                 // update all window's panel status
@@ -637,15 +1691,9 @@ impl Verso {
             }
             VersoInternalMsg::UpdateDownload(id, new_state) => {
                 if let Some(download) = self.downloads.get_mut(&id) {
-                    if let Some(status) = new_state.status {
-                        download.status = status;
-                    }
-                    if let Some(progress) = new_state.progress {
-                        download.progress = progress;
-                    }
-                    if let Some(stopped) = new_state.stopped {
-                        download.stopped = stopped;
-                    }
+                    download.update(new_state);
+                    let download = download.clone();
+                    self.notify_download_updated(&download);
                 }
             }
             VersoInternalMsg::UpdateDownloadsPage(sender) => {
@@ -676,53 +1724,692 @@ impl Verso {
                     log::error!("Failed to rename bookmarks");
                 }
             }
-        }
-    }
-
-    fn get_embedder_message_webview_id(msg: &EmbedderMsg) -> Option<&WebViewId> {
-        match msg {
-            EmbedderMsg::Status(webview_id, _) => Some(webview_id),
-            EmbedderMsg::ChangePageTitle(webview_id, _) => Some(webview_id),
-            EmbedderMsg::MoveTo(webview_id, _) => Some(webview_id),
-            EmbedderMsg::ResizeTo(webview_id, _) => Some(webview_id),
-            EmbedderMsg::ShowSimpleDialog(webview_id, _) => Some(webview_id),
-            EmbedderMsg::RequestAuthentication(webview_id, ..) => Some(webview_id),
-            EmbedderMsg::ShowContextMenu(webview_id, _, _, _) => Some(webview_id),
-            EmbedderMsg::AllowNavigationRequest(webview_id, _, _) => Some(webview_id),
-            EmbedderMsg::AllowOpeningWebView(webview_id, _) => Some(webview_id),
-            EmbedderMsg::WebViewClosed(webview_id) => Some(webview_id),
-            EmbedderMsg::WebViewFocused(webview_id) => Some(webview_id),
-            EmbedderMsg::WebViewBlurred => None,
-            EmbedderMsg::AllowUnload(webview_id, _) => Some(webview_id),
-            EmbedderMsg::Keyboard(webview_id, _) => Some(webview_id),
-            EmbedderMsg::ClearClipboard(webview_id) => Some(webview_id),
-            EmbedderMsg::GetClipboardText(webview_id, _) => Some(webview_id),
-            EmbedderMsg::SetClipboardText(webview_id, _) => Some(webview_id),
-            EmbedderMsg::SetCursor(webview_id, _) => Some(webview_id),
-            EmbedderMsg::NewFavicon(webview_id, _) => Some(webview_id),
-            EmbedderMsg::HistoryChanged(webview_id, _, _) => Some(webview_id),
-            EmbedderMsg::NotifyFullscreenStateChanged(webview_id, _) => Some(webview_id),
-            EmbedderMsg::NotifyLoadStatusChanged(webview_id, _) => Some(webview_id),
-            EmbedderMsg::WebResourceRequested(opt_webview_id, _, _) => opt_webview_id.as_ref(),
-            EmbedderMsg::Panic(webview_id, _, _) => Some(webview_id),
-            EmbedderMsg::GetSelectedBluetoothDevice(webview_id, _, _) => Some(webview_id),
-            EmbedderMsg::SelectFiles(webview_id, _, _, _) => Some(webview_id),
-            EmbedderMsg::PromptPermission(webview_id, _, _) => Some(webview_id),
-            EmbedderMsg::ShowIME(webview_id, _, _, _, _) => Some(webview_id),
-            EmbedderMsg::HideIME(webview_id) => Some(webview_id),
-            EmbedderMsg::ReportProfile(_) => None,
-            EmbedderMsg::MediaSessionEvent(webview_id, _) => Some(webview_id),
-            EmbedderMsg::OnDevtoolsStarted(_, _) => None,
-            EmbedderMsg::RequestDevtoolsConnection(_) => None,
-            EmbedderMsg::PlayGamepadHapticEffect(webview_id, _, _, _) => Some(webview_id),
-            EmbedderMsg::StopGamepadHapticEffect(webview_id, _, _) => Some(webview_id),
-            EmbedderMsg::ShowNotification(opt_webview_id, _) => opt_webview_id.as_ref(),
-            EmbedderMsg::ShowSelectElementMenu(webview_id, _, _, _, _) => Some(webview_id),
-            EmbedderMsg::ShutdownComplete => None,
-        }
-    }
-
-    /// Request Verso to redraw. It will queue a redraw event on current focused window.
+            VersoInternalMsg::ToggleBookmark(name, url) => {
+                self.bookmark_manager.toggle_bookmark(name, url);
+            }
+            VersoInternalMsg::ImportBookmarks => {
+                let Some(path) = rfd::FileDialog::new()
+                    .add_filter("Bookmarks", &["html", "htm"])
+                    .pick_file()
+                else {
+                    return;
+                };
+                if let Err(e) = self.import_bookmarks(&path) {
+                    log::error!("{e}");
+                }
+            }
+            VersoInternalMsg::ExportBookmarks => {
+                let Some(path) = rfd::FileDialog::new()
+                    .set_file_name("bookmarks.html")
+                    .add_filter("Bookmarks", &["html"])
+                    .save_file()
+                else {
+                    return;
+                };
+                if let Err(e) = self.export_bookmarks(&path) {
+                    log::error!("{e}");
+                }
+            }
+            VersoInternalMsg::HighlightList(url, sender) => {
+                let highlights = self.highlight_manager.highlights(url.as_url());
+                if let Ok(highlights_json) = serde_json::to_string(highlights) {
+                    let _ = sender.send(PromptResponse::Ok(highlights_json));
+                } else {
+                    log::error!("Failed to serialize highlights");
+                    let _ = sender.send(PromptResponse::Cancel);
+                }
+            }
+            VersoInternalMsg::HighlightAdd(url, highlight, sender) => {
+                let id = self
+                    .highlight_manager
+                    .add_highlight(url.as_url(), highlight);
+                let _ = sender.send(PromptResponse::Ok(id.to_string()));
+            }
+            VersoInternalMsg::HighlightRemove(url, id) => {
+                if let Err(e) = self.highlight_manager.remove_highlight(url.as_url(), id) {
+                    log::error!("Failed to remove highlight: {e}");
+                }
+            }
+            VersoInternalMsg::CrashList(sender) => {
+                let reports = self
+                    .storage
+                    .crash_storage()
+                    .map(|crash_storage| crash_storage.load_reports())
+                    .unwrap_or_default();
+                let response = serde_json::json!({
+                    "reports": reports,
+                    "canSubmit": self.config.crash_report_url.is_some(),
+                });
+                let _ = sender.send(PromptResponse::Ok(response.to_string()));
+            }
+            VersoInternalMsg::CrashSubmit(id, sender) => {
+                let (Some(crash_storage), Some(url)) = (
+                    self.storage.crash_storage(),
+                    self.config.crash_report_url.clone(),
+                ) else {
+                    let _ = sender.send(PromptResponse::Ok("false".to_string()));
+                    return;
+                };
+                let Some(report) = crash_storage.load_report(id) else {
+                    let _ = sender.send(PromptResponse::Ok("false".to_string()));
+                    return;
+                };
+                crash_reporter::submit(crash_storage, report, url, move |submitted| {
+                    let _ = sender.send(PromptResponse::Ok(submitted.to_string()));
+                });
+            }
+            VersoInternalMsg::CrashDelete(id) => {
+                if let Some(crash_storage) = self.storage.crash_storage() {
+                    if let Err(e) = crash_storage.delete_report(id) {
+                        log::error!("Failed to delete crash report {id}: {e}");
+                    }
+                }
+            }
+            VersoInternalMsg::SaveSession => {
+                self.save_session();
+            }
+            VersoInternalMsg::OpenPrivateWindow => {
+                private::open_private_window(&self.config);
+            }
+            VersoInternalMsg::ExportMetrics => {
+                if !self.config.metrics.enabled {
+                    log::warn!("Metrics aren't recorded, enable them with --metrics");
+                    return;
+                }
+                let Some(path) = rfd::FileDialog::new()
+                    .set_file_name("metrics.json")
+                    .add_filter("JSON", &["json"])
+                    .save_file()
+                else {
+                    return;
+                };
+                self.merge_frame_times();
+                let result = serde_json::to_vec_pretty(&self.metrics)
+                    .map_err(std::io::Error::from)
+                    .and_then(|json| std::fs::write(&path, json));
+                if let Err(e) = result {
+                    log::error!("Failed to export metrics to {}: {e}", path.display());
+                }
+            }
+            VersoInternalMsg::ExportHighlights => {
+                let Some(path) = rfd::FileDialog::new()
+                    .set_file_name("highlights.md")
+                    .add_filter("Markdown", &["md"])
+                    .save_file()
+                else {
+                    return;
+                };
+                if let Err(e) = std::fs::write(&path, self.highlight_manager.to_markdown()) {
+                    log::error!("Failed to export highlights to {}: {e}", path.display());
+                }
+            }
+            VersoInternalMsg::WatchedFilesChanged => {
+                let Some(watch_directory) = &self.config.watch_directory else {
+                    return;
+                };
+                // Compare canonical paths, the directory or the files may be behind symlinks
+                let watch_directory = watch_directory
+                    .canonicalize()
+                    .unwrap_or_else(|_| watch_directory.clone());
+                for (window, _) in self.windows.values() {
+                    for tab_id in window.tab_manager.tab_ids() {
+                        let is_watched = window
+                            .tab_manager
+                            .tab(tab_id)
+                            .and_then(|tab| tab.current_url())
+                            .filter(|url| url.scheme() == "file")
+                            .and_then(|url| url.as_url().to_file_path().ok())
+                            .is_some_and(|path| {
+                                path.canonicalize()
+                                    .unwrap_or(path)
+                                    .starts_with(&watch_directory)
+                            });
+                        if is_watched {
+                            self.watch_reloads.insert(tab_id);
+                            send_to_constellation(
+                                &self.constellation_sender,
+                                EmbedderToConstellationMessage::Reload(tab_id),
+                            );
+                        }
+                    }
+                }
+            }
+            VersoInternalMsg::PowerStateChanged(state) => {
+                log::debug!("Verso power state changed: {state:?}");
+                let battery_changed = self.power_state.battery != state.battery;
+                self.power_state = state;
+                self.update_power_save();
+                if battery_changed && self.config.battery_status_api {
+                    self.notify_battery_status();
+                }
+            }
+            VersoInternalMsg::GetGeolocation(webview_id, request_id, high_accuracy) => {
+                match &self.mock_geolocation {
+                    Some(position) => {
+                        let script = geolocation_result_script(request_id, &Ok(position.clone()));
+                        let _ = execute_script(&self.constellation_sender, &webview_id, script);
+                    }
+                    None => geolocation::request_position(
+                        webview_id,
+                        request_id,
+                        high_accuracy,
+                        self.verso_internal_sender.clone(),
+                    ),
+                }
+            }
+            VersoInternalMsg::GeolocationResult(webview_id, request_id, result) => {
+                let script = geolocation_result_script(request_id, &result);
+                let _ = execute_script(&self.constellation_sender, &webview_id, script);
+            }
+            VersoInternalMsg::Share(webview_id, data, sender) => {
+                let Some((window, _)) = self
+                    .windows
+                    .values()
+                    .find(|(window, _)| window.tab_manager.tab(webview_id).is_some())
+                else {
+                    let _ = sender.send(PromptResponse::Cancel);
+                    return;
+                };
+                let response = match share::share(&window.window, &data, self.clipboard.as_mut()) {
+                    Ok(ShareOutcome::Shown) => PromptResponse::Ok("shown".to_string()),
+                    Ok(ShareOutcome::Copied) => PromptResponse::Ok("copied".to_string()),
+                    Err(e) => {
+                        log::error!("Verso failed to share: {e}");
+                        PromptResponse::Cancel
+                    }
+                };
+                let _ = sender.send(response);
+            }
+            VersoInternalMsg::SensorReadingChanged(reading) => {
+                self.sensor_reading = reading;
+                // Only visible documents receive sensor readings
+                let script = sensor_reading_script(&reading);
+                for (window, _) in self.windows.values() {
+                    if let Some(tab_id) = window.tab_manager.current_tab_id() {
+                        let _ = execute_script(&self.constellation_sender, &tab_id, &script);
+                    }
+                }
+            }
+            VersoInternalMsg::GetSensorReading(sender) => {
+                let reading = serde_json::to_string(&self.sensor_reading)
+                    .unwrap_or_else(|_| "{}".to_string());
+                let _ = sender.send(PromptResponse::Ok(reading));
+            }
+            VersoInternalMsg::GetBatteryStatus(sender) => {
+                let status = serde_json::to_string(&self.power_state.battery)
+                    .unwrap_or_else(|_| "null".to_string());
+                let _ = sender.send(PromptResponse::Ok(status));
+            }
+            VersoInternalMsg::SetWakeLock(webview_id, held) => {
+                self.sleep_inhibitors.set_wake_lock(webview_id, held);
+                self.update_power_save();
+            }
+            VersoInternalMsg::WebAppManifestLoaded(webview_id, manifest, icon) => {
+                let Some(web_app) = self.web_app.as_mut() else {
+                    return;
+                };
+                if let Some((window, _)) = self
+                    .windows
+                    .values()
+                    .find(|(window, _)| window.tab_manager.tab(webview_id).is_some())
+                {
+                    apply_manifest(&window.window, &manifest, icon);
+                }
+                web_app.set_manifest(manifest);
+                // The theme color of the page wins over the one of the manifest
+                self.update_titlebar_color(webview_id);
+            }
+            VersoInternalMsg::ScriptHung(webview_id) => {
+                let Some((window, _)) = self
+                    .windows
+                    .values_mut()
+                    .find(|(window, _)| window.tab_manager.tab(webview_id).is_some())
+                else {
+                    return;
+                };
+                if !window.show_slow_script_dialog(&self.constellation_sender, webview_id) {
+                    // Scripts are blocked while the page shows a dialog
+                    self.script_watchdog.wait(webview_id);
+                    return;
+                }
+                log::warn!("Verso found the script of webview {webview_id:?} unresponsive");
+                self.slow_script_dialogs.insert(webview_id);
+                self.notify_script_unresponsive(webview_id, true);
+            }
+            VersoInternalMsg::ScriptResponsive(webview_id) => {
+                self.notify_script_unresponsive(webview_id, false);
+                if self.slow_script_dialogs.remove(&webview_id) {
+                    if let Some((window, _)) = self
+                        .windows
+                        .values_mut()
+                        .find(|(window, _)| window.tab_manager.tab(webview_id).is_some())
+                    {
+                        window.close_prompt_dialog(webview_id);
+                    }
+                }
+            }
+            VersoInternalMsg::PageLoaded(page) => {
+                let Some(history) = &mut self.history else {
+                    return;
+                };
+                if !page.url.starts_with("http:") && !page.url.starts_with("https:") {
+                    return;
+                }
+                if let Err(e) = history.add_visit(&page.url, page.title.as_deref(), &page.text) {
+                    log::error!("Failed to record {} in the history: {e}", page.url);
+                }
+            }
+            VersoInternalMsg::SearchHistory(query, sender) => {
+                let matches = self.search_history(&query);
+                let _ = sender.send(match serde_json::to_string(&matches) {
+                    Ok(matches) => PromptResponse::Ok(matches),
+                    Err(e) => {
+                        log::error!("Failed to serialize history matches: {e}");
+                        PromptResponse::Cancel
+                    }
+                });
+            }
+            VersoInternalMsg::SuggestHistory(input, sender) => {
+                let suggestions = self.suggest_history(&input);
+                let _ = sender.send(match serde_json::to_string(&suggestions) {
+                    Ok(suggestions) => PromptResponse::Ok(suggestions),
+                    Err(e) => {
+                        log::error!("Failed to serialize history suggestions: {e}");
+                        PromptResponse::Cancel
+                    }
+                });
+            }
+            VersoInternalMsg::ClearHistory => {
+                self.clear_history(None);
+            }
+            VersoInternalMsg::ScriptTimeLimitExceeded(webview_id) => {
+                let (Some((window, _)), Some(compositor)) = (
+                    self.windows
+                        .values_mut()
+                        .find(|(window, _)| window.tab_manager.tab(webview_id).is_some()),
+                    &mut self.compositor,
+                ) else {
+                    return;
+                };
+                // Scripts are blocked while the page shows a dialog, that time isn't counted
+                if !self.slow_script_dialogs.contains(&webview_id)
+                    && window
+                        .tab_manager
+                        .tab(webview_id)
+                        .is_some_and(|tab| tab.has_prompt())
+                {
+                    self.script_watchdog.wait(webview_id);
+                    return;
+                }
+                log::warn!(
+                    "Verso closed webview {webview_id:?}, its script exceeded its time limit"
+                );
+                notify_limit_exceeded(
+                    window,
+                    self.to_controller_sender.as_ref(),
+                    webview_id,
+                    ResourceLimit::ScriptTime,
+                );
+                window.close_tab(compositor, webview_id);
+            }
+            VersoInternalMsg::DownloadSizeLimitExceeded(webview_id) => {
+                if let Some((window, _)) = self
+                    .windows
+                    .values()
+                    .find(|(window, _)| window.tab_manager.tab(webview_id).is_some())
+                {
+                    notify_limit_exceeded(
+                        window,
+                        self.to_controller_sender.as_ref(),
+                        webview_id,
+                        ResourceLimit::DownloadSize,
+                    );
+                }
+            }
+            VersoInternalMsg::SlowScriptDialogResponse(webview_id, stop) => {
+                if !self.slow_script_dialogs.remove(&webview_id) {
+                    return;
+                }
+                if !stop {
+                    self.script_watchdog.wait(webview_id);
+                    return;
+                }
+                if let (Some((window, _)), Some(compositor)) = (
+                    self.windows
+                        .values_mut()
+                        .find(|(window, _)| window.tab_manager.tab(webview_id).is_some()),
+                    &mut self.compositor,
+                ) {
+                    window.close_tab(compositor, webview_id);
+                }
+            }
+            VersoInternalMsg::ThemeColorChanged(webview_id, color) => {
+                let Some((window, _)) = self
+                    .windows
+                    .values_mut()
+                    .find(|(window, _)| window.tab_manager.tab(webview_id).is_some())
+                else {
+                    return;
+                };
+                let background = window.tab_manager.current_tab_id() != Some(webview_id);
+                if let Some(tab) = window.tab_manager.tab_mut(webview_id) {
+                    tab.set_theme_color(color.clone());
+                }
+                if let Some(to_controller_sender) = &self.to_controller_sender {
+                    if window.event_listeners.on_theme_color_changed {
+                        if let Err(error) =
+                            to_controller_sender.send(ToControllerMessage::OnThemeColorChanged(
+                                bincode::serialize(&webview_id).unwrap(),
+                                color,
+                                background,
+                            ))
+                        {
+                            log::error!(
+                                "Verso failed to send OnThemeColorChanged to controller: {error}"
+                            )
+                        }
+                    }
+                }
+                self.update_titlebar_color(webview_id);
+            }
+            VersoInternalMsg::UpdateTitlebarColor(webview_id) => {
+                self.update_titlebar_color(webview_id);
+            }
+            VersoInternalMsg::GetPrivacySettings(privacy, sender) => {
+                let privacy = privacy.unwrap_or_else(|| self.config.privacy.clone());
+                let _ = sender.send(match serde_json::to_string(&privacy) {
+                    Ok(privacy) => PromptResponse::Ok(privacy),
+                    Err(e) => {
+                        log::error!("Failed to serialize privacy settings: {e}");
+                        PromptResponse::Cancel
+                    }
+                });
+            }
+            VersoInternalMsg::SecretGet(request, sender) => {
+                let response = self
+                    .secret_bridge_namespace(&request)
+                    .and_then(
+                        |namespace| match self.secret_store.get(&namespace, &request.key) {
+                            Ok(secret) => secret,
+                            Err(e) => {
+                                log::error!("Failed to get secret: {e}");
+                                None
+                            }
+                        },
+                    )
+                    .map_or(PromptResponse::Cancel, PromptResponse::Ok);
+                let _ = sender.send(response);
+            }
+            VersoInternalMsg::SecretSet(request, secret, sender) => {
+                let response = match self.secret_bridge_namespace(&request) {
+                    Some(namespace) => {
+                        match self.secret_store.set(&namespace, &request.key, &secret) {
+                            Ok(()) => PromptResponse::Ok(String::new()),
+                            Err(e) => {
+                                log::error!("Failed to save secret: {e}");
+                                PromptResponse::Cancel
+                            }
+                        }
+                    }
+                    None => PromptResponse::Cancel,
+                };
+                let _ = sender.send(response);
+            }
+            VersoInternalMsg::SecretDelete(request, sender) => {
+                let response = match self.secret_bridge_namespace(&request) {
+                    Some(namespace) => match self.secret_store.delete(&namespace, &request.key) {
+                        Ok(true) => PromptResponse::Ok(String::new()),
+                        Ok(false) => PromptResponse::Cancel,
+                        Err(e) => {
+                            log::error!("Failed to delete secret: {e}");
+                            PromptResponse::Cancel
+                        }
+                    },
+                    None => PromptResponse::Cancel,
+                };
+                let _ = sender.send(response);
+            }
+            VersoInternalMsg::ZoomChanged(url, zoom) => {
+                self.zoom_levels.set_zoom(url.as_url(), zoom);
+                // Other tabs of the site are zoomed as well
+                let origin = url.origin();
+                let tabs: Vec<(WebViewId, ServoUrl)> = self
+                    .windows
+                    .values()
+                    .flat_map(|(window, _)| window.tab_manager.tab_ids())
+                    .filter_map(|tab_id| Some((tab_id, self.tab_url(tab_id)?.clone())))
+                    .filter(|(_, tab_url)| tab_url.origin() == origin)
+                    .collect();
+                for (tab_id, tab_url) in tabs {
+                    self.apply_zoom_level(tab_id, &tab_url);
+                }
+            }
+            VersoInternalMsg::Clipboard(webview_id, action) => {
+                self.clipboard_action(webview_id, action);
+            }
+            VersoInternalMsg::SetClipboard(content) => {
+                if let Some(clipboard) = self.clipboard.as_mut() {
+                    clipboard::write(clipboard, content);
+                }
+            }
+            VersoInternalMsg::CspViolation(webview_id, violation) => {
+                log::warn!("{}: {}", violation.document_url, csp::describe(&violation));
+                if let Some(devtools_bridge) = &self.devtools_bridge {
+                    devtools_bridge.report_csp_violation(webview_id, &violation);
+                }
+                let blocked = integrity::blocked_by_csp(&violation);
+                self.control_clients.broadcast(ControlEvent::CspViolation {
+                    webview: devtools_bridge::target_id(webview_id),
+                    violation,
+                });
+                if let Some(subresource) = blocked {
+                    self.subresource_blocked(webview_id, subresource);
+                }
+            }
+            VersoInternalMsg::SubresourceBlocked(webview_id, subresource) => {
+                log::warn!(
+                    "{}: {}",
+                    subresource.document_url,
+                    integrity::describe(&subresource)
+                );
+                self.subresource_blocked(webview_id, subresource);
+            }
+            VersoInternalMsg::GetJavaScriptEnabled(origin, sender) => {
+                let enabled = match origin {
+                    Some(origin) => {
+                        javascript::javascript_enabled(&self.config.javascript, &origin)
+                    }
+                    None => self.config.javascript.enabled,
+                };
+                let _ = sender.send(PromptResponse::Ok(enabled.to_string()));
+            }
+            VersoInternalMsg::SiteSettings(sender) => {
+                let response = serde_json::json!({ "javascript": self.config.javascript });
+                let _ = sender.send(PromptResponse::Ok(response.to_string()));
+            }
+            VersoInternalMsg::SetJavaScriptEnabled(origin, enabled) => {
+                self.set_javascript_enabled(origin.as_ref(), enabled);
+            }
+            VersoInternalMsg::RememberCredentials(url, for_proxy, credentials) => {
+                self.remembered_credentials.remember(
+                    &self.secret_store,
+                    &url,
+                    for_proxy,
+                    credentials,
+                );
+            }
+            VersoInternalMsg::GetImagePolicy(sender) => {
+                let _ = sender.send(match serde_json::to_string(&self.image_policy) {
+                    Ok(policy) => PromptResponse::Ok(policy),
+                    Err(e) => {
+                        log::error!("Failed to serialize image policy: {e}");
+                        PromptResponse::Cancel
+                    }
+                });
+            }
+            VersoInternalMsg::CertificateError(host, sender) => {
+                let allowed = host.is_some_and(|host| self.certificate_overrides.contains(&host));
+                let can_proceed =
+                    self.config.certificate_errors == CertificateErrorPolicy::Interstitial;
+                let mut response = serde_json::json!({
+                    "allowed": allowed,
+                    "canProceed": can_proceed,
+                });
+                // The page doesn't get the secret from Servo when certificate errors are blocked.
+                // Any page of an allowed host could ask for it while its certificate is invalid,
+                // and allow other certificates with it, which is why only the controller can
+                // allow hosts then.
+                if allowed && !can_proceed {
+                    response["secret"] = net_traits::PRIVILEGED_SECRET.to_string().into();
+                }
+                let _ = sender.send(PromptResponse::Ok(response.to_string()));
+            }
+            VersoInternalMsg::LoadImage(webview_id, url) => {
+                if let Some(tab) = self
+                    .windows
+                    .values_mut()
+                    .find_map(|(window, _)| window.tab_manager.tab_mut(webview_id))
+                {
+                    tab.load_image(url);
+                }
+            }
+        }
+    }
+
+    /// Set whether the pages of the origin run JavaScript, or remove its setting if `None`. Every
+    /// origin without a setting of its own is set if the origin is `None`, and runs JavaScript
+    /// again if the setting is `None` too. Documents already loaded keep running as they were.
+    fn set_javascript_enabled(&mut self, origin: Option<&url::Url>, enabled: Option<bool>) {
+        match origin {
+            Some(origin) => javascript::set_origin_javascript_enabled(
+                &mut self.config.javascript,
+                origin,
+                enabled,
+            ),
+            None => self.config.javascript.enabled = enabled.unwrap_or(true),
+        }
+        self.config.settings.javascript = self.config.javascript.clone();
+    }
+
+    /// Report the blocked script or stylesheet of the page of the webview to the DevTools console
+    /// and the clients of the control protocol.
+    fn subresource_blocked(&self, webview_id: WebViewId, subresource: BlockedSubresource) {
+        if let Some(devtools_bridge) = &self.devtools_bridge {
+            devtools_bridge.report_blocked_subresource(webview_id, &subresource);
+        }
+        self.control_clients
+            .broadcast(ControlEvent::SubresourceBlocked {
+                webview: devtools_bridge::target_id(webview_id),
+                subresource,
+            });
+    }
+
+    /// Copy, cut or paste in the focused element of the webview.
+    fn clipboard_action(&mut self, webview_id: WebViewId, action: ClipboardAction) {
+        match action {
+            ClipboardAction::Copy | ClipboardAction::Cut => clipboard::copy(
+                self.constellation_sender.clone(),
+                webview_id,
+                action == ClipboardAction::Cut,
+                self.verso_internal_sender.clone(),
+            ),
+            ClipboardAction::Paste | ClipboardAction::PasteAsPlainText => {
+                let Some(clipboard) = self.clipboard.as_mut() else {
+                    return;
+                };
+                let (text, html) =
+                    clipboard::read(clipboard, action == ClipboardAction::PasteAsPlainText);
+                clipboard::paste(self.constellation_sender.clone(), webview_id, text, html);
+            }
+        }
+    }
+
+    /// Zoom the tab to the zoom level saved for the origin of its URL, or to the default zoom.
+    fn apply_zoom_level(&mut self, webview_id: WebViewId, url: &ServoUrl) {
+        let Some(compositor) = &mut self.compositor else {
+            return;
+        };
+        let Some((window, _)) = self
+            .windows
+            .values()
+            .find(|(window, _)| window.tab_manager.tab(webview_id).is_some())
+        else {
+            return;
+        };
+        let page_zoom = self
+            .zoom_levels
+            .zoom(url.as_url())
+            .unwrap_or(compositor.default_page_zoom());
+        if compositor.page_zoom(webview_id) != page_zoom {
+            compositor.set_page_zoom(webview_id, page_zoom, window);
+        }
+    }
+
+    /// Get the namespace of secrets saved by the origin of the request, `None` if the request
+    /// wasn't sent by the bridge script or its origin isn't allowed to use the secrets bridge.
+    fn secret_bridge_namespace(&self, request: &SecretRequest) -> Option<String> {
+        if request.token != self.config.secret_bridge_token {
+            log::warn!(
+                "Secrets bridge request with an invalid token from {}",
+                request.origin
+            );
+            return None;
+        }
+        let origin = url::Url::parse(&request.origin).ok()?.origin();
+        if !self.config.secret_bridge_origins.contains(&origin) {
+            log::warn!(
+                "{} isn't allowed to access the secrets bridge",
+                request.origin
+            );
+            return None;
+        }
+        Some(origin.ascii_serialization())
+    }
+
+    fn get_embedder_message_webview_id(msg: &EmbedderMsg) -> Option<&WebViewId> {
+        match msg {
+            EmbedderMsg::Status(webview_id, _) => Some(webview_id),
+            EmbedderMsg::ChangePageTitle(webview_id, _) => Some(webview_id),
+            EmbedderMsg::MoveTo(webview_id, _) => Some(webview_id),
+            EmbedderMsg::ResizeTo(webview_id, _) => Some(webview_id),
+            EmbedderMsg::ShowSimpleDialog(webview_id, _) => Some(webview_id),
+            EmbedderMsg::RequestAuthentication(webview_id, ..) => Some(webview_id),
+            EmbedderMsg::ShowContextMenu(webview_id, _, _, _) => Some(webview_id),
+            EmbedderMsg::AllowNavigationRequest(webview_id, _, _) => Some(webview_id),
+            EmbedderMsg::AllowOpeningWebView(webview_id, _) => Some(webview_id),
+            EmbedderMsg::WebViewClosed(webview_id) => Some(webview_id),
+            EmbedderMsg::WebViewFocused(webview_id) => Some(webview_id),
+            EmbedderMsg::WebViewBlurred => None,
+            EmbedderMsg::AllowUnload(webview_id, _) => Some(webview_id),
+            EmbedderMsg::Keyboard(webview_id, _) => Some(webview_id),
+            EmbedderMsg::ClearClipboard(webview_id) => Some(webview_id),
+            EmbedderMsg::GetClipboardText(webview_id, _) => Some(webview_id),
+            EmbedderMsg::SetClipboardText(webview_id, _) => Some(webview_id),
+            EmbedderMsg::SetCursor(webview_id, _) => Some(webview_id),
+            EmbedderMsg::NewFavicon(webview_id, _) => Some(webview_id),
+            EmbedderMsg::HistoryChanged(webview_id, _, _) => Some(webview_id),
+            EmbedderMsg::NotifyFullscreenStateChanged(webview_id, _) => Some(webview_id),
+            EmbedderMsg::NotifyLoadStatusChanged(webview_id, _) => Some(webview_id),
+            EmbedderMsg::WebResourceRequested(opt_webview_id, _, _) => opt_webview_id.as_ref(),
+            EmbedderMsg::Panic(webview_id, _, _) => Some(webview_id),
+            EmbedderMsg::GetSelectedBluetoothDevice(webview_id, _, _) => Some(webview_id),
+            EmbedderMsg::SelectFiles(webview_id, _, _, _) => Some(webview_id),
+            EmbedderMsg::PromptPermission(webview_id, _, _) => Some(webview_id),
+            EmbedderMsg::ShowIME(webview_id, _, _, _, _) => Some(webview_id),
+            EmbedderMsg::HideIME(webview_id) => Some(webview_id),
+            EmbedderMsg::ReportProfile(_) => None,
+            EmbedderMsg::MediaSessionEvent(webview_id, _) => Some(webview_id),
+            EmbedderMsg::OnDevtoolsStarted(_, _) => None,
+            EmbedderMsg::RequestDevtoolsConnection(_) => None,
+            EmbedderMsg::PlayGamepadHapticEffect(webview_id, _, _, _) => Some(webview_id),
+            EmbedderMsg::StopGamepadHapticEffect(webview_id, _, _) => Some(webview_id),
+            EmbedderMsg::ShowNotification(opt_webview_id, _) => opt_webview_id.as_ref(),
+            EmbedderMsg::ShowSelectElementMenu(webview_id, _, _, _, _) => Some(webview_id),
+            EmbedderMsg::ShutdownComplete => None,
+        }
+    }
+
+    /// Request Verso to redraw. It will queue a redraw event on current focused window.
     pub fn request_redraw(&mut self, evl: &ActiveEventLoop) {
         if let Some(compositor) = &mut self.compositor {
             if let Some(window) = self.windows.get(&compositor.current_window) {
@@ -735,9 +2422,14 @@ impl Verso {
     }
 
     /// Handle message came from webview controller.
-    pub fn handle_incoming_webview_message(&mut self, message: ToVersoMessage) {
+    pub fn handle_incoming_webview_message(
+        &mut self,
+        message: ToVersoMessage,
+        evl: &ActiveEventLoop,
+    ) {
         match message {
             ToVersoMessage::Exit => {
+                self.save_session();
                 if let Some(compositor) = &mut self.compositor {
                     compositor.maybe_start_shutting_down();
                 }
@@ -758,6 +2450,17 @@ impl Verso {
                     );
                 }
             }
+            ToVersoMessage::LoadHtml(html, base_url) => {
+                if let Some(webview_id) = self.first_webview_id() {
+                    send_to_constellation(
+                        &self.constellation_sender,
+                        EmbedderToConstellationMessage::LoadUrl(
+                            webview_id,
+                            ServoUrl::from_url(html_data_url(&html, base_url.as_ref())),
+                        ),
+                    );
+                }
+            }
             ToVersoMessage::Reload => {
                 if let Some(webview_id) = self.first_webview_id() {
                     send_to_constellation(
@@ -785,213 +2488,1539 @@ impl Verso {
                     let _ = execute_script(&self.constellation_sender, &webview_id, js);
                 }
             }
-            ToVersoMessage::ListenToWebResourceRequests => {
-                if let Some(window) = self.first_window_mut() {
-                    window
-                        .event_listeners
-                        .on_web_resource_requested
-                        .replace(HashMap::new());
-                }
+            ToVersoMessage::ListenToWebResourceRequests => {
+                if let Some(window) = self.first_window_mut() {
+                    window
+                        .event_listeners
+                        .on_web_resource_requested
+                        .replace(HashMap::new());
+                }
+            }
+            ToVersoMessage::WebResourceRequestResponse(response) => {
+                let request = self.first_window_mut().and_then(|window| {
+                    window
+                        .event_listeners
+                        .on_web_resource_requested
+                        .as_mut()?
+                        .remove(&response.id)
+                });
+                if let Some(EmbedderMsg::WebResourceRequested(webview_id, request, sender)) =
+                    request
+                {
+                    if let Some(response) = response.response {
+                        let _ = sender
+                            .send(WebResourceResponseMsg::Start(
+                                WebResourceResponse::new(request.url)
+                                    .headers(response.headers().clone())
+                                    .status_code(response.status()),
+                            ))
+                            .and_then(|_| {
+                                sender.send(WebResourceResponseMsg::SendBodyData(
+                                    response.into_body(),
+                                ))
+                            })
+                            .and_then(|_| sender.send(WebResourceResponseMsg::FinishLoad));
+                    } else if self.mock_network {
+                        let _ = sender.send(WebResourceResponseMsg::CancelLoad);
+                    } else if let Some(EmbedderMsg::WebResourceRequested(.., sender)) = self
+                        .handle_network_request(EmbedderMsg::WebResourceRequested(
+                            webview_id, request, sender,
+                        ))
+                    {
+                        let _ = sender.send(WebResourceResponseMsg::DoNotIntercept);
+                    }
+                }
+            }
+            ToVersoMessage::SetResourceOverride(resource_override) => {
+                self.resource_overrides.set(resource_override);
+            }
+            ToVersoMessage::RemoveResourceOverride(url) => {
+                if !self.resource_overrides.remove(&url) {
+                    log::warn!("Verso can't find resource override of {url} to remove");
+                }
+            }
+            ToVersoMessage::SetMockNetwork(enabled) => {
+                self.mock_network = enabled;
+            }
+            ToVersoMessage::SetFilePickerOverride(paths) => {
+                self.file_picker_override = paths;
+            }
+            ToVersoMessage::ListenToOnDialog => {
+                if let Some(window) = self.first_window_mut() {
+                    window.event_listeners.on_dialog.replace(HashMap::new());
+                }
+            }
+            ToVersoMessage::DialogResponse(id, response) => {
+                let dialog = self
+                    .first_window_mut()
+                    .and_then(|window| window.event_listeners.on_dialog.as_mut())
+                    .and_then(|dialogs| dialogs.remove(&id));
+                match dialog {
+                    Some(dialog) => dialog.respond(response),
+                    None => log::warn!("Verso can't find dialog {id} to respond to"),
+                }
+            }
+            ToVersoMessage::ListenToFormSubmissions => {
+                if let Some(window) = self.first_window_mut() {
+                    window
+                        .event_listeners
+                        .on_form_submission
+                        .replace(HashMap::new());
+                }
+            }
+            ToVersoMessage::FormSubmissionResponse(id, response) => {
+                let submission = self
+                    .first_window_mut()
+                    .and_then(|window| window.event_listeners.on_form_submission.as_mut())
+                    .and_then(|submissions| submissions.remove(&id));
+                match submission {
+                    Some(response_sender) => forms::respond(response_sender, response),
+                    None => log::warn!("Verso can't find form submission {id} to respond to"),
+                }
+            }
+            ToVersoMessage::ListenToAuthentication => {
+                if let Some(window) = self.first_window_mut() {
+                    window
+                        .event_listeners
+                        .on_authentication
+                        .replace(HashMap::new());
+                }
+            }
+            ToVersoMessage::AuthenticationResponse(id, credentials) => {
+                let request = self
+                    .first_window_mut()
+                    .and_then(|window| window.event_listeners.on_authentication.as_mut())
+                    .and_then(|requests| requests.remove(&id));
+                match request {
+                    Some(response_sender) => http_auth::respond(response_sender, credentials),
+                    None => log::warn!("Verso can't find authentication {id} to respond to"),
+                }
+            }
+            ToVersoMessage::SetVsync(vsync) => {
+                self.config.vsync = vsync;
+                self.config.settings.vsync = vsync;
+                self.apply_vsync();
+            }
+            ToVersoMessage::SetRotation(rotation) => {
+                self.config.rotation = rotation;
+                self.config.settings.rotation = rotation;
+                self.apply_rotation();
+            }
+            ToVersoMessage::SetCookiePolicy(cookies) => {
+                self.config.cookies = cookies;
+                self.config.settings.cookies = cookies;
+                self.cookie_jar.set_policy(cookies);
+            }
+            ToVersoMessage::ClearCookies(origin) => {
+                self.cookie_jar
+                    .clear(&self.resource_threads, origin.as_ref());
+            }
+            ToVersoMessage::SetJavaScriptEnabled(enabled) => {
+                self.set_javascript_enabled(None, Some(enabled));
+            }
+            ToVersoMessage::SetOriginJavaScriptEnabled(origin, enabled) => {
+                self.set_javascript_enabled(Some(&origin), enabled);
+            }
+            ToVersoMessage::AllowCertificateOverride(host) => {
+                self.certificate_overrides.insert(host.to_ascii_lowercase());
+            }
+            ToVersoMessage::SetCertificateErrorPolicy(policy) => {
+                self.config.certificate_errors = policy;
+                self.config.settings.certificate_errors = policy;
+                self.config
+                    .certificate_errors_blocked
+                    .store(policy == CertificateErrorPolicy::Block, Ordering::Relaxed);
+            }
+            ToVersoMessage::SetAnimationPolicy(policy) => {
+                self.config.animations = policy;
+                self.config.settings.animations = policy;
+                if let Some(compositor) = &mut self.compositor {
+                    compositor.set_animation_policy(policy);
+                }
+            }
+            ToVersoMessage::SetImagePolicy(policy) => {
+                self.image_policy = policy;
+                self.config.images = Some(policy);
+                self.config.settings.images = Some(policy);
+                if let Some(image_policy_storage) = self.storage.image_policy_storage() {
+                    if let Err(e) = image_policy_storage.save_to_file(&policy) {
+                        log::error!("Failed to save image policy: {e}");
+                    }
+                }
+            }
+            ToVersoMessage::ClearCache => {
+                if let Some(http_cache) = &self.http_cache {
+                    http_cache.clear();
+                }
+            }
+            ToVersoMessage::Clipboard(action) => {
+                if let Some(webview_id) = self.first_webview_id() {
+                    self.clipboard_action(webview_id, action);
+                }
+            }
+            ToVersoMessage::SetSecret(key, secret) => {
+                if let Err(e) = self
+                    .secret_store
+                    .set(CONTROLLER_SECRET_NAMESPACE, &key, &secret)
+                {
+                    log::error!("Verso failed to save secret {key}: {e}");
+                }
+            }
+            ToVersoMessage::GetSecret(id, key) => {
+                let secret = self
+                    .secret_store
+                    .get(CONTROLLER_SECRET_NAMESPACE, &key)
+                    .unwrap_or_else(|e| {
+                        log::error!("Verso failed to get secret {key}: {e}");
+                        None
+                    });
+                if let Err(error) = self
+                    .to_controller_sender
+                    .as_ref()
+                    .unwrap()
+                    .send(ToControllerMessage::GetSecretResponse(id, secret))
+                {
+                    log::error!("Verso failed to send GetSecretResponse to controller: {error}")
+                }
+            }
+            ToVersoMessage::StartAuthFlow(id, auth_url, callback_url) => {
+                let Some(compositor) = self.compositor.as_mut() else {
+                    return;
+                };
+                let Some(webrender_document) = self.windows.values().next().map(|(_, d)| *d) else {
+                    return;
+                };
+                let window_attributes = winit::window::Window::default_attributes()
+                    .with_title("Sign in")
+                    .with_inner_size(LogicalSize::new(500, 700));
+                let mut window = Window::new_with_compositor(
+                    evl,
+                    window_attributes,
+                    compositor,
+                    self.verso_internal_sender.clone(),
+                );
+                window.private = self.config.private;
+                window.set_rotation(self.config.rotation, compositor);
+                let webview_id =
+                    window.create_tab(&self.constellation_sender, ServoUrl::from_url(auth_url));
+                self.auth_flows.insert(
+                    window.id(),
+                    AuthFlow {
+                        id,
+                        callback_url,
+                        webview_id,
+                    },
+                );
+                self.windows
+                    .insert(window.id(), (window, webrender_document));
+            }
+            ToVersoMessage::DeleteSecret(key) => {
+                if let Err(e) = self.secret_store.delete(CONTROLLER_SECRET_NAMESPACE, &key) {
+                    log::error!("Verso failed to delete secret {key}: {e}");
+                }
+            }
+            ToVersoMessage::SetSize(size) => {
+                if let Some(window) = self.first_window() {
+                    let _ = window.window.request_inner_size(size);
+                }
+            }
+            ToVersoMessage::SetPosition(position) => {
+                if let Some(window) = self.first_window() {
+                    window.window.set_outer_position(position);
+                }
+            }
+            ToVersoMessage::SetMaximized(maximized) => {
+                if let Some(window) = self.first_window() {
+                    window.window.set_maximized(maximized);
+                }
+            }
+            ToVersoMessage::SetMinimized(minimized) => {
+                if let Some(window) = self.first_window() {
+                    window.window.set_minimized(minimized);
+                }
+            }
+            ToVersoMessage::SetFullscreen(fullscreen) => {
+                if let Some(window) = self.first_window() {
+                    window.window.set_fullscreen(if fullscreen {
+                        Some(winit::window::Fullscreen::Borderless(None))
+                    } else {
+                        None
+                    });
+                }
+            }
+            ToVersoMessage::SetVisible(visible) => {
+                if let Some(window) = self.first_window() {
+                    window.window.set_visible(visible);
+                }
+            }
+            ToVersoMessage::StartDragging => {
+                if let Some(window) = self.first_window() {
+                    let _ = window.window.drag_window();
+                }
+            }
+            ToVersoMessage::Focus => {
+                if let Some(window) = self.first_window() {
+                    window.window.focus_window();
+                }
+            }
+            ToVersoMessage::GetSize(id, size_type) => {
+                if let Some(window) = self.first_window() {
+                    if let Err(error) = self.to_controller_sender.as_ref().unwrap().send(
+                        ToControllerMessage::GetSizeResponse(
+                            id,
+                            match size_type {
+                                SizeType::Inner => window.window.inner_size(),
+                                SizeType::Outer => window.window.outer_size(),
+                            },
+                        ),
+                    ) {
+                        log::error!("Verso failed to send GetSizeReponse to controller: {error}")
+                    }
+                }
+            }
+            ToVersoMessage::GetPosition(id, position_type) => {
+                if let Some(window) = self.first_window() {
+                    if let Err(error) = self.to_controller_sender.as_ref().unwrap().send(
+                        ToControllerMessage::GetPositionResponse(
+                            id,
+                            match position_type {
+                                PositionType::Inner => window.window.inner_position(),
+                                PositionType::Outer => window.window.outer_position(),
+                            }
+                            .ok(),
+                        ),
+                    ) {
+                        log::error!(
+                            "Verso failed to send GetPositionResponse to controller: {error}"
+                        )
+                    }
+                }
+            }
+            ToVersoMessage::GetMinimized(id) => {
+                if let Some(window) = self.first_window() {
+                    if let Err(error) = self.to_controller_sender.as_ref().unwrap().send(
+                        ToControllerMessage::GetMinimizedResponse(
+                            id,
+                            window.window.is_minimized().unwrap_or_default(),
+                        ),
+                    ) {
+                        log::error!(
+                            "Verso failed to send GetMinimizedResponse to controller: {error}"
+                        )
+                    }
+                }
+            }
+            ToVersoMessage::GetMaximized(id) => {
+                if let Some(window) = self.first_window() {
+                    if let Err(error) = self.to_controller_sender.as_ref().unwrap().send(
+                        ToControllerMessage::GetMaximizedResponse(id, window.window.is_maximized()),
+                    ) {
+                        log::error!(
+                            "Verso failed to send GetMaximizedResponse to controller: {error}"
+                        )
+                    }
+                }
+            }
+            ToVersoMessage::GetFullscreen(id) => {
+                if let Some(window) = self.first_window() {
+                    if let Err(error) = self.to_controller_sender.as_ref().unwrap().send(
+                        ToControllerMessage::GetFullscreenResponse(
+                            id,
+                            window.window.fullscreen().is_some(),
+                        ),
+                    ) {
+                        log::error!(
+                            "Verso failed to send GetFullscreenResponse to controller: {error}"
+                        )
+                    }
+                }
+            }
+            ToVersoMessage::GetVisible(id) => {
+                if let Some(window) = self.first_window() {
+                    if let Err(error) = self.to_controller_sender.as_ref().unwrap().send(
+                        ToControllerMessage::GetVisibleResponse(
+                            id,
+                            window.window.is_visible().unwrap_or(true),
+                        ),
+                    ) {
+                        log::error!(
+                            "Verso failed to send GetVisibleResponse to controller: {error}"
+                        )
+                    }
+                }
+            }
+            ToVersoMessage::GetScaleFactor(id) => {
+                if let Some(window) = self.first_window() {
+                    if let Err(error) = self.to_controller_sender.as_ref().unwrap().send(
+                        ToControllerMessage::GetScaleFactorResponse(
+                            id,
+                            window.window.scale_factor(),
+                        ),
+                    ) {
+                        log::error!(
+                            "Verso failed to send GetScaleFactorResponse to controller: {error}"
+                        )
+                    }
+                }
+            }
+            ToVersoMessage::GetWebViews(id) => {
+                let webview_ids = self
+                    .windows
+                    .values()
+                    .flat_map(|(window, _)| window.tab_manager.tab_ids())
+                    .map(|webview_id| bincode::serialize(&webview_id).unwrap())
+                    .collect();
+                if let Some(to_controller_sender) = &self.to_controller_sender {
+                    if let Err(error) = to_controller_sender
+                        .send(ToControllerMessage::GetWebViewsResponse(id, webview_ids))
+                    {
+                        log::error!(
+                            "Verso failed to send GetWebViewsResponse to controller: {error}"
+                        )
+                    }
+                }
+            }
+            ToVersoMessage::SetWebViewName(webview_id, name) => {
+                let webview_id = bincode::deserialize(&webview_id).unwrap();
+                if let Err(error) = self.set_tab_name(webview_id, name) {
+                    log::error!("Verso failed to name the webview: {error}");
+                }
+            }
+            ToVersoMessage::GetWebViewByName(id, name) => {
+                let webview_id = self
+                    .named_tab(&name)
+                    .map(|webview_id| bincode::serialize(&webview_id).unwrap());
+                if let Some(to_controller_sender) = &self.to_controller_sender {
+                    if let Err(error) = to_controller_sender.send(
+                        ToControllerMessage::GetWebViewByNameResponse(id, webview_id),
+                    ) {
+                        log::error!(
+                            "Verso failed to send GetWebViewByNameResponse to controller: {error}"
+                        )
+                    }
+                }
+            }
+            ToVersoMessage::ListProfiles(id) => {
+                if let Some(to_controller_sender) = &self.to_controller_sender {
+                    if let Err(error) = to_controller_sender.send(
+                        ToControllerMessage::ListProfilesResponse(id, storage::list_profiles()),
+                    ) {
+                        log::error!(
+                            "Verso failed to send ListProfilesResponse to controller: {error}"
+                        )
+                    }
+                }
+            }
+            ToVersoMessage::CreateProfile(id, name) => {
+                let result = storage::create_profile(&name);
+                if let Some(to_controller_sender) = &self.to_controller_sender {
+                    if let Err(error) = to_controller_sender
+                        .send(ToControllerMessage::CreateProfileResponse(id, result))
+                    {
+                        log::error!(
+                            "Verso failed to send CreateProfileResponse to controller: {error}"
+                        )
+                    }
+                }
+            }
+            ToVersoMessage::DeleteProfile(id, name) => {
+                let result =
+                    storage::delete_profile(&name, self.config.profile_directory.as_deref());
+                if let Some(to_controller_sender) = &self.to_controller_sender {
+                    if let Err(error) = to_controller_sender
+                        .send(ToControllerMessage::DeleteProfileResponse(id, result))
+                    {
+                        log::error!(
+                            "Verso failed to send DeleteProfileResponse to controller: {error}"
+                        )
+                    }
+                }
+            }
+            ToVersoMessage::ListBookmarks(id) => {
+                let bookmarks = self
+                    .bookmark_manager
+                    .bookmarks()
+                    .iter()
+                    .map(Bookmark::to_info)
+                    .collect();
+                if let Some(to_controller_sender) = &self.to_controller_sender {
+                    if let Err(error) = to_controller_sender
+                        .send(ToControllerMessage::ListBookmarksResponse(id, bookmarks))
+                    {
+                        log::error!(
+                            "Verso failed to send ListBookmarksResponse to controller: {error}"
+                        )
+                    }
+                }
+            }
+            ToVersoMessage::AddBookmark(bookmark) => {
+                self.bookmark_manager.add_bookmark(bookmark.into());
+            }
+            ToVersoMessage::RemoveBookmark(url) => {
+                if let Ok(id) = BookmarkId::from_str(&url) {
+                    if let Err(e) = self.bookmark_manager.remove_bookmark(id) {
+                        log::error!("Failed to remove bookmark: {e}");
+                    }
+                }
+            }
+            ToVersoMessage::ImportBookmarks(id, path) => {
+                let result = self.import_bookmarks(&path);
+                if let Some(to_controller_sender) = &self.to_controller_sender {
+                    if let Err(error) = to_controller_sender
+                        .send(ToControllerMessage::ImportBookmarksResponse(id, result))
+                    {
+                        log::error!(
+                            "Verso failed to send ImportBookmarksResponse to controller: {error}"
+                        )
+                    }
+                }
+            }
+            ToVersoMessage::ExportBookmarks(id, path) => {
+                let result = self.export_bookmarks(&path);
+                if let Some(to_controller_sender) = &self.to_controller_sender {
+                    if let Err(error) = to_controller_sender
+                        .send(ToControllerMessage::ExportBookmarksResponse(id, result))
+                    {
+                        log::error!(
+                            "Verso failed to send ExportBookmarksResponse to controller: {error}"
+                        )
+                    }
+                }
+            }
+            ToVersoMessage::GetCurrentUrl(id) => {
+                if let Some(window) = self.first_window() {
+                    let tab = window.tab_manager.current_tab().unwrap();
+                    let history = tab.history();
+                    if let Err(error) = self.to_controller_sender.as_ref().unwrap().send(
+                        ToControllerMessage::GetCurrentUrlResponse(
+                            id,
+                            history.list[history.current_idx].as_url().clone(),
+                        ),
+                    ) {
+                        log::error!(
+                            "Verso failed to send GetScaleFactorResponse to controller: {error}"
+                        )
+                    }
+                }
+            }
+            ToVersoMessage::ExtractText(id, selector) => {
+                let Some(to_controller_sender) = self.to_controller_sender.clone() else {
+                    return;
+                };
+                if let Some(webview_id) = self.first_webview_id() {
+                    text::send_extracted_text(
+                        self.constellation_sender.clone(),
+                        to_controller_sender,
+                        id,
+                        webview_id,
+                        selector,
+                    );
+                } else if let Err(error) =
+                    to_controller_sender.send(ToControllerMessage::ExtractTextResponse(id, None))
+                {
+                    log::error!("Verso failed to send ExtractTextResponse to controller: {error}")
+                }
+            }
+            ToVersoMessage::SaveAsMarkdown(id, path) => {
+                let Some(to_controller_sender) = self.to_controller_sender.clone() else {
+                    return;
+                };
+                if let Some(webview_id) = self.first_webview_id() {
+                    text::save_as_markdown(
+                        self.constellation_sender.clone(),
+                        to_controller_sender,
+                        id,
+                        webview_id,
+                        path,
+                    );
+                } else if let Err(error) =
+                    to_controller_sender.send(ToControllerMessage::SaveAsMarkdownResponse(
+                        id,
+                        Err("No page is opened".into()),
+                    ))
+                {
+                    log::error!(
+                        "Verso failed to send SaveAsMarkdownResponse to controller: {error}"
+                    )
+                }
+            }
+            ToVersoMessage::CaptureFrame(id) => {
+                let Some(to_controller_sender) = self.to_controller_sender.clone() else {
+                    return;
+                };
+                let send_response = move |image: Option<Vec<u8>>| {
+                    if let Err(error) = to_controller_sender
+                        .send(ToControllerMessage::CaptureFrameResponse(id, image))
+                    {
+                        log::error!(
+                            "Verso failed to send CaptureFrameResponse to controller: {error}"
+                        )
+                    }
+                };
+                let (Some(compositor), Some((window, _))) =
+                    (self.compositor.as_mut(), self.windows.values().next())
+                else {
+                    send_response(None);
+                    return;
+                };
+                compositor
+                    .rendering_context
+                    .capture_frame(Box::new(move |image| {
+                        send_response(image.and_then(|image| {
+                            let mut png = Vec::new();
+                            image
+                                .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+                                .inspect_err(|e| log::error!("Failed to encode the frame: {e}"))
+                                .ok()
+                                .map(|_| png)
+                        }))
+                    }));
+                // Composite the pending changes, so the frame is presented even if nothing
+                // changes in the webviews
+                compositor.composite(window);
+                window.window.request_redraw();
+            }
+            ToVersoMessage::WaitFor(id, condition, timeout) => {
+                let Some(to_controller_sender) = self.to_controller_sender.clone() else {
+                    return;
+                };
+                if let Some(webview_id) = self.first_webview_id() {
+                    wait::wait_for(
+                        self.constellation_sender.clone(),
+                        to_controller_sender,
+                        id,
+                        webview_id,
+                        condition,
+                        Duration::from_millis(timeout),
+                    );
+                } else if let Err(error) =
+                    to_controller_sender.send(ToControllerMessage::WaitForResponse(id, false))
+                {
+                    log::error!("Verso failed to send WaitForResponse to controller: {error}")
+                }
+            }
+            ToVersoMessage::Find(id, text, options) => {
+                let Some(webview_id) = self.first_webview_id() else {
+                    if let Some(to_controller_sender) = &self.to_controller_sender {
+                        if let Err(error) = to_controller_sender
+                            .send(ToControllerMessage::FindResponse(id, FindResult::default()))
+                        {
+                            log::error!("Verso failed to send FindResponse to controller: {error}")
+                        }
+                    }
+                    return;
+                };
+                let to_controller_sender = self.to_controller_sender.clone();
+                find::find_in_page(
+                    self.constellation_sender.clone(),
+                    webview_id,
+                    text,
+                    options,
+                    move |result| {
+                        if let Some(to_controller_sender) = to_controller_sender {
+                            if let Err(error) = to_controller_sender
+                                .send(ToControllerMessage::FindResponse(id, result))
+                            {
+                                log::error!(
+                                    "Verso failed to send FindResponse to controller: {error}"
+                                )
+                            }
+                        }
+                    },
+                );
+            }
+            ToVersoMessage::SearchHistory(id, query) => {
+                let matches = self.search_history(&query);
+                if let Err(error) = self
+                    .to_controller_sender
+                    .as_ref()
+                    .unwrap()
+                    .send(ToControllerMessage::SearchHistoryResponse(id, matches))
+                {
+                    log::error!("Verso failed to send SearchHistoryResponse to controller: {error}")
+                }
+            }
+            ToVersoMessage::SuggestHistory(id, input) => {
+                let suggestions = self.suggest_history(&input);
+                if let Err(error) = self
+                    .to_controller_sender
+                    .as_ref()
+                    .unwrap()
+                    .send(ToControllerMessage::SuggestHistoryResponse(id, suggestions))
+                {
+                    log::error!(
+                        "Verso failed to send SuggestHistoryResponse to controller: {error}"
+                    )
+                }
+            }
+            ToVersoMessage::ClearHistory(since) => {
+                self.clear_history(since);
+            }
+            ToVersoMessage::ListenToOnTitleChanged => {
+                if let Some(window) = self.first_window_mut() {
+                    window.event_listeners.on_title_changed = true;
+                }
+            }
+            ToVersoMessage::ListenToOnFaviconChanged => {
+                if let Some(window) = self.first_window_mut() {
+                    window.event_listeners.on_favicon_changed = true;
+                }
+            }
+            ToVersoMessage::ListenToOnAudioStateChanged => {
+                if let Some(window) = self.first_window_mut() {
+                    window.event_listeners.on_audio_state_changed = true;
+                }
+            }
+            ToVersoMessage::ListenToOnThemeColorChanged => {
+                if let Some(window) = self.first_window_mut() {
+                    window.event_listeners.on_theme_color_changed = true;
+                }
+            }
+            ToVersoMessage::ListenToOnScriptUnresponsive => {
+                if let Some(window) = self.first_window_mut() {
+                    window.event_listeners.on_script_unresponsive = true;
+                }
+            }
+            ToVersoMessage::ListenToOnResourceLimitExceeded => {
+                if let Some(window) = self.first_window_mut() {
+                    window.event_listeners.on_resource_limit_exceeded = true;
+                }
+            }
+            ToVersoMessage::ListenToOnDownloadUpdated => {
+                for (window, _) in self.windows.values_mut() {
+                    window.event_listeners.on_download_updated = true;
+                }
+            }
+            ToVersoMessage::PauseDownload(id) => {
+                if let Some(download) = self.downloads.get_mut(&DownloadId::from_str(&id).unwrap())
+                {
+                    download.control(DownloadControl::Pause);
+                }
+            }
+            ToVersoMessage::ResumeDownload(id) => {
+                if let Some(download) = self.downloads.get_mut(&DownloadId::from_str(&id).unwrap())
+                {
+                    download.control(DownloadControl::Resume);
+                }
+            }
+            ToVersoMessage::CancelDownload(id) => {
+                if let Some(download) = self.downloads.get_mut(&DownloadId::from_str(&id).unwrap())
+                {
+                    download.abort();
+                }
+            }
+            ToVersoMessage::SetResourceLimits(webview_id, limits) => {
+                let webview_id = bincode::deserialize(&webview_id).unwrap();
+                let Some(tab) = self
+                    .windows
+                    .values_mut()
+                    .find_map(|(window, _)| window.tab_manager.tab_mut(webview_id))
+                else {
+                    log::warn!("Verso can't find webview {webview_id:?} to set resource limits");
+                    return;
+                };
+                self.script_watchdog.set_limit(
+                    webview_id,
+                    limits.max_script_time.map(Duration::from_millis),
+                );
+                tab.set_resource_limits(limits);
+            }
+            ToVersoMessage::GetFrameTree(id, webview_id) => {
+                let Some(to_controller_sender) = self.to_controller_sender.clone() else {
+                    return;
+                };
+                let webview_id = bincode::deserialize(&webview_id).unwrap();
+                if self
+                    .windows
+                    .values()
+                    .any(|(window, _)| window.has_webview(webview_id))
+                {
+                    frames::send_frame_tree(
+                        self.constellation_sender.clone(),
+                        to_controller_sender,
+                        id,
+                        webview_id,
+                    );
+                } else if let Err(error) =
+                    to_controller_sender.send(ToControllerMessage::GetFrameTreeResponse(id, None))
+                {
+                    log::error!("Verso failed to send GetFrameTreeResponse to controller: {error}")
+                }
+            }
+            ToVersoMessage::ExecuteScriptInFrame(id, frame_id, script) => {
+                let Some(to_controller_sender) = self.to_controller_sender.clone() else {
+                    return;
+                };
+                frames::execute_script_in_frame(
+                    self.constellation_sender.clone(),
+                    to_controller_sender,
+                    id,
+                    bincode::deserialize(&frame_id).unwrap(),
+                    script,
+                );
+            }
+            ToVersoMessage::GetConfig(id) => {
+                if let Err(error) = self.to_controller_sender.as_ref().unwrap().send(
+                    ToControllerMessage::GetConfigResponse(id, self.redacted_settings()),
+                ) {
+                    log::error!("Verso failed to send GetConfigResponse to controller: {error}")
+                }
+            }
+            ToVersoMessage::UpdateConfig(settings) => {
+                self.update_config(settings);
+            }
+            ToVersoMessage::ListenToOnConfigChanged => {
+                if let Some(window) = self.first_window_mut() {
+                    window.event_listeners.on_config_changed = true;
+                }
+            }
+            ToVersoMessage::SendFrameInput(frame_id, input) => {
+                frames::send_frame_input(
+                    self.constellation_sender.clone(),
+                    bincode::deserialize(&frame_id).unwrap(),
+                    input,
+                );
+            }
+            ToVersoMessage::CloseWebView(webview_id) => {
+                let webview_id = bincode::deserialize(&webview_id).unwrap();
+                if let (Some((window, _)), Some(compositor)) = (
+                    self.windows
+                        .values_mut()
+                        .find(|(window, _)| window.tab_manager.tab(webview_id).is_some()),
+                    &mut self.compositor,
+                ) {
+                    window.close_tab(compositor, webview_id);
+                }
+            }
+            ToVersoMessage::CreateTabGroup(id, name, webview_ids) => {
+                let webview_ids = webview_ids
+                    .iter()
+                    .map(|webview_id| bincode::deserialize(webview_id).unwrap())
+                    .collect();
+                self.session_manager.create_tab_group(id, name, webview_ids);
+            }
+            ToVersoMessage::RemoveTabGroup(id) => {
+                if let Err(error) = self.session_manager.remove_tab_group(id) {
+                    log::error!("Failed to remove tab group: {error}");
+                }
+            }
+            ToVersoMessage::CollapseTabGroup(id) => {
+                self.update_tab_group_urls(Some(id));
+                let Some(group) = self.session_manager.tab_group_mut(id) else {
+                    return;
+                };
+                if group.collapsed {
+                    return;
+                }
+                group.collapsed = true;
+                let webview_ids = std::mem::take(&mut group.webview_ids);
+
+                let Some(compositor) = self.compositor.as_mut() else {
+                    return;
+                };
+                for webview_id in webview_ids {
+                    if let Some((window, _)) = self
+                        .windows
+                        .values_mut()
+                        .find(|(window, _)| window.tab_manager.tab(webview_id).is_some())
+                    {
+                        window.close_tab(compositor, webview_id);
+                    }
+                }
+            }
+            ToVersoMessage::RestoreTabGroup(id) => {
+                let Some(group) = self.session_manager.tab_group_mut(id) else {
+                    return;
+                };
+                if !group.collapsed {
+                    return;
+                }
+                let Some((window, _)) = self.windows.values_mut().next() else {
+                    return;
+                };
+                group.collapsed = false;
+                group.webview_ids = group
+                    .urls
+                    .iter()
+                    .filter_map(|url| ServoUrl::parse(url).ok())
+                    .map(|url| window.create_tab(&self.constellation_sender, url))
+                    .collect();
+            }
+            ToVersoMessage::GetTabGroups(id) => {
+                self.update_tab_group_urls(None);
+                let tab_groups = self
+                    .session_manager
+                    .tab_groups()
+                    .iter()
+                    .map(|group| TabGroupInfo {
+                        id: group.id,
+                        name: group.name.clone(),
+                        collapsed: group.collapsed,
+                        urls: group
+                            .urls
+                            .iter()
+                            .filter_map(|url| url::Url::parse(url).ok())
+                            .collect(),
+                        webview_ids: group
+                            .webview_ids
+                            .iter()
+                            .map(|webview_id| bincode::serialize(webview_id).unwrap())
+                            .collect(),
+                    })
+                    .collect();
+                if let Err(error) = self
+                    .to_controller_sender
+                    .as_ref()
+                    .unwrap()
+                    .send(ToControllerMessage::GetTabGroupsResponse(id, tab_groups))
+                {
+                    log::error!("Verso failed to send GetTabGroupsResponse to controller: {error}")
+                }
+            }
+            ToVersoMessage::RegisterCommand(id, title) => {
+                if let Some(window) = self.first_window_mut() {
+                    if !window
+                        .command_registry
+                        .register(Command::new_embedder(id.clone(), title))
+                    {
+                        log::warn!(
+                            "Verso can't register command {id}, a built-in command has the id"
+                        );
+                    }
+                }
+            }
+            ToVersoMessage::UnregisterCommand(id) => {
+                if let Some(window) = self.first_window_mut() {
+                    window.command_registry.unregister(&id);
+                }
+            }
+            ToVersoMessage::ExecuteCommand(id) => {
+                let Some(compositor) = self.compositor.as_mut() else {
+                    return;
+                };
+                let Some((window, _)) = self.windows.values_mut().next() else {
+                    return;
+                };
+                if window.execute_command(&id, compositor, &self.to_controller_sender)
+                    == CommandAction::NewWindow
+                {
+                    self.open_window(evl);
+                }
+            }
+            ToVersoMessage::SetWebViewPrivacy(webview_id, privacy) => {
+                let webview_id = bincode::deserialize(&webview_id).unwrap();
+                if let Some(tab) = self
+                    .windows
+                    .values_mut()
+                    .find_map(|(window, _)| window.tab_manager.tab_mut(webview_id))
+                {
+                    tab.set_privacy(privacy);
+                } else {
+                    log::warn!("Verso can't find webview {webview_id:?} to set privacy settings");
+                }
+            }
+            ToVersoMessage::SetLifecycleState(webview_id, state) => {
+                let webview_id = bincode::deserialize(&webview_id).unwrap();
+                if let Some((window, _)) = self
+                    .windows
+                    .values_mut()
+                    .find(|(window, _)| window.tab_manager.tab(webview_id).is_some())
+                {
+                    window.set_tab_lifecycle_state(&self.constellation_sender, webview_id, state);
+                }
+            }
+            ToVersoMessage::SetMockGeolocation(position) => {
+                self.mock_geolocation = position;
+            }
+            ToVersoMessage::SetSleepInhibited(webview_id, inhibited) => {
+                let webview_id = bincode::deserialize(&webview_id).unwrap();
+                self.sleep_inhibitors.set(webview_id, inhibited);
+                self.update_power_save();
+            }
+            ToVersoMessage::SetMuted(webview_id, muted) => {
+                let webview_id = bincode::deserialize(&webview_id).unwrap();
+                if let Some((window, _)) = self
+                    .windows
+                    .values_mut()
+                    .find(|(window, _)| window.tab_manager.tab(webview_id).is_some())
+                {
+                    window.set_tab_muted(
+                        &self.constellation_sender,
+                        &self.to_controller_sender,
+                        webview_id,
+                        muted,
+                    );
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Replace the config with the settings of the controller, and apply the ones Verso uses
+    /// while it runs. The others, e.g. user scripts and the initial windows, apply on the next
+    /// launch.
+    fn update_config(&mut self, mut settings: ConfigFromController) {
+        // The token is left out of the config sent to controllers, keep it if they send it back
+        // without
+        settings.ipc_token = settings
+            .ipc_token
+            .or_else(|| self.config.settings.ipc_token.clone());
+        let previous =
+            std::mem::replace(&mut self.config, Config::from_controller_config(settings));
+        // Pages and the resource reader got these at startup
+        self.config.secret_bridge_token = previous.secret_bridge_token;
+        previous.certificate_errors_blocked.store(
+            self.config.certificate_errors == CertificateErrorPolicy::Block,
+            Ordering::Relaxed,
+        );
+        self.config.certificate_errors_blocked = previous.certificate_errors_blocked;
+        self.update_power_save();
+        if let Some(compositor) = &mut self.compositor {
+            compositor.set_animation_policy(self.config.animations);
+        }
+        self.apply_vsync();
+        self.apply_rotation();
+        self.cookie_jar.set_policy(self.config.cookies);
+        self.cookie_jar
+            .set_partitioned(self.config.partition_network);
+        if let Some(http_cache) = &self.http_cache {
+            http_cache.set_max_size(self.config.http_cache_size.saturating_mul(MEGABYTE));
+            http_cache.set_partitioned(self.config.partition_network);
+        }
+        for (window, _) in self.windows.values_mut() {
+            window.set_warm_pool_size(&self.constellation_sender, self.config.warm_pool_size);
+        }
+
+        if self
+            .first_window()
+            .is_some_and(|window| window.event_listeners.on_config_changed)
+        {
+            if let Some(to_controller_sender) = &self.to_controller_sender {
+                if let Err(error) = to_controller_sender.send(ToControllerMessage::OnConfigChanged(
+                    self.redacted_settings(),
+                )) {
+                    log::error!("Verso failed to send OnConfigChanged to controller: {error}");
+                }
+            }
+        }
+        self.control_clients.broadcast(ControlEvent::ConfigChanged {
+            config: Box::new(self.redacted_settings()),
+        });
+    }
+
+    /// The settings of the config for controllers and clients of the control protocol, without
+    /// the token of the control protocol.
+    fn redacted_settings(&self) -> ConfigFromController {
+        ConfigFromController {
+            ipc_token: None,
+            ..self.config.settings.clone()
+        }
+    }
+
+    /// Tell the controller about the progress of the download, if it's listening.
+    fn notify_download_updated(&self, download: &DownloadItem) {
+        if !self
+            .first_window()
+            .is_some_and(|window| window.event_listeners.on_download_updated)
+        {
+            return;
+        }
+        if let Some(to_controller_sender) = &self.to_controller_sender {
+            if let Err(error) = to_controller_sender
+                .send(ToControllerMessage::OnDownloadUpdated(download.to_update()))
+            {
+                log::error!("Verso failed to send OnDownloadUpdated to controller: {error}");
+            }
+        }
+    }
+
+    /// Apply the vsync mode of the config to every window.
+    fn apply_vsync(&self) {
+        let Some(compositor) = &self.compositor else {
+            return;
+        };
+        compositor.rendering_context.set_vsync(self.config.vsync);
+        for (window, _) in self.windows.values() {
+            if let Err(e) = compositor.rendering_context.apply_vsync(&window.surface) {
+                log::error!("Verso failed to set vsync of window {:?}: {e}", window.id());
+            }
+        }
+    }
+
+    /// Apply the rotation of the config to every window.
+    fn apply_rotation(&mut self) {
+        let Some(compositor) = &mut self.compositor else {
+            return;
+        };
+        for (window, _) in self.windows.values_mut() {
+            window.set_rotation(self.config.rotation, compositor);
+        }
+    }
+
+    /// Update the URLs of opened tab groups from their webviews,
+    /// webviews which are closed already are removed from their group.
+    fn update_tab_group_urls(&mut self, group_id: Option<uuid::Uuid>) {
+        for group in self.session_manager.tab_groups_mut() {
+            if group.collapsed || group_id.is_some_and(|id| id != group.id) {
+                continue;
+            }
+            let tabs: Vec<_> = group
+                .webview_ids
+                .iter()
+                .filter_map(|webview_id| {
+                    self.windows
+                        .values()
+                        .find_map(|(window, _)| window.tab_manager.tab(*webview_id))
+                })
+                .collect();
+            group.webview_ids = tabs.iter().map(|tab| tab.id()).collect();
+            group.urls = tabs
+                .iter()
+                .filter_map(|tab| tab.current_url())
+                .map(|url| url.to_string())
+                .collect();
+        }
+    }
+
+    /// Handle a request of a client of the control socket on the tab it addresses, and send the
+    /// response back to the connection it came from. Commands changing a tab are refused while
+    /// another client holds a lease on it.
+    pub fn handle_control_request(
+        &mut self,
+        request: ControlRequest,
+        client: ControlClient,
+        evl: &ActiveEventLoop,
+    ) {
+        let respond = |outcome| client.respond(request.id, outcome);
+        self.control_clients.register(&client);
+        let now = Instant::now();
+        match request.command {
+            ControlCommand::Hello { version, .. } => {
+                respond(control::hello(version, client.id));
+                return;
+            }
+            ControlCommand::ListWebViews => {
+                let webviews = self
+                    .windows
+                    .values()
+                    .flat_map(|(window, _)| window.tab_manager.tab_ids())
+                    .map(|webview_id| ControlWebView {
+                        id: devtools_bridge::target_id(webview_id),
+                        name: self.tab_name(webview_id).map(str::to_owned),
+                        window: self
+                            .tab_window(webview_id)
+                            .and_then(|window| window.name.clone()),
+                        url: self.tab_url(webview_id).map(|url| url.to_string()),
+                        title: self.tab_title(webview_id),
+                        owner: self.control_clients.owner(webview_id, now),
+                    })
+                    .collect();
+                respond(ControlOutcome::WebViews(webviews));
+                return;
+            }
+            ControlCommand::ListMonitors => {
+                let monitors = self
+                    .first_window()
+                    .map(snap::list_monitors)
+                    .unwrap_or_default();
+                respond(ControlOutcome::Monitors(monitors));
+                return;
+            }
+            ControlCommand::Subscribe | ControlCommand::Unsubscribe => {
+                let subscribed = matches!(request.command, ControlCommand::Subscribe);
+                self.control_clients.set_subscribed(client.id, subscribed);
+                respond(ControlOutcome::Result(None));
+                return;
             }
-            ToVersoMessage::WebResourceRequestResponse(response) => {
-                if let Some(window) = self.first_window_mut() {
-                    if let Some((url, sender)) = window
-                        .event_listeners
-                        .on_web_resource_requested
-                        .as_mut()
-                        .and_then(|senders| senders.remove(&response.id))
-                    {
-                        if let Some(response) = response.response {
-                            let _ = sender
-                                .send(WebResourceResponseMsg::Start(
-                                    WebResourceResponse::new(url)
-                                        .headers(response.headers().clone())
-                                        .status_code(response.status()),
-                                ))
-                                .and_then(|_| {
-                                    sender.send(WebResourceResponseMsg::SendBodyData(
-                                        response.into_body(),
-                                    ))
-                                })
-                                .and_then(|_| sender.send(WebResourceResponseMsg::FinishLoad));
-                        } else {
-                            let _ = sender.send(WebResourceResponseMsg::DoNotIntercept);
-                        }
-                    }
-                }
+            _ => {}
+        }
+        let webview_id = match (&request.webview, &request.window) {
+            (Some(key), _) => self.find_tab(key),
+            (None, Some(name)) => {
+                let Some(window) = self.named_window(name) else {
+                    respond(ControlOutcome::error(
+                        ControlErrorCode::NoWebView,
+                        format!("No window {name}"),
+                    ));
+                    return;
+                };
+                window.tab_manager.current_tab_id()
             }
-            ToVersoMessage::SetSize(size) => {
-                if let Some(window) = self.first_window() {
-                    let _ = window.window.request_inner_size(size);
-                }
+            (None, None) => self.first_webview_id(),
+        };
+        let Some(webview_id) = webview_id else {
+            let message = match &request.webview {
+                Some(key) => format!("No tab {key}"),
+                None => "No tab is open".to_string(),
+            };
+            respond(ControlOutcome::error(ControlErrorCode::NoWebView, message));
+            return;
+        };
+
+        // The window is shared by its tabs, so it's only changed while nobody else holds a lease
+        let lease_target = match request.command {
+            ControlCommand::GetTitle
+            | ControlCommand::GetUrl
+            | ControlCommand::GetValue { .. }
+            | ControlCommand::Acquire { .. }
+            | ControlCommand::Release => None,
+            ControlCommand::Resize { .. }
+            | ControlCommand::Snap { .. }
+            | ControlCommand::SetWindowName { .. }
+            | ControlCommand::Exit => Some(None),
+            _ => Some(Some(webview_id)),
+        };
+        if let Some(target) = lease_target {
+            if let Err(outcome) = self.control_clients.check(client.id, target, now) {
+                respond(outcome);
+                return;
             }
-            ToVersoMessage::SetPosition(position) => {
-                if let Some(window) = self.first_window() {
-                    window.window.set_outer_position(position);
-                }
+        }
+
+        let frame_id = || bincode::serialize(&webview_id.0).unwrap();
+        let message = match request.command {
+            ControlCommand::Navigate { url } => {
+                send_to_constellation(
+                    &self.constellation_sender,
+                    EmbedderToConstellationMessage::LoadUrl(webview_id, ServoUrl::from_url(url)),
+                );
+                respond(ControlOutcome::Result(None));
+                return;
             }
-            ToVersoMessage::SetMaximized(maximized) => {
-                if let Some(window) = self.first_window() {
-                    window.window.set_maximized(maximized);
-                }
+            ControlCommand::Reload => {
+                send_to_constellation(
+                    &self.constellation_sender,
+                    EmbedderToConstellationMessage::Reload(webview_id),
+                );
+                respond(ControlOutcome::Result(None));
+                return;
             }
-            ToVersoMessage::SetMinimized(minimized) => {
-                if let Some(window) = self.first_window() {
-                    window.window.set_minimized(minimized);
-                }
+            ControlCommand::Back | ControlCommand::Forward => {
+                let direction = match request.command {
+                    ControlCommand::Back => TraversalDirection::Back(1),
+                    _ => TraversalDirection::Forward(1),
+                };
+                send_to_constellation(
+                    &self.constellation_sender,
+                    EmbedderToConstellationMessage::TraverseHistory(webview_id, direction),
+                );
+                respond(ControlOutcome::Result(None));
+                return;
             }
-            ToVersoMessage::SetFullscreen(fullscreen) => {
-                if let Some(window) = self.first_window() {
-                    window.window.set_fullscreen(if fullscreen {
-                        Some(winit::window::Fullscreen::Borderless(None))
-                    } else {
-                        None
-                    });
-                }
+            ControlCommand::Click { x, y } => {
+                ToVersoMessage::SendFrameInput(frame_id(), FrameInput::Click { x, y })
             }
-            ToVersoMessage::SetVisible(visible) => {
-                if let Some(window) = self.first_window() {
-                    window.window.set_visible(visible);
-                }
+            ControlCommand::Text { text } => {
+                ToVersoMessage::SendFrameInput(frame_id(), FrameInput::Text(text))
             }
-            ToVersoMessage::StartDragging => {
-                if let Some(window) = self.first_window() {
-                    let _ = window.window.drag_window();
-                }
+            ControlCommand::Key { key } => {
+                ToVersoMessage::SendFrameInput(frame_id(), FrameInput::Key(key))
             }
-            ToVersoMessage::Focus => {
-                if let Some(window) = self.first_window() {
-                    window.window.focus_window();
+            ControlCommand::Resize { width, height } => {
+                if let Some(window) = self.tab_window(webview_id) {
+                    let _ = window
+                        .window
+                        .request_inner_size(PhysicalSize::new(width, height));
                 }
+                respond(ControlOutcome::Result(None));
+                return;
             }
-            ToVersoMessage::GetSize(id, size_type) => {
-                if let Some(window) = self.first_window() {
-                    if let Err(error) = self.to_controller_sender.as_ref().unwrap().send(
-                        ToControllerMessage::GetSizeResponse(
-                            id,
-                            match size_type {
-                                SizeType::Inner => window.window.inner_size(),
-                                SizeType::Outer => window.window.outer_size(),
-                            },
-                        ),
-                    ) {
-                        log::error!("Verso failed to send GetSizeReponse to controller: {error}")
+            ControlCommand::Snap { area, monitor } => {
+                let result = match self.tab_window(webview_id) {
+                    Some(window) => window.snap(area, monitor),
+                    None => Ok(()),
+                };
+                let outcome = match result {
+                    Ok(()) => ControlOutcome::Result(None),
+                    Err(message) => {
+                        ControlOutcome::error(ControlErrorCode::InvalidRequest, message)
                     }
-                }
+                };
+                respond(outcome);
+                return;
             }
-            ToVersoMessage::GetPosition(id, position_type) => {
-                if let Some(window) = self.first_window() {
-                    if let Err(error) = self.to_controller_sender.as_ref().unwrap().send(
-                        ToControllerMessage::GetPositionResponse(
-                            id,
-                            match position_type {
-                                PositionType::Inner => window.window.inner_position(),
-                                PositionType::Outer => window.window.outer_position(),
-                            }
-                            .ok(),
-                        ),
-                    ) {
-                        log::error!(
-                            "Verso failed to send GetPositionResponse to controller: {error}"
-                        )
+            ControlCommand::SetName { name } => {
+                let outcome = match self.set_tab_name(webview_id, name) {
+                    Ok(()) => ControlOutcome::Result(None),
+                    Err(message) => {
+                        ControlOutcome::error(ControlErrorCode::InvalidRequest, message)
                     }
-                }
+                };
+                respond(outcome);
+                return;
             }
-            ToVersoMessage::GetMinimized(id) => {
-                if let Some(window) = self.first_window() {
-                    if let Err(error) = self.to_controller_sender.as_ref().unwrap().send(
-                        ToControllerMessage::GetMinimizedResponse(
-                            id,
-                            window.window.is_minimized().unwrap_or_default(),
-                        ),
-                    ) {
-                        log::error!(
-                            "Verso failed to send GetMinimizedResponse to controller: {error}"
-                        )
+            ControlCommand::SetWindowName { name } => {
+                let outcome = match self.set_window_name(webview_id, name) {
+                    Ok(()) => ControlOutcome::Result(None),
+                    Err(message) => {
+                        ControlOutcome::error(ControlErrorCode::InvalidRequest, message)
                     }
-                }
+                };
+                respond(outcome);
+                return;
             }
-            ToVersoMessage::GetMaximized(id) => {
-                if let Some(window) = self.first_window() {
-                    if let Err(error) = self.to_controller_sender.as_ref().unwrap().send(
-                        ToControllerMessage::GetMaximizedResponse(id, window.window.is_maximized()),
-                    ) {
-                        log::error!(
-                            "Verso failed to send GetMaximizedResponse to controller: {error}"
-                        )
-                    }
-                }
+            ControlCommand::Close => {
+                ToVersoMessage::CloseWebView(bincode::serialize(&webview_id).unwrap())
             }
-            ToVersoMessage::GetFullscreen(id) => {
-                if let Some(window) = self.first_window() {
-                    if let Err(error) = self.to_controller_sender.as_ref().unwrap().send(
-                        ToControllerMessage::GetFullscreenResponse(
-                            id,
-                            window.window.fullscreen().is_some(),
-                        ),
-                    ) {
-                        log::error!(
-                            "Verso failed to send GetFullscreenResponse to controller: {error}"
-                        )
-                    }
-                }
+            ControlCommand::Exit => ToVersoMessage::Exit,
+            ControlCommand::GetTitle => {
+                respond(ControlOutcome::Result(self.tab_title(webview_id)));
+                return;
             }
-            ToVersoMessage::GetVisible(id) => {
-                if let Some(window) = self.first_window() {
-                    if let Err(error) = self.to_controller_sender.as_ref().unwrap().send(
-                        ToControllerMessage::GetVisibleResponse(
-                            id,
-                            window.window.is_visible().unwrap_or(true),
-                        ),
-                    ) {
-                        log::error!(
-                            "Verso failed to send GetVisibleResponse to controller: {error}"
-                        )
-                    }
-                }
+            ControlCommand::GetUrl => {
+                let url = self.tab_url(webview_id).map(|url| url.to_string());
+                respond(ControlOutcome::Result(url));
+                return;
             }
-            ToVersoMessage::GetScaleFactor(id) => {
-                if let Some(window) = self.first_window() {
-                    if let Err(error) = self.to_controller_sender.as_ref().unwrap().send(
-                        ToControllerMessage::GetScaleFactorResponse(
-                            id,
-                            window.window.scale_factor(),
-                        ),
-                    ) {
-                        log::error!(
-                            "Verso failed to send GetScaleFactorResponse to controller: {error}"
-                        )
-                    }
-                }
+            ControlCommand::Acquire { ttl } => {
+                let ttl = Duration::from_millis(ttl.unwrap_or(DEFAULT_CONTROL_LEASE));
+                let result = self
+                    .control_clients
+                    .acquire(client.id, webview_id, ttl, now);
+                respond(result.err().unwrap_or(ControlOutcome::Result(None)));
+                return;
             }
-            ToVersoMessage::GetCurrentUrl(id) => {
-                if let Some(window) = self.first_window() {
-                    let tab = window.tab_manager.current_tab().unwrap();
-                    let history = tab.history();
-                    if let Err(error) = self.to_controller_sender.as_ref().unwrap().send(
-                        ToControllerMessage::GetCurrentUrlResponse(
-                            id,
-                            history.list[history.current_idx].as_url().clone(),
-                        ),
-                    ) {
-                        log::error!(
-                            "Verso failed to send GetScaleFactorResponse to controller: {error}"
-                        )
-                    }
-                }
+            ControlCommand::Release => {
+                let result = self.control_clients.release(client.id, webview_id, now);
+                respond(result.err().unwrap_or(ControlOutcome::Result(None)));
+                return;
             }
-            _ => {}
-        }
+            ControlCommand::GetValue { selector } => {
+                self.control_field_value(webview_id, selector, None, client, request.id);
+                return;
+            }
+            ControlCommand::SetValue { selector, value } => {
+                self.control_field_value(webview_id, selector, Some(value), client, request.id);
+                return;
+            }
+            command => {
+                respond(ControlOutcome::error(
+                    ControlErrorCode::UnknownCommand,
+                    format!("Unsupported command {command:?}"),
+                ));
+                return;
+            }
+        };
+        self.handle_incoming_webview_message(message, evl);
+        respond(ControlOutcome::Result(None));
+    }
+
+    /// Get or set the value of a form field of the tab for a control client, and answer it once
+    /// the page did.
+    fn control_field_value(
+        &self,
+        webview_id: WebViewId,
+        selector: String,
+        value: Option<String>,
+        client: ControlClient,
+        id: u64,
+    ) {
+        frames::field_value(
+            self.constellation_sender.clone(),
+            webview_id.0,
+            selector,
+            value,
+            move |result| {
+                let outcome = match result {
+                    Ok(value) => ControlOutcome::Result(Some(value)),
+                    Err(message) => {
+                        ControlOutcome::error(ControlErrorCode::InvalidRequest, message)
+                    }
+                };
+                client.respond(id, outcome);
+            },
+        );
+    }
+
+    /// Forget the disconnected client of the control socket and release its leases.
+    pub fn handle_control_client_closed(&mut self, client: u64) {
+        self.control_clients.remove(client);
     }
 
     fn first_window(&self) -> Option<&Window> {
         self.windows.values().next().map(|(window, _)| window)
     }
 
+    /// Open a new window with the panel, as the `window.new` command does.
+    fn open_window(&mut self, evl: &ActiveEventLoop) {
+        let Some(compositor) = self.compositor.as_mut() else {
+            return;
+        };
+        // Every window renders to the same WebRender document
+        let Some(webrender_document) = self.windows.values().next().map(|(_, document)| *document)
+        else {
+            return;
+        };
+        let mut window = Window::new_with_compositor(
+            evl,
+            self.config.window_attributes.clone(),
+            compositor,
+            self.verso_internal_sender.clone(),
+        );
+        window.private = self.config.private;
+        window.set_rotation(self.config.rotation, compositor);
+        window.create_panel(&self.constellation_sender, self.config.url.clone());
+        window.set_warm_pool_size(&self.constellation_sender, self.config.warm_pool_size);
+        self.windows
+            .insert(window.id(), (window, webrender_document));
+    }
+
     fn first_window_mut(&mut self) -> Option<&mut Window> {
         self.windows.values_mut().next().map(|(window, _)| window)
     }
 
+    /// URL of the tab, if the webview is a tab.
+    /// Whether the message is a resource request of a webview whose requests the controller
+    /// intercepts.
+    fn is_intercepted_by_controller(&self, msg: &EmbedderMsg) -> bool {
+        let EmbedderMsg::WebResourceRequested(Some(webview_id), ..) = msg else {
+            return false;
+        };
+        self.to_controller_sender.is_some()
+            && self.windows.values().any(|(window, _)| {
+                window.has_webview(*webview_id)
+                    && window.event_listeners.on_web_resource_requested.is_some()
+            })
+    }
+
+    /// Serve a resource request from the HTTP cache, or fetch it with the cookie jar's policy,
+    /// and return `None`, other messages are given back. Requests the controller intercepts get
+    /// here once it doesn't answer them. Nothing is served from disk or fetched by Verso in mock
+    /// network mode.
+    fn handle_network_request(&mut self, msg: EmbedderMsg) -> Option<EmbedderMsg> {
+        if self.mock_network {
+            return Some(msg);
+        }
+        let EmbedderMsg::WebResourceRequested(webview_id, ..) = &msg else {
+            return Some(msg);
+        };
+        let webview_id = *webview_id;
+        let top_level_url = webview_id.and_then(|webview_id| self.tab_url(webview_id).cloned());
+        let msg = match &self.http_cache {
+            Some(http_cache) => {
+                let reload =
+                    webview_id.is_some_and(|webview_id| self.watch_reloads.contains(&webview_id));
+                http_cache.handle_request(msg, top_level_url.as_ref(), reload)?
+            }
+            None => msg,
+        };
+        self.cookie_jar.handle_request(msg, top_level_url.as_ref())
+    }
+
+    fn tab_url(&self, webview_id: WebViewId) -> Option<&ServoUrl> {
+        self.windows
+            .values()
+            .find_map(|(window, _)| window.tab_manager.tab(webview_id))
+            .and_then(|tab| tab.current_url())
+    }
+
+    /// Name of the tab, if the webview is a named tab.
+    fn tab_name(&self, webview_id: WebViewId) -> Option<&str> {
+        self.tab_window(webview_id)
+            .and_then(|window| window.tab_manager.tab(webview_id))
+            .and_then(|tab| tab.name())
+    }
+
+    /// Window of the tab, if the webview is a tab.
+    fn tab_window(&self, webview_id: WebViewId) -> Option<&Window> {
+        self.windows
+            .values()
+            .map(|(window, _)| window)
+            .find(|window| window.tab_manager.tab(webview_id).is_some())
+    }
+
+    /// Tab with this name.
+    fn named_tab(&self, name: &str) -> Option<WebViewId> {
+        self.windows
+            .values()
+            .flat_map(|(window, _)| window.tab_manager.tab_ids())
+            .find(|webview_id| self.tab_name(*webview_id) == Some(name))
+    }
+
+    /// Window with this name.
+    fn named_window(&self, name: &str) -> Option<&Window> {
+        self.windows
+            .values()
+            .map(|(window, _)| window)
+            .find(|window| window.name.as_deref() == Some(name))
+    }
+
+    /// Tab addressed by a control request, by its name or else by its ID.
+    fn find_tab(&self, key: &str) -> Option<WebViewId> {
+        self.named_tab(key).or_else(|| {
+            self.windows
+                .values()
+                .flat_map(|(window, _)| window.tab_manager.tab_ids())
+                .find(|webview_id| {
+                    devtools_bridge::target_id(*webview_id).eq_ignore_ascii_case(key)
+                })
+        })
+    }
+
+    /// Name the tab, or remove its name if `None` or empty. Fails if another tab has the name.
+    fn set_tab_name(&mut self, webview_id: WebViewId, name: Option<String>) -> Result<(), String> {
+        let name = name.filter(|name| !name.is_empty());
+        if let Some(name) = &name {
+            if self
+                .named_tab(name)
+                .is_some_and(|named| named != webview_id)
+            {
+                return Err(format!("Another tab is named {name}"));
+            }
+        }
+        let tab = self
+            .windows
+            .values_mut()
+            .find_map(|(window, _)| window.tab_manager.tab_mut(webview_id))
+            .ok_or_else(|| "No such tab".to_string())?;
+        tab.set_name(name);
+        Ok(())
+    }
+
+    /// Name the window of the tab, or remove its name if `None` or empty. Fails if another
+    /// window has the name.
+    fn set_window_name(
+        &mut self,
+        webview_id: WebViewId,
+        name: Option<String>,
+    ) -> Result<(), String> {
+        let name = name.filter(|name| !name.is_empty());
+        let window_id = self
+            .tab_window(webview_id)
+            .map(|window| window.id())
+            .ok_or_else(|| "No such tab".to_string())?;
+        if let Some(name) = &name {
+            if self
+                .named_window(name)
+                .is_some_and(|named| named.id() != window_id)
+            {
+                return Err(format!("Another window is named {name}"));
+            }
+        }
+        if let Some((window, _)) = self.windows.get_mut(&window_id) {
+            window.name = name;
+        }
+        Ok(())
+    }
+
+    /// Title of the tab, if the webview is a tab with a title.
+    fn tab_title(&self, webview_id: WebViewId) -> Option<String> {
+        self.windows
+            .values()
+            .find_map(|(window, _)| window.tab_manager.tab(webview_id))
+            .map(|tab| tab.title())
+            // Tabs without a title are titled "null"
+            .filter(|title| title != "null")
+    }
+
+    /// Merge the frame times measured by the compositor in the metrics.
+    fn merge_frame_times(&mut self) {
+        if let Some(compositor) = &mut self.compositor {
+            self.metrics
+                .merge_frame_times(std::mem::take(&mut compositor.frame_times));
+        }
+    }
+
     fn first_webview_id(&self) -> Option<WebViewId> {
         self.windows
             .values()
@@ -1024,7 +4053,7 @@ impl Verso {
 /// Parse the command line arguments,
 /// if `ipc_channel` is set, we try to connect to it and set up routing to the event loop proxy
 /// then return the config from [`ToVersoMessage::SetConfig`] or fallback to from the command line arguments
-fn try_connect_ipc_and_get_config(
+pub(crate) fn try_connect_ipc_and_get_config(
     proxy: &EventLoopProxy<EventLoopProxyMessage>,
 ) -> (Config, Option<IpcSender<ToControllerMessage>>) {
     let cli_args = parse_cli_args().unwrap_or_default();
@@ -1076,6 +4105,10 @@ pub enum EventLoopProxyMessage {
     IpcMessage(Box<ToVersoMessage>),
     /// Message coming from the internal channel
     VersoInternalMessage(VersoInternalMsg),
+    /// Request of a client of the control socket, and the client to answer
+    ControlRequest(ControlRequest, ControlClient),
+    /// A client of the control socket disconnected
+    ControlClientClosed(u64),
 }
 
 #[derive(Debug, Clone)]
@@ -1150,6 +4183,68 @@ where
     }
 }
 
+/// Create a `data:` URL of a HTML document, with a `<base>` element if `base_url` is set.
+fn html_data_url(html: &str, base_url: Option<&url::Url>) -> url::Url {
+    let html = match base_url {
+        Some(base_url) => {
+            let href = base_url
+                .as_str()
+                .replace('&', "&amp;")
+                .replace('"', "&quot;");
+            let base = format!("<base href=\"{href}\">");
+            // Keep the doctype first, or the document will be rendered in quirks mode
+            let doctype_end = html
+                .trim_start()
+                .get(..9)
+                .filter(|start| start.eq_ignore_ascii_case("<!doctype"))
+                .and_then(|_| html.find('>'))
+                .map_or(0, |end| end + 1);
+            format!("{}{base}{}", &html[..doctype_end], &html[doctype_end..])
+        }
+        None => html.to_string(),
+    };
+    let encoded = percent_encoding::utf8_percent_encode(&html, percent_encoding::NON_ALPHANUMERIC);
+    url::Url::parse(&format!("data:text/html;charset=utf-8,{encoded}"))
+        .expect("Percent encoded data URL should be valid")
+}
+
+/// The tabs of the window to save in the session, `None` if it has no tab with a URL.
+fn session_window(window: &Window, compositor: &IOCompositor) -> Option<SessionWindow> {
+    let current_tab_id = window.tab_manager.current_tab_id();
+    let mut active_tab = 0;
+    let mut tabs = Vec::new();
+    for tab_id in window.tab_manager.tab_ids() {
+        let Some(url) = window
+            .tab_manager
+            .tab(tab_id)
+            .and_then(|tab| tab.current_url())
+        else {
+            continue;
+        };
+        if current_tab_id == Some(tab_id) {
+            active_tab = tabs.len();
+        }
+        let scroll = compositor
+            .webview_scroll_position(tab_id)
+            .unwrap_or_default();
+        tabs.push(SessionTab {
+            url: url.to_string(),
+            scroll_x: scroll.x,
+            scroll_y: scroll.y,
+            name: window
+                .tab_manager
+                .tab(tab_id)
+                .and_then(|tab| tab.name())
+                .map(str::to_owned),
+        });
+    }
+    (!tabs.is_empty()).then(|| SessionWindow {
+        tabs,
+        active_tab,
+        name: window.name.clone(),
+    })
+}
+
 pub(crate) fn send_to_constellation(
     sender: &Sender<EmbedderToConstellationMessage>,
     msg: EmbedderToConstellationMessage,
@@ -0,0 +1,291 @@
+use versoview_messages::VirtualKeyboard;
+
+/// Script which keeps the focused field above the on-screen keyboard when it's focused by touch,
+/// and draws the built-in keyboard at the bottom of the viewport in `built-in` mode, e.g. for
+/// DRM/KMS outputs which have no platform keyboard. Fields focused with a mouse get no keyboard.
+const VIRTUAL_KEYBOARD_SCRIPT: &str = r#"
+((mode) => {
+  if (window.versoVirtualKeyboard) {
+    return;
+  }
+  window.versoVirtualKeyboard = true;
+
+  const TEXT_INPUT_TYPES = ['text', 'search', 'url', 'tel', 'email', 'password', 'number'];
+  const MARGIN = 16;
+  const isEditable = (element) => {
+    if (!element || element.disabled || element.readOnly) {
+      return false;
+    }
+    if (element instanceof HTMLTextAreaElement) {
+      return true;
+    }
+    if (element instanceof HTMLInputElement) {
+      return TEXT_INPUT_TYPES.includes(element.type);
+    }
+    return element.isContentEditable;
+  };
+
+  // Touches are followed by compatibility mouse events, so a touch is recent for a moment
+  let lastTouch = 0;
+  addEventListener('touchstart', () => { lastTouch = Date.now(); }, true);
+  addEventListener('mousedown', () => {
+    if (Date.now() - lastTouch > 1000) {
+      lastTouch = 0;
+    }
+  }, true);
+  const focusedByTouch = () => Date.now() - lastTouch < 1000;
+
+  let keyboard = null;
+  const keyboardHeight = () => (keyboard ? keyboard.offsetHeight : 0);
+  const viewportHeight = () => (window.visualViewport ? visualViewport.height : innerHeight);
+
+  // Scroll the element to be in view above the keyboard
+  const scrollAbove = (element) => {
+    element.scrollIntoView({ block: 'nearest' });
+    const rect = element.getBoundingClientRect();
+    const bottom = viewportHeight() - keyboardHeight();
+    if (rect.bottom + MARGIN > bottom) {
+      window.scrollBy(0, Math.min(rect.bottom + MARGIN - bottom, rect.top - MARGIN));
+    } else if (rect.top < MARGIN) {
+      window.scrollBy(0, rect.top - MARGIN);
+    }
+  };
+
+  const dispatchInput = (element, inputType, data) => {
+    element.dispatchEvent(new InputEvent('input', { bubbles: true, inputType, data }));
+  };
+
+  // Insert text at the caret of the field, replacing the selection
+  const insertText = (element, text) => {
+    if (element.isContentEditable) {
+      const selection = getSelection();
+      if (!selection.rangeCount) {
+        return;
+      }
+      const range = selection.getRangeAt(0);
+      range.deleteContents();
+      const node = document.createTextNode(text);
+      range.insertNode(node);
+      range.setStartAfter(node);
+      range.collapse(true);
+      selection.removeAllRanges();
+      selection.addRange(range);
+    } else {
+      try {
+        element.setRangeText(text, element.selectionStart, element.selectionEnd, 'end');
+      } catch {
+        // Numbers and emails have no selection
+        element.value += text;
+      }
+    }
+    dispatchInput(element, 'insertText', text);
+  };
+
+  const deleteBackward = (element) => {
+    if (element.isContentEditable) {
+      const selection = getSelection();
+      if (!selection.rangeCount) {
+        return;
+      }
+      const range = selection.getRangeAt(0);
+      if (range.collapsed) {
+        if (range.startContainer.nodeType !== Node.TEXT_NODE || range.startOffset === 0) {
+          return;
+        }
+        range.setStart(range.startContainer, range.startOffset - 1);
+      }
+      range.deleteContents();
+    } else {
+      try {
+        const start = element.selectionStart;
+        const end = element.selectionEnd;
+        if (start === end && start > 0) {
+          element.setRangeText('', start - 1, end, 'end');
+        } else {
+          element.setRangeText('', start, end, 'end');
+        }
+      } catch {
+        element.value = element.value.slice(0, -1);
+      }
+    }
+    dispatchInput(element, 'deleteContentBackward', null);
+  };
+
+  const pressEnter = (element) => {
+    const init = { key: 'Enter', code: 'Enter', bubbles: true, cancelable: true };
+    const handled = !element.dispatchEvent(new KeyboardEvent('keydown', init));
+    element.dispatchEvent(new KeyboardEvent('keyup', init));
+    if (handled) {
+      return;
+    }
+    if (element instanceof HTMLInputElement) {
+      if (element.form) {
+        element.form.requestSubmit ? element.form.requestSubmit() : element.form.submit();
+      }
+    } else {
+      insertText(element, '\n');
+    }
+  };
+
+  const LAYOUTS = {
+    letters: ['qwertyuiop', 'asdfghjkl', '⇧zxcvbnm⌫', ['123', ' ', '⏎', '▾']],
+    symbols: ['1234567890', '-/:;()$&@"', '.,?!\'#%*+=', ['abc', ' ', '⏎', '▾']],
+  };
+  let layout = 'letters';
+  let shifted = false;
+  let field = null;
+
+  const press = (key) => {
+    if (!field) {
+      return;
+    }
+    switch (key) {
+      case '⇧':
+        shifted = !shifted;
+        render();
+        return;
+      case '⌫':
+        deleteBackward(field);
+        break;
+      case '⏎':
+        pressEnter(field);
+        break;
+      case '▾':
+        field.blur();
+        return;
+      case '123':
+        layout = 'symbols';
+        render();
+        return;
+      case 'abc':
+        layout = 'letters';
+        render();
+        return;
+      default:
+        insertText(field, shifted ? key.toUpperCase() : key);
+        if (shifted) {
+          shifted = false;
+          render();
+        }
+    }
+    scrollAbove(field);
+  };
+
+  let root = null;
+  const render = () => {
+    root.innerHTML = `<style>
+      :host { all: initial; position: fixed; left: 0; right: 0; bottom: 0; z-index: 2147483647;
+        background: #d1d4d9; padding: 4px; font: 20px system-ui, sans-serif;
+        user-select: none; box-shadow: 0 -1px 4px rgba(0, 0, 0, 0.3); }
+      .row { display: flex; justify-content: center; gap: 4px; margin: 4px 0; }
+      .key { flex: 1 1 0; max-width: 64px; height: 48px; border-radius: 6px; background: white;
+        display: flex; align-items: center; justify-content: center; color: black;
+        box-shadow: 0 1px 0 rgba(0, 0, 0, 0.3); }
+      .wide { max-width: 96px; background: #adb2bb; }
+      .space { flex-grow: 5; max-width: 320px; }
+      .on { background: #7a8594; color: white; }
+    </style>`;
+    for (const row of LAYOUTS[layout]) {
+      const element = document.createElement('div');
+      element.className = 'row';
+      for (const key of typeof row === 'string' ? [...row] : row) {
+        const button = document.createElement('div');
+        button.className = 'key';
+        if (key === ' ') {
+          button.classList.add('space');
+        } else if (['⇧', '⌫', '⏎', '▾', '123', 'abc'].includes(key)) {
+          button.classList.add('wide');
+        }
+        if (key === '⇧' && shifted) {
+          button.classList.add('on');
+        }
+        button.textContent = shifted && key.length === 1 ? key.toUpperCase() : key;
+        button.dataset.key = key;
+        element.append(button);
+      }
+      root.append(element);
+    }
+  };
+
+  // Keys are pressed on touch or mouse down and don't take the focus from the field
+  const onKeyDown = (event) => {
+    event.preventDefault();
+    if (event.type === 'touchstart') {
+      lastTouch = Date.now();
+    } else if (focusedByTouch()) {
+      return;
+    }
+    const key = event.composedPath().find((element) => element.dataset?.key)?.dataset.key;
+    if (key) {
+      press(key);
+    }
+  };
+
+  const showKeyboard = () => {
+    if (!keyboard) {
+      keyboard = document.createElement('verso-virtual-keyboard');
+      root = keyboard.attachShadow({ mode: 'closed' });
+      keyboard.addEventListener('touchstart', onKeyDown);
+      keyboard.addEventListener('mousedown', onKeyDown);
+      render();
+    }
+    if (!keyboard.isConnected) {
+      document.documentElement.append(keyboard);
+      // Let the page scroll its bottom above the keyboard
+      document.documentElement.style.paddingBottom = `${keyboard.offsetHeight}px`;
+    }
+  };
+
+  const hideKeyboard = () => {
+    if (keyboard && keyboard.isConnected) {
+      keyboard.remove();
+      document.documentElement.style.paddingBottom = '';
+    }
+  };
+
+  addEventListener('focusin', (event) => {
+    const element = event.composedPath()[0];
+    if (!isEditable(element) || !focusedByTouch()) {
+      return;
+    }
+    field = element;
+    if (mode === 'built-in') {
+      showKeyboard();
+      scrollAbove(element);
+    } else {
+      // The platform keyboard is shown once the field is focused
+      setTimeout(() => field === element && scrollAbove(element), 300);
+    }
+  }, true);
+
+  addEventListener('focusout', (event) => {
+    if (event.composedPath()[0] !== field) {
+      return;
+    }
+    field = null;
+    shifted = false;
+    hideKeyboard();
+  }, true);
+
+  // The platform keyboard shrinks the viewport when it's shown
+  const onResize = () => {
+    if (field && document.activeElement === field) {
+      scrollAbove(field);
+    }
+  };
+  addEventListener('resize', onResize);
+  if (window.visualViewport) {
+    visualViewport.addEventListener('resize', onResize);
+  }
+})
+"#;
+
+/// Script of the virtual keyboard mode, `None` if the pages get no virtual keyboard.
+pub(crate) fn virtual_keyboard_script(mode: VirtualKeyboard) -> Option<String> {
+    let mode = match mode {
+        VirtualKeyboard::Off => return None,
+        VirtualKeyboard::Platform => "platform",
+        VirtualKeyboard::BuiltIn => "built-in",
+    };
+    Some(format!("{VIRTUAL_KEYBOARD_SCRIPT}('{mode}')"))
+}
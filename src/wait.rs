@@ -0,0 +1,101 @@
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+use base::id::WebViewId;
+use constellation_traits::EmbedderToConstellationMessage;
+use crossbeam_channel::Sender;
+use embedder_traits::WebDriverJSValue;
+use ipc_channel::ipc::IpcSender;
+use versoview_messages::{ToControllerMessage, WaitCondition};
+
+use crate::frames;
+
+/// How often the condition is checked while it isn't met.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Wait on another thread until the condition is met in the webview or the timeout is over,
+/// and send [`ToControllerMessage::WaitForResponse`]. The condition is checked again after
+/// navigations, since it's evaluated in whichever document the webview shows.
+pub(crate) fn wait_for(
+    constellation_sender: Sender<EmbedderToConstellationMessage>,
+    to_controller_sender: IpcSender<ToControllerMessage>,
+    id: uuid::Uuid,
+    webview_id: WebViewId,
+    condition: WaitCondition,
+    timeout: Duration,
+) {
+    let script = condition_script(&condition);
+    let result = thread::Builder::new()
+        .name("WaitFor".to_owned())
+        .spawn(move || {
+            let deadline = Instant::now() + timeout;
+            let met = loop {
+                let remaining = deadline.saturating_duration_since(Instant::now());
+                let value =
+                    frames::execute(&constellation_sender, webview_id.0, &script, remaining);
+                let met = match (&condition, value) {
+                    (WaitCondition::Url(pattern), Some(Ok(WebDriverJSValue::String(url)))) => {
+                        matches_pattern(pattern, &url)
+                    }
+                    (_, Some(Ok(WebDriverJSValue::Boolean(met)))) => met,
+                    // The document may be loading or the page may throw, check again
+                    _ => false,
+                };
+                if met {
+                    break true;
+                }
+                let remaining = deadline.saturating_duration_since(Instant::now());
+                if remaining.is_zero() {
+                    break false;
+                }
+                thread::sleep(remaining.min(POLL_INTERVAL));
+            };
+            if let Err(error) =
+                to_controller_sender.send(ToControllerMessage::WaitForResponse(id, met))
+            {
+                log::error!("Verso failed to send WaitForResponse to controller: {error}")
+            }
+        });
+    if let Err(e) = result {
+        log::error!("Failed to spawn wait thread: {e}");
+    }
+}
+
+/// Script checking the condition, URLs are matched by Verso so the page can't mess with the
+/// pattern.
+fn condition_script(condition: &WaitCondition) -> String {
+    match condition {
+        WaitCondition::Url(_) => "location.href".to_string(),
+        WaitCondition::Selector(selector) => format!(
+            "document.querySelector({}) !== null",
+            serde_json::to_string(selector).unwrap()
+        ),
+        WaitCondition::LoadComplete => "document.readyState === 'complete'".to_string(),
+        WaitCondition::Script(script) => format!(
+            "Boolean((0, eval)({}))",
+            serde_json::to_string(script).unwrap()
+        ),
+    }
+}
+
+/// Whether the text matches the pattern, where `*` matches any characters.
+fn matches_pattern(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+    // Without `*`, the pattern is the whole text
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = text.strip_prefix(first) else {
+        return false;
+    };
+    let Some(last) = parts.next_back() else {
+        return rest.is_empty();
+    };
+    for part in parts {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
@@ -0,0 +1,157 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+use base::id::WebViewId;
+use constellation_traits::EmbedderToConstellationMessage;
+use crossbeam_channel::Sender;
+use embedder_traits::{WebDriverCommandMsg, WebDriverJSResult, WebDriverScriptCommand};
+use ipc_channel::ipc::{self, IpcReceiver, IpcSender, TryRecvError};
+
+use crate::verso::{VersoInternalMsg, send_to_constellation};
+
+/// How often the watched webviews are probed.
+const PROBE_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Probe of a webview, its script thread is hung if the probe isn't answered in time.
+#[derive(Default)]
+struct WatchState {
+    /// When the pending probe was sent, or the user chose to wait, and its result.
+    probe: Option<(Instant, IpcReceiver<WebDriverJSResult>)>,
+    /// Whether the webview was reported as hung since it last answered.
+    hung: bool,
+    /// Whether the hang was reported since the timer of the probe started.
+    reported: bool,
+    /// Budget of script time set by the controller.
+    limit: Option<Duration>,
+    /// Whether the budget was reported as exceeded since the timer of the probe started.
+    exceeded: bool,
+}
+
+/// Watchdog detecting webviews whose script thread doesn't respond. Each watched webview is
+/// probed with a tiny script, and [`VersoInternalMsg::ScriptHung`] is sent if it isn't run within
+/// the timeout, then [`VersoInternalMsg::ScriptResponsive`] once it is. Webviews with a budget
+/// of script time also get [`VersoInternalMsg::ScriptTimeLimitExceeded`] once they use it.
+pub(crate) struct ScriptWatchdog {
+    webviews: Arc<Mutex<HashMap<WebViewId, WatchState>>>,
+    /// Timeout before hangs are reported, `None` if only budgets are watched.
+    timeout: Option<Duration>,
+}
+
+impl ScriptWatchdog {
+    /// Spawn the thread probing the webviews.
+    pub fn new(
+        timeout: Option<Duration>,
+        constellation_sender: Sender<EmbedderToConstellationMessage>,
+        verso_internal_sender: IpcSender<VersoInternalMsg>,
+    ) -> Self {
+        let webviews: Arc<Mutex<HashMap<WebViewId, WatchState>>> = Default::default();
+        let watched = webviews.clone();
+        let result = thread::Builder::new()
+            .name("ScriptWatchdog".to_owned())
+            .spawn(move || {
+                loop {
+                    thread::sleep(PROBE_INTERVAL);
+                    let mut webviews = watched.lock().unwrap();
+                    for (webview_id, state) in webviews.iter_mut() {
+                        let message = match &state.probe {
+                            Some((started, receiver)) => match receiver.try_recv() {
+                                Ok(_) | Err(TryRecvError::IpcError(_)) => {
+                                    state.probe = None;
+                                    state.reported = false;
+                                    state.exceeded = false;
+                                    std::mem::take(&mut state.hung)
+                                        .then_some(VersoInternalMsg::ScriptResponsive(*webview_id))
+                                }
+                                Err(TryRecvError::Empty) => {
+                                    let elapsed = started.elapsed();
+                                    if !state.exceeded
+                                        && state.limit.is_some_and(|limit| elapsed >= limit)
+                                    {
+                                        state.exceeded = true;
+                                        Some(VersoInternalMsg::ScriptTimeLimitExceeded(*webview_id))
+                                    } else if !state.reported
+                                        && timeout.is_some_and(|timeout| elapsed >= timeout)
+                                    {
+                                        state.hung = true;
+                                        state.reported = true;
+                                        Some(VersoInternalMsg::ScriptHung(*webview_id))
+                                    } else {
+                                        None
+                                    }
+                                }
+                            },
+                            None => {
+                                state.probe = probe(&constellation_sender, *webview_id);
+                                None
+                            }
+                        };
+                        if let Some(message) = message {
+                            if let Err(e) = verso_internal_sender.send(message) {
+                                log::error!("Script watchdog failed to notify Verso, stop: {e}");
+                                return;
+                            }
+                        }
+                    }
+                }
+            });
+        if let Err(e) = result {
+            log::error!("Failed to spawn script watchdog thread: {e}");
+        }
+        Self { webviews, timeout }
+    }
+
+    /// Start watching the webview for hangs, if it isn't watched yet.
+    pub fn watch(&self, webview_id: WebViewId) {
+        if self.timeout.is_some() {
+            self.webviews.lock().unwrap().entry(webview_id).or_default();
+        }
+    }
+
+    /// Set the budget of script time of the webview, `None` to remove it.
+    pub fn set_limit(&self, webview_id: WebViewId, limit: Option<Duration>) {
+        let mut webviews = self.webviews.lock().unwrap();
+        if limit.is_none() && self.timeout.is_none() {
+            webviews.remove(&webview_id);
+        } else {
+            let state = webviews.entry(webview_id).or_default();
+            state.limit = limit;
+            state.exceeded = false;
+        }
+    }
+
+    /// Stop watching the webview, e.g. once it's closed.
+    pub fn unwatch(&self, webview_id: WebViewId) {
+        self.webviews.lock().unwrap().remove(&webview_id);
+    }
+
+    /// Give the hung webview another timeout and budget before it's reported again.
+    pub fn wait(&self, webview_id: WebViewId) {
+        if let Some(state) = self.webviews.lock().unwrap().get_mut(&webview_id) {
+            if let Some((started, _)) = &mut state.probe {
+                *started = Instant::now();
+            }
+            state.reported = false;
+            state.exceeded = false;
+        }
+    }
+}
+
+/// Send a script doing nothing to the webview, without waiting for its result.
+fn probe(
+    constellation_sender: &Sender<EmbedderToConstellationMessage>,
+    webview_id: WebViewId,
+) -> Option<(Instant, IpcReceiver<WebDriverJSResult>)> {
+    let (result_sender, result_receiver) = ipc::channel::<WebDriverJSResult>().ok()?;
+    send_to_constellation(
+        constellation_sender,
+        EmbedderToConstellationMessage::WebDriverCommand(WebDriverCommandMsg::ScriptCommand(
+            webview_id.0,
+            WebDriverScriptCommand::ExecuteScript("0".to_string(), result_sender),
+        )),
+    );
+    Some((Instant::now(), result_receiver))
+}
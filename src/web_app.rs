@@ -0,0 +1,773 @@
+use constellation_traits::EmbedderToConstellationMessage;
+use crossbeam_channel::Sender;
+use embedder_traits::EmbedderMsg;
+use serde::{Deserialize, Serialize};
+use url::Url;
+use versoview_messages::Icon;
+use winit::{
+    dpi::PhysicalSize,
+    window::{Fullscreen, Theme, Window},
+};
+
+use crate::verso::send_to_constellation;
+
+/// Script which finds the web app manifest of the document and sends it to Verso with the
+/// `WEB_APP_MANIFEST` request of the Verso bridge. Only added in standalone app mode.
+pub(crate) const WEB_APP_MANIFEST_SCRIPT: &str = r#"
+(() => {
+  if (location.protocol === 'verso:' || window !== window.top) {
+    return;
+  }
+  const sendManifest = () => {
+    const link = document.querySelector('link[rel~="manifest"][href]');
+    if (link) {
+      prompt(`VERSO::WEB_APP_MANIFEST::${link.href}`);
+    }
+  };
+  if (document.readyState === 'loading') {
+    document.addEventListener('DOMContentLoaded', sendManifest);
+  } else {
+    sendManifest();
+  }
+})();
+"#;
+
+/// Script which sends the `theme-color` of the document to Verso with the `THEME_COLOR` request of
+/// the Verso bridge, normalized to `#rrggbb` and empty if it has none. Changes of the meta elements,
+/// e.g. by navigations of single-page apps, and of the media they match are sent too.
+pub(crate) const THEME_COLOR_SCRIPT: &str = r#"
+(() => {
+  if (location.protocol === 'verso:' || window !== window.top) {
+    return;
+  }
+  const context = document.createElement('canvas').getContext('2d');
+  const normalize = (color) => {
+    if (!context) {
+      return color;
+    }
+    // Invalid colors leave the fill style unchanged, whichever it was
+    context.fillStyle = '#000000';
+    context.fillStyle = color;
+    const dark = context.fillStyle;
+    context.fillStyle = '#ffffff';
+    context.fillStyle = color;
+    return dark === context.fillStyle ? dark : '';
+  };
+  const themeColor = () => {
+    for (const meta of document.querySelectorAll('meta[name="theme-color" i][content]')) {
+      const media = meta.getAttribute('media');
+      if (!media || matchMedia(media).matches) {
+        return normalize(meta.content.trim());
+      }
+    }
+    return '';
+  };
+
+  let current = '';
+  const update = () => {
+    const color = themeColor();
+    if (color !== current) {
+      current = color;
+      prompt(`VERSO::THEME_COLOR::${color}`);
+    }
+  };
+  new MutationObserver(update).observe(document, {
+    subtree: true,
+    childList: true,
+    attributes: true,
+    attributeFilter: ['name', 'content', 'media'],
+  });
+  matchMedia('(prefers-color-scheme: dark)').addEventListener?.('change', update);
+  window.addEventListener('popstate', update);
+  if (document.readyState === 'loading') {
+    document.addEventListener('DOMContentLoaded', update);
+  } else {
+    update();
+  }
+})();
+"#;
+
+/// The members of a [web app manifest](https://w3c.github.io/manifest/) Verso uses, with their
+/// URLs resolved against the manifest URL.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WebAppManifest {
+    /// Name of the app, the short name if it has no name
+    pub name: Option<String>,
+    /// URL the app starts at
+    pub start_url: Option<Url>,
+    /// Navigations outside of the scope are opened in the default browser
+    pub scope: Option<Url>,
+    /// Icons of the app
+    pub icons: Vec<ManifestIcon>,
+    /// Color of the titlebar, in sRGB
+    pub theme_color: Option<[u8; 3]>,
+    /// How the window of the app is shown
+    pub display: DisplayMode,
+    /// Orientation the window of the app is locked to
+    pub orientation: Option<Orientation>,
+    /// Shortcuts to parts of the app, shown in the jump list, dock menu or launcher actions
+    pub shortcuts: Vec<ManifestShortcut>,
+}
+
+/// [Display mode](https://w3c.github.io/manifest/#display-modes) of a web app.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DisplayMode {
+    /// Covers the whole screen
+    Fullscreen,
+    /// A window without browser UI
+    #[default]
+    Standalone,
+    /// A window with minimal browser UI, shown as standalone since app windows have no UI
+    MinimalUi,
+    /// A browser tab, shown as standalone too since the app was launched with `--app`
+    Browser,
+}
+
+/// Orientation of a web app. Desktop windows don't rotate, so the window is kept taller or wider
+/// than it's high instead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Orientation {
+    /// Taller than wide
+    Portrait,
+    /// Wider than tall
+    Landscape,
+}
+
+/// A shortcut of a web app manifest.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManifestShortcut {
+    /// Name of the shortcut
+    pub name: String,
+    /// URL the shortcut opens, in the scope of the app
+    pub url: Url,
+}
+
+/// An icon of a web app manifest.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManifestIcon {
+    /// URL of the icon
+    pub src: Url,
+    /// Largest size the icon has, 0 if it's unknown or `any`
+    pub size: u32,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct RawManifest {
+    name: Option<String>,
+    short_name: Option<String>,
+    start_url: Option<String>,
+    scope: Option<String>,
+    icons: Vec<RawIcon>,
+    theme_color: Option<String>,
+    display: Option<String>,
+    orientation: Option<String>,
+    shortcuts: Vec<RawShortcut>,
+}
+
+#[derive(Deserialize)]
+struct RawShortcut {
+    name: Option<String>,
+    short_name: Option<String>,
+    url: String,
+}
+
+#[derive(Deserialize)]
+struct RawIcon {
+    src: String,
+    sizes: Option<String>,
+}
+
+impl WebAppManifest {
+    /// Parse a manifest, members with invalid URLs are ignored.
+    pub fn parse(json: &str, manifest_url: &Url) -> serde_json::Result<Self> {
+        let raw: RawManifest = serde_json::from_str(json)?;
+        let resolve = |url: &str| manifest_url.join(url).ok();
+        Ok(Self {
+            name: raw.name.or(raw.short_name),
+            start_url: raw.start_url.as_deref().and_then(resolve),
+            scope: raw.scope.as_deref().and_then(resolve),
+            icons: raw
+                .icons
+                .iter()
+                .filter_map(|icon| {
+                    Some(ManifestIcon {
+                        src: resolve(&icon.src)?,
+                        size: icon
+                            .sizes
+                            .iter()
+                            .flat_map(|sizes| sizes.split_whitespace())
+                            .filter_map(|size| size.split_once(['x', 'X'])?.0.parse().ok())
+                            .max()
+                            .unwrap_or(0),
+                    })
+                })
+                .collect(),
+            theme_color: raw.theme_color.as_deref().and_then(parse_color),
+            display: match raw.display.as_deref() {
+                Some("fullscreen") => DisplayMode::Fullscreen,
+                Some("minimal-ui") => DisplayMode::MinimalUi,
+                Some("browser") => DisplayMode::Browser,
+                _ => DisplayMode::Standalone,
+            },
+            orientation: raw
+                .orientation
+                .as_deref()
+                .and_then(|orientation| match orientation {
+                    "portrait" | "portrait-primary" | "portrait-secondary" => {
+                        Some(Orientation::Portrait)
+                    }
+                    "landscape" | "landscape-primary" | "landscape-secondary" => {
+                        Some(Orientation::Landscape)
+                    }
+                    _ => None,
+                }),
+            shortcuts: raw
+                .shortcuts
+                .into_iter()
+                .filter_map(|shortcut| {
+                    Some(ManifestShortcut {
+                        name: shortcut.name.or(shortcut.short_name)?,
+                        url: resolve(&shortcut.url)?,
+                    })
+                })
+                .collect(),
+        })
+    }
+
+    /// The icon closest to the size, preferring larger icons.
+    pub fn icon(&self, size: u32) -> Option<&ManifestIcon> {
+        self.icons
+            .iter()
+            .min_by_key(|icon| (icon.size < size, icon.size.abs_diff(size)))
+    }
+}
+
+impl Orientation {
+    /// Keep the window in the orientation. Windows in the wrong orientation are rotated if
+    /// `rotate`, otherwise they are made square, so resizing the window stops at the square.
+    pub fn apply(&self, window: &Window, rotate: bool) {
+        let size = window.inner_size();
+        let wrong = match self {
+            Orientation::Portrait => size.width > size.height,
+            Orientation::Landscape => size.height > size.width,
+        };
+        if !wrong {
+            return;
+        }
+        let new_size = if rotate {
+            PhysicalSize::new(size.height, size.width)
+        } else {
+            let side = size.width.min(size.height);
+            PhysicalSize::new(side, side)
+        };
+        let _ = window.request_inner_size(new_size);
+    }
+}
+
+/// Parse a CSS hex color, `#rgb`, `#rrggbb` or with alpha, which is ignored.
+pub(crate) fn parse_color(color: &str) -> Option<[u8; 3]> {
+    let hex = color.trim().strip_prefix('#')?;
+    if !hex.is_ascii() {
+        return None;
+    }
+    let channel = |digits: &str| u8::from_str_radix(digits, 16).ok();
+    match hex.len() {
+        3 | 4 => {
+            let mut rgb = [0; 3];
+            for (i, value) in rgb.iter_mut().enumerate() {
+                *value = channel(&hex[i..i + 1])? * 0x11;
+            }
+            Some(rgb)
+        }
+        6 | 8 => Some([
+            channel(&hex[0..2])?,
+            channel(&hex[2..4])?,
+            channel(&hex[4..6])?,
+        ]),
+        _ => None,
+    }
+}
+
+/// Tint the titlebar of the window with the color on Windows and macOS, and use the light or dark
+/// theme matching it everywhere else.
+pub(crate) fn set_titlebar_color(window: &Window, color: Option<[u8; 3]>) {
+    let theme = color.map(|[r, g, b]| {
+        // Relative luminance of the color, without gamma correction
+        let luminance = 0.2126 * f32::from(r) + 0.7152 * f32::from(g) + 0.0722 * f32::from(b);
+        if luminance < 128.0 {
+            Theme::Dark
+        } else {
+            Theme::Light
+        }
+    });
+    window.set_theme(theme);
+    if let Err(e) = platform::set_titlebar_color(window, color, theme == Some(Theme::Dark)) {
+        log::error!("Verso failed to set the titlebar color: {e}");
+    }
+}
+
+/// Apply the manifest to the window of the app: its name, icon, theme color, display mode and
+/// orientation, and register its shortcuts with the system.
+pub(crate) fn apply_manifest(window: &Window, manifest: &WebAppManifest, icon: Option<Icon>) {
+    if let Some(name) = &manifest.name {
+        window.set_title(name);
+    }
+    if let Some(icon) = icon {
+        window.set_window_icon(
+            winit::window::Icon::from_rgba(icon.rgba, icon.width, icon.height).ok(),
+        );
+    }
+    if manifest.theme_color.is_some() {
+        set_titlebar_color(window, manifest.theme_color);
+    }
+    if manifest.display == DisplayMode::Fullscreen {
+        window.set_fullscreen(Some(Fullscreen::Borderless(None)));
+    }
+    if let Some(orientation) = manifest.orientation {
+        orientation.apply(window, true);
+    }
+    if let Err(e) = platform::set_shortcuts(manifest) {
+        log::error!("Verso failed to register the app shortcuts: {e}");
+    }
+}
+
+/// Launch a new Verso process running the URL as a standalone app, shortcuts are opened this way.
+#[cfg(macos)]
+fn launch_app(url: &Url) {
+    let result = std::env::current_exe().and_then(|exe| {
+        std::process::Command::new(exe)
+            .arg("--app")
+            .arg(url.as_str())
+            .spawn()
+    });
+    if let Err(e) = result {
+        log::error!("Verso failed to open the app shortcut {url}: {e}");
+    }
+}
+
+/// A site running as a standalone app, see `--app`.
+pub struct WebApp {
+    /// Navigations outside of the scope are opened in the default browser.
+    scope: Url,
+    /// The manifest of the app, once loaded.
+    manifest: Option<WebAppManifest>,
+}
+
+impl WebApp {
+    /// Create the app starting at the URL. Its scope is the directory of the URL until the manifest
+    /// sets one.
+    pub fn new(start_url: &Url) -> Self {
+        Self {
+            scope: start_url.join("./").unwrap_or_else(|_| start_url.clone()),
+            manifest: None,
+        }
+    }
+
+    /// The manifest of the app, once loaded.
+    pub fn manifest(&self) -> Option<&WebAppManifest> {
+        self.manifest.as_ref()
+    }
+
+    /// Use the scope of the manifest. It has to be on the origin of the app.
+    pub fn set_manifest(&mut self, manifest: WebAppManifest) {
+        if let Some(scope) = manifest
+            .scope
+            .as_ref()
+            .filter(|scope| scope.origin() == self.scope.origin())
+        {
+            self.scope = scope.clone();
+        }
+        self.manifest = Some(manifest);
+    }
+
+    /// Orientation the window of the app is locked to.
+    pub fn orientation(&self) -> Option<Orientation> {
+        self.manifest.as_ref()?.orientation
+    }
+
+    /// Whether the URL is in the scope of the app.
+    pub fn is_in_scope(&self, url: &Url) -> bool {
+        url.origin() == self.scope.origin() && url.path().starts_with(self.scope.path())
+    }
+
+    /// Open web pages outside of the scope in the default browser instead, and return `None`.
+    /// Other messages are given back.
+    pub(crate) fn handle_navigation(
+        &self,
+        msg: EmbedderMsg,
+        constellation_sender: &Sender<EmbedderToConstellationMessage>,
+    ) -> Option<EmbedderMsg> {
+        let EmbedderMsg::AllowNavigationRequest(_, pipeline_id, ref url) = msg else {
+            return Some(msg);
+        };
+        let url = url.as_url();
+        if !matches!(url.scheme(), "http" | "https") || self.is_in_scope(url) {
+            return Some(msg);
+        }
+        send_to_constellation(
+            constellation_sender,
+            EmbedderToConstellationMessage::AllowNavigationResponse(pipeline_id, false),
+        );
+        if let Err(e) = open::that_detached(url.as_str()) {
+            log::error!("Verso failed to open {url} in the default browser: {e}");
+        }
+        None
+    }
+}
+
+/// Fetch and parse the manifest, then fetch its icon for the window.
+pub(crate) async fn fetch_manifest(
+    client: &reqwest::Client,
+    manifest_url: Url,
+) -> Result<(WebAppManifest, Option<Icon>), Box<dyn std::error::Error + Send + Sync>> {
+    let json = client
+        .get(manifest_url.clone())
+        .send()
+        .await?
+        .text()
+        .await?;
+    let manifest = WebAppManifest::parse(&json, &manifest_url)?;
+    let icon = match manifest.icon(256) {
+        Some(icon) => fetch_icon(client, icon.src.clone())
+            .await
+            .inspect_err(|e| log::warn!("Verso failed to load the app icon {}: {e}", icon.src))
+            .ok(),
+        None => None,
+    };
+    Ok((manifest, icon))
+}
+
+async fn fetch_icon(
+    client: &reqwest::Client,
+    url: Url,
+) -> Result<Icon, Box<dyn std::error::Error + Send + Sync>> {
+    let bytes = client.get(url).send().await?.bytes().await?;
+    let image = image::load_from_memory(&bytes)?.into_rgba8();
+    Ok(Icon {
+        width: image.width(),
+        height: image.height(),
+        rgba: image.into_raw(),
+    })
+}
+
+#[cfg(macos)]
+mod platform {
+    use std::cell::RefCell;
+
+    use objc2::{
+        ffi,
+        rc::Id,
+        runtime::{AnyObject, Imp, Sel},
+        sel,
+    };
+    use objc2_app_kit::{NSApplication, NSColor, NSMenu, NSMenuItem, NSView};
+    use objc2_foundation::{MainThreadMarker, NSString};
+    use raw_window_handle::{HasWindowHandle, RawWindowHandle};
+    use url::Url;
+    use winit::window::Window;
+
+    use super::{WebAppManifest, launch_app};
+
+    thread_local! {
+        /// Dock menu returned to AppKit by `applicationDockMenu:`.
+        static DOCK_MENU: RefCell<Option<Id<NSMenu>>> = const { RefCell::new(None) };
+        /// URLs of the shortcuts, indexed by the tags of their menu items.
+        static SHORTCUT_URLS: RefCell<Vec<Url>> = const { RefCell::new(Vec::new()) };
+    }
+
+    /// Make the titlebar transparent over the background color of the window.
+    #[allow(unused_unsafe)]
+    pub fn set_titlebar_color(
+        window: &Window,
+        color: Option<[u8; 3]>,
+        _dark: bool,
+    ) -> Result<(), String> {
+        let handle = window.window_handle().map_err(|e| e.to_string())?;
+        let RawWindowHandle::AppKit(handle) = handle.as_raw() else {
+            return Ok(());
+        };
+        unsafe {
+            let view: Id<NSView> =
+                Id::retain(handle.ns_view.as_ptr().cast()).ok_or("The window has no view")?;
+            let ns_window = view.window().ok_or("The view has no window")?;
+            ns_window.setTitlebarAppearsTransparent(color.is_some());
+            let background = match color {
+                Some([r, g, b]) => NSColor::colorWithSRGBRed_green_blue_alpha(
+                    f64::from(r) / 255.0,
+                    f64::from(g) / 255.0,
+                    f64::from(b) / 255.0,
+                    1.0,
+                ),
+                None => NSColor::windowBackgroundColor(),
+            };
+            ns_window.setBackgroundColor(Some(&background));
+        }
+        Ok(())
+    }
+
+    /// Show the shortcuts in the dock menu. The menu is returned by an `applicationDockMenu:`
+    /// method added to the application delegate of winit.
+    #[allow(unused_unsafe)]
+    pub fn set_shortcuts(manifest: &WebAppManifest) -> Result<(), String> {
+        let mtm = MainThreadMarker::new().ok_or("Shortcuts must be set on the main thread")?;
+        let app = NSApplication::sharedApplication(mtm);
+        let delegate: Id<AnyObject> =
+            unsafe { Id::cast(app.delegate().ok_or("The application has no delegate")?) };
+
+        unsafe {
+            let class = delegate.class() as *const _ as *mut _;
+            let dock_menu: unsafe extern "C" fn(&AnyObject, Sel, &AnyObject) -> *mut NSMenu =
+                dock_menu;
+            let open_shortcut: unsafe extern "C" fn(&AnyObject, Sel, &NSMenuItem) = open_shortcut;
+            // Adding methods fails if they were added for a previous manifest, which is fine
+            ffi::class_addMethod(
+                class,
+                sel!(applicationDockMenu:),
+                std::mem::transmute::<_, Imp>(dock_menu),
+                c"@@:@".as_ptr(),
+            );
+            ffi::class_addMethod(
+                class,
+                sel!(versoOpenShortcut:),
+                std::mem::transmute::<_, Imp>(open_shortcut),
+                c"v@:@".as_ptr(),
+            );
+        }
+
+        let menu = NSMenu::new(mtm);
+        for (index, shortcut) in manifest.shortcuts.iter().enumerate() {
+            unsafe {
+                let item = NSMenuItem::initWithTitle_action_keyEquivalent(
+                    mtm.alloc(),
+                    &NSString::from_str(&shortcut.name),
+                    Some(sel!(versoOpenShortcut:)),
+                    &NSString::from_str(""),
+                );
+                item.setTag(index as isize);
+                item.setTarget(Some(&delegate));
+                menu.addItem(&item);
+            }
+        }
+        SHORTCUT_URLS.set(
+            manifest
+                .shortcuts
+                .iter()
+                .map(|shortcut| shortcut.url.clone())
+                .collect(),
+        );
+        DOCK_MENU.set((!manifest.shortcuts.is_empty()).then_some(menu));
+        Ok(())
+    }
+
+    unsafe extern "C" fn dock_menu(
+        _this: &AnyObject,
+        _cmd: Sel,
+        _sender: &AnyObject,
+    ) -> *mut NSMenu {
+        DOCK_MENU.with_borrow(|menu| {
+            menu.as_ref()
+                .map_or(std::ptr::null_mut(), |menu| Id::as_ptr(menu).cast_mut())
+        })
+    }
+
+    unsafe extern "C" fn open_shortcut(_this: &AnyObject, _cmd: Sel, sender: &NSMenuItem) {
+        let index = unsafe { sender.tag() };
+        SHORTCUT_URLS.with_borrow(|urls| {
+            if let Some(url) = usize::try_from(index)
+                .ok()
+                .and_then(|index| urls.get(index))
+            {
+                launch_app(url);
+            }
+        });
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use raw_window_handle::{HasWindowHandle, RawWindowHandle};
+    use windows::{
+        Win32::{
+            Foundation::{COLORREF, HWND},
+            Graphics::Dwm::{DWMWA_CAPTION_COLOR, DWMWA_TEXT_COLOR, DwmSetWindowAttribute},
+            Storage::EnhancedStorage::PKEY_Title,
+            System::Com::{CLSCTX_INPROC_SERVER, CoCreateInstance, StructuredStorage::PROPVARIANT},
+            UI::Shell::{
+                Common::{IObjectArray, IObjectCollection},
+                DestinationList, EnumerableObjectCollection, ICustomDestinationList, IShellLinkW,
+                PropertiesSystem::IPropertyStore,
+                ShellLink,
+            },
+        },
+        core::{HSTRING, Interface},
+    };
+    use winit::window::Window;
+
+    use super::WebAppManifest;
+
+    /// Color the caption of the window with DWM, supported since Windows 11.
+    pub fn set_titlebar_color(
+        window: &Window,
+        color: Option<[u8; 3]>,
+        dark: bool,
+    ) -> Result<(), String> {
+        let handle = window.window_handle().map_err(|e| e.to_string())?;
+        let RawWindowHandle::Win32(handle) = handle.as_raw() else {
+            return Ok(());
+        };
+        let hwnd = HWND(handle.hwnd.get() as _);
+        // 0xFFFFFFFF restores the default color
+        let (caption, text) = match color {
+            Some([r, g, b]) => (
+                COLORREF(u32::from(r) | u32::from(g) << 8 | u32::from(b) << 16),
+                COLORREF(if dark { 0x00ffffff } else { 0 }),
+            ),
+            None => (COLORREF(0xffffffff), COLORREF(0xffffffff)),
+        };
+        for (attribute, color) in [(DWMWA_CAPTION_COLOR, caption), (DWMWA_TEXT_COLOR, text)] {
+            unsafe {
+                DwmSetWindowAttribute(
+                    hwnd,
+                    attribute,
+                    &color as *const COLORREF as *const _,
+                    size_of::<COLORREF>() as u32,
+                )
+            }
+            .map_err(|e| e.to_string())?;
+        }
+        Ok(())
+    }
+
+    /// Add the shortcuts to the tasks of the jump list, each launching Verso with `--app`.
+    pub fn set_shortcuts(manifest: &WebAppManifest) -> Result<(), String> {
+        let exe = std::env::current_exe().map_err(|e| e.to_string())?;
+        let set_tasks = || -> windows::core::Result<()> {
+            let list: ICustomDestinationList =
+                unsafe { CoCreateInstance(&DestinationList, None, CLSCTX_INPROC_SERVER)? };
+            let mut max_slots = 0;
+            let _removed: IObjectArray = unsafe { list.BeginList(&mut max_slots)? };
+            let tasks: IObjectCollection = unsafe {
+                CoCreateInstance(&EnumerableObjectCollection, None, CLSCTX_INPROC_SERVER)?
+            };
+            for shortcut in manifest.shortcuts.iter().take(max_slots as usize) {
+                let link: IShellLinkW =
+                    unsafe { CoCreateInstance(&ShellLink, None, CLSCTX_INPROC_SERVER)? };
+                unsafe {
+                    link.SetPath(&HSTRING::from(exe.as_path()))?;
+                    link.SetArguments(&HSTRING::from(format!("--app \"{}\"", shortcut.url)))?;
+                    link.SetDescription(&HSTRING::from(&shortcut.name))?;
+                    let properties: IPropertyStore = link.cast()?;
+                    properties.SetValue(&PKEY_Title, &PROPVARIANT::from(shortcut.name.as_str()))?;
+                    properties.Commit()?;
+                    tasks.AddObject(&link)?;
+                }
+            }
+            unsafe {
+                list.AddUserTasks(&tasks.cast::<IObjectArray>()?)?;
+                list.CommitList()
+            }
+        };
+        set_tasks().map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(linux)]
+mod platform {
+    use std::fmt::Write;
+
+    use directories::BaseDirs;
+    use winit::window::Window;
+
+    use super::WebAppManifest;
+
+    /// Linux desktops don't let apps color their titlebar.
+    pub fn set_titlebar_color(
+        _window: &Window,
+        _color: Option<[u8; 3]>,
+        _dark: bool,
+    ) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// Install a desktop entry for the app, with the shortcuts as its actions, which launchers show
+    /// in the menu of the app.
+    pub fn set_shortcuts(manifest: &WebAppManifest) -> Result<(), String> {
+        let (Some(name), Some(start_url)) = (&manifest.name, &manifest.start_url) else {
+            return Ok(());
+        };
+        let exe = std::env::current_exe().map_err(|e| e.to_string())?;
+        let exec = |url: &url::Url| {
+            format!(
+                "{} --app {}",
+                quote(&exe.to_string_lossy()),
+                quote(url.as_str())
+            )
+        };
+
+        let mut entry = format!(
+            "[Desktop Entry]\nType=Application\nName={name}\nExec={}\n",
+            exec(start_url)
+        );
+        if !manifest.shortcuts.is_empty() {
+            let actions: Vec<String> = (0..manifest.shortcuts.len())
+                .map(|index| format!("shortcut{index};"))
+                .collect();
+            let _ = writeln!(entry, "Actions={}", actions.concat());
+        }
+        for (index, shortcut) in manifest.shortcuts.iter().enumerate() {
+            let _ = write!(
+                entry,
+                "\n[Desktop Action shortcut{index}]\nName={}\nExec={}\n",
+                shortcut.name,
+                exec(&shortcut.url)
+            );
+        }
+
+        let host = start_url.host_str().unwrap_or("local");
+        let directory = BaseDirs::new()
+            .ok_or("No home directory")?
+            .data_dir()
+            .join("applications");
+        std::fs::create_dir_all(&directory).map_err(|e| e.to_string())?;
+        std::fs::write(directory.join(format!("verso-app-{host}.desktop")), entry)
+            .map_err(|e| e.to_string())
+    }
+
+    /// Quote an argument of the `Exec` key of a desktop entry. Backslashes are escaped twice, once
+    /// for the string value and once for the quoting.
+    fn quote(argument: &str) -> String {
+        let mut quoted = String::from('"');
+        for c in argument.chars() {
+            match c {
+                '"' | '`' | '$' | '\\' => {
+                    quoted.push_str("\\\\");
+                    quoted.push(c);
+                }
+                '%' => quoted.push_str("%%"),
+                c => quoted.push(c),
+            }
+        }
+        quoted.push('"');
+        quoted
+    }
+}
+
+#[cfg(not(any(linux, macos, windows)))]
+mod platform {
+    use winit::window::Window;
+
+    use super::WebAppManifest;
+
+    pub fn set_titlebar_color(
+        _window: &Window,
+        _color: Option<[u8; 3]>,
+        _dark: bool,
+    ) -> Result<(), String> {
+        Ok(())
+    }
+
+    pub fn set_shortcuts(_manifest: &WebAppManifest) -> Result<(), String> {
+        Ok(())
+    }
+}
@@ -0,0 +1,50 @@
+use constellation_traits::EmbedderToConstellationMessage;
+use crossbeam_channel::Sender;
+use euclid::{Rect, Scale};
+use image::RgbaImage;
+use ipc_channel::ipc::IpcSharedMemory;
+use net_traits::image::base::{Image, PixelFormat};
+use net_traits::image_cache::CorsStatus;
+use style_traits::CSSPixel;
+use webrender_api::units::{DeviceIntRect, DevicePixel, DeviceRect};
+
+/// Start the WebDriver server of Servo on the port. It sends the commands of the sessions to the
+/// constellation, screenshots are taken by the compositor with [`screenshot_rect`] and
+/// [`screenshot_image`].
+pub(crate) fn start_server(
+    port: u16,
+    constellation_sender: Sender<EmbedderToConstellationMessage>,
+) {
+    log::info!("Starting WebDriver server on port {port}");
+    webdriver_server::start_server(port, constellation_sender);
+}
+
+/// The rectangle of the window to take a screenshot of, for the element at `page_rect` in the
+/// webview or the whole webview if `None`.
+pub(crate) fn screenshot_rect(
+    webview_rect: DeviceRect,
+    page_rect: Option<Rect<f32, CSSPixel>>,
+    device_pixels_per_page_pixel: Scale<f32, CSSPixel, DevicePixel>,
+) -> DeviceIntRect {
+    let rect = match page_rect {
+        Some(page_rect) => device_pixels_per_page_pixel
+            .transform_rect(&page_rect)
+            .to_box2d()
+            .translate(webview_rect.min.to_vector())
+            .intersection_unchecked(&webview_rect),
+        None => webview_rect,
+    };
+    rect.round_out().to_i32()
+}
+
+/// Convert a frame read back from the window to the image WebDriver encodes.
+pub(crate) fn screenshot_image(image: RgbaImage) -> Image {
+    Image {
+        width: image.width(),
+        height: image.height(),
+        format: PixelFormat::RGBA8,
+        bytes: IpcSharedMemory::from_bytes(&image),
+        id: None,
+        cors_status: CorsStatus::Safe,
+    }
+}
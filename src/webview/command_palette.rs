@@ -0,0 +1,85 @@
+use super::webview_menu::WebViewMenu;
+use crate::webview::WebView;
+use crate::window::Window;
+use base::id::WebViewId;
+use constellation_traits::EmbedderToConstellationMessage;
+use crossbeam_channel::Sender;
+use dpi::PhysicalPosition;
+use embedder_traits::ViewportDetails;
+use serde::Deserialize;
+use servo_url::ServoUrl;
+use webrender_api::units::DeviceRect;
+use winit::dpi::LogicalPosition;
+
+/// The Command Palette of the Window. It will be opened with Ctrl/Cmd+Shift+P, and lists
+/// every command of the window's [`crate::command::CommandRegistry`].
+#[derive(Clone)]
+pub struct CommandPalette {
+    /// The webview that the command palette is attached to
+    pub(crate) webview: WebView,
+    /// Palette position, always the top left corner of the window
+    position: LogicalPosition<f64>,
+}
+
+impl CommandPalette {
+    /// Create a new command palette
+    pub fn new() -> Self {
+        let webview_id = WebViewId::new();
+        let webview = WebView::new(webview_id, ViewportDetails::default());
+
+        Self {
+            webview,
+            position: LogicalPosition::new(0.0, 0.0),
+        }
+    }
+}
+
+impl Default for CommandPalette {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl WebViewMenu for CommandPalette {
+    fn webview(&self) -> &WebView {
+        &self.webview
+    }
+
+    fn resource_url(&self) -> ServoUrl {
+        ServoUrl::parse("verso://resources/components/command_palette.html").unwrap()
+    }
+
+    fn set_webview_rect(&mut self, rect: DeviceRect) {
+        self.webview.set_size(rect);
+    }
+
+    fn position(&self) -> LogicalPosition<f64> {
+        self.position
+    }
+
+    fn set_position(&mut self, position: LogicalPosition<f64>) {
+        self.position = position;
+    }
+}
+
+/// Command Palette Result
+#[derive(Debug, Clone, Deserialize)]
+pub struct CommandPaletteUIResponse {
+    /// id of the selected command, `None` if the palette is dismissed
+    pub id: Option<String>,
+}
+
+impl Window {
+    pub(crate) fn show_command_palette(&mut self, sender: &Sender<EmbedderToConstellationMessage>) {
+        self.close_webview_menu(sender);
+
+        let mut palette = CommandPalette::new();
+        palette.show(sender, self, PhysicalPosition::new(0.0, 0.0));
+        self.webview_menu = Some(Box::new(palette));
+    }
+
+    /// Search commands for the command palette, returns the results as a JSON string
+    pub(crate) fn search_commands(&self, query: &str) -> String {
+        serde_json::to_string(&self.command_registry.search(query)).unwrap()
+    }
+}
@@ -1,15 +1,26 @@
-use crate::verso::send_to_constellation;
+use crate::clipboard::{self, ClipboardContent};
+use crate::command::CommandAction;
+use crate::compositor::IOCompositor;
+use crate::download::download_body;
+use crate::keyboard::CMD_OR_CONTROL;
+use crate::verso::{VersoInternalMsg, send_to_constellation};
+use crate::webview::execute_script;
 use crate::window::Window;
-use constellation_traits::{EmbedderToConstellationMessage, TraversalDirection};
-use embedder_traits::ContextMenuResult;
+use base::id::WebViewId;
+use constellation_traits::EmbedderToConstellationMessage;
+use crossbeam_channel::Sender;
 #[cfg(linux)]
 use embedder_traits::ViewportDetails;
+use embedder_traits::{ContextMenuResult, InputEvent};
 use ipc_channel::ipc::IpcSender;
+use keyboard_types::{Code, Key, KeyState, KeyboardEvent, Location};
+use serde::Deserialize;
+use servo_url::ServoUrl;
+use url::Url;
+use versoview_messages::{ClipboardAction, ToControllerMessage};
 
 /* macOS, Windows Native Implementation */
 #[cfg(any(target_os = "macos", target_os = "windows"))]
-use crossbeam_channel::Sender;
-#[cfg(any(target_os = "macos", target_os = "windows"))]
 use muda::MenuEvent;
 #[cfg(any(target_os = "macos", target_os = "windows"))]
 use muda::{ContextMenu as MudaContextMenu, Menu as MudaMenu};
@@ -22,19 +33,82 @@ use super::webview_menu::WebViewMenu;
 #[cfg(linux)]
 use crate::webview::WebView;
 #[cfg(linux)]
-use base::id::WebViewId;
-#[cfg(linux)]
-use crossbeam_channel::Sender;
-#[cfg(linux)]
-use serde::{Deserialize, Serialize};
-#[cfg(linux)]
-use servo_url::ServoUrl;
-#[cfg(linux)]
-use url::Url;
+use serde::Serialize;
 #[cfg(linux)]
 use webrender_api::units::DeviceRect;
 #[cfg(linux)]
-use winit::dpi::LogicalPosition;
+use winit::dpi::{LogicalPosition, PhysicalPosition};
+
+/// Script which reports what the context menu is opened on with the `CONTEXT_MENU_TARGET`
+/// request of the Verso bridge. The prompt is answered before the page's `contextmenu` event
+/// ends, so it always comes before Servo asks to show the menu.
+pub(crate) const CONTEXT_MENU_SCRIPT: &str = r#"
+(() => {
+  if (window.versoContextMenu) {
+    return;
+  }
+  window.versoContextMenu = true;
+
+  const TEXT_INPUT_TYPES = ['text', 'search', 'url', 'tel', 'email', 'password', 'number'];
+  let target = null;
+
+  addEventListener('contextmenu', (event) => {
+    target = event.composedPath().find((node) => node instanceof Element) ?? null;
+    if (!target) {
+      return;
+    }
+    const link = target.closest('a[href], area[href]');
+    const image = target.closest('img');
+    const field = target instanceof HTMLInputElement || target instanceof HTMLTextAreaElement;
+    let selection = '';
+    if (field) {
+      try {
+        selection = target.value.substring(target.selectionStart, target.selectionEnd);
+      } catch {
+        // Numbers and emails have no selection
+      }
+    } else {
+      selection = String(getSelection() ?? '');
+    }
+    const editable = !target.disabled && !target.readOnly
+      && (target instanceof HTMLTextAreaElement
+        || (target instanceof HTMLInputElement && TEXT_INPUT_TYPES.includes(target.type))
+        || target.isContentEditable);
+    prompt(`VERSO::CONTEXT_MENU_TARGET::${JSON.stringify({
+      link: link ? link.href : null,
+      image: image ? image.currentSrc || image.src : null,
+      selection,
+      editable,
+    })}`);
+  }, true);
+
+  // Outline the element and log it to the console of the developer tools
+  window.versoInspectContextMenuTarget = () => {
+    if (!target || !target.isConnected) {
+      return;
+    }
+    target.scrollIntoView({ block: 'nearest' });
+    const outline = target.style.outline;
+    target.style.outline = '2px solid #1a73e8';
+    setTimeout(() => { target.style.outline = outline; }, 2000);
+    console.log(target);
+  };
+})();
+"#;
+
+/// What the context menu of a tab is opened on, for its items.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub(crate) struct ContextMenuTarget {
+    /// URL of the link under the pointer
+    pub link: Option<String>,
+    /// URL of the image under the pointer
+    pub image: Option<String>,
+    /// Selected text of the document, or of the field under the pointer
+    pub selection: String,
+    /// Whether the element under the pointer is a text field or editable content
+    pub editable: bool,
+}
 
 /// Basic menu type building block
 #[cfg(any(target_os = "macos", target_os = "windows"))]
@@ -229,29 +303,163 @@ pub struct ContextMenuUIResponse {
 
 // Context Menu methods
 impl Window {
+    /// Items of the context menu opened on the webview as (id, label, enabled). The items of
+    /// what the page reported it's opened on come first, the navigation items are only shown
+    /// if there are none.
+    fn context_menu_items(&self, webview_id: WebViewId) -> Vec<(&'static str, &'static str, bool)> {
+        let target = self
+            .context_menu_target
+            .borrow()
+            .as_ref()
+            .filter(|(id, _)| *id == webview_id)
+            .map(|(_, target)| target.clone());
+        let mut items = Vec::new();
+        if let Some(target) = &target {
+            let has_selection = !target.selection.is_empty();
+            if target.link.is_some() {
+                items.push(("context.open_link", "Open Link in New Tab", true));
+                items.push(("context.copy_link", "Copy Link", true));
+            }
+            if target.image.is_some() {
+                items.push(("context.open_image", "Open Image in New Tab", true));
+                items.push(("context.save_image", "Save Image", true));
+                items.push(("context.copy_image", "Copy Image", true));
+                items.push(("context.copy_image_address", "Copy Image Address", true));
+            }
+            if target.editable {
+                items.push(("context.cut", "Cut", has_selection));
+                items.push(("context.copy", "Copy", has_selection));
+                items.push(("context.paste", "Paste", true));
+                items.push(("context.paste_plain", "Paste as Plain Text", true));
+                items.push(("context.select_all", "Select All", true));
+            } else if has_selection {
+                items.push(("context.copy", "Copy", true));
+            }
+        }
+        if items.is_empty() {
+            let tab = self.tab_manager.current_tab().unwrap();
+            let history = tab.history();
+            items.push(("navigation.back", "Back", history.current_idx > 0));
+            items.push((
+                "navigation.forward",
+                "Forward",
+                history.current_idx + 1 < history.list.len(),
+            ));
+            items.push(("navigation.reload", "Reload", true));
+        }
+        if target.is_some() {
+            items.push(("context.inspect", "Inspect", true));
+        }
+        items
+    }
+
+    /// Execute the action of a context menu item on what the menu was opened on. Return false if
+    /// the id isn't one of a context menu action, e.g. for command ids.
+    fn execute_context_menu_action(&mut self, id: &str, compositor: &IOCompositor) -> bool {
+        if !id.starts_with("context.") {
+            return false;
+        }
+        let Some((webview_id, target)) = self.context_menu_target.take() else {
+            return true;
+        };
+        let sender = compositor.constellation_chan.clone();
+        let send = |message| {
+            let _ = self.verso_internal_sender.send(message);
+        };
+        let copy = |text| send(VersoInternalMsg::SetClipboard(ClipboardContent::text(text)));
+        match id {
+            "context.open_link" | "context.open_image" => {
+                let url = if id == "context.open_link" {
+                    target.link
+                } else {
+                    target.image
+                };
+                match url.as_deref().map(ServoUrl::parse) {
+                    Some(Ok(url)) => {
+                        self.create_tab(&sender, url);
+                    }
+                    _ => log::error!("Verso failed to open context menu URL {url:?}"),
+                }
+            }
+            "context.copy_link" => copy(target.link.unwrap_or_default()),
+            "context.copy_image" => {
+                if let Some(url) = target.image {
+                    clipboard::copy_image(url, self.verso_internal_sender.clone());
+                }
+            }
+            "context.copy_image_address" => copy(target.image.unwrap_or_default()),
+            "context.copy" => copy(target.selection),
+            "context.save_image" => {
+                let Some(Ok(url)) = target.image.as_deref().map(Url::parse) else {
+                    log::error!("Verso failed to save context menu image {:?}", target.image);
+                    return true;
+                };
+                let client = self.reqwest_client.clone();
+                let verso_internal_sender = self.verso_internal_sender.clone();
+                let max_download_size = self
+                    .tab_manager
+                    .tab(webview_id)
+                    .and_then(|tab| tab.resource_limits().max_download_size);
+                tokio::spawn(async move {
+                    match client.get(url.clone()).send().await {
+                        Ok(resp) => {
+                            download_body(
+                                url,
+                                resp,
+                                webview_id,
+                                max_download_size,
+                                verso_internal_sender,
+                            )
+                            .await
+                        }
+                        Err(e) => log::error!("Verso failed to save image {url}: {e}"),
+                    }
+                });
+            }
+            "context.cut" => send(VersoInternalMsg::Clipboard(
+                webview_id,
+                ClipboardAction::Cut,
+            )),
+            "context.paste" => send(VersoInternalMsg::Clipboard(
+                webview_id,
+                ClipboardAction::Paste,
+            )),
+            "context.paste_plain" => send(VersoInternalMsg::Clipboard(
+                webview_id,
+                ClipboardAction::PasteAsPlainText,
+            )),
+            // Fields handle the select all shortcut themselves
+            "context.select_all" => send_shortcut(&sender, webview_id, "a", Code::KeyA),
+            "context.inspect" => {
+                let _ = execute_script(
+                    &sender,
+                    &webview_id,
+                    "window.versoInspectContextMenuTarget?.()",
+                );
+            }
+            _ => log::warn!("Verso failed to find context menu action {id}"),
+        }
+        true
+    }
+
     #[cfg(any(target_os = "macos", target_os = "windows"))]
     pub(crate) fn show_context_menu(
         &self,
+        webview_id: WebViewId,
         servo_sender: IpcSender<ContextMenuResult>,
     ) -> ContextMenu {
-        use muda::MenuItem;
-
-        let tab = self.tab_manager.current_tab().unwrap();
-        let history = tab.history();
-        let history_len = history.list.len();
-
-        // items
-        let back = MenuItem::with_id("back", "Back", history.current_idx > 0, None);
-        let forward = MenuItem::with_id(
-            "forward",
-            "Forward",
-            history.current_idx + 1 < history_len,
-            None,
-        );
-        let reload = MenuItem::with_id("reload", "Reload", true, None);
+        use muda::{IsMenuItem, MenuItem};
+
+        let items: Vec<MenuItem> = self
+            .context_menu_items(webview_id)
+            .into_iter()
+            .map(|(id, label, enabled)| MenuItem::with_id(id, label, enabled, None))
+            .collect();
+        let items: Vec<&dyn IsMenuItem> =
+            items.iter().map(|item| item as &dyn IsMenuItem).collect();
 
         let menu = MudaMenu::new();
-        let _ = menu.append_items(&[&back, &forward, &reload]);
+        let _ = menu.append_items(&items);
 
         let context_menu = ContextMenu::new_with_menu(servo_sender, Menu(menu));
         context_menu.show(self.window.window_handle().unwrap());
@@ -263,25 +471,20 @@ impl Window {
     pub(crate) fn show_context_menu(
         &mut self,
         sender: &Sender<EmbedderToConstellationMessage>,
+        webview_id: WebViewId,
         servo_sender: IpcSender<ContextMenuResult>,
     ) -> ContextMenu {
-        let tab = self.tab_manager.current_tab().unwrap();
-        let history = tab.history();
-        let history_len = history.list.len();
-
-        // items
-        let back = MenuItem::new(Some("back"), "Back", history.current_idx > 0);
-        let forward = MenuItem::new(
-            Some("forward"),
-            "Forward",
-            history.current_idx + 1 < history_len,
-        );
-        let reload = MenuItem::new(Some("reload"), "Reload", true);
+        let items = self
+            .context_menu_items(webview_id)
+            .into_iter()
+            .map(|(id, label, enabled)| MenuItem::new(Some(id), label, enabled))
+            .collect();
 
-        let mut context_menu =
-            ContextMenu::new_with_menu(servo_sender, Menu(vec![back, forward, reload]));
+        let mut context_menu = ContextMenu::new_with_menu(servo_sender, Menu(items));
 
-        let position = self.mouse_position.get().unwrap();
+        // The menu is shown in the rotated content, like the page under it
+        let point = self.content_point(self.mouse_position.get().unwrap());
+        let position = PhysicalPosition::new(point.x.into(), point.y.into());
         context_menu.show(sender, self, position);
 
         context_menu
@@ -289,41 +492,19 @@ impl Window {
 
     #[cfg(any(target_os = "macos", target_os = "windows"))]
     pub(crate) fn handle_context_menu_event(
-        &self,
+        &mut self,
         mut context_menu: ContextMenu,
-        sender: &Sender<EmbedderToConstellationMessage>,
+        compositor: &mut IOCompositor,
+        to_controller_sender: &Option<IpcSender<ToControllerMessage>>,
         event: MenuEvent,
-    ) {
+    ) -> CommandAction {
         context_menu.send_result_to_servo(ContextMenuResult::Dismissed);
-        // TODO: should be more flexible to handle different menu items
-        let active_tab = self.tab_manager.current_tab().unwrap();
-        match event.id().0.as_str() {
-            "back" => {
-                send_to_constellation(
-                    sender,
-                    EmbedderToConstellationMessage::TraverseHistory(
-                        active_tab.id(),
-                        TraversalDirection::Back(1),
-                    ),
-                );
-            }
-            "forward" => {
-                send_to_constellation(
-                    sender,
-                    EmbedderToConstellationMessage::TraverseHistory(
-                        active_tab.id(),
-                        TraversalDirection::Forward(1),
-                    ),
-                );
-            }
-            "reload" => {
-                send_to_constellation(
-                    sender,
-                    EmbedderToConstellationMessage::Reload(active_tab.id()),
-                );
-            }
-            _ => {}
+        let id = event.id().0.as_str();
+        // Menu item ids are command ids, except the ones of context menu actions
+        if self.execute_context_menu_action(id, compositor) {
+            return CommandAction::None;
         }
+        self.execute_command(id, compositor, to_controller_sender)
     }
 
     /// Handle linux context menu event
@@ -333,41 +514,46 @@ impl Window {
     pub(crate) fn handle_context_menu_event(
         &mut self,
         sender: &Sender<EmbedderToConstellationMessage>,
+        compositor: &mut IOCompositor,
+        to_controller_sender: &Option<IpcSender<ToControllerMessage>>,
         event: crate::webview::context_menu::ContextMenuUIResponse,
-    ) {
+    ) -> CommandAction {
         self.close_webview_menu(sender);
-        if let Some(id) = event.id {
-            if let Some(tab_id) = self.tab_manager.current_tab_id() {
-                match id.as_str() {
-                    "back" => {
-                        send_to_constellation(
-                            sender,
-                            EmbedderToConstellationMessage::TraverseHistory(
-                                tab_id,
-                                TraversalDirection::Back(1),
-                            ),
-                        );
-                    }
-                    "forward" => {
-                        send_to_constellation(
-                            sender,
-                            EmbedderToConstellationMessage::TraverseHistory(
-                                tab_id,
-                                TraversalDirection::Forward(1),
-                            ),
-                        );
-                    }
-                    "reload" => {
-                        send_to_constellation(
-                            sender,
-                            EmbedderToConstellationMessage::Reload(tab_id),
-                        );
-                    }
-                    _ => {}
-                }
-            } else {
-                log::error!("No active webview to handle context menu event");
-            }
+        let Some(id) = event.id else {
+            return CommandAction::None;
         };
+        // Menu item ids are command ids, except the ones of context menu actions
+        if self.execute_context_menu_action(&id, compositor) {
+            return CommandAction::None;
+        }
+        self.execute_command(&id, compositor, to_controller_sender)
+    }
+}
+
+/// Type the editing shortcut of the key in the focused element of the webview.
+fn send_shortcut(
+    sender: &Sender<EmbedderToConstellationMessage>,
+    webview_id: WebViewId,
+    key: &str,
+    code: Code,
+) {
+    for state in [KeyState::Down, KeyState::Up] {
+        let event = KeyboardEvent {
+            state,
+            key: Key::Character(key.to_string()),
+            code,
+            location: Location::Standard,
+            modifiers: CMD_OR_CONTROL,
+            repeat: false,
+            is_composing: false,
+        };
+        send_to_constellation(
+            sender,
+            EmbedderToConstellationMessage::ForwardInputEvent(
+                webview_id,
+                InputEvent::Keyboard(event),
+                None,
+            ),
+        );
     }
 }
@@ -0,0 +1,207 @@
+use crate::find;
+use crate::verso::send_to_constellation;
+use crate::webview::WebView;
+use crate::window::Window;
+use base::id::WebViewId;
+use constellation_traits::EmbedderToConstellationMessage;
+use crossbeam_channel::Sender;
+use embedder_traits::{
+    EmbedderMsg, LoadStatus, PromptResponse, SimpleDialog, ViewportDetails, WebDriverCommandMsg,
+    WebDriverScriptCommand,
+};
+use euclid::Scale;
+use serde::Deserialize;
+use servo_url::ServoUrl;
+use versoview_messages::FindOptions;
+use webrender_api::units::{DevicePoint, DeviceRect, DeviceSize};
+
+/// Width of the find bar, in logical pixels
+const FIND_BAR_WIDTH: f64 = 360.0;
+/// Height of the find bar, in logical pixels
+const FIND_BAR_HEIGHT: f64 = 40.0;
+/// Space between the find bar and the edges of the tab, in logical pixels
+const FIND_BAR_MARGIN: f64 = 8.0;
+
+/// The find bar of the window. It's opened with Ctrl/Cmd+F over the top right corner of the
+/// current tab, and finds the text typed in it with [`crate::find`]. Unlike the webview menus, it
+/// only covers its own area, so the page can still be scrolled and clicked.
+#[derive(Clone)]
+pub struct FindBar {
+    /// The webview of the find bar
+    pub(crate) webview: WebView,
+}
+
+impl FindBar {
+    /// Create a new find bar over the tab
+    pub fn new(tab_rect: DeviceRect, scale_factor: f64) -> Self {
+        let webview_id = WebViewId::new();
+        let mut webview = WebView::new(webview_id, ViewportDetails::default());
+        webview.set_size(Self::rect(tab_rect, scale_factor));
+        Self { webview }
+    }
+
+    /// Area of the find bar over the tab, narrower on narrow tabs.
+    pub fn rect(tab_rect: DeviceRect, scale_factor: f64) -> DeviceRect {
+        let margin = (FIND_BAR_MARGIN * scale_factor) as f32;
+        let width = ((FIND_BAR_WIDTH * scale_factor) as f32)
+            .min(tab_rect.width() - 2.0 * margin)
+            .max(0.0);
+        let height = ((FIND_BAR_HEIGHT * scale_factor) as f32)
+            .min(tab_rect.height())
+            .max(0.0);
+        let origin = DevicePoint::new(tab_rect.max.x - margin - width, tab_rect.min.y + margin);
+        DeviceRect::from_origin_and_size(origin, DeviceSize::new(width, height))
+    }
+}
+
+/// Search sent by the find bar
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FindBarRequest {
+    /// Text typed in the find bar, the highlights are removed if it's empty
+    pub text: String,
+    /// Match the case of the text
+    pub case_sensitive: bool,
+    /// Move to the previous match instead of the next one
+    pub backwards: bool,
+}
+
+impl Window {
+    /// Open the find bar over the current tab, or focus its input if it's open.
+    pub(crate) fn show_find_bar(&mut self, sender: &Sender<EmbedderToConstellationMessage>) {
+        if let Some(find_bar) = &self.find_bar {
+            send_to_constellation(
+                sender,
+                EmbedderToConstellationMessage::FocusWebView(find_bar.webview.webview_id),
+            );
+            run_in_find_bar(sender, find_bar, "window.focusFindInput()");
+            return;
+        }
+        let Some(tab) = self.tab_manager.current_tab() else {
+            return;
+        };
+
+        let find_bar = FindBar::new(tab.webview().rect, self.scale_factor());
+        let hidpi_scale_factor = Scale::new(self.scale_factor() as f32);
+        send_to_constellation(
+            sender,
+            EmbedderToConstellationMessage::NewWebView(
+                ServoUrl::parse("verso://resources/components/find_bar.html").unwrap(),
+                find_bar.webview.webview_id,
+                ViewportDetails {
+                    size: find_bar.webview.rect.size() / hidpi_scale_factor,
+                    hidpi_scale_factor,
+                },
+            ),
+        );
+        self.find_bar = Some(find_bar);
+    }
+
+    /// Move to the next or previous match of the find bar, opening it if it's closed.
+    pub(crate) fn find_next(
+        &mut self,
+        sender: &Sender<EmbedderToConstellationMessage>,
+        backwards: bool,
+    ) {
+        match &self.find_bar {
+            Some(find_bar) => {
+                run_in_find_bar(sender, find_bar, &format!("window.findNext({backwards})"));
+            }
+            None => self.show_find_bar(sender),
+        }
+    }
+
+    /// Close the find bar and remove the highlights of the current tab.
+    pub(crate) fn close_find_bar(&mut self, sender: &Sender<EmbedderToConstellationMessage>) {
+        let Some(find_bar) = &self.find_bar else {
+            return;
+        };
+        send_to_constellation(
+            sender,
+            EmbedderToConstellationMessage::CloseWebView(find_bar.webview.webview_id),
+        );
+        if let Some(tab_id) = self.tab_manager.current_tab_id() {
+            find::find_in_page(sender.clone(), tab_id, None, FindOptions::default(), |_| {});
+            send_to_constellation(sender, EmbedderToConstellationMessage::FocusWebView(tab_id));
+        }
+    }
+
+    /// Handle servo messages of the find bar.
+    pub(crate) fn handle_servo_messages_with_find_bar(
+        &mut self,
+        webview_id: WebViewId,
+        message: EmbedderMsg,
+        sender: &Sender<EmbedderToConstellationMessage>,
+    ) {
+        log::trace!("Verso Find Bar {webview_id:?} is handling Embedder message: {message:?}",);
+        match message {
+            EmbedderMsg::WebViewBlurred => {
+                self.focused_webview_id = None;
+            }
+            EmbedderMsg::WebViewFocused(webview_id) => {
+                self.focused_webview_id = Some(webview_id);
+            }
+            EmbedderMsg::NotifyLoadStatusChanged(_webview_id, LoadStatus::Complete) => {
+                // Let the find bar receive the text typed right after opening it
+                send_to_constellation(
+                    sender,
+                    EmbedderToConstellationMessage::FocusWebView(webview_id),
+                );
+            }
+            EmbedderMsg::ShowSimpleDialog(
+                _webview_id,
+                SimpleDialog::Prompt {
+                    message,
+                    response_sender,
+                    ..
+                },
+            ) => {
+                let request = message
+                    .strip_prefix("FIND:")
+                    .and_then(|request| serde_json::from_str::<FindBarRequest>(request).ok());
+                match (request, self.tab_manager.current_tab_id()) {
+                    (Some(request), Some(tab_id)) => {
+                        let options = FindOptions {
+                            case_sensitive: request.case_sensitive,
+                            backwards: request.backwards,
+                        };
+                        let text = Some(request.text).filter(|text| !text.is_empty());
+                        // The find bar waits for the matches while the page is searched
+                        find::find_in_page(sender.clone(), tab_id, text, options, move |result| {
+                            let _ = response_sender
+                                .send(PromptResponse::Ok(serde_json::to_string(&result).unwrap()));
+                        });
+                    }
+                    _ => {
+                        let _ = response_sender.send(PromptResponse::default());
+                        if message == "FIND_CLOSE" {
+                            self.close_find_bar(sender);
+                        }
+                    }
+                }
+            }
+            e => {
+                log::trace!("Verso find bar isn't supporting this message yet: {e:?}")
+            }
+        }
+    }
+}
+
+/// Run the script in the find bar without waiting for it, the find bar may be waiting for a
+/// search itself.
+fn run_in_find_bar(
+    sender: &Sender<EmbedderToConstellationMessage>,
+    find_bar: &FindBar,
+    script: &str,
+) {
+    let Ok((result_sender, _)) = ipc_channel::ipc::channel() else {
+        return;
+    };
+    send_to_constellation(
+        sender,
+        EmbedderToConstellationMessage::WebDriverCommand(WebDriverCommandMsg::ScriptCommand(
+            find_bar.webview.webview_id.0,
+            WebDriverScriptCommand::ExecuteScript(script.to_string(), result_sender),
+        )),
+    );
+}
@@ -1,8 +1,12 @@
 mod webview;
 /// WebView
 pub use webview::{Panel, WebView, execute_script};
+/// Command palette
+pub mod command_palette;
 /// Context Menu
 pub mod context_menu;
+/// Find bar
+pub mod find_bar;
 /// Browsing history menu
 pub mod history_menu;
 /// Prompt Dialog
@@ -1,3 +1,5 @@
+use std::path::PathBuf;
+
 use base::id::WebViewId;
 use constellation_traits::EmbedderToConstellationMessage;
 use crossbeam_channel::Sender;
@@ -9,6 +11,7 @@ use euclid::Scale;
 use ipc_channel::ipc::IpcSender;
 use serde::{Deserialize, Serialize};
 use servo_url::ServoUrl;
+use versoview_messages::DialogResponse;
 use webrender_api::units::DeviceRect;
 
 use crate::{verso::send_to_constellation, webview::WebView};
@@ -28,6 +31,10 @@ enum PromptType {
     ///
     /// <https://developer.mozilla.org/en-US/docs/Web/API/Window/confirm>
     AllowDeny(String),
+    /// Confirm leaving the page, Leave/Stay
+    ///
+    /// <https://developer.mozilla.org/en-US/docs/Web/API/Window/beforeunload_event>
+    LeaveStay,
     /// Input dialog
     ///
     /// <https://developer.mozilla.org/en-US/docs/Web/API/Window/prompt>
@@ -35,7 +42,14 @@ enum PromptType {
     /// HTTP basic authentication dialog (username / password)
     ///
     /// <https://developer.mozilla.org/en-US/docs/Web/HTTP/Authentication#basic>
-    HttpBasicAuth,
+    HttpBasicAuth {
+        /// Host asking for the credentials
+        host: String,
+        /// Whether the host is a proxy
+        for_proxy: bool,
+        /// Whether the credentials can be remembered
+        can_remember: bool,
+    },
 }
 
 /// Prompt Sender, used to send prompt result back to the caller
@@ -53,6 +67,60 @@ pub enum PromptSender {
     HttpBasicAuthSender(IpcSender<Option<AuthenticationResponse>>),
 }
 
+/// A dialog shown by the controller instead of Verso, waiting for a
+/// [`versoview_messages::ToVersoMessage::DialogResponse`]
+pub enum DelegatedDialog {
+    /// JavaScript dialog, permission prompt or HTTP authentication
+    Prompt(PromptSender),
+    /// File picker of `<input type="file">`
+    FilePicker(IpcSender<Option<Vec<PathBuf>>>),
+}
+
+impl DelegatedDialog {
+    /// Send the answer of the controller to the page, responses which don't fit the dialog
+    /// cancel it.
+    pub fn respond(self, response: DialogResponse) {
+        match self {
+            DelegatedDialog::Prompt(PromptSender::AlertSender(sender)) => {
+                let _ = sender.send(AlertResponse::default());
+            }
+            DelegatedDialog::Prompt(PromptSender::ConfirmSender(sender)) => {
+                let _ = sender.send(match response {
+                    DialogResponse::Ok => ConfirmResponse::Ok,
+                    _ => ConfirmResponse::Cancel,
+                });
+            }
+            DelegatedDialog::Prompt(PromptSender::InputSender(sender)) => {
+                let _ = sender.send(match response {
+                    DialogResponse::Text(text) => PromptResponse::Ok(text),
+                    DialogResponse::Ok => PromptResponse::Ok(String::new()),
+                    _ => PromptResponse::Cancel,
+                });
+            }
+            DelegatedDialog::Prompt(PromptSender::AllowDenySender(sender)) => {
+                let _ = sender.send(match response {
+                    DialogResponse::Ok => AllowOrDeny::Allow,
+                    _ => AllowOrDeny::Deny,
+                });
+            }
+            DelegatedDialog::Prompt(PromptSender::HttpBasicAuthSender(sender)) => {
+                let _ = sender.send(match response {
+                    DialogResponse::Credentials { username, password } => {
+                        Some(AuthenticationResponse { username, password })
+                    }
+                    _ => None,
+                });
+            }
+            DelegatedDialog::FilePicker(sender) => {
+                let _ = sender.send(match response {
+                    DialogResponse::Files(files) => Some(files),
+                    _ => None,
+                });
+            }
+        }
+    }
+}
+
 /// Prompt input result send from prompt dialog to backend
 /// - action: "ok" / "cancel"
 /// - value: user input value in input prompt
@@ -73,6 +141,7 @@ pub struct PromptInputResult {
 /// Prompt input result send from prompt dialog to backend
 /// - action: "signin" / "cancel"
 /// - auth: { username: string, password: string }
+/// - remember: whether to remember the credentials for the origin
 ///
 /// Behavior:
 /// - **signin**: return { username: string, password: string }
@@ -83,6 +152,9 @@ pub struct HttpBasicAuthInputResult {
     pub action: String,
     /// User input value
     pub auth: AuthenticationResponse,
+    /// Remember the credentials for the origin
+    #[serde(default)]
+    pub remember: bool,
 }
 
 /// Prompt Dialog
@@ -90,6 +162,7 @@ pub struct HttpBasicAuthInputResult {
 pub struct PromptDialog {
     webview: WebView,
     prompt_sender: Option<PromptSender>,
+    authentication: Option<(url::Url, bool)>,
 }
 
 impl PromptDialog {
@@ -98,6 +171,7 @@ impl PromptDialog {
         PromptDialog {
             webview: WebView::new(WebViewId::new(), ViewportDetails::default()),
             prompt_sender: None,
+            authentication: None,
         }
     }
     /// Get prompt webview
@@ -114,6 +188,11 @@ impl PromptDialog {
         self.prompt_sender.clone()
     }
 
+    /// Get the URL of the request of an HTTP authentication prompt, and whether it's for a proxy.
+    pub fn authentication(&self) -> Option<&(url::Url, bool)> {
+        self.authentication.as_ref()
+    }
+
     /// Resize prompt webview size with new window context size
     ///
     /// ## Example:
@@ -200,6 +279,30 @@ impl PromptDialog {
         self.show(sender, rect, scale_factor, PromptType::AllowDeny(message));
     }
 
+    /// Show Leave/Stay prompt of a page with a `beforeunload` handler, pages can't choose its
+    /// message
+    ///
+    /// After you call `leave_stay(..)`, you must call `sender()` to get prompt sender,
+    /// then send user interaction result back to caller.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// if let Some(PromptSender::AllowDenySender(sender)) = prompt.sender() {
+    ///     let _ = sender.send(AllowOrDeny::Allow);
+    /// }
+    /// ```
+    pub fn leave_stay(
+        &mut self,
+        sender: &Sender<EmbedderToConstellationMessage>,
+        rect: DeviceRect,
+        scale_factor: f32,
+        prompt_sender: IpcSender<AllowOrDeny>,
+    ) {
+        self.prompt_sender = Some(PromptSender::AllowDenySender(prompt_sender));
+        self.show(sender, rect, scale_factor, PromptType::LeaveStay);
+    }
+
     /// Show input prompt
     ///
     /// After you call `input(..)`, you must call `sender()` to get prompt sender,
@@ -230,9 +333,10 @@ impl PromptDialog {
         );
     }
 
-    /// Show input prompt
+    /// Show HTTP authentication prompt for the URL of the request, of a proxy if the second
+    /// value is `true`. A "Remember" checkbox is shown if `can_remember`.
     ///
-    /// After you call `input(..)`, you must call `sender()` to get prompt sender,
+    /// After you call `http_basic_auth(..)`, you must call `sender()` to get prompt sender,
     /// then send user interaction result back to caller.
     ///
     /// ## Example
@@ -250,10 +354,18 @@ impl PromptDialog {
         sender: &Sender<EmbedderToConstellationMessage>,
         rect: DeviceRect,
         scale_factor: f32,
+        authentication: (url::Url, bool),
+        can_remember: bool,
         prompt_sender: IpcSender<Option<AuthenticationResponse>>,
     ) {
         self.prompt_sender = Some(PromptSender::HttpBasicAuthSender(prompt_sender));
-        self.show(sender, rect, scale_factor, PromptType::HttpBasicAuth);
+        let prompt_type = PromptType::HttpBasicAuth {
+            host: authentication.0.host_str().unwrap_or_default().to_string(),
+            for_proxy: authentication.1,
+            can_remember,
+        };
+        self.authentication = Some(authentication);
+        self.show(sender, rect, scale_factor, prompt_type);
     }
 
     fn show(
@@ -291,6 +403,9 @@ impl PromptDialog {
             PromptType::AllowDeny(msg) => {
                 format!("verso://resources/components/prompt/allow_deny.html?msg={msg}")
             }
+            PromptType::LeaveStay => {
+                "verso://resources/components/prompt/leave_stay.html".to_string()
+            }
             PromptType::Input(msg, default_value) => {
                 let mut url = format!("verso://resources/components/prompt/prompt.html?msg={msg}");
                 if let Some(default_value) = default_value {
@@ -298,8 +413,14 @@ impl PromptDialog {
                 }
                 url
             }
-            PromptType::HttpBasicAuth => {
-                "verso://resources/components/prompt/http_basic_auth.html".to_string()
+            PromptType::HttpBasicAuth {
+                host,
+                for_proxy,
+                can_remember,
+            } => {
+                format!(
+                    "verso://resources/components/prompt/http_basic_auth.html?host={host}&proxy={for_proxy}&remember={can_remember}"
+                )
             }
         };
         ServoUrl::parse(&url).unwrap()
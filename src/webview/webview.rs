@@ -2,31 +2,52 @@ use std::str::FromStr;
 
 use arboard::Clipboard;
 use base::id::WebViewId;
-use constellation_traits::{EmbedderToConstellationMessage, TraversalDirection};
+use constellation_traits::EmbedderToConstellationMessage;
 use crossbeam_channel::Sender;
 use embedder_traits::{
     AlertResponse, AllowOrDeny, ConfirmResponse, ContextMenuResult, EmbedderMsg, LoadStatus,
-    PromptResponse, SimpleDialog, ViewportDetails, WebDriverCommandMsg, WebDriverJSResult,
-    WebDriverScriptCommand,
+    MediaSessionEvent, MediaSessionPlaybackState, PromptResponse, SimpleDialog, ViewportDetails,
+    WebDriverCommandMsg, WebDriverJSResult, WebDriverScriptCommand,
 };
 use euclid::Scale;
-use ipc_channel::ipc::{self, IpcSender};
+use ipc_channel::{
+    ipc::{self, IpcSender},
+    router::ROUTER,
+};
 use servo_url::ServoUrl;
 use url::Url;
-use versoview_messages::ToControllerMessage;
+use versoview_messages::{
+    BlockedSubresource, CspViolation, DialogRequest, DialogResponse, HttpCredentials,
+    LifecycleState, ToControllerMessage,
+};
 use webrender_api::units::{DevicePoint, DeviceRect};
 
 use crate::{
+    badge::{Badge, set_badge},
     bookmark::{BookmarkId, BookmarkManager},
+    command::{CommandAction, panel_command_id},
     compositor::IOCompositor,
-    download::{DownloadId, check_should_download, download_body},
-    tab::{Tab, TabActivateRequest, TabCloseRequest, TabCreateResponse},
+    download::{DownloadControl, DownloadId, check_should_download, download_body},
+    drag_drop::DraggedLink,
+    file_picker::pick_files,
+    history::URL_BAR_AUTOFILL_SCRIPT,
+    javascript::JavaScriptSetting,
+    private::PRIVATE_PANEL_SCRIPT,
+    secrets::SecretRequest,
+    share::ShareData,
+    tab::{Tab, TabActivateRequest, TabCloseRequest, TabCreateResponse, TabMuteRequest},
     verso::{VersoInternalMsg, send_to_constellation},
+    web_app::fetch_manifest,
     webview::{
+        command_palette::CommandPaletteUIResponse,
+        context_menu::ContextMenuTarget,
         history_menu::{HistoryMenuUIResponse, OpenHistoryMenuRequest},
-        prompt::{HttpBasicAuthInputResult, PromptDialog, PromptInputResult, PromptSender},
+        prompt::{
+            DelegatedDialog, HttpBasicAuthInputResult, PromptDialog, PromptInputResult,
+            PromptSender,
+        },
     },
-    window::Window,
+    window::{Window, lifecycle_state_script, mute_media_script},
 };
 
 #[cfg(linux)]
@@ -77,7 +98,7 @@ pub struct Panel {
 }
 
 impl Window {
-    /// Handle servo messages with corresponding web view ID.
+    /// Handle servo messages with corresponding web view ID, returning what's left to do of the command it executes.
     pub fn handle_servo_messages_with_webview(
         &mut self,
         webview_id: WebViewId,
@@ -86,7 +107,7 @@ impl Window {
         to_controller_sender: &Option<ipc::IpcSender<ToControllerMessage>>,
         clipboard: Option<&mut Clipboard>,
         compositor: &mut IOCompositor,
-    ) {
+    ) -> CommandAction {
         log::trace!("Verso WebView {webview_id:?} is handling Embedder message: {message:?}",);
         match message {
             EmbedderMsg::WebViewClosed(_) => {
@@ -108,39 +129,120 @@ impl Window {
                 );
             }
             EmbedderMsg::NotifyLoadStatusChanged(_webview_id, status) => match status {
+                // Servo doesn't report how much of the page is loaded, so the progress bar only
+                // shows the stages of the load
+                LoadStatus::Started | LoadStatus::HeadParsed => {
+                    let progress = if status == LoadStatus::Started {
+                        0.2
+                    } else {
+                        0.6
+                    };
+                    if let Some(tab) = self.tab_manager.tab_mut(webview_id) {
+                        tab.set_load_progress(Some(progress));
+                    }
+                    compositor.update_load_progress(self);
+                }
                 LoadStatus::Complete => {
+                    if let Some(tab) = self.tab_manager.tab_mut(webview_id) {
+                        tab.set_load_progress(None);
+                    }
+                    compositor.update_load_progress(self);
                     self.window.request_redraw();
                     send_to_constellation(
                         sender,
                         EmbedderToConstellationMessage::FocusWebView(webview_id),
                     );
-                }
-                _ => {
-                    log::trace!(
-                        "Verso WebView {webview_id:?} ignores NotifyLoadStatusChanged status: {status:?}"
-                    );
+                    // Keep the tab muted after navigating to another document
+                    if self
+                        .tab_manager
+                        .tab(webview_id)
+                        .is_some_and(|tab| tab.is_muted())
+                    {
+                        let _ = execute_script(sender, &webview_id, mute_media_script(true));
+                    }
+                    // And in the lifecycle state set by the controller
+                    if let Some(state) = self
+                        .tab_manager
+                        .tab(webview_id)
+                        .and_then(|tab| tab.lifecycle_state())
+                        .filter(|state| *state != LifecycleState::Visible)
+                    {
+                        let _ = execute_script(sender, &webview_id, lifecycle_state_script(state));
+                    }
                 }
             },
             EmbedderMsg::ChangePageTitle(_webview_id, title) => {
+                let background = self.tab_manager.current_tab_id() != Some(webview_id);
+                if let Some(tab) = self.tab_manager.tab_mut(webview_id) {
+                    tab.set_title(title.clone().unwrap_or_else(|| "null".to_string()));
+                }
+
                 if let Some(panel) = self.panel.as_ref() {
-                    let tab = self.tab_manager.current_tab_mut().unwrap();
-                    let title = if let Some(title) = title {
-                        tab.set_title(title.clone());
+                    let title_arg = if let Some(title) = &title {
                         format!("'{title}'")
                     } else {
-                        tab.set_title("null".to_string());
                         "null".to_string()
                     };
 
                     let script = format!(
                         "window.navbar.setTabTitle('{}', {})",
                         serde_json::to_string(&webview_id).unwrap(),
-                        title.as_str()
+                        title_arg.as_str()
                     );
                     let _ = execute_script(sender, &panel.webview.webview_id, script);
                 }
+
+                if let Some(to_controller_sender) = to_controller_sender {
+                    if self.event_listeners.on_title_changed {
+                        if let Err(error) =
+                            to_controller_sender.send(ToControllerMessage::OnTitleChanged(
+                                bincode::serialize(&webview_id).unwrap(),
+                                title,
+                                background,
+                            ))
+                        {
+                            log::error!(
+                                "Verso failed to send OnTitleChanged to controller: {error}"
+                            )
+                        }
+                    }
+                }
             }
-            EmbedderMsg::AllowNavigationRequest(_webview_id, id, url) => {
+            EmbedderMsg::NewFavicon(_webview_id, url) => {
+                let background = self.tab_manager.current_tab_id() != Some(webview_id);
+                if let Some(tab) = self.tab_manager.tab_mut(webview_id) {
+                    tab.set_favicon(url.clone());
+                }
+
+                if let Some(to_controller_sender) = to_controller_sender {
+                    if self.event_listeners.on_favicon_changed {
+                        if let Err(error) =
+                            to_controller_sender.send(ToControllerMessage::OnFaviconChanged(
+                                bincode::serialize(&webview_id).unwrap(),
+                                url.into_url(),
+                                background,
+                            ))
+                        {
+                            log::error!(
+                                "Verso failed to send OnFaviconChanged to controller: {error}"
+                            )
+                        }
+                    }
+                }
+            }
+            EmbedderMsg::MediaSessionEvent(
+                _webview_id,
+                MediaSessionEvent::PlaybackStateChange(state),
+            ) => {
+                let audible = matches!(state, MediaSessionPlaybackState::Playing);
+                if let Some(tab) = self.tab_manager.tab_mut(webview_id) {
+                    if tab.is_audible() != audible {
+                        tab.set_audible(audible);
+                        self.notify_audio_state_changed(sender, to_controller_sender, webview_id);
+                    }
+                }
+            }
+            EmbedderMsg::AllowNavigationRequest(webview_id, id, url) => {
                 if let Some(to_controller_sender) = to_controller_sender {
                     if self.event_listeners.on_navigation_starting {
                         if let Err(error) =
@@ -155,22 +257,34 @@ impl Window {
                         } else {
                             // We will handle a ToVersoMessage::OnNavigationStartingResponse
                             // and send EmbedderToConstellationMessage::AllowNavigationResponse there if the call succeed
-                            return;
+                            return CommandAction::None;
                         }
                     }
                 }
 
-                // If it's in Verso Browser, check if we should download the url
-                if self.panel.is_some() {
+                // If it's in Verso Browser or the controller handles downloads, check if we should
+                // download the url
+                if self.panel.is_some() || self.event_listeners.on_download_updated {
                     let sender = sender.clone();
                     let url = url.into_url();
                     let client = self.reqwest_client.clone();
                     let verso_internal_sender = self.verso_internal_sender.clone();
+                    let max_download_size = self
+                        .tab_manager
+                        .tab(webview_id)
+                        .and_then(|tab| tab.resource_limits().max_download_size);
 
                     tokio::spawn(async move {
                         let (should_download, resp) = check_should_download(&client, &url).await;
                         if should_download && resp.is_some() {
-                            download_body(url, resp.unwrap(), verso_internal_sender).await;
+                            download_body(
+                                url,
+                                resp.unwrap(),
+                                webview_id,
+                                max_download_size,
+                                verso_internal_sender,
+                            )
+                            .await;
                         } else {
                             send_to_constellation(
                                 &sender,
@@ -186,13 +300,13 @@ impl Window {
                     );
                 }
             }
-            EmbedderMsg::WebResourceRequested(_webview_id, request, sender) => {
+            EmbedderMsg::WebResourceRequested(request_webview_id, request, sender) => {
                 if let Some(to_controller_sender) = to_controller_sender {
                     if let Some(request_map) = &mut self.event_listeners.on_web_resource_requested {
                         let id = uuid::Uuid::new_v4();
                         let mut builder = http::request::Builder::new()
                             .uri(request.url.as_str())
-                            .method(request.method);
+                            .method(request.method.clone());
                         for (key, value) in request.headers.iter() {
                             builder = builder.header(key, value);
                         }
@@ -206,7 +320,14 @@ impl Window {
                             ),
                         ) {
                             Ok(_) => {
-                                request_map.insert(id, (request.url, sender));
+                                request_map.insert(
+                                    id,
+                                    EmbedderMsg::WebResourceRequested(
+                                        request_webview_id,
+                                        request,
+                                        sender,
+                                    ),
+                                );
                                 // We will handle a ToVersoMessage::WebResourceRequestResponse
                                 // and send the response through this sender there if the call succeed
                             }
@@ -275,24 +396,105 @@ impl Window {
                     );
                 }
             }
-            EmbedderMsg::ShowContextMenu(_webview_id, servo_sender, _title, _options) => {
+            EmbedderMsg::ShowContextMenu(webview_id, servo_sender, _title, _options) => {
                 #[cfg(linux)]
                 if self.webview_menu.is_none() {
-                    self.webview_menu =
-                        Some(Box::new(self.show_context_menu(sender, servo_sender)));
+                    self.webview_menu = Some(Box::new(self.show_context_menu(
+                        sender,
+                        webview_id,
+                        servo_sender,
+                    )));
                 } else {
                     let _ = servo_sender.send(ContextMenuResult::Ignored);
                 }
                 #[cfg(any(target_os = "windows", target_os = "macos"))]
                 {
-                    let context_menu = self.show_context_menu(servo_sender);
+                    let context_menu = self.show_context_menu(webview_id, servo_sender);
                     // FIXME: there's chance to lose the event since the channel is async.
                     if let Ok(event) = self.menu_event_receiver.try_recv() {
-                        self.handle_context_menu_event(context_menu, sender, event);
+                        return self.handle_context_menu_event(
+                            context_menu,
+                            compositor,
+                            to_controller_sender,
+                            event,
+                        );
                     }
                 }
             }
             EmbedderMsg::ShowSimpleDialog(_webview_id, simple_dialog) => {
+                // Prompts of the bridge of Verso pages are always handled by Verso
+                let internal = match &simple_dialog {
+                    SimpleDialog::Prompt { message, .. } => {
+                        message.starts_with("VERSO::") && message != "VERSO::GEOLOCATION_PERMISSION"
+                    }
+                    _ => false,
+                };
+                if !internal && self.delegates_dialogs(to_controller_sender) {
+                    let (request, dialog) = match simple_dialog {
+                        SimpleDialog::Alert {
+                            message,
+                            response_sender,
+                        } => (
+                            DialogRequest::Alert { message },
+                            PromptSender::AlertSender(response_sender),
+                        ),
+                        SimpleDialog::Confirm {
+                            message,
+                            response_sender,
+                        } => (
+                            DialogRequest::Confirm { message },
+                            PromptSender::ConfirmSender(response_sender),
+                        ),
+                        SimpleDialog::Prompt {
+                            message,
+                            response_sender,
+                            ..
+                        } if message == "VERSO::GEOLOCATION_PERMISSION" => (
+                            DialogRequest::Permission {
+                                feature: "geolocation".to_string(),
+                            },
+                            PromptSender::AllowDenySender(geolocation_permission_sender(
+                                response_sender,
+                            )),
+                        ),
+                        SimpleDialog::Prompt {
+                            message,
+                            default,
+                            response_sender,
+                        } => (
+                            DialogRequest::Prompt { message, default },
+                            PromptSender::InputSender(response_sender),
+                        ),
+                    };
+                    self.delegate_dialog(
+                        webview_id,
+                        to_controller_sender,
+                        request,
+                        DelegatedDialog::Prompt(dialog),
+                    );
+                    return CommandAction::None;
+                }
+                if let SimpleDialog::Prompt {
+                    message,
+                    response_sender,
+                    ..
+                } = &simple_dialog
+                {
+                    if let Some(form) = message.strip_prefix("VERSO::FORM_SUBMISSION::") {
+                        let page_url = self
+                            .tab_manager
+                            .tab(webview_id)
+                            .and_then(|tab| tab.current_url().cloned());
+                        self.delegate_form_submission(
+                            webview_id,
+                            page_url,
+                            form,
+                            response_sender.clone(),
+                            to_controller_sender,
+                        );
+                        return CommandAction::None;
+                    }
+                }
                 if let Some(tab) = self.tab_manager.tab(webview_id) {
                     let mut prompt = PromptDialog::new();
                     let rect = tab.webview().rect;
@@ -326,24 +528,36 @@ impl Window {
                             default,
                             response_sender,
                         } => {
-                            if message.starts_with("VERSO::") {
+                            if message == "VERSO::GEOLOCATION_PERMISSION" {
+                                // Ask like Servo does for its own permissions, and answer the page
+                                // once the user has chosen
+                                prompt.allow_deny(
+                                    sender,
+                                    rect,
+                                    self.scale_factor() as f32,
+                                    "This website would like to know your location.".to_string(),
+                                    PromptSender::AllowDenySender(geolocation_permission_sender(
+                                        response_sender,
+                                    )),
+                                );
+                            } else if message.starts_with("VERSO::") {
                                 self.handle_verso_internal_messages_with_webview(
                                     message.strip_prefix("VERSO::").unwrap(),
                                     response_sender,
                                     sender,
                                     tab,
                                 );
-                                return;
+                                return CommandAction::None;
+                            } else {
+                                prompt.input(
+                                    sender,
+                                    rect,
+                                    self.scale_factor() as f32,
+                                    message,
+                                    Some(default),
+                                    response_sender,
+                                );
                             }
-
-                            prompt.input(
-                                sender,
-                                rect,
-                                self.scale_factor() as f32,
-                                message,
-                                Some(default),
-                                response_sender,
-                            );
                         }
                     }
 
@@ -354,7 +568,42 @@ impl Window {
                     log::error!("Failed to get WebView {webview_id:?} in this window.");
                 }
             }
+            EmbedderMsg::AllowUnload(_webview_id, response_sender) => {
+                if self.delegates_dialogs(to_controller_sender) {
+                    self.delegate_dialog(
+                        webview_id,
+                        to_controller_sender,
+                        DialogRequest::BeforeUnload,
+                        DelegatedDialog::Prompt(PromptSender::AllowDenySender(response_sender)),
+                    );
+                    return CommandAction::None;
+                }
+                if let Some(tab) = self.tab_manager.tab(webview_id) {
+                    let mut prompt = PromptDialog::new();
+                    prompt.leave_stay(
+                        sender,
+                        tab.webview().rect,
+                        self.scale_factor() as f32,
+                        response_sender,
+                    );
+                    self.tab_manager.set_prompt(webview_id, prompt);
+                } else {
+                    log::error!("Failed to get WebView {webview_id:?} in this window.");
+                    let _ = response_sender.send(AllowOrDeny::Allow);
+                }
+            }
             EmbedderMsg::PromptPermission(_webview_id, feature, prompt_sender) => {
+                if self.delegates_dialogs(to_controller_sender) {
+                    self.delegate_dialog(
+                        webview_id,
+                        to_controller_sender,
+                        DialogRequest::Permission {
+                            feature: format!("{feature:?}").to_lowercase(),
+                        },
+                        DelegatedDialog::Prompt(PromptSender::AllowDenySender(prompt_sender)),
+                    );
+                    return CommandAction::None;
+                }
                 if let Some(tab) = self.tab_manager.tab(webview_id) {
                     let message = format!(
                         "This website would like to request permission for {:?}.",
@@ -374,7 +623,28 @@ impl Window {
                     log::error!("Failed to get WebView {webview_id:?} in this window.");
                 }
             }
-            EmbedderMsg::RequestAuthentication(_webview_id, _url, _proxy, response_sender) => {
+            EmbedderMsg::RequestAuthentication(_webview_id, url, for_proxy, response_sender) => {
+                let Some(response_sender) = self.delegate_authentication(
+                    webview_id,
+                    url.clone().into_url(),
+                    for_proxy,
+                    response_sender,
+                    to_controller_sender,
+                ) else {
+                    return CommandAction::None;
+                };
+                if self.delegates_dialogs(to_controller_sender) {
+                    self.delegate_dialog(
+                        webview_id,
+                        to_controller_sender,
+                        DialogRequest::Authentication {
+                            url: url.into_url(),
+                            for_proxy,
+                        },
+                        DelegatedDialog::Prompt(PromptSender::HttpBasicAuthSender(response_sender)),
+                    );
+                    return CommandAction::None;
+                }
                 if let Some(tab) = self.tab_manager.tab(webview_id) {
                     let mut prompt = PromptDialog::new();
                     let rect = tab.webview().rect;
@@ -382,6 +652,8 @@ impl Window {
                         sender,
                         rect,
                         self.scale_factor() as f32,
+                        (url.into_url(), for_proxy),
+                        !self.private,
                         response_sender,
                     );
                     self.tab_manager.set_prompt(webview_id, prompt);
@@ -391,38 +663,28 @@ impl Window {
             }
             EmbedderMsg::SelectFiles(
                 _webview_id,
-                _filter_pattern,
-                _allow_multiple_files,
+                filter_patterns,
+                allow_multiple_files,
                 ipc_sender,
             ) => {
-                if _allow_multiple_files {
-                    rfd::FileDialog::new()
-                        .pick_files()
-                        .map(|files| {
-                            if let Err(e) = ipc_sender.send(Some(files)) {
-                                log::warn!("Verso Panel failed to send files: {}", e);
-                            }
-                        })
-                        .unwrap_or_else(|| {
-                            log::error!("Failed to open file dialog.");
-                            if let Err(e) = ipc_sender.send(None) {
-                                log::warn!("Verso Panel failed to send files: {}", e);
-                            }
-                        });
-                } else {
-                    rfd::FileDialog::new()
-                        .pick_file()
-                        .map(|file| {
-                            if let Err(e) = ipc_sender.send(Some(vec![file])) {
-                                log::warn!("Verso Panel failed to send files: {}", e);
-                            }
-                        })
-                        .unwrap_or_else(|| {
-                            log::error!("Failed to open file dialog.");
-                            if let Err(e) = ipc_sender.send(None) {
-                                log::warn!("Verso Panel failed to send files: {}", e);
-                            }
-                        });
+                if self.delegates_dialogs(to_controller_sender) {
+                    self.delegate_dialog(
+                        webview_id,
+                        to_controller_sender,
+                        DialogRequest::FilePicker {
+                            filter_patterns: filter_patterns
+                                .into_iter()
+                                .map(|pattern| pattern.0)
+                                .collect(),
+                            allow_multiple: allow_multiple_files,
+                        },
+                        DelegatedDialog::FilePicker(ipc_sender),
+                    );
+                    return CommandAction::None;
+                }
+                let files = pick_files(&self.window, &filter_patterns, allow_multiple_files);
+                if let Err(e) = ipc_sender.send(files) {
+                    log::warn!("Verso Panel failed to send files: {}", e);
                 }
             }
             EmbedderMsg::ShowIME(_webview_id, input_method_type, text, multiline, position) => {
@@ -444,18 +706,58 @@ impl Window {
                 log::trace!("Verso WebView isn't supporting this message yet: {e:?}")
             }
         }
+        CommandAction::None
+    }
+
+    /// Whether the controller shows the dialogs of webviews instead of Verso.
+    fn delegates_dialogs(
+        &self,
+        to_controller_sender: &Option<ipc::IpcSender<ToControllerMessage>>,
+    ) -> bool {
+        to_controller_sender.is_some() && self.event_listeners.on_dialog.is_some()
     }
 
-    /// Handle servo messages with main panel. Return true it requests a new window.
+    /// Send the dialog of the webview to the controller, the page waits until it's answered with
+    /// a [`versoview_messages::ToVersoMessage::DialogResponse`].
+    fn delegate_dialog(
+        &mut self,
+        webview_id: WebViewId,
+        to_controller_sender: &Option<ipc::IpcSender<ToControllerMessage>>,
+        request: DialogRequest,
+        dialog: DelegatedDialog,
+    ) {
+        let (Some(to_controller_sender), Some(dialogs)) =
+            (to_controller_sender, &mut self.event_listeners.on_dialog)
+        else {
+            return;
+        };
+        let id = uuid::Uuid::new_v4();
+        match to_controller_sender.send(ToControllerMessage::OnDialog(
+            id,
+            bincode::serialize(&webview_id).unwrap(),
+            request,
+        )) {
+            Ok(_) => {
+                dialogs.insert(id, dialog);
+            }
+            Err(error) => {
+                log::error!("Verso failed to send OnDialog to controller: {error}");
+                dialog.respond(DialogResponse::Cancel);
+            }
+        }
+    }
+
+    /// Handle servo messages with main panel, returning what's left to do of the command it executes.
     pub fn handle_servo_messages_with_panel(
         &mut self,
         panel_id: WebViewId,
         message: EmbedderMsg,
         sender: Sender<EmbedderToConstellationMessage>,
+        to_controller_sender: &Option<IpcSender<ToControllerMessage>>,
         clipboard: Option<&mut Clipboard>,
         compositor: &mut IOCompositor,
         bookmark_manager: &mut BookmarkManager,
-    ) -> bool {
+    ) -> CommandAction {
         log::trace!("Verso Panel {panel_id:?} is handling Embedder message: {message:?}",);
         match message {
             EmbedderMsg::WebViewClosed(_) => {
@@ -481,8 +783,17 @@ impl Window {
                         &sender,
                         EmbedderToConstellationMessage::FocusWebView(panel_id),
                     );
+                    if self.private {
+                        let _ = execute_script(&sender, &panel_id, PRIVATE_PANEL_SCRIPT);
+                    } else {
+                        let _ = execute_script(&sender, &panel_id, URL_BAR_AUTOFILL_SCRIPT);
+                    }
 
-                    self.create_tab(&sender, self.panel.as_ref().unwrap().initial_url.clone());
+                    if self.has_restored_tabs() {
+                        self.open_restored_tabs(compositor);
+                    } else {
+                        self.create_tab(&sender, self.panel.as_ref().unwrap().initial_url.clone());
+                    }
                 } else {
                     log::trace!("Verso Panel ignores NotifyLoadStatusChanged status: {status:?}");
                 }
@@ -519,7 +830,20 @@ impl Window {
                             }
 
                             let _ = response_sender.send(PromptResponse::default());
-                            return false;
+                            return CommandAction::None;
+                        } else if message.starts_with("MUTE_TAB:") {
+                            let request_str = message.strip_prefix("MUTE_TAB:").unwrap();
+                            let request: TabMuteRequest = serde_json::from_str(request_str)
+                                .expect("Failed to parse TabMuteRequest");
+
+                            let _ = response_sender.send(PromptResponse::default());
+                            self.set_tab_muted(
+                                &sender,
+                                to_controller_sender,
+                                request.id,
+                                request.muted,
+                            );
+                            return CommandAction::None;
                         } else if message.starts_with("ACTIVATE_TAB:") {
                             let request_str = message.strip_prefix("ACTIVATE_TAB:").unwrap();
                             let request: TabActivateRequest = serde_json::from_str(request_str)
@@ -532,7 +856,7 @@ impl Window {
                             // FIXME: set dirty flag, and only resize when flag is set
                             self.activate_tab(compositor, tab_id, self.tab_manager.count() > 1);
 
-                            return false;
+                            return CommandAction::None;
                         } else if message == "NEW_TAB" {
                             let hidpi_scale_factor = Scale::new(self.scale_factor() as f32);
 
@@ -569,7 +893,15 @@ impl Window {
                                 id: webview_id,
                             };
                             let _ = response_sender.send(PromptResponse::Ok(result.to_json()));
-                            return false;
+                            return CommandAction::None;
+                        } else if let Some(input) = message.strip_prefix("HISTORY_SUGGEST::") {
+                            let _ =
+                                self.verso_internal_sender
+                                    .send(VersoInternalMsg::SuggestHistory(
+                                        input.to_string(),
+                                        response_sender,
+                                    ));
+                            return CommandAction::None;
                         } else if message.starts_with("OPEN_HISTORY_MENU") {
                             let request_str = message.strip_prefix("OPEN_HISTORY_MENU:").unwrap();
                             let request: OpenHistoryMenuRequest = serde_json::from_str(request_str)
@@ -580,14 +912,14 @@ impl Window {
                                 self.webview_menu = Some(Box::new(menu));
                             }
 
-                            return false;
+                            return CommandAction::None;
                         } else if message == "UPDATE_BOOKMARK" {
                             self.show_bookmark = !bookmark_manager.bookmarks().is_empty();
                             compositor.resize(self.size(), self);
                             let _ = response_sender.send(PromptResponse::Ok(
                                 serde_json::to_string(bookmark_manager.bookmarks()).unwrap(),
                             ));
-                            return false;
+                            return CommandAction::None;
                         } else if message == "BOOKMARK" {
                             if let Some(tab) = self.tab_manager.current_tab() {
                                 if let Some(url) = tab.history().list.get(tab.history().current_idx)
@@ -595,17 +927,11 @@ impl Window {
                                     let url = url.to_string();
                                     // Ignore the bookmark if it starts with "verso://"
                                     if url.starts_with("verso://") {
-                                        return false;
+                                        return CommandAction::None;
                                     }
                                     let bookmark_previously_shown =
                                         !bookmark_manager.bookmarks().is_empty();
-                                    if bookmark_manager.bookmarks().iter().any(|b| b.url == url) {
-                                        let _ = bookmark_manager.remove_bookmark(
-                                            BookmarkId::from_str(url.as_str()).unwrap(),
-                                        );
-                                    } else {
-                                        bookmark_manager.append_bookmark(tab.title(), url);
-                                    }
+                                    bookmark_manager.toggle_bookmark(tab.title(), url);
                                     let _ = response_sender.send(PromptResponse::Ok(
                                         serde_json::to_string(bookmark_manager.bookmarks())
                                             .unwrap(),
@@ -619,38 +945,24 @@ impl Window {
                                     }
                                 }
                             }
-                            return false;
+                            return CommandAction::None;
                         }
 
                         let _ = response_sender.send(PromptResponse::default());
 
+                        /* Commands */
+                        if let Some(command_id) = panel_command_id(&message) {
+                            return self.execute_command(
+                                command_id,
+                                compositor,
+                                to_controller_sender,
+                            );
+                        }
+
                         /* Window */
-                        match message.as_str() {
-                            "NEW_WINDOW" => {
-                                let _ = response_sender.send(PromptResponse::default());
-                                return true;
-                            }
-                            "MINIMIZE" => {
-                                self.window.set_minimized(true);
-                                return false;
-                            }
-                            "MAXIMIZE" | "DBCLICK_PANEL" => {
-                                let is_maximized = self.window.is_maximized();
-                                self.window.set_maximized(!is_maximized);
-                                return false;
-                            }
-                            "DRAG_WINDOW" => {
-                                let _ = self.window.drag_window();
-                                return false;
-                            }
-                            "DOWNLOAD" => {
-                                self.create_tab(
-                                    &sender,
-                                    ServoUrl::parse("verso://resources/components/downloads.html")
-                                        .unwrap(),
-                                );
-                            }
-                            _ => {}
+                        if message == "DRAG_WINDOW" {
+                            let _ = self.window.drag_window();
+                            return CommandAction::None;
                         }
 
                         /* Main WebView */
@@ -672,12 +984,19 @@ impl Window {
 
                                 let client = self.reqwest_client.clone();
                                 let verso_internal_sender = self.verso_internal_sender.clone();
+                                let max_download_size = tab.resource_limits().max_download_size;
                                 tokio::spawn(async move {
                                     let (should_download, resp) =
                                         check_should_download(&client, &url).await;
                                     if should_download && resp.is_some() {
-                                        download_body(url, resp.unwrap(), verso_internal_sender)
-                                            .await;
+                                        download_body(
+                                            url,
+                                            resp.unwrap(),
+                                            id,
+                                            max_download_size,
+                                            verso_internal_sender,
+                                        )
+                                        .await;
                                     } else {
                                         send_to_constellation(
                                             &sender,
@@ -688,44 +1007,10 @@ impl Window {
                                         );
                                     }
                                 });
-                            } else if message == "OPEN_BOOKMARK_MANAGER" {
-                                self.create_tab(
-                                    &sender,
-                                    ServoUrl::parse("verso://resources/components/bookmark.html")
-                                        .unwrap(),
-                                );
                             } else {
-                                match message.as_str() {
-                                    "PREV" => {
-                                        send_to_constellation(
-                                            &sender,
-                                            EmbedderToConstellationMessage::TraverseHistory(
-                                                id,
-                                                TraversalDirection::Back(1),
-                                            ),
-                                        );
-                                        // TODO Set EmbedderMsg::Status to None
-                                    }
-                                    "FORWARD" => {
-                                        send_to_constellation(
-                                            &sender,
-                                            EmbedderToConstellationMessage::TraverseHistory(
-                                                id,
-                                                TraversalDirection::Forward(1),
-                                            ),
-                                        );
-                                        // TODO Set EmbedderMsg::Status to None
-                                    }
-                                    "REFRESH" => {
-                                        send_to_constellation(
-                                            &sender,
-                                            EmbedderToConstellationMessage::Reload(id),
-                                        );
-                                    }
-                                    e => log::trace!(
-                                        "Verso Panel isn't supporting this prompt message yet: {e}"
-                                    ),
-                                }
+                                log::trace!(
+                                    "Verso Panel isn't supporting this prompt message yet: {message}"
+                                );
                             }
                         }
                     }
@@ -752,20 +1037,28 @@ impl Window {
                     }
                 }
             }
-            EmbedderMsg::ShowContextMenu(_, servo_sender, _, _) => {
+            EmbedderMsg::ShowContextMenu(webview_id, servo_sender, _, _) => {
                 #[cfg(linux)]
                 if self.webview_menu.is_none() {
-                    self.webview_menu =
-                        Some(Box::new(self.show_context_menu(&sender, servo_sender)));
+                    self.webview_menu = Some(Box::new(self.show_context_menu(
+                        &sender,
+                        webview_id,
+                        servo_sender,
+                    )));
                 } else {
                     let _ = servo_sender.send(ContextMenuResult::Ignored);
                 }
                 #[cfg(any(target_os = "windows", target_os = "macos"))]
                 {
-                    let context_menu = self.show_context_menu(servo_sender);
+                    let context_menu = self.show_context_menu(webview_id, servo_sender);
                     // FIXME: there's chance to lose the event since the channel is async.
                     if let Ok(event) = self.menu_event_receiver.try_recv() {
-                        self.handle_context_menu_event(context_menu, &sender, event);
+                        return self.handle_context_menu_event(
+                            context_menu,
+                            compositor,
+                            to_controller_sender,
+                            event,
+                        );
                     }
                 }
             }
@@ -773,18 +1066,19 @@ impl Window {
                 log::trace!("Verso Panel isn't supporting this message yet: {e:?}")
             }
         }
-        false
+        CommandAction::None
     }
 
-    /// Handle servo messages with webview menu. Return true it requests a new window.
+    /// Handle servo messages with webview menu, returning what's left to do of the command it executes.
     pub fn handle_servo_messages_with_webview_menu(
         &mut self,
         webview_id: WebViewId,
         message: EmbedderMsg,
         sender: &Sender<EmbedderToConstellationMessage>,
+        to_controller_sender: &Option<IpcSender<ToControllerMessage>>,
         _clipboard: Option<&mut Clipboard>,
-        _compositor: &mut IOCompositor,
-    ) -> bool {
+        compositor: &mut IOCompositor,
+    ) -> CommandAction {
         log::trace!("Verso WebView Menu {webview_id:?} is handling Embedder message: {message:?}",);
         match message {
             EmbedderMsg::WebViewBlurred => {
@@ -793,12 +1087,24 @@ impl Window {
             EmbedderMsg::WebViewFocused(webview_id) => {
                 self.focused_webview_id = Some(webview_id);
             }
+            EmbedderMsg::NotifyLoadStatusChanged(_webview_id, LoadStatus::Complete) => {
+                // Let the menu receive keyboard inputs, e.g. the command palette's search input
+                send_to_constellation(
+                    sender,
+                    EmbedderToConstellationMessage::FocusWebView(webview_id),
+                );
+            }
             EmbedderMsg::ShowSimpleDialog(_webview_id, simple_dialog) => match simple_dialog {
                 SimpleDialog::Prompt {
                     message,
                     default: _,
                     response_sender,
                 } => {
+                    if let Some(query) = message.strip_prefix("COMMAND_PALETTE_SEARCH:") {
+                        let _ =
+                            response_sender.send(PromptResponse::Ok(self.search_commands(query)));
+                        return CommandAction::None;
+                    }
                     let _ = response_sender.send(PromptResponse::default());
 
                     #[cfg(linux)]
@@ -807,7 +1113,12 @@ impl Window {
                         let result =
                             serde_json::from_str::<ContextMenuUIResponse>(json_str_msg).unwrap();
 
-                        self.handle_context_menu_event(sender, result);
+                        return self.handle_context_menu_event(
+                            sender,
+                            compositor,
+                            to_controller_sender,
+                            result,
+                        );
                     }
                     if message.starts_with("HISTORY_MENU:") {
                         let json_str_msg = message.strip_prefix("HISTORY_MENU:").unwrap();
@@ -816,14 +1127,27 @@ impl Window {
 
                         self.handle_history_menu_event(sender, result);
                     }
+                    if message.starts_with("COMMAND_PALETTE:") {
+                        let json_str_msg = message.strip_prefix("COMMAND_PALETTE:").unwrap();
+                        let result =
+                            serde_json::from_str::<CommandPaletteUIResponse>(json_str_msg).unwrap();
+
+                        self.close_webview_menu(sender);
+                        if let Some(id) = result.id {
+                            return self.execute_command(&id, compositor, to_controller_sender);
+                        }
+                    }
                 }
                 _ => log::trace!("Verso context menu isn't supporting this prompt yet"),
             },
             #[cfg(linux)]
-            EmbedderMsg::ShowContextMenu(_webview_id, servo_sender, _title, _options) => {
+            EmbedderMsg::ShowContextMenu(webview_id, servo_sender, _title, _options) => {
                 if self.webview_menu.is_none() {
-                    self.webview_menu =
-                        Some(Box::new(self.show_context_menu(sender, servo_sender)));
+                    self.webview_menu = Some(Box::new(self.show_context_menu(
+                        sender,
+                        webview_id,
+                        servo_sender,
+                    )));
                 } else {
                     let _ = servo_sender.send(ContextMenuResult::Ignored);
                 }
@@ -832,7 +1156,7 @@ impl Window {
                 log::trace!("Verso context menu isn't supporting this message yet: {e:?}")
             }
         }
-        false
+        CommandAction::None
     }
 
     /// Handle servo messages with prompt. Return true it requests a new window.
@@ -913,11 +1237,28 @@ impl Window {
                             let _ = sender.send(result);
                         }
                         PromptSender::HttpBasicAuthSender(sender) => {
-                            if let Ok(HttpBasicAuthInputResult { action, auth }) =
-                                serde_json::from_str::<HttpBasicAuthInputResult>(&message)
+                            if let Ok(HttpBasicAuthInputResult {
+                                action,
+                                auth,
+                                remember,
+                            }) = serde_json::from_str::<HttpBasicAuthInputResult>(&message)
                             {
                                 match action.as_str() {
                                     "signin" => {
+                                        if let Some((url, for_proxy)) =
+                                            remember.then(|| prompt.authentication()).flatten()
+                                        {
+                                            let _ = self.verso_internal_sender.send(
+                                                VersoInternalMsg::RememberCredentials(
+                                                    url.clone(),
+                                                    *for_proxy,
+                                                    HttpCredentials {
+                                                        username: auth.username.clone(),
+                                                        password: auth.password.clone(),
+                                                    },
+                                                ),
+                                            );
+                                        }
                                         let _ = sender.send(Some(auth));
                                     }
                                     "cancel" => {
@@ -970,6 +1311,24 @@ impl Window {
                     ));
             }
             return;
+        } else if let Some(id) = message.strip_prefix("PAUSE_DOWNLOAD::") {
+            let _ = response_sender.send(PromptResponse::Cancel);
+            let _ = self
+                .verso_internal_sender
+                .send(VersoInternalMsg::ControlDownload(
+                    DownloadId::from_str(id).unwrap(),
+                    DownloadControl::Pause,
+                ));
+            return;
+        } else if let Some(id) = message.strip_prefix("RESUME_DOWNLOAD::") {
+            let _ = response_sender.send(PromptResponse::Cancel);
+            let _ = self
+                .verso_internal_sender
+                .send(VersoInternalMsg::ControlDownload(
+                    DownloadId::from_str(id).unwrap(),
+                    DownloadControl::Resume,
+                ));
+            return;
         } else if message.starts_with("BOOKMARK_LIST_GET") {
             let _ = self
                 .verso_internal_sender
@@ -1006,6 +1365,305 @@ impl Window {
                     });
             }
             return;
+        } else if let Some(query) = message.strip_prefix("HISTORY_SEARCH::") {
+            // The text of visited pages is only shown on the history page
+            if tab.current_url().is_none_or(|url| url.scheme() != "verso") {
+                let _ = response_sender.send(PromptResponse::Cancel);
+                return;
+            }
+            let _ = self
+                .verso_internal_sender
+                .send(VersoInternalMsg::SearchHistory(
+                    query.to_string(),
+                    response_sender,
+                ));
+            return;
+        } else if message.starts_with("CRASH_") {
+            // Crash reports are only shown on the crash reports page
+            if tab.current_url().is_none_or(|url| url.scheme() != "verso") {
+                let _ = response_sender.send(PromptResponse::Cancel);
+                return;
+            }
+            let message = if message == "CRASH_LIST" {
+                VersoInternalMsg::CrashList(response_sender)
+            } else if let Some(id) = message
+                .strip_prefix("CRASH_SUBMIT::")
+                .and_then(|id| uuid::Uuid::from_str(id).ok())
+            {
+                VersoInternalMsg::CrashSubmit(id, response_sender)
+            } else if let Some(id) = message
+                .strip_prefix("CRASH_DELETE::")
+                .and_then(|id| uuid::Uuid::from_str(id).ok())
+            {
+                let _ = response_sender.send(PromptResponse::Cancel);
+                VersoInternalMsg::CrashDelete(id)
+            } else {
+                log::error!("Invalid crash report request: {message}");
+                let _ = response_sender.send(PromptResponse::Cancel);
+                return;
+            };
+            let _ = self.verso_internal_sender.send(message);
+            return;
+        } else if message.starts_with("HIGHLIGHT_") {
+            // Highlights are looked up by the URL of the tab, so pages only reach their own
+            let Some(url) = tab.current_url().cloned() else {
+                let _ = response_sender.send(PromptResponse::Cancel);
+                return;
+            };
+            let message = if message == "HIGHLIGHT_LIST" {
+                VersoInternalMsg::HighlightList(url, response_sender)
+            } else if let Some(highlight) = message
+                .strip_prefix("HIGHLIGHT_ADD::")
+                .and_then(|highlight| serde_json::from_str(highlight).ok())
+            {
+                VersoInternalMsg::HighlightAdd(url, highlight, response_sender)
+            } else if let Some(id) = message
+                .strip_prefix("HIGHLIGHT_REMOVE::")
+                .and_then(|id| uuid::Uuid::from_str(id).ok())
+            {
+                let _ = response_sender.send(PromptResponse::Cancel);
+                VersoInternalMsg::HighlightRemove(url, id)
+            } else {
+                log::error!("Invalid highlight request: {message}");
+                let _ = response_sender.send(PromptResponse::Cancel);
+                return;
+            };
+            let _ = self.verso_internal_sender.send(message);
+            return;
+        } else if let Some(host) = message.strip_prefix("CERTIFICATE_ERROR::") {
+            // The page shown for the error is the document of the tab, not of one of its frames
+            let host = host.to_ascii_lowercase();
+            let host = tab
+                .current_url()
+                .and_then(|url| url.host_str())
+                .is_some_and(|tab_host| tab_host.eq_ignore_ascii_case(&host))
+                .then_some(host);
+            let _ = self
+                .verso_internal_sender
+                .send(VersoInternalMsg::CertificateError(host, response_sender));
+            return;
+        } else if message == "IMAGE_POLICY" {
+            let _ = self
+                .verso_internal_sender
+                .send(VersoInternalMsg::GetImagePolicy(response_sender));
+            return;
+        } else if let Some(url) = message.strip_prefix("LOAD_IMAGE::") {
+            let _ = response_sender.send(PromptResponse::Cancel);
+            match url::Url::parse(url) {
+                Ok(url) => {
+                    let _ = self
+                        .verso_internal_sender
+                        .send(VersoInternalMsg::LoadImage(tab.id(), url));
+                }
+                Err(e) => log::error!("Invalid image to load {url}: {e}"),
+            }
+            return;
+        } else if let Some(origin) = message.strip_prefix("JAVASCRIPT_ENABLED::") {
+            // Pages only get the setting of the tab's origin, not of the origins of other sites
+            let origin = tab
+                .current_url()
+                .is_some_and(|url| url.origin().ascii_serialization() == origin)
+                .then(|| origin.to_string());
+            let _ = self
+                .verso_internal_sender
+                .send(VersoInternalMsg::GetJavaScriptEnabled(
+                    origin,
+                    response_sender,
+                ));
+            return;
+        } else if message.starts_with("SITE_SETTINGS") {
+            // Site settings are only changed on the site settings page
+            if tab.current_url().is_none_or(|url| url.scheme() != "verso") {
+                let _ = response_sender.send(PromptResponse::Cancel);
+                return;
+            }
+            let message = if message == "SITE_SETTINGS_LIST" {
+                VersoInternalMsg::SiteSettings(response_sender)
+            } else if let Some(setting) = message
+                .strip_prefix("SITE_SETTINGS_JAVASCRIPT::")
+                .and_then(|setting| serde_json::from_str::<JavaScriptSetting>(setting).ok())
+            {
+                let _ = response_sender.send(PromptResponse::Cancel);
+                VersoInternalMsg::SetJavaScriptEnabled(setting.origin, setting.enabled)
+            } else {
+                log::error!("Invalid site settings request: {message}");
+                let _ = response_sender.send(PromptResponse::Cancel);
+                return;
+            };
+            let _ = self.verso_internal_sender.send(message);
+            return;
+        } else if message == "PRIVACY_SETTINGS" {
+            let _ = self
+                .verso_internal_sender
+                .send(VersoInternalMsg::GetPrivacySettings(
+                    tab.privacy().cloned(),
+                    response_sender,
+                ));
+            return;
+        } else if let Some(request) = message.strip_prefix("GEOLOCATION_POSITION::") {
+            let _ = response_sender.send(PromptResponse::Cancel);
+            let Some((accuracy, request_id)) = request
+                .split_once("::")
+                .and_then(|(accuracy, id)| Some((accuracy, id.parse::<u64>().ok()?)))
+            else {
+                log::error!("Invalid geolocation request: {request}");
+                return;
+            };
+            let _ = self
+                .verso_internal_sender
+                .send(VersoInternalMsg::GetGeolocation(
+                    tab.id(),
+                    request_id,
+                    accuracy == "HIGH",
+                ));
+            return;
+        } else if let Some(value) = message.strip_prefix("APP_BADGE::") {
+            let _ = response_sender.send(PromptResponse::Cancel);
+            match Badge::parse(value) {
+                Some(badge) => set_badge(&self.window, badge),
+                None => log::error!("Invalid app badge: {value}"),
+            }
+            return;
+        } else if let Some(color) = message.strip_prefix("THEME_COLOR::") {
+            let _ = response_sender.send(PromptResponse::Cancel);
+            let color = (!color.is_empty()).then(|| color.to_string());
+            let _ = self
+                .verso_internal_sender
+                .send(VersoInternalMsg::ThemeColorChanged(tab.id(), color));
+            return;
+        } else if let Some(manifest_url) = message.strip_prefix("WEB_APP_MANIFEST::") {
+            let _ = response_sender.send(PromptResponse::Cancel);
+            let Ok(manifest_url) = Url::parse(manifest_url) else {
+                log::error!("Invalid web app manifest URL: {manifest_url}");
+                return;
+            };
+            let client = self.reqwest_client.clone();
+            let verso_internal_sender = self.verso_internal_sender.clone();
+            let webview_id = tab.id();
+            tokio::spawn(async move {
+                match fetch_manifest(&client, manifest_url.clone()).await {
+                    Ok((manifest, icon)) => {
+                        let _ = verso_internal_sender.send(VersoInternalMsg::WebAppManifestLoaded(
+                            webview_id, manifest, icon,
+                        ));
+                    }
+                    Err(e) => {
+                        log::error!("Verso failed to load the web app manifest {manifest_url}: {e}")
+                    }
+                }
+            });
+            return;
+        } else if let Some(request) = message.strip_prefix("SHARE::") {
+            match serde_json::from_str::<ShareData>(request) {
+                Ok(data) => {
+                    let _ = self.verso_internal_sender.send(VersoInternalMsg::Share(
+                        tab.id(),
+                        data,
+                        response_sender,
+                    ));
+                }
+                Err(e) => {
+                    log::error!("Invalid share request: {e}");
+                    let _ = response_sender.send(PromptResponse::Cancel);
+                }
+            }
+            return;
+        } else if message == "SENSOR_READING" {
+            let _ = self
+                .verso_internal_sender
+                .send(VersoInternalMsg::GetSensorReading(response_sender));
+            return;
+        } else if message == "BATTERY_STATUS" {
+            let _ = self
+                .verso_internal_sender
+                .send(VersoInternalMsg::GetBatteryStatus(response_sender));
+            return;
+        } else if let Some(request) = message.strip_prefix("WAKE_LOCK::") {
+            let held = match request {
+                "ACQUIRE" => {
+                    // Only the visible tab of the window can keep the display awake
+                    let visible = self.tab_manager.current_tab_id() == Some(tab.id())
+                        && tab
+                            .lifecycle_state()
+                            .is_none_or(|state| state == LifecycleState::Visible);
+                    if !visible {
+                        let _ = response_sender.send(PromptResponse::Cancel);
+                        return;
+                    }
+                    let _ = response_sender.send(PromptResponse::Ok(String::new()));
+                    true
+                }
+                "RELEASE" => {
+                    let _ = response_sender.send(PromptResponse::Cancel);
+                    false
+                }
+                _ => {
+                    log::error!("Invalid wake lock request: {request}");
+                    let _ = response_sender.send(PromptResponse::Cancel);
+                    return;
+                }
+            };
+            let _ = self
+                .verso_internal_sender
+                .send(VersoInternalMsg::SetWakeLock(tab.id(), held));
+            return;
+        } else if let Some((operation, request)) = message
+            .strip_prefix("SECRET_")
+            .and_then(|message| message.split_once("::"))
+        {
+            // Verso checks the token and if the origin is allowed to use the secrets bridge
+            let request = serde_json::from_str::<SecretRequest>(request);
+            let message = match (operation, request) {
+                ("GET", Ok(request)) => VersoInternalMsg::SecretGet(request, response_sender),
+                ("DELETE", Ok(request)) => VersoInternalMsg::SecretDelete(request, response_sender),
+                ("SET", Ok(mut request)) if request.value.is_some() => {
+                    let secret = request.value.take().unwrap_or_default();
+                    VersoInternalMsg::SecretSet(request, secret, response_sender)
+                }
+                _ => {
+                    log::error!("Invalid secrets bridge message");
+                    let _ = response_sender.send(PromptResponse::Cancel);
+                    return;
+                }
+            };
+            let _ = self.verso_internal_sender.send(message);
+            return;
+        } else if let Some(violation) = message.strip_prefix("CSP_VIOLATION::") {
+            let _ = response_sender.send(PromptResponse::Cancel);
+            match serde_json::from_str::<CspViolation>(violation) {
+                Ok(violation) => {
+                    let _ = self
+                        .verso_internal_sender
+                        .send(VersoInternalMsg::CspViolation(tab.id(), violation));
+                }
+                Err(e) => log::error!("Invalid CSP violation: {e}"),
+            }
+            return;
+        } else if let Some(subresource) = message.strip_prefix("SUBRESOURCE_BLOCKED::") {
+            let _ = response_sender.send(PromptResponse::Cancel);
+            match serde_json::from_str::<BlockedSubresource>(subresource) {
+                Ok(subresource) => {
+                    let _ = self
+                        .verso_internal_sender
+                        .send(VersoInternalMsg::SubresourceBlocked(tab.id(), subresource));
+                }
+                Err(e) => log::error!("Invalid blocked subresource: {e}"),
+            }
+            return;
+        } else if let Some(link) = message.strip_prefix("DRAG_LINK::") {
+            let _ = response_sender.send(PromptResponse::Cancel);
+            match serde_json::from_str::<DraggedLink>(link) {
+                Ok(link) => self.drag_link(link),
+                Err(e) => log::error!("Invalid dragged link: {e}"),
+            }
+            return;
+        } else if let Some(target) = message.strip_prefix("CONTEXT_MENU_TARGET::") {
+            let _ = response_sender.send(PromptResponse::Cancel);
+            match serde_json::from_str::<ContextMenuTarget>(target) {
+                Ok(target) => *self.context_menu_target.borrow_mut() = Some((tab.id(), target)),
+                Err(e) => log::error!("Invalid context menu target: {e}"),
+            }
+            return;
         } else if message.starts_with("NAVIGATE_TO::") {
             let url = message.strip_prefix("NAVIGATE_TO::").unwrap();
             let url = match Url::parse(url) {
@@ -1027,6 +1685,25 @@ impl Window {
     }
 }
 
+/// Answer the `VERSO::GEOLOCATION_PERMISSION` prompt of the geolocation polyfill once the
+/// returned sender gets whether the user allowed it, like Servo asks for its own permissions.
+fn geolocation_permission_sender(
+    response_sender: IpcSender<PromptResponse>,
+) -> IpcSender<AllowOrDeny> {
+    let (allow_sender, allow_receiver) = ipc::channel::<AllowOrDeny>().unwrap();
+    ROUTER.add_typed_route(
+        allow_receiver,
+        Box::new(move |result| {
+            let response = match result {
+                Ok(AllowOrDeny::Allow) => PromptResponse::Ok("allow".to_string()),
+                _ => PromptResponse::Cancel,
+            };
+            let _ = response_sender.send(response);
+        }),
+    );
+    allow_sender
+}
+
 /// Blocking execute a script on this webview
 pub fn execute_script(
     constellation_sender: &Sender<EmbedderToConstellationMessage>,
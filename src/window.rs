@@ -1,23 +1,28 @@
-use std::{cell::Cell, collections::HashMap};
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+};
 
 use base::id::WebViewId;
-use constellation_traits::EmbedderToConstellationMessage;
+use constellation_traits::{EmbedderToConstellationMessage, WindowSizeType};
 use crossbeam_channel::Sender;
 use embedder_traits::{
-    AlertResponse, AllowOrDeny, ConfirmResponse, Cursor, EmbedderMsg, ImeEvent, InputEvent,
-    MouseButton, MouseButtonAction, MouseButtonEvent, MouseMoveEvent, Notification, PromptResponse,
-    TouchEventType, ViewportDetails, WebDriverJSValue, WebResourceResponseMsg, WheelMode,
+    AlertResponse, AllowOrDeny, AuthenticationResponse, ConfirmResponse, Cursor, EmbedderMsg,
+    ImeEvent, InputEvent, MouseButton, MouseButtonAction, MouseButtonEvent, MouseMoveEvent,
+    Notification, PromptResponse, TouchEvent, TouchEventType, ViewportDetails, WebDriverJSValue,
+    WheelMode,
 };
-use euclid::{Point2D, Scale, Size2D};
+use euclid::{Scale, Size2D};
 use glutin::{
     config::{ConfigTemplateBuilder, GlConfig},
     surface::{Surface, WindowSurface},
 };
 use glutin_winit::DisplayBuilder;
-use ipc_channel::ipc::IpcSender;
-use keyboard_types::{
-    Code, CompositionEvent, CompositionState, KeyState, KeyboardEvent, Modifiers,
+use ipc_channel::{
+    ipc::{self, IpcSender},
+    router::ROUTER,
 };
+use keyboard_types::{CompositionEvent, CompositionState, KeyState, KeyboardEvent};
 #[cfg(any(target_os = "macos", target_os = "windows"))]
 use muda::{MenuEvent, MenuEventReceiver};
 #[cfg(linux)]
@@ -26,7 +31,7 @@ use notify_rust::Image;
 use raw_window_handle::HasWindowHandle;
 use reqwest::Client;
 use servo_url::ServoUrl;
-use versoview_messages::ToControllerMessage;
+use versoview_messages::{LifecycleState, Rotation, ToControllerMessage, Vsync};
 use webrender_api::{
     ScrollLocation,
     units::{DeviceIntPoint, DevicePoint, DeviceRect, DeviceSize, LayoutVector2D},
@@ -35,7 +40,7 @@ use webrender_api::{
 use winit::window::ResizeDirection;
 use winit::{
     dpi::{LogicalPosition, LogicalSize, PhysicalPosition},
-    event::{ElementState, Ime, TouchPhase, WindowEvent},
+    event::{DeviceId, ElementState, Ime, TouchPhase, WindowEvent},
     event_loop::ActiveEventLoop,
     keyboard::ModifiersState,
     window::{CursorIcon, Window as WinitWindow, WindowAttributes, WindowId},
@@ -43,12 +48,25 @@ use winit::{
 
 use crate::{
     bookmark::BookmarkManager,
+    command::{CommandAction, CommandRegistry, Keybinding},
     compositor::IOCompositor,
+    drag_drop::FileDrag,
     keyboard::keyboard_event_from_winit,
-    rendering::{RenderingContext, gl_config_picker},
+    pointer::Pointers,
+    power::RELEASE_WAKE_LOCKS_SCRIPT,
+    rendering::{self, RenderingContext, gl_config_picker},
+    rotation,
+    session::{SessionTab, SessionWindow},
     tab::TabManager,
     verso::{VersoInternalMsg, send_to_constellation},
-    webview::{Panel, WebView, execute_script, prompt::PromptSender, webview_menu::WebViewMenu},
+    webview::{
+        Panel, WebView,
+        context_menu::ContextMenuTarget,
+        execute_script,
+        find_bar::FindBar,
+        prompt::{DelegatedDialog, PromptDialog, PromptSender},
+        webview_menu::WebViewMenu,
+    },
 };
 
 use arboard::Clipboard;
@@ -57,16 +75,44 @@ const PANEL_HEIGHT: f64 = 50.0;
 const TAB_HEIGHT: f64 = 30.0;
 const BOOKMARK_HEIGHT: f64 = 30.0;
 const PANEL_PADDING: f64 = 4.0;
+/// Color of the window until Webrender draws its first frame.
+const PLACEHOLDER_COLOR: [f32; 4] = [0.94, 0.94, 0.94, 1.0];
+/// Page zoom of a double tap on the touchpad, relative to the default page zoom.
+const DOUBLE_TAP_ZOOM: f32 = 1.5;
 
 #[derive(Default)]
 pub(crate) struct EventListeners {
     /// This is `true` if the controller wants to get and handle OnNavigationStarting/AllowNavigationRequest
     pub(crate) on_navigation_starting: bool,
-    /// An id to request response sender map if the controller wants to get and handle web resource requests
-    pub(crate) on_web_resource_requested:
-        Option<HashMap<uuid::Uuid, (url::Url, IpcSender<WebResourceResponseMsg>)>>,
+    /// An id to request map if the controller wants to get and handle web resource requests, the
+    /// requests are handled by Verso if the controller doesn't answer them
+    pub(crate) on_web_resource_requested: Option<HashMap<uuid::Uuid, EmbedderMsg>>,
     /// This is `true` if the controller wants to get and handle WindowEvent::CloseRequested
     pub(crate) on_close_requested: bool,
+    /// This is `true` if the controller wants to get notified on page title changes
+    pub(crate) on_title_changed: bool,
+    /// This is `true` if the controller wants to get notified on favicon changes
+    pub(crate) on_favicon_changed: bool,
+    /// This is `true` if the controller wants to get notified on audio state changes
+    pub(crate) on_audio_state_changed: bool,
+    /// This is `true` if the controller wants to get notified on `theme-color` changes
+    pub(crate) on_theme_color_changed: bool,
+    /// This is `true` if the controller wants to get notified on hung scripts
+    pub(crate) on_script_unresponsive: bool,
+    /// This is `true` if the controller wants to get notified on exceeded resource budgets
+    pub(crate) on_resource_limit_exceeded: bool,
+    /// This is `true` if the controller wants to get notified on config changes
+    pub(crate) on_config_changed: bool,
+    /// This is `true` if the controller wants to get notified on the progress of downloads
+    pub(crate) on_download_updated: bool,
+    /// An id to dialog map if the controller shows the dialogs of webviews instead of Verso
+    pub(crate) on_dialog: Option<HashMap<uuid::Uuid, DelegatedDialog>>,
+    /// An id to page map of the form submissions waiting for the controller if it listens to them
+    pub(crate) on_form_submission: Option<HashMap<uuid::Uuid, IpcSender<PromptResponse>>>,
+    /// An id to request map of the HTTP authentication waiting for the controller if it listens
+    /// to them
+    pub(crate) on_authentication:
+        Option<HashMap<uuid::Uuid, IpcSender<Option<AuthenticationResponse>>>>,
 }
 
 #[derive(Debug, Default)]
@@ -91,6 +137,8 @@ pub struct Window {
     pub(crate) event_listeners: EventListeners,
     /// The mouse physical position in the web view.
     pub(crate) mouse_position: Cell<Option<PhysicalPosition<f64>>>,
+    /// Position and hover state of each pointer of the window
+    pointers: Pointers,
     /// Modifiers state of the keyboard.
     modifiers_state: Cell<ModifiersState>,
     /// State to indicate if the window is resizing.
@@ -103,12 +151,35 @@ pub struct Window {
     pub(crate) focused_webview_id: Option<WebViewId>,
     /// Window-wide menu. e.g. context menu(Wayland) and browsing history menu.
     pub(crate) webview_menu: Option<Box<dyn WebViewMenu>>,
+    /// What the page of the tab reported the context menu is opened on, until one of its items
+    /// is chosen
+    pub(crate) context_menu_target: RefCell<Option<(WebViewId, ContextMenuTarget)>>,
+    /// Files dragged over the window from other apps
+    pub(crate) file_drag: FileDrag,
+    /// Find bar over the current tab, opened with Ctrl/Cmd+F
+    pub(crate) find_bar: Option<FindBar>,
+    /// Commands which can be executed from the command palette
+    pub(crate) command_registry: CommandRegistry,
     /// Show the bookmark bar or not
     pub show_bookmark: bool,
     /// The reqwest client
     pub(crate) reqwest_client: Client,
     /// The sender for the Verso internal channel
     pub(crate) verso_internal_sender: IpcSender<VersoInternalMsg>,
+    /// Blank webviews created ahead of time, new tabs take one so they only have to navigate
+    warm_pool: Vec<WebView>,
+    /// How many blank webviews are kept in the warm pool
+    warm_pool_size: usize,
+    /// Tabs of the restored session, opened once the panel is loaded
+    restored_tabs: Vec<SessionTab>,
+    /// Index of the active tab in `restored_tabs`
+    restored_active_tab: usize,
+    /// Name to address the window with in the control protocol, kept in the session
+    pub(crate) name: Option<String>,
+    /// Whether the window is private, its panel is marked as such
+    pub(crate) private: bool,
+    /// Clockwise rotation of the output of the window
+    pub(crate) rotation: Rotation,
 }
 
 impl Window {
@@ -117,19 +188,29 @@ impl Window {
         evl: &ActiveEventLoop,
         window_attributes: WindowAttributes,
         verso_internal_sender: IpcSender<VersoInternalMsg>,
+        software_rendering: bool,
+        vsync: Vsync,
     ) -> (Self, RenderingContext) {
-        let template = ConfigTemplateBuilder::new()
-            .with_alpha_size(8)
-            .with_transparency(cfg!(macos));
-
-        let (window, gl_config) = DisplayBuilder::new()
-            .with_window_attributes(Some(window_attributes))
-            .build(evl, template, gl_config_picker)
-            .expect("Failed to create window and gl config");
-
-        let window = window.ok_or("Failed to create window").unwrap();
-
-        log::debug!("Picked a config with {} samples", gl_config.num_samples());
+        if software_rendering {
+            rendering::use_software_rasterizer();
+        }
+        let (window, rendering_context, surface) = match create_window_and_context(
+            evl,
+            window_attributes.clone(),
+            software_rendering,
+            vsync,
+        ) {
+            Ok(created) => created,
+            Err(e) if !software_rendering => {
+                log::warn!(
+                    "Failed to create rendering context, falling back to software rendering: {e}"
+                );
+                rendering::use_software_rasterizer();
+                create_window_and_context(evl, window_attributes, true, vsync)
+                    .expect("Failed to create software rendering context")
+            }
+            Err(e) => panic!("Failed to create software rendering context: {e}"),
+        };
 
         #[cfg(macos)]
         unsafe {
@@ -141,9 +222,6 @@ impl Window {
                 );
             }
         }
-        let (rendering_context, surface) =
-            RenderingContext::create(&window, &gl_config, window.inner_size())
-                .expect("Failed to create rendering context");
         log::trace!("Created rendering context for window {:?}", window);
 
         (
@@ -154,6 +232,7 @@ impl Window {
                 panel: None,
                 event_listeners: Default::default(),
                 mouse_position: Default::default(),
+                pointers: Pointers::default(),
                 modifiers_state: Cell::new(ModifiersState::default()),
                 resizing: false,
                 #[cfg(any(target_os = "macos", target_os = "windows"))]
@@ -161,14 +240,39 @@ impl Window {
                 tab_manager: TabManager::new(),
                 focused_webview_id: None,
                 webview_menu: None,
+                context_menu_target: RefCell::new(None),
+                file_drag: FileDrag::default(),
+                find_bar: None,
+                command_registry: CommandRegistry::new(),
                 show_bookmark: false,
                 reqwest_client: Client::new(),
                 verso_internal_sender,
+                warm_pool: Vec::new(),
+                warm_pool_size: 0,
+                restored_tabs: Vec::new(),
+                restored_active_tab: 0,
+                name: None,
+                private: false,
+                rotation: Rotation::default(),
             },
             rendering_context,
         )
     }
 
+    /// Paint the window with a plain color and show it, while Webrender and Servo are starting.
+    pub(crate) fn paint_placeholder(&self, rendering_context: &RenderingContext) {
+        let [red, green, blue, alpha] = PLACEHOLDER_COLOR;
+        if let Err(e) = rendering_context.make_gl_context_current(&self.surface) {
+            log::warn!("Failed to paint the placeholder of the window: {e}");
+            return;
+        }
+        rendering_context.gl.clear_color(red, green, blue, alpha);
+        rendering_context.gl.clear(gleam::gl::COLOR_BUFFER_BIT);
+        if let Err(e) = rendering_context.present(&self.surface) {
+            log::warn!("Failed to paint the placeholder of the window: {e}");
+        }
+    }
+
     /// Create a Verso window with the rendering context.
     pub fn new_with_compositor(
         evl: &ActiveEventLoop,
@@ -203,6 +307,7 @@ impl Window {
             // webview: None,
             event_listeners: Default::default(),
             mouse_position: Default::default(),
+            pointers: Pointers::default(),
             modifiers_state: Cell::new(ModifiersState::default()),
             resizing: false,
             #[cfg(any(target_os = "macos", target_os = "windows"))]
@@ -210,9 +315,20 @@ impl Window {
             tab_manager: TabManager::new(),
             focused_webview_id: None,
             webview_menu: None,
+            context_menu_target: RefCell::new(None),
+            file_drag: FileDrag::default(),
+            find_bar: None,
+            command_registry: CommandRegistry::new(),
             show_bookmark: false,
             reqwest_client: Client::new(),
             verso_internal_sender,
+            warm_pool: Vec::new(),
+            warm_pool_size: 0,
+            restored_tabs: Vec::new(),
+            restored_active_tab: 0,
+            name: None,
+            private: false,
+            rotation: Rotation::default(),
         };
         compositor.swap_current_window(&mut window);
         window
@@ -249,9 +365,7 @@ impl Window {
         initial_url: url::Url,
     ) {
         let hidpi_scale_factor = Scale::new(self.scale_factor() as f32);
-        let size = self.window.inner_size();
-        let size = Size2D::new(size.width as f32, size.height as f32);
-        let size = size.to_f32() / hidpi_scale_factor;
+        let size = self.size().to_f32() / hidpi_scale_factor;
         let viewport_details = ViewportDetails {
             size,
             hidpi_scale_factor,
@@ -271,26 +385,87 @@ impl Window {
         );
     }
 
+    /// Open the panel, or the tab without it, of a new window. The tabs of the restored window
+    /// replace the initial URL if there is one.
+    pub fn open_initial_tabs(
+        &mut self,
+        constellation_sender: &Sender<EmbedderToConstellationMessage>,
+        with_panel: bool,
+        initial_url: url::Url,
+        restored: Option<SessionWindow>,
+    ) {
+        if let Some(restored) = &restored {
+            if self.name.is_none() {
+                self.name.clone_from(&restored.name);
+            }
+        }
+        if with_panel {
+            if let Some(restored) = restored {
+                self.restored_tabs = restored.tabs;
+                self.restored_active_tab = restored.active_tab;
+            }
+            self.create_panel(constellation_sender, initial_url);
+            return;
+        }
+        // Without a tab strip, only the tab which was active is restored
+        let restored_tab = restored
+            .and_then(|window| window.tabs.into_iter().nth(window.active_tab))
+            .and_then(|tab| Some((ServoUrl::parse(&tab.url).ok()?, tab)));
+        match restored_tab {
+            Some((url, tab)) => {
+                let tab_id = self.create_tab(constellation_sender, url);
+                self.restore_tab(tab_id, &tab);
+            }
+            None => {
+                self.create_tab(constellation_sender, initial_url.into());
+            }
+        }
+    }
+
+    /// Whether the tabs of a restored window are waiting for the panel to open them
+    pub fn has_restored_tabs(&self) -> bool {
+        !self.restored_tabs.is_empty()
+    }
+
+    /// Open the tabs of the restored window and activate the one which was active
+    pub fn open_restored_tabs(&mut self, compositor: &mut IOCompositor) {
+        let tabs = std::mem::take(&mut self.restored_tabs);
+        let mut tab_ids = Vec::new();
+        for tab in tabs {
+            let Ok(url) = ServoUrl::parse(&tab.url) else {
+                continue;
+            };
+            let tab_id = self.create_tab(&compositor.constellation_chan, url);
+            self.restore_tab(tab_id, &tab);
+            tab_ids.push(tab_id);
+        }
+        if let Some(tab_id) = tab_ids.get(self.restored_active_tab) {
+            self.select_tab(compositor, *tab_id);
+        }
+    }
+
+    fn restore_tab(&mut self, tab_id: WebViewId, tab: &SessionTab) {
+        if let Some(restored) = self.tab_manager.tab_mut(tab_id) {
+            restored.set_name(tab.name.clone());
+            if tab.scroll_x != 0.0 || tab.scroll_y != 0.0 {
+                restored.set_restore_scroll(LayoutVector2D::new(tab.scroll_x, tab.scroll_y));
+            }
+        }
+    }
+
     /// Create a new webview and send the constellation message to load the initial URL
     pub fn create_tab(
         &mut self,
         constellation_sender: &Sender<EmbedderToConstellationMessage>,
         initial_url: ServoUrl,
-    ) {
-        let webview_id = WebViewId::new();
-        let size = self.size().to_f32();
-        let rect = DeviceRect::from_size(size);
-
+    ) -> WebViewId {
         let show_tab = self.tab_manager.count() >= 1;
-        let content_size = self.get_content_size(rect, show_tab, self.show_bookmark);
-
-        let hidpi_scale_factor = Scale::new(self.scale_factor() as f32);
-        let size = content_size.size().to_f32() / hidpi_scale_factor;
-        let viewport_details = ViewportDetails {
-            size,
-            hidpi_scale_factor,
-        };
+        let (content_size, viewport_details) = self.tab_viewport(show_tab);
 
+        let pooled = self.warm_pool.pop();
+        let webview_id = pooled
+            .as_ref()
+            .map_or_else(WebViewId::new, |webview| webview.webview_id);
         let mut webview = WebView::new(webview_id, viewport_details);
         webview.set_size(content_size);
 
@@ -306,11 +481,85 @@ impl Window {
 
         self.tab_manager.append_tab(webview, true);
 
-        send_to_constellation(
-            constellation_sender,
-            EmbedderToConstellationMessage::NewWebView(initial_url, webview_id, viewport_details),
-        );
+        if let Some(pooled) = pooled {
+            if pooled.rect != content_size {
+                send_to_constellation(
+                    constellation_sender,
+                    EmbedderToConstellationMessage::ChangeViewportDetails(
+                        webview_id,
+                        viewport_details,
+                        WindowSizeType::Resize,
+                    ),
+                );
+            }
+            send_to_constellation(
+                constellation_sender,
+                EmbedderToConstellationMessage::LoadUrl(webview_id, initial_url),
+            );
+            self.fill_warm_pool(constellation_sender);
+        } else {
+            send_to_constellation(
+                constellation_sender,
+                EmbedderToConstellationMessage::NewWebView(
+                    initial_url,
+                    webview_id,
+                    viewport_details,
+                ),
+            );
+        }
         log::debug!("Verso Window {:?} adds webview {}", self.id(), webview_id);
+        webview_id
+    }
+
+    /// Area and viewport of a tab, with or without the tab bar.
+    fn tab_viewport(&self, show_tab: bool) -> (DeviceRect, ViewportDetails) {
+        let rect = DeviceRect::from_size(self.size().to_f32());
+        let content_size = self.get_content_size(rect, show_tab, self.show_bookmark);
+
+        let hidpi_scale_factor = Scale::new(self.scale_factor() as f32);
+        let size = content_size.size().to_f32() / hidpi_scale_factor;
+        let viewport_details = ViewportDetails {
+            size,
+            hidpi_scale_factor,
+        };
+        (content_size, viewport_details)
+    }
+
+    /// Keep this many blank webviews ready for new tabs, the missing ones are created now.
+    pub(crate) fn set_warm_pool_size(
+        &mut self,
+        constellation_sender: &Sender<EmbedderToConstellationMessage>,
+        size: usize,
+    ) {
+        self.warm_pool_size = size;
+        while self.warm_pool.len() > size {
+            let webview = self.warm_pool.pop().unwrap();
+            send_to_constellation(
+                constellation_sender,
+                EmbedderToConstellationMessage::CloseWebView(webview.webview_id),
+            );
+        }
+        self.fill_warm_pool(constellation_sender);
+    }
+
+    /// Create blank webviews until the warm pool is full. They're sized for a window with a tab
+    /// bar, since they're used for the tabs opened after the first one.
+    fn fill_warm_pool(&mut self, constellation_sender: &Sender<EmbedderToConstellationMessage>) {
+        while self.warm_pool.len() < self.warm_pool_size {
+            let (content_size, viewport_details) = self.tab_viewport(true);
+            let webview_id = WebViewId::new();
+            let mut webview = WebView::new(webview_id, viewport_details);
+            webview.set_size(content_size);
+            send_to_constellation(
+                constellation_sender,
+                EmbedderToConstellationMessage::NewWebView(
+                    ServoUrl::parse("about:blank").unwrap(),
+                    webview_id,
+                    viewport_details,
+                ),
+            );
+            self.warm_pool.push(webview);
+        }
     }
 
     /// Close a tab
@@ -349,6 +598,10 @@ impl Window {
         tab_id: WebViewId,
         show_tab: bool,
     ) {
+        // The find bar searches the current tab, it's closed with the tab's highlights
+        if self.tab_manager.current_tab_id() != Some(tab_id) {
+            self.close_find_bar(&compositor.constellation_chan);
+        }
         let size = self.size().to_f32();
         let rect = DeviceRect::from_size(size);
         let content_size = self.get_content_size(rect, show_tab, self.show_bookmark);
@@ -362,14 +615,35 @@ impl Window {
 
             let old_tab_id = self.tab_manager.current_tab_id();
             if self.tab_manager.activate_tab(tab_id).is_some() {
+                // the titlebar follows the theme color of the current tab
+                let _ = self
+                    .verso_internal_sender
+                    .send(VersoInternalMsg::UpdateTitlebarColor(tab_id));
+                // the old tab is no longer visible, so it loses its wake locks
+                if let Some(old_tab_id) = old_tab_id.filter(|old_tab_id| *old_tab_id != tab_id) {
+                    self.release_wake_locks(&compositor.constellation_chan, old_tab_id);
+                }
                 // throttle the old tab to avoid unnecessary animation caclulations
-                if let Some(old_tab_id) = old_tab_id {
+                if let Some(old_tab_id) = old_tab_id.filter(|old_tab_id| {
+                    self.tab_manager
+                        .tab(*old_tab_id)
+                        .is_some_and(|tab| tab.lifecycle_state().is_none())
+                }) {
                     let _ = compositor.constellation_chan.send(
                         EmbedderToConstellationMessage::SetWebViewThrottled(old_tab_id, true),
                     );
                 }
+                // and unthrottle the new one, unless the controller keeps it hidden
+                let hidden_by_controller = self
+                    .tab_manager
+                    .tab(tab_id)
+                    .and_then(|tab| tab.lifecycle_state())
+                    .is_some_and(|state| state != LifecycleState::Visible);
                 let _ = compositor.constellation_chan.send(
-                    EmbedderToConstellationMessage::SetWebViewThrottled(tab_id, false),
+                    EmbedderToConstellationMessage::SetWebViewThrottled(
+                        tab_id,
+                        hidden_by_controller,
+                    ),
                 );
 
                 self.focused_webview_id = Some(tab_id);
@@ -396,13 +670,174 @@ impl Window {
         }
     }
 
-    /// Handle Winit window event and return a boolean to indicate if the compositor should repaint immediately.
+    /// Activate the tab after the current one in the tab strip, or before it if `forward` is
+    /// false, and select it in the panel
+    pub fn activate_adjacent_tab(&mut self, compositor: &mut IOCompositor, forward: bool) {
+        let Some(tab_id) = self.tab_manager.adjacent_tab_id(forward) else {
+            return;
+        };
+        if self.tab_manager.current_tab_id() != Some(tab_id) {
+            self.select_tab(compositor, tab_id);
+        }
+    }
+
+    /// Activate the tab and select it in the panel
+    pub fn select_tab(&mut self, compositor: &mut IOCompositor, tab_id: WebViewId) {
+        if let Some(panel) = &self.panel {
+            let cmd: String = format!(
+                "window.navbar.activateTab('{}')",
+                serde_json::to_string(&tab_id).unwrap()
+            );
+            let _ = execute_script(
+                &compositor.constellation_chan,
+                &panel.webview.webview_id,
+                cmd,
+            );
+        }
+        self.activate_tab(compositor, tab_id, self.tab_manager.count() > 1);
+    }
+
+    /// Move the current tab by `offset` positions in the tab strip
+    pub fn move_current_tab(
+        &mut self,
+        sender: &Sender<EmbedderToConstellationMessage>,
+        offset: isize,
+    ) {
+        let Some(tab_id) = self.tab_manager.current_tab_id() else {
+            return;
+        };
+        let Some(index) = self.tab_manager.tab_index(tab_id) else {
+            return;
+        };
+        let Ok(index) = self
+            .tab_manager
+            .move_tab(tab_id, index.saturating_add_signed(offset))
+        else {
+            return;
+        };
+        if let Some(panel) = &self.panel {
+            let cmd: String = format!(
+                "window.navbar.moveTab('{}', {index})",
+                serde_json::to_string(&tab_id).unwrap()
+            );
+            let _ = execute_script(sender, &panel.webview.webview_id, cmd);
+        }
+    }
+
+    /// Mute or unmute all media elements of a tab
+    pub fn set_tab_muted(
+        &mut self,
+        sender: &Sender<EmbedderToConstellationMessage>,
+        to_controller_sender: &Option<IpcSender<ToControllerMessage>>,
+        tab_id: WebViewId,
+        muted: bool,
+    ) {
+        let Some(tab) = self.tab_manager.tab_mut(tab_id) else {
+            return;
+        };
+        tab.set_muted(muted);
+        let _ = execute_script(sender, &tab_id, mute_media_script(muted));
+        self.notify_audio_state_changed(sender, to_controller_sender, tab_id);
+    }
+
+    /// Set the lifecycle state of a tab, dispatch its events and throttle it unless it's visible
+    pub fn set_tab_lifecycle_state(
+        &mut self,
+        sender: &Sender<EmbedderToConstellationMessage>,
+        tab_id: WebViewId,
+        state: LifecycleState,
+    ) {
+        let Some(tab) = self.tab_manager.tab_mut(tab_id) else {
+            return;
+        };
+        tab.set_lifecycle_state(state);
+        send_to_constellation(
+            sender,
+            EmbedderToConstellationMessage::SetWebViewThrottled(
+                tab_id,
+                state != LifecycleState::Visible,
+            ),
+        );
+        let _ = execute_script(sender, &tab_id, lifecycle_state_script(state));
+        if state != LifecycleState::Visible {
+            self.release_wake_locks(sender, tab_id);
+        }
+    }
+
+    /// Release the screen wake locks of a tab which is no longer visible
+    pub(crate) fn release_wake_locks(
+        &self,
+        sender: &Sender<EmbedderToConstellationMessage>,
+        tab_id: WebViewId,
+    ) {
+        let _ = execute_script(sender, &tab_id, RELEASE_WAKE_LOCKS_SCRIPT);
+        let _ = self
+            .verso_internal_sender
+            .send(VersoInternalMsg::SetWakeLock(tab_id, false));
+    }
+
+    /// Notify the panel and the controller about the audio state of a tab
+    pub(crate) fn notify_audio_state_changed(
+        &self,
+        sender: &Sender<EmbedderToConstellationMessage>,
+        to_controller_sender: &Option<IpcSender<ToControllerMessage>>,
+        tab_id: WebViewId,
+    ) {
+        let Some(tab) = self.tab_manager.tab(tab_id) else {
+            return;
+        };
+        let (audible, muted) = (tab.is_audible(), tab.is_muted());
+
+        if let Some(panel) = &self.panel {
+            let script = format!(
+                "window.navbar.setTabAudioState?.('{}', {audible}, {muted})",
+                serde_json::to_string(&tab_id).unwrap()
+            );
+            let _ = execute_script(sender, &panel.webview.webview_id, script);
+        }
+
+        if let Some(to_controller_sender) = to_controller_sender {
+            if self.event_listeners.on_audio_state_changed {
+                if let Err(error) =
+                    to_controller_sender.send(ToControllerMessage::OnAudioStateChanged(
+                        bincode::serialize(&tab_id).unwrap(),
+                        audible,
+                        muted,
+                    ))
+                {
+                    log::error!("Verso failed to send OnAudioStateChanged to controller: {error}")
+                }
+            }
+        }
+    }
+
+    /// Tab zoomed by a touchpad gesture, the one under the pointer of the touchpad or else the
+    /// current tab.
+    fn gesture_tab_id(&self, device_id: DeviceId) -> Option<WebViewId> {
+        self.pointers
+            .get(device_id)
+            .and_then(|pointer| pointer.hovered)
+            .filter(|webview_id| self.tab_manager.tab(*webview_id).is_some())
+            .or_else(|| self.tab_manager.current_tab_id())
+    }
+
+    /// Position of the pointer of the device, or of the pointer moved last if the device has no
+    /// pointer of its own, e.g. a touchpad sending gestures.
+    fn pointer_position(&self, device_id: DeviceId) -> Option<PhysicalPosition<f64>> {
+        self.pointers
+            .get(device_id)
+            .map(|pointer| pointer.position)
+            .or_else(|| self.mouse_position.get())
+    }
+
+    /// Handle Winit window event, returning what's left to do of the command its keyboard shortcut executes.
     pub fn handle_winit_window_event(
         &mut self,
         sender: &Sender<EmbedderToConstellationMessage>,
+        to_controller_sender: &Option<IpcSender<ToControllerMessage>>,
         compositor: &mut IOCompositor,
         event: &winit::event::WindowEvent,
-    ) {
+    ) -> CommandAction {
         match event {
             WindowEvent::RedrawRequested => {
                 if compositor.ready_to_present {
@@ -414,6 +849,7 @@ impl Window {
                 }
             }
             WindowEvent::Focused(focused) => {
+                compositor.set_window_in_background(self, !*focused);
                 if *focused {
                     compositor.swap_current_window(self);
                 }
@@ -422,26 +858,50 @@ impl Window {
                 if self.window.has_focus() {
                     self.resizing = true;
                 }
-                let size = Size2D::new(size.width, size.height);
-                compositor.resize(size.to_f32(), self);
+                let size = Size2D::new(size.width as f32, size.height as f32);
+                compositor.resize(rotation::rotate_size(self.rotation, size), self);
             }
             WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
                 compositor.on_scale_factor_event(*scale_factor as f32, self);
             }
+            WindowEvent::HoveredFile(path) => {
+                compositor.swap_current_window(self);
+                self.file_hovered(compositor, sender, path);
+            }
+            WindowEvent::HoveredFileCancelled => {
+                self.file_drag_cancelled(sender);
+            }
+            WindowEvent::DroppedFile(path) => {
+                self.file_dropped(compositor, sender, path);
+            }
             WindowEvent::CursorEntered { .. } => {
                 compositor.swap_current_window(self);
             }
-            WindowEvent::CursorLeft { .. } => {
-                self.mouse_position.set(None);
+            WindowEvent::CursorLeft { device_id } => {
+                let position = self
+                    .pointers
+                    .left(*device_id)
+                    .map(|pointer| pointer.position);
+                self.mouse_position.set(position);
             }
-            WindowEvent::CursorMoved { position, .. } => {
-                let point: DevicePoint = DevicePoint::new(position.x as f32, position.y as f32);
+            WindowEvent::CursorMoved {
+                device_id,
+                position,
+            } => {
+                let point = self.content_point(*position);
                 self.mouse_position.set(Some(*position));
-                let webview_id = match self.focused_webview_id {
+                if self.file_drag.is_active() {
+                    self.file_drag_moved(compositor, sender);
+                    return CommandAction::None;
+                }
+                // Every pointer hovers the webview under it, whichever webview is focused
+                let hovered = compositor.webview_id_from_point(point);
+                self.pointers.moved(*device_id, *position, hovered);
+                let webview_id = match hovered.or(self.focused_webview_id) {
                     Some(webview_id) => webview_id,
                     None => {
-                        log::trace!("No focused webview, skipping MouseInput event.");
-                        return;
+                        log::trace!("No webview under the pointer, skipping MouseMove event.");
+                        return CommandAction::None;
                     }
                 };
 
@@ -461,12 +921,16 @@ impl Window {
                     }
                 }
             }
-            WindowEvent::MouseInput { state, button, .. } => {
-                let point = match self.mouse_position.get() {
-                    Some(point) => Point2D::new(point.x as f32, point.y as f32),
+            WindowEvent::MouseInput {
+                device_id,
+                state,
+                button,
+            } => {
+                let point = match self.pointer_position(*device_id) {
+                    Some(position) => self.content_point(position),
                     None => {
                         log::trace!("Mouse position is None, skipping MouseInput event.");
-                        return;
+                        return CommandAction::None;
                     }
                 };
 
@@ -474,7 +938,7 @@ impl Window {
                 if let (ElementState::Pressed, winit::event::MouseButton::Right) = (state, button) {
                     let prompt = self.tab_manager.current_prompt();
                     if prompt.is_some() {
-                        return;
+                        return CommandAction::None;
                     }
                 }
 
@@ -499,7 +963,7 @@ impl Window {
                         log::trace!(
                             "Verso Window isn't supporting this mouse button yet: {button:?}"
                         );
-                        return;
+                        return CommandAction::None;
                     }
                 };
 
@@ -521,7 +985,7 @@ impl Window {
 
                 let Some(webview_id) = &compositor.webview_id_from_point(point) else {
                     log::trace!("No webview at point, skipping MouseInput event.");
-                    return;
+                    return CommandAction::None;
                 };
 
                 forward_input_event(
@@ -546,15 +1010,73 @@ impl Window {
                     );
                 }
             }
-            WindowEvent::PinchGesture { delta, .. } => {
-                compositor.on_zoom_window_event(1.0 + *delta as f32, self);
+            WindowEvent::PinchGesture {
+                device_id, delta, ..
+            } => {
+                if let Some(tab_id) = self.gesture_tab_id(*device_id) {
+                    let page_zoom = compositor.page_zoom(tab_id) * (1.0 + *delta as f32);
+                    self.zoom_tab(compositor, tab_id, Some(page_zoom));
+                }
+            }
+            WindowEvent::DoubleTapGesture { device_id } => {
+                // Smart zoom of the touchpad zooms in, or back out if the page is zoomed
+                if let Some(tab_id) = self.gesture_tab_id(*device_id) {
+                    let default_page_zoom = compositor.default_page_zoom();
+                    let page_zoom = (compositor.page_zoom(tab_id) == default_page_zoom)
+                        .then_some(default_page_zoom * DOUBLE_TAP_ZOOM);
+                    self.zoom_tab(compositor, tab_id, page_zoom);
+                }
+            }
+            WindowEvent::RotationGesture { .. } => {
+                // Pages can't be rotated, the gesture is only taken from the page so it doesn't
+                // fall back to scrolling
+            }
+            WindowEvent::PanGesture {
+                device_id,
+                delta,
+                phase,
+            } => {
+                let Some(position) = self.pointer_position(*device_id) else {
+                    log::trace!("Mouse position is None, skipping PanGesture event.");
+                    return CommandAction::None;
+                };
+                let point = self.content_point(position);
+                let delta = delta.to_logical::<f32>(self.window.scale_factor());
+                compositor.on_scroll_event(
+                    ScrollLocation::Delta(LayoutVector2D::new(delta.x, delta.y)),
+                    DeviceIntPoint::new(point.x as i32, point.y as i32),
+                    touch_event_type(*phase),
+                );
+            }
+            WindowEvent::Touch(touch) => {
+                // Touches don't move the pointers, and touch screens get touch ids of their own
+                let ended = matches!(touch.phase, TouchPhase::Ended | TouchPhase::Cancelled);
+                let touch_id = self.pointers.touch_id(touch.device_id, touch.id, ended);
+                let point = self.content_point(touch.location);
+                let Some(webview_id) = compositor.webview_id_from_point(point) else {
+                    log::trace!("No webview at point, skipping Touch event.");
+                    return CommandAction::None;
+                };
+                let event = TouchEvent::new(touch_event_type(touch.phase), touch_id, point);
+                // Pinching with two fingers zooms the tab like the touchpad does
+                let magnification = compositor.on_touch_event(webview_id, event);
+                if let Some(magnification) = magnification {
+                    if self.tab_manager.tab(webview_id).is_some() {
+                        let page_zoom = compositor.page_zoom(webview_id) * magnification;
+                        self.zoom_tab(compositor, webview_id, Some(page_zoom));
+                    }
+                }
             }
-            WindowEvent::MouseWheel { delta, phase, .. } => {
-                let point = match self.mouse_position.get() {
-                    Some(point) => point,
+            WindowEvent::MouseWheel {
+                device_id,
+                delta,
+                phase,
+            } => {
+                let point = match self.pointer_position(*device_id) {
+                    Some(position) => self.content_point(position),
                     None => {
                         log::trace!("Mouse position is None, skipping MouseWheel event.");
-                        return;
+                        return CommandAction::None;
                     }
                 };
 
@@ -579,17 +1101,10 @@ impl Window {
                     y = 0.0;
                 }
 
-                let phase: TouchEventType = match phase {
-                    TouchPhase::Started => TouchEventType::Down,
-                    TouchPhase::Moved => TouchEventType::Move,
-                    TouchPhase::Ended => TouchEventType::Up,
-                    TouchPhase::Cancelled => TouchEventType::Cancel,
-                };
-
                 compositor.on_scroll_event(
                     ScrollLocation::Delta(LayoutVector2D::new(x as f32, y as f32)),
                     DeviceIntPoint::new(point.x as i32, point.y as i32),
-                    phase,
+                    touch_event_type(*phase),
                 );
             }
             WindowEvent::ModifiersChanged(modifier) => self.modifiers_state.set(modifier.state()),
@@ -598,7 +1113,7 @@ impl Window {
                     Some(webview_id) => webview_id,
                     None => {
                         log::trace!("No focused webview, skipping Ime event.");
-                        return;
+                        return CommandAction::None;
                     }
                 };
                 if !self.has_webview(webview_id) {
@@ -606,7 +1121,7 @@ impl Window {
                         "Webview {:?} doesn't exist, skipping Ime event.",
                         webview_id
                     );
-                    return;
+                    return CommandAction::None;
                 }
 
                 match event {
@@ -662,7 +1177,7 @@ impl Window {
                     Some(webview_id) => webview_id,
                     None => {
                         log::trace!("No focused webview, skipping KeyboardInput event.");
-                        return;
+                        return CommandAction::None;
                     }
                 };
                 if !self.has_webview(webview_id) {
@@ -670,78 +1185,60 @@ impl Window {
                         "Webview {:?} doesn't exist, skipping KeyboardInput event.",
                         webview_id
                     );
-                    return;
+                    return CommandAction::None;
                 }
                 let event = keyboard_event_from_winit(event, self.modifiers_state.get());
                 log::trace!("Verso is handling {:?}", event);
 
                 /* Window operation keyboard shortcut */
-                if self.handle_keyboard_shortcut(compositor, &event) {
-                    return;
+                if let Some(action) =
+                    self.handle_keyboard_shortcut(compositor, to_controller_sender, &event)
+                {
+                    return action;
                 }
                 forward_input_event(compositor, webview_id, sender, InputEvent::Keyboard(event));
             }
             e => log::trace!("Verso Window isn't supporting this window event yet: {e:?}"),
         }
+        CommandAction::None
     }
 
     /// Handle Window keyboard shortcut
     ///
-    /// - Returns `true` if the event is handled, then we should skip sending it to constellation
+    /// - Returns what's left to do of the command if the event is handled, then we should skip sending it to constellation
     fn handle_keyboard_shortcut(
         &mut self,
         compositor: &mut IOCompositor,
+        to_controller_sender: &Option<IpcSender<ToControllerMessage>>,
         event: &KeyboardEvent,
-    ) -> bool {
-        let is_macos = cfg!(target_os = "macos");
-        let control_or_meta = if is_macos {
-            Modifiers::META
-        } else {
-            Modifiers::CONTROL
-        };
+    ) -> Option<CommandAction> {
+        if event.state != KeyState::Down {
+            return None;
+        }
 
-        if event.state == KeyState::Down {
-            // TODO: New Window, Close Browser
-            match (event.modifiers, event.code) {
-                (modifiers, Code::KeyT) if modifiers == control_or_meta => {
-                    (*self).create_tab(
-                        &compositor.constellation_chan,
-                        ServoUrl::parse("https://example.com").unwrap(),
-                    );
-                    return true;
-                }
-                (modifiers, Code::KeyW) if modifiers == control_or_meta => {
-                    if let Some(tab_id) = self.tab_manager.current_tab_id() {
-                        (*self).close_tab(compositor, tab_id);
-                    }
-                    return true;
-                }
-                (modifiers, Code::KeyL) if modifiers == control_or_meta => {
-                    // focus on navigation input
-                    if let Some(panel) = &self.panel {
-                        let webview_id = &panel.webview.webview_id;
+        // TODO: Close Browser
+        let keybinding = Keybinding::new(event.modifiers, event.code);
+        let command = self.command_registry.command_for_keybinding(keybinding)?;
+        let id = command.id.clone();
+        Some(self.execute_command(&id, compositor, to_controller_sender))
+    }
 
-                        let _ = compositor.constellation_chan.send(
-                            EmbedderToConstellationMessage::FocusWebView(webview_id.clone()),
-                        );
+    /// Focus on the navigation input of the panel
+    pub(crate) fn focus_url_input(&self, sender: &Sender<EmbedderToConstellationMessage>) {
+        if let Some(panel) = &self.panel {
+            let webview_id = &panel.webview.webview_id;
 
-                        let _ = execute_script(
-                            &compositor.constellation_chan,
-                            webview_id,
-                            "window.navbar.focusUrlInput()".to_string(),
-                        );
-                    }
-                    return true;
-                }
+            let _ = sender.send(EmbedderToConstellationMessage::FocusWebView(*webview_id));
 
-                _ => (),
-            }
+            let _ = execute_script(
+                sender,
+                webview_id,
+                "window.navbar.focusUrlInput()".to_string(),
+            );
         }
-
-        false
     }
 
-    /// Handle servo messages. Return true if it requests a new window
+    /// Handle servo messages, returning what's left to do of the command it executes.
     pub fn handle_servo_message(
         &mut self,
         webview_id: WebViewId,
@@ -751,10 +1248,10 @@ impl Window {
         clipboard: Option<&mut Clipboard>,
         compositor: &mut IOCompositor,
         bookmark_manager: &mut BookmarkManager,
-    ) -> bool {
+    ) -> CommandAction {
         if let EmbedderMsg::SetCursor(_, cursor) = message {
             self.set_cursor_icon(cursor);
-            return false;
+            return CommandAction::None;
         }
 
         // Handle message in Verso Panel
@@ -764,6 +1261,7 @@ impl Window {
                     webview_id,
                     message,
                     sender.clone(),
+                    to_controller_sender,
                     clipboard,
                     compositor,
                     bookmark_manager,
@@ -772,17 +1270,27 @@ impl Window {
         }
         if let Some(webview_menu) = &self.webview_menu {
             if webview_menu.webview().webview_id == webview_id {
-                self.handle_servo_messages_with_webview_menu(
-                    webview_id, message, &sender, clipboard, compositor,
+                return self.handle_servo_messages_with_webview_menu(
+                    webview_id,
+                    message,
+                    &sender,
+                    to_controller_sender,
+                    clipboard,
+                    compositor,
                 );
-                return false;
+            }
+        }
+        if let Some(find_bar) = &self.find_bar {
+            if find_bar.webview.webview_id == webview_id {
+                self.handle_servo_messages_with_find_bar(webview_id, message, &sender);
+                return CommandAction::None;
             }
         }
         if self.tab_manager.has_prompt(webview_id) {
             self.handle_servo_messages_with_prompt(
                 webview_id, message, &sender, clipboard, compositor,
             );
-            return false;
+            return CommandAction::None;
         }
 
         // Handle message in Verso WebView
@@ -793,8 +1301,7 @@ impl Window {
             to_controller_sender,
             clipboard,
             compositor,
-        );
-        false
+        )
     }
 
     /// Queues a Winit `WindowEvent::RedrawRequested` event to be emitted that aligns with the windowing system drawing loop.
@@ -805,7 +1312,33 @@ impl Window {
     /// Size of the window that's used by webrender.
     pub fn size(&self) -> DeviceSize {
         let size = self.window.inner_size();
-        Size2D::new(size.width as f32, size.height as f32)
+        rotation::rotate_size(
+            self.rotation,
+            Size2D::new(size.width as f32, size.height as f32),
+        )
+    }
+
+    /// Point of the rotated content under a position of the window, e.g. of the cursor.
+    pub(crate) fn content_point(&self, position: PhysicalPosition<f64>) -> DevicePoint {
+        let size = self.window.inner_size();
+        rotation::output_to_content(
+            self.rotation,
+            DevicePoint::new(position.x as f32, position.y as f32),
+            Size2D::new(size.width as f32, size.height as f32),
+        )
+    }
+
+    /// Rotate the output of the window clockwise, and lay its webviews out again for the rotated
+    /// size.
+    pub(crate) fn set_rotation(&mut self, rotation: Rotation, compositor: &mut IOCompositor) {
+        if self.rotation == rotation {
+            return;
+        }
+        self.rotation = rotation;
+        // Other windows are laid out again when the compositor swaps to them
+        if compositor.current_window == self.id() {
+            compositor.resize(self.size(), self);
+        }
     }
 
     /// Size of the window, including the window decorations.
@@ -838,6 +1371,14 @@ impl Window {
             return true;
         }
 
+        if self
+            .find_bar
+            .as_ref()
+            .is_some_and(|find_bar| find_bar.webview.webview_id == id)
+        {
+            return true;
+        }
+
         if let Some(panel) = &self.panel {
             if panel.webview.webview_id == id {
                 return true;
@@ -872,6 +1413,14 @@ impl Window {
             return (Some(prompt.webview().clone()), false);
         }
 
+        if self
+            .find_bar
+            .as_ref()
+            .is_some_and(|find_bar| find_bar.webview.webview_id == id)
+        {
+            return (self.find_bar.take().map(|find_bar| find_bar.webview), false);
+        }
+
         if self
             .panel
             .as_ref()
@@ -879,6 +1428,7 @@ impl Window {
             .is_some()
         {
             // Removing panel, remove all webviews and shut down the compositor
+            self.set_warm_pool_size(&compositor.constellation_chan, 0);
             let tab_ids = self.tab_manager.tab_ids();
             for tab_id in tab_ids {
                 send_to_constellation(
@@ -889,9 +1439,13 @@ impl Window {
             (self.panel.take().map(|panel| panel.webview), false)
         } else if let Ok(tab) = self.tab_manager.close_tab(id) {
             let close_window = self.tab_manager.count() == 0 || self.panel.is_none();
+            if close_window {
+                self.set_warm_pool_size(&compositor.constellation_chan, 0);
+            }
             if self.focused_webview_id == Some(id) {
                 self.focused_webview_id = None;
             }
+            self.pointers.remove_webview(id);
             (Some(tab.webview().clone()), close_window)
         } else {
             (None, false)
@@ -909,6 +1463,10 @@ impl Window {
             order.push(tab.webview());
         }
 
+        if let Some(find_bar) = &self.find_bar {
+            order.push(&find_bar.webview);
+        }
+
         if let Some(webview_menu) = &self.webview_menu {
             order.push(webview_menu.webview());
         }
@@ -1032,8 +1590,101 @@ impl Window {
     }
 }
 
+/// Script to mute or unmute every media element of a page,
+/// including the ones added to the document later on.
+pub(crate) fn mute_media_script(muted: bool) -> String {
+    format!(
+        "(() => {{
+            window.__versoMuted = {muted};
+            const apply = () => document
+                .querySelectorAll('audio, video')
+                .forEach((media) => (media.muted = window.__versoMuted));
+            apply();
+            if (!window.__versoMuteObserver) {{
+                window.__versoMuteObserver = new MutationObserver(apply);
+                window.__versoMuteObserver.observe(document.documentElement, {{
+                    childList: true,
+                    subtree: true,
+                }});
+            }}
+        }})()"
+    )
+}
+
+/// Script to report the lifecycle state to the page and dispatch the events of the transition
+pub(crate) fn lifecycle_state_script(state: LifecycleState) -> String {
+    let state = match state {
+        LifecycleState::Visible => "visible",
+        LifecycleState::Hidden => "hidden",
+        LifecycleState::Frozen => "frozen",
+    };
+    format!(
+        "(() => {{
+            const state = '{state}';
+            const previous = window.__versoLifecycleState || 'visible';
+            window.__versoLifecycleState = state;
+            const visibility = (state) => (state === 'visible' ? 'visible' : 'hidden');
+            Object.defineProperty(document, 'visibilityState', {{
+                get: () => visibility(state),
+                configurable: true,
+            }});
+            Object.defineProperty(document, 'hidden', {{
+                get: () => state !== 'visible',
+                configurable: true,
+            }});
+            if (previous === 'frozen' && state !== 'frozen') {{
+                document.dispatchEvent(new Event('resume'));
+            }}
+            if (visibility(previous) !== visibility(state)) {{
+                document.dispatchEvent(new Event('visibilitychange'));
+            }}
+            if (state === 'frozen' && previous !== 'frozen') {{
+                document.dispatchEvent(new Event('freeze'));
+            }}
+        }})()"
+    )
+}
+
 // Prompt methods
 impl Window {
+    /// Ask the user whether to stop the tab whose script is hung or to wait. The answer is sent
+    /// with [`VersoInternalMsg::SlowScriptDialogResponse`]. Returns `false` if the tab already
+    /// shows a dialog.
+    pub(crate) fn show_slow_script_dialog(
+        &mut self,
+        sender: &Sender<EmbedderToConstellationMessage>,
+        tab_id: WebViewId,
+    ) -> bool {
+        let Some(rect) = self
+            .tab_manager
+            .tab(tab_id)
+            .filter(|tab| !tab.has_prompt())
+            .map(|tab| tab.webview().rect)
+        else {
+            return false;
+        };
+        let (confirm_sender, confirm_receiver) = ipc::channel::<ConfirmResponse>().unwrap();
+        let verso_internal_sender = self.verso_internal_sender.clone();
+        ROUTER.add_typed_route(
+            confirm_receiver,
+            Box::new(move |result| {
+                let stop = matches!(result, Ok(ConfirmResponse::Ok));
+                let _ = verso_internal_sender
+                    .send(VersoInternalMsg::SlowScriptDialogResponse(tab_id, stop));
+            }),
+        );
+        let mut prompt = PromptDialog::new();
+        prompt.ok_cancel(
+            sender,
+            rect,
+            self.scale_factor() as f32,
+            "This page is not responding. Press OK to stop it, or Cancel to wait.".to_string(),
+            confirm_sender,
+        );
+        self.tab_manager.set_prompt(tab_id, prompt);
+        true
+    }
+
     /// Close window's prompt dialog
     pub(crate) fn close_prompt_dialog(&mut self, tab_id: WebViewId) {
         if let Some(sender) = self
@@ -1183,6 +1834,16 @@ pub unsafe fn decorate_window(view: *mut AnyObject, _position: LogicalPosition<f
     );
 }
 
+/// Touch event type of the phase of a winit touch, scroll or gesture.
+fn touch_event_type(phase: TouchPhase) -> TouchEventType {
+    match phase {
+        TouchPhase::Started => TouchEventType::Down,
+        TouchPhase::Moved => TouchEventType::Move,
+        TouchPhase::Ended => TouchEventType::Up,
+        TouchPhase::Cancelled => TouchEventType::Cancel,
+    }
+}
+
 /// Forward input event to compositor or constellation.
 fn forward_input_event(
     compositor: &mut IOCompositor,
@@ -1200,3 +1861,27 @@ fn forward_input_event(
         webview_id, event, None, /* hit_test */
     ));
 }
+
+/// Create a Winit window on a new GL display and the rendering context of the window.
+fn create_window_and_context(
+    evl: &ActiveEventLoop,
+    window_attributes: WindowAttributes,
+    software_rendering: bool,
+    vsync: Vsync,
+) -> Result<(WinitWindow, RenderingContext, Surface<WindowSurface>), Box<dyn std::error::Error>> {
+    let template = ConfigTemplateBuilder::new()
+        .with_alpha_size(8)
+        .with_transparency(cfg!(macos));
+    let template = rendering::software_config_template(template, software_rendering);
+
+    let (window, gl_config) = DisplayBuilder::new()
+        .with_window_attributes(Some(window_attributes))
+        .build(evl, template, gl_config_picker)?;
+    let window = window.ok_or("Failed to create window")?;
+
+    log::debug!("Picked a config with {} samples", gl_config.num_samples());
+
+    let (rendering_context, surface) =
+        RenderingContext::create(&window, &gl_config, window.inner_size(), vsync)?;
+    Ok((window, rendering_context, surface))
+}
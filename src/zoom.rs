@@ -0,0 +1,101 @@
+use std::{collections::BTreeMap, fs::File, path::PathBuf};
+
+use base::id::WebViewId;
+
+use crate::{compositor::IOCompositor, verso::VersoInternalMsg, window::Window};
+
+/// Page zoom chosen for each origin, so sites are zoomed as they were the last time they were
+/// visited. Origins at the default zoom aren't listed.
+#[derive(Default)]
+pub(crate) struct ZoomLevels {
+    levels: BTreeMap<String, f32>,
+}
+
+impl ZoomLevels {
+    /// Set the zoom levels loaded from disk.
+    pub fn set_levels(&mut self, levels: BTreeMap<String, f32>) {
+        self.levels = levels;
+    }
+
+    /// Get the zoom levels of every origin.
+    pub fn levels(&self) -> &BTreeMap<String, f32> {
+        &self.levels
+    }
+
+    /// Page zoom saved for the origin of the URL.
+    pub fn zoom(&self, url: &url::Url) -> Option<f32> {
+        self.levels.get(&origin_key(url)?).copied()
+    }
+
+    /// Save the page zoom of the origin of the URL, or forget it if `None`. Pages without an
+    /// origin of their own, such as data URLs, aren't remembered.
+    pub fn set_zoom(&mut self, url: &url::Url, zoom: Option<f32>) {
+        let Some(key) = origin_key(url) else {
+            return;
+        };
+        match zoom {
+            Some(zoom) => self.levels.insert(key, zoom),
+            None => self.levels.remove(&key),
+        };
+    }
+}
+
+fn origin_key(url: &url::Url) -> Option<String> {
+    let origin = url.origin();
+    origin.is_tuple().then(|| origin.ascii_serialization())
+}
+
+impl Window {
+    /// Zoom the page of the tab, or reset its zoom if `None`, and remember the zoom for the origin
+    /// of its page.
+    pub(crate) fn zoom_tab(
+        &self,
+        compositor: &mut IOCompositor,
+        tab_id: WebViewId,
+        page_zoom: Option<f32>,
+    ) {
+        let default_page_zoom = compositor.default_page_zoom();
+        let page_zoom =
+            compositor.set_page_zoom(tab_id, page_zoom.unwrap_or(default_page_zoom), self);
+        let Some(url) = self
+            .tab_manager
+            .tab(tab_id)
+            .and_then(|tab| tab.current_url())
+        else {
+            return;
+        };
+        let zoom = (page_zoom != default_page_zoom).then_some(page_zoom);
+        let _ = self
+            .verso_internal_sender
+            .send(VersoInternalMsg::ZoomChanged(url.clone(), zoom));
+    }
+}
+
+pub(crate) struct ZoomStorage {
+    config_dir_path: PathBuf,
+}
+
+impl ZoomStorage {
+    /// Create a new `ZoomStorage`.
+    pub fn new(config_dir_path: PathBuf) -> Self {
+        Self { config_dir_path }
+    }
+
+    fn zoom_file_path(&self) -> PathBuf {
+        self.config_dir_path.join("zoom.json")
+    }
+
+    /// Load zoom levels from disk.
+    pub fn load_from_file(&self) -> Result<BTreeMap<String, f32>, std::io::Error> {
+        let file = File::open(self.zoom_file_path())?;
+        let levels = serde_json::from_reader(file)?;
+        Ok(levels)
+    }
+
+    /// Save zoom levels to disk.
+    pub fn save_to_file(&self, levels: &BTreeMap<String, f32>) -> Result<(), std::io::Error> {
+        let file = File::create(self.zoom_file_path())?;
+        serde_json::to_writer(file, levels)?;
+        Ok(())
+    }
+}
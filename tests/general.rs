@@ -1,7 +1,39 @@
-use verso::verso_test;
-use winit::event_loop::EventLoopWindowTarget;
+use versoview::test::TestContext;
+use versoview::verso_test;
+use versoview_messages::FrameInput;
 
-fn smoke(_elwt: &EventLoopWindowTarget<()>) {}
-fn other_smoke(_elwt: &EventLoopWindowTarget<()>) {}
+fn smoke(_ctx: &TestContext) {}
 
-verso_test!(smoke, other_smoke);
+fn navigation(ctx: &TestContext) {
+    let url: url::Url = "data:text/html,<title>Navigation</title>".parse().unwrap();
+    assert!(ctx.navigate(url.clone()));
+    assert_eq!(ctx.current_url(), url);
+    assert_eq!(
+        ctx.evaluate("document.title"),
+        Ok("\"Navigation\"".to_string())
+    );
+}
+
+fn input(ctx: &TestContext) {
+    assert!(ctx.navigate("data:text/html,<input id=field>".parse().unwrap()));
+    let _ = ctx.evaluate("document.getElementById('field').focus()");
+    ctx.send_input(FrameInput::Text("verso".to_string()));
+    assert_eq!(
+        ctx.evaluate("document.getElementById('field').value"),
+        Ok("\"verso\"".to_string())
+    );
+}
+
+fn compositing(ctx: &TestContext) {
+    assert!(
+        ctx.navigate(
+            "data:text/html,<body style=margin:0;background:rgb(255,0,0)>"
+                .parse()
+                .unwrap()
+        )
+    );
+    let frame = ctx.capture_frame().expect("Failed to capture frame");
+    assert_eq!(frame.get_pixel(10, 10).0, [255, 0, 0, 255]);
+}
+
+verso_test!(smoke, navigation, input, compositing);
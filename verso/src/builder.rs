@@ -1,6 +1,14 @@
 use dpi::{Position, Size};
-use std::path::{Path, PathBuf};
-use versoview_messages::{ConfigFromController, ProfilerSettings, UserScript};
+use std::{
+    net::SocketAddr,
+    path::{Path, PathBuf},
+};
+use versoview_messages::{
+    AnimationPolicy, BrowserImport, CertificateErrorPolicy, CertificatePin, ConfigFromController,
+    CookiePolicy, CrossOriginFramePolicy, FileAccessPolicy, FontRendering, ImagePolicy,
+    JavaScriptSettings, MetricsConfig, PowerSaveConfig, PrivacySettings, ProfilerSettings,
+    ResourceOverride, Rotation, UpdateConfig, UserScript, VirtualKeyboard, Vsync, WindowConfig,
+};
 
 use crate::VersoviewController;
 
@@ -20,6 +28,18 @@ impl VersoBuilder {
         self
     }
 
+    /// Sets the site to run as a standalone app, without control panel and with its own profile.
+    pub fn app(mut self, url: url::Url) -> Self {
+        self.0.app = Some(url);
+        self
+    }
+
+    /// Sets the named profile to run in, with its own cookies, cache, history and settings.
+    pub fn profile(mut self, name: impl Into<String>) -> Self {
+        self.0.profile = Some(name.into());
+        self
+    }
+
     /// Sets the initial window size.
     pub fn inner_size(mut self, size: impl Into<Size>) -> Self {
         self.0.inner_size = Some(size.into());
@@ -86,6 +106,25 @@ impl VersoBuilder {
         self
     }
 
+    /// Port number to start a WebDriver server on, to drive Verso with automation tools.
+    pub fn webdriver_port(mut self, port: u16) -> Self {
+        self.0.webdriver_port = Some(port);
+        self
+    }
+
+    /// Port number to serve the Chrome DevTools Protocol on, on the loopback interface. 0 for
+    /// random port.
+    pub fn cdp_port(mut self, port: u16) -> Self {
+        self.0.cdp_port = Some(port);
+        self
+    }
+
+    /// Allows a web page of the origin to connect to the Chrome DevTools Protocol server.
+    pub fn cdp_allowed_origin(mut self, origin: url::Url) -> Self {
+        self.0.cdp_allowed_origins.push(origin);
+        self
+    }
+
     /// Sets the profiler settings.
     pub fn profiler_settings(mut self, settings: ProfilerSettings) -> Self {
         self.0.profiler_settings = Some(settings);
@@ -128,6 +167,288 @@ impl VersoBuilder {
         self
     }
 
+    /// Adds a window to open on startup. If no window is added, a single window is opened from
+    /// the initial URL and the window settings of this builder.
+    pub fn window(mut self, window: WindowConfig) -> Self {
+        self.0.windows.push(window);
+        self
+    }
+
+    /// Reloads webviews showing `file://` content from this directory when files in it change.
+    pub fn watch_directory(mut self, path: impl Into<PathBuf>) -> Self {
+        self.0.watch_directory = Some(path.into());
+        self
+    }
+
+    /// Sets whether hidden files are shown in `file://` directory listings.
+    pub fn show_hidden_files(mut self, show_hidden_files: bool) -> Self {
+        self.0.show_hidden_files = show_hidden_files;
+        self
+    }
+
+    /// Sets the access policy of `file://` URLs.
+    pub fn file_access(mut self, policy: FileAccessPolicy) -> Self {
+        self.0.file_access = policy;
+        self
+    }
+
+    /// Serves a URL from a local file or in-memory bytes instead of the network.
+    pub fn resource_override(mut self, resource_override: ResourceOverride) -> Self {
+        self.0.resource_overrides.push(resource_override);
+        self
+    }
+
+    /// Sets whether to launch in mock network mode, see [`VersoviewController::set_mock_network`].
+    pub fn mock_network(mut self, mock_network: bool) -> Self {
+        self.0.mock_network = mock_network;
+        self
+    }
+
+    /// Imports cookies, bookmarks and history from another browser on startup.
+    pub fn import(mut self, import: BrowserImport) -> Self {
+        self.0.import = Some(import);
+        self
+    }
+
+    /// Allows an origin to store secrets in the OS keychain through `window.verso.secrets`.
+    pub fn secret_bridge_origin(mut self, origin: url::Url) -> Self {
+        self.0.secret_bridge_origins.push(origin);
+        self
+    }
+
+    /// Sets a PEM file of root CA certificates to trust instead of the default ones.
+    pub fn certificate_bundle(mut self, path: impl Into<PathBuf>) -> Self {
+        self.0.certificate_bundle = Some(path.into());
+        self
+    }
+
+    /// Sets when and how much animations are throttled to save power.
+    pub fn power_save(mut self, power_save: PowerSaveConfig) -> Self {
+        self.0.power_save = power_save;
+        self
+    }
+
+    /// Sets whether pages can read the battery of the system with `navigator.getBattery()`.
+    pub fn battery_status_api(mut self, enabled: bool) -> Self {
+        self.0.battery_status_api = enabled;
+        self
+    }
+
+    /// Sets whether pages can read the motion and light sensors of the device.
+    pub fn sensors(mut self, enabled: bool) -> Self {
+        self.0.sensors = enabled;
+        self
+    }
+
+    /// Sets whether the titlebar of windows is tinted with the `theme-color` of their current tab.
+    pub fn theme_color_titlebar(mut self, enabled: bool) -> Self {
+        self.0.theme_color_titlebar = enabled;
+        self
+    }
+
+    /// Sets whether a progress bar is drawn at the top of the current tab while it loads.
+    pub fn load_progress_bar(mut self, enabled: bool) -> Self {
+        self.0.load_progress_bar = enabled;
+        self
+    }
+
+    /// Sets the seconds the script of a page can be unresponsive before the user is asked whether
+    /// to stop it, 0 to never ask.
+    pub fn slow_script_timeout(mut self, seconds: u64) -> Self {
+        self.0.slow_script_timeout = seconds;
+        self
+    }
+
+    /// Sets whether pages can embed frames from other origins, or only sandboxed ones.
+    pub fn cross_origin_frames(mut self, policy: CrossOriginFramePolicy) -> Self {
+        self.0.cross_origin_frames = policy;
+        self
+    }
+
+    /// Sets whether the text of visited pages is indexed to search the history by content.
+    pub fn index_history_text(mut self, index_history_text: bool) -> Self {
+        self.0.index_history_text = index_history_text;
+        self
+    }
+
+    /// Sets how many days visited pages are kept in the history, 0 to keep them forever.
+    pub fn history_retention_days(mut self, days: u32) -> Self {
+        self.0.history_retention_days = days;
+        self
+    }
+
+    /// Sets how many blank webviews every window keeps ready for new tabs.
+    pub fn warm_pool_size(mut self, size: usize) -> Self {
+        self.0.warm_pool_size = size;
+        self
+    }
+
+    /// Sets whether the shader programs compiled by the GL driver are cached on disk.
+    pub fn shader_cache(mut self, shader_cache: bool) -> Self {
+        self.0.shader_cache = shader_cache;
+        self
+    }
+
+    /// Sets whether Verso starts in safe mode, without user scripts, resource overrides, the
+    /// shader cache and optional GPU features.
+    pub fn safe_mode(mut self, safe_mode: bool) -> Self {
+        self.0.safe_mode = safe_mode;
+        self
+    }
+
+    /// Sets whether the windows and tabs of the last session are reopened on startup.
+    pub fn restore_session(mut self, restore_session: bool) -> Self {
+        self.0.restore_session = restore_session;
+        self
+    }
+
+    /// Sets whether the windows are private, keeping nothing the pages store after they close.
+    pub fn private(mut self, private: bool) -> Self {
+        self.0.private = private;
+        self
+    }
+
+    /// Sets whether pages are rendered with the software rasterizer of the GL driver.
+    pub fn software_rendering(mut self, software_rendering: bool) -> Self {
+        self.0.software_rendering = software_rendering;
+        self
+    }
+
+    /// Sets how windows wait for the vertical blank before presenting a frame.
+    pub fn vsync(mut self, vsync: Vsync) -> Self {
+        self.0.vsync = vsync;
+        self
+    }
+
+    /// Sets the clockwise rotation of the output of the windows, for screens mounted in another
+    /// orientation.
+    pub fn rotation(mut self, rotation: Rotation) -> Self {
+        self.0.rotation = rotation;
+        self
+    }
+
+    /// Sets what Verso keeps of the cookies pages set. Cookies are saved in the profile by
+    /// default.
+    pub fn cookies(mut self, cookies: CookiePolicy) -> Self {
+        self.0.cookies = cookies;
+        self
+    }
+
+    /// Sets whether pages run JavaScript, for every origin and for some of them. JavaScript runs
+    /// everywhere by default.
+    pub fn javascript(mut self, javascript: JavaScriptSettings) -> Self {
+        self.0.javascript = javascript;
+        self
+    }
+
+    /// Sets which images pages load and whether to save data on them, instead of the policy saved
+    /// in the profile.
+    pub fn images(mut self, images: ImagePolicy) -> Self {
+        self.0.images = Some(images);
+        self
+    }
+
+    /// Sets the antialiasing, hinting and gamma of the text of pages, e.g. grayscale antialiasing
+    /// for screens whose subpixels aren't RGB stripes.
+    pub fn font_rendering(mut self, font_rendering: FontRendering) -> Self {
+        self.0.font_rendering = font_rendering;
+        self
+    }
+
+    /// Sets when pages run their animations, e.g. to pause them in windows which aren't focused.
+    pub fn animations(mut self, animations: AnimationPolicy) -> Self {
+        self.0.animations = animations;
+        self
+    }
+
+    /// Sets the most megabytes of responses kept in the HTTP cache of the profile, 0 to disable
+    /// it.
+    pub fn http_cache_size(mut self, http_cache_size: u64) -> Self {
+        self.0.http_cache_size = http_cache_size;
+        self
+    }
+
+    /// Sets whether the HTTP cache and the cookies of requests to other sites are keyed by the
+    /// site of the tab, they're shared by every site by default.
+    pub fn partition_network(mut self, partition_network: bool) -> Self {
+        self.0.partition_network = partition_network;
+        self
+    }
+
+    /// Sets the on-screen keyboard shown when an editable field is focused by touch, e.g.
+    /// [`VirtualKeyboard::BuiltIn`] for DRM/KMS outputs.
+    pub fn virtual_keyboard(mut self, virtual_keyboard: VirtualKeyboard) -> Self {
+        self.0.virtual_keyboard = virtual_keyboard;
+        self
+    }
+
+    /// Serves the JSON control protocol on this unix socket, or named pipe on Windows.
+    pub fn ipc_socket(mut self, path: impl Into<PathBuf>) -> Self {
+        self.0.ipc_socket = Some(path.into());
+        self
+    }
+
+    /// Sets the token clients of the control protocol authenticate with.
+    pub fn ipc_token(mut self, token: impl Into<String>) -> Self {
+        self.0.ipc_token = Some(token.into());
+        self
+    }
+
+    /// Sets the requests per second each client of the control protocol may send, 0 for no
+    /// limit.
+    pub fn ipc_rate_limit(mut self, requests_per_second: u32) -> Self {
+        self.0.ipc_rate_limit = requests_per_second;
+        self
+    }
+
+    /// Sets whether the control protocol may be served on TCP addresses other than loopback.
+    pub fn ipc_allow_remote(mut self, ipc_allow_remote: bool) -> Self {
+        self.0.ipc_allow_remote = ipc_allow_remote;
+        self
+    }
+
+    /// Also serves the JSON control protocol over WebSocket on this address.
+    pub fn ipc_websocket(mut self, address: SocketAddr) -> Self {
+        self.0.ipc_websocket = Some(address);
+        self
+    }
+
+    /// Sets the URL crash reports are submitted to.
+    pub fn crash_report_url(mut self, url: url::Url) -> Self {
+        self.0.crash_report_url = Some(url);
+        self
+    }
+
+    /// Sets whether performance metrics are recorded in the profile and where they are uploaded.
+    pub fn metrics(mut self, metrics: MetricsConfig) -> Self {
+        self.0.metrics = metrics;
+        self
+    }
+
+    /// Checks for signed releases on startup and installs them on the next restart.
+    pub fn update(mut self, update: UpdateConfig) -> Self {
+        self.0.update = Some(update);
+        self
+    }
+
+    /// Sets the referrer policy and Do-Not-Track settings of every webview.
+    pub fn privacy(mut self, privacy: PrivacySettings) -> Self {
+        self.0.privacy = privacy;
+        self
+    }
+
+    /// Pins the public keys of an origin's certificates.
+    pub fn certificate_pin(mut self, pin: CertificatePin) -> Self {
+        self.0.certificate_pins.push(pin);
+        self
+    }
+
+    /// Sets whether users can proceed to sites with an invalid certificate, they can by default.
+    pub fn certificate_errors(mut self, policy: CertificateErrorPolicy) -> Self {
+        self.0.certificate_errors = policy;
+        self
+    }
+
     /// Builds the [`VersoviewController`] with the configured settings.
     pub fn build(
         self,
@@ -9,12 +9,23 @@ use ipc_channel::{
 use log::error;
 use std::{
     collections::HashMap,
-    path::Path,
+    path::{Path, PathBuf},
     process::Command,
     sync::{Arc, Mutex, mpsc::Sender as MpscSender},
+    time::Duration,
 };
 pub use versoview_messages::{
-    ConfigFromController as VersoviewSettings, Icon, ProfilerSettings, UserScript,
+    AnimationPolicy, AuthCallback, AuthenticationRequest, BookmarkInfo, Browser, BrowserImport,
+    CertificateErrorPolicy, CertificatePin, ClipboardAction,
+    ConfigFromController as VersoviewSettings, CookiePolicy, CrossOriginFramePolicy, DialogRequest,
+    DialogResponse, DownloadState, DownloadUpdate, FileAccessPolicy, FindOptions, FindResult,
+    FontAntialiasing, FontHinting, FontRendering, FormField, FormSubmission,
+    FormSubmissionResponse, FrameInfo, FrameInput, GeolocationPosition, HistoryMatch,
+    HttpCredentials, Icon, ImageLoading, ImagePolicy, JavaScriptSettings, LifecycleState,
+    MetricsConfig, PowerSaveConfig, PrivacySettings, ProfilerSettings, ReferrerPolicy,
+    ResourceLimit, ResourceLimits, ResourceOverride, ResourceOverrideSource, Rotation,
+    SerializedFrameId, SerializedWebViewId, TabGroupInfo, UpdateChannel, UpdateConfig, UserScript,
+    VirtualKeyboard, Vsync, WaitCondition, WindowConfig,
 };
 use versoview_messages::{
     PositionType, SizeType, ToControllerMessage, ToVersoMessage, WebResourceRequestResponse,
@@ -24,12 +35,70 @@ type ResponseFunction = Box<dyn FnOnce(Option<http::Response<Vec<u8>>>) + Send>;
 type Listener<T> = Arc<Mutex<Option<T>>>;
 type ResponseListener<T> = Arc<Mutex<HashMap<uuid::Uuid, T>>>;
 
+/// Shows the dialogs of webviews with the UI of the embedder instead of the built-in ones of
+/// Verso, set with [`VersoviewController::set_dialog_delegate`]
+pub trait DialogDelegate: Send + 'static {
+    /// Show the dialog opened by the webview, the page waits until it's answered with the
+    /// responder, which can be sent to another thread
+    fn show_dialog(
+        &self,
+        webview_id: SerializedWebViewId,
+        request: DialogRequest,
+        responder: DialogResponder,
+    );
+}
+
+/// Answers a dialog shown by a [`DialogDelegate`], the dialog is cancelled if it's dropped
+/// without answering it
+pub struct DialogResponder {
+    id: uuid::Uuid,
+    sender: Option<IpcSender<ToVersoMessage>>,
+}
+
+impl DialogResponder {
+    /// Answer the dialog
+    pub fn respond(mut self, response: DialogResponse) {
+        if let Some(sender) = self.sender.take() {
+            if let Err(error) = sender.send(ToVersoMessage::DialogResponse(self.id, response)) {
+                error!("Error while sending back dialog response: {error}");
+            }
+        }
+    }
+}
+
+impl Drop for DialogResponder {
+    fn drop(&mut self) {
+        if let Some(sender) = self.sender.take() {
+            let _ = sender.send(ToVersoMessage::DialogResponse(
+                self.id,
+                DialogResponse::Cancel,
+            ));
+        }
+    }
+}
+
 #[derive(Default)]
 struct EventListeners {
     on_close_requested: Listener<Box<dyn Fn() + Send + 'static>>,
     on_navigation_starting: Listener<Box<dyn Fn(url::Url) -> bool + Send + 'static>>,
+    on_form_submission:
+        Listener<Box<dyn Fn(FormSubmission) -> FormSubmissionResponse + Send + 'static>>,
+    on_authentication:
+        Listener<Box<dyn Fn(AuthenticationRequest) -> Option<HttpCredentials> + Send + 'static>>,
     on_web_resource_requested:
         Listener<Box<dyn Fn(http::Request<Vec<u8>>, ResponseFunction) + Send + 'static>>,
+    on_title_changed:
+        Listener<Box<dyn Fn(SerializedWebViewId, Option<String>, bool) + Send + 'static>>,
+    on_favicon_changed: Listener<Box<dyn Fn(SerializedWebViewId, url::Url, bool) + Send + 'static>>,
+    on_audio_state_changed: Listener<Box<dyn Fn(SerializedWebViewId, bool, bool) + Send + 'static>>,
+    on_theme_color_changed:
+        Listener<Box<dyn Fn(SerializedWebViewId, Option<String>, bool) + Send + 'static>>,
+    on_script_unresponsive: Listener<Box<dyn Fn(SerializedWebViewId, bool) + Send + 'static>>,
+    on_resource_limit_exceeded:
+        Listener<Box<dyn Fn(SerializedWebViewId, ResourceLimit) + Send + 'static>>,
+    on_config_changed: Listener<Box<dyn Fn(VersoviewSettings) + Send + 'static>>,
+    on_download_updated: Listener<Box<dyn Fn(DownloadUpdate) + Send + 'static>>,
+    dialog_delegate: Listener<Box<dyn DialogDelegate>>,
     size_response: ResponseListener<MpscSender<PhysicalSize<u32>>>,
     position_response: ResponseListener<MpscSender<Option<PhysicalPosition<i32>>>>,
     maximized_response: ResponseListener<MpscSender<bool>>,
@@ -38,6 +107,28 @@ struct EventListeners {
     visible_response: ResponseListener<MpscSender<bool>>,
     scale_factor_response: ResponseListener<MpscSender<f64>>,
     get_url_response: ResponseListener<MpscSender<url::Url>>,
+    extract_text_response: ResponseListener<MpscSender<Option<String>>>,
+    search_history_response: ResponseListener<MpscSender<Vec<HistoryMatch>>>,
+    suggest_history_response: ResponseListener<MpscSender<Vec<HistoryMatch>>>,
+    save_as_markdown_response: ResponseListener<MpscSender<Result<(), String>>>,
+    wait_for_response: ResponseListener<MpscSender<bool>>,
+    find_response: ResponseListener<MpscSender<FindResult>>,
+    capture_frame_response: ResponseListener<MpscSender<Option<Vec<u8>>>>,
+    get_tab_groups_response: ResponseListener<MpscSender<Vec<TabGroupInfo>>>,
+    get_secret_response: ResponseListener<MpscSender<Option<String>>>,
+    auth_flow_response: ResponseListener<MpscSender<Option<AuthCallback>>>,
+    get_frame_tree_response: ResponseListener<MpscSender<Option<FrameInfo>>>,
+    execute_script_in_frame_response: ResponseListener<MpscSender<Result<String, String>>>,
+    get_config_response: ResponseListener<MpscSender<VersoviewSettings>>,
+    get_webviews_response: ResponseListener<MpscSender<Vec<SerializedWebViewId>>>,
+    get_webview_by_name_response: ResponseListener<MpscSender<Option<SerializedWebViewId>>>,
+    list_profiles_response: ResponseListener<MpscSender<Vec<String>>>,
+    create_profile_response: ResponseListener<MpscSender<Result<(), String>>>,
+    delete_profile_response: ResponseListener<MpscSender<Result<(), String>>>,
+    list_bookmarks_response: ResponseListener<MpscSender<Vec<BookmarkInfo>>>,
+    import_bookmarks_response: ResponseListener<MpscSender<Result<usize, String>>>,
+    export_bookmarks_response: ResponseListener<MpscSender<Result<(), String>>>,
+    commands: Arc<Mutex<HashMap<String, Box<dyn Fn() + Send + 'static>>>>,
 }
 
 /// A VersoView controller
@@ -77,7 +168,18 @@ impl VersoviewController {
         let event_listeners = EventListeners::default();
         let on_close_requested = event_listeners.on_close_requested.clone();
         let on_navigation_starting = event_listeners.on_navigation_starting.clone();
+        let on_form_submission = event_listeners.on_form_submission.clone();
+        let on_authentication = event_listeners.on_authentication.clone();
         let on_web_resource_requested = event_listeners.on_web_resource_requested.clone();
+        let on_title_changed = event_listeners.on_title_changed.clone();
+        let on_favicon_changed = event_listeners.on_favicon_changed.clone();
+        let on_audio_state_changed = event_listeners.on_audio_state_changed.clone();
+        let on_theme_color_changed = event_listeners.on_theme_color_changed.clone();
+        let on_script_unresponsive = event_listeners.on_script_unresponsive.clone();
+        let on_resource_limit_exceeded = event_listeners.on_resource_limit_exceeded.clone();
+        let on_config_changed = event_listeners.on_config_changed.clone();
+        let on_download_updated = event_listeners.on_download_updated.clone();
+        let dialog_delegate = event_listeners.dialog_delegate.clone();
         let size_response = event_listeners.size_response.clone();
         let position_response = event_listeners.position_response.clone();
         let minimized_response = event_listeners.minimized_response.clone();
@@ -86,6 +188,29 @@ impl VersoviewController {
         let visible_response = event_listeners.visible_response.clone();
         let scale_factor_response = event_listeners.scale_factor_response.clone();
         let get_url_response = event_listeners.get_url_response.clone();
+        let extract_text_response = event_listeners.extract_text_response.clone();
+        let search_history_response = event_listeners.search_history_response.clone();
+        let suggest_history_response = event_listeners.suggest_history_response.clone();
+        let save_as_markdown_response = event_listeners.save_as_markdown_response.clone();
+        let wait_for_response = event_listeners.wait_for_response.clone();
+        let find_response = event_listeners.find_response.clone();
+        let capture_frame_response = event_listeners.capture_frame_response.clone();
+        let get_tab_groups_response = event_listeners.get_tab_groups_response.clone();
+        let get_secret_response = event_listeners.get_secret_response.clone();
+        let auth_flow_response = event_listeners.auth_flow_response.clone();
+        let get_frame_tree_response = event_listeners.get_frame_tree_response.clone();
+        let execute_script_in_frame_response =
+            event_listeners.execute_script_in_frame_response.clone();
+        let get_config_response = event_listeners.get_config_response.clone();
+        let get_webviews_response = event_listeners.get_webviews_response.clone();
+        let get_webview_by_name_response = event_listeners.get_webview_by_name_response.clone();
+        let list_profiles_response = event_listeners.list_profiles_response.clone();
+        let create_profile_response = event_listeners.create_profile_response.clone();
+        let delete_profile_response = event_listeners.delete_profile_response.clone();
+        let list_bookmarks_response = event_listeners.list_bookmarks_response.clone();
+        let import_bookmarks_response = event_listeners.import_bookmarks_response.clone();
+        let export_bookmarks_response = event_listeners.export_bookmarks_response.clone();
+        let commands = event_listeners.commands.clone();
         let to_verso_sender = sender.clone();
         ROUTER.add_typed_route(
             receiver,
@@ -107,6 +232,24 @@ impl VersoviewController {
                             }
                         }
                     }
+                    ToControllerMessage::OnFormSubmission(id, submission) => {
+                        if let Some(ref callback) = *on_form_submission.lock().unwrap() {
+                            if let Err(error) = to_verso_sender.send(
+                                ToVersoMessage::FormSubmissionResponse(id, callback(submission)),
+                            ) {
+                                error!("Error while sending back OnFormSubmission result: {error}");
+                            }
+                        }
+                    }
+                    ToControllerMessage::OnAuthentication(id, request) => {
+                        if let Some(ref callback) = *on_authentication.lock().unwrap() {
+                            if let Err(error) = to_verso_sender.send(
+                                ToVersoMessage::AuthenticationResponse(id, callback(request)),
+                            ) {
+                                error!("Error while sending back OnAuthentication result: {error}");
+                            }
+                        }
+                    }
                     ToControllerMessage::OnWebResourceRequested(request) => {
                         if let Some(ref callback) = *on_web_resource_requested.lock().unwrap() {
                             let sender_clone = to_verso_sender.clone();
@@ -123,6 +266,41 @@ impl VersoviewController {
                             );
                         }
                     }
+                    ToControllerMessage::OnTitleChanged(webview_id, title, background) => {
+                        if let Some(ref callback) = *on_title_changed.lock().unwrap() {
+                            callback(webview_id, title, background);
+                        }
+                    }
+                    ToControllerMessage::OnFaviconChanged(webview_id, url, background) => {
+                        if let Some(ref callback) = *on_favicon_changed.lock().unwrap() {
+                            callback(webview_id, url, background);
+                        }
+                    }
+                    ToControllerMessage::OnAudioStateChanged(webview_id, audible, muted) => {
+                        if let Some(ref callback) = *on_audio_state_changed.lock().unwrap() {
+                            callback(webview_id, audible, muted);
+                        }
+                    }
+                    ToControllerMessage::OnThemeColorChanged(webview_id, color, background) => {
+                        if let Some(ref callback) = *on_theme_color_changed.lock().unwrap() {
+                            callback(webview_id, color, background);
+                        }
+                    }
+                    ToControllerMessage::OnScriptUnresponsive(webview_id, unresponsive) => {
+                        if let Some(ref callback) = *on_script_unresponsive.lock().unwrap() {
+                            callback(webview_id, unresponsive);
+                        }
+                    }
+                    ToControllerMessage::OnResourceLimitExceeded(webview_id, limit) => {
+                        if let Some(ref callback) = *on_resource_limit_exceeded.lock().unwrap() {
+                            callback(webview_id, limit);
+                        }
+                    }
+                    ToControllerMessage::OnDownloadUpdated(download) => {
+                        if let Some(ref callback) = *on_download_updated.lock().unwrap() {
+                            callback(download);
+                        }
+                    }
                     ToControllerMessage::GetSizeResponse(id, size) => {
                         if let Some(sender) = size_response.lock().unwrap().get(&id).take() {
                             sender.send(size).unwrap();
@@ -158,11 +336,145 @@ impl VersoviewController {
                             sender.send(scale_factor).unwrap();
                         }
                     }
+                    ToControllerMessage::ExtractTextResponse(id, text) => {
+                        if let Some(sender) = extract_text_response.lock().unwrap().remove(&id) {
+                            sender.send(text).unwrap();
+                        }
+                    }
+                    ToControllerMessage::SaveAsMarkdownResponse(id, result) => {
+                        if let Some(sender) = save_as_markdown_response.lock().unwrap().remove(&id)
+                        {
+                            sender.send(result).unwrap();
+                        }
+                    }
+                    ToControllerMessage::CaptureFrameResponse(id, image) => {
+                        if let Some(sender) = capture_frame_response.lock().unwrap().remove(&id) {
+                            sender.send(image).unwrap();
+                        }
+                    }
+                    ToControllerMessage::WaitForResponse(id, met) => {
+                        if let Some(sender) = wait_for_response.lock().unwrap().remove(&id) {
+                            sender.send(met).unwrap();
+                        }
+                    }
+                    ToControllerMessage::FindResponse(id, result) => {
+                        if let Some(sender) = find_response.lock().unwrap().remove(&id) {
+                            sender.send(result).unwrap();
+                        }
+                    }
+                    ToControllerMessage::SearchHistoryResponse(id, matches) => {
+                        if let Some(sender) = search_history_response.lock().unwrap().remove(&id) {
+                            sender.send(matches).unwrap();
+                        }
+                    }
+                    ToControllerMessage::SuggestHistoryResponse(id, suggestions) => {
+                        if let Some(sender) = suggest_history_response.lock().unwrap().remove(&id)
+                        {
+                            sender.send(suggestions).unwrap();
+                        }
+                    }
                     ToControllerMessage::GetCurrentUrlResponse(id, url) => {
                         if let Some(sender) = get_url_response.lock().unwrap().get(&id).take() {
                             sender.send(url).unwrap();
                         }
                     }
+                    ToControllerMessage::OnCommandExecuted(id) => {
+                        if let Some(callback) = commands.lock().unwrap().get(&id) {
+                            callback();
+                        }
+                    }
+                    ToControllerMessage::GetTabGroupsResponse(id, tab_groups) => {
+                        if let Some(sender) =
+                            get_tab_groups_response.lock().unwrap().get(&id).take()
+                        {
+                            sender.send(tab_groups).unwrap();
+                        }
+                    }
+                    ToControllerMessage::GetSecretResponse(id, secret) => {
+                        if let Some(sender) = get_secret_response.lock().unwrap().get(&id).take() {
+                            sender.send(secret).unwrap();
+                        }
+                    }
+                    ToControllerMessage::AuthFlowResponse(id, callback) => {
+                        if let Some(sender) = auth_flow_response.lock().unwrap().remove(&id) {
+                            sender.send(callback).unwrap();
+                        }
+                    }
+                    ToControllerMessage::GetFrameTreeResponse(id, tree) => {
+                        if let Some(sender) = get_frame_tree_response.lock().unwrap().remove(&id) {
+                            sender.send(tree).unwrap();
+                        }
+                    }
+                    ToControllerMessage::ExecuteScriptInFrameResponse(id, result) => {
+                        if let Some(sender) =
+                            execute_script_in_frame_response.lock().unwrap().remove(&id)
+                        {
+                            sender.send(result).unwrap();
+                        }
+                    }
+                    ToControllerMessage::GetConfigResponse(id, config) => {
+                        if let Some(sender) = get_config_response.lock().unwrap().remove(&id) {
+                            sender.send(config).unwrap();
+                        }
+                    }
+                    ToControllerMessage::GetWebViewsResponse(id, webview_ids) => {
+                        if let Some(sender) = get_webviews_response.lock().unwrap().remove(&id) {
+                            sender.send(webview_ids).unwrap();
+                        }
+                    }
+                    ToControllerMessage::GetWebViewByNameResponse(id, webview_id) => {
+                        if let Some(sender) =
+                            get_webview_by_name_response.lock().unwrap().remove(&id)
+                        {
+                            sender.send(webview_id).unwrap();
+                        }
+                    }
+                    ToControllerMessage::ListProfilesResponse(id, names) => {
+                        if let Some(sender) = list_profiles_response.lock().unwrap().remove(&id) {
+                            sender.send(names).unwrap();
+                        }
+                    }
+                    ToControllerMessage::CreateProfileResponse(id, result) => {
+                        if let Some(sender) = create_profile_response.lock().unwrap().remove(&id) {
+                            sender.send(result).unwrap();
+                        }
+                    }
+                    ToControllerMessage::DeleteProfileResponse(id, result) => {
+                        if let Some(sender) = delete_profile_response.lock().unwrap().remove(&id) {
+                            sender.send(result).unwrap();
+                        }
+                    }
+                    ToControllerMessage::ListBookmarksResponse(id, bookmarks) => {
+                        if let Some(sender) = list_bookmarks_response.lock().unwrap().remove(&id) {
+                            sender.send(bookmarks).unwrap();
+                        }
+                    }
+                    ToControllerMessage::ImportBookmarksResponse(id, result) => {
+                        if let Some(sender) = import_bookmarks_response.lock().unwrap().remove(&id)
+                        {
+                            sender.send(result).unwrap();
+                        }
+                    }
+                    ToControllerMessage::ExportBookmarksResponse(id, result) => {
+                        if let Some(sender) = export_bookmarks_response.lock().unwrap().remove(&id)
+                        {
+                            sender.send(result).unwrap();
+                        }
+                    }
+                    ToControllerMessage::OnConfigChanged(config) => {
+                        if let Some(ref callback) = *on_config_changed.lock().unwrap() {
+                            callback(config);
+                        }
+                    }
+                    ToControllerMessage::OnDialog(id, webview_id, request) => {
+                        let responder = DialogResponder {
+                            id,
+                            sender: Some(to_verso_sender.clone()),
+                        };
+                        if let Some(ref delegate) = *dialog_delegate.lock().unwrap() {
+                            delegate.show_dialog(webview_id, request, responder);
+                        }
+                    }
                     _ => {}
                 },
                 Err(e) => error!("Error while receiving VersoMessage: {e}"),
@@ -213,6 +525,17 @@ impl VersoviewController {
         self.sender.send(ToVersoMessage::NavigateTo(url))
     }
 
+    /// Load a HTML string in the current webview through a `data:` URL, without a temp file or
+    /// a server. Relative URLs in the document are resolved against `base_url` if it's set.
+    pub fn load_html(
+        &self,
+        html: impl Into<String>,
+        base_url: Option<url::Url>,
+    ) -> Result<(), Box<ipc_channel::ErrorKind>> {
+        self.sender
+            .send(ToVersoMessage::LoadHtml(html.into(), base_url))
+    }
+
     /// Reload the current webview
     pub fn reload(&self) -> Result<(), Box<ipc_channel::ErrorKind>> {
         self.sender.send(ToVersoMessage::Reload)
@@ -237,6 +560,45 @@ impl VersoviewController {
         Ok(())
     }
 
+    /// Listen on form submissions of pages, e.g. to audit what web apps send, return in the
+    /// callback whether to submit the form, block it or submit other fields. The page waits
+    /// for the callback.
+    pub fn on_form_submission(
+        &self,
+        callback: impl Fn(FormSubmission) -> FormSubmissionResponse + Send + 'static,
+    ) -> Result<(), Box<ipc_channel::ErrorKind>> {
+        let old_listener = self
+            .event_listeners
+            .on_form_submission
+            .lock()
+            .unwrap()
+            .replace(Box::new(callback));
+        if old_listener.is_none() {
+            self.sender.send(ToVersoMessage::ListenToFormSubmissions)?;
+        }
+        Ok(())
+    }
+
+    /// Listen on HTTP authentication asked by sites and proxies, e.g. to sign in headlessly,
+    /// return in the callback the credentials to answer with, or `None` to cancel the request.
+    /// Credentials the user chose to remember are answered without calling it. The request
+    /// waits for the callback.
+    pub fn on_authentication(
+        &self,
+        callback: impl Fn(AuthenticationRequest) -> Option<HttpCredentials> + Send + 'static,
+    ) -> Result<(), Box<ipc_channel::ErrorKind>> {
+        let old_listener = self
+            .event_listeners
+            .on_authentication
+            .lock()
+            .unwrap()
+            .replace(Box::new(callback));
+        if old_listener.is_none() {
+            self.sender.send(ToVersoMessage::ListenToAuthentication)?;
+        }
+        Ok(())
+    }
+
     /// Listen on web resource requests,
     /// return a boolean in the callback to decide whether or not allowing this navigation
     pub fn on_web_resource_requested(
@@ -256,99 +618,489 @@ impl VersoviewController {
         Ok(())
     }
 
-    /// Sets the webview window's size
-    pub fn set_size<S: Into<Size>>(&self, size: S) -> Result<(), Box<ipc_channel::ErrorKind>> {
-        self.sender.send(ToVersoMessage::SetSize(size.into()))?;
+    /// Listen on page title changes of any webview,
+    /// the boolean in the callback is `true` if the webview is a background tab
+    pub fn on_title_changed(
+        &self,
+        callback: impl Fn(SerializedWebViewId, Option<String>, bool) + Send + 'static,
+    ) -> Result<(), Box<ipc_channel::ErrorKind>> {
+        let old_listener = self
+            .event_listeners
+            .on_title_changed
+            .lock()
+            .unwrap()
+            .replace(Box::new(callback));
+        if old_listener.is_none() {
+            self.sender.send(ToVersoMessage::ListenToOnTitleChanged)?;
+        }
         Ok(())
     }
 
-    /// Sets the webview window's position
-    pub fn set_position<P: Into<Position>>(
+    /// Listen on favicon changes of any webview,
+    /// the boolean in the callback is `true` if the webview is a background tab
+    pub fn on_favicon_changed(
         &self,
-        position: P,
+        callback: impl Fn(SerializedWebViewId, url::Url, bool) + Send + 'static,
     ) -> Result<(), Box<ipc_channel::ErrorKind>> {
-        self.sender
-            .send(ToVersoMessage::SetPosition(position.into()))?;
+        let old_listener = self
+            .event_listeners
+            .on_favicon_changed
+            .lock()
+            .unwrap()
+            .replace(Box::new(callback));
+        if old_listener.is_none() {
+            self.sender.send(ToVersoMessage::ListenToOnFaviconChanged)?;
+        }
         Ok(())
     }
 
-    /// Maximize or unmaximize the window
-    pub fn set_maximized(&self, maximized: bool) -> Result<(), Box<ipc_channel::ErrorKind>> {
-        self.sender.send(ToVersoMessage::SetMaximized(maximized))?;
+    /// Listen on audio state changes of any webview,
+    /// the booleans in the callback are whether the webview is audible and whether it is muted
+    pub fn on_audio_state_changed(
+        &self,
+        callback: impl Fn(SerializedWebViewId, bool, bool) + Send + 'static,
+    ) -> Result<(), Box<ipc_channel::ErrorKind>> {
+        let old_listener = self
+            .event_listeners
+            .on_audio_state_changed
+            .lock()
+            .unwrap()
+            .replace(Box::new(callback));
+        if old_listener.is_none() {
+            self.sender
+                .send(ToVersoMessage::ListenToOnAudioStateChanged)?;
+        }
         Ok(())
     }
 
-    /// Minimize or unminimize the window
-    pub fn set_minimized(&self, minimized: bool) -> Result<(), Box<ipc_channel::ErrorKind>> {
-        self.sender.send(ToVersoMessage::SetMinimized(minimized))?;
+    /// Listen on `theme-color` changes of any webview, the color is `#rrggbb` or `None` if the page
+    /// has none, the boolean in the callback is `true` if the webview is a background tab
+    pub fn on_theme_color_changed(
+        &self,
+        callback: impl Fn(SerializedWebViewId, Option<String>, bool) + Send + 'static,
+    ) -> Result<(), Box<ipc_channel::ErrorKind>> {
+        let old_listener = self
+            .event_listeners
+            .on_theme_color_changed
+            .lock()
+            .unwrap()
+            .replace(Box::new(callback));
+        if old_listener.is_none() {
+            self.sender
+                .send(ToVersoMessage::ListenToOnThemeColorChanged)?;
+        }
         Ok(())
     }
 
-    /// Sets the window to fullscreen or back
-    pub fn set_fullscreen(&self, fullscreen: bool) -> Result<(), Box<ipc_channel::ErrorKind>> {
-        self.sender
-            .send(ToVersoMessage::SetFullscreen(fullscreen))?;
+    /// Listen on webviews whose script stops responding, with `true`, or responds again, with
+    /// `false`. Hung webviews can be closed with [`Self::close_webview`].
+    pub fn on_script_unresponsive(
+        &self,
+        callback: impl Fn(SerializedWebViewId, bool) + Send + 'static,
+    ) -> Result<(), Box<ipc_channel::ErrorKind>> {
+        let old_listener = self
+            .event_listeners
+            .on_script_unresponsive
+            .lock()
+            .unwrap()
+            .replace(Box::new(callback));
+        if old_listener.is_none() {
+            self.sender
+                .send(ToVersoMessage::ListenToOnScriptUnresponsive)?;
+        }
         Ok(())
     }
 
-    /// Show or hide the window
-    pub fn set_visible(&self, visible: bool) -> Result<(), Box<ipc_channel::ErrorKind>> {
-        self.sender.send(ToVersoMessage::SetVisible(visible))?;
+    /// Listen on webviews exceeding one of the budgets set with [`Self::set_resource_limits`]
+    pub fn on_resource_limit_exceeded(
+        &self,
+        callback: impl Fn(SerializedWebViewId, ResourceLimit) + Send + 'static,
+    ) -> Result<(), Box<ipc_channel::ErrorKind>> {
+        let old_listener = self
+            .event_listeners
+            .on_resource_limit_exceeded
+            .lock()
+            .unwrap()
+            .replace(Box::new(callback));
+        if old_listener.is_none() {
+            self.sender
+                .send(ToVersoMessage::ListenToOnResourceLimitExceeded)?;
+        }
         Ok(())
     }
 
-    /// Moves the window with the left mouse button until the button is released
-    pub fn start_dragging(&self) -> Result<(), Box<ipc_channel::ErrorKind>> {
-        self.sender.send(ToVersoMessage::StartDragging)?;
+    /// Listen on the progress of downloads. Links and navigations to files are downloaded without
+    /// the control panel too once this is set, after asking the user where to save them.
+    pub fn on_download_updated(
+        &self,
+        callback: impl Fn(DownloadUpdate) + Send + 'static,
+    ) -> Result<(), Box<ipc_channel::ErrorKind>> {
+        let old_listener = self
+            .event_listeners
+            .on_download_updated
+            .lock()
+            .unwrap()
+            .replace(Box::new(callback));
+        if old_listener.is_none() {
+            self.sender
+                .send(ToVersoMessage::ListenToOnDownloadUpdated)?;
+        }
         Ok(())
     }
 
-    /// Bring the window to the front, and capture input focus
-    pub fn focus(&self) -> Result<(), Box<ipc_channel::ErrorKind>> {
-        self.sender.send(ToVersoMessage::Focus)?;
-        Ok(())
+    /// Pause a download, the rest of the file is held back by the server until it's resumed
+    pub fn pause_download(&self, id: impl Into<String>) -> Result<(), Box<ipc_channel::ErrorKind>> {
+        self.sender.send(ToVersoMessage::PauseDownload(id.into()))
     }
 
-    /// Get the window's size
-    fn get_size(
+    /// Resume a paused download
+    pub fn resume_download(
         &self,
-        size_type: SizeType,
-    ) -> Result<PhysicalSize<u32>, Box<ipc_channel::ErrorKind>> {
-        let id = uuid::Uuid::new_v4();
-        let (sender, receiver) = std::sync::mpsc::channel();
-        self.event_listeners
-            .size_response
-            .lock()
-            .unwrap()
-            .insert(id, sender);
-        if let Err(error) = self.sender.send(ToVersoMessage::GetSize(id, size_type)) {
-            self.event_listeners
-                .size_response
-                .lock()
-                .unwrap()
-                .remove(&id);
-            return Err(error);
-        };
-        Ok(receiver.recv().unwrap())
+        id: impl Into<String>,
+    ) -> Result<(), Box<ipc_channel::ErrorKind>> {
+        self.sender.send(ToVersoMessage::ResumeDownload(id.into()))
     }
 
-    /// Returns the physical size of the window's client area.
-    ///
-    /// The client area is the content of the window, excluding the title bar and borders.
-    pub fn get_inner_size(&self) -> Result<PhysicalSize<u32>, Box<ipc_channel::ErrorKind>> {
-        self.get_size(SizeType::Inner)
+    /// Cancel a download and remove the partly written file
+    pub fn cancel_download(
+        &self,
+        id: impl Into<String>,
+    ) -> Result<(), Box<ipc_channel::ErrorKind>> {
+        self.sender.send(ToVersoMessage::CancelDownload(id.into()))
     }
 
-    /// Returns the physical size of the entire window.
-    ///
-    /// These dimensions include the title bar and borders.
-    /// If you don't want that (and you usually don't), use [`Self::get_inner_size`] instead.
-    pub fn get_outer_size(&self) -> Result<PhysicalSize<u32>, Box<ipc_channel::ErrorKind>> {
-        self.get_size(SizeType::Outer)
+    /// Listen on changes of the config at runtime, e.g. to keep a settings UI in sync with
+    /// [`Self::update_config`] calls made elsewhere
+    pub fn on_config_changed(
+        &self,
+        callback: impl Fn(VersoviewSettings) + Send + 'static,
+    ) -> Result<(), Box<ipc_channel::ErrorKind>> {
+        let old_listener = self
+            .event_listeners
+            .on_config_changed
+            .lock()
+            .unwrap()
+            .replace(Box::new(callback));
+        if old_listener.is_none() {
+            self.sender.send(ToVersoMessage::ListenToOnConfigChanged)?;
+        }
+        Ok(())
     }
 
-    /// Get the window's position,
-    /// returns [`None`] on unsupported platforms (currently only Wayland)
+    /// Show the dialogs of webviews, i.e. JavaScript dialogs, HTTP authentication, permission
+    /// prompts and file pickers, with the delegate instead of the built-in ones of Verso
+    pub fn set_dialog_delegate(
+        &self,
+        delegate: impl DialogDelegate,
+    ) -> Result<(), Box<ipc_channel::ErrorKind>> {
+        let old_delegate = self
+            .event_listeners
+            .dialog_delegate
+            .lock()
+            .unwrap()
+            .replace(Box::new(delegate));
+        if old_delegate.is_none() {
+            self.sender.send(ToVersoMessage::ListenToOnDialog)?;
+        }
+        Ok(())
+    }
+
+    /// Set the budgets of script time, requests and download size of a webview, for hosts running
+    /// several untrusted pages side by side. [`ResourceLimits::default`] removes them.
+    pub fn set_resource_limits(
+        &self,
+        webview_id: SerializedWebViewId,
+        limits: ResourceLimits,
+    ) -> Result<(), Box<ipc_channel::ErrorKind>> {
+        self.sender
+            .send(ToVersoMessage::SetResourceLimits(webview_id, limits))
+    }
+
+    /// Set the page lifecycle state of a webview, for embedders managing their own tab-like UI.
+    /// `visibilitychange`, `freeze` and `resume` are dispatched to the page and its rendering is
+    /// throttled unless it's visible. Verso doesn't change the state of this webview by itself
+    /// afterwards.
+    pub fn set_lifecycle_state(
+        &self,
+        webview_id: SerializedWebViewId,
+        state: LifecycleState,
+    ) -> Result<(), Box<ipc_channel::ErrorKind>> {
+        self.sender
+            .send(ToVersoMessage::SetLifecycleState(webview_id, state))
+    }
+
+    /// Keep the display awake while the webview is opened, e.g. while it's playing a video or
+    /// presenting. Animations aren't throttled for idleness meanwhile.
+    pub fn set_sleep_inhibited(
+        &self,
+        webview_id: SerializedWebViewId,
+        inhibited: bool,
+    ) -> Result<(), Box<ipc_channel::ErrorKind>> {
+        self.sender
+            .send(ToVersoMessage::SetSleepInhibited(webview_id, inhibited))
+    }
+
+    /// Close a webview, e.g. one whose script is hung
+    pub fn close_webview(
+        &self,
+        webview_id: SerializedWebViewId,
+    ) -> Result<(), Box<ipc_channel::ErrorKind>> {
+        self.sender.send(ToVersoMessage::CloseWebView(webview_id))
+    }
+
+    /// Report this position to pages instead of the one of the system, `None` to use the system
+    /// again
+    pub fn set_mock_geolocation(
+        &self,
+        position: Option<GeolocationPosition>,
+    ) -> Result<(), Box<ipc_channel::ErrorKind>> {
+        self.sender
+            .send(ToVersoMessage::SetMockGeolocation(position))
+    }
+
+    /// Mute or unmute all media of a webview
+    pub fn set_muted(
+        &self,
+        webview_id: SerializedWebViewId,
+        muted: bool,
+    ) -> Result<(), Box<ipc_channel::ErrorKind>> {
+        self.sender
+            .send(ToVersoMessage::SetMuted(webview_id, muted))
+    }
+
+    /// Register a command to the command palette, the callback is called when it's executed,
+    /// registering with an existing id replaces the old command, except for the ids of Verso's built-in commands
+    pub fn register_command(
+        &self,
+        id: String,
+        title: String,
+        callback: impl Fn() + Send + 'static,
+    ) -> Result<(), Box<ipc_channel::ErrorKind>> {
+        self.event_listeners
+            .commands
+            .lock()
+            .unwrap()
+            .insert(id.clone(), Box::new(callback));
+        self.sender.send(ToVersoMessage::RegisterCommand(id, title))
+    }
+
+    /// Remove a command registered with [`VersoviewController::register_command`]
+    pub fn unregister_command(&self, id: String) -> Result<(), Box<ipc_channel::ErrorKind>> {
+        self.event_listeners.commands.lock().unwrap().remove(&id);
+        self.sender.send(ToVersoMessage::UnregisterCommand(id))
+    }
+
+    /// Execute a command by its id, the same way as picking it from the command palette,
+    /// e.g. `navigation.reload`, `zoom.in` or a command registered with [`VersoviewController::register_command`]
+    pub fn execute_command(&self, id: String) -> Result<(), Box<ipc_channel::ErrorKind>> {
+        self.sender.send(ToVersoMessage::ExecuteCommand(id))
+    }
+
+    /// Serve a URL from a local file or in-memory bytes instead of the network,
+    /// replacing the existing override of the same URL
+    pub fn set_resource_override(
+        &self,
+        resource_override: ResourceOverride,
+    ) -> Result<(), Box<ipc_channel::ErrorKind>> {
+        self.sender
+            .send(ToVersoMessage::SetResourceOverride(resource_override))
+    }
+
+    /// Remove the override of this URL set with [`VersoviewController::set_resource_override`]
+    pub fn remove_resource_override(
+        &self,
+        url: url::Url,
+    ) -> Result<(), Box<ipc_channel::ErrorKind>> {
+        self.sender
+            .send(ToVersoMessage::RemoveResourceOverride(url))
+    }
+
+    /// Enable or disable mock network mode for hermetic tests. Network requests are only answered
+    /// by the [`VersoviewController::on_web_resource_requested`] callback, and fail if it responds
+    /// with `None` or isn't set
+    pub fn set_mock_network(&self, enabled: bool) -> Result<(), Box<ipc_channel::ErrorKind>> {
+        self.sender.send(ToVersoMessage::SetMockNetwork(enabled))
+    }
+
+    /// Answer the file pickers of `<input type="file">` with the files they accept among these
+    /// instead of showing a dialog, for tests or embedders choosing the files themselves. Pickers
+    /// of a single file get the first one, and cancel if none of the files is accepted. Show
+    /// dialogs again with `None`
+    pub fn set_file_picker_override(
+        &self,
+        paths: Option<Vec<PathBuf>>,
+    ) -> Result<(), Box<ipc_channel::ErrorKind>> {
+        self.sender
+            .send(ToVersoMessage::SetFilePickerOverride(paths))
+    }
+
+    /// Set how every window waits for the vertical blank before presenting a frame, e.g.
+    /// [`Vsync::Immediate`] to measure how fast pages render
+    pub fn set_vsync(&self, vsync: Vsync) -> Result<(), Box<ipc_channel::ErrorKind>> {
+        self.sender.send(ToVersoMessage::SetVsync(vsync))
+    }
+
+    /// Rotate the output of every window clockwise, pages are laid out again for the rotated
+    /// size and input is rotated with them
+    pub fn set_rotation(&self, rotation: Rotation) -> Result<(), Box<ipc_channel::ErrorKind>> {
+        self.sender.send(ToVersoMessage::SetRotation(rotation))
+    }
+
+    /// Change what Verso keeps of the cookies pages set, see [`CookiePolicy`]
+    pub fn set_cookie_policy(
+        &self,
+        cookie_policy: CookiePolicy,
+    ) -> Result<(), Box<ipc_channel::ErrorKind>> {
+        self.sender
+            .send(ToVersoMessage::SetCookiePolicy(cookie_policy))
+    }
+
+    /// Load the pages of the host even if its certificate is invalid, until Verso quits, e.g. for
+    /// a device with a self-signed certificate. Pages loaded afterwards proceed without asking
+    pub fn allow_certificate_override(
+        &self,
+        host: impl Into<String>,
+    ) -> Result<(), Box<ipc_channel::ErrorKind>> {
+        self.sender
+            .send(ToVersoMessage::AllowCertificateOverride(host.into()))
+    }
+
+    /// Change whether users can proceed to sites with an invalid certificate, see
+    /// [`CertificateErrorPolicy`]
+    pub fn set_certificate_error_policy(
+        &self,
+        policy: CertificateErrorPolicy,
+    ) -> Result<(), Box<ipc_channel::ErrorKind>> {
+        self.sender
+            .send(ToVersoMessage::SetCertificateErrorPolicy(policy))
+    }
+
+    /// Run JavaScript on the pages of origins without a setting of their own, or not. Pages loaded
+    /// afterwards use it, see [`JavaScriptSettings`]
+    pub fn set_javascript_enabled(&self, enabled: bool) -> Result<(), Box<ipc_channel::ErrorKind>> {
+        self.sender
+            .send(ToVersoMessage::SetJavaScriptEnabled(enabled))
+    }
+
+    /// Run JavaScript on the pages of the origin of the URL, or not, regardless of
+    /// [`Self::set_javascript_enabled`], or remove the setting of the origin if `None`
+    pub fn set_origin_javascript_enabled(
+        &self,
+        origin: url::Url,
+        enabled: Option<bool>,
+    ) -> Result<(), Box<ipc_channel::ErrorKind>> {
+        self.sender
+            .send(ToVersoMessage::SetOriginJavaScriptEnabled(origin, enabled))
+    }
+
+    /// Change which images pages load and whether to save data on them, see [`ImagePolicy`]. It's
+    /// saved in the profile
+    pub fn set_image_policy(&self, policy: ImagePolicy) -> Result<(), Box<ipc_channel::ErrorKind>> {
+        self.sender.send(ToVersoMessage::SetImagePolicy(policy))
+    }
+
+    /// Change when pages run their CSS animations and animate their images, see
+    /// [`AnimationPolicy`]
+    pub fn set_animation_policy(
+        &self,
+        policy: AnimationPolicy,
+    ) -> Result<(), Box<ipc_channel::ErrorKind>> {
+        self.sender.send(ToVersoMessage::SetAnimationPolicy(policy))
+    }
+
+    /// Sets the webview window's size
+    pub fn set_size<S: Into<Size>>(&self, size: S) -> Result<(), Box<ipc_channel::ErrorKind>> {
+        self.sender.send(ToVersoMessage::SetSize(size.into()))?;
+        Ok(())
+    }
+
+    /// Sets the webview window's position
+    pub fn set_position<P: Into<Position>>(
+        &self,
+        position: P,
+    ) -> Result<(), Box<ipc_channel::ErrorKind>> {
+        self.sender
+            .send(ToVersoMessage::SetPosition(position.into()))?;
+        Ok(())
+    }
+
+    /// Maximize or unmaximize the window
+    pub fn set_maximized(&self, maximized: bool) -> Result<(), Box<ipc_channel::ErrorKind>> {
+        self.sender.send(ToVersoMessage::SetMaximized(maximized))?;
+        Ok(())
+    }
+
+    /// Minimize or unminimize the window
+    pub fn set_minimized(&self, minimized: bool) -> Result<(), Box<ipc_channel::ErrorKind>> {
+        self.sender.send(ToVersoMessage::SetMinimized(minimized))?;
+        Ok(())
+    }
+
+    /// Sets the window to fullscreen or back
+    pub fn set_fullscreen(&self, fullscreen: bool) -> Result<(), Box<ipc_channel::ErrorKind>> {
+        self.sender
+            .send(ToVersoMessage::SetFullscreen(fullscreen))?;
+        Ok(())
+    }
+
+    /// Show or hide the window
+    pub fn set_visible(&self, visible: bool) -> Result<(), Box<ipc_channel::ErrorKind>> {
+        self.sender.send(ToVersoMessage::SetVisible(visible))?;
+        Ok(())
+    }
+
+    /// Moves the window with the left mouse button until the button is released
+    pub fn start_dragging(&self) -> Result<(), Box<ipc_channel::ErrorKind>> {
+        self.sender.send(ToVersoMessage::StartDragging)?;
+        Ok(())
+    }
+
+    /// Bring the window to the front, and capture input focus
+    pub fn focus(&self) -> Result<(), Box<ipc_channel::ErrorKind>> {
+        self.sender.send(ToVersoMessage::Focus)?;
+        Ok(())
+    }
+
+    /// Get the window's size
+    fn get_size(
+        &self,
+        size_type: SizeType,
+    ) -> Result<PhysicalSize<u32>, Box<ipc_channel::ErrorKind>> {
+        let id = uuid::Uuid::new_v4();
+        let (sender, receiver) = std::sync::mpsc::channel();
+        self.event_listeners
+            .size_response
+            .lock()
+            .unwrap()
+            .insert(id, sender);
+        if let Err(error) = self.sender.send(ToVersoMessage::GetSize(id, size_type)) {
+            self.event_listeners
+                .size_response
+                .lock()
+                .unwrap()
+                .remove(&id);
+            return Err(error);
+        };
+        Ok(receiver.recv().unwrap())
+    }
+
+    /// Returns the physical size of the window's client area.
+    ///
+    /// The client area is the content of the window, excluding the title bar and borders.
+    pub fn get_inner_size(&self) -> Result<PhysicalSize<u32>, Box<ipc_channel::ErrorKind>> {
+        self.get_size(SizeType::Inner)
+    }
+
+    /// Returns the physical size of the entire window.
+    ///
+    /// These dimensions include the title bar and borders.
+    /// If you don't want that (and you usually don't), use [`Self::get_inner_size`] instead.
+    pub fn get_outer_size(&self) -> Result<PhysicalSize<u32>, Box<ipc_channel::ErrorKind>> {
+        self.get_size(SizeType::Outer)
+    }
+
+    /// Get the window's position,
+    /// returns [`None`] on unsupported platforms (currently only Wayland)
     fn get_position(
         &self,
         position_type: PositionType,
@@ -510,6 +1262,696 @@ impl VersoviewController {
         Ok(receiver.recv().unwrap())
     }
 
+    /// Get the visible text of the current page, or the `innerText` of the first element matching
+    /// the CSS selector, e.g. for search indexing. Returns `None` if no element matches.
+    pub fn extract_text(
+        &self,
+        selector: Option<&str>,
+    ) -> Result<Option<String>, Box<ipc_channel::ErrorKind>> {
+        let id = uuid::Uuid::new_v4();
+        let (sender, receiver) = std::sync::mpsc::channel();
+        self.event_listeners
+            .extract_text_response
+            .lock()
+            .unwrap()
+            .insert(id, sender);
+        if let Err(error) = self.sender.send(ToVersoMessage::ExtractText(
+            id,
+            selector.map(ToString::to_string),
+        )) {
+            self.event_listeners
+                .extract_text_response
+                .lock()
+                .unwrap()
+                .remove(&id);
+            return Err(error);
+        };
+        Ok(receiver.recv().unwrap())
+    }
+
+    /// Convert the readable content of the current page to Markdown and write it to the file, e.g.
+    /// for note-taking. Returns the error if the page couldn't be converted or the file written.
+    pub fn save_as_markdown(
+        &self,
+        path: impl Into<PathBuf>,
+    ) -> Result<Result<(), String>, Box<ipc_channel::ErrorKind>> {
+        let id = uuid::Uuid::new_v4();
+        let (sender, receiver) = std::sync::mpsc::channel();
+        self.event_listeners
+            .save_as_markdown_response
+            .lock()
+            .unwrap()
+            .insert(id, sender);
+        if let Err(error) = self
+            .sender
+            .send(ToVersoMessage::SaveAsMarkdown(id, path.into()))
+        {
+            self.event_listeners
+                .save_as_markdown_response
+                .lock()
+                .unwrap()
+                .remove(&id);
+            return Err(error);
+        };
+        Ok(receiver.recv().unwrap())
+    }
+
+    /// Take a screenshot of the first window, composited with the pending changes of its
+    /// webviews. Returns the PNG image, or `None` if it couldn't be captured.
+    pub fn capture_frame(&self) -> Result<Option<Vec<u8>>, Box<ipc_channel::ErrorKind>> {
+        let id = uuid::Uuid::new_v4();
+        let (sender, receiver) = std::sync::mpsc::channel();
+        self.event_listeners
+            .capture_frame_response
+            .lock()
+            .unwrap()
+            .insert(id, sender);
+        if let Err(error) = self.sender.send(ToVersoMessage::CaptureFrame(id)) {
+            self.event_listeners
+                .capture_frame_response
+                .lock()
+                .unwrap()
+                .remove(&id);
+            return Err(error);
+        };
+        Ok(receiver.recv().unwrap())
+    }
+
+    /// Block until the condition is met in the current page, e.g. navigating to a URL or an element
+    /// appearing, so clients don't have to poll. Returns `false` if the timeout is over first.
+    pub fn wait_for(
+        &self,
+        condition: WaitCondition,
+        timeout: Duration,
+    ) -> Result<bool, Box<ipc_channel::ErrorKind>> {
+        let id = uuid::Uuid::new_v4();
+        let (sender, receiver) = std::sync::mpsc::channel();
+        self.event_listeners
+            .wait_for_response
+            .lock()
+            .unwrap()
+            .insert(id, sender);
+        if let Err(error) = self.sender.send(ToVersoMessage::WaitFor(
+            id,
+            condition,
+            timeout.as_millis() as u64,
+        )) {
+            self.event_listeners
+                .wait_for_response
+                .lock()
+                .unwrap()
+                .remove(&id);
+            return Err(error);
+        };
+        Ok(receiver.recv().unwrap())
+    }
+
+    /// Find the text in the current page, highlight the matches and scroll to the first one, like
+    /// the find bar opened with Ctrl/Cmd+F. Finding the same text with the same options again
+    /// moves to the next match, or the previous one with [`FindOptions::backwards`].
+    pub fn find(
+        &self,
+        text: impl Into<String>,
+        options: FindOptions,
+    ) -> Result<FindResult, Box<ipc_channel::ErrorKind>> {
+        let id = uuid::Uuid::new_v4();
+        let (sender, receiver) = std::sync::mpsc::channel();
+        self.event_listeners
+            .find_response
+            .lock()
+            .unwrap()
+            .insert(id, sender);
+        if let Err(error) = self
+            .sender
+            .send(ToVersoMessage::Find(id, Some(text.into()), options))
+        {
+            self.event_listeners
+                .find_response
+                .lock()
+                .unwrap()
+                .remove(&id);
+            return Err(error);
+        };
+        Ok(receiver.recv().unwrap())
+    }
+
+    /// Remove the highlights of [`VersoviewController::find`] from the current page.
+    pub fn stop_finding(&self) -> Result<(), Box<ipc_channel::ErrorKind>> {
+        self.sender.send(ToVersoMessage::Find(
+            uuid::Uuid::new_v4(),
+            None,
+            FindOptions::default(),
+        ))
+    }
+
+    /// Search the history by the text of the visited pages, the best matches first. An empty
+    /// query returns the most recently visited pages.
+    pub fn search_history(
+        &self,
+        query: impl ToString,
+    ) -> Result<Vec<HistoryMatch>, Box<ipc_channel::ErrorKind>> {
+        let id = uuid::Uuid::new_v4();
+        let (sender, receiver) = std::sync::mpsc::channel();
+        self.event_listeners
+            .search_history_response
+            .lock()
+            .unwrap()
+            .insert(id, sender);
+        if let Err(error) = self
+            .sender
+            .send(ToVersoMessage::SearchHistory(id, query.to_string()))
+        {
+            self.event_listeners
+                .search_history_response
+                .lock()
+                .unwrap()
+                .remove(&id);
+            return Err(error);
+        };
+        Ok(receiver.recv().unwrap())
+    }
+
+    /// Suggest visited pages for the text typed in a URL bar, the pages visited most often and
+    /// most recently first. Pages whose URL starts with the text, without the scheme and `www.`,
+    /// come first, to complete the URL inline.
+    pub fn suggest_history(
+        &self,
+        input: impl ToString,
+    ) -> Result<Vec<HistoryMatch>, Box<ipc_channel::ErrorKind>> {
+        let id = uuid::Uuid::new_v4();
+        let (sender, receiver) = std::sync::mpsc::channel();
+        self.event_listeners
+            .suggest_history_response
+            .lock()
+            .unwrap()
+            .insert(id, sender);
+        if let Err(error) = self
+            .sender
+            .send(ToVersoMessage::SuggestHistory(id, input.to_string()))
+        {
+            self.event_listeners
+                .suggest_history_response
+                .lock()
+                .unwrap()
+                .remove(&id);
+            return Err(error);
+        };
+        Ok(receiver.recv().unwrap())
+    }
+
+    /// Forget the pages last visited since the time, in milliseconds since the Unix epoch, or
+    /// clear the whole history if `None`.
+    pub fn clear_history(&self, since: Option<i64>) -> Result<(), Box<ipc_channel::ErrorKind>> {
+        self.sender.send(ToVersoMessage::ClearHistory(since))
+    }
+
+    /// Delete the cookies of the site of the origin, e.g. `https://example.com` also clears the
+    /// ones of its subdomains, or every cookie if `None`.
+    pub fn clear_cookies(
+        &self,
+        origin: Option<url::Url>,
+    ) -> Result<(), Box<ipc_channel::ErrorKind>> {
+        self.sender.send(ToVersoMessage::ClearCookies(origin))
+    }
+
+    /// Remove every response of the HTTP cache of the profile
+    pub fn clear_cache(&self) -> Result<(), Box<ipc_channel::ErrorKind>> {
+        self.sender.send(ToVersoMessage::ClearCache)
+    }
+
+    /// Copy the selection of the focused element of the current tab
+    pub fn copy(&self) -> Result<(), Box<ipc_channel::ErrorKind>> {
+        self.sender
+            .send(ToVersoMessage::Clipboard(ClipboardAction::Copy))
+    }
+
+    /// Cut the selection of the focused element of the current tab
+    pub fn cut(&self) -> Result<(), Box<ipc_channel::ErrorKind>> {
+        self.sender
+            .send(ToVersoMessage::Clipboard(ClipboardAction::Cut))
+    }
+
+    /// Paste the clipboard in the focused element of the current tab
+    pub fn paste(&self) -> Result<(), Box<ipc_channel::ErrorKind>> {
+        self.sender
+            .send(ToVersoMessage::Clipboard(ClipboardAction::Paste))
+    }
+
+    /// Paste the text of the clipboard without its formatting in the focused element of the
+    /// current tab
+    pub fn paste_as_plain_text(&self) -> Result<(), Box<ipc_channel::ErrorKind>> {
+        self.sender
+            .send(ToVersoMessage::Clipboard(ClipboardAction::PasteAsPlainText))
+    }
+
+    /// Create a tab group from opened webviews, returns the id of the new group
+    pub fn create_tab_group(
+        &self,
+        name: String,
+        webview_ids: Vec<SerializedWebViewId>,
+    ) -> Result<uuid::Uuid, Box<ipc_channel::ErrorKind>> {
+        let id = uuid::Uuid::new_v4();
+        self.sender
+            .send(ToVersoMessage::CreateTabGroup(id, name, webview_ids))?;
+        Ok(id)
+    }
+
+    /// Remove a tab group, its webviews are kept opened
+    pub fn remove_tab_group(&self, id: uuid::Uuid) -> Result<(), Box<ipc_channel::ErrorKind>> {
+        self.sender.send(ToVersoMessage::RemoveTabGroup(id))
+    }
+
+    /// Close the webviews of a tab group, their URLs are kept in the session
+    pub fn collapse_tab_group(&self, id: uuid::Uuid) -> Result<(), Box<ipc_channel::ErrorKind>> {
+        self.sender.send(ToVersoMessage::CollapseTabGroup(id))
+    }
+
+    /// Reopen the webviews of a collapsed tab group
+    pub fn restore_tab_group(&self, id: uuid::Uuid) -> Result<(), Box<ipc_channel::ErrorKind>> {
+        self.sender.send(ToVersoMessage::RestoreTabGroup(id))
+    }
+
+    /// Get all tab groups, including the ones saved from previous sessions
+    pub fn get_tab_groups(&self) -> Result<Vec<TabGroupInfo>, Box<ipc_channel::ErrorKind>> {
+        let id = uuid::Uuid::new_v4();
+        let (sender, receiver) = std::sync::mpsc::channel();
+        self.event_listeners
+            .get_tab_groups_response
+            .lock()
+            .unwrap()
+            .insert(id, sender);
+        if let Err(error) = self.sender.send(ToVersoMessage::GetTabGroups(id)) {
+            self.event_listeners
+                .get_tab_groups_response
+                .lock()
+                .unwrap()
+                .remove(&id);
+            return Err(error);
+        };
+        Ok(receiver.recv().unwrap())
+    }
+
+    /// Save a secret in the OS keychain, replacing the existing one of the same key
+    pub fn set_secret(
+        &self,
+        key: impl ToString,
+        secret: impl ToString,
+    ) -> Result<(), Box<ipc_channel::ErrorKind>> {
+        self.sender.send(ToVersoMessage::SetSecret(
+            key.to_string(),
+            secret.to_string(),
+        ))
+    }
+
+    /// Get a secret from the OS keychain, `None` if it doesn't exist
+    pub fn get_secret(
+        &self,
+        key: impl ToString,
+    ) -> Result<Option<String>, Box<ipc_channel::ErrorKind>> {
+        let id = uuid::Uuid::new_v4();
+        let (sender, receiver) = std::sync::mpsc::channel();
+        self.event_listeners
+            .get_secret_response
+            .lock()
+            .unwrap()
+            .insert(id, sender);
+        if let Err(error) = self
+            .sender
+            .send(ToVersoMessage::GetSecret(id, key.to_string()))
+        {
+            self.event_listeners
+                .get_secret_response
+                .lock()
+                .unwrap()
+                .remove(&id);
+            return Err(error);
+        };
+        Ok(receiver.recv().unwrap())
+    }
+
+    /// Delete a secret from the OS keychain
+    pub fn delete_secret(&self, key: impl ToString) -> Result<(), Box<ipc_channel::ErrorKind>> {
+        self.sender
+            .send(ToVersoMessage::DeleteSecret(key.to_string()))
+    }
+
+    /// Set the referrer policy and Do-Not-Track settings of a webview, `None` to use the ones of
+    /// the config again. They are applied from the next document loaded in the webview.
+    pub fn set_webview_privacy(
+        &self,
+        webview_id: SerializedWebViewId,
+        privacy: Option<PrivacySettings>,
+    ) -> Result<(), Box<ipc_channel::ErrorKind>> {
+        self.sender
+            .send(ToVersoMessage::SetWebViewPrivacy(webview_id, privacy))
+    }
+
+    /// Run an OAuth flow in a popup window: open the authorization URL and wait for a redirect to
+    /// the callback URL, then close the popup and return the redirect with its parameters.
+    /// The request to the callback URL is never sent, so it doesn't need a server listening on it.
+    ///
+    /// Blocks until the flow is done, returns `None` if the user closes the popup before that.
+    pub fn authenticate(
+        &self,
+        auth_url: url::Url,
+        callback_url: url::Url,
+    ) -> Result<Option<AuthCallback>, Box<ipc_channel::ErrorKind>> {
+        let id = uuid::Uuid::new_v4();
+        let (sender, receiver) = std::sync::mpsc::channel();
+        self.event_listeners
+            .auth_flow_response
+            .lock()
+            .unwrap()
+            .insert(id, sender);
+        if let Err(error) =
+            self.sender
+                .send(ToVersoMessage::StartAuthFlow(id, auth_url, callback_url))
+        {
+            self.event_listeners
+                .auth_flow_response
+                .lock()
+                .unwrap()
+                .remove(&id);
+            return Err(error);
+        };
+        Ok(receiver.recv().unwrap())
+    }
+
+    /// Get the frame tree of a webview, with the ids to address its frames with
+    /// [`Self::execute_script_in_frame`] and [`Self::send_frame_input`]. Returns `None` if the
+    /// webview doesn't exist, frames whose script is hung are left out.
+    pub fn get_frame_tree(
+        &self,
+        webview_id: SerializedWebViewId,
+    ) -> Result<Option<FrameInfo>, Box<ipc_channel::ErrorKind>> {
+        let id = uuid::Uuid::new_v4();
+        let (sender, receiver) = std::sync::mpsc::channel();
+        self.event_listeners
+            .get_frame_tree_response
+            .lock()
+            .unwrap()
+            .insert(id, sender);
+        if let Err(error) = self
+            .sender
+            .send(ToVersoMessage::GetFrameTree(id, webview_id))
+        {
+            self.event_listeners
+                .get_frame_tree_response
+                .lock()
+                .unwrap()
+                .remove(&id);
+            return Err(error);
+        };
+        Ok(receiver.recv().unwrap())
+    }
+
+    /// Evaluate a script in the global scope of a frame, and return its result as JSON or the
+    /// error it threw. Works for cross-origin frames too.
+    pub fn execute_script_in_frame(
+        &self,
+        frame_id: SerializedFrameId,
+        script: impl ToString,
+    ) -> Result<Result<String, String>, Box<ipc_channel::ErrorKind>> {
+        let id = uuid::Uuid::new_v4();
+        let (sender, receiver) = std::sync::mpsc::channel();
+        self.event_listeners
+            .execute_script_in_frame_response
+            .lock()
+            .unwrap()
+            .insert(id, sender);
+        if let Err(error) = self.sender.send(ToVersoMessage::ExecuteScriptInFrame(
+            id,
+            frame_id,
+            script.to_string(),
+        )) {
+            self.event_listeners
+                .execute_script_in_frame_response
+                .lock()
+                .unwrap()
+                .remove(&id);
+            return Err(error);
+        };
+        Ok(receiver.recv().unwrap())
+    }
+
+    /// Dispatch input events to the document of a frame, e.g. to click a button. They are
+    /// synthetic DOM events, so pages see them with `isTrusted` set to `false`.
+    pub fn send_frame_input(
+        &self,
+        frame_id: SerializedFrameId,
+        input: FrameInput,
+    ) -> Result<(), Box<ipc_channel::ErrorKind>> {
+        self.sender
+            .send(ToVersoMessage::SendFrameInput(frame_id, input))
+    }
+
+    /// Get the webviews of every window, the tabs of a window in tab strip order
+    pub fn get_webviews(&self) -> Result<Vec<SerializedWebViewId>, Box<ipc_channel::ErrorKind>> {
+        let id = uuid::Uuid::new_v4();
+        let (sender, receiver) = std::sync::mpsc::channel();
+        self.event_listeners
+            .get_webviews_response
+            .lock()
+            .unwrap()
+            .insert(id, sender);
+        if let Err(error) = self.sender.send(ToVersoMessage::GetWebViews(id)) {
+            self.event_listeners
+                .get_webviews_response
+                .lock()
+                .unwrap()
+                .remove(&id);
+            return Err(error);
+        };
+        Ok(receiver.recv().unwrap())
+    }
+
+    /// Name a webview, or remove its name if `None`. Names are unique and kept in the session,
+    /// so the webview can be found again with [`Self::get_webview_by_name`] after a restart.
+    pub fn set_webview_name(
+        &self,
+        webview_id: SerializedWebViewId,
+        name: Option<String>,
+    ) -> Result<(), Box<ipc_channel::ErrorKind>> {
+        self.sender
+            .send(ToVersoMessage::SetWebViewName(webview_id, name))
+    }
+
+    /// Get the webview with this name, `None` if no webview has it
+    pub fn get_webview_by_name(
+        &self,
+        name: impl Into<String>,
+    ) -> Result<Option<SerializedWebViewId>, Box<ipc_channel::ErrorKind>> {
+        let id = uuid::Uuid::new_v4();
+        let (sender, receiver) = std::sync::mpsc::channel();
+        self.event_listeners
+            .get_webview_by_name_response
+            .lock()
+            .unwrap()
+            .insert(id, sender);
+        if let Err(error) = self
+            .sender
+            .send(ToVersoMessage::GetWebViewByName(id, name.into()))
+        {
+            self.event_listeners
+                .get_webview_by_name_response
+                .lock()
+                .unwrap()
+                .remove(&id);
+            return Err(error);
+        };
+        Ok(receiver.recv().unwrap())
+    }
+
+    /// List the names of the profiles that can be run with
+    /// [`VersoBuilder::profile`](crate::VersoBuilder::profile) or `--profile`.
+    pub fn list_profiles(&self) -> Result<Vec<String>, Box<ipc_channel::ErrorKind>> {
+        let id = uuid::Uuid::new_v4();
+        let (sender, receiver) = std::sync::mpsc::channel();
+        self.event_listeners
+            .list_profiles_response
+            .lock()
+            .unwrap()
+            .insert(id, sender);
+        if let Err(error) = self.sender.send(ToVersoMessage::ListProfiles(id)) {
+            self.event_listeners
+                .list_profiles_response
+                .lock()
+                .unwrap()
+                .remove(&id);
+            return Err(error);
+        };
+        Ok(receiver.recv().unwrap())
+    }
+
+    /// Create an empty profile. Names are made of letters, digits, `-`, `_` and `.`, and don't
+    /// start with a `.`. Returns the error if the profile couldn't be created.
+    pub fn create_profile(
+        &self,
+        name: impl Into<String>,
+    ) -> Result<Result<(), String>, Box<ipc_channel::ErrorKind>> {
+        let id = uuid::Uuid::new_v4();
+        let (sender, receiver) = std::sync::mpsc::channel();
+        self.event_listeners
+            .create_profile_response
+            .lock()
+            .unwrap()
+            .insert(id, sender);
+        if let Err(error) = self
+            .sender
+            .send(ToVersoMessage::CreateProfile(id, name.into()))
+        {
+            self.event_listeners
+                .create_profile_response
+                .lock()
+                .unwrap()
+                .remove(&id);
+            return Err(error);
+        };
+        Ok(receiver.recv().unwrap())
+    }
+
+    /// Delete a profile with its cookies, cache, history and settings. The profile this instance
+    /// runs in can't be deleted. Returns the error if the profile couldn't be deleted.
+    pub fn delete_profile(
+        &self,
+        name: impl Into<String>,
+    ) -> Result<Result<(), String>, Box<ipc_channel::ErrorKind>> {
+        let id = uuid::Uuid::new_v4();
+        let (sender, receiver) = std::sync::mpsc::channel();
+        self.event_listeners
+            .delete_profile_response
+            .lock()
+            .unwrap()
+            .insert(id, sender);
+        if let Err(error) = self
+            .sender
+            .send(ToVersoMessage::DeleteProfile(id, name.into()))
+        {
+            self.event_listeners
+                .delete_profile_response
+                .lock()
+                .unwrap()
+                .remove(&id);
+            return Err(error);
+        };
+        Ok(receiver.recv().unwrap())
+    }
+
+    /// List the bookmarks, in the order they were added.
+    pub fn list_bookmarks(&self) -> Result<Vec<BookmarkInfo>, Box<ipc_channel::ErrorKind>> {
+        let id = uuid::Uuid::new_v4();
+        let (sender, receiver) = std::sync::mpsc::channel();
+        self.event_listeners
+            .list_bookmarks_response
+            .lock()
+            .unwrap()
+            .insert(id, sender);
+        if let Err(error) = self.sender.send(ToVersoMessage::ListBookmarks(id)) {
+            self.event_listeners
+                .list_bookmarks_response
+                .lock()
+                .unwrap()
+                .remove(&id);
+            return Err(error);
+        };
+        Ok(receiver.recv().unwrap())
+    }
+
+    /// Bookmark a page, or move and rename its bookmark if it's already bookmarked.
+    pub fn add_bookmark(&self, bookmark: BookmarkInfo) -> Result<(), Box<ipc_channel::ErrorKind>> {
+        self.sender.send(ToVersoMessage::AddBookmark(bookmark))
+    }
+
+    /// Remove the bookmark of the URL.
+    pub fn remove_bookmark(&self, url: impl ToString) -> Result<(), Box<ipc_channel::ErrorKind>> {
+        self.sender
+            .send(ToVersoMessage::RemoveBookmark(url.to_string()))
+    }
+
+    /// Import the bookmarks of a file in the Netscape bookmarks HTML format, which other browsers
+    /// export. Returns the number of bookmarks added, pages already bookmarked are skipped.
+    pub fn import_bookmarks(
+        &self,
+        path: impl Into<PathBuf>,
+    ) -> Result<Result<usize, String>, Box<ipc_channel::ErrorKind>> {
+        let id = uuid::Uuid::new_v4();
+        let (sender, receiver) = std::sync::mpsc::channel();
+        self.event_listeners
+            .import_bookmarks_response
+            .lock()
+            .unwrap()
+            .insert(id, sender);
+        if let Err(error) = self
+            .sender
+            .send(ToVersoMessage::ImportBookmarks(id, path.into()))
+        {
+            self.event_listeners
+                .import_bookmarks_response
+                .lock()
+                .unwrap()
+                .remove(&id);
+            return Err(error);
+        };
+        Ok(receiver.recv().unwrap())
+    }
+
+    /// Export the bookmarks to a file in the Netscape bookmarks HTML format, which other browsers
+    /// import. Returns the error if the file couldn't be written.
+    pub fn export_bookmarks(
+        &self,
+        path: impl Into<PathBuf>,
+    ) -> Result<Result<(), String>, Box<ipc_channel::ErrorKind>> {
+        let id = uuid::Uuid::new_v4();
+        let (sender, receiver) = std::sync::mpsc::channel();
+        self.event_listeners
+            .export_bookmarks_response
+            .lock()
+            .unwrap()
+            .insert(id, sender);
+        if let Err(error) = self
+            .sender
+            .send(ToVersoMessage::ExportBookmarks(id, path.into()))
+        {
+            self.event_listeners
+                .export_bookmarks_response
+                .lock()
+                .unwrap()
+                .remove(&id);
+            return Err(error);
+        };
+        Ok(receiver.recv().unwrap())
+    }
+
+    /// Get the current config
+    pub fn get_config(&self) -> Result<VersoviewSettings, Box<ipc_channel::ErrorKind>> {
+        let id = uuid::Uuid::new_v4();
+        let (sender, receiver) = std::sync::mpsc::channel();
+        self.event_listeners
+            .get_config_response
+            .lock()
+            .unwrap()
+            .insert(id, sender);
+        if let Err(error) = self.sender.send(ToVersoMessage::GetConfig(id)) {
+            self.event_listeners
+                .get_config_response
+                .lock()
+                .unwrap()
+                .remove(&id);
+            return Err(error);
+        };
+        Ok(receiver.recv().unwrap())
+    }
+
+    /// Replace the config at runtime. Settings used while Verso runs, e.g. power saving, privacy
+    /// and the warm pool, apply right away, the ones used on startup, e.g. user scripts and the
+    /// initial windows, apply on the next launch.
+    pub fn update_config(
+        &self,
+        settings: VersoviewSettings,
+    ) -> Result<(), Box<ipc_channel::ErrorKind>> {
+        self.sender.send(ToVersoMessage::UpdateConfig(settings))
+    }
+
     // /// Add init script to run on document started to load
     // pub fn add_init_script(&self, script: String) -> Result<(), Box<ipc_channel::ErrorKind>> {
     //     self.sender.send(ToVersoMessage::AddInitScript(script))
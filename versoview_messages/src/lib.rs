@@ -1,4 +1,4 @@
-use std::path::PathBuf;
+use std::{collections::BTreeMap, net::SocketAddr, path::PathBuf};
 
 use dpi::{PhysicalPosition, PhysicalSize, Position, Size};
 use ipc_channel::ipc;
@@ -10,6 +10,10 @@ use serde::{Deserialize, Serialize};
 
 // Can't use `PipelineId` directly or else we need to pull in servo as a dependency
 type SerializedPipelineId = Vec<u8>;
+/// Serialized form of Servo's `WebViewId`, pass it back as is to address a specific webview
+pub type SerializedWebViewId = Vec<u8>;
+/// Serialized form of Servo's `BrowsingContextId`, pass it back as is to address a specific frame
+pub type SerializedFrameId = Vec<u8>;
 
 /// Message sent from the controller to versoview
 #[derive(Debug, Serialize, Deserialize)]
@@ -25,6 +29,8 @@ pub enum ToVersoMessage {
     ListenToOnCloseRequested,
     /// Navigate to this URL
     NavigateTo(url::Url),
+    /// Load this HTML string, relative URLs in it are resolved against the optional base URL
+    LoadHtml(String, Option<url::Url>),
     /// Reload the current webview
     Reload,
     /// Register a listener on versoview for getting notified on navigation starting,
@@ -70,6 +76,204 @@ pub enum ToVersoMessage {
     GetScaleFactor(uuid::Uuid),
     /// Get the current URL of the webview, need a response with [`ToControllerMessage::GetCurrentUrlResponse`]
     GetCurrentUrl(uuid::Uuid),
+    /// Get the visible text of the current page, or of the element matching the selector, need a
+    /// response with [`ToControllerMessage::ExtractTextResponse`]
+    ExtractText(uuid::Uuid, Option<String>),
+    /// Convert the readable content of the current page to Markdown and write it to the file, need
+    /// a response with [`ToControllerMessage::SaveAsMarkdownResponse`]
+    SaveAsMarkdown(uuid::Uuid, PathBuf),
+    /// Capture the next frame presented in the first window as a PNG image, need a response with
+    /// [`ToControllerMessage::CaptureFrameResponse`]
+    CaptureFrame(uuid::Uuid),
+    /// Wait until the condition is met in the current page, or the timeout in milliseconds is
+    /// over, need a response with [`ToControllerMessage::WaitForResponse`]
+    WaitFor(uuid::Uuid, WaitCondition, u64),
+    /// Find the text in the current page and highlight the matches, or remove the highlights if
+    /// `None`. Finding the same text again moves to the next match, need a response with
+    /// [`ToControllerMessage::FindResponse`]
+    Find(uuid::Uuid, Option<String>, FindOptions),
+    /// Search the text of visited pages, need a response with
+    /// [`ToControllerMessage::SearchHistoryResponse`]
+    SearchHistory(uuid::Uuid, String),
+    /// Suggest visited pages for the text typed in a URL bar, need a response with
+    /// [`ToControllerMessage::SuggestHistoryResponse`]
+    SuggestHistory(uuid::Uuid, String),
+    /// Forget the visited pages last visited since this time, in milliseconds since the Unix
+    /// epoch, or every page if `None`
+    ClearHistory(Option<i64>),
+    /// Register a listener on versoview for getting notified on page title changes of every webview,
+    /// veroview will send a [`ToControllerMessage::OnTitleChanged`] when that happens
+    ListenToOnTitleChanged,
+    /// Register a listener on versoview for getting notified on favicon changes of every webview,
+    /// veroview will send a [`ToControllerMessage::OnFaviconChanged`] when that happens
+    ListenToOnFaviconChanged,
+    /// Register a listener on versoview for getting notified on audio state changes of every webview,
+    /// veroview will send a [`ToControllerMessage::OnAudioStateChanged`] when that happens
+    ListenToOnAudioStateChanged,
+    /// Register a listener on versoview for getting notified on `theme-color` changes of every webview,
+    /// veroview will send a [`ToControllerMessage::OnThemeColorChanged`] when that happens
+    ListenToOnThemeColorChanged,
+    /// Register a listener on versoview for getting notified when the script of a webview hangs or
+    /// responds again, veroview will send a [`ToControllerMessage::OnScriptUnresponsive`] when
+    /// that happens
+    ListenToOnScriptUnresponsive,
+    /// Register a listener on versoview for getting notified when a webview exceeds one of its
+    /// budgets, versoview will send a [`ToControllerMessage::OnResourceLimitExceeded`] when that
+    /// happens
+    ListenToOnResourceLimitExceeded,
+    /// Register a listener on versoview for getting notified on the progress of downloads, and
+    /// start downloads without the control panel too, versoview will send a
+    /// [`ToControllerMessage::OnDownloadUpdated`] when a download starts, progresses or stops
+    ListenToOnDownloadUpdated,
+    /// Pause the download with this id, see [`DownloadUpdate::id`]
+    PauseDownload(String),
+    /// Resume the paused download with this id
+    ResumeDownload(String),
+    /// Cancel the download with this id and remove the partly written file
+    CancelDownload(String),
+    /// Mute or unmute all media of a webview
+    SetMuted(SerializedWebViewId, bool),
+    /// Create a tab group with this id and name from opened webviews
+    CreateTabGroup(uuid::Uuid, String, Vec<SerializedWebViewId>),
+    /// Remove a tab group, its webviews are kept opened
+    RemoveTabGroup(uuid::Uuid),
+    /// Close the webviews of a tab group, and only keep their URLs in the session
+    CollapseTabGroup(uuid::Uuid),
+    /// Reopen the webviews of a collapsed tab group
+    RestoreTabGroup(uuid::Uuid),
+    /// Get all tab groups, need a response with [`ToControllerMessage::GetTabGroupsResponse`]
+    GetTabGroups(uuid::Uuid),
+    /// Register a command with this id and title to the command palette,
+    /// veroview will send a [`ToControllerMessage::OnCommandExecuted`] when it's executed
+    RegisterCommand(String, String),
+    /// Remove a command registered with [`ToVersoMessage::RegisterCommand`]
+    UnregisterCommand(String),
+    /// Execute a command by its id, e.g. `navigation.reload` or `zoom.in`
+    ExecuteCommand(String),
+    /// Serve a URL from a local file or in-memory bytes instead of the network
+    SetResourceOverride(ResourceOverride),
+    /// Remove the override of this URL set with [`ToVersoMessage::SetResourceOverride`]
+    RemoveResourceOverride(url::Url),
+    /// Enable or disable mock network mode, network requests are only answered by
+    /// [`ToVersoMessage::WebResourceRequestResponse`] and never reach the network
+    SetMockNetwork(bool),
+    /// Answer the file pickers of `<input type="file">` with the files they accept among these
+    /// instead of showing a dialog, e.g. for tests, or show dialogs again if `None`
+    SetFilePickerOverride(Option<Vec<PathBuf>>),
+    /// Set how every window waits for the vertical blank before presenting a frame
+    SetVsync(Vsync),
+    /// Rotate the output of every window, and the input with it
+    SetRotation(Rotation),
+    /// Change what Verso keeps of the cookies pages set
+    SetCookiePolicy(CookiePolicy),
+    /// Load the pages of the host even if its certificate is invalid, for the session. Pages
+    /// loaded afterwards proceed without asking whatever the [`CertificateErrorPolicy`]
+    AllowCertificateOverride(String),
+    /// Change whether users can proceed to sites with an invalid certificate
+    SetCertificateErrorPolicy(CertificateErrorPolicy),
+    /// Delete the cookies of the site of the origin, or every cookie if `None`
+    ClearCookies(Option<url::Url>),
+    /// Remove every response of the HTTP cache of the profile
+    ClearCache,
+    /// Run JavaScript on the pages of origins without a setting of their own, or not. Pages
+    /// loaded afterwards use it
+    SetJavaScriptEnabled(bool),
+    /// Run JavaScript on the pages of the origin, or not, regardless of the setting of every
+    /// origin, or remove the setting of the origin if `None`. Pages loaded afterwards use it
+    SetOriginJavaScriptEnabled(url::Url, Option<bool>),
+    /// Change which images pages load and whether to save data on them, saved in the profile.
+    /// Pages loaded afterwards show the placeholders of blocked images
+    SetImagePolicy(ImagePolicy),
+    /// Change when pages run their animations, see [`AnimationPolicy`]
+    SetAnimationPolicy(AnimationPolicy),
+    /// Copy, cut or paste in the focused element of the current tab
+    Clipboard(ClipboardAction),
+    /// Register a listener on versoview for showing the dialogs of webviews instead of Verso,
+    /// veroview will send a [`ToControllerMessage::OnDialog`] for every dialog
+    ListenToOnDialog,
+    /// Response to a [`ToControllerMessage::OnDialog`]
+    DialogResponse(uuid::Uuid, DialogResponse),
+    /// Register a listener on versoview for form submissions of pages, veroview will send a
+    /// [`ToControllerMessage::OnFormSubmission`] for every form submitted
+    ListenToFormSubmissions,
+    /// Response to a [`ToControllerMessage::OnFormSubmission`]
+    FormSubmissionResponse(uuid::Uuid, FormSubmissionResponse),
+    /// Register a listener on versoview for HTTP authentication asked by sites and proxies,
+    /// veroview will send a [`ToControllerMessage::OnAuthentication`] instead of asking the user
+    ListenToAuthentication,
+    /// Response to a [`ToControllerMessage::OnAuthentication`], `None` to cancel it
+    AuthenticationResponse(uuid::Uuid, Option<HttpCredentials>),
+    /// Save a secret in the OS keychain, replacing the existing one of the same key
+    SetSecret(String, String),
+    /// Get a secret from the OS keychain, need a response with [`ToControllerMessage::GetSecretResponse`]
+    GetSecret(uuid::Uuid, String),
+    /// Delete a secret from the OS keychain
+    DeleteSecret(String),
+    /// Open a popup window at the authorization URL (first) and wait for it to be redirected to
+    /// the callback URL (second), need a response with [`ToControllerMessage::AuthFlowResponse`]
+    StartAuthFlow(uuid::Uuid, url::Url, url::Url),
+    /// Set the privacy settings of a webview, `None` to use the ones of the config again
+    SetWebViewPrivacy(SerializedWebViewId, Option<PrivacySettings>),
+    /// Set the page lifecycle state of a webview, Verso stops changing its state itself
+    SetLifecycleState(SerializedWebViewId, LifecycleState),
+    /// Keep the display awake while the webview is opened, e.g. while it's playing a video or
+    /// presenting
+    SetSleepInhibited(SerializedWebViewId, bool),
+    /// Report this position to pages instead of the one of the system, `None` to use the system
+    /// again. Useful for testing, pages still have to be granted the permission.
+    SetMockGeolocation(Option<GeolocationPosition>),
+    /// Close a webview, e.g. one whose script is hung
+    CloseWebView(SerializedWebViewId),
+    /// Set the resource budgets of a webview, the default ones remove them
+    SetResourceLimits(SerializedWebViewId, ResourceLimits),
+    /// Get the frame tree of a webview, need a response with
+    /// [`ToControllerMessage::GetFrameTreeResponse`]
+    GetFrameTree(uuid::Uuid, SerializedWebViewId),
+    /// Evaluate a script in a frame, need a response with
+    /// [`ToControllerMessage::ExecuteScriptInFrameResponse`]
+    ExecuteScriptInFrame(uuid::Uuid, SerializedFrameId, String),
+    /// Dispatch input events to the document of a frame
+    SendFrameInput(SerializedFrameId, FrameInput),
+    /// Get the webviews of every window, need a response with
+    /// [`ToControllerMessage::GetWebViewsResponse`]
+    GetWebViews(uuid::Uuid),
+    /// Name a tab, or remove its name if `None`. Names are unique and kept in the session, so
+    /// the tab can be found again after a restart.
+    SetWebViewName(SerializedWebViewId, Option<String>),
+    /// Get the tab with this name, need a response with
+    /// [`ToControllerMessage::GetWebViewByNameResponse`]
+    GetWebViewByName(uuid::Uuid, String),
+    /// List the names of the profiles, need a response with
+    /// [`ToControllerMessage::ListProfilesResponse`]
+    ListProfiles(uuid::Uuid),
+    /// Create an empty profile with this name, need a response with
+    /// [`ToControllerMessage::CreateProfileResponse`]
+    CreateProfile(uuid::Uuid, String),
+    /// Delete the profile with this name and everything in it, need a response with
+    /// [`ToControllerMessage::DeleteProfileResponse`]
+    DeleteProfile(uuid::Uuid, String),
+    /// List the bookmarks, need a response with [`ToControllerMessage::ListBookmarksResponse`]
+    ListBookmarks(uuid::Uuid),
+    /// Bookmark a page, or move and rename its bookmark if it's already bookmarked
+    AddBookmark(BookmarkInfo),
+    /// Remove the bookmark of the URL
+    RemoveBookmark(String),
+    /// Import the bookmarks of a Netscape bookmarks HTML file, need a response with
+    /// [`ToControllerMessage::ImportBookmarksResponse`]
+    ImportBookmarks(uuid::Uuid, PathBuf),
+    /// Export the bookmarks to a Netscape bookmarks HTML file, need a response with
+    /// [`ToControllerMessage::ExportBookmarksResponse`]
+    ExportBookmarks(uuid::Uuid, PathBuf),
+    /// Get the current config, without [`ConfigFromController::ipc_token`], need a response with
+    /// [`ToControllerMessage::GetConfigResponse`]
+    GetConfig(uuid::Uuid),
+    /// Replace the config at runtime, versoview will send a
+    /// [`ToControllerMessage::OnConfigChanged`] if a listener is registered, and a
+    /// [`ControlEvent::ConfigChanged`] to the subscribed clients of the control protocol
+    UpdateConfig(ConfigFromController),
+    /// Register a listener on versoview for getting notified when the config changes at runtime,
+    /// versoview will send a [`ToControllerMessage::OnConfigChanged`] when that happens
+    ListenToOnConfigChanged,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -111,17 +315,998 @@ pub enum ToControllerMessage {
     GetScaleFactorResponse(uuid::Uuid, f64),
     /// Response to a [`ToVersoMessage::GetCurrentUrl`]
     GetCurrentUrlResponse(uuid::Uuid, url::Url),
+    /// Response to a [`ToVersoMessage::ExtractText`], `None` if no element matches the selector
+    ExtractTextResponse(uuid::Uuid, Option<String>),
+    /// Response to a [`ToVersoMessage::SaveAsMarkdown`], with the error if the page couldn't be
+    /// converted or the file written
+    SaveAsMarkdownResponse(uuid::Uuid, Result<(), String>),
+    /// Response to a [`ToVersoMessage::CaptureFrame`], with the PNG image of the frame, `None` if
+    /// it couldn't be captured
+    CaptureFrameResponse(uuid::Uuid, Option<Vec<u8>>),
+    /// Response to a [`ToVersoMessage::WaitFor`], `true` if the condition was met before the
+    /// timeout
+    WaitForResponse(uuid::Uuid, bool),
+    /// Response to a [`ToVersoMessage::Find`]
+    FindResponse(uuid::Uuid, FindResult),
+    /// Response to a [`ToVersoMessage::SearchHistory`], the best matches first
+    SearchHistoryResponse(uuid::Uuid, Vec<HistoryMatch>),
+    /// Response to a [`ToVersoMessage::SuggestHistory`], the pages visited most often and most
+    /// recently first, with empty snippets
+    SuggestHistoryResponse(uuid::Uuid, Vec<HistoryMatch>),
     /// Verso have recieved a close request from the OS
     OnCloseRequested,
+    /// Sent when the title of a webview changed,
+    /// the last field is `true` if the webview is not the active tab of its window
+    OnTitleChanged(SerializedWebViewId, Option<String>, bool),
+    /// Sent when the favicon of a webview changed,
+    /// the last field is `true` if the webview is not the active tab of its window
+    OnFaviconChanged(SerializedWebViewId, url::Url, bool),
+    /// Sent when a webview starts or stops playing media, or gets muted or unmuted,
+    /// the fields are whether the webview is audible and whether it is muted
+    OnAudioStateChanged(SerializedWebViewId, bool, bool),
+    /// Sent when the `theme-color` of a webview changed, as a `#rrggbb` color or `None` if it has
+    /// none, the last field is `true` if the webview is not the active tab of its window
+    OnThemeColorChanged(SerializedWebViewId, Option<String>, bool),
+    /// Sent when the script of a webview stops responding for longer than
+    /// [`ConfigFromController::slow_script_timeout`], with `true`, and with `false` once it
+    /// responds again
+    OnScriptUnresponsive(SerializedWebViewId, bool),
+    /// Sent when a webview exceeds one of the budgets set with
+    /// [`ToVersoMessage::SetResourceLimits`]
+    OnResourceLimitExceeded(SerializedWebViewId, ResourceLimit),
+    /// Sent when a download starts, progresses, is paused or resumed, or stops
+    OnDownloadUpdated(DownloadUpdate),
+    /// Response to a [`ToVersoMessage::GetTabGroups`]
+    GetTabGroupsResponse(uuid::Uuid, Vec<TabGroupInfo>),
+    /// Response to a [`ToVersoMessage::GetSecret`], `None` if the secret doesn't exist
+    GetSecretResponse(uuid::Uuid, Option<String>),
+    /// Response to a [`ToVersoMessage::StartAuthFlow`], `None` if the popup is closed before
+    /// being redirected to the callback URL
+    AuthFlowResponse(uuid::Uuid, Option<AuthCallback>),
+    /// Sent when a command registered by the controller is executed
+    OnCommandExecuted(String),
+    /// Response to a [`ToVersoMessage::GetFrameTree`], `None` if the webview doesn't exist
+    GetFrameTreeResponse(uuid::Uuid, Option<FrameInfo>),
+    /// Response to a [`ToVersoMessage::ExecuteScriptInFrame`], the result as JSON or the error
+    ExecuteScriptInFrameResponse(uuid::Uuid, Result<String, String>),
+    /// Response to a [`ToVersoMessage::GetWebViews`], the tabs of every window in tab strip order
+    GetWebViewsResponse(uuid::Uuid, Vec<SerializedWebViewId>),
+    /// Response to a [`ToVersoMessage::GetWebViewByName`], `None` if no tab has the name
+    GetWebViewByNameResponse(uuid::Uuid, Option<SerializedWebViewId>),
+    /// Response to a [`ToVersoMessage::ListProfiles`], the names of the profiles in alphabetical
+    /// order, without the default profile
+    ListProfilesResponse(uuid::Uuid, Vec<String>),
+    /// Response to a [`ToVersoMessage::CreateProfile`], with the error if the name isn't valid,
+    /// the profile exists or its directory couldn't be created
+    CreateProfileResponse(uuid::Uuid, Result<(), String>),
+    /// Response to a [`ToVersoMessage::DeleteProfile`], with the error if the profile doesn't
+    /// exist, is in use by this instance or couldn't be removed
+    DeleteProfileResponse(uuid::Uuid, Result<(), String>),
+    /// Response to a [`ToVersoMessage::ListBookmarks`], in the order they were added
+    ListBookmarksResponse(uuid::Uuid, Vec<BookmarkInfo>),
+    /// Response to a [`ToVersoMessage::ImportBookmarks`], with the number of bookmarks added,
+    /// pages already bookmarked are skipped
+    ImportBookmarksResponse(uuid::Uuid, Result<usize, String>),
+    /// Response to a [`ToVersoMessage::ExportBookmarks`], with the error if the file couldn't be
+    /// written
+    ExportBookmarksResponse(uuid::Uuid, Result<(), String>),
+    /// Response to a [`ToVersoMessage::GetConfig`]
+    GetConfigResponse(uuid::Uuid, ConfigFromController),
+    /// Sent when the config is changed at runtime, with the new config
+    OnConfigChanged(ConfigFromController),
+    /// Sent when a webview opens a dialog, need a response with [`ToVersoMessage::DialogResponse`]
+    OnDialog(uuid::Uuid, SerializedWebViewId, DialogRequest),
+    /// Sent when a page submits a form, need a response with
+    /// [`ToVersoMessage::FormSubmissionResponse`]
+    OnFormSubmission(uuid::Uuid, FormSubmission),
+    /// Sent when a site or a proxy asks for HTTP authentication without credentials remembered
+    /// for it, need a response with [`ToVersoMessage::AuthenticationResponse`]
+    OnAuthentication(uuid::Uuid, AuthenticationRequest),
+}
+
+/// A position reported to pages by the Geolocation API, see
+/// [`GeolocationCoordinates`](https://developer.mozilla.org/en-US/docs/Web/API/GeolocationCoordinates).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GeolocationPosition {
+    /// Latitude in degrees
+    pub latitude: f64,
+    /// Longitude in degrees
+    pub longitude: f64,
+    /// Accuracy of the latitude and longitude in meters
+    pub accuracy: f64,
+    /// Altitude in meters above the WGS84 ellipsoid
+    pub altitude: Option<f64>,
+    /// Accuracy of the altitude in meters
+    pub altitude_accuracy: Option<f64>,
+    /// Direction of travel in degrees clockwise from true north
+    pub heading: Option<f64>,
+    /// Speed in meters per second
+    pub speed: Option<f64>,
+}
+
+/// Throttle animations when the user is away or the system saves power.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PowerSaveConfig {
+    /// Throttle animations when the system is idle or in power saver mode.
+    pub enabled: bool,
+    /// Seconds without user input before the system is considered idle. The system is never idle
+    /// while a webview keeps the display awake.
+    pub idle_timeout: u64,
+    /// Animation frame rate while saving power.
+    pub frame_rate: u32,
+    /// Also throttle animations while the system runs on its battery.
+    pub on_battery: bool,
+}
+
+impl Default for PowerSaveConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            idle_timeout: 300,
+            frame_rate: 30,
+            on_battery: false,
+        }
+    }
+}
+
+/// When pages run their CSS animations and transitions and animate their images. Tabs in the
+/// background are throttled whatever the policy, animation frame callbacks of the tabs shown
+/// always run.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AnimationPolicy {
+    /// Animate the tabs shown
+    #[default]
+    Normal,
+    /// Also pause the animations of the tabs shown in windows which aren't focused
+    PauseInBackground,
+    /// Animate once: pause the animations of a page a few seconds after they start, until they
+    /// all end or the page is loaded again
+    Once,
+}
+
+impl std::str::FromStr for AnimationPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "normal" => Ok(Self::Normal),
+            "pause-in-background" => Ok(Self::PauseInBackground),
+            "once" => Ok(Self::Once),
+            _ => Err(format!("Unknown animation policy {s}")),
+        }
+    }
+}
+
+/// Page lifecycle state of a webview, see the
+/// [Page Lifecycle](https://wicg.github.io/page-lifecycle/) spec.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LifecycleState {
+    /// The page is shown
+    Visible,
+    /// The page isn't shown, its rendering and timers are throttled
+    Hidden,
+    /// The page isn't shown and is expected to stop its work, `freeze` is dispatched to it
+    Frozen,
+}
+
+/// A dialog opened by a webview, shown by the controller instead of Verso
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum DialogRequest {
+    /// `window.alert()`, answered with [`DialogResponse::Ok`]
+    Alert {
+        /// Message of the page
+        message: String,
+    },
+    /// `window.confirm()`, answered with [`DialogResponse::Ok`] or [`DialogResponse::Cancel`]
+    Confirm {
+        /// Message of the page
+        message: String,
+    },
+    /// `window.prompt()`, answered with [`DialogResponse::Text`] or [`DialogResponse::Cancel`]
+    Prompt {
+        /// Message of the page
+        message: String,
+        /// Default value of the input
+        default: String,
+    },
+    /// A `beforeunload` handler asks to confirm leaving the page, answered with
+    /// [`DialogResponse::Ok`] to leave or [`DialogResponse::Cancel`] to stay
+    BeforeUnload,
+    /// A permission asked by the page, e.g. `geolocation`, answered with [`DialogResponse::Ok`]
+    /// to allow or [`DialogResponse::Cancel`] to deny it
+    Permission {
+        /// Name of the permission
+        feature: String,
+    },
+    /// HTTP authentication asked by a site or a proxy, answered with
+    /// [`DialogResponse::Credentials`] or [`DialogResponse::Cancel`]
+    Authentication {
+        /// URL of the request
+        url: url::Url,
+        /// Whether the credentials are for a proxy
+        for_proxy: bool,
+    },
+    /// `<input type="file">`, answered with [`DialogResponse::Files`] or
+    /// [`DialogResponse::Cancel`]
+    FilePicker {
+        /// Patterns of the file types the input accepts, every file if empty
+        filter_patterns: Vec<String>,
+        /// Whether several files can be picked
+        allow_multiple: bool,
+    },
+}
+
+/// Answer of the controller to a [`DialogRequest`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum DialogResponse {
+    /// Accept the dialog
+    Ok,
+    /// Dismiss the dialog, or deny the permission
+    Cancel,
+    /// Accept a prompt with this text
+    Text(String),
+    /// Sign in with these credentials
+    Credentials {
+        /// User name
+        username: String,
+        /// Password
+        password: String,
+    },
+    /// Pick these files
+    Files(Vec<PathBuf>),
+}
+
+/// A form a page submits, before it's sent
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FormSubmission {
+    /// Webview of the page
+    pub webview_id: SerializedWebViewId,
+    /// URL of the page
+    pub page_url: Option<url::Url>,
+    /// URL the form is sent to
+    pub action: url::Url,
+    /// Method of the form, `get` or `post`
+    pub method: String,
+    /// Fields of the form in the order they're sent, with the values typed in them, passwords
+    /// included
+    pub fields: Vec<FormField>,
+}
+
+/// A field of a [`FormSubmission`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FormField {
+    /// Name of the field
+    pub name: String,
+    /// Value of the field, the name of the file for file inputs
+    pub value: String,
+}
+
+/// What to do with a [`FormSubmission`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FormSubmissionResponse {
+    /// Submit the form as it is
+    Allow,
+    /// Don't submit the form, the page stays as it is
+    Block,
+    /// Submit these fields instead, without the files of the form. Verso submits them itself,
+    /// so the `submit` listeners of the page don't run.
+    Modify(Vec<FormField>),
+}
+
+/// HTTP authentication asked by a site or a proxy, before the user is asked
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuthenticationRequest {
+    /// Webview of the page the request is sent for
+    pub webview_id: SerializedWebViewId,
+    /// URL of the request
+    pub url: url::Url,
+    /// Whether the credentials are for a proxy
+    pub for_proxy: bool,
+}
+
+/// Credentials of HTTP authentication
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HttpCredentials {
+    /// User name
+    pub username: String,
+    /// Password
+    pub password: String,
+}
+
+/// The redirect to the callback URL of an OAuth flow
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuthCallback {
+    /// The callback URL with its parameters
+    pub url: url::Url,
+    /// Parameters of the query and the fragment of the URL, e.g. `code` and `state`
+    pub params: Vec<(String, String)>,
+}
+
+impl AuthCallback {
+    /// Get the value of a parameter
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// A frame of a webview and the frames in its document, reported to the controller
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FrameInfo {
+    /// Frame id, the one of the top-level frame addresses the document of the webview
+    pub id: SerializedFrameId,
+    /// URL of the document of the frame, `None` if it can't be read, e.g. while it's loading
+    pub url: Option<url::Url>,
+    /// `window.name` of the frame
+    pub name: String,
+    /// Child frames in document order
+    pub children: Vec<FrameInfo>,
+}
+
+/// Input events dispatched to the document of a frame. They are synthetic DOM events, so pages
+/// see them with `isTrusted` set to `false`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum FrameInput {
+    /// Click the element at this point, in CSS pixels of the frame's viewport
+    Click {
+        /// Horizontal position
+        x: f64,
+        /// Vertical position
+        y: f64,
+    },
+    /// Insert text in the focused editable element
+    Text(String),
+    /// Press and release a key on the focused element, with a
+    /// [`KeyboardEvent.key`](https://developer.mozilla.org/en-US/docs/Web/API/KeyboardEvent/key)
+    /// value such as `Enter`
+    Key(String),
+}
+
+/// Condition to wait for in a page with [`ToVersoMessage::WaitFor`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum WaitCondition {
+    /// The URL of the page matches the pattern, where `*` matches any characters, e.g.
+    /// `https://example.com/search?*`
+    Url(String),
+    /// An element of the page matches the CSS selector
+    Selector(String),
+    /// The document of the page is completely loaded
+    LoadComplete,
+    /// The script evaluated in the global scope of the page returns a truthy value
+    Script(String),
+}
+
+/// Options of [`ToVersoMessage::Find`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FindOptions {
+    /// Match the case of the text, otherwise the case is ignored
+    pub case_sensitive: bool,
+    /// Move to the previous match instead of the next one
+    pub backwards: bool,
+}
+
+/// Matches of the text found with [`ToVersoMessage::Find`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FindResult {
+    /// Number of matches in the page
+    pub matches: u32,
+    /// Index of the match scrolled to, from 0, `None` if there is no match
+    pub active_match: Option<u32>,
+}
+
+/// State of a download
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DownloadState {
+    /// The file is being created
+    Waiting,
+    /// The body is being written to the file
+    Downloading,
+    /// The download is paused until it's resumed
+    Paused,
+    /// The file is completely written
+    Finished,
+    /// The download was cancelled and the partly written file removed
+    Cancelled,
+    /// The download stopped with this error
+    Failed(String),
+}
+
+/// Progress of a download, see [`ToControllerMessage::OnDownloadUpdated`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DownloadUpdate {
+    /// Id of the download, to pause, resume or cancel it
+    pub id: String,
+    /// URL the file is downloaded from
+    pub url: url::Url,
+    /// Name of the file
+    pub file_name: String,
+    /// Number of bytes written to the file
+    pub received_bytes: u64,
+    /// Size of the file, `None` if the server didn't tell
+    pub total_bytes: Option<u64>,
+    /// State of the download
+    pub state: DownloadState,
+}
+
+/// Version of the control protocol. Commands and fields are only added within a version, so a
+/// client written against a version keeps working with every release supporting it.
+pub const CONTROL_PROTOCOL_VERSION: u32 = 1;
+
+/// Longest request of the control protocol, in bytes. Connections sending a longer line are
+/// answered with [`ControlErrorCode::TooLong`] and closed.
+pub const MAX_CONTROL_REQUEST_LENGTH: usize = 64 * 1024;
+
+/// The `type` of every [`ControlCommand`] of this version, advertised as the capabilities in
+/// the response to [`ControlCommand::Hello`].
+pub const CONTROL_CAPABILITIES: &[&str] = &[
+    "hello",
+    "navigate",
+    "reload",
+    "back",
+    "forward",
+    "click",
+    "text",
+    "key",
+    "resize",
+    "close",
+    "exit",
+    "getTitle",
+    "getUrl",
+    "listWebViews",
+    "acquire",
+    "release",
+    "subscribe",
+    "unsubscribe",
+    "setName",
+    "setWindowName",
+    "listMonitors",
+    "snap",
+    "getValue",
+    "setValue",
+];
+
+/// How long a lease of [`ControlCommand::Acquire`] lasts without a `ttl`, in milliseconds.
+pub const DEFAULT_CONTROL_LEASE: u64 = 30_000;
+
+/// Request of the control protocol served with `--ipc-socket`. Every line sent to the socket is
+/// a JSON request, and Verso answers it with a line of [`ControlResponse`] with the same `id`.
+/// Over the WebSocket of `--ipc-websocket`, every message is a request or response instead.
+/// Commands address the tab of `webview`, a name given with [`ControlCommand::SetName`] or an ID
+/// listed by [`ControlCommand::ListWebViews`], or the current tab of the window named `window`, or
+/// of the first window. Names are kept in the session, so scripts can address tabs and windows
+/// the same way after a restart. Several clients may be connected at once: a client can
+/// [`ControlCommand::Acquire`] a lease on a tab so others can only read it, and clients which
+/// [`ControlCommand::Subscribe`] are sent a [`ControlEvent`] line for every change of the tabs.
+/// Clients should start with
+/// [`ControlCommand::Hello`] to agree on a version, and to authenticate with the token of
+/// `--ipc-token` if one is set. Other requests are refused until the token is sent.
+///
+/// ```json
+/// {"id": 0, "type": "hello", "version": 1, "token": "secret"}
+/// {"id": 1, "type": "navigate", "url": "https://example.com"}
+/// {"id": 2, "type": "click", "x": 10, "y": 20}
+/// {"id": 3, "type": "resize", "width": 800, "height": 600}
+/// {"id": 4, "type": "getTitle"}
+/// {"id": 5, "type": "acquire", "webview": "0100000001000000", "ttl": 60000}
+/// {"id": 6, "type": "setName", "webview": "0100000001000000", "name": "dashboard"}
+/// {"id": 7, "type": "reload", "webview": "dashboard"}
+/// {"id": 8, "type": "snap", "window": "dashboard", "area": "left", "monitor": 1}
+/// {"id": 9, "type": "snap", "area": {"cell": {"columns": 3, "rows": 2, "column": 2, "row": 0}}}
+/// {"id": 10, "type": "setValue", "selector": "#email", "value": "kiosk@example.com"}
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ControlRequest {
+    /// Chosen by the client to match the response to the request
+    #[serde(default)]
+    pub id: u64,
+    /// Version of the protocol the request is written against, requests of a newer version than
+    /// [`CONTROL_PROTOCOL_VERSION`] are rejected
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<u32>,
+    /// Name or ID of the tab to run the command in, the current tab of the window if `None`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub webview: Option<String>,
+    /// Name of the window to run the command in, the first window if `None`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub window: Option<String>,
+    /// What to do
+    #[serde(flatten)]
+    pub command: ControlCommand,
+}
+
+/// Command of a [`ControlRequest`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+#[non_exhaustive]
+pub enum ControlCommand {
+    /// Negotiate the version of the protocol, answered with [`ControlOutcome::Hello`]
+    Hello {
+        /// Latest version the client supports
+        version: u32,
+        /// Token the control server is protected with, the connection is closed if it's wrong
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        token: Option<String>,
+    },
+    /// Load the URL
+    Navigate {
+        /// URL to load
+        url: url::Url,
+    },
+    /// Reload the page
+    Reload,
+    /// Go back in history
+    Back,
+    /// Go forward in history
+    Forward,
+    /// Click the element at this point, in CSS pixels of the viewport
+    Click {
+        /// Horizontal position
+        x: f64,
+        /// Vertical position
+        y: f64,
+    },
+    /// Insert text in the focused editable element
+    Text {
+        /// Text to insert
+        text: String,
+    },
+    /// Press and release a key on the focused element, with a `KeyboardEvent.key` value
+    Key {
+        /// Key to press, such as `Enter`
+        key: String,
+    },
+    /// Resize the window, in physical pixels
+    Resize {
+        /// Inner width
+        width: u32,
+        /// Inner height
+        height: u32,
+    },
+    /// Close the tab
+    Close,
+    /// Exit Verso
+    Exit,
+    /// Get the title of the page, the result is `null` if it has none
+    GetTitle,
+    /// Get the URL of the page
+    GetUrl,
+    /// List the tabs of every window, answered with [`ControlOutcome::WebViews`]
+    ListWebViews,
+    /// Lease the tab, so only this client may change it until the lease expires, it's released
+    /// or the client disconnects. Acquiring a tab leased by the client renews the lease. Fails
+    /// with [`ControlErrorCode::LeaseHeld`] if another client holds it.
+    Acquire {
+        /// How long the lease lasts, in milliseconds, [`DEFAULT_CONTROL_LEASE`] if `None`
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        ttl: Option<u64>,
+    },
+    /// Release the lease of the client on the tab
+    Release,
+    /// Receive a [`ControlEvent`] for every change of the tabs
+    Subscribe,
+    /// Stop receiving events
+    Unsubscribe,
+    /// Name the tab, or remove its name if `None`. Fails with
+    /// [`ControlErrorCode::InvalidRequest`] if another tab has the name.
+    SetName {
+        /// Name to address the tab with
+        #[serde(default)]
+        name: Option<String>,
+    },
+    /// Name the window of the tab, or remove its name if `None`. Fails with
+    /// [`ControlErrorCode::InvalidRequest`] if another window has the name.
+    SetWindowName {
+        /// Name to address the window with
+        #[serde(default)]
+        name: Option<String>,
+    },
+    /// List the monitors windows can be snapped to, answered with [`ControlOutcome::Monitors`]
+    ListMonitors,
+    /// Move and resize the window of the tab to fill an area of a monitor. Maximized and
+    /// fullscreen windows are restored first.
+    Snap {
+        /// Area of the monitor to fill
+        area: SnapArea,
+        /// Index of the monitor in [`ControlCommand::ListMonitors`], the monitor of the window if
+        /// `None`
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        monitor: Option<usize>,
+    },
+    /// Get the value of the first form field of the page matching the CSS selector, `"true"` or
+    /// `"false"` for checkboxes and radio buttons. Fails with
+    /// [`ControlErrorCode::InvalidRequest`] if no field matches.
+    GetValue {
+        /// CSS selector of the field
+        selector: String,
+    },
+    /// Set the value of the first form field of the page matching the CSS selector, and dispatch
+    /// its `input` and `change` events as if the user typed it, so scripts of the page see the
+    /// change. Checkboxes and radio buttons are checked with `"true"`. Fails with
+    /// [`ControlErrorCode::InvalidRequest`] if no field matches.
+    SetValue {
+        /// CSS selector of the field
+        selector: String,
+        /// New value
+        value: String,
+    },
+}
+
+/// Area of a monitor a window fills with [`ControlCommand::Snap`], `"left"` or
+/// `{"cell": {"columns": 3, "rows": 2, "column": 2, "row": 0}}`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SnapArea {
+    /// The whole monitor, without maximizing the window
+    Full,
+    /// Left half
+    Left,
+    /// Right half
+    Right,
+    /// Top half
+    Top,
+    /// Bottom half
+    Bottom,
+    /// Top left quarter
+    TopLeft,
+    /// Top right quarter
+    TopRight,
+    /// Bottom left quarter
+    BottomLeft,
+    /// Bottom right quarter
+    BottomRight,
+    /// Cells of a grid laid over the monitor
+    #[serde(rename_all = "camelCase")]
+    Cell {
+        /// Columns of the grid
+        columns: u32,
+        /// Rows of the grid
+        rows: u32,
+        /// Column of the first cell, from the left starting at 0
+        column: u32,
+        /// Row of the first cell, from the top starting at 0
+        row: u32,
+        /// Columns the window spans
+        #[serde(default = "default_span")]
+        column_span: u32,
+        /// Rows the window spans
+        #[serde(default = "default_span")]
+        row_span: u32,
+    },
+}
+
+fn default_span() -> u32 {
+    1
+}
+
+/// A monitor listed by [`ControlCommand::ListMonitors`], in physical pixels of the desktop
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ControlMonitor {
+    /// Index to snap windows to the monitor with in [`ControlCommand::Snap`]
+    pub index: usize,
+    /// Name of the monitor, if the platform has one
+    pub name: Option<String>,
+    /// Horizontal position of the monitor on the desktop
+    pub x: i32,
+    /// Vertical position of the monitor on the desktop
+    pub y: i32,
+    /// Width of the monitor
+    pub width: u32,
+    /// Height of the monitor
+    pub height: u32,
+    /// Scale factor of the monitor
+    pub scale_factor: f64,
+    /// Whether it's the primary monitor of the system
+    pub primary: bool,
+}
+
+/// Response to a [`ControlRequest`], either `{"id": 1, "result": ...}` with the title or URL
+/// for queries and `null` for other commands, `{"id": 0, "hello": {...}}`, or
+/// `{"id": 1, "error": {"code": "noWebView", "message": "..."}}`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ControlResponse {
+    /// ID of the request
+    pub id: u64,
+    /// Result of the request
+    #[serde(flatten)]
+    pub outcome: ControlOutcome,
+}
+
+/// Outcome of a [`ControlRequest`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ControlOutcome {
+    /// The command succeeded
+    Result(Option<String>),
+    /// The protocol agreed on in response to [`ControlCommand::Hello`]
+    Hello(ControlHello),
+    /// The tabs listed in response to [`ControlCommand::ListWebViews`]
+    WebViews(Vec<ControlWebView>),
+    /// The monitors listed in response to [`ControlCommand::ListMonitors`]
+    Monitors(Vec<ControlMonitor>),
+    /// The command failed or couldn't be parsed
+    Error(ControlError),
+}
+
+impl ControlOutcome {
+    /// An error with this code and message.
+    pub fn error(code: ControlErrorCode, message: impl Into<String>) -> Self {
+        Self::Error(ControlError {
+            code,
+            message: message.into(),
+        })
+    }
+}
+
+/// Protocol agreed on by the client and Verso
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ControlHello {
+    /// Version both support, later requests should be written against it
+    pub version: u32,
+    /// The commands Verso supports in this version
+    pub capabilities: Vec<String>,
+    /// ID of the client, the owner of its leases in [`ControlWebView`] and [`ControlEvent`]
+    #[serde(default)]
+    pub client: u64,
+}
+
+/// A tab listed by [`ControlCommand::ListWebViews`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ControlWebView {
+    /// ID to address the tab with in [`ControlRequest::webview`]
+    pub id: String,
+    /// Name of the tab, which can be used in place of its ID
+    pub name: Option<String>,
+    /// Name of the window of the tab
+    pub window: Option<String>,
+    /// URL of the page, if it has loaded one
+    pub url: Option<String>,
+    /// Title of the page, if it has one
+    pub title: Option<String>,
+    /// Client holding a lease on the tab
+    pub owner: Option<u64>,
+}
+
+/// Event sent to the clients which sent [`ControlCommand::Subscribe`], as
+/// `{"event": "titleChanged", "webview": "...", "title": "..."}`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "camelCase")]
+#[non_exhaustive]
+pub enum ControlEvent {
+    /// The title of a tab changed
+    TitleChanged {
+        /// ID of the tab
+        webview: String,
+        /// New title, `None` if the page has none
+        title: Option<String>,
+    },
+    /// A tab navigated to another URL
+    UrlChanged {
+        /// ID of the tab
+        webview: String,
+        /// New URL
+        url: String,
+    },
+    /// A tab finished loading its page
+    LoadComplete {
+        /// ID of the tab
+        webview: String,
+    },
+    /// A tab was closed
+    WebViewClosed {
+        /// ID of the tab
+        webview: String,
+    },
+    /// A client acquired or released its lease on a tab
+    LeaseChanged {
+        /// ID of the tab
+        webview: String,
+        /// Client holding the lease, `None` if it's released
+        owner: Option<u64>,
+    },
+    /// A page of a tab violated its Content Security Policy
+    CspViolation {
+        /// ID of the tab
+        webview: String,
+        /// The violation
+        #[serde(flatten)]
+        violation: CspViolation,
+    },
+    /// A script or stylesheet of a page of a tab was blocked, e.g. to alarm on compromised
+    /// third-party resources
+    SubresourceBlocked {
+        /// ID of the tab
+        webview: String,
+        /// The blocked resource
+        #[serde(flatten)]
+        subresource: BlockedSubresource,
+    },
+    /// The controller replaced the config with [`ToVersoMessage::UpdateConfig`], so settings UIs
+    /// connected as other clients stay up to date
+    ConfigChanged {
+        /// The new config, without [`ConfigFromController::ipc_token`]
+        config: Box<ConfigFromController>,
+    },
+}
+
+/// A script or stylesheet a page wasn't allowed to use
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockedSubresource {
+    /// URL of the document
+    pub document_url: String,
+    /// URL of the resource, or `inline` or `eval` for scripts of the document
+    pub url: String,
+    /// Element which loads it, `script`, `link`, or `style` for inline styles
+    pub element: String,
+    /// Why it was blocked
+    pub reason: BlockedReason,
+}
+
+/// Why a [`BlockedSubresource`] was blocked
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BlockedReason {
+    /// Its content doesn't match the `integrity` attribute of the element, or it's a cross-origin
+    /// resource loaded without CORS, see <https://w3c.github.io/webappsec-subresource-integrity/>
+    Integrity,
+    /// The Content Security Policy of the page doesn't allow it
+    ContentSecurityPolicy,
+}
+
+/// A violation of the Content Security Policy of a page, as its `securitypolicyviolation` event
+/// reports it, see <https://w3c.github.io/webappsec-csp/#violation-events>
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CspViolation {
+    /// URL of the document
+    pub document_url: String,
+    /// URL of the blocked resource, or `inline`, `eval` or `wasm-eval`
+    pub blocked_url: String,
+    /// Directive the resource violated, e.g. `script-src-elem`
+    pub effective_directive: String,
+    /// The policy the directive is in, as the page sent it
+    pub original_policy: String,
+    /// Whether the policy is enforced and the resource blocked
+    pub enforced: bool,
+    /// Script the violation happened in
+    pub source_file: Option<String>,
+    /// Line of the violation in the source file
+    pub line_number: u32,
+    /// Column of the violation in the line
+    pub column_number: u32,
+    /// The start of the blocked inline script or style, if the policy asks for samples
+    pub sample: String,
+}
+
+/// A line sent to a client of the control protocol, either the response to one of its requests or
+/// an event it subscribed to.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ControlMessage {
+    /// Response to a request
+    Response(ControlResponse),
+    /// Event of a tab
+    Event(ControlEvent),
+}
+
+impl From<ControlResponse> for ControlMessage {
+    fn from(response: ControlResponse) -> Self {
+        Self::Response(response)
+    }
+}
+
+impl From<ControlEvent> for ControlMessage {
+    fn from(event: ControlEvent) -> Self {
+        Self::Event(event)
+    }
+}
+
+/// Error answered to a [`ControlRequest`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ControlError {
+    /// What went wrong, for clients to match on
+    pub code: ControlErrorCode,
+    /// Description of the error, for humans
+    pub message: String,
+}
+
+/// Kind of a [`ControlError`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[non_exhaustive]
+pub enum ControlErrorCode {
+    /// The request is longer than [`MAX_CONTROL_REQUEST_LENGTH`]
+    TooLong,
+    /// The request isn't a JSON object
+    InvalidJson,
+    /// The request has no supported `type`
+    UnknownCommand,
+    /// The fields of the command are missing or invalid
+    InvalidRequest,
+    /// The version of the request isn't supported
+    UnsupportedVersion,
+    /// There is no webview to run the command in
+    NoWebView,
+    /// The client hasn't sent the token of the control server, or sent the wrong one
+    Unauthorized,
+    /// The client sent more requests per second than the control server allows
+    RateLimited,
+    /// Another client holds a lease on the tab
+    LeaseHeld,
+}
+
+/// A visited page matching a history search
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryMatch {
+    /// URL of the page
+    pub url: url::Url,
+    /// Title of the page
+    pub title: Option<String>,
+    /// Excerpt of the text of the page around the searched words, which are wrapped in U+E000 and
+    /// U+E001. Empty for an empty search.
+    pub snippet: String,
+    /// Time of the last visit, in milliseconds since the Unix epoch
+    pub last_visit: i64,
+}
+
+/// A bookmarked page
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BookmarkInfo {
+    /// Name of the bookmark
+    pub name: String,
+    /// URL of the page
+    pub url: String,
+    /// Path of the folder of the bookmark from the top level, empty at the top level
+    #[serde(default)]
+    pub folder: Vec<String>,
+}
+
+/// A tab group reported to the controller
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TabGroupInfo {
+    /// Tab group id
+    pub id: uuid::Uuid,
+    /// Tab group name
+    pub name: String,
+    /// Whether the webviews of this group are closed
+    pub collapsed: bool,
+    /// URLs of the tabs in this group
+    pub urls: Vec<url::Url>,
+    /// Opened webviews of this group, empty if collapsed
+    pub webview_ids: Vec<SerializedWebViewId>,
 }
 
 /// Configuration of Verso instance.
 #[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
 pub struct ConfigFromController {
     /// URL to load initially.
     pub url: Option<url::Url>,
     /// Should launch without or without control panel
     pub with_panel: bool,
+    /// Run the site as a standalone app: without control panel, in its own profile, with the name
+    /// and icon of its manifest, and with links outside of its scope opened in the default browser.
+    /// Overrides [`Self::url`] and [`Self::with_panel`].
+    pub app: Option<url::Url>,
+    /// Named profile to run in, with cookies, cache, history, bookmarks, session and settings of
+    /// its own, see [`ToVersoMessage::CreateProfile`]. The default profile if `None`. Standalone
+    /// [apps](Self::app) keep their own profile.
+    pub profile: Option<String>,
     /// Window size for the initial winit window
     pub inner_size: Option<Size>,
     /// Window position for the initial winit window
@@ -144,6 +1329,15 @@ pub struct ConfigFromController {
     pub icon: Option<Icon>,
     /// Port number to start a server to listen to remote Firefox devtools connections. 0 for random port.
     pub devtools_port: Option<u16>,
+    /// Port number to start a WebDriver server on, to drive Verso with automation tools.
+    pub webdriver_port: Option<u16>,
+    /// Port number to serve a subset of the Chrome DevTools Protocol on, on the loopback
+    /// interface, for tools like Puppeteer and Playwright. 0 for random port.
+    pub cdp_port: Option<u16>,
+    /// Origins of web pages allowed to connect to the Chrome DevTools Protocol server, e.g. a
+    /// DevTools frontend served over HTTP. Clients which aren't web pages don't send an origin
+    /// and are always allowed.
+    pub cdp_allowed_origins: Vec<url::Url>,
     /// Servo time profile settings
     pub profiler_settings: Option<ProfilerSettings>,
     /// Override the user agent
@@ -155,6 +1349,141 @@ pub struct ConfigFromController {
     /// Path to resource directory. If None, Verso will try to get default directory. And if that
     /// still doesn't exist, all resource configuration will set to default values.
     pub resources_directory: Option<PathBuf>,
+    /// Windows to open on startup. If empty, a single window is opened from `url`, `inner_size`,
+    /// `position`, `maximized` and `fullscreen`.
+    pub windows: Vec<WindowConfig>,
+    /// Reload webviews showing `file://` content from this directory when files in it change.
+    pub watch_directory: Option<PathBuf>,
+    /// Show hidden files in `file://` directory listings.
+    pub show_hidden_files: bool,
+    /// Access policy of `file://` URLs.
+    pub file_access: FileAccessPolicy,
+    /// URLs to serve from local files or in-memory bytes instead of the network.
+    pub resource_overrides: Vec<ResourceOverride>,
+    /// Launch in mock network mode, network requests are only answered by the controller
+    /// and never reach the network, for hermetic tests.
+    pub mock_network: bool,
+    /// Import cookies, bookmarks and history from another browser on startup.
+    pub import: Option<BrowserImport>,
+    /// Origins allowed to store secrets in the OS keychain through `window.verso.secrets`,
+    /// e.g. `https://example.com`. Secrets are only visible to the origin which saved them.
+    pub secret_bridge_origins: Vec<url::Url>,
+    /// PEM file of root CA certificates to trust instead of the default ones,
+    /// e.g. for intranet or IoT devices with a private CA.
+    pub certificate_bundle: Option<PathBuf>,
+    /// Certificate pins of origins, requests to a pinned origin are blocked if its certificate chain
+    /// doesn't have one of the pinned public keys. The chain is checked on a connection of Verso's
+    /// own once per session, not on the connections the requests are sent on.
+    pub certificate_pins: Vec<CertificatePin>,
+    /// Whether users can proceed to sites with an invalid certificate, see
+    /// [`ToVersoMessage::SetCertificateErrorPolicy`] to change it at runtime.
+    pub certificate_errors: CertificateErrorPolicy,
+    /// Referrer policy and Do-Not-Track settings of every webview, see
+    /// [`ToVersoMessage::SetWebViewPrivacy`] to change them for a webview.
+    pub privacy: PrivacySettings,
+    /// Throttle animations when the system is idle or saving power.
+    pub power_save: PowerSaveConfig,
+    /// Pause animations of windows in the background or after they ran once, to use less CPU and
+    /// distract less. See [`ToVersoMessage::SetAnimationPolicy`] to change it at runtime.
+    pub animations: AnimationPolicy,
+    /// Expose the battery of the system to pages with the Battery Status API. It's off by
+    /// default since the battery status can be used to fingerprint users.
+    pub battery_status_api: bool,
+    /// Expose the accelerometer, gyroscope and ambient light sensor of the device to pages, e.g.
+    /// for kiosks and embedded hardware.
+    pub sensors: bool,
+    /// Tint the titlebar of windows with the `theme-color` of their current tab. Windows of
+    /// standalone apps are always tinted.
+    pub theme_color_titlebar: bool,
+    /// Draw a thin progress bar at the top of the current tab while it loads, e.g. for windows
+    /// without control panel.
+    pub load_progress_bar: bool,
+    /// Seconds the script of a page can be unresponsive before the user is asked whether to stop
+    /// it, 0 to never ask.
+    pub slow_script_timeout: u64,
+    /// Whether pages can embed frames from other origins, or only sandboxed ones, e.g. for
+    /// display appliances which only show trusted content.
+    pub cross_origin_frames: CrossOriginFramePolicy,
+    /// Index the text of visited pages, so the history can be searched by content. The index is
+    /// stored in the history database of the profile.
+    pub index_history_text: bool,
+    /// Days the visited pages are kept in the history after their last visit, 0 to keep them
+    /// forever. The history is pruned on startup.
+    pub history_retention_days: u32,
+    /// Blank webviews kept ready in every window, so new tabs only have to navigate instead of
+    /// waiting for Servo to create them, e.g. for automation spawning many short-lived tabs.
+    /// Each one costs the memory of an empty page.
+    pub warm_pool_size: usize,
+    /// Keep the shader programs compiled by the GL driver in the cache directory, so they aren't
+    /// compiled again on the next startup, which takes seconds with some drivers.
+    pub shader_cache: bool,
+    /// Check for signed releases on startup and install them on the next restart, disabled if
+    /// `None`.
+    pub update: Option<UpdateConfig>,
+    /// Record performance counters in the profile, disabled by default.
+    pub metrics: MetricsConfig,
+    /// URL crash reports are posted to as JSON when the user submits them from `verso://crashes`.
+    /// Reports are only written to the profile if `None`.
+    pub crash_report_url: Option<url::Url>,
+    /// Start without user scripts, resource overrides, the shader cache and optional GPU
+    /// features, to recover from a configuration which prevents Verso from starting.
+    pub safe_mode: bool,
+    /// Reopen the windows and tabs of the last session instead of the initial URL. The session
+    /// is saved periodically and on exit either way.
+    pub restore_session: bool,
+    /// Open private windows. Pages don't keep their cookies, cache and storage after the windows
+    /// close, and the history, session and zoom levels aren't saved. Bookmarks aren't loaded
+    /// either, so nothing is written to the profile.
+    pub private: bool,
+    /// Render with the software rasterizer of the GL driver, e.g. Mesa's llvmpipe, instead of the
+    /// GPU. Verso also falls back to it if no hardware context can be created.
+    pub software_rendering: bool,
+    /// How windows wait for the vertical blank before presenting a frame.
+    pub vsync: Vsync,
+    /// Clockwise rotation of the output of the windows, for screens mounted in another
+    /// orientation, e.g. portrait kiosk screens. Pages are laid out for the rotated size.
+    pub rotation: Rotation,
+    /// Antialiasing, hinting and gamma of the text of pages. It applies on the next launch.
+    pub font_rendering: FontRendering,
+    /// Cookies are saved in the profile directory when Verso quits, unless the windows are
+    /// private. See [`ToVersoMessage::SetCookiePolicy`] to change the policy at runtime.
+    pub cookies: CookiePolicy,
+    /// Whether pages run JavaScript, for every origin and for some of them. See
+    /// [`ToVersoMessage::SetJavaScriptEnabled`] and [`ToVersoMessage::SetOriginJavaScriptEnabled`]
+    /// to change it at runtime, or `verso://site-settings`.
+    pub javascript: JavaScriptSettings,
+    /// Which images pages load, and whether to save data on them. The policy saved in the
+    /// profile is used if `None`, every image is loaded by default. See
+    /// [`ToVersoMessage::SetImagePolicy`] to change it at runtime.
+    pub images: Option<ImagePolicy>,
+    /// Most megabytes of responses kept in the HTTP cache of the profile, so resources aren't
+    /// fetched again on every launch, 0 to disable it. Private windows don't cache on disk. See
+    /// [`ToVersoMessage::ClearCache`] to empty it.
+    pub http_cache_size: u64,
+    /// Key the HTTP cache, and the cookies of requests to other sites than the tab's, by the site
+    /// of the tab, so a site embedded in several sites can't track users across them. The
+    /// cookies of a partition are only kept for the session. Servo keeps the HSTS list itself, so
+    /// it isn't partitioned. It applies to the requests sent from now on.
+    pub partition_network: bool,
+    /// On-screen keyboard shown when an editable field is focused by touch. The field is
+    /// scrolled into view above it.
+    pub virtual_keyboard: VirtualKeyboard,
+    /// Serve the [`ControlRequest`] protocol on this unix socket, or named pipe on Windows, so
+    /// other processes can drive Verso without the controller. A TCP address like
+    /// `127.0.0.1:9230` is listened on instead.
+    pub ipc_socket: Option<PathBuf>,
+    /// Token clients of the control protocol authenticate with in their
+    /// [`ControlCommand::Hello`], anyone able to connect may use it if `None`.
+    pub ipc_token: Option<String>,
+    /// Requests per second each client of the control protocol may send, 0 for no limit.
+    pub ipc_rate_limit: u32,
+    /// Listen for the control protocol on TCP addresses other than loopback. It needs an
+    /// [`ipc_token`](Self::ipc_token) regardless.
+    pub ipc_allow_remote: bool,
+    /// Also serve the [`ControlRequest`] protocol over WebSocket on this address, a request or
+    /// response per message, with the same token and rate limit. Web pages can only connect with
+    /// the [`ipc_token`](Self::ipc_token).
+    pub ipc_websocket: Option<SocketAddr>,
 }
 
 impl Default for ConfigFromController {
@@ -162,6 +1491,8 @@ impl Default for ConfigFromController {
         Self {
             url: None,
             with_panel: false,
+            app: None,
+            profile: None,
             inner_size: None,
             position: None,
             maximized: false,
@@ -173,15 +1504,594 @@ impl Default for ConfigFromController {
             icon: None,
             fullscreen: false,
             devtools_port: None,
+            webdriver_port: None,
+            cdp_port: None,
+            cdp_allowed_origins: Vec::new(),
             profiler_settings: None,
             user_agent: None,
             user_scripts: Vec::new(),
             zoom_level: None,
             resources_directory: None,
+            windows: Vec::new(),
+            watch_directory: None,
+            show_hidden_files: false,
+            file_access: FileAccessPolicy::default(),
+            resource_overrides: Vec::new(),
+            mock_network: false,
+            import: None,
+            secret_bridge_origins: Vec::new(),
+            certificate_bundle: None,
+            certificate_pins: Vec::new(),
+            certificate_errors: CertificateErrorPolicy::default(),
+            privacy: PrivacySettings::default(),
+            power_save: PowerSaveConfig::default(),
+            animations: AnimationPolicy::default(),
+            battery_status_api: false,
+            sensors: false,
+            theme_color_titlebar: false,
+            load_progress_bar: false,
+            slow_script_timeout: 10,
+            cross_origin_frames: CrossOriginFramePolicy::default(),
+            index_history_text: true,
+            history_retention_days: 90,
+            warm_pool_size: 0,
+            shader_cache: true,
+            update: None,
+            metrics: MetricsConfig::default(),
+            crash_report_url: None,
+            safe_mode: false,
+            restore_session: false,
+            private: false,
+            software_rendering: false,
+            vsync: Vsync::default(),
+            rotation: Rotation::default(),
+            font_rendering: FontRendering::default(),
+            cookies: CookiePolicy::default(),
+            javascript: JavaScriptSettings::default(),
+            images: None,
+            http_cache_size: 256,
+            partition_network: false,
+            virtual_keyboard: VirtualKeyboard::default(),
+            ipc_socket: None,
+            ipc_token: None,
+            ipc_rate_limit: 100,
+            ipc_allow_remote: false,
+            ipc_websocket: None,
+        }
+    }
+}
+
+/// Access policy of `file://` URLs, for locked-down deployments.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FileAccessPolicy {
+    /// Allow loading `file://` URLs at all.
+    pub enabled: bool,
+    /// If not empty, only files under these directories can be loaded.
+    pub allowed_roots: Vec<PathBuf>,
+    /// Block requests from `file://` documents to remote origins, so local pages can't send
+    /// credentials to remote servers.
+    pub block_remote_requests: bool,
+}
+
+impl Default for FileAccessPolicy {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            allowed_roots: Vec::new(),
+            block_remote_requests: false,
         }
     }
 }
 
+/// Referrer policy applied to documents, see the
+/// [Referrer Policy](https://w3c.github.io/webappsec-referrer-policy/#referrer-policies) spec.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ReferrerPolicy {
+    /// Never send the referrer.
+    NoReferrer,
+    /// Only send the referrer to the same origin.
+    SameOrigin,
+    /// Only send the origin, and nothing when downgrading from HTTPS to HTTP.
+    StrictOrigin,
+    /// Send the full URL to the same origin and only the origin to others.
+    StrictOriginWhenCrossOrigin,
+}
+
+impl std::str::FromStr for ReferrerPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "no-referrer" => Ok(Self::NoReferrer),
+            "same-origin" => Ok(Self::SameOrigin),
+            "strict-origin" => Ok(Self::StrictOrigin),
+            "strict-origin-when-cross-origin" => Ok(Self::StrictOriginWhenCrossOrigin),
+            _ => Err(format!("Unknown referrer policy {s}")),
+        }
+    }
+}
+
+/// How frames from another origin than their parent document are treated. Frames are handled
+/// as soon as they are inserted in the document, so a frame whose navigation already started is
+/// navigated again once sandboxed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CrossOriginFramePolicy {
+    /// Load cross-origin frames like any other frame.
+    #[default]
+    Allow,
+    /// Load cross-origin frames with an empty `sandbox` attribute, so they can't run scripts,
+    /// submit forms or open popups. Legacy `<frame>` elements can't be sandboxed and are removed.
+    Sandbox,
+    /// Remove cross-origin frames from the document.
+    Block,
+}
+
+impl std::str::FromStr for CrossOriginFramePolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "allow" => Ok(Self::Allow),
+            "sandbox" => Ok(Self::Sandbox),
+            "block" => Ok(Self::Block),
+            _ => Err(format!("Unknown cross-origin frame policy {s}")),
+        }
+    }
+}
+
+/// Privacy settings of webviews.
+///
+/// Servo doesn't let the embedder add request headers, so Do-Not-Track and Global Privacy Control
+/// are exposed to scripts as `navigator.doNotTrack` and `navigator.globalPrivacyControl`.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PrivacySettings {
+    /// Referrer policy of every document, unless the page sets a stricter one after it is loaded.
+    /// If `None`, the policy of the page is used.
+    pub referrer_policy: Option<ReferrerPolicy>,
+    /// Tell pages the user doesn't want to be tracked.
+    pub do_not_track: bool,
+    /// Tell pages the user doesn't want their data to be sold or shared.
+    pub global_privacy_control: bool,
+    /// Report the same screen size, timezone and hardware concurrency as other users, and add
+    /// noise to canvas readbacks, so pages can't tell users apart from them.
+    pub resist_fingerprinting: bool,
+}
+
+/// Resource budgets of a webview, for hosts running several untrusted pages side by side. `None`
+/// means unlimited.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ResourceLimits {
+    /// Milliseconds a script may run without going back to the event loop. Scripts can't be
+    /// interrupted, so the webview is closed when it's exceeded. It's checked twice per second.
+    pub max_script_time: Option<u64>,
+    /// Requests a document and its subresources may send, the next ones are cancelled. Servo
+    /// doesn't tell when a request is done, so this limits the requests of each load rather than
+    /// the concurrent connections.
+    pub max_requests: Option<u32>,
+    /// Bytes a download may write, it's cancelled when it gets bigger.
+    pub max_download_size: Option<u64>,
+}
+
+/// A budget of [`ResourceLimits`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ResourceLimit {
+    /// [`ResourceLimits::max_script_time`]
+    ScriptTime,
+    /// [`ResourceLimits::max_requests`]
+    Requests,
+    /// [`ResourceLimits::max_download_size`]
+    DownloadSize,
+}
+
+/// Public keys pinned for an origin, like HTTP Public Key Pinning.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CertificatePin {
+    /// The pinned origin, e.g. `https://intranet.example.com`. Only its host and port are used.
+    pub origin: url::Url,
+    /// Base64 encoded SHA-256 hashes of the pinned SubjectPublicKeyInfo, the same as the
+    /// `pin-sha256` values of HPKP. Any certificate of the chain can match.
+    pub sha256_pins: Vec<String>,
+}
+
+/// What users can do on the page shown instead of a site with an invalid certificate.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CertificateErrorPolicy {
+    /// Explain the error and let the user proceed anyway for the session
+    #[default]
+    Interstitial,
+    /// Explain the error without letting the user proceed, only the hosts allowed with
+    /// [`ToVersoMessage::AllowCertificateOverride`] load
+    Block,
+}
+
+/// Serve a URL from a local file or in-memory bytes instead of the network, e.g. to patch a
+/// third-party script while debugging.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ResourceOverride {
+    /// The URL to override, its fragment is ignored.
+    pub url: url::Url,
+    /// The content to serve.
+    pub source: ResourceOverrideSource,
+    /// Content type of the response. If `None`, it's guessed from the file extension.
+    #[serde(default)]
+    pub content_type: Option<String>,
+}
+
+/// Content of a [`ResourceOverride`].
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum ResourceOverrideSource {
+    /// Read from this file on every request, so changes to it show up on reload.
+    File(PathBuf),
+    /// Serve these bytes.
+    Bytes(Vec<u8>),
+}
+
+/// Browsers which Verso can import data from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Browser {
+    /// Google Chrome
+    Chrome,
+    /// Mozilla Firefox
+    Firefox,
+}
+
+impl std::str::FromStr for Browser {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "chrome" => Ok(Self::Chrome),
+            "firefox" => Ok(Self::Firefox),
+            _ => Err(format!("Unknown browser {s}, expected chrome or firefox")),
+        }
+    }
+}
+
+/// Import cookies, bookmarks and history from the profile of another browser.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BrowserImport {
+    /// The browser to import from.
+    pub browser: Browser,
+    /// The profile directory to import from. If `None`, the default profile is used.
+    #[serde(default)]
+    pub profile_directory: Option<PathBuf>,
+}
+
+/// Opt-in recording of aggregate performance counters: frame times, page load times and crashes.
+/// They are kept in the profile and never leave it unless an upload endpoint is set.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MetricsConfig {
+    /// Record the metrics.
+    pub enabled: bool,
+    /// Endpoint the metrics recorded so far are posted to as JSON on startup.
+    pub upload_url: Option<url::Url>,
+}
+
+/// How windows wait for the vertical blank of the display before presenting a frame.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Vsync {
+    /// Wait for every vertical blank
+    #[default]
+    Enabled,
+    /// Present as soon as the frame is rendered, frames can tear, e.g. for benchmarks
+    Immediate,
+    /// Wait for the vertical blank, unless the last frame missed it, so slow frames aren't delayed
+    /// further
+    Adaptive,
+    /// Wait for this number of vertical blanks between frames, `0` presents immediately
+    Interval(u32),
+}
+
+impl std::str::FromStr for Vsync {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "enabled" => Ok(Self::Enabled),
+            "immediate" => Ok(Self::Immediate),
+            "adaptive" => Ok(Self::Adaptive),
+            _ => s.parse().map(Self::Interval).map_err(|_| {
+                format!(
+                    "Unknown vsync mode {s}, expected enabled, immediate, adaptive or an interval"
+                )
+            }),
+        }
+    }
+}
+
+/// Clockwise rotation of the output of a window.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Rotation {
+    /// Not rotated
+    #[default]
+    #[serde(rename = "0")]
+    Normal,
+    /// Turned a quarter clockwise, the top of the pages is on the right of the screen
+    #[serde(rename = "90")]
+    Rotate90,
+    /// Upside down
+    #[serde(rename = "180")]
+    Rotate180,
+    /// Turned a quarter counterclockwise, the top of the pages is on the left of the screen
+    #[serde(rename = "270")]
+    Rotate270,
+}
+
+impl std::str::FromStr for Rotation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "0" => Ok(Self::Normal),
+            "90" => Ok(Self::Rotate90),
+            "180" => Ok(Self::Rotate180),
+            "270" => Ok(Self::Rotate270),
+            _ => Err(format!("Unknown rotation {s}, expected 0, 90, 180 or 270")),
+        }
+    }
+}
+
+/// How the edges of glyphs are smoothed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FontAntialiasing {
+    /// Sharp edges, every pixel is either covered by the glyph or not
+    None,
+    /// Shades of gray, e.g. for screens whose subpixels aren't laid out as RGB stripes
+    Grayscale,
+    /// Each subpixel of the screen is shaded on its own, on opaque backgrounds
+    #[default]
+    Subpixel,
+}
+
+impl std::str::FromStr for FontAntialiasing {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(Self::None),
+            "grayscale" => Ok(Self::Grayscale),
+            "subpixel" => Ok(Self::Subpixel),
+            _ => Err(format!("Unknown font antialiasing {s}")),
+        }
+    }
+}
+
+/// How much the outlines of glyphs are fitted to the pixel grid, like the hint styles of
+/// fontconfig. Only FreeType glyphs are hinted, e.g. on Linux.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FontHinting {
+    /// Keep the outlines of the font, glyphs are blurrier but keep their shape
+    None,
+    /// Only fit the outlines vertically
+    Slight,
+    /// Fit the outlines on both axes for the antialiasing, the sharpest glyphs
+    #[default]
+    Full,
+}
+
+impl std::str::FromStr for FontHinting {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(Self::None),
+            "slight" => Ok(Self::Slight),
+            "full" => Ok(Self::Full),
+            _ => Err(format!("Unknown font hinting {s}")),
+        }
+    }
+}
+
+/// How WebRender rasterizes the text of pages, e.g. when its defaults look wrong on a screen.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FontRendering {
+    /// How the edges of glyphs are smoothed.
+    pub antialiasing: FontAntialiasing,
+    /// How much the outlines of glyphs are fitted to the pixel grid.
+    pub hinting: FontHinting,
+    /// Gamma the coverage of glyphs is corrected with, higher makes text look bolder. WebRender
+    /// only takes it on Windows.
+    pub gamma: f32,
+}
+
+impl Default for FontRendering {
+    fn default() -> Self {
+        Self {
+            antialiasing: FontAntialiasing::default(),
+            hinting: FontHinting::default(),
+            gamma: 1.8,
+        }
+    }
+}
+
+/// Clipboard action in the focused element of a webview, as with its keyboard shortcut.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ClipboardAction {
+    /// Copy the selection, with its HTML, or the selected image
+    Copy,
+    /// Copy the selection and remove it from the editable element
+    Cut,
+    /// Paste the clipboard, editable content gets its HTML or image
+    Paste,
+    /// Paste the text of the clipboard without its formatting
+    PasteAsPlainText,
+}
+
+/// What Verso keeps of the cookies pages set.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CookiePolicy {
+    /// Forget the cookies when Verso quits instead of saving them in the profile.
+    pub session_only: bool,
+    /// Send the requests of a page to other sites without cookies, and ignore the cookies they
+    /// set. The cookies sites set as the page of a tab are kept. Servo doesn't tell which page a
+    /// request is sent for, so the site of the tab is the one compared, and requests with a body
+    /// are still sent with their cookies.
+    pub block_third_party: bool,
+}
+
+/// Whether pages run JavaScript, e.g. to only show the content of documents in secure reading
+/// deployments. It's enforced with a Content Security Policy set before the document is parsed,
+/// so inline scripts, event handler attributes and `javascript:` URLs don't run either. Verso's
+/// own pages always run JavaScript.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct JavaScriptSettings {
+    /// Run JavaScript on the pages of origins without a setting of their own.
+    pub enabled: bool,
+    /// Origins which run JavaScript or not regardless of [`Self::enabled`], keyed by their
+    /// serialization, e.g. `https://example.com`.
+    pub origins: BTreeMap<String, bool>,
+}
+
+impl Default for JavaScriptSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            origins: BTreeMap::new(),
+        }
+    }
+}
+
+/// Which images pages load.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ImageLoading {
+    /// Load every image
+    #[default]
+    All,
+    /// Load no image, their alternative text is shown instead
+    Block,
+    /// Show a placeholder for every image until it's clicked
+    ClickToLoad,
+}
+
+impl std::str::FromStr for ImageLoading {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "all" => Ok(Self::All),
+            "block" => Ok(Self::Block),
+            "click-to-load" => Ok(Self::ClickToLoad),
+            _ => Err(format!("Unknown image loading mode {s}")),
+        }
+    }
+}
+
+/// Images of the pages of a profile, e.g. to save data on metered connections. Images opened as
+/// the page of a tab are always loaded.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ImagePolicy {
+    /// Which images pages load.
+    pub loading: ImageLoading,
+    /// Load the smallest candidate of responsive images, of their `srcset` and of the sources of
+    /// `<picture>` elements.
+    pub data_saver: bool,
+}
+
+/// On-screen keyboard of editable fields focused by touch.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum VirtualKeyboard {
+    /// No on-screen keyboard, the platform's input methods are still enabled
+    Off,
+    /// Request the on-screen keyboard of the platform, where it has one
+    #[default]
+    Platform,
+    /// Draw Verso's keyboard at the bottom of the page instead of the platform's keyboard and
+    /// input methods, e.g. for DRM/KMS outputs
+    BuiltIn,
+}
+
+impl std::str::FromStr for VirtualKeyboard {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "off" => Ok(Self::Off),
+            "platform" => Ok(Self::Platform),
+            "built-in" => Ok(Self::BuiltIn),
+            _ => Err(format!(
+                "Unknown virtual keyboard {s}, expected off, platform or built-in"
+            )),
+        }
+    }
+}
+
+/// Release channel Verso updates itself from.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum UpdateChannel {
+    /// Tested releases
+    #[default]
+    Stable,
+    /// Builds of the latest changes
+    Nightly,
+}
+
+impl std::str::FromStr for UpdateChannel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "stable" => Ok(Self::Stable),
+            "nightly" => Ok(Self::Nightly),
+            _ => Err(format!(
+                "Unknown update channel {s}, expected stable or nightly"
+            )),
+        }
+    }
+}
+
+/// Where and how Verso updates itself.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct UpdateConfig {
+    /// URL of the JSON manifest listing the latest release of every channel, with the URL and
+    /// signature of its binary for every platform.
+    pub url: url::Url,
+    /// The channel to update from.
+    #[serde(default)]
+    pub channel: UpdateChannel,
+    /// Base64 Ed25519 public key the releases are signed with, each signature covers the version,
+    /// the target triple and the SHA-256 hash of the binary. If `None`, the key set with the
+    /// `VERSO_UPDATE_PUBLIC_KEY` environment variable at build time is used, and updates are
+    /// disabled if there is none.
+    #[serde(default)]
+    pub public_key: Option<String>,
+}
+
+/// Layout of a window opened on startup, other window settings are shared with
+/// [`ConfigFromController`].
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WindowConfig {
+    /// URL to load in the window, falls back to [`ConfigFromController::url`].
+    pub url: Option<url::Url>,
+    /// Window size
+    pub inner_size: Option<Size>,
+    /// Window position
+    pub position: Option<Position>,
+    /// Launch maximized or not
+    pub maximized: bool,
+    /// Launch fullscreen or not
+    pub fullscreen: bool,
+    /// Name to address the window with in [`ControlRequest::window`], kept in the session
+    pub name: Option<String>,
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct Icon {
     /// RGBA bytes of the icon.